@@ -0,0 +1,45 @@
+#!/usr/bin/env bash
+# Generates the TypeScript and Python clients from the server's OpenAPI spec and packs them into
+# clients/dist. Usage: clients/build.sh [version] [text-to-cypher binary]
+#
+# The version defaults to the crate's; the binary defaults to `cargo run`, which builds the server
+# but starts nothing: `--export-openapi` only writes the spec.
+set -euo pipefail
+
+binary="${2:+$(realpath "$2")}"
+cd "$(dirname "$0")"
+version="${1:-$(sed -n 's/^version = "\(.*\)"/\1/p' ../Cargo.toml | head -1)}"
+version="${version#v}"
+
+echo "📄 Exporting the OpenAPI spec..."
+if [ -n "$binary" ]; then
+    "$binary" --export-openapi openapi.json
+else
+    cargo run --quiet --manifest-path ../Cargo.toml -- --export-openapi openapi.json
+fi
+rm -rf dist
+mkdir -p dist
+cp openapi.json dist/
+
+echo "🟦 Building the TypeScript client ${version}..."
+(
+    cd typescript
+    npm pkg set version="$version"
+    npm install --no-audit --no-fund
+    npm run generate
+    npm run build
+    npm pack --pack-destination ../dist
+)
+
+echo "🐍 Building the Python client ${version}..."
+(
+    cd python
+    sed -i.bak "s/^version = \".*\"/version = \"$version\"/" pyproject.toml && rm pyproject.toml.bak
+    python3 -m pip install --quiet "datamodel-code-generator==0.26.*" build
+    datamodel-codegen --input ../openapi.json --input-file-type openapi \
+        --output-model-type pydantic_v2.BaseModel --output text_to_cypher_client/models.py
+    python3 -m build --outdir ../dist
+)
+
+echo "✅ Clients ${version} in clients/dist/"
+ls dist
@@ -0,0 +1,138 @@
+"""Client for the text-to-cypher REST API.
+
+``TextToCypherClient.stream`` yields the progress events of a ``/text_to_cypher`` request as they
+arrive, and ``TextToCypherClient.ask`` returns the :class:`Answer` they amount to. The request and
+response models generated from the server's OpenAPI spec are in ``text_to_cypher_client.models``.
+"""
+
+from __future__ import annotations
+
+from typing import Any, AsyncIterator, Dict, Iterator, Mapping, Optional
+
+import httpx
+
+from .sse import (
+    Answer,
+    Progress,
+    SseEvent,
+    aparse_sse,
+    aprogress_events,
+    parse_sse,
+    progress_events,
+    progress_kind,
+)
+
+__all__ = [
+    "Answer",
+    "AsyncTextToCypherClient",
+    "Progress",
+    "SseEvent",
+    "TextToCypherClient",
+    "TextToCypherError",
+    "aparse_sse",
+    "aprogress_events",
+    "parse_sse",
+    "progress_events",
+    "progress_kind",
+]
+
+
+class TextToCypherError(Exception):
+    """A failed, non-streaming response."""
+
+    def __init__(self, status: int, message: str) -> None:
+        super().__init__(message)
+        self.status = status
+
+
+def _headers(api_key: Optional[str]) -> Dict[str, str]:
+    headers = {"Accept": "text/event-stream"}
+    if api_key:
+        headers["Authorization"] = f"Bearer {api_key}"
+    return headers
+
+
+def _error(response: httpx.Response) -> TextToCypherError:
+    try:
+        message = response.json().get("error") or response.text
+    except ValueError:
+        message = response.text or response.reason_phrase
+    return TextToCypherError(response.status_code, message)
+
+
+def _body(request: Any) -> Mapping[str, Any]:
+    """A request as JSON: a dict, or a generated pydantic model."""
+    if hasattr(request, "model_dump"):
+        return request.model_dump(mode="json", exclude_none=True)
+    return request
+
+
+class TextToCypherClient:
+    """Calls a text-to-cypher server."""
+
+    def __init__(
+        self,
+        base_url: str,
+        api_key: Optional[str] = None,
+        timeout: Optional[float] = None,
+        http: Optional[httpx.Client] = None,
+    ) -> None:
+        self.base_url = base_url.rstrip("/")
+        self.api_key = api_key
+        self.http = http or httpx.Client(timeout=timeout)
+
+    def stream(self, request: Any) -> Iterator[Progress]:
+        """Streams the progress events of a ``/text_to_cypher`` request, ending after ``Done``."""
+        with self.http.stream(
+            "POST",
+            f"{self.base_url}/text_to_cypher",
+            json=_body(request),
+            headers=_headers(self.api_key),
+        ) as response:
+            if response.is_error:
+                response.read()
+                raise _error(response)
+            yield from progress_events(parse_sse(response.iter_lines()))
+
+    def ask(self, request: Any) -> Answer:
+        """Runs a ``/text_to_cypher`` request to the end and returns what it amounted to."""
+        answer = Answer()
+        for progress in self.stream(request):
+            answer.add(progress)
+        return answer
+
+
+class AsyncTextToCypherClient:
+    """Like :class:`TextToCypherClient`, with ``asyncio``."""
+
+    def __init__(
+        self,
+        base_url: str,
+        api_key: Optional[str] = None,
+        timeout: Optional[float] = None,
+        http: Optional[httpx.AsyncClient] = None,
+    ) -> None:
+        self.base_url = base_url.rstrip("/")
+        self.api_key = api_key
+        self.http = http or httpx.AsyncClient(timeout=timeout)
+
+    async def stream(self, request: Any) -> AsyncIterator[Progress]:
+        """Streams the progress events of a ``/text_to_cypher`` request, ending after ``Done``."""
+        async with self.http.stream(
+            "POST",
+            f"{self.base_url}/text_to_cypher",
+            json=_body(request),
+            headers=_headers(self.api_key),
+        ) as response:
+            if response.is_error:
+                await response.aread()
+                raise _error(response)
+            async for progress in aprogress_events(aparse_sse(response.aiter_lines())):
+                yield progress
+
+    async def ask(self, request: Any) -> Answer:
+        """Runs a ``/text_to_cypher`` request to the end and returns what it amounted to."""
+        answer = Answer()
+        async for progress in self.stream(request):
+            answer.add(progress)
+        return answer
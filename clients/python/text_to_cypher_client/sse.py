@@ -0,0 +1,158 @@
+"""Parsing of server-sent events and of the `/text_to_cypher` progress protocol."""
+
+from __future__ import annotations
+
+import json
+from dataclasses import dataclass, field
+from typing import Any, AsyncIterable, AsyncIterator, Dict, Iterable, Iterator, List, Optional
+
+#: One progress event, e.g. ``{"Status": "..."}`` or ``{"Done": {"status": "success", ...}}``.
+Progress = Dict[str, Any]
+
+
+@dataclass
+class SseEvent:
+    """One server-sent event: its ``data:`` payload and, when the server signs events, its ``id:``."""
+
+    data: str
+    id: Optional[str] = None
+
+
+class _SseParser:
+    """Feeds lines in, gets events out, following the SSE specification."""
+
+    def __init__(self) -> None:
+        self.data: List[str] = []
+        self.id: Optional[str] = None
+
+    def feed(self, line: str) -> Optional[SseEvent]:
+        line = line.rstrip("\r\n")
+        if line == "":
+            if not self.data:
+                return None
+            event = SseEvent(data="\n".join(self.data), id=self.id)
+            self.data = []
+            return event
+        if line.startswith(":"):
+            return None
+        name, _, value = line.partition(":")
+        if value.startswith(" "):
+            value = value[1:]
+        if name == "data":
+            self.data.append(value)
+        elif name == "id":
+            self.id = value
+        return None
+
+    def finish(self) -> Optional[SseEvent]:
+        return self.feed("")
+
+
+def parse_sse(lines: Iterable[str]) -> Iterator[SseEvent]:
+    """Parses the lines of a ``text/event-stream`` body into events."""
+    parser = _SseParser()
+    for line in lines:
+        event = parser.feed(line)
+        if event is not None:
+            yield event
+    event = parser.finish()
+    if event is not None:
+        yield event
+
+
+async def aparse_sse(lines: AsyncIterable[str]) -> AsyncIterator[SseEvent]:
+    """Like :func:`parse_sse`, for an asynchronous body."""
+    parser = _SseParser()
+    async for line in lines:
+        event = parser.feed(line)
+        if event is not None:
+            yield event
+    event = parser.finish()
+    if event is not None:
+        yield event
+
+
+def progress_kind(progress: Progress) -> str:
+    """The kind of a progress event, such as ``"Status"`` or ``"Done"``: its only key."""
+    return next(iter(progress))
+
+
+def progress_events(events: Iterable[SseEvent]) -> Iterator[Progress]:
+    """Decodes progress events, ending after ``Done``."""
+    for event in events:
+        progress = json.loads(event.data)
+        yield progress
+        if "Done" in progress:
+            return
+
+
+async def aprogress_events(events: AsyncIterable[SseEvent]) -> AsyncIterator[Progress]:
+    """Like :func:`progress_events`, for an asynchronous stream."""
+    async for event in events:
+        progress = json.loads(event.data)
+        yield progress
+        if "Done" in progress:
+            return
+
+
+@dataclass
+class Answer:
+    """What a whole ``/text_to_cypher`` stream amounted to."""
+
+    #: ``success``, ``no_answer`` or ``error``; ``None`` if the stream was cut off before ``Done``.
+    status: Optional[str] = None
+    request_id: Optional[str] = None
+    #: The graph a request without a ``graph_name`` was routed to.
+    graph: Optional[str] = None
+    #: The last query generated.
+    cypher_query: Optional[str] = None
+    cypher_result: Optional[str] = None
+    #: The execution plan of a ``dry_run`` request's query, one operation per line.
+    query_plan: Optional[List[str]] = None
+    #: The final answer: the ``Result`` event, or the streamed answer chunks if none arrived.
+    answer: str = ""
+    #: Why the model wrote no query, for a ``no_answer`` stream.
+    no_answer: Optional[str] = None
+    error: Optional[str] = None
+    #: ``{"code": ..., "message": ...}`` for each non-fatal issue.
+    warnings: List[Dict[str, str]] = field(default_factory=list)
+    confidence: Optional[int] = None
+    followups: List[str] = field(default_factory=list)
+    usage: Optional[Dict[str, int]] = None
+    _chunks: str = field(default="", repr=False)
+    _has_result: bool = field(default=False, repr=False)
+
+    def add(self, progress: Progress) -> None:
+        """Folds one progress event into the answer."""
+        kind = progress_kind(progress)
+        value = progress[kind]
+        if kind == "Graph":
+            self.graph = value
+        elif kind == "CypherQuery":
+            self.cypher_query = value
+        elif kind == "CypherResult":
+            self.cypher_result = value
+        elif kind == "QueryPlan":
+            self.query_plan = value
+        elif kind == "ModelOutputChunk":
+            self._chunks += value
+            if not self._has_result:
+                self.answer = self._chunks
+        elif kind == "Result":
+            self._has_result = True
+            self.answer = value
+        elif kind == "NoAnswer":
+            self.no_answer = value
+        elif kind == "Error":
+            self.error = value
+        elif kind == "Warning":
+            self.warnings.append(value)
+        elif kind == "Confidence":
+            self.confidence = value
+        elif kind == "Followups":
+            self.followups = value
+        elif kind == "Usage":
+            self.usage = value
+        elif kind == "Done":
+            self.status = value["status"]
+            self.request_id = value["request_id"]
@@ -0,0 +1,83 @@
+import type { components } from "./schema.js";
+import { type Answer, collectAnswer, type ProgressEvent, progressEvents } from "./progress.js";
+
+export type { components, paths } from "./schema.js";
+export * from "./progress.js";
+export * from "./sse.js";
+
+export type TextToCypherRequest = components["schemas"]["TextToCypherRequest"];
+export type ErrorResponse = components["schemas"]["ErrorResponse"];
+
+export interface ClientOptions {
+  /** Sent as `Authorization: Bearer <key>` when the server requires API keys. */
+  apiKey?: string;
+  /** Replaces the global `fetch`, e.g. to add headers or retries. */
+  fetch?: typeof fetch;
+}
+
+/** A failed, non-streaming response. */
+export class TextToCypherError extends Error {
+  constructor(
+    readonly status: number,
+    message: string,
+  ) {
+    super(message);
+    this.name = "TextToCypherError";
+  }
+}
+
+/** Calls a text-to-cypher server. */
+export class TextToCypherClient {
+  private readonly baseUrl: string;
+  private readonly fetch: typeof fetch;
+
+  constructor(
+    baseUrl: string,
+    private readonly options: ClientOptions = {},
+  ) {
+    this.baseUrl = baseUrl.replace(/\/+$/, "");
+    this.fetch = options.fetch ?? globalThis.fetch.bind(globalThis);
+  }
+
+  /** Sends a request to `path` and returns the response, throwing on an error status. */
+  async request(path: string, init: RequestInit = {}): Promise<Response> {
+    const headers = new Headers(init.headers);
+    if (this.options.apiKey) {
+      headers.set("Authorization", `Bearer ${this.options.apiKey}`);
+    }
+    if (init.body !== undefined && !headers.has("Content-Type")) {
+      headers.set("Content-Type", "application/json");
+    }
+    const response = await this.fetch(`${this.baseUrl}${path}`, { ...init, headers });
+    if (!response.ok) {
+      const text = await response.text();
+      let message = text || response.statusText;
+      try {
+        message = (JSON.parse(text) as ErrorResponse).error ?? message;
+      } catch {
+        // Not an ErrorResponse
+      }
+      throw new TextToCypherError(response.status, message);
+    }
+    return response;
+  }
+
+  /** Streams the progress events of a `/text_to_cypher` request, ending after `Done`. */
+  async *stream(request: TextToCypherRequest, signal?: AbortSignal): AsyncGenerator<ProgressEvent> {
+    const response = await this.request("/text_to_cypher", {
+      method: "POST",
+      body: JSON.stringify(request),
+      headers: { Accept: "text/event-stream" },
+      signal,
+    });
+    if (!response.body) {
+      throw new TextToCypherError(response.status, "The response has no body");
+    }
+    yield* progressEvents(response.body);
+  }
+
+  /** Runs a `/text_to_cypher` request to the end and returns what it amounted to. */
+  async ask(request: TextToCypherRequest, signal?: AbortSignal): Promise<Answer> {
+    return collectAnswer(this.stream(request, signal));
+  }
+}
@@ -0,0 +1,83 @@
+import type { components } from "./schema.js";
+import { parseSse } from "./sse.js";
+
+/** One event of a `/text_to_cypher` stream, e.g. `{ Status: "..." }` or `{ CypherQuery: "..." }`. */
+export type Progress = components["schemas"]["Progress"];
+export type Warning = components["schemas"]["Warning"];
+export type TokenUsage = components["schemas"]["TokenUsage"];
+export type StreamStatus = components["schemas"]["StreamStatus"];
+
+/** Every key of the `Progress` union, such as `"Status"` or `"Done"`. */
+export type ProgressKind = Progress extends infer P ? (P extends unknown ? keyof P : never) : never;
+
+/** The kind of a progress event: its only key. */
+export function progressKind(event: Progress): ProgressKind {
+  return Object.keys(event)[0] as ProgressKind;
+}
+
+/** A progress event together with its signed SSE `id`, when the server signs events. */
+export interface ProgressEvent {
+  id?: string;
+  progress: Progress;
+}
+
+/** Parses a `/text_to_cypher` response body into progress events, ending after `Done`. */
+export async function* progressEvents(body: ReadableStream<Uint8Array>): AsyncGenerator<ProgressEvent> {
+  for await (const event of parseSse(body)) {
+    const progress = JSON.parse(event.data) as Progress;
+    yield { id: event.id, progress };
+    if ("Done" in progress) {
+      return;
+    }
+  }
+}
+
+/** What a whole stream amounted to. */
+export interface Answer {
+  /** How the stream ended; `undefined` if it was cut off before `Done`. */
+  status?: StreamStatus;
+  requestId?: string;
+  /** The graph a request without a `graph_name` was routed to. */
+  graph?: string;
+  /** The last query generated. */
+  cypherQuery?: string;
+  cypherResult?: string;
+  /** The execution plan of a `dry_run` request's query, one operation per line. */
+  plan?: string[];
+  /** The final answer: the `Result` event, or the streamed answer chunks if none arrived. */
+  answer: string;
+  /** Why the model wrote no query, for a `NoAnswer` stream. */
+  noAnswer?: string;
+  error?: string;
+  warnings: Warning[];
+  confidence?: number;
+  followups: string[];
+  usage?: TokenUsage;
+}
+
+/** Folds progress events into an {@link Answer}. */
+export async function collectAnswer(events: AsyncIterable<ProgressEvent>): Promise<Answer> {
+  const answer: Answer = { answer: "", warnings: [], followups: [] };
+  let chunks = "";
+  let result: string | undefined;
+  for await (const { progress } of events) {
+    if ("Graph" in progress) answer.graph = progress.Graph;
+    else if ("CypherQuery" in progress) answer.cypherQuery = progress.CypherQuery;
+    else if ("CypherResult" in progress) answer.cypherResult = progress.CypherResult;
+    else if ("QueryPlan" in progress) answer.plan = progress.QueryPlan;
+    else if ("ModelOutputChunk" in progress) chunks += progress.ModelOutputChunk;
+    else if ("Result" in progress) result = progress.Result;
+    else if ("NoAnswer" in progress) answer.noAnswer = progress.NoAnswer;
+    else if ("Error" in progress) answer.error = progress.Error;
+    else if ("Warning" in progress) answer.warnings.push(progress.Warning);
+    else if ("Confidence" in progress) answer.confidence = progress.Confidence;
+    else if ("Followups" in progress) answer.followups = progress.Followups;
+    else if ("Usage" in progress) answer.usage = progress.Usage;
+    else if ("Done" in progress) {
+      answer.status = progress.Done.status;
+      answer.requestId = progress.Done.request_id;
+    }
+  }
+  answer.answer = result ?? chunks;
+  return answer;
+}
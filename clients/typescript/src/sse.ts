@@ -0,0 +1,69 @@
+/** One server-sent event: its `data:` payload and, when the server signs events, its `id:`. */
+export interface SseEvent {
+  id?: string;
+  data: string;
+}
+
+/**
+ * Parses a `text/event-stream` body into events, following the SSE specification: fields may be
+ * split across chunks, multi-line `data:` fields are joined with newlines, and comments are
+ * skipped.
+ */
+export async function* parseSse(body: ReadableStream<Uint8Array>): AsyncGenerator<SseEvent> {
+  const reader = body.pipeThrough(new TextDecoderStream()).getReader();
+  let buffer = "";
+  let data: string[] = [];
+  let id: string | undefined;
+
+  // Returns the event a blank line completes
+  const feed = (line: string): SseEvent | undefined => {
+    if (line === "") {
+      const event = data.length > 0 ? { id, data: data.join("\n") } : undefined;
+      data = [];
+      return event;
+    }
+    if (line.startsWith(":")) {
+      return undefined;
+    }
+    const colon = line.indexOf(":");
+    const field = colon === -1 ? line : line.slice(0, colon);
+    let value = colon === -1 ? "" : line.slice(colon + 1);
+    if (value.startsWith(" ")) {
+      value = value.slice(1);
+    }
+    if (field === "data") {
+      data.push(value);
+    } else if (field === "id") {
+      id = value;
+    }
+    return undefined;
+  };
+
+  try {
+    for (;;) {
+      const { value, done } = await reader.read();
+      if (done) {
+        break;
+      }
+      buffer += value;
+      // A trailing "\r" may be the first half of a "\r\n" split across chunks
+      const carry = buffer.endsWith("\r") ? "\r" : "";
+      const lines = buffer.slice(0, buffer.length - carry.length).split(/\r\n|\r|\n/);
+      buffer = (lines.pop() ?? "") + carry;
+      for (const line of lines) {
+        const event = feed(line);
+        if (event) {
+          yield event;
+        }
+      }
+    }
+    for (const line of [buffer.replace(/\r$/, ""), ""]) {
+      const event = feed(line);
+      if (event) {
+        yield event;
+      }
+    }
+  } finally {
+    reader.releaseLock();
+  }
+}
@@ -9,6 +9,7 @@
 //! 2. Set your API key: export OPENAI_API_KEY=your-key-here
 //! 3. Run: cargo run --example `library_usage` --no-default-features
 
+use text_to_cypher::validator::ValidationPolicy;
 use text_to_cypher::{ChatMessage, ChatRequest, ChatRole, TextToCypherClient, core};
 
 #[tokio::main]
@@ -56,8 +57,16 @@ async fn example_with_client(
     falkordb_connection: &str,
     graph_name: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Create a client
-    let client = TextToCypherClient::new(model, api_key, falkordb_connection);
+    // Create a client that may create nodes; without a validation policy every query runs read-only
+    let client = TextToCypherClient::builder()
+        .model(model)
+        .api_key(api_key)
+        .falkordb_connection(falkordb_connection)
+        .validation_policy(ValidationPolicy {
+            allow_create: true,
+            ..ValidationPolicy::default()
+        })
+        .build()?;
 
     // Discover the schema first (optional, but helpful to see what's available)
     println!("Discovering graph schema...");
@@ -0,0 +1,271 @@
+#![recursion_limit = "256"]
+//! Load test of the REST server, for catching performance regressions before a release.
+//!
+//! Seeds a graph, starts a mock `OpenAI`-compatible model on a local port, and sends `REQUESTS`
+//! `POST /text_to_cypher` requests to the server at `T2C_URL`, `CONCURRENCY` at a time. Each
+//! request names the mock as its `llm_endpoint`, so the run measures the pipeline (schema
+//! discovery, validation, execution, streaming) without model latency or cost. The report gives
+//! the throughput and the latency percentiles of complete streams.
+//!
+//! Settings (environment):
+//! - `T2C_URL`: the server (default `http://127.0.0.1:8080`); it must run on this host to reach
+//!   the mock model.
+//! - `T2C_API_KEY`: sent as `X-API-Key` when the server requires keys.
+//! - `FALKORDB_CONNECTION`: the database the server queries (default `falkor://127.0.0.1:6379`).
+//! - `GRAPH_NAME`: the graph replaced by the seed (default `load_test`).
+//! - `CONCURRENCY` (default 8) and `REQUESTS` (default 200).
+//!
+//! Run: `cargo run --release --example load_test`
+//!
+//! The process exits non-zero when a request fails, so CI can run it.
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use text_to_cypher::core;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// The query the mock model writes for every question.
+const MOCK_QUERY: &str = "MATCH (p:Person)-[:WORKS_AT]->(c:Company) RETURN c.name AS company, count(p) AS employees ORDER BY employees DESC LIMIT 10";
+
+/// The answer the mock model streams.
+const MOCK_ANSWER: &str = "Company 0 has the most employees.\nCONFIDENCE: 90";
+
+const QUESTION: &str = "Which companies have the most employees?";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let server = std::env::var("T2C_URL").unwrap_or_else(|_| "http://127.0.0.1:8080".to_string());
+    let api_key = std::env::var("T2C_API_KEY").ok();
+    let falkordb_connection =
+        std::env::var("FALKORDB_CONNECTION").unwrap_or_else(|_| "falkor://127.0.0.1:6379".to_string());
+    let graph_name = std::env::var("GRAPH_NAME").unwrap_or_else(|_| "load_test".to_string());
+    let concurrency = env_count("CONCURRENCY", 8)?;
+    let requests = env_count("REQUESTS", 200)?;
+
+    seed_graph(&falkordb_connection, &graph_name).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let llm_endpoint = format!("http://{}/v1/", listener.local_addr()?);
+    tokio::spawn(serve_mock_model(listener));
+
+    println!("{requests} requests to {server}, {concurrency} at a time, model at {llm_endpoint}");
+    let body = serde_json::json!({
+        "graph_name": graph_name,
+        "chat_request": { "messages": [{ "role": "user", "content": QUESTION }] },
+        "model": "gpt-4o-mini",
+        "key": "mock",
+        "llm_endpoint": llm_endpoint,
+        "falkordb_connection": falkordb_connection,
+    });
+    let client = reqwest::Client::new();
+    let next = Arc::new(AtomicUsize::new(0));
+    let started = Instant::now();
+    let workers: Vec<_> = (0..concurrency)
+        .map(|_| {
+            let (client, next, body) = (client.clone(), next.clone(), body.clone());
+            let url = format!("{}/text_to_cypher", server.trim_end_matches('/'));
+            let api_key = api_key.clone();
+            tokio::spawn(async move {
+                let mut outcomes = Vec::new();
+                while next.fetch_add(1, Ordering::Relaxed) < requests {
+                    let sent = Instant::now();
+                    let outcome = ask(&client, &url, api_key.as_deref(), &body).await;
+                    outcomes.push((sent.elapsed(), outcome));
+                }
+                outcomes
+            })
+        })
+        .collect();
+
+    let mut latencies = Vec::with_capacity(requests);
+    let mut failures = 0;
+    for worker in workers {
+        for (latency, outcome) in worker.await? {
+            if let Err(e) = outcome {
+                if failures == 0 {
+                    eprintln!("First failure: {e}");
+                }
+                failures += 1;
+            }
+            latencies.push(latency);
+        }
+    }
+    let elapsed = started.elapsed();
+    latencies.sort_unstable();
+
+    println!(
+        "{} requests in {:.2}s: {:.1} requests/s, {failures} failed",
+        latencies.len(),
+        elapsed.as_secs_f64(),
+        f64::from(u32::try_from(latencies.len()).unwrap_or(u32::MAX)) / elapsed.as_secs_f64()
+    );
+    for percentile in [50, 90, 95, 99] {
+        println!(
+            "p{percentile}: {:.1} ms",
+            percentile_of(&latencies, percentile).as_secs_f64() * 1000.0
+        );
+    }
+    if let Some(max) = latencies.last() {
+        println!("max: {:.1} ms", max.as_secs_f64() * 1000.0);
+    }
+    if failures > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn env_count(
+    name: &str,
+    default: usize,
+) -> Result<usize, String> {
+    std::env::var(name).map_or(Ok(default), |value| match value.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("{name} must be a positive number, got '{value}'")),
+    })
+}
+
+/// The latency `percentile` percent of the sorted `latencies` are within.
+fn percentile_of(
+    latencies: &[Duration],
+    percentile: usize,
+) -> Duration {
+    if latencies.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (latencies.len() * percentile).div_ceil(100).max(1);
+    latencies[rank - 1]
+}
+
+/// Replaces the graph's content with 50 companies and 2,000 people working at them.
+async fn seed_graph(
+    falkordb_connection: &str,
+    graph_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for query in [
+        "MATCH (n) DETACH DELETE n",
+        "UNWIND range(0, 49) AS i CREATE (:Company {id: i, name: 'Company ' + toString(i)})",
+        "UNWIND range(0, 1999) AS i MATCH (c:Company {id: i % 50}) \
+         CREATE (:Person {id: i, name: 'Person ' + toString(i), age: 20 + i % 45})-[:WORKS_AT {since: 2000 + i % 25}]->(c)",
+    ] {
+        core::execute_cypher_query(query, graph_name, falkordb_connection, false).await?;
+    }
+    Ok(())
+}
+
+/// Sends one question and reads its progress stream to the end; an `Error` event or a stream
+/// without a `Result` is a failure.
+async fn ask(
+    client: &reqwest::Client,
+    url: &str,
+    api_key: Option<&str>,
+    body: &serde_json::Value,
+) -> Result<(), String> {
+    let mut request = client.post(url).json(body);
+    if let Some(api_key) = api_key {
+        request = request.header("X-API-Key", api_key);
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    let stream = response.text().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("{status}: {stream}"));
+    }
+    let events = stream
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .filter_map(|data| serde_json::from_str::<serde_json::Value>(data.trim()).ok());
+    for event in events {
+        if let Some(error) = event.get("Error") {
+            return Err(error.to_string());
+        }
+        if event.get("Result").is_some() {
+            return Ok(());
+        }
+    }
+    Err("the stream ended without a result".to_string())
+}
+
+/// Answers `OpenAI` chat completion calls: [`MOCK_QUERY`] for query generation, and
+/// [`MOCK_ANSWER`] for the streamed answer.
+async fn serve_mock_model(listener: TcpListener) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(async move {
+                    if let Err(e) = answer_model_call(stream).await {
+                        eprintln!("Mock model connection failed: {e}");
+                    }
+                });
+            }
+            Err(e) => eprintln!("Mock model accept failed: {e}"),
+        }
+    }
+}
+
+async fn answer_model_call(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 8192];
+    let body_start = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+    let headers = String::from_utf8_lossy(&buffer[..body_start]).to_ascii_lowercase();
+    let content_length = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|length| length.trim().parse().ok())
+        .unwrap_or(0);
+    while buffer.len() < body_start + content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let request: serde_json::Value = serde_json::from_slice(&buffer[body_start..]).unwrap_or_default();
+
+    let usage = serde_json::json!({ "prompt_tokens": 1000, "completion_tokens": 40, "total_tokens": 1040 });
+    let (content_type, payload) = if request["stream"] == true {
+        let chunk = |choices: serde_json::Value| serde_json::json!({ "id": "mock", "object": "chat.completion.chunk", "created": 0, "model": "mock", "choices": choices });
+        let mut end = chunk(serde_json::json!([]));
+        end["usage"] = usage;
+        let events = [
+            chunk(
+                serde_json::json!([{ "index": 0, "delta": { "role": "assistant", "content": MOCK_ANSWER }, "finish_reason": null }]),
+            ),
+            chunk(serde_json::json!([{ "index": 0, "delta": {}, "finish_reason": "stop" }])),
+            end,
+        ];
+        let mut payload = String::new();
+        for event in &events {
+            let _ = write!(payload, "data: {event}\n\n");
+        }
+        payload.push_str("data: [DONE]\n\n");
+        ("text/event-stream", payload)
+    } else {
+        let completion = serde_json::json!({
+            "id": "mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{ "index": 0, "message": { "role": "assistant", "content": MOCK_QUERY }, "finish_reason": "stop" }],
+            "usage": usage,
+        });
+        ("application/json", completion.to_string())
+    };
+    let response = format!(
+        "HTTP/1.1 200 OK\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{payload}",
+        payload.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
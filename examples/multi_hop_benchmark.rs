@@ -0,0 +1,52 @@
+#![recursion_limit = "256"]
+//! Runs the bundled multi-hop question benchmark.
+//!
+//! Modes, picked by the first argument:
+//! - `replay` (default): checks the recorded replies offline; no model or database needed.
+//! - `execute`: seeds the demo graphs (`bench_movies`, `bench_org`) in `FalkorDB`, runs the
+//!   recorded queries, and compares their results with the expected ones.
+//! - `live`: as `execute`, but the queries are generated by `MODEL` with `API_KEY`.
+//!
+//! `execute` and `live` replace the content of the demo graphs on `FALKORDB_CONNECTION`
+//! (default `falkor://127.0.0.1:6379`).
+//!
+//! Run: `cargo run --example multi_hop_benchmark --no-default-features -- live`
+//!
+//! The process exits non-zero when a case fails, so CI can run it.
+
+use text_to_cypher::TextToCypherClient;
+use text_to_cypher::benchmark::{self, Corpus, QuerySource};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mode = std::env::args().nth(1).unwrap_or_else(|| "replay".to_string());
+    let falkordb_connection =
+        std::env::var("FALKORDB_CONNECTION").unwrap_or_else(|_| "falkor://127.0.0.1:6379".to_string());
+    let corpus = Corpus::multi_hop();
+
+    let report = match mode.as_str() {
+        "replay" => benchmark::replay(&corpus),
+        "execute" => benchmark::run(&corpus, QuerySource::Recorded, &falkordb_connection).await?,
+        "live" => {
+            let model = std::env::var("MODEL").map_err(|_| "Set MODEL to run the live benchmark")?;
+            let api_key = std::env::var("API_KEY").map_err(|_| "Set API_KEY to run the live benchmark")?;
+            let client = TextToCypherClient::new(model, api_key, &falkordb_connection);
+            benchmark::run(&corpus, QuerySource::Model(&client), &falkordb_connection).await?
+        }
+        other => return Err(format!("Unknown mode '{other}'; expected replay, execute, or live").into()),
+    };
+
+    for case in &report.cases {
+        let status = if case.passed() { "ok" } else { "FAIL" };
+        println!(
+            "[{status:>4}] {}: {}",
+            case.id,
+            case.query.as_deref().unwrap_or("(no query)")
+        );
+    }
+    println!("{}", report.summary());
+    if !report.passed() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
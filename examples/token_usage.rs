@@ -1,3 +1,4 @@
+#![recursion_limit = "256"]
 //! Example demonstrating token-usage tracking with the text-to-cypher library.
 //!
 //! A single `text_to_cypher` request may issue several LLM calls (schema-aware Cypher
@@ -6,7 +7,8 @@
 //! into `TextToCypherResponse::token_usage`.
 //!
 //! This example runs a real request against an `OpenAI` model and prints the aggregated
-//! `TokenUsage`.
+//! `TokenUsage`, each call's counts, and the estimated cost when the model has a price (see
+//! `TextToCypherClient::builder().model_prices(...)`).
 //!
 //! To run this example:
 //! 1. Ensure `FalkorDB` is running, e.g.:
@@ -95,18 +97,32 @@ fn report_usage(response: &TextToCypherResponse) {
         println!("Answer: {answer}");
     }
 
-    match response.token_usage {
+    match &response.token_usage {
         Some(TokenUsage {
             prompt_tokens,
             completion_tokens,
             total_tokens,
+            calls,
+            estimated_cost_usd,
         }) => {
             println!("Token usage:");
             println!("  prompt_tokens     = {prompt_tokens}");
             println!("  completion_tokens = {completion_tokens}");
             println!("  total_tokens      = {total_tokens}");
+            for (index, call) in calls.iter().enumerate() {
+                println!(
+                    "  call {}: {} prompt={} completion={}",
+                    index + 1,
+                    call.model,
+                    call.prompt_tokens,
+                    call.completion_tokens
+                );
+            }
+            if let Some(cost) = estimated_cost_usd {
+                println!("  estimated cost    = ${cost:.6}");
+            }
 
-            if total_tokens == 0 {
+            if *total_tokens == 0 {
                 eprintln!("⚠ token_usage was reported but total_tokens is 0 — the provider may not return usage data.");
             } else {
                 println!("✓ Token usage tracking is working.");
@@ -0,0 +1,126 @@
+//! Friendly names for graphs.
+//!
+//! Operators map user-facing aliases to physical graph keys, optionally with a description, so
+//! requests can say `sales` while the data lives in `crm_prod_v2`. Aliases are configured as a
+//! semicolon-separated list of `alias=graph[:description]` entries, for example
+//! `GRAPH_ALIASES=sales=crm_prod_v2:Accounts, deals and pipeline;hr=people_v3`. Names that are not
+//! aliases resolve to themselves, so physical keys keep working.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// One alias and the graph it names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct GraphAlias {
+    /// The user-facing name.
+    pub alias: String,
+    /// The physical graph key in `FalkorDB`.
+    pub graph: String,
+    /// What the graph contains, for people and agents choosing between graphs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Configured graph aliases.
+#[derive(Debug, Clone, Default)]
+pub struct GraphAliases {
+    aliases: Vec<GraphAlias>,
+}
+
+impl GraphAliases {
+    /// Parses an `alias=graph[:description];...` list. Blank entries are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending entry if it has no `=`, an empty alias or graph, or
+    /// repeats an alias.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut aliases: Vec<GraphAlias> = Vec::new();
+        for entry in spec.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+            let (alias, target) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("graph alias '{entry}' must look like alias=graph[:description]"))?;
+            let (graph, description) = match target.split_once(':') {
+                Some((graph, description)) => (graph, Some(description.trim()).filter(|d| !d.is_empty())),
+                None => (target, None),
+            };
+            let (alias, graph) = (alias.trim(), graph.trim());
+            if alias.is_empty() || graph.is_empty() {
+                return Err(format!("graph alias '{entry}' needs both an alias and a graph"));
+            }
+            if aliases.iter().any(|existing| existing.alias == alias) {
+                return Err(format!("graph alias '{alias}' is defined more than once"));
+            }
+            aliases.push(GraphAlias {
+                alias: alias.to_string(),
+                graph: graph.to_string(),
+                description: description.map(ToString::to_string),
+            });
+        }
+        Ok(Self { aliases })
+    }
+
+    /// Returns true when no aliases are configured.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// The configured aliases, in configuration order.
+    #[must_use]
+    pub fn aliases(&self) -> &[GraphAlias] {
+        &self.aliases
+    }
+
+    /// Returns the physical graph key for `name`: the aliased graph, or `name` itself.
+    #[must_use]
+    pub fn resolve<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> &'a str {
+        self.aliases
+            .iter()
+            .find(|alias| alias.alias == name)
+            .map_or(name, |alias| alias.graph.as_str())
+    }
+
+    /// Returns the first alias configured for the physical graph `graph`, if any.
+    #[must_use]
+    pub fn alias_for(
+        &self,
+        graph: &str,
+    ) -> Option<&GraphAlias> {
+        self.aliases.iter().find(|alias| alias.graph == graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_resolves_aliases() {
+        let aliases =
+            GraphAliases::parse(" sales = crm_prod_v2 : Accounts, deals and pipeline ; hr=people_v3;").unwrap();
+        assert_eq!(aliases.aliases().len(), 2);
+        assert_eq!(aliases.resolve("sales"), "crm_prod_v2");
+        assert_eq!(aliases.resolve("crm_prod_v2"), "crm_prod_v2");
+        assert_eq!(aliases.resolve("unknown"), "unknown");
+
+        let sales = aliases.alias_for("crm_prod_v2").unwrap();
+        assert_eq!(sales.alias, "sales");
+        assert_eq!(sales.description.as_deref(), Some("Accounts, deals and pipeline"));
+        assert_eq!(aliases.alias_for("people_v3").unwrap().description, None);
+        assert!(GraphAliases::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        assert!(GraphAliases::parse("sales").is_err());
+        assert!(GraphAliases::parse("=crm").is_err());
+        assert!(GraphAliases::parse("sales=").is_err());
+        assert!(GraphAliases::parse("sales=a;sales=b").is_err());
+    }
+}
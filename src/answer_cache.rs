@@ -0,0 +1,150 @@
+//! Answers reused when a question's query returns the same result as before.
+//!
+//! Recurring questions, such as a report asked every morning, usually get the same query and, when
+//! the graph has not changed, the same result. The answer is stored under a SHA-256 hash of what it
+//! was written from: the graph, model, audience, conversation, query, result, and property units.
+//! Asking again then skips the answer-generation call and reuses the stored prose; any change to the
+//! result produces a new key and a freshly written answer.
+
+use crate::chat::ChatRequest;
+use crate::storage::{self, Storage, StorageError};
+use crate::template::Audience;
+use aws_lc_rs::digest;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Storage namespace holding cached answers.
+pub const ANSWER_CACHE_NAMESPACE: &str = "answers";
+
+/// An answer as the model wrote it, before response hooks, and its confidence.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CachedAnswer {
+    pub answer: String,
+    pub confidence: Option<u8>,
+}
+
+/// Everything an answer is written from; two requests with equal inputs get the same answer.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct AnswerInputs<'a> {
+    pub graph_name: &'a str,
+    pub model: &'a str,
+    pub audience: Option<Audience>,
+    pub chat_request: &'a ChatRequest,
+    pub query: &'a str,
+    pub query_result: &'a str,
+    /// The units of the properties the query reads, as written into the answer prompt.
+    pub units: &'a str,
+}
+
+impl AnswerInputs<'_> {
+    /// The cache key: the hex SHA-256 of the inputs.
+    #[must_use]
+    pub fn key(&self) -> String {
+        // Serializing borrowed strings and plain enums cannot fail
+        let encoded = serde_json::to_vec(self).unwrap_or_default();
+        let hash = digest::digest(&digest::SHA256, &encoded);
+        hash.as_ref().iter().fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+    }
+}
+
+/// Stores answers by the hash of their inputs, each for `ttl`.
+#[derive(Debug, Clone)]
+pub struct AnswerCache {
+    storage: Arc<dyn Storage>,
+    ttl: Duration,
+}
+
+impl AnswerCache {
+    /// Creates a cache backed by `storage` whose answers expire after `ttl`.
+    #[must_use]
+    pub fn new(
+        storage: Arc<dyn Storage>,
+        ttl: Duration,
+    ) -> Self {
+        Self { storage, ttl }
+    }
+
+    /// The answer stored for `inputs`, or `None` if there is none or it expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the answer cannot be read.
+    pub async fn get(
+        &self,
+        inputs: &AnswerInputs<'_>,
+    ) -> Result<Option<CachedAnswer>, StorageError> {
+        storage::get_json(self.storage.as_ref(), ANSWER_CACHE_NAMESPACE, &inputs.key()).await
+    }
+
+    /// Stores `answer` for `inputs`, replacing any previous one.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the answer cannot be saved.
+    pub async fn put(
+        &self,
+        inputs: &AnswerInputs<'_>,
+        answer: &CachedAnswer,
+    ) -> Result<(), StorageError> {
+        storage::put_json(
+            self.storage.as_ref(),
+            ANSWER_CACHE_NAMESPACE,
+            &inputs.key(),
+            answer,
+            Some(self.ttl),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{ChatMessage, ChatRole};
+    use crate::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn answers_are_reused_only_for_the_same_result() {
+        let cache = AnswerCache::new(Arc::new(InMemoryStorage::new()), Duration::from_secs(60));
+        let chat_request = ChatRequest {
+            messages: vec![ChatMessage {
+                role: ChatRole::User,
+                content: "How many orders shipped yesterday?".to_string(),
+            }],
+        };
+        let inputs = AnswerInputs {
+            graph_name: "sales",
+            model: "openai:gpt-4o-mini",
+            audience: None,
+            chat_request: &chat_request,
+            query: "MATCH (o:Order {shipped: date() - duration('P1D')}) RETURN count(o)",
+            query_result: "[42]",
+            units: "",
+        };
+        let answer = CachedAnswer {
+            answer: "42 orders shipped yesterday.".to_string(),
+            confidence: Some(95),
+        };
+        assert_eq!(cache.get(&inputs).await.unwrap(), None);
+
+        cache.put(&inputs, &answer).await.unwrap();
+        assert_eq!(cache.get(&inputs).await.unwrap(), Some(answer));
+
+        let changed = AnswerInputs {
+            query_result: "[43]",
+            ..inputs
+        };
+        assert_ne!(changed.key(), inputs.key());
+        assert_eq!(cache.get(&changed).await.unwrap(), None);
+        let executive = AnswerInputs {
+            audience: Some(Audience::Executive),
+            ..inputs
+        };
+        assert_eq!(cache.get(&executive).await.unwrap(), None);
+    }
+}
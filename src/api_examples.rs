@@ -0,0 +1,396 @@
+//! Example payloads for the `OpenAPI` document (Swagger UI "Try it out" presets).
+//!
+//! Every example is built from the request/response types the endpoints actually use and then
+//! serialized, so renaming or retyping a field breaks the build or the round-trip tests below
+//! instead of leaving stale examples in the docs. The Snowflake envelopes (`{"data": [[0, {...}]]}`)
+//! are untyped, so their row fields come from the same constants the examples share.
+
+use crate::chat::{ChatMessage, ChatRequest, ChatRole};
+use crate::schema::attribute::{Attribute, AttributeType};
+use crate::schema::discovery::{DiscoveryOptions, Schema};
+use crate::schema::entity::Entity;
+use crate::schema::relation::Relation;
+use crate::usage::{CallUsage, TokenUsage};
+use crate::{
+    AskRequest, CreateSessionRequest, EmbedRequest, EmbedResponse, GraphDeleteRequest, GraphListRequest,
+    GraphQueryRequest, LoadCsvRequest, Progress, RemoteImportRequest, SearchRequest, SearchResponse,
+    SessionCreatedResponse, TextToCypherRequest,
+};
+use ::text_to_cypher::import::{InspectOptions, inspect_csv};
+use ::text_to_cypher::latency::LatencyMode;
+use ::text_to_cypher::model_chain::ModelChain;
+use ::text_to_cypher::model_options::ModelOptions;
+use ::text_to_cypher::request_queue::Priority;
+use ::text_to_cypher::search::SearchHit;
+use ::text_to_cypher::session::{EXPORT_FORMAT_VERSION, Session, SessionExport, SessionTurn};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+const GRAPH: &str = "movies";
+const QUESTION: &str = "Which actors appeared in The Matrix?";
+const QUERY: &str = "MATCH (a:Actor)-[:ACTED_IN]->(m:Movie {title: 'The Matrix'}) RETURN a.name LIMIT 25";
+const QUERY_RESULT: &str = "1. \"Keanu Reeves\"\n2. \"Carrie-Anne Moss\"\n3. \"Laurence Fishburne\"";
+const ANSWER: &str = "Keanu Reeves, Carrie-Anne Moss and Laurence Fishburne appeared in The Matrix.";
+const SESSION_ID: &str = "6f1c2d9e-4b7a-4c1e-9a53-2f8e1d0b7c44";
+const CSV_FILE: &str = "actors.csv";
+const CSV_QUERY: &str = "LOAD CSV WITH HEADERS FROM 'file://actors.csv' AS row MERGE (:Actor {name: row.name})";
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Wraps `row` in the Snowflake external-function envelope: `{"data": [[0, row]]}`.
+fn snowflake_rows(row: &Value) -> Vec<Value> {
+    vec![json!([0, row])]
+}
+
+fn chat_request() -> ChatRequest {
+    ChatRequest {
+        messages: vec![ChatMessage {
+            role: ChatRole::User,
+            content: QUESTION.to_string(),
+        }],
+    }
+}
+
+pub fn text_to_cypher_request() -> Value {
+    to_value(&TextToCypherRequest {
+        graph_name: GRAPH.to_string(),
+        graphs: Vec::new(),
+        chat_request: chat_request(),
+        model: Some(ModelChain::new("openai:gpt-4o-mini").with_fallback("anthropic:claude-3-haiku")),
+        key: None,
+        falkordb_connection: None,
+        llm_endpoint: None,
+        cypher_only: false,
+        dry_run: false,
+        audience: None,
+        followups: false,
+        session_id: None,
+        strict_validation: false,
+        refine_lint_hints: false,
+        allow_destructive: false,
+        confirm: None,
+        latency_mode: LatencyMode::Balanced,
+        query_vector: None,
+        parameterized: false,
+        discovery: DiscoveryOptions::default(),
+        model_options: ModelOptions::default().with_temperature(0.0),
+        priority: Priority::Interactive,
+        schema: None,
+        clause_policy: None,
+        tenant_filter: None,
+        aggregate_only: false,
+        previous_query: None,
+        few_shot_prompt: None,
+    })
+}
+
+pub fn embed_request() -> Value {
+    to_value(&EmbedRequest {
+        text: "A hacker learns that reality is a simulation".to_string(),
+        model: Some("openai:text-embedding-3-small".to_string()),
+        key: None,
+        llm_endpoint: None,
+    })
+}
+
+pub fn search_request() -> Value {
+    to_value(&SearchRequest {
+        text: "hacker simulation".to_string(),
+        targets: Some("Movie;Movie.embedding".to_string()),
+        limit: 5,
+        query_vector: None,
+        model: Some("openai:text-embedding-3-small".to_string()),
+        key: None,
+        llm_endpoint: None,
+    })
+}
+
+pub fn search_response() -> Value {
+    to_value(&SearchResponse {
+        hits: vec![SearchHit {
+            id: 12,
+            labels: vec!["Movie".to_string()],
+            properties: json!({"title": "The Matrix", "released": 1999}),
+            score: 0.0325,
+            matched_by: vec!["fulltext:Movie".to_string(), "vector:Movie.embedding".to_string()],
+        }],
+    })
+}
+
+pub fn embed_response() -> Value {
+    // Real embeddings have hundreds of components; the example keeps four.
+    to_value(&EmbedResponse {
+        query_vector: vec![0.012, -0.034, 0.051, 0.007],
+        dimension: 4,
+        model: "openai:text-embedding-3-small".to_string(),
+    })
+}
+
+/// The events a successful `/text_to_cypher` run streams, in order.
+fn text_to_cypher_events() -> Vec<Progress> {
+    vec![
+        Progress::Status(format!("Discovering schema for graph: {GRAPH}")),
+        Progress::Schema(to_value(&schema()).to_string()),
+        Progress::Status("Generating Cypher query using schema ...".to_string()),
+        Progress::CypherQuery(QUERY.to_string()),
+        Progress::CypherResult(QUERY_RESULT.to_string()),
+        Progress::ModelOutputChunk("Keanu Reeves, ".to_string()),
+        Progress::Result(ANSWER.to_string()),
+        Progress::Confidence(95),
+        Progress::Usage(TokenUsage {
+            prompt_tokens: 1840,
+            completion_tokens: 64,
+            total_tokens: 1904,
+            calls: vec![
+                CallUsage {
+                    model: "gpt-4o-mini".to_string(),
+                    prompt_tokens: 1500,
+                    completion_tokens: 40,
+                },
+                CallUsage {
+                    model: "gpt-4o-mini".to_string(),
+                    prompt_tokens: 340,
+                    completion_tokens: 24,
+                },
+            ],
+            estimated_cost_usd: Some(0.000_314_4),
+        }),
+    ]
+}
+
+/// A `/text_to_cypher` SSE transcript: one `data:` line per progress event.
+pub fn text_to_cypher_stream() -> Value {
+    let mut transcript = String::new();
+    for event in text_to_cypher_events() {
+        transcript.push_str("data: ");
+        transcript.push_str(&to_value(&event).to_string());
+        transcript.push_str("\n\n");
+    }
+    Value::String(transcript)
+}
+
+fn schema() -> Schema {
+    let name = || Attribute::new("name".to_string(), AttributeType::String, 0, false, true);
+    Schema {
+        entities: vec![
+            Entity::new("Actor".to_string(), vec![name()], None),
+            Entity::new(
+                "Movie".to_string(),
+                vec![
+                    Attribute::new("title".to_string(), AttributeType::String, 0, true, true),
+                    Attribute::new("released".to_string(), AttributeType::Integer, 0, false, false),
+                ],
+                None,
+            ),
+        ],
+        relations: vec![Relation::new(
+            "ACTED_IN".to_string(),
+            "Actor".to_string(),
+            "Movie".to_string(),
+            Vec::new(),
+        )],
+        label_combinations: Vec::new(),
+        indexes: Vec::new(),
+        constraints: Vec::new(),
+    }
+}
+
+pub fn get_schema_response() -> Value {
+    Value::String(to_value(&schema()).to_string())
+}
+
+pub fn graph_query_request() -> Value {
+    to_value(&GraphQueryRequest {
+        data: snowflake_rows(&json!({ "graph_name": GRAPH, "query": QUERY })),
+    })
+}
+
+pub fn graph_query_response() -> Value {
+    json!({ "data": snowflake_rows(&json!([["Keanu Reeves"], ["Carrie-Anne Moss"], ["Laurence Fishburne"]])) })
+}
+
+pub fn graph_list_request() -> Value {
+    to_value(&GraphListRequest {
+        data: snowflake_rows(&json!({})),
+    })
+}
+
+pub fn graph_names() -> Value {
+    json!([GRAPH, "social"])
+}
+
+pub fn graph_list_response() -> Value {
+    json!({ "data": snowflake_rows(&graph_names()) })
+}
+
+pub fn graph_delete_request() -> Value {
+    to_value(&GraphDeleteRequest {
+        data: snowflake_rows(&json!({ "graph_name": GRAPH })),
+    })
+}
+
+pub fn load_csv_request() -> Value {
+    to_value(&LoadCsvRequest {
+        data: snowflake_rows(&json!({ "graph_name": GRAPH, "csv_file": CSV_FILE, "cypher_query": CSV_QUERY })),
+    })
+}
+
+pub fn remote_import_request() -> Value {
+    to_value(&RemoteImportRequest {
+        url: format!("s3://exports/{CSV_FILE}"),
+        cypher_query: CSV_QUERY.to_string(),
+        sha256: None,
+        max_bytes: None,
+    })
+}
+
+pub fn echo_request() -> Value {
+    json!({ "graph_name": GRAPH, "question": QUESTION })
+}
+
+pub fn create_session_request() -> Value {
+    to_value(&CreateSessionRequest {
+        graph_name: GRAPH.to_string(),
+    })
+}
+
+pub fn session_created_response() -> Value {
+    to_value(&SessionCreatedResponse {
+        session_id: SESSION_ID.to_string(),
+    })
+}
+
+pub fn ask_request() -> Value {
+    to_value(&AskRequest {
+        question: "Which of them also appeared in John Wick?".to_string(),
+        model: None,
+        key: None,
+        llm_endpoint: None,
+        cypher_only: false,
+        audience: None,
+        followups: false,
+        latency_mode: LatencyMode::Balanced,
+        model_options: ModelOptions::default(),
+        priority: Priority::Interactive,
+    })
+}
+
+pub fn session_export() -> Value {
+    let mut turn = SessionTurn::new(QUESTION);
+    turn.model = Some("openai:gpt-4o-mini".to_string());
+    turn.cypher_query = Some(QUERY.to_string());
+    turn.set_result(QUERY_RESULT);
+    turn.answer = Some(ANSWER.to_string());
+    turn.confidence = Some(95);
+    turn.timestamp = 1_760_000_030;
+
+    let mut messages = chat_request().messages;
+    messages.push(ChatMessage {
+        role: ChatRole::Assistant,
+        content: ANSWER.to_string(),
+    });
+
+    to_value(&SessionExport {
+        format_version: EXPORT_FORMAT_VERSION,
+        exported_at: 1_760_000_100,
+        session: Session {
+            id: SESSION_ID.to_string(),
+            graph_name: GRAPH.to_string(),
+            created_at: 1_760_000_000,
+            updated_at: 1_760_000_030,
+            messages,
+            turns: vec![turn],
+        },
+    })
+}
+
+pub fn csv_inspection() -> Value {
+    let csv = "name,born,oscars\nKeanu Reeves,1964-09-02,0\nCarrie-Anne Moss,1967-08-21,\n";
+    to_value(&inspect_csv(csv.as_bytes(), CSV_FILE, &InspectOptions::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::text_to_cypher::import::CsvInspection;
+
+    fn snowflake_row(envelope: &Value) -> &Value {
+        &envelope["data"][0][1]
+    }
+
+    #[test]
+    fn typed_examples_round_trip() {
+        let request: TextToCypherRequest = serde_json::from_value(text_to_cypher_request()).unwrap();
+        assert_eq!(request.graph_name, GRAPH);
+        assert_eq!(request.chat_request.last_user_question(), Some(QUESTION));
+
+        serde_json::from_value::<CreateSessionRequest>(create_session_request()).unwrap();
+        serde_json::from_value::<SessionCreatedResponse>(session_created_response()).unwrap();
+        serde_json::from_value::<AskRequest>(ask_request()).unwrap();
+        serde_json::from_value::<EmbedRequest>(embed_request()).unwrap();
+        let embedding: EmbedResponse = serde_json::from_value(embed_response()).unwrap();
+        assert_eq!(embedding.dimension, embedding.query_vector.len());
+        serde_json::from_value::<SearchRequest>(search_request()).unwrap();
+        let search: SearchResponse = serde_json::from_value(search_response()).unwrap();
+        assert_eq!(search.hits[0].matched_by.len(), 2);
+        let export: SessionExport = serde_json::from_value(session_export()).unwrap();
+        assert_eq!(export.session.turns.len(), 1);
+        let inspection: CsvInspection = serde_json::from_value(csv_inspection()).unwrap();
+        assert!(inspection.load_csv.contains(CSV_FILE));
+        let remote: RemoteImportRequest = serde_json::from_value(remote_import_request()).unwrap();
+        assert!(remote.cypher_query.contains(CSV_FILE));
+
+        let schema_json = get_schema_response();
+        serde_json::from_str::<Schema>(schema_json.as_str().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn snowflake_examples_carry_the_fields_handlers_read() {
+        let query = graph_query_request();
+        assert_eq!(snowflake_row(&query)["graph_name"], GRAPH);
+        assert_eq!(snowflake_row(&query)["query"], QUERY);
+
+        assert_eq!(snowflake_row(&graph_delete_request())["graph_name"], GRAPH);
+
+        let load = load_csv_request();
+        for field in ["graph_name", "csv_file", "cypher_query"] {
+            assert!(
+                snowflake_row(&load)[field].is_string(),
+                "load_csv example lacks {field}"
+            );
+        }
+
+        serde_json::from_value::<GraphListRequest>(graph_list_request()).unwrap();
+        assert!(snowflake_row(&graph_query_response()).is_array());
+    }
+
+    #[test]
+    fn stream_transcript_parses_as_progress_events() {
+        let transcript = text_to_cypher_stream();
+        let events: Vec<Progress> = transcript
+            .as_str()
+            .unwrap()
+            .split("\n\n")
+            .filter(|chunk| !chunk.is_empty())
+            .map(|chunk| serde_json::from_str(chunk.strip_prefix("data: ").unwrap()).unwrap())
+            .collect();
+        assert_eq!(events.len(), text_to_cypher_events().len());
+        assert!(matches!(events.last(), Some(Progress::Usage(_))));
+    }
+
+    #[test]
+    fn exported_spec_lists_endpoints_and_components() {
+        for yaml in [false, true] {
+            let spec = crate::openapi_spec(yaml).unwrap();
+            let spec: Value = if yaml {
+                serde_yaml_ng::from_str(&spec).unwrap()
+            } else {
+                serde_json::from_str(&spec).unwrap()
+            };
+            assert!(spec["paths"]["/text_to_cypher"]["post"].is_object());
+            assert!(spec["components"]["schemas"]["Progress"].is_object());
+        }
+    }
+}
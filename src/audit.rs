@@ -0,0 +1,282 @@
+//! Append-only audit trail of graph mutations, persisted in the server's [`Storage`], together with
+//! short-lived per-request [`DebugBundle`]s.
+//!
+//! Every `/text_to_cypher` request is recorded too, with its model and [`RequestUsage`], for the
+//! usage reports of [`crate::usage_report`]. Those records expire after the request retention.
+
+use crate::debug_bundle::{DEBUG_BUNDLE_TTL, DEBUG_NAMESPACE, DebugBundle};
+use crate::storage::{self, Storage, StorageError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Storage namespace holding audit records.
+pub const AUDIT_NAMESPACE: &str = "audit";
+
+/// The action of a served `/text_to_cypher` request's record.
+pub const TEXT_TO_CYPHER_ACTION: &str = "text_to_cypher";
+
+/// How long request records are kept by default: 30 days.
+pub const DEFAULT_REQUEST_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// How an audited action ended.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+/// What a served request cost.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default, PartialEq)]
+pub struct RequestUsage {
+    /// Milliseconds from the request's arrival to the end of its stream.
+    pub latency_ms: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Estimated cost in US dollars, when every model called has a price.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
+    /// Times the query was regenerated after it failed.
+    #[serde(default)]
+    pub heal_attempts: usize,
+}
+
+/// One audited action.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// Record identifier; sorts chronologically.
+    pub id: String,
+    /// Unix timestamp (milliseconds) of the action.
+    pub timestamp_ms: u64,
+    /// Who performed the action (see [`crate::auth::ApiKeys::key_id`]), never the raw key.
+    pub actor: Option<String>,
+    /// What was done, e.g. `"destructive_query"`.
+    pub action: String,
+    /// Graph the action targeted.
+    pub graph_name: String,
+    /// The executed query.
+    pub query: String,
+    pub outcome: AuditOutcome,
+    /// Result summary or error message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// The model that served a request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Latency, tokens and self-healing of a request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<RequestUsage>,
+}
+
+impl AuditRecord {
+    /// Creates a record stamped with the current time.
+    #[must_use]
+    pub fn new(
+        actor: Option<String>,
+        action: &str,
+        graph_name: &str,
+        query: &str,
+        outcome: AuditOutcome,
+        detail: Option<String>,
+    ) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX));
+        Self {
+            // Zero-padded timestamp first so key order is chronological.
+            id: format!("{timestamp_ms:020}-{}", Uuid::new_v4()),
+            timestamp_ms,
+            actor,
+            action: action.to_string(),
+            graph_name: graph_name.to_string(),
+            query: query.to_string(),
+            outcome,
+            detail,
+            model: None,
+            usage: None,
+        }
+    }
+
+    /// The record of a request served by `model`.
+    #[must_use]
+    pub fn with_usage(
+        mut self,
+        model: Option<String>,
+        usage: RequestUsage,
+    ) -> Self {
+        self.model = model;
+        self.usage = Some(usage);
+        self
+    }
+}
+
+/// Writes and reads audit records.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    storage: Arc<dyn Storage>,
+}
+
+impl AuditLog {
+    /// Creates an audit log backed by `storage`.
+    #[must_use]
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Appends `record` and mirrors it to the `audit` tracing target.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the record cannot be saved.
+    pub async fn record(
+        &self,
+        record: &AuditRecord,
+    ) -> Result<(), StorageError> {
+        tracing::info!(
+            target: "audit",
+            actor = record.actor.as_deref().unwrap_or("anonymous"),
+            action = %record.action,
+            graph = %record.graph_name,
+            outcome = ?record.outcome,
+            "{}",
+            record.query
+        );
+        storage::put_json(self.storage.as_ref(), AUDIT_NAMESPACE, &record.id, record, None).await
+    }
+
+    /// Appends the record of a served request, which expires after `retention` (`None` keeps it).
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the record cannot be saved.
+    pub async fn record_request(
+        &self,
+        record: &AuditRecord,
+        retention: Option<Duration>,
+    ) -> Result<(), StorageError> {
+        storage::put_json(self.storage.as_ref(), AUDIT_NAMESPACE, &record.id, record, retention).await
+    }
+
+    /// Returns all records, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if records cannot be listed or read.
+    pub async fn list(&self) -> Result<Vec<AuditRecord>, StorageError> {
+        self.list_between(0, u64::MAX).await
+    }
+
+    /// Returns the records stamped from `from_ms` up to `to_ms` (exclusive), oldest first. Only
+    /// those records are read: their IDs start with their timestamp.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if records cannot be listed or read.
+    pub async fn list_between(
+        &self,
+        from_ms: u64,
+        to_ms: u64,
+    ) -> Result<Vec<AuditRecord>, StorageError> {
+        let mut ids = self.storage.list_keys(AUDIT_NAMESPACE).await?;
+        ids.retain(|id| {
+            id.split('-')
+                .next()
+                .and_then(|timestamp| timestamp.parse::<u64>().ok())
+                .is_some_and(|timestamp| (from_ms..to_ms).contains(&timestamp))
+        });
+        ids.sort();
+        let mut records = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(record) = storage::get_json(self.storage.as_ref(), AUDIT_NAMESPACE, &id).await? {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Stores the debug bundle of a finished request for [`DEBUG_BUNDLE_TTL`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the bundle cannot be saved.
+    pub async fn record_debug_bundle(
+        &self,
+        bundle: &DebugBundle,
+    ) -> Result<(), StorageError> {
+        storage::put_json(
+            self.storage.as_ref(),
+            DEBUG_NAMESPACE,
+            &bundle.request_id,
+            bundle,
+            Some(DEBUG_BUNDLE_TTL),
+        )
+        .await
+    }
+
+    /// Returns the debug bundle of request `request_id`, unless it is unknown or has expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the backend fails or the stored bundle is corrupt.
+    pub async fn debug_bundle(
+        &self,
+        request_id: &str,
+    ) -> Result<Option<DebugBundle>, StorageError> {
+        storage::get_json(self.storage.as_ref(), DEBUG_NAMESPACE, request_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn records_are_listed_in_order() {
+        let log = AuditLog::new(Arc::new(InMemoryStorage::new()));
+        let first = AuditRecord::new(
+            None,
+            "destructive_query",
+            "g",
+            "MATCH (n) DELETE n",
+            AuditOutcome::Success,
+            None,
+        );
+        let mut second = AuditRecord::new(
+            Some("…abcd".into()),
+            "destructive_query",
+            "g",
+            "MATCH (m) DELETE m",
+            AuditOutcome::Failure,
+            Some("boom".into()),
+        );
+        second.id = format!("{:020}-x", first.timestamp_ms + 1);
+        log.record(&second).await.unwrap();
+        log.record(&first).await.unwrap();
+
+        assert_eq!(
+            log.list_between(first.timestamp_ms + 1, u64::MAX).await.unwrap(),
+            vec![second.clone()]
+        );
+        assert_eq!(log.list().await.unwrap(), vec![first, second]);
+    }
+
+    #[tokio::test]
+    async fn debug_bundles_are_stored_by_request_id() {
+        let log = AuditLog::new(Arc::new(InMemoryStorage::new()));
+        let bundle = crate::debug_bundle::DebugTrace::default().into_bundle(
+            "r1",
+            "g",
+            &crate::session::SessionTurn::new("q"),
+            std::time::Duration::ZERO,
+        );
+        log.record_debug_bundle(&bundle).await.unwrap();
+
+        assert_eq!(log.debug_bundle("r1").await.unwrap(), Some(bundle));
+        assert_eq!(log.debug_bundle("r2").await.unwrap(), None);
+        assert!(log.list().await.unwrap().is_empty());
+    }
+}
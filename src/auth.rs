@@ -0,0 +1,183 @@
+//! API keys and the scopes they grant.
+//!
+//! Keys are configured as a comma-separated list of `key[:scope]` entries (scope defaults to
+//! `read`), for example `API_KEYS=k1,k2:write,k3:admin`. Scopes are ordered: `admin` includes
+//! `write`, which includes `read`. Operations that only read the graph stay open when no keys are
+//! configured; anything requiring [`Scope::Write`] or above always needs a key.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Permission level granted to an API key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Scope {
+    /// Query and inspect graphs.
+    Read,
+    /// Additionally run queries that modify graphs.
+    Write,
+    /// Additionally use administrative endpoints.
+    Admin,
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            "admin" => Ok(Self::Admin),
+            other => Err(format!("unknown scope '{other}' (expected read, write, or admin)")),
+        }
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Admin => "admin",
+        })
+    }
+}
+
+/// Why a request was not authorized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// The operation needs a key and none was sent.
+    MissingKey(Scope),
+    /// The key is not configured.
+    InvalidKey,
+    /// The key is valid but its scope is too low.
+    InsufficientScope { required: Scope, granted: Scope },
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::MissingKey(required) => write!(f, "an API key with {required} scope is required"),
+            Self::InvalidKey => write!(f, "invalid API key"),
+            Self::InsufficientScope { required, granted } => {
+                write!(f, "API key has {granted} scope but {required} scope is required")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Configured API keys.
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeys {
+    keys: HashMap<String, Scope>,
+}
+
+impl ApiKeys {
+    /// Parses a `key[:scope],...` list. Blank entries are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending entry if a scope is unknown.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut keys = HashMap::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (key, scope) = match entry.rsplit_once(':') {
+                Some((key, scope)) => (key.trim(), scope.parse()?),
+                None => (entry, Scope::Read),
+            };
+            if !key.is_empty() {
+                keys.insert(key.to_string(), scope);
+            }
+        }
+        Ok(Self { keys })
+    }
+
+    /// Returns true when no keys are configured.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Checks that `key` grants at least `required`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason the key does not grant `required`. Read access without a key is allowed
+    /// only when no keys are configured.
+    pub fn authorize(
+        &self,
+        key: Option<&str>,
+        required: Scope,
+    ) -> Result<Scope, AuthError> {
+        let Some(key) = key else {
+            return if required == Scope::Read && self.is_empty() {
+                Ok(Scope::Read)
+            } else {
+                Err(AuthError::MissingKey(required))
+            };
+        };
+        let granted = *self.keys.get(key).ok_or(AuthError::InvalidKey)?;
+        if granted >= required {
+            Ok(granted)
+        } else {
+            Err(AuthError::InsufficientScope { required, granted })
+        }
+    }
+
+    /// A loggable identifier for `key` that does not reveal it: its last four characters.
+    #[must_use]
+    pub fn key_id(key: &str) -> String {
+        let tail: String = key.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect();
+        format!("…{tail}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scopes_with_read_default() {
+        let keys = ApiKeys::parse(" reader , writer:write,boss:ADMIN,").unwrap();
+        assert_eq!(keys.authorize(Some("reader"), Scope::Read), Ok(Scope::Read));
+        assert_eq!(keys.authorize(Some("boss"), Scope::Write), Ok(Scope::Admin));
+        assert_eq!(
+            keys.authorize(Some("reader"), Scope::Write),
+            Err(AuthError::InsufficientScope {
+                required: Scope::Write,
+                granted: Scope::Read
+            })
+        );
+        assert_eq!(keys.authorize(Some("nope"), Scope::Read), Err(AuthError::InvalidKey));
+        assert!(ApiKeys::parse("k:root").is_err());
+    }
+
+    #[test]
+    fn anonymous_reads_only_without_configured_keys() {
+        let open = ApiKeys::default();
+        assert_eq!(open.authorize(None, Scope::Read), Ok(Scope::Read));
+        assert_eq!(
+            open.authorize(None, Scope::Write),
+            Err(AuthError::MissingKey(Scope::Write))
+        );
+
+        let locked = ApiKeys::parse("k").unwrap();
+        assert_eq!(
+            locked.authorize(None, Scope::Read),
+            Err(AuthError::MissingKey(Scope::Read))
+        );
+    }
+
+    #[test]
+    fn key_id_hides_the_key() {
+        assert_eq!(ApiKeys::key_id("secret-abcd"), "…abcd");
+        assert_eq!(ApiKeys::key_id("ab"), "…ab");
+    }
+}
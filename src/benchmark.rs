@@ -0,0 +1,379 @@
+//! Multi-hop question benchmark.
+//!
+//! [`Corpus::multi_hop`] bundles small demo graphs (their schema and the queries that build them)
+//! with questions that need two or more relationship hops to answer, each with the signature of its
+//! correct result: the number of rows and values those rows must contain. Every case also carries a
+//! recorded model reply, so the corpus runs in three modes:
+//!
+//! - [`replay`] parses, validates and shape-checks the recorded replies, with no model and no
+//!   database. It runs in `cargo test`, so validator and reply-parsing changes that break a case fail CI.
+//! - [`run`] with [`QuerySource::Recorded`] also seeds the demo graphs in `FalkorDB`, executes the
+//!   recorded queries and compares their results with the expected signatures.
+//! - [`run`] with [`QuerySource::Model`] asks a model instead, to measure a prompt or model change.
+//!
+//! ```rust
+//! use text_to_cypher::benchmark::{self, Corpus};
+//!
+//! let report = benchmark::replay(&Corpus::multi_hop());
+//! assert!(report.passed(), "{}", report.summary());
+//! ```
+
+use crate::TextToCypherClient;
+use crate::chat::{ChatMessage, ChatRequest, ChatRole};
+use crate::core::{GenerationOutcome, query_rows};
+use crate::error::TextToCypherError;
+use crate::query_result::ResultValue;
+use crate::schema::discovery::Schema;
+use crate::validator::{CypherValidator, ValidationOptions};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::sync::OnceLock;
+
+/// The bundled multi-hop corpus.
+const MULTI_HOP_CORPUS: &str = include_str!("../benchmarks/multi_hop.json");
+
+/// A demo graph the cases are asked against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DemoGraph {
+    /// Graph key. Seeding replaces the graph's content, so the name must not be a real graph.
+    pub name: String,
+    /// The schema discovery reports once the graph is seeded.
+    pub schema: Schema,
+    /// Write queries that build the graph, in order.
+    pub seed: Vec<String>,
+}
+
+/// The result a correct query returns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResultSignature {
+    /// Number of rows.
+    pub rows: usize,
+    /// Values that must each appear in some cell, in any order. Strings match as they are, other
+    /// values as their JSON text (`4`, `true`).
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+/// One question of a corpus.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkCase {
+    pub id: String,
+    /// Name of the [`DemoGraph`] the question is about.
+    pub graph: String,
+    pub question: String,
+    /// Relationship patterns a correct query needs at least; a variable-length pattern counts once.
+    pub min_hops: usize,
+    pub expected: ResultSignature,
+    /// A model reply known to answer the question, used when no model is asked.
+    pub recorded_reply: String,
+}
+
+/// Demo graphs and the questions asked against them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Corpus {
+    pub graphs: Vec<DemoGraph>,
+    pub cases: Vec<BenchmarkCase>,
+}
+
+impl Corpus {
+    /// The bundled multi-hop corpus (`benchmarks/multi_hop.json`).
+    ///
+    /// # Panics
+    ///
+    /// Never: the bundled corpus is checked by the crate's tests.
+    #[must_use]
+    pub fn multi_hop() -> Self {
+        Self::from_json(MULTI_HOP_CORPUS).expect("the bundled multi-hop corpus is valid")
+    }
+
+    /// Parses a corpus in the format of `benchmarks/multi_hop.json`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON does not parse, or a case names a graph the corpus lacks.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let corpus: Self = serde_json::from_str(json).map_err(|e| format!("Invalid benchmark corpus: {e}"))?;
+        if let Some(case) = corpus.cases.iter().find(|case| corpus.graph(&case.graph).is_none()) {
+            return Err(format!("Case '{}' names unknown graph '{}'", case.id, case.graph));
+        }
+        Ok(corpus)
+    }
+
+    fn graph(
+        &self,
+        name: &str,
+    ) -> Option<&DemoGraph> {
+        self.graphs.iter().find(|graph| graph.name == name)
+    }
+}
+
+/// Where [`run`] gets each case's query.
+#[derive(Clone, Copy)]
+pub enum QuerySource<'a> {
+    /// The case's recorded reply.
+    Recorded,
+    /// The client's model, asked with only the question.
+    Model(&'a TextToCypherClient),
+}
+
+/// The outcome of one case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseReport {
+    pub id: String,
+    /// The query checked, if one was generated.
+    pub query: Option<String>,
+    /// Why the case failed; empty when it passed.
+    pub failures: Vec<String>,
+}
+
+impl CaseReport {
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// The outcome of a benchmark run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub cases: Vec<CaseReport>,
+}
+
+impl BenchmarkReport {
+    /// Whether every case passed.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.cases.iter().all(CaseReport::passed)
+    }
+
+    /// One line per failed case and a final `passed/total` line.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        for case in self.cases.iter().filter(|case| !case.passed()) {
+            let _ = writeln!(summary, "FAIL {}: {}", case.id, case.failures.join("; "));
+        }
+        let passed = self.cases.iter().filter(|case| case.passed()).count();
+        let _ = write!(summary, "{passed}/{} cases passed", self.cases.len());
+        summary
+    }
+}
+
+/// Checks the recorded replies without a model or database: each must parse to a query that
+/// validates against its graph's schema and has at least the case's `min_hops`.
+#[must_use]
+pub fn replay(corpus: &Corpus) -> BenchmarkReport {
+    BenchmarkReport {
+        cases: corpus
+            .cases
+            .iter()
+            .map(|case| {
+                let (query, failures) = recorded_query(case).map_or_else(
+                    |failure| (None, vec![failure]),
+                    |query| {
+                        let failures = check_query(corpus, case, &query);
+                        (Some(query), failures)
+                    },
+                );
+                CaseReport {
+                    id: case.id.clone(),
+                    query,
+                    failures,
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Seeds the demo graphs, gets each case's query from `source`, and checks it as [`replay`] does
+/// and by executing it and comparing the result with the expected signature.
+///
+/// # Errors
+///
+/// Returns the error of a demo graph that could not be seeded; failing cases are reported instead.
+pub async fn run(
+    corpus: &Corpus,
+    source: QuerySource<'_>,
+    falkordb_connection: &str,
+) -> Result<BenchmarkReport, TextToCypherError> {
+    for graph in &corpus.graphs {
+        seed(graph, falkordb_connection).await?;
+    }
+
+    let mut report = BenchmarkReport::default();
+    for case in &corpus.cases {
+        let query = match source {
+            QuerySource::Recorded => recorded_query(case),
+            QuerySource::Model(client) => generated_query(client, case).await,
+        };
+        let mut failures = Vec::new();
+        if let Ok(query) = &query {
+            failures = check_query(corpus, case, query);
+            match query_rows(query, &case.graph, falkordb_connection, true).await {
+                Ok(rows) => failures.extend(check_result(&case.expected, &rows)),
+                Err(e) => failures.push(format!("execution failed: {e}")),
+            }
+        }
+        report.cases.push(CaseReport {
+            id: case.id.clone(),
+            failures: query.as_ref().err().cloned().into_iter().chain(failures).collect(),
+            query: query.ok(),
+        });
+    }
+    Ok(report)
+}
+
+/// Replaces a demo graph's content with its seed.
+async fn seed(
+    graph: &DemoGraph,
+    falkordb_connection: &str,
+) -> Result<(), TextToCypherError> {
+    query_rows("MATCH (n) DETACH DELETE n", &graph.name, falkordb_connection, false).await?;
+    for query in &graph.seed {
+        query_rows(query, &graph.name, falkordb_connection, false).await?;
+    }
+    Ok(())
+}
+
+fn recorded_query(case: &BenchmarkCase) -> Result<String, String> {
+    match GenerationOutcome::from_reply(Some(&case.recorded_reply)) {
+        GenerationOutcome::Query(query) => Ok(query),
+        GenerationOutcome::NoAnswer { reason } => Err(format!("recorded reply has no query: {reason}")),
+    }
+}
+
+async fn generated_query(
+    client: &TextToCypherClient,
+    case: &BenchmarkCase,
+) -> Result<String, String> {
+    let request = ChatRequest {
+        messages: vec![ChatMessage {
+            role: ChatRole::User,
+            content: case.question.clone(),
+        }],
+    };
+    let response = client.cypher_only(&case.graph, request).await.map_err(|e| e.to_string())?;
+    response
+        .cypher_query
+        .ok_or_else(|| format!("no query generated: {}", response.no_answer_reason.unwrap_or_default()))
+}
+
+/// Validation errors against the case's schema, and too few hops.
+fn check_query(
+    corpus: &Corpus,
+    case: &BenchmarkCase,
+    query: &str,
+) -> Vec<String> {
+    let mut failures = Vec::new();
+    if let Some(graph) = corpus.graph(&case.graph) {
+        let schema = serde_json::to_string(&graph.schema).unwrap_or_default();
+        let validation =
+            CypherValidator::validate_with_options(query, &ValidationOptions::default().with_schema(&schema));
+        failures.extend(validation.errors.into_iter().map(|error| format!("invalid query: {error}")));
+    }
+    let hops = relationship_hops(query);
+    if hops < case.min_hops {
+        failures.push(format!("query has {hops} hops, expected at least {}", case.min_hops));
+    }
+    failures
+}
+
+/// Number of relationship patterns in `query`: `-[...]-` and bare `--`, `-->` or `<--` links.
+fn relationship_hops(query: &str) -> usize {
+    static HOP: OnceLock<Regex> = OnceLock::new();
+    let hop = HOP.get_or_init(|| Regex::new(r"-\[|\)\s*<?--?>?\s*\(").expect("valid hop pattern"));
+    hop.find_iter(query).count()
+}
+
+/// Differences between executed rows and the expected signature.
+fn check_result(
+    expected: &ResultSignature,
+    rows: &[Vec<falkordb::FalkorValue>],
+) -> Vec<String> {
+    let mut failures = Vec::new();
+    if rows.len() != expected.rows {
+        failures.push(format!("returned {} rows, expected {}", rows.len(), expected.rows));
+    }
+    let cells: Vec<String> = rows
+        .iter()
+        .flatten()
+        .map(|value| match ResultValue::from(value) {
+            ResultValue::String(text) => text,
+            other => other.to_json(),
+        })
+        .collect();
+    for value in &expected.values {
+        if !cells.contains(value) {
+            failures.push(format!("result lacks '{value}'"));
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use falkordb::FalkorValue;
+
+    #[test]
+    fn bundled_corpus_replays_cleanly() {
+        let corpus = Corpus::multi_hop();
+        assert!(corpus.cases.len() >= 10);
+        let report = replay(&corpus);
+        assert!(report.passed(), "{}", report.summary());
+        assert!(report.summary().ends_with(&format!("{0}/{0} cases passed", corpus.cases.len())));
+    }
+
+    #[test]
+    fn replay_reports_invalid_and_shallow_queries() {
+        let mut corpus = Corpus::multi_hop();
+        corpus.cases.truncate(1);
+        corpus.cases[0].recorded_reply = "MATCH (m:Movie)-[:ACTED_IN]->(p:Person) RETURN p.name".to_string();
+        let report = replay(&corpus);
+        assert!(!report.passed());
+        let failures = &report.cases[0].failures;
+        assert!(
+            failures.iter().any(|failure| failure.starts_with("invalid query")),
+            "{failures:?}"
+        );
+        assert!(
+            failures.iter().any(|failure| failure.contains("1 hops")),
+            "{failures:?}"
+        );
+
+        corpus.cases[0].recorded_reply = "NO ANSWER: unknown".to_string();
+        assert_eq!(replay(&corpus).cases[0].query, None);
+    }
+
+    #[test]
+    fn counts_relationship_patterns() {
+        assert_eq!(relationship_hops("MATCH (a)-[:X]->(b)<-[:Y]-(c) RETURN a"), 2);
+        assert_eq!(relationship_hops("MATCH (a)-->(b)--(c), (c)<--(d) RETURN a"), 3);
+        assert_eq!(relationship_hops("MATCH (a)-[:X*1..3]->(b) RETURN a.x - 1"), 1);
+    }
+
+    #[test]
+    fn compares_rows_with_the_signature() {
+        let expected = ResultSignature {
+            rows: 2,
+            values: vec!["Bob".to_string(), "4".to_string()],
+        };
+        let rows = vec![vec![FalkorValue::String("Bob".to_string())], vec![FalkorValue::I64(4)]];
+        assert!(check_result(&expected, &rows).is_empty());
+        assert_eq!(
+            check_result(&expected, &rows[..1]),
+            vec!["returned 1 rows, expected 2", "result lacks '4'"]
+        );
+    }
+
+    #[test]
+    fn rejects_cases_on_unknown_graphs() {
+        let json = r#"{"graphs": [], "cases": [{"id": "c", "graph": "missing", "question": "q", "min_hops": 2,
+            "expected": {"rows": 1}, "recorded_reply": "MATCH (n) RETURN n"}]}"#;
+        assert_eq!(
+            Corpus::from_json(json).unwrap_err(),
+            "Case 'c' names unknown graph 'missing'"
+        );
+    }
+}
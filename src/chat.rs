@@ -13,15 +13,27 @@ pub enum ChatRole {
     System,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "server", derive(ToSchema))]
 pub struct ChatMessage {
     pub role: ChatRole,
     pub content: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[cfg_attr(feature = "server", derive(ToSchema))]
 pub struct ChatRequest {
     pub messages: Vec<ChatMessage>,
 }
+
+impl ChatRequest {
+    /// Returns the content of the most recent user message, if any.
+    #[must_use]
+    pub fn last_user_question(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|message| message.role == ChatRole::User)
+            .map(|message| message.content.as_str())
+    }
+}
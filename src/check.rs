@@ -0,0 +1,142 @@
+//! The `--check` startup self-check.
+//!
+//! `text-to-cypher --check` validates the configuration, connects to `FalkorDB`, resolves the default
+//! model, reads the stored values back through the encryption keys, and renders every prompt template, then prints one line per check and exits non-zero if
+//! any of them failed. Deployment pipelines can run it against the target environment before
+//! rolling out. The server does not start in this mode.
+
+use crate::template::TemplateEngine;
+use crate::{AppConfig, STORAGE_NAMESPACES, list_graphs};
+use ::text_to_cypher::core::create_genai_client_with_endpoint;
+use std::fmt::Write as _;
+
+/// The outcome of one check: a short detail on success, the reason on failure, or `None` when the
+/// check does not apply to this configuration.
+struct Check {
+    name: &'static str,
+    outcome: Option<Result<String, String>>,
+}
+
+/// Runs every check and returns the report and whether all checks passed.
+pub async fn run(config: &AppConfig) -> (String, bool) {
+    let checks = [
+        Check {
+            name: "configuration",
+            outcome: Some(check_configuration(config)),
+        },
+        Check {
+            name: "falkordb",
+            outcome: Some(check_falkordb(&config.falkordb_connection).await),
+        },
+        Check {
+            name: "default model",
+            outcome: check_default_model(config).await,
+        },
+        Check {
+            name: "storage encryption",
+            outcome: check_storage_encryption(config).await,
+        },
+        Check {
+            name: "templates",
+            outcome: Some(check_templates()),
+        },
+    ];
+
+    let mut report = String::new();
+    let mut passed = true;
+    for check in &checks {
+        let (status, detail) = match &check.outcome {
+            Some(Ok(detail)) => ("ok", detail.as_str()),
+            Some(Err(reason)) => {
+                passed = false;
+                ("FAIL", reason.as_str())
+            }
+            None => ("skip", "not configured"),
+        };
+        let _ = writeln!(report, "[{status:>4}] {}: {detail}", check.name);
+    }
+    report.push_str(if passed {
+        "All checks passed"
+    } else {
+        "Self-check failed"
+    });
+    (report, passed)
+}
+
+/// Reports every setting [`AppConfig::load`] fell back from, so a typo fails the check instead of
+/// only logging an error at startup.
+fn check_configuration(config: &AppConfig) -> Result<String, String> {
+    if config.config_errors.is_empty() {
+        Ok("valid".to_string())
+    } else {
+        Err(config
+            .config_errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; "))
+    }
+}
+
+/// Reads every stored value back through the keyring, so a key dropped before `--reencrypt` ran,
+/// or a clear-text value left under `STORAGE_ENCRYPTION_STRICT`, fails the check instead of the
+/// request that reads it.
+async fn check_storage_encryption(config: &AppConfig) -> Option<Result<String, String>> {
+    if std::env::var("STORAGE_ENCRYPTION_KEYS").is_err() && std::env::var("STORAGE_ENCRYPTION_KEYS_FILE").is_err() {
+        return None;
+    }
+    if config.storage.backend_name() == "memory" {
+        return Some(Err("not in use: the storage backend fell back to memory".to_string()));
+    }
+    let mut read = 0;
+    let mut problems = Vec::new();
+    for namespace in STORAGE_NAMESPACES {
+        let keys = match config.storage.list_keys(namespace).await {
+            Ok(keys) => keys,
+            Err(e) => return Some(Err(format!("cannot list {namespace}: {e}"))),
+        };
+        for key in keys {
+            match config.storage.get(namespace, &key).await {
+                Ok(_) => read += 1,
+                Err(e) => problems.push(e.to_string()),
+            }
+        }
+    }
+    Some(if problems.is_empty() {
+        Ok(format!("{read} stored value(s) readable"))
+    } else {
+        Err(format!(
+            "{} unreadable value(s): {}",
+            problems.len(),
+            problems.join("; ")
+        ))
+    })
+}
+
+async fn check_falkordb(falkordb_connection: &str) -> Result<String, String> {
+    list_graphs(falkordb_connection)
+        .await
+        .map(|graphs| format!("connected, {} graph(s)", graphs.len()))
+        .map_err(|e| e.to_string())
+}
+
+async fn check_default_model(config: &AppConfig) -> Option<Result<String, String>> {
+    let model = config.default_model.as_deref()?;
+    let client = create_genai_client_with_endpoint(config.default_key.as_deref(), None);
+    Some(
+        client
+            .resolve_service_target(model)
+            .await
+            .map(|target| format!("{model} ({})", target.model.adapter_kind))
+            .map_err(|e| format!("cannot resolve '{model}': {e}")),
+    )
+}
+
+fn check_templates() -> Result<String, String> {
+    let unresolved = TemplateEngine::unresolved_placeholders();
+    if unresolved.is_empty() {
+        Ok("all templates render".to_string())
+    } else {
+        Err(format!("unresolved placeholders: {}", unresolved.join(", ")))
+    }
+}
@@ -3,15 +3,23 @@
 //! This module contains the shared logic for text-to-cypher conversion that works
 //! in both the standalone HTTP server and library contexts.
 
-use crate::chat::{ChatRequest, ChatRole};
-use crate::formatter::{build_falkordb_async_client, format_query_records, rows_lossy};
-use crate::schema::discovery::Schema;
+use crate::chat::ChatRequest;
+use crate::error::TextToCypherError;
+use crate::formatter::{DEFAULT_MAX_RESULT_BYTES, format_rows_within, rows_lossy};
+use crate::latency::Faithfulness;
+use crate::model_options::ModelOptions;
+use crate::pool::{connect, execute_query_blocking, with_failover};
+use crate::prompts;
+use crate::routing::{self, GraphCandidate};
+use crate::schema::descriptions::SchemaDescriptions;
+use crate::schema::discovery::{DiscoveryOptions, DiscoveryProgress, Schema};
 use crate::skills::{self, SkillCatalog};
-use crate::template::TemplateEngine;
+use crate::suggest;
+use crate::template::{Audience, TemplateEngine};
 use crate::udf::{UdfCatalog, UdfError};
 use crate::usage::TokenUsage;
-use crate::validator::CypherValidator;
-use falkordb::{FalkorAsyncClient, FalkorConnectionInfo};
+use crate::validator::{CypherValidator, ProcedureAllowlist};
+use futures::StreamExt;
 use genai::adapter::AdapterKind;
 use genai::chat::ChatMessage as GenAiChatMessage;
 use genai::resolver::{AuthData, AuthResolver, Endpoint, ServiceTargetResolver};
@@ -20,6 +28,168 @@ use regex::Regex;
 use std::error::Error;
 use std::sync::OnceLock;
 
+/// How the system prompt asks the model to reply when it cannot write a query (`NO ANSWER: <reason>`).
+pub const NO_ANSWER_MARKER: &str = "NO ANSWER";
+
+/// How the parameterized-mode prompt asks the model to give parameter values, on a trailing
+/// `PARAMETERS: {"name": "Alice"}` line.
+pub const PARAMETERS_MARKER: &str = "PARAMETERS:";
+
+/// Values of a generated query's `$name` parameters, by name.
+pub type QueryParameters = serde_json::Map<String, serde_json::Value>;
+
+/// Reason reported when the model gave none.
+const DEFAULT_NO_ANSWER_REASON: &str = "The question cannot be answered with the graph schema";
+
+/// What the model produced when asked for a Cypher query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerationOutcome {
+    /// A cleaned (not necessarily valid) query.
+    Query(String),
+    /// The model declined to write a query, typically because the question is outside the schema.
+    NoAnswer { reason: String },
+}
+
+impl GenerationOutcome {
+    /// Interprets a model reply. An empty reply, an empty query, or a `NO ANSWER[: reason]` reply
+    /// is a [`Self::NoAnswer`]; anything else is the cleaned query, with the values of a trailing
+    /// [`PARAMETERS_MARKER`] line declared as its parameters (see [`declare_parameters`]).
+    #[must_use]
+    pub fn from_reply(reply: Option<&str>) -> Self {
+        let (reply, parameters) = split_query_parameters(reply.unwrap_or_default());
+        let text = extract_fenced_block(reply).unwrap_or(reply).trim();
+        if let Some(prefix) = text.get(..NO_ANSWER_MARKER.len())
+            && prefix.eq_ignore_ascii_case(NO_ANSWER_MARKER)
+        {
+            return Self::no_answer(text[NO_ANSWER_MARKER.len()..].trim_start_matches([':', '-', ' ']));
+        }
+        let query = clean_generated_cypher_response(reply);
+        if query.is_empty() {
+            return Self::no_answer("");
+        }
+        match parameters.map(|parameters| declare_parameters(&query, &parameters)) {
+            Some(Ok(declared)) => Self::Query(declared),
+            Some(Err(e)) => {
+                // The query still names the parameters, so execution fails and self-healing retries.
+                tracing::warn!("Ignoring the generated query parameters: {e}");
+                Self::Query(query)
+            }
+            None => Self::Query(query),
+        }
+    }
+
+    fn no_answer(reason: &str) -> Self {
+        let reason = reason.trim();
+        Self::NoAnswer {
+            reason: if reason.is_empty() {
+                DEFAULT_NO_ANSWER_REASON.to_string()
+            } else {
+                reason.to_string()
+            },
+        }
+    }
+
+    /// Returns the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TextToCypherError::Generation`] carrying the reason for a [`Self::NoAnswer`].
+    pub fn into_query(self) -> Result<String, TextToCypherError> {
+        match self {
+            Self::Query(query) => Ok(query),
+            Self::NoAnswer { reason } => Err(TextToCypherError::Generation(format!(
+                "No query was generated: {reason}"
+            ))),
+        }
+    }
+}
+
+/// Splits a trailing [`PARAMETERS_MARKER`] line off a model reply, returning the rest of the reply
+/// and the parameter values.
+///
+/// The values are `None`, and the reply is returned whole, when it has no marker or the marker is
+/// not followed by a JSON object.
+#[must_use]
+pub fn split_query_parameters(reply: &str) -> (&str, Option<QueryParameters>) {
+    let Some(index) = reply.to_ascii_uppercase().rfind(PARAMETERS_MARKER) else {
+        return (reply, None);
+    };
+    let parameters = serde_json::Deserializer::from_str(&reply[index + PARAMETERS_MARKER.len()..])
+        .into_iter::<serde_json::Value>()
+        .next()
+        .and_then(Result::ok);
+    match parameters {
+        // A code fence the marker was written in is left unclosed, which extraction tolerates.
+        Some(serde_json::Value::Object(parameters)) => (&reply[..index], Some(parameters)),
+        _ => (reply, None),
+    }
+}
+
+/// Declares `parameters` in a `CYPHER name=value ...` preamble of `query`, the form in which
+/// `FalkorDB` receives query parameters.
+///
+/// Values are encoded by the `FalkorDB` client, so a quote in a value cannot end its string early.
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::Validation`] for a name that is not a plain identifier or a value
+/// that cannot be a parameter.
+pub fn declare_parameters(
+    query: &str,
+    parameters: &QueryParameters,
+) -> Result<String, TextToCypherError> {
+    let mut declarations = Vec::with_capacity(parameters.len());
+    for (name, value) in parameters {
+        let identifier = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !identifier {
+            return Err(TextToCypherError::Validation(format!(
+                "'{name}' is not a valid parameter name"
+            )));
+        }
+        let encoded = falkordb::to_cypher_param(&falkor_value(value))
+            .map_err(|e| TextToCypherError::Validation(format!("Parameter '{name}' cannot be encoded: {e}")))?;
+        declarations.push(format!("{name}={encoded}"));
+    }
+    Ok(with_preamble(query, &declarations.join(" ")))
+}
+
+/// The `FalkorDB` value of a JSON parameter value.
+fn falkor_value(value: &serde_json::Value) -> falkordb::FalkorValue {
+    use falkordb::FalkorValue;
+    use serde_json::Value;
+    match value {
+        Value::Null => FalkorValue::None,
+        Value::Bool(value) => FalkorValue::Bool(*value),
+        Value::Number(number) => number.as_i64().map_or_else(
+            || FalkorValue::F64(number.as_f64().unwrap_or(f64::NAN)),
+            FalkorValue::I64,
+        ),
+        Value::String(value) => FalkorValue::String(value.clone()),
+        Value::Array(values) => FalkorValue::Array(values.iter().map(falkor_value).collect()),
+        Value::Object(entries) => {
+            FalkorValue::Map(entries.iter().map(|(key, value)| (key.clone(), falkor_value(value))).collect())
+        }
+    }
+}
+
+/// Adds `declarations` (`name=value ...`) to the `CYPHER` preamble of `query`, starting one when
+/// the query has none.
+fn with_preamble(
+    query: &str,
+    declarations: &str,
+) -> String {
+    let query = query.trim_start();
+    if declarations.is_empty() {
+        return query.to_string();
+    }
+    let rest = query
+        .get(..7)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("CYPHER "))
+        .map_or(query, |_| &query[7..]);
+    format!("CYPHER {declarations} {rest}")
+}
+
 /// Matches a trailing `CONFIDENCE: <0-100>` marker emitted by the answer prompt.
 fn confidence_regex() -> &'static Regex {
     static RE: OnceLock<Regex> = OnceLock::new();
@@ -49,25 +219,58 @@ pub fn parse_answer_confidence(answer: &str) -> (String, Option<u8>) {
 ///
 /// # Errors
 ///
-/// Returns an error if connection fails, schema discovery fails, or JSON serialization fails
+/// Returns a [`TextToCypherError::SchemaDiscovery`] if connection fails, schema discovery fails, or
+/// JSON serialization fails
 pub async fn discover_graph_schema(
     falkordb_connection: &str,
     graph_name: &str,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
-    let connection_info: FalkorConnectionInfo = falkordb_connection
-        .try_into()
-        .map_err(|e| format!("Invalid connection info: {e}"))?;
+) -> Result<String, TextToCypherError> {
+    discover_graph_schema_with_progress(falkordb_connection, graph_name, &|_| {}).await
+}
 
-    let client = build_falkordb_async_client(connection_info)
-        .await
-        .map_err(|e| format!("Failed to build client: {e}"))?;
+/// Discovers the graph schema and returns it as a JSON string, reporting each discovery milestone
+/// to `on_progress` (e.g. to stream "discovered 12/40 labels" to a client)
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::SchemaDiscovery`] if connection fails, schema discovery fails, or
+/// JSON serialization fails
+pub async fn discover_graph_schema_with_progress(
+    falkordb_connection: &str,
+    graph_name: &str,
+    on_progress: &(dyn Fn(DiscoveryProgress) + Send + Sync),
+) -> Result<String, TextToCypherError> {
+    discover_graph_schema_with_options(
+        falkordb_connection,
+        graph_name,
+        DiscoveryOptions::default(),
+        on_progress,
+    )
+    .await
+}
 
-    let mut graph = client.select_graph(graph_name);
-    let schema = Schema::discover_from_graph(&mut graph, 100)
+/// Discovers the graph schema as `options` set (sample size, relationship pairing) and returns it
+/// as a JSON string, reporting each discovery milestone to `on_progress`
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::SchemaDiscovery`] if connection fails, schema discovery fails, or
+/// JSON serialization fails
+pub async fn discover_graph_schema_with_options(
+    falkordb_connection: &str,
+    graph_name: &str,
+    options: DiscoveryOptions,
+    on_progress: &(dyn Fn(DiscoveryProgress) + Send + Sync),
+) -> Result<String, TextToCypherError> {
+    let (client, target) = connect(falkordb_connection).await.map_err(TextToCypherError::SchemaDiscovery)?;
+
+    let mut graph = client.select_graph(target.graph_key(graph_name));
+    let schema = Schema::discover_from_graph_with_options(&mut graph, options, on_progress)
         .await
-        .map_err(|e| format!("Failed to discover schema: {e}"))?;
+        .map_err(|e| TextToCypherError::SchemaDiscovery(format!("Failed to discover schema: {e}")))?;
 
-    let json_schema = serde_json::to_string(&schema).map_err(|e| format!("Failed to serialize schema: {e}"))?;
+    let json_schema = serde_json::to_string(&schema)
+        .map_err(|e| TextToCypherError::SchemaDiscovery(format!("Failed to serialize schema: {e}")))?;
 
     Ok(json_schema)
 }
@@ -83,13 +286,7 @@ pub async fn discover_graph_schema(
 /// (older `FalkorDB`), and [`UdfError::Transport`] when the connection cannot be established or the
 /// command fails for another reason.
 pub async fn discover_udfs(falkordb_connection: &str) -> Result<UdfCatalog, UdfError> {
-    let connection_info: FalkorConnectionInfo = falkordb_connection
-        .try_into()
-        .map_err(|e| UdfError::Transport(format!("Invalid connection info: {e}")))?;
-
-    let client = build_falkordb_async_client(connection_info)
-        .await
-        .map_err(|e| UdfError::Transport(format!("Failed to build client: {e}")))?;
+    let (client, _) = connect(falkordb_connection).await.map_err(UdfError::Transport)?;
 
     UdfCatalog::discover(&client).await
 }
@@ -104,10 +301,40 @@ pub async fn generate_cypher_query(
     schema: &str,
     client: &GenAiClient,
     model: &str,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
+) -> Result<String, TextToCypherError> {
     generate_cypher_query_with_skills(chat_request, schema, client, model, None).await
 }
 
+/// Generates a Cypher query like [`generate_cypher_query`], calling the model with `options`
+/// (temperature, output token limit, nucleus sampling).
+///
+/// # Errors
+///
+/// Returns an error if AI chat request fails, validation fails, or no query is generated
+pub async fn generate_cypher_query_with_options(
+    chat_request: &ChatRequest,
+    schema: &str,
+    client: &GenAiClient,
+    model: &str,
+    options: &ModelOptions,
+) -> Result<String, TextToCypherError> {
+    let mut usage = TokenUsage::new();
+    generate_cypher_outcome_with_template(
+        chat_request,
+        schema,
+        client,
+        model,
+        options,
+        None,
+        "",
+        None,
+        None,
+        &mut usage,
+    )
+    .await?
+    .into_query()
+}
+
 /// Generates a Cypher query with optional dynamic skill loading via tool calling.
 ///
 /// When skills are provided and the model supports tool calling, the LLM can
@@ -123,7 +350,7 @@ pub async fn generate_cypher_query_with_skills(
     client: &GenAiClient,
     model: &str,
     skill_catalog: Option<&SkillCatalog>,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
+) -> Result<String, TextToCypherError> {
     let mut usage = TokenUsage::new();
     generate_cypher_query_with_skills_and_usage(chat_request, schema, client, model, skill_catalog, &mut usage).await
 }
@@ -147,7 +374,7 @@ pub async fn generate_cypher_query_with_skills_and_usage(
     model: &str,
     skill_catalog: Option<&SkillCatalog>,
     token_usage: &mut TokenUsage,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
+) -> Result<String, TextToCypherError> {
     generate_cypher_query_with_context_and_usage(chat_request, schema, client, model, skill_catalog, "", token_usage)
         .await
 }
@@ -169,11 +396,75 @@ pub async fn generate_cypher_query_with_context_and_usage(
     skill_catalog: Option<&SkillCatalog>,
     udfs: &str,
     token_usage: &mut TokenUsage,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
+) -> Result<String, TextToCypherError> {
+    generate_cypher_outcome(chat_request, schema, client, model, skill_catalog, udfs, token_usage)
+        .await?
+        .into_query()
+}
+
+/// Like [`generate_cypher_query_with_context_and_usage`], but reports a model that declines to write
+/// a query as [`GenerationOutcome::NoAnswer`] instead of an error.
+///
+/// # Errors
+///
+/// Returns an error if the AI chat request fails or the generated query fails validation
+pub async fn generate_cypher_outcome(
+    chat_request: &ChatRequest,
+    schema: &str,
+    client: &GenAiClient,
+    model: &str,
+    skill_catalog: Option<&SkillCatalog>,
+    udfs: &str,
+    token_usage: &mut TokenUsage,
+) -> Result<GenerationOutcome, TextToCypherError> {
+    generate_cypher_outcome_with_template(
+        chat_request,
+        schema,
+        client,
+        model,
+        &ModelOptions::default(),
+        skill_catalog,
+        udfs,
+        None,
+        None,
+        token_usage,
+    )
+    .await
+}
+
+/// Generates a Cypher query like [`generate_cypher_outcome`], calling the model with `options`,
+/// rendering the system prompt from `system_template` when given instead of the built-in template,
+/// and leaving the procedures `procedures` forbids out of it.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn generate_cypher_outcome_with_template(
+    chat_request: &ChatRequest,
+    schema: &str,
+    client: &GenAiClient,
+    model: &str,
+    options: &ModelOptions,
+    skill_catalog: Option<&SkillCatalog>,
+    udfs: &str,
+    system_template: Option<&str>,
+    procedures: Option<&ProcedureAllowlist>,
+    token_usage: &mut TokenUsage,
+) -> Result<GenerationOutcome, TextToCypherError> {
     let use_tools = skill_catalog.is_some_and(|c| !c.is_empty()) && skills::supports_tool_calling(model);
+    let query_request = |use_tools| {
+        let request = prompts::create_cypher_query_chat_request_with_template(
+            chat_request,
+            schema,
+            skill_catalog,
+            udfs,
+            system_template,
+            use_tools,
+        );
+        match procedures {
+            Some(allowlist) => prompts::restrict_procedures(request, allowlist),
+            None => request,
+        }
+    };
 
-    let mut genai_chat_request =
-        create_cypher_query_chat_request_with_skills(chat_request, schema, skill_catalog, udfs, use_tools);
+    let mut genai_chat_request = query_request(use_tools);
 
     // Register the read_skill tool if supported
     if use_tools {
@@ -182,32 +473,35 @@ pub async fn generate_cypher_query_with_context_and_usage(
         }
     }
 
+    let chat_options = options.chat_options();
     for _round in 0..skills::MAX_TOOL_ROUNDS {
-        let chat_response = match client.exec_chat(model, genai_chat_request.clone(), None).await {
+        let chat_response = match client.exec_chat(model, genai_chat_request.clone(), Some(&chat_options)).await {
             Ok(response) => response,
             Err(err) if use_tools => {
                 tracing::warn!("Tool-enabled chat request failed; retrying without tools: {err}");
-                let fallback_request =
-                    create_cypher_query_chat_request_with_skills(chat_request, schema, skill_catalog, udfs, false);
-                let fallback_response = client
-                    .exec_chat(model, fallback_request, None)
-                    .await
-                    .map_err(|fallback_err| format!("Chat request failed: {err}; fallback failed: {fallback_err}"))?;
-                token_usage.add_genai_usage(&fallback_response.usage);
-                let query = fallback_response.into_first_text().unwrap_or_else(|| "NO ANSWER".to_string());
-                return validate_generated_query(&query);
+                let fallback_request = query_request(false);
+                let fallback_response =
+                    client
+                        .exec_chat(model, fallback_request, Some(&chat_options))
+                        .await
+                        .map_err(|fallback_err| {
+                            TextToCypherError::Generation(format!(
+                                "Chat request failed: {err}; fallback failed: {fallback_err}"
+                            ))
+                        })?;
+                token_usage.add_genai_usage(model, &fallback_response.usage);
+                return validate_outcome(GenerationOutcome::from_reply(fallback_response.first_text()));
             }
-            Err(err) => return Err(format!("Chat request failed: {err}").into()),
+            Err(err) => return Err(TextToCypherError::Generation(format!("Chat request failed: {err}"))),
         };
 
-        token_usage.add_genai_usage(&chat_response.usage);
+        token_usage.add_genai_usage(model, &chat_response.usage);
 
         let tool_calls = chat_response.tool_calls().into_iter().cloned().collect::<Vec<_>>();
 
         if tool_calls.is_empty() {
             // No tool calls — extract query from text response
-            let query = chat_response.into_first_text().unwrap_or_else(|| "NO ANSWER".to_string());
-            return validate_generated_query(&query);
+            return validate_outcome(GenerationOutcome::from_reply(chat_response.first_text()));
         }
 
         // Handle tool calls: append assistant turn once, then each tool response
@@ -222,29 +516,26 @@ pub async fn generate_cypher_query_with_context_and_usage(
     // If we exhausted tool rounds, force one final text response without allowing another tool call.
     genai_chat_request.tools = None;
     let final_response = client
-        .exec_chat(model, genai_chat_request, None)
+        .exec_chat(model, genai_chat_request, Some(&chat_options))
         .await
-        .map_err(|e| format!("Chat request failed after tool rounds: {e}"))?;
+        .map_err(|e| TextToCypherError::Generation(format!("Chat request failed after tool rounds: {e}")))?;
 
-    token_usage.add_genai_usage(&final_response.usage);
-    let query = final_response.into_first_text().unwrap_or_else(|| "NO ANSWER".to_string());
-    validate_generated_query(&query)
+    token_usage.add_genai_usage(model, &final_response.usage);
+    validate_outcome(GenerationOutcome::from_reply(final_response.first_text()))
 }
 
-/// Validate and clean a generated query string.
-fn validate_generated_query(query: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
-    if query.trim().is_empty() || query.trim() == "NO ANSWER" {
-        return Err("No valid query was generated".into());
-    }
-
-    let clean_query = clean_generated_cypher_response(query);
-
-    let validation_result = CypherValidator::validate(&clean_query);
-    if !validation_result.is_valid {
-        return Err(format!("Query validation failed: {}", validation_result.errors.join("; ")).into());
+/// Validates the query of a generation outcome.
+fn validate_outcome(outcome: GenerationOutcome) -> Result<GenerationOutcome, TextToCypherError> {
+    if let GenerationOutcome::Query(query) = &outcome {
+        let validation_result = CypherValidator::validate(query);
+        if !validation_result.is_valid {
+            return Err(TextToCypherError::Validation(format!(
+                "Query validation failed: {}",
+                validation_result.errors.join("; ")
+            )));
+        }
     }
-
-    Ok(clean_query)
+    Ok(outcome)
 }
 
 /// Clean common formatting that LLMs may add around generated Cypher.
@@ -305,7 +596,7 @@ fn strip_surrounding_quotes(mut query: &str) -> &str {
 
 fn trim_to_first_cypher_keyword(query: &str) -> &str {
     let trimmed = query.trim_start();
-    if starts_with_cypher_keyword(trimmed) {
+    if starts_with_cypher_keyword(trimmed) || starts_with_parameter_preamble(trimmed) {
         return trimmed;
     }
 
@@ -324,6 +615,25 @@ fn starts_with_cypher_keyword(query: &str) -> bool {
         .any(|keyword| lower.starts_with(keyword) && is_word_boundary(&lower, keyword.len()))
 }
 
+/// Whether `query` starts by declaring parameters, e.g. `CYPHER name='Alice' MATCH ...`.
+fn starts_with_parameter_preamble(query: &str) -> bool {
+    let Some(rest) = query
+        .get(..6)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("cypher"))
+        .map(|_| &query[6..])
+    else {
+        return false;
+    };
+    if !rest.starts_with(char::is_whitespace) {
+        return false;
+    }
+    let declarations = rest.trim_start();
+    let name_len = declarations
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(declarations.len());
+    name_len > 0 && declarations[name_len..].trim_start().starts_with('=')
+}
+
 fn find_word(
     haystack: &str,
     needle: &str,
@@ -369,32 +679,213 @@ fn find_case_insensitive(
 
 /// Executes a Cypher query against the graph database
 ///
+/// The result may take up to [`DEFAULT_MAX_RESULT_BYTES`] once formatted; see
+/// [`execute_cypher_query_within`].
+///
 /// # Errors
 ///
-/// Returns an error if connection fails, query execution fails, or task spawning fails
+/// Returns a [`TextToCypherError::Execution`] if connection fails, query execution fails, the
+/// result is too large, or task spawning fails
 pub async fn execute_cypher_query(
     query: &str,
     graph_name: &str,
     falkordb_connection: &str,
     read_only: bool,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
-    let connection_info: FalkorConnectionInfo = falkordb_connection
-        .try_into()
-        .map_err(|e| format!("Invalid connection info: {e}"))?;
+) -> Result<String, TextToCypherError> {
+    execute_cypher_query_within(
+        query,
+        graph_name,
+        falkordb_connection,
+        read_only,
+        Some(DEFAULT_MAX_RESULT_BYTES),
+    )
+    .await
+}
 
-    let client = build_falkordb_async_client(connection_info)
-        .await
-        .map_err(|e| format!("Failed to build client: {e}"))?;
+/// Executes a Cypher query against the graph database, formatting its rows as they are read
+///
+/// Formatting stops, and the query fails, once the result exceeds `max_result_bytes` (`None` for
+/// no limit), so a query missing its `LIMIT` cannot exhaust the process's memory.
+///
+/// A read-only query whose endpoint is unreachable runs again on the next failover endpoint.
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::Execution`] if connection fails, query execution fails, the
+/// result exceeds `max_result_bytes`, or task spawning fails
+pub async fn execute_cypher_query_within(
+    query: &str,
+    graph_name: &str,
+    falkordb_connection: &str,
+    read_only: bool,
+    max_result_bytes: Option<usize>,
+) -> Result<String, TextToCypherError> {
+    execute_with_failover(query, graph_name, falkordb_connection, read_only, move |data| {
+        format_rows_within(data.into_values_lossy(), max_result_bytes)
+            .map_err(|e| TextToCypherError::Execution(e.to_string()))
+    })
+    .await
+}
+
+/// Executes a Cypher query against the graph database and returns its rows
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::Execution`] if connection fails, query execution fails, or task
+/// spawning fails
+pub(crate) async fn query_rows(
+    query: &str,
+    graph_name: &str,
+    falkordb_connection: &str,
+    read_only: bool,
+) -> Result<Vec<Vec<falkordb::FalkorValue>>, TextToCypherError> {
+    execute_with_failover(query, graph_name, falkordb_connection, read_only, |data| {
+        Ok(rows_lossy(data))
+    })
+    .await
+}
+
+/// Runs a query on the connection's current endpoint, and a read-only one again on the next
+/// failover endpoint when that one is unreachable
+async fn execute_with_failover<T: Send + 'static>(
+    query: &str,
+    graph_name: &str,
+    falkordb_connection: &str,
+    read_only: bool,
+    consume: impl Fn(falkordb::RowStream) -> Result<T, TextToCypherError> + Clone + Send + Sync + 'static,
+) -> Result<T, TextToCypherError> {
+    let result = with_failover(falkordb_connection, read_only, |client, target| {
+        let graph_name = target.graph_key(graph_name);
+        let query = query.to_string();
+        let consume = consume.clone();
+        async move {
+            tokio::task::spawn_blocking(move || {
+                execute_query_blocking(&client, &graph_name, &query, read_only, consume).unwrap_or_else(|e| {
+                    Ok(Err(TextToCypherError::Execution(format!(
+                        "Failed to create runtime: {e}"
+                    ))))
+                })
+            })
+            .await
+            .unwrap_or_else(|e| {
+                Ok(Err(TextToCypherError::Execution(format!(
+                    "Failed to execute blocking task: {e}"
+                ))))
+            })
+        }
+    })
+    .await
+    .map_err(TextToCypherError::Execution)?;
+    result.map_err(|e| TextToCypherError::Execution(format!("Query execution failed: {e}")))?
+}
+
+/// Lists the graphs on a `FalkorDB` instance
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::SchemaDiscovery`] if connection fails or the graphs cannot be listed
+pub async fn list_graphs(falkordb_connection: &str) -> Result<Vec<String>, TextToCypherError> {
+    with_failover(falkordb_connection, true, async |client, target| {
+        client.list_graphs().await.map(|keys| target.graph_names(keys))
+    })
+    .await
+    .map_err(TextToCypherError::SchemaDiscovery)?
+    .map_err(|e| TextToCypherError::SchemaDiscovery(format!("Failed to list graphs: {e}")))
+}
+
+/// Returns the "not found" message for `graph_name`, with close-match suggestions, or `None` when
+/// the graph is among `graphs`
+#[must_use]
+pub fn graph_not_found_message(
+    graph_name: &str,
+    graphs: &[String],
+) -> Option<String> {
+    if graphs.iter().any(|graph| graph == graph_name) {
+        return None;
+    }
+    let suggestions = suggest::closest_matches(graph_name, graphs.iter().map(String::as_str), 3);
+    Some(format!(
+        "Graph '{graph_name}' not found.{}",
+        suggest::did_you_mean(&suggestions)
+    ))
+}
 
-    let graph_name = graph_name.to_string();
+/// Returns the execution plan of a Cypher query (`GRAPH.EXPLAIN`) without running it, one
+/// operation per line
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::Execution`] if connection fails, the query cannot be planned, or
+/// task spawning fails
+pub async fn explain_cypher_query(
+    query: &str,
+    graph_name: &str,
+    falkordb_connection: &str,
+) -> Result<Vec<String>, TextToCypherError> {
+    let (client, target) = connect(falkordb_connection).await.map_err(TextToCypherError::Execution)?;
+
+    let graph_name = target.graph_key(graph_name);
     let query = query.to_string();
 
-    let result = tokio::task::spawn_blocking(move || execute_query_blocking(&client, &graph_name, &query, read_only))
+    // The execution plan is not `Send`, so it is built and flattened on a dedicated runtime.
+    tokio::task::spawn_blocking(move || -> Result<Vec<String>, String> {
+        let rt = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to create runtime: {e}"))?;
+        rt.block_on(async {
+            let mut graph = client.select_graph(&graph_name);
+            let plan = graph
+                .explain(&query)
+                .execute()
+                .await
+                .map_err(|e| format!("Query explain failed: {e}"))?;
+            Ok(plan.plan().to_vec())
+        })
+    })
+    .await
+    .map_err(|e| TextToCypherError::Execution(format!("Failed to execute blocking task: {e}")))?
+    .map_err(TextToCypherError::Execution)
+}
+
+/// Parameter through which similarity queries receive the embedded question text, e.g.
+/// `vec.cosineDistance(d.embedding, vecf32($query_vector))`.
+pub const QUERY_VECTOR_PARAMETER: &str = "query_vector";
+
+/// Embeds `text` with an embedding `model` (e.g. `openai:text-embedding-3-small`), giving the
+/// vector to pass as `$query_vector`
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::Generation`] if the embedding request fails or returns no vector
+pub async fn embed_text(
+    client: &GenAiClient,
+    model: &str,
+    text: &str,
+) -> Result<Vec<f32>, TextToCypherError> {
+    let response = client
+        .embed(model, text, None)
         .await
-        .map_err(|e| format!("Failed to execute blocking task: {e}"))??;
+        .map_err(|e| TextToCypherError::Generation(format!("Embedding request failed: {e}")))?;
+    response
+        .embeddings
+        .into_iter()
+        .next()
+        .map(|embedding| embedding.vector)
+        .ok_or_else(|| TextToCypherError::Generation("The embedding model returned no vector".to_string()))
+}
 
-    let formatted_result = format_query_records(&result);
-    Ok(formatted_result)
+/// Declares `vector` as the `$query_vector` parameter of `query` (`CYPHER query_vector=[...] ...`).
+///
+/// Parameters the query already declares are kept, and queries that do not read the parameter
+/// are returned unchanged.
+#[must_use]
+pub fn with_query_vector(
+    query: &str,
+    vector: &[f32],
+) -> String {
+    if !query.contains(&format!("${QUERY_VECTOR_PARAMETER}")) {
+        return query.to_string();
+    }
+    let values = vector.iter().map(|value| format!("{value:?}")).collect::<Vec<_>>().join(", ");
+    with_preamble(query, &format!("{QUERY_VECTOR_PARAMETER}=[{values}]"))
 }
 
 /// Generates a final answer using AI based on the query and results
@@ -408,11 +899,41 @@ pub async fn generate_final_answer(
     cypher_result: &str,
     client: &GenAiClient,
     model: &str,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
+) -> Result<String, TextToCypherError> {
     let mut usage = TokenUsage::new();
     generate_final_answer_with_usage(chat_request, cypher_query, cypher_result, client, model, &mut usage).await
 }
 
+/// Generates a final answer like [`generate_final_answer`], calling the model with `options`
+/// (temperature, output token limit, nucleus sampling).
+///
+/// # Errors
+///
+/// Returns an error if the AI chat request fails
+pub async fn generate_final_answer_with_options(
+    chat_request: &ChatRequest,
+    cypher_query: &str,
+    cypher_result: &str,
+    client: &GenAiClient,
+    model: &str,
+    options: &ModelOptions,
+) -> Result<String, TextToCypherError> {
+    let mut usage = TokenUsage::new();
+    let (answer, _confidence) = generate_final_answer_for_audience(
+        chat_request,
+        cypher_query,
+        cypher_result,
+        client,
+        model,
+        options,
+        None,
+        "",
+        &mut usage,
+    )
+    .await?;
+    Ok(answer)
+}
+
 /// Generates a final answer, accumulating the token usage of the call.
 ///
 /// Behaves like [`generate_final_answer`] but also records the [`TokenUsage`]
@@ -428,7 +949,7 @@ pub async fn generate_final_answer_with_usage(
     client: &GenAiClient,
     model: &str,
     token_usage: &mut TokenUsage,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
+) -> Result<String, TextToCypherError> {
     let (answer, _confidence) =
         generate_final_answer_with_confidence(chat_request, cypher_query, cypher_result, client, model, token_usage)
             .await?;
@@ -452,15 +973,53 @@ pub async fn generate_final_answer_with_confidence(
     client: &GenAiClient,
     model: &str,
     token_usage: &mut TokenUsage,
-) -> Result<(String, Option<u8>), Box<dyn Error + Send + Sync>> {
-    let genai_chat_request = create_answer_chat_request(chat_request, cypher_query, cypher_result);
+) -> Result<(String, Option<u8>), TextToCypherError> {
+    generate_final_answer_for_audience(
+        chat_request,
+        cypher_query,
+        cypher_result,
+        client,
+        model,
+        &ModelOptions::default(),
+        None,
+        "",
+        token_usage,
+    )
+    .await
+}
+
+/// Generates a final answer written for `audience` with a model call made with `options`.
+///
+/// Returns the prose answer and the model's self-reported confidence like
+/// [`generate_final_answer_with_confidence`].
+///
+/// Each [`Audience`] selects its own answer-template variant; `None` uses the default prompt.
+/// `units` lists the units of the values read, from [`crate::schema::units::units_for_query`]; empty for none.
+///
+/// # Errors
+///
+/// Returns an error if the AI chat request fails
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_final_answer_for_audience(
+    chat_request: &ChatRequest,
+    cypher_query: &str,
+    cypher_result: &str,
+    client: &GenAiClient,
+    model: &str,
+    options: &ModelOptions,
+    audience: Option<Audience>,
+    units: &str,
+    token_usage: &mut TokenUsage,
+) -> Result<(String, Option<u8>), TextToCypherError> {
+    let genai_chat_request =
+        prompts::create_answer_chat_request(chat_request, cypher_query, cypher_result, audience, units);
 
     let chat_response = client
-        .exec_chat(model, genai_chat_request, None)
+        .exec_chat(model, genai_chat_request, Some(&options.chat_options()))
         .await
-        .map_err(|e| format!("Chat request failed: {e}"))?;
+        .map_err(|e| TextToCypherError::Answer(format!("Chat request failed: {e}")))?;
 
-    token_usage.add_genai_usage(&chat_response.usage);
+    token_usage.add_genai_usage(model, &chat_response.usage);
 
     let answer = chat_response
         .into_first_text()
@@ -469,6 +1028,262 @@ pub async fn generate_final_answer_with_confidence(
     Ok(parse_answer_confidence(&answer))
 }
 
+/// Generates a final answer like [`generate_final_answer_for_audience`], streaming it to `on_chunk`.
+///
+/// The answer is passed on piece by piece as the model writes it. The trailing `CONFIDENCE` marker
+/// is held back, so the chunks add up to exactly the returned answer.
+///
+/// # Errors
+///
+/// Returns an error if the AI chat request or its stream fails
+#[allow(clippy::too_many_arguments)]
+pub async fn stream_final_answer_for_audience(
+    chat_request: &ChatRequest,
+    cypher_query: &str,
+    cypher_result: &str,
+    client: &GenAiClient,
+    model: &str,
+    options: &ModelOptions,
+    audience: Option<Audience>,
+    units: &str,
+    mut on_chunk: impl FnMut(&str),
+    token_usage: &mut TokenUsage,
+) -> Result<(String, Option<u8>), TextToCypherError> {
+    // Bytes withheld from the end of the text, enough to cover a `CONFIDENCE: <0-100>` marker
+    // split across chunks.
+    const HOLD_BYTES: usize = 48;
+
+    let genai_chat_request =
+        prompts::create_answer_chat_request(chat_request, cypher_query, cypher_result, audience, units);
+    let chat_options = options.chat_options().with_capture_usage(true);
+    let chat_response = client
+        .exec_chat_stream(model, genai_chat_request, Some(&chat_options))
+        .await
+        .map_err(|e| TextToCypherError::Answer(format!("Chat request failed: {e}")))?;
+
+    let mut full = String::new();
+    let mut sent = 0;
+    let mut stream = chat_response.stream;
+    while let Some(event) = stream.next().await {
+        match event.map_err(|e| TextToCypherError::Answer(format!("Answer streaming failed: {e}")))? {
+            genai::chat::ChatStreamEvent::Chunk(chunk) => {
+                full.push_str(&chunk.content);
+                let safe_end = floor_char_boundary(&full, full.len().saturating_sub(HOLD_BYTES));
+                if safe_end > sent {
+                    on_chunk(&full[sent..safe_end]);
+                    sent = safe_end;
+                }
+            }
+            genai::chat::ChatStreamEvent::End(end) => {
+                if let Some(usage) = end.captured_usage.as_ref() {
+                    token_usage.add_genai_usage(model, usage);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let (answer, confidence) = parse_answer_confidence(&full);
+    let start = floor_char_boundary(&answer, sent.min(answer.len()));
+    if start < answer.len() {
+        on_chunk(&answer[start..]);
+    }
+    Ok((answer, confidence))
+}
+
+/// Returns the largest byte index `<= index` that lies on a UTF-8 char boundary.
+fn floor_char_boundary(
+    s: &str,
+    index: usize,
+) -> usize {
+    let mut i = index.min(s.len());
+    while i > 0 && !s.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+/// Maximum number of follow-up questions returned by [`generate_followup_questions`].
+pub const MAX_FOLLOWUPS: usize = 3;
+
+/// Splits the follow-up model output into individual questions.
+///
+/// Tolerates list markers the model adds despite instructions (`-`, `*`, `1.`, `2)`), drops blank
+/// and duplicate lines, and keeps at most [`MAX_FOLLOWUPS`] questions.
+#[must_use]
+pub fn parse_followup_questions(text: &str) -> Vec<String> {
+    let mut questions: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let question = line
+            .trim()
+            .trim_start_matches(['-', '*', '•'])
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .trim_start_matches(['.', ')'])
+            .trim();
+        if question.is_empty() || questions.iter().any(|q| q.eq_ignore_ascii_case(question)) {
+            continue;
+        }
+        questions.push(question.to_string());
+        if questions.len() == MAX_FOLLOWUPS {
+            break;
+        }
+    }
+    questions
+}
+
+/// Suggests 2–3 follow-up questions grounded in the schema and the current result.
+///
+/// This is one extra, non-streaming LLM call; its token usage is accumulated into `token_usage`.
+///
+/// # Errors
+///
+/// Returns an error if the AI chat request fails
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_followup_questions(
+    question: &str,
+    schema: &str,
+    cypher_query: &str,
+    cypher_result: &str,
+    answer: &str,
+    client: &GenAiClient,
+    model: &str,
+    token_usage: &mut TokenUsage,
+) -> Result<Vec<String>, TextToCypherError> {
+    let prompt = TemplateEngine::render_followups_prompt(schema, question, cypher_query, cypher_result, answer);
+    let genai_chat_request = genai::chat::ChatRequest::default().append_message(GenAiChatMessage::user(prompt));
+
+    let chat_response = client
+        .exec_chat(model, genai_chat_request, None)
+        .await
+        .map_err(|e| TextToCypherError::Answer(format!("Chat request failed: {e}")))?;
+
+    token_usage.add_genai_usage(model, &chat_response.usage);
+
+    Ok(chat_response
+        .into_first_text()
+        .map(|text| parse_followup_questions(&text))
+        .unwrap_or_default())
+}
+
+/// Asks the model whether `answer` is supported by the query result it was derived from.
+///
+/// This is one extra, non-streaming LLM call; its token usage is accumulated into `token_usage`.
+///
+/// # Errors
+///
+/// Returns an error if the AI chat request fails
+#[allow(clippy::too_many_arguments)]
+pub async fn check_answer_faithfulness(
+    question: &str,
+    cypher_query: &str,
+    cypher_result: &str,
+    answer: &str,
+    client: &GenAiClient,
+    model: &str,
+    token_usage: &mut TokenUsage,
+) -> Result<Faithfulness, TextToCypherError> {
+    let prompt = TemplateEngine::render_faithfulness_prompt(question, cypher_query, cypher_result, answer);
+    let genai_chat_request = genai::chat::ChatRequest::default().append_message(GenAiChatMessage::user(prompt));
+
+    let chat_response = client
+        .exec_chat(model, genai_chat_request, None)
+        .await
+        .map_err(|e| TextToCypherError::Answer(format!("Chat request failed: {e}")))?;
+
+    token_usage.add_genai_usage(model, &chat_response.usage);
+
+    Ok(chat_response
+        .into_first_text()
+        .map_or(Faithfulness::Supported, |text| Faithfulness::parse(&text)))
+}
+
+/// Asks the model which of `candidates` holds the data `question` is about, and returns its name.
+/// A single candidate is returned without asking.
+///
+/// This is one non-streaming LLM call; its token usage is accumulated into `token_usage`.
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::SchemaDiscovery`] if there are no candidates, or a
+/// [`TextToCypherError::Generation`] if the AI chat request fails or its reply names no candidate
+pub async fn route_question(
+    question: &str,
+    candidates: &[GraphCandidate],
+    client: &GenAiClient,
+    model: &str,
+    token_usage: &mut TokenUsage,
+) -> Result<String, TextToCypherError> {
+    match candidates {
+        [] => {
+            return Err(TextToCypherError::SchemaDiscovery(
+                "No graphs to route the question to".to_string(),
+            ));
+        }
+        [only] => return Ok(only.name.clone()),
+        _ => {}
+    }
+    let prompt = TemplateEngine::render_route_graph_prompt(&routing::describe(candidates), question);
+    let genai_chat_request = genai::chat::ChatRequest::default().append_message(GenAiChatMessage::user(prompt));
+
+    let chat_response = client
+        .exec_chat(model, genai_chat_request, None)
+        .await
+        .map_err(|e| TextToCypherError::Generation(format!("Chat request failed: {e}")))?;
+
+    token_usage.add_genai_usage(model, &chat_response.usage);
+
+    let reply = chat_response.into_first_text().unwrap_or_default();
+    routing::parse_choice(&reply, candidates)
+        .map(ToString::to_string)
+        .ok_or_else(|| {
+            TextToCypherError::Generation(format!(
+                "None of the graphs ({}) holds the data the question is about",
+                candidates
+                    .iter()
+                    .map(|candidate| candidate.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })
+}
+
+/// Asks the model to describe the labels, relationships and properties of `schema`.
+///
+/// Returns the schema with its empty descriptions filled and how many were filled. Descriptions
+/// already in the schema are kept.
+///
+/// This is one non-streaming LLM call; its token usage is accumulated into `token_usage`.
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::SchemaDiscovery`] if the AI chat request fails or its reply
+/// holds no descriptions
+pub async fn enrich_schema(
+    schema: &Schema,
+    client: &GenAiClient,
+    model: &str,
+    token_usage: &mut TokenUsage,
+) -> Result<(Schema, usize), TextToCypherError> {
+    let ontology = serde_json::to_string(schema)
+        .map_err(|e| TextToCypherError::SchemaDiscovery(format!("Failed to serialize schema: {e}")))?;
+    let prompt = TemplateEngine::render_enrich_schema_prompt(&ontology);
+    let genai_chat_request = genai::chat::ChatRequest::default().append_message(GenAiChatMessage::user(prompt));
+
+    let chat_response = client
+        .exec_chat(model, genai_chat_request, None)
+        .await
+        .map_err(|e| TextToCypherError::SchemaDiscovery(format!("Chat request failed: {e}")))?;
+
+    token_usage.add_genai_usage(model, &chat_response.usage);
+
+    let reply = chat_response.into_first_text().unwrap_or_default();
+    let descriptions = SchemaDescriptions::parse(&reply)
+        .map_err(|e| TextToCypherError::SchemaDiscovery(format!("Schema enrichment failed: {e}")))?;
+    let mut enriched = schema.clone();
+    let filled = descriptions.apply(&mut enriched);
+    Ok((enriched, filled))
+}
+
 /// Creates a `GenAI` client with optional custom API key
 #[must_use]
 pub fn create_genai_client(api_key: Option<&str>) -> GenAiClient {
@@ -531,126 +1346,6 @@ fn normalize_llm_endpoint(endpoint: &str) -> Option<String> {
     })
 }
 
-// Private helper functions
-
-#[must_use]
-fn create_cypher_query_chat_request_with_skills(
-    chat_request: &ChatRequest,
-    ontology: &str,
-    skill_catalog: Option<&SkillCatalog>,
-    udfs: &str,
-    use_tools: bool,
-) -> genai::chat::ChatRequest {
-    let mut chat_req = genai::chat::ChatRequest::default();
-
-    for (index, message) in chat_request.messages.iter().enumerate() {
-        let is_last_user_message = index == chat_request.messages.len() - 1 && message.role == ChatRole::User;
-
-        let genai_message = match message.role {
-            ChatRole::User => {
-                if is_last_user_message {
-                    let processed_content = process_last_user_message(&message.content);
-                    genai::chat::ChatMessage::user(processed_content)
-                } else {
-                    genai::chat::ChatMessage::user(message.content.clone())
-                }
-            }
-            ChatRole::Assistant => genai::chat::ChatMessage::assistant(message.content.clone()),
-            ChatRole::System => genai::chat::ChatMessage::system(message.content.clone()),
-        };
-
-        chat_req = chat_req.append_message(genai_message);
-    }
-
-    // Build the skills catalog text for the prompt
-    let skills_text = match skill_catalog {
-        Some(catalog) if !catalog.is_empty() => {
-            if use_tools {
-                // Tool-calling mode: compact catalog, LLM will call read_skill for details
-                catalog.render_catalog()
-            } else {
-                // Fallback mode: inject full content for providers without tool support
-                catalog.render_all_content()
-            }
-        }
-        _ => String::new(),
-    };
-
-    let system_prompt = TemplateEngine::render_system_prompt_with_context(ontology, &skills_text, udfs);
-    chat_req = chat_req.with_system(system_prompt);
-
-    chat_req
-}
-
-fn create_answer_chat_request(
-    chat_request: &ChatRequest,
-    cypher_query: &str,
-    cypher_result: &str,
-) -> genai::chat::ChatRequest {
-    let mut chat_req = genai::chat::ChatRequest::default();
-
-    for (index, message) in chat_request.messages.iter().enumerate() {
-        let is_last_user_message = index == chat_request.messages.len() - 1 && message.role == ChatRole::User;
-
-        let genai_message = match message.role {
-            ChatRole::User => {
-                if is_last_user_message {
-                    let processed_content = process_last_request_prompt(&message.content, cypher_query, cypher_result);
-                    genai::chat::ChatMessage::user(processed_content)
-                } else {
-                    genai::chat::ChatMessage::user(message.content.clone())
-                }
-            }
-            ChatRole::Assistant => genai::chat::ChatMessage::assistant(message.content.clone()),
-            ChatRole::System => genai::chat::ChatMessage::system(message.content.clone()),
-        };
-
-        chat_req = chat_req.append_message(genai_message);
-    }
-
-    chat_req
-}
-
-fn process_last_user_message(question: &str) -> String {
-    TemplateEngine::render_user_prompt(question)
-}
-
-fn process_last_request_prompt(
-    content: &str,
-    cypher_query: &str,
-    cypher_result: &str,
-) -> String {
-    TemplateEngine::render_last_request_prompt(content, cypher_query, cypher_result)
-}
-
-fn execute_query_blocking(
-    client: &FalkorAsyncClient,
-    graph_name: &str,
-    query: &str,
-    read_only: bool,
-) -> Result<Vec<Vec<falkordb::FalkorValue>>, Box<dyn Error + Send + Sync>> {
-    let rt = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to create runtime: {e}"))?;
-
-    rt.block_on(async {
-        let mut graph = client.select_graph(graph_name);
-        let query_result = if read_only {
-            graph
-                .ro_query(query)
-                .execute()
-                .await
-                .map_err(|e| format!("Query execution failed: {e}"))?
-        } else {
-            graph
-                .query(query)
-                .execute()
-                .await
-                .map_err(|e| format!("Query execution failed: {e}"))?
-        };
-
-        Ok(rows_lossy(query_result.data))
-    })
-}
-
 /// Lists available model names for a specific AI provider.
 ///
 /// The result combines two sources:
@@ -809,6 +1504,35 @@ pub async fn list_all_models_with_endpoint(
 mod tests {
     use super::*;
 
+    #[test]
+    fn query_vector_is_declared_only_when_read() {
+        let query =
+            "MATCH (d:Document) RETURN d.title ORDER BY vec.cosineDistance(d.embedding, vecf32($query_vector)) LIMIT 5";
+        assert_eq!(
+            with_query_vector(query, &[0.5, 1.0]),
+            format!("CYPHER query_vector=[0.5, 1.0] {query}")
+        );
+        assert_eq!(
+            with_query_vector(&format!("cypher k=5 {query}"), &[0.5]),
+            format!("CYPHER query_vector=[0.5] k=5 {query}")
+        );
+        assert_eq!(with_query_vector("MATCH (d) RETURN d", &[0.5]), "MATCH (d) RETURN d");
+    }
+
+    #[test]
+    fn parse_followup_questions_strips_markers_and_caps() {
+        let text = "1. Which movies did he direct?\n- Who co-starred with him?\n\n* who co-starred with him?\n2) How many awards?\nWhat genres?";
+        assert_eq!(
+            parse_followup_questions(text),
+            vec![
+                "Which movies did he direct?".to_string(),
+                "Who co-starred with him?".to_string(),
+                "How many awards?".to_string(),
+            ]
+        );
+        assert!(parse_followup_questions("  \n").is_empty());
+    }
+
     #[test]
     fn parse_answer_confidence_extracts_and_strips_marker() {
         let (answer, confidence) = parse_answer_confidence("The route is A to C to D.\nCONFIDENCE: 85");
@@ -826,6 +1550,43 @@ mod tests {
         assert_eq!(none, None);
     }
 
+    #[test]
+    fn clean_generated_cypher_response_keeps_parameter_preamble() {
+        assert_eq!(
+            clean_generated_cypher_response("CYPHER name='Alice' MATCH (u:User {name: $name}) RETURN u.id"),
+            "CYPHER name='Alice' MATCH (u:User {name: $name}) RETURN u.id"
+        );
+        assert_eq!(
+            clean_generated_cypher_response("Cypher: MATCH (n) RETURN n"),
+            "MATCH (n) RETURN n"
+        );
+    }
+
+    #[test]
+    fn reply_parameters_are_declared_in_a_preamble() {
+        let reply = "```cypher\nMATCH (p:Person {name: $name}) WHERE p.age > $age RETURN p\nPARAMETERS: {\"name\": \"O'Brien\", \"age\": 30}\n```";
+        assert_eq!(
+            GenerationOutcome::from_reply(Some(reply)),
+            GenerationOutcome::Query(
+                "CYPHER age=30 name='O\\'Brien' MATCH (p:Person {name: $name}) WHERE p.age > $age RETURN p".to_string()
+            )
+        );
+
+        let (rest, parameters) = split_query_parameters("MATCH (n) RETURN n\nPARAMETERS: not json");
+        assert_eq!((rest, parameters), ("MATCH (n) RETURN n\nPARAMETERS: not json", None));
+
+        let parameters: QueryParameters = serde_json::from_str(r#"{"bad name": 1}"#).unwrap();
+        assert!(matches!(
+            declare_parameters("MATCH (n) RETURN n", &parameters),
+            Err(TextToCypherError::Validation(_))
+        ));
+        let parameters: QueryParameters = serde_json::from_str(r#"{"ids": [1, 2.5], "flag": null}"#).unwrap();
+        assert_eq!(
+            declare_parameters("CYPHER k=5 MATCH (n) RETURN n", &parameters).unwrap(),
+            "CYPHER flag=null ids=[1, 2.5] k=5 MATCH (n) RETURN n"
+        );
+    }
+
     #[test]
     fn clean_generated_cypher_response_strips_surrounding_quotes() {
         assert_eq!(
@@ -851,9 +1612,40 @@ mod tests {
     }
 
     #[test]
-    fn validate_generated_query_accepts_quoted_query() {
-        let query = validate_generated_query("\"MATCH (n) RETURN count(n)\"").expect("quoted query should validate");
-        assert_eq!(query, "MATCH (n) RETURN count(n)");
+    fn validate_outcome_accepts_quoted_query() {
+        let outcome = validate_outcome(GenerationOutcome::from_reply(Some("\"MATCH (n) RETURN count(n)\"")))
+            .expect("quoted query should validate");
+        assert_eq!(
+            outcome,
+            GenerationOutcome::Query("MATCH (n) RETURN count(n)".to_string())
+        );
+    }
+
+    #[test]
+    fn no_answer_replies_become_typed_outcomes() {
+        assert_eq!(
+            GenerationOutcome::from_reply(Some("NO ANSWER: the schema has no salary data")),
+            GenerationOutcome::NoAnswer {
+                reason: "the schema has no salary data".to_string()
+            }
+        );
+        for reply in [
+            None,
+            Some(""),
+            Some("NO ANSWER"),
+            Some("```cypher\n```"),
+            Some("```\nno answer\n```"),
+        ] {
+            assert_eq!(
+                GenerationOutcome::from_reply(reply),
+                GenerationOutcome::NoAnswer {
+                    reason: DEFAULT_NO_ANSWER_REASON.to_string()
+                },
+                "{reply:?}"
+            );
+        }
+        let error = GenerationOutcome::from_reply(None).into_query().unwrap_err();
+        assert!(error.to_string().contains(DEFAULT_NO_ANSWER_REASON));
     }
 
     #[tokio::test]
@@ -887,4 +1679,18 @@ mod tests {
             assert!(!models.is_empty(), "{kind} should have models");
         }
     }
+
+    #[test]
+    fn test_graph_not_found_message() {
+        let graphs = vec!["movies".to_string(), "social".to_string()];
+        assert_eq!(graph_not_found_message("movies", &graphs), None);
+        assert_eq!(
+            graph_not_found_message("moveis", &graphs).as_deref(),
+            Some("Graph 'moveis' not found. Did you mean 'movies'?")
+        );
+        assert_eq!(
+            graph_not_found_message("finance", &graphs).as_deref(),
+            Some("Graph 'finance' not found.")
+        );
+    }
 }
@@ -0,0 +1,1364 @@
+//! A tokenizer and recursive-descent parser for the openCypher that `FalkorDB` runs.
+//!
+//! [`check`] parses a whole query — clauses, patterns and expressions — and reports the first
+//! syntax error with its line and column: unterminated strings, unbalanced brackets, unknown
+//! clauses, trailing text after the query, and so on. It only decides whether the query parses; it
+//! builds no syntax tree and knows nothing of the schema. The parser also records each node
+//! pattern it reads, which tenant filtering uses to find where its predicate belongs. A leading `CYPHER name=value ...`
+//! preamble declaring query parameters is accepted, and index and constraint statements
+//! (`CREATE INDEX`, `DROP INDEX`, ...) are accepted without being parsed.
+
+use std::fmt;
+use std::ops::Range;
+
+/// Where and why a query fails to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub message: String,
+    /// Line of the offending text, from 1.
+    pub line: usize,
+    /// Column of the offending text, in characters from 1.
+    pub column: usize,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "Syntax error at line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+/// Parses `query` and returns the first syntax error, if any.
+///
+/// # Errors
+///
+/// Returns a [`SyntaxError`] if `query` is not a single well-formed Cypher statement.
+pub fn check(query: &str) -> Result<(), SyntaxError> {
+    parse(query).map(drop)
+}
+
+/// A node pattern of a query, as [`node_patterns`] finds it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NodePattern {
+    /// Byte range of the whole pattern, parentheses included.
+    pub range: Range<usize>,
+    pub variable: Option<String>,
+    pub labeled: bool,
+    /// Byte offset just past the variable and labels (or the opening parenthesis), where a property
+    /// map would go.
+    pub head_end: usize,
+    pub properties: Option<NodeProperties>,
+    /// The clause whose pattern holds the node (`MATCH`, `OPTIONAL MATCH`, `CREATE` or `MERGE`),
+    /// or `None` for a pattern in an expression.
+    pub clause: Option<&'static str>,
+    /// Whether a relationship pattern connects the node to another.
+    pub related: bool,
+    /// Whether the variable was bound before the pattern, so CREATE and MERGE reuse the node.
+    pub bound: bool,
+}
+
+/// The properties of a node pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum NodeProperties {
+    /// An inline map: the byte range of its contents between the braces, and the key and value
+    /// byte range of each entry.
+    Map {
+        contents: Range<usize>,
+        entries: Vec<(String, Range<usize>)>,
+    },
+    /// A parameter such as `$props`.
+    Parameter,
+}
+
+/// Parses `query` and returns its node patterns, in the order their patterns end.
+///
+/// # Errors
+///
+/// Returns a [`SyntaxError`] if `query` is not a single well-formed Cypher statement.
+pub(crate) fn node_patterns(query: &str) -> Result<Vec<NodePattern>, SyntaxError> {
+    parse(query).map(|parser| parser.nodes)
+}
+
+/// `query` with its comments blanked out, keeping byte offsets.
+///
+/// # Errors
+///
+/// Returns a [`SyntaxError`] if `query` cannot be tokenized, e.g. has an unterminated comment.
+pub(crate) fn without_comments(query: &str) -> Result<String, SyntaxError> {
+    let tokens = tokenize(query).map_err(|(offset, message)| error_at(query, offset, message))?;
+    let mut stripped = String::with_capacity(query.len());
+    let mut last = 0;
+    for token in &tokens {
+        stripped.extend(std::iter::repeat_n(' ', token.offset - last));
+        stripped.push_str(&query[token.offset..token.end]);
+        last = token.end;
+    }
+    Ok(stripped)
+}
+
+fn parse(query: &str) -> Result<Parser, SyntaxError> {
+    let tokens = tokenize(query).map_err(|(offset, message)| error_at(query, offset, message))?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        nodes: Vec::new(),
+        scope: Vec::new(),
+        returned: false,
+    };
+    parser
+        .statement()
+        .map_err(|(offset, message)| error_at(query, offset, message))?;
+    Ok(parser)
+}
+
+fn error_at(
+    query: &str,
+    offset: usize,
+    message: String,
+) -> SyntaxError {
+    let before = &query[..offset.min(query.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |text| text.chars().count()) + 1;
+    SyntaxError { message, line, column }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    /// A name, keyword, or backtick-quoted name (`quoted`).
+    Ident {
+        quoted: bool,
+    },
+    Number,
+    String,
+    Parameter,
+    Symbol,
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: Kind,
+    text: String,
+    offset: usize,
+    /// Byte offset just past the token's text in the query.
+    end: usize,
+}
+
+impl Token {
+    fn new(
+        kind: Kind,
+        text: impl Into<String>,
+        span: Range<usize>,
+    ) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+            offset: span.start,
+            end: span.end,
+        }
+    }
+}
+
+/// Symbols of more than one character, longest first within each shared prefix.
+const SYMBOLS: [&str; 7] = ["..", "<>", "<=", ">=", "=~", "!=", "+="];
+
+type Failure = (usize, String);
+
+fn tokenize(query: &str) -> Result<Vec<Token>, Failure> {
+    let bytes = query.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < query.len() {
+        let c = query[i..].chars().next().unwrap_or_default();
+        let start = i;
+        if c.is_whitespace() {
+            i += c.len_utf8();
+        } else if query[i..].starts_with("//") {
+            i = query[i..].find('\n').map_or(query.len(), |end| i + end);
+        } else if query[i..].starts_with("/*") {
+            let end = query[i + 2..]
+                .find("*/")
+                .ok_or_else(|| (start, "unterminated comment".to_string()))?;
+            i += end + 4;
+        } else if c == '\'' || c == '"' {
+            i += 1;
+            loop {
+                match bytes.get(i) {
+                    None => return Err((start, "unterminated string literal".to_string())),
+                    Some(b'\\') => i += 2,
+                    // A doubled quote stands for the quote itself
+                    Some(&b) if b == c as u8 && bytes.get(i + 1) == Some(&b) => i += 2,
+                    Some(&b) if b == c as u8 => break,
+                    Some(_) => i += 1,
+                }
+            }
+            i += 1;
+            tokens.push(Token::new(Kind::String, &query[start..i], start..i));
+        } else if c == '`' {
+            let mut name = String::new();
+            i += 1;
+            loop {
+                match query[i..].find('`') {
+                    None => return Err((start, "unterminated quoted name".to_string())),
+                    Some(end) if query[i + end + 1..].starts_with('`') => {
+                        name.push_str(&query[i..=i + end]);
+                        i += end + 2;
+                    }
+                    Some(end) => {
+                        name.push_str(&query[i..i + end]);
+                        i += end + 1;
+                        break;
+                    }
+                }
+            }
+            tokens.push(Token::new(Kind::Ident { quoted: true }, name, start..i));
+        } else if c.is_alphabetic() || c == '_' {
+            i += word_len(&query[i..]);
+            tokens.push(Token::new(Kind::Ident { quoted: false }, &query[start..i], start..i));
+        } else if c == '$' {
+            i += 1;
+            let len = word_len(&query[i..]);
+            if len == 0 {
+                return Err((start, "expected a parameter name after '$'".to_string()));
+            }
+            i += len;
+            tokens.push(Token::new(Kind::Parameter, &query[start..i], start..i));
+        } else if c.is_ascii_digit() || (c == '.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)) {
+            i += number_len(&query[i..]);
+            tokens.push(Token::new(Kind::Number, &query[start..i], start..i));
+        } else if "()[]{},.:;|+-*/%^=<>!&".contains(c) {
+            let len = SYMBOLS
+                .iter()
+                .find(|symbol| query[i..].starts_with(*symbol))
+                .map_or(1, |symbol| symbol.len());
+            i += len;
+            tokens.push(Token::new(Kind::Symbol, &query[start..i], start..i));
+        } else {
+            return Err((start, format!("unexpected character '{c}'")));
+        }
+    }
+    tokens.push(Token::new(Kind::End, "", query.len()..query.len()));
+    Ok(tokens)
+}
+
+/// Length of the name at the start of `text`.
+fn word_len(text: &str) -> usize {
+    text.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(text.len())
+}
+
+/// Length of the number at the start of `text`: an integer, hexadecimal, decimal (`.5` included)
+/// or exponent form. A `.` followed by another `.` is a range, not a decimal point.
+fn number_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    if text.starts_with("0x") || text.starts_with("0X") {
+        return 2 + text[2..].find(|c: char| !c.is_ascii_hexdigit()).unwrap_or(text.len() - 2);
+    }
+    let digits = |from: usize| from + text[from..].find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len() - from);
+    let mut end = digits(0);
+    if bytes.get(end) == Some(&b'.') && bytes.get(end + 1).is_some_and(u8::is_ascii_digit) {
+        end = digits(end + 1);
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+        if bytes.get(end + 1 + sign).is_some_and(u8::is_ascii_digit) {
+            end = digits(end + 1 + sign);
+        }
+    }
+    end
+}
+
+/// Keywords that start a clause.
+const CLAUSES: [&str; 16] = [
+    "MATCH", "OPTIONAL", "RETURN", "WITH", "UNWIND", "CREATE", "MERGE", "SET", "DELETE", "DETACH", "REMOVE", "CALL",
+    "FOREACH", "LOAD", "DROP", "USE",
+];
+
+/// Keywords that cannot stand for a variable in an expression.
+const RESERVED: [&str; 14] = [
+    "WHERE", "ORDER", "SKIP", "LIMIT", "UNION", "YIELD", "AS", "AND", "OR", "XOR", "IN", "WHEN", "THEN", "ELSE",
+];
+
+/// Functions whose argument is `variable IN list [WHERE predicate] [| expression]`.
+const ITERATING_FUNCTIONS: [&str; 6] = ["all", "any", "none", "single", "filter", "extract"];
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// The node patterns parsed so far.
+    nodes: Vec<NodePattern>,
+    /// The variables bound so far in the current query part.
+    scope: Vec<String>,
+    /// Whether the last clause parsed was RETURN.
+    returned: bool,
+}
+
+type Parsed = Result<(), Failure>;
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        self.peek_at(0)
+    }
+
+    fn peek_at(
+        &self,
+        ahead: usize,
+    ) -> &Token {
+        let last = self.tokens.len() - 1;
+        &self.tokens[(self.pos + ahead).min(last)]
+    }
+
+    /// Byte offset just past the last token consumed.
+    fn previous_end(&self) -> usize {
+        self.pos.checked_sub(1).map_or(0, |previous| self.tokens[previous].end)
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token.kind != Kind::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    /// Whether the token `ahead` is the keyword `keyword` (an unquoted name, in any case).
+    fn is_keyword_at(
+        &self,
+        ahead: usize,
+        keyword: &str,
+    ) -> bool {
+        let token = self.peek_at(ahead);
+        token.kind == (Kind::Ident { quoted: false }) && token.text.eq_ignore_ascii_case(keyword)
+    }
+
+    fn is_keyword(
+        &self,
+        keyword: &str,
+    ) -> bool {
+        self.is_keyword_at(0, keyword)
+    }
+
+    fn is_symbol_at(
+        &self,
+        ahead: usize,
+        symbol: &str,
+    ) -> bool {
+        let token = self.peek_at(ahead);
+        token.kind == Kind::Symbol && token.text == symbol
+    }
+
+    fn is_symbol(
+        &self,
+        symbol: &str,
+    ) -> bool {
+        self.is_symbol_at(0, symbol)
+    }
+
+    fn is_name(&self) -> bool {
+        matches!(self.peek().kind, Kind::Ident { .. })
+    }
+
+    fn eat_keyword(
+        &mut self,
+        keyword: &str,
+    ) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_symbol(
+        &mut self,
+        symbol: &str,
+    ) -> bool {
+        let found = self.is_symbol(symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn fail<T>(
+        &self,
+        expected: &str,
+    ) -> Result<T, Failure> {
+        let token = self.peek();
+        let found = match token.kind {
+            Kind::End => "the end of the query".to_string(),
+            Kind::Ident { quoted: true } => format!("`{}`", token.text),
+            _ => format!("'{}'", token.text),
+        };
+        Err((token.offset, format!("expected {expected}, found {found}")))
+    }
+
+    fn expect_keyword(
+        &mut self,
+        keyword: &str,
+    ) -> Parsed {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.fail(keyword)
+        }
+    }
+
+    fn expect_symbol(
+        &mut self,
+        symbol: &str,
+    ) -> Parsed {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            self.fail(&format!("'{symbol}'"))
+        }
+    }
+
+    fn expect_name(
+        &mut self,
+        what: &str,
+    ) -> Parsed {
+        if self.is_name() {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.fail(what)
+        }
+    }
+
+    /// Runs `parse` and rewinds if it fails, returning whether it succeeded.
+    fn attempt(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Parsed,
+    ) -> bool {
+        let (start, nodes) = (self.pos, self.nodes.len());
+        if parse(self).is_ok() {
+            true
+        } else {
+            self.pos = start;
+            self.nodes.truncate(nodes);
+            false
+        }
+    }
+
+    fn statement(&mut self) -> Parsed {
+        self.parameters()?;
+        if !self.eat_keyword("EXPLAIN") {
+            self.eat_keyword("PROFILE");
+        }
+        self.query()?;
+        self.eat_symbol(";");
+        if self.peek().kind == Kind::End {
+            Ok(())
+        } else {
+            self.fail("the end of the query")
+        }
+    }
+
+    /// The `CYPHER name=value ...` preamble that declares the query's parameters, if any.
+    fn parameters(&mut self) -> Parsed {
+        if !self.eat_keyword("CYPHER") {
+            return Ok(());
+        }
+        while self.is_name() && self.is_symbol_at(1, "=") {
+            self.pos += 2;
+            self.expression()?;
+        }
+        Ok(())
+    }
+
+    /// One or more single queries joined by `UNION [ALL]`.
+    fn query(&mut self) -> Parsed {
+        self.single_query()?;
+        while self.eat_keyword("UNION") {
+            self.eat_keyword("ALL");
+            self.single_query()?;
+        }
+        Ok(())
+    }
+
+    fn single_query(&mut self) -> Parsed {
+        self.scope.clear();
+        if !self.is_clause_start() {
+            return self.unknown_clause();
+        }
+        while self.is_clause_start() {
+            self.clause()?;
+        }
+        if self.is_name() && !self.is_keyword("UNION") {
+            return self.unknown_clause();
+        }
+        Ok(())
+    }
+
+    fn is_clause_start(&self) -> bool {
+        let token = self.peek();
+        token.kind == (Kind::Ident { quoted: false }) && CLAUSES.iter().any(|c| token.text.eq_ignore_ascii_case(c))
+    }
+
+    fn unknown_clause(&self) -> Parsed {
+        let token = self.peek();
+        if matches!(token.kind, Kind::Ident { .. }) {
+            Err((token.offset, format!("unknown clause '{}'", token.text)))
+        } else {
+            self.fail("a clause such as MATCH, RETURN or CREATE")
+        }
+    }
+
+    fn clause(&mut self) -> Parsed {
+        let keyword = self.advance().text.to_ascii_uppercase();
+        let parsed = match keyword.as_str() {
+            "OPTIONAL" => {
+                self.expect_keyword("MATCH")?;
+                self.match_body(Some("OPTIONAL MATCH"))
+            }
+            "MATCH" => self.match_body(Some("MATCH")),
+            "RETURN" => self.projection(false),
+            "WITH" => self.projection(true),
+            "UNWIND" => {
+                self.expression()?;
+                self.expect_keyword("AS")?;
+                self.bind_name("a variable")
+            }
+            "CREATE" if self.is_ddl() => {
+                self.skip_rest();
+                Ok(())
+            }
+            "CREATE" => self.pattern(Some("CREATE")),
+            "MERGE" => {
+                self.pattern_part(Some("MERGE"))?;
+                while self.eat_keyword("ON") {
+                    if !self.eat_keyword("CREATE") {
+                        self.expect_keyword("MATCH")?;
+                    }
+                    self.expect_keyword("SET")?;
+                    self.set_items()?;
+                }
+                Ok(())
+            }
+            "SET" => self.set_items(),
+            "DETACH" => {
+                self.expect_keyword("DELETE")?;
+                self.expression_list()
+            }
+            "DELETE" => self.expression_list(),
+            "REMOVE" => self.remove_items(),
+            "CALL" => self.call(),
+            "FOREACH" => {
+                // The variable and whatever the inner clauses bind are local to FOREACH
+                let outer = self.scope.clone();
+                self.expect_symbol("(")?;
+                self.bind_name("a variable")?;
+                self.expect_keyword("IN")?;
+                self.expression()?;
+                self.expect_symbol("|")?;
+                if !self.is_clause_start() {
+                    return self.unknown_clause();
+                }
+                while self.is_clause_start() {
+                    self.clause()?;
+                }
+                self.scope = outer;
+                self.expect_symbol(")")
+            }
+            "LOAD" => {
+                self.expect_keyword("CSV")?;
+                if self.eat_keyword("WITH") {
+                    self.expect_keyword("HEADERS")?;
+                }
+                self.expect_keyword("FROM")?;
+                self.expression()?;
+                self.expect_keyword("AS")?;
+                self.bind_name("a variable")?;
+                if self.eat_keyword("FIELDTERMINATOR") {
+                    if self.peek().kind != Kind::String {
+                        return self.fail("a string");
+                    }
+                    self.pos += 1;
+                }
+                Ok(())
+            }
+            "USE" => self.expect_name("a graph name"),
+            // DROP INDEX and DROP CONSTRAINT
+            _ => {
+                self.skip_rest();
+                Ok(())
+            }
+        };
+        self.returned = keyword == "RETURN";
+        parsed
+    }
+
+    /// Whether a CREATE clause creates an index or constraint rather than a pattern.
+    fn is_ddl(&self) -> bool {
+        ["INDEX", "CONSTRAINT", "FULLTEXT", "VECTOR", "RANGE"]
+            .iter()
+            .any(|keyword| self.is_keyword(keyword))
+    }
+
+    fn skip_rest(&mut self) {
+        self.pos = self.tokens.len() - 1;
+    }
+
+    fn match_body(
+        &mut self,
+        clause: Option<&'static str>,
+    ) -> Parsed {
+        self.pattern(clause)?;
+        if self.eat_keyword("WHERE") {
+            self.expression()?;
+        }
+        Ok(())
+    }
+
+    /// The items of RETURN, or of WITH with its optional WHERE.
+    fn projection(
+        &mut self,
+        with: bool,
+    ) -> Parsed {
+        self.eat_keyword("DISTINCT");
+        let mut names = Vec::new();
+        if self.eat_symbol("*") {
+            names.clone_from(&self.scope);
+            if self.eat_symbol(",") {
+                names.extend(self.projection_items()?);
+            }
+        } else {
+            names = self.projection_items()?;
+        }
+        // The projected names are all that later clauses see
+        self.scope = names;
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                self.expression()?;
+                for direction in ["ASC", "ASCENDING", "DESC", "DESCENDING"] {
+                    if self.eat_keyword(direction) {
+                        break;
+                    }
+                }
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        if self.eat_keyword("SKIP") {
+            self.expression()?;
+        }
+        if self.eat_keyword("LIMIT") {
+            self.expression()?;
+        }
+        if with && self.eat_keyword("WHERE") {
+            self.expression()?;
+        }
+        Ok(())
+    }
+
+    /// The items of a projection, returning the name each projects: its alias, or the variable
+    /// an item of a single variable keeps.
+    fn projection_items(&mut self) -> Result<Vec<String>, Failure> {
+        let mut names = Vec::new();
+        loop {
+            let start = self.pos;
+            self.expression()?;
+            if self.eat_keyword("AS") {
+                self.expect_name("an alias")?;
+                names.push(self.tokens[self.pos - 1].text.clone());
+            } else if self.pos == start + 1 && matches!(self.tokens[start].kind, Kind::Ident { .. }) {
+                names.push(self.tokens[start].text.clone());
+            }
+            if !self.eat_symbol(",") {
+                return Ok(names);
+            }
+        }
+    }
+
+    fn expression_list(&mut self) -> Parsed {
+        self.expression()?;
+        while self.eat_symbol(",") {
+            self.expression()?;
+        }
+        Ok(())
+    }
+
+    /// `n.prop = value`, `n = map`, `n += map` or `n:Label`, comma-separated.
+    fn set_items(&mut self) -> Parsed {
+        loop {
+            self.expect_name("a variable")?;
+            if self.is_symbol(":") {
+                self.labels()?;
+            } else {
+                while self.eat_symbol(".") {
+                    self.expect_name("a property name")?;
+                }
+                if !self.eat_symbol("=") && !self.eat_symbol("+=") {
+                    return self.fail("'=' or '+='");
+                }
+                self.expression()?;
+            }
+            if !self.eat_symbol(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    /// `n.prop` or `n:Label`, comma-separated.
+    fn remove_items(&mut self) -> Parsed {
+        loop {
+            self.expect_name("a variable")?;
+            if self.is_symbol(":") {
+                self.labels()?;
+            } else {
+                self.expect_symbol(".")?;
+                self.expect_name("a property name")?;
+                while self.eat_symbol(".") {
+                    self.expect_name("a property name")?;
+                }
+            }
+            if !self.eat_symbol(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    /// A `CALL { subquery }`, or a procedure call with its optional YIELD.
+    fn call(&mut self) -> Parsed {
+        if self.eat_symbol("{") {
+            // The subquery binds only what its RETURN returns
+            let outer = std::mem::take(&mut self.scope);
+            self.query()?;
+            let returned = if self.returned {
+                std::mem::take(&mut self.scope)
+            } else {
+                Vec::new()
+            };
+            self.scope = outer;
+            self.scope.extend(returned);
+            self.returned = false;
+            return self.expect_symbol("}");
+        }
+        self.expect_name("a procedure name")?;
+        while self.eat_symbol(".") {
+            self.expect_name("a procedure name")?;
+        }
+        if self.eat_symbol("(") && !self.eat_symbol(")") {
+            self.expression_list()?;
+            self.expect_symbol(")")?;
+        }
+        if self.eat_keyword("YIELD") {
+            if !self.eat_symbol("*") {
+                loop {
+                    if self.is_keyword_at(1, "AS") {
+                        self.expect_name("a yielded field")?;
+                        self.pos += 1;
+                        self.bind_name("an alias")?;
+                    } else {
+                        self.bind_name("a yielded field")?;
+                    }
+                    if !self.eat_symbol(",") {
+                        break;
+                    }
+                }
+            }
+            if self.eat_keyword("WHERE") {
+                self.expression()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The comma-separated pattern of `clause`, or of an expression when `clause` is `None`.
+    fn pattern(
+        &mut self,
+        clause: Option<&'static str>,
+    ) -> Parsed {
+        self.pattern_part(clause)?;
+        while self.eat_symbol(",") {
+            self.pattern_part(clause)?;
+        }
+        Ok(())
+    }
+
+    /// `[path =] chain`, where the chain may be wrapped in `shortestPath(...)`.
+    fn pattern_part(
+        &mut self,
+        clause: Option<&'static str>,
+    ) -> Parsed {
+        if self.is_name() && self.is_symbol_at(1, "=") {
+            self.bind(clause);
+            self.pos += 1;
+        }
+        if (self.is_keyword("shortestPath") || self.is_keyword("allShortestPaths")) && self.is_symbol_at(1, "(") {
+            self.pos += 2;
+            self.chain(clause)?;
+            return self.expect_symbol(")");
+        }
+        self.chain(clause)
+    }
+
+    /// A node pattern followed by any number of relationship and node patterns.
+    fn chain(
+        &mut self,
+        clause: Option<&'static str>,
+    ) -> Parsed {
+        let first = self.nodes.len();
+        self.node(clause)?;
+        while self.is_relationship_start() {
+            self.relationship(clause)?;
+            self.node(clause)?;
+        }
+        self.relate(first);
+        Ok(())
+    }
+
+    /// Marks the nodes parsed since the `first` as related, if a relationship joined them.
+    fn relate(
+        &mut self,
+        first: usize,
+    ) {
+        if self.nodes.len() > first + 1 {
+            for node in &mut self.nodes[first..] {
+                node.related = true;
+            }
+        }
+    }
+
+    /// Skips the variable at the current token, binding it when parsing the pattern of a clause.
+    fn bind(
+        &mut self,
+        clause: Option<&'static str>,
+    ) {
+        let variable = self.advance().text;
+        if clause.is_some() {
+            self.declare(variable);
+        }
+    }
+
+    /// Skips and binds the name that must follow, such as an UNWIND or YIELD variable.
+    fn bind_name(
+        &mut self,
+        what: &str,
+    ) -> Parsed {
+        if !self.is_name() {
+            return self.fail(what);
+        }
+        let name = self.advance().text;
+        self.declare(name);
+        Ok(())
+    }
+
+    fn declare(
+        &mut self,
+        variable: String,
+    ) {
+        if !self.scope.contains(&variable) {
+            self.scope.push(variable);
+        }
+    }
+
+    fn is_relationship_start(&self) -> bool {
+        (self.is_symbol("-") && (self.is_symbol_at(1, "-") || self.is_symbol_at(1, "[")))
+            || (self.is_symbol("<") && self.is_symbol_at(1, "-"))
+    }
+
+    /// `(variable:Label {properties} WHERE predicate)`, each part optional.
+    fn node(
+        &mut self,
+        clause: Option<&'static str>,
+    ) -> Parsed {
+        let start = self.peek().offset;
+        self.expect_symbol("(")?;
+        let mut variable = None;
+        let mut bound = false;
+        if self.is_name() && !self.is_keyword("WHERE") {
+            let name = self.peek().text.clone();
+            bound = self.scope.contains(&name);
+            variable = Some(name);
+            self.bind(clause);
+        }
+        let labeled = self.is_symbol(":");
+        if labeled {
+            self.labels()?;
+        }
+        let head_end = self.previous_end();
+        let properties = self.properties()?;
+        if self.eat_keyword("WHERE") {
+            self.expression()?;
+        }
+        self.expect_symbol(")")?;
+        self.nodes.push(NodePattern {
+            range: start..self.previous_end(),
+            variable,
+            labeled,
+            head_end,
+            properties,
+            clause,
+            related: false,
+            bound,
+        });
+        Ok(())
+    }
+
+    /// `-[variable:TYPE|OTHER *1..3 {properties}]->`, with either arrowhead and no brackets for `--`.
+    fn relationship(
+        &mut self,
+        clause: Option<&'static str>,
+    ) -> Parsed {
+        self.eat_symbol("<");
+        self.expect_symbol("-")?;
+        if self.eat_symbol("[") {
+            if self.is_name() && !self.is_keyword("WHERE") {
+                self.bind(clause);
+            }
+            if self.is_symbol(":") {
+                self.labels()?;
+            }
+            if self.eat_symbol("*") {
+                if self.peek().kind == Kind::Number {
+                    self.pos += 1;
+                }
+                if self.eat_symbol("..") && self.peek().kind == Kind::Number {
+                    self.pos += 1;
+                }
+            }
+            self.properties()?;
+            if self.eat_keyword("WHERE") {
+                self.expression()?;
+            }
+            self.expect_symbol("]")?;
+        }
+        self.expect_symbol("-")?;
+        self.eat_symbol(">");
+        Ok(())
+    }
+
+    /// `:A:B`, `:A|B` or `:A|:B`, with `&` and `!` from label expressions.
+    fn labels(&mut self) -> Parsed {
+        self.label_expression(true)
+    }
+
+    /// Labels without `|` alternatives, which would swallow the `|` of a list comprehension when
+    /// checking labels in an expression such as `[x IN list WHERE x:City | x.name]`.
+    fn label_expression(
+        &mut self,
+        alternatives: bool,
+    ) -> Parsed {
+        self.expect_symbol(":")?;
+        loop {
+            self.eat_symbol("!");
+            self.expect_name("a label or relationship type")?;
+            if self.eat_symbol(":") || self.eat_symbol("&") {
+                continue;
+            }
+            if alternatives && self.eat_symbol("|") {
+                self.eat_symbol(":");
+                continue;
+            }
+            return Ok(());
+        }
+    }
+
+    /// A property map or parameter, if one follows.
+    fn properties(&mut self) -> Result<Option<NodeProperties>, Failure> {
+        if self.is_symbol("{") {
+            let open = self.peek().end;
+            let entries = self.map_entries()?;
+            let close = self.tokens[self.pos - 1].offset;
+            Ok(Some(NodeProperties::Map {
+                contents: open..close,
+                entries,
+            }))
+        } else if self.peek().kind == Kind::Parameter {
+            self.pos += 1;
+            Ok(Some(NodeProperties::Parameter))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// `{key: value, ...}`
+    fn map(&mut self) -> Parsed {
+        self.map_entries().map(drop)
+    }
+
+    /// A map, returning the key and value byte range of each entry.
+    fn map_entries(&mut self) -> Result<Vec<(String, Range<usize>)>, Failure> {
+        self.expect_symbol("{")?;
+        let mut entries = Vec::new();
+        if self.eat_symbol("}") {
+            return Ok(entries);
+        }
+        loop {
+            self.expect_name("a property name")?;
+            let key = self.tokens[self.pos - 1].text.clone();
+            self.expect_symbol(":")?;
+            let start = self.peek().offset;
+            self.expression()?;
+            entries.push((key, start..self.previous_end()));
+            if !self.eat_symbol(",") {
+                self.expect_symbol("}")?;
+                return Ok(entries);
+            }
+        }
+    }
+
+    fn expression(&mut self) -> Parsed {
+        self.binary(0)
+    }
+
+    /// Operators by precedence level: OR, XOR, AND, then NOT and comparisons below.
+    fn binary(
+        &mut self,
+        level: usize,
+    ) -> Parsed {
+        const LEVELS: [&str; 3] = ["OR", "XOR", "AND"];
+        let Some(operator) = LEVELS.get(level) else {
+            return self.negation();
+        };
+        self.binary(level + 1)?;
+        while self.eat_keyword(operator) {
+            self.binary(level + 1)?;
+        }
+        Ok(())
+    }
+
+    fn negation(&mut self) -> Parsed {
+        while self.eat_keyword("NOT") {}
+        self.comparison()
+    }
+
+    /// Comparisons, string and list predicates and null checks.
+    fn comparison(&mut self) -> Parsed {
+        self.additive()?;
+        loop {
+            if self.eat_keyword("IS") {
+                self.eat_keyword("NOT");
+                self.expect_keyword("NULL")?;
+                continue;
+            }
+            if self.eat_keyword("STARTS") || self.eat_keyword("ENDS") {
+                self.expect_keyword("WITH")?;
+            } else if ["=", "<>", "!=", "<", ">", "<=", ">=", "=~"]
+                .iter()
+                .any(|op| self.is_symbol(op))
+            {
+                self.pos += 1;
+            } else if !self.eat_keyword("IN") && !self.eat_keyword("CONTAINS") {
+                return Ok(());
+            }
+            self.additive()?;
+        }
+    }
+
+    fn additive(&mut self) -> Parsed {
+        self.multiplicative()?;
+        while self.eat_symbol("+") || self.eat_symbol("-") {
+            self.multiplicative()?;
+        }
+        Ok(())
+    }
+
+    fn multiplicative(&mut self) -> Parsed {
+        self.power()?;
+        while self.eat_symbol("*") || self.eat_symbol("/") || self.eat_symbol("%") {
+            self.power()?;
+        }
+        Ok(())
+    }
+
+    fn power(&mut self) -> Parsed {
+        self.unary()?;
+        while self.eat_symbol("^") {
+            self.unary()?;
+        }
+        Ok(())
+    }
+
+    fn unary(&mut self) -> Parsed {
+        while self.eat_symbol("-") || self.eat_symbol("+") {}
+        self.atom()?;
+        self.postfix()
+    }
+
+    /// Property lookups, indexes, slices and label checks after an atom.
+    fn postfix(&mut self) -> Parsed {
+        loop {
+            if self.eat_symbol(".") {
+                self.expect_name("a property name")?;
+            } else if self.eat_symbol("[") {
+                if !self.is_symbol("..") {
+                    self.expression()?;
+                }
+                if self.eat_symbol("..") && !self.is_symbol("]") {
+                    self.expression()?;
+                }
+                self.expect_symbol("]")?;
+            } else if self.is_symbol(":") && matches!(self.peek_at(1).kind, Kind::Ident { .. }) {
+                self.label_expression(false)?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn atom(&mut self) -> Parsed {
+        let token = self.peek().clone();
+        match token.kind {
+            Kind::Number | Kind::String | Kind::Parameter => {
+                self.pos += 1;
+                Ok(())
+            }
+            Kind::Symbol if token.text == "(" => {
+                // A pattern used as a predicate, e.g. WHERE (a)-[:KNOWS]->(b)
+                let first = self.nodes.len();
+                if self.attempt(|p| {
+                    p.node(None)?;
+                    if p.is_relationship_start() {
+                        Ok(())
+                    } else {
+                        p.fail("a relationship")
+                    }
+                }) {
+                    while self.is_relationship_start() {
+                        self.relationship(None)?;
+                        self.node(None)?;
+                    }
+                    self.relate(first);
+                    return Ok(());
+                }
+                self.pos += 1;
+                self.expression()?;
+                self.expect_symbol(")")
+            }
+            Kind::Symbol if token.text == "[" => self.list(),
+            Kind::Symbol if token.text == "{" => self.map(),
+            Kind::Ident { quoted: false } if token.text.eq_ignore_ascii_case("CASE") => self.case(),
+            Kind::Ident { quoted: false }
+                if ["EXISTS", "COUNT", "COLLECT"]
+                    .iter()
+                    .any(|k| token.text.eq_ignore_ascii_case(k))
+                    && self.is_symbol_at(1, "{") =>
+            {
+                self.pos += 2;
+                // What the subquery binds is local to it
+                let outer = self.scope.clone();
+                self.subquery_body()?;
+                self.scope = outer;
+                self.expect_symbol("}")
+            }
+            Kind::Ident { quoted } => {
+                if !quoted
+                    && (RESERVED.iter().chain(&CLAUSES).any(|k| token.text.eq_ignore_ascii_case(k))
+                        || token.text.eq_ignore_ascii_case("NOT"))
+                {
+                    return self.fail("an expression");
+                }
+                if let Some(dots) = self.function_name_len() {
+                    self.pos += dots * 2 + 2;
+                    return self.arguments(&token.text);
+                }
+                self.pos += 1;
+                if self.is_symbol("{") {
+                    self.map_projection()?;
+                }
+                Ok(())
+            }
+            _ => self.fail("an expression"),
+        }
+    }
+
+    /// If a function call starts here, the number of `.`s in its name, e.g. 1 for `algo.bfs(`.
+    fn function_name_len(&self) -> Option<usize> {
+        let mut dots = 0;
+        loop {
+            if self.is_symbol_at(dots * 2 + 1, "(") {
+                return Some(dots);
+            }
+            if !self.is_symbol_at(dots * 2 + 1, ".") || !matches!(self.peek_at(dots * 2 + 2).kind, Kind::Ident { .. }) {
+                return None;
+            }
+            dots += 1;
+        }
+    }
+
+    /// A function's arguments, after its opening parenthesis.
+    fn arguments(
+        &mut self,
+        name: &str,
+    ) -> Parsed {
+        if self.eat_symbol(")") {
+            return Ok(());
+        }
+        let iterating = ITERATING_FUNCTIONS.iter().any(|f| name.eq_ignore_ascii_case(f));
+        if iterating && self.is_name() && self.is_keyword_at(1, "IN") {
+            self.iteration()?;
+        } else if name.eq_ignore_ascii_case("reduce") && self.is_name() && self.is_symbol_at(1, "=") {
+            self.pos += 2;
+            self.expression()?;
+            self.expect_symbol(",")?;
+            self.iteration()?;
+        } else if !self.eat_symbol("*") {
+            self.eat_keyword("DISTINCT");
+            self.expression_list()?;
+        }
+        self.expect_symbol(")")
+    }
+
+    /// `variable IN list [WHERE predicate] [| expression]`
+    fn iteration(&mut self) -> Parsed {
+        self.expect_name("a variable")?;
+        self.expect_keyword("IN")?;
+        self.expression()?;
+        if self.eat_keyword("WHERE") {
+            self.expression()?;
+        }
+        if self.eat_symbol("|") {
+            self.expression()?;
+        }
+        Ok(())
+    }
+
+    /// A list literal, list comprehension or pattern comprehension.
+    fn list(&mut self) -> Parsed {
+        self.expect_symbol("[")?;
+        if self.eat_symbol("]") {
+            return Ok(());
+        }
+        if self.is_name() && self.is_keyword_at(1, "IN") {
+            self.iteration()?;
+        } else if self.attempt(|p| {
+            if p.is_name() && p.is_symbol_at(1, "=") {
+                p.pos += 2;
+            }
+            let first = p.nodes.len();
+            p.node(None)?;
+            if !p.is_relationship_start() {
+                return p.fail("a relationship");
+            }
+            while p.is_relationship_start() {
+                p.relationship(None)?;
+                p.node(None)?;
+            }
+            p.relate(first);
+            if p.eat_keyword("WHERE") {
+                p.expression()?;
+            }
+            p.expect_symbol("|")
+        }) {
+            self.expression()?;
+        } else {
+            self.expression_list()?;
+        }
+        self.expect_symbol("]")
+    }
+
+    /// `{.prop, .*, key: value, variable}` after a variable.
+    fn map_projection(&mut self) -> Parsed {
+        self.expect_symbol("{")?;
+        if self.eat_symbol("}") {
+            return Ok(());
+        }
+        loop {
+            if self.eat_symbol(".") {
+                if !self.eat_symbol("*") {
+                    self.expect_name("a property name")?;
+                }
+            } else {
+                self.expect_name("a property name")?;
+                if self.eat_symbol(":") {
+                    self.expression()?;
+                }
+            }
+            if !self.eat_symbol(",") {
+                return self.expect_symbol("}");
+            }
+        }
+    }
+
+    fn case(&mut self) -> Parsed {
+        self.expect_keyword("CASE")?;
+        if !self.is_keyword("WHEN") {
+            self.expression()?;
+        }
+        if !self.is_keyword("WHEN") {
+            return self.fail("WHEN");
+        }
+        while self.eat_keyword("WHEN") {
+            self.expression()?;
+            self.expect_keyword("THEN")?;
+            self.expression()?;
+        }
+        if self.eat_keyword("ELSE") {
+            self.expression()?;
+        }
+        self.expect_keyword("END")
+    }
+
+    /// The body of `EXISTS { ... }` and `COUNT { ... }`: a query, or a pattern with an optional WHERE.
+    fn subquery_body(&mut self) -> Parsed {
+        if self.is_clause_start() {
+            self.query()
+        } else {
+            self.match_body(Some("MATCH"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_valid_queries_and_locates_errors() {
+        for query in [
+            "MATCH (n:Person {name: 'O\\'Brien', alias: 'O''Neil'})-[:ACTED_IN*1..3]->(m:Movie) WHERE m.year >= 2000 \
+             AND NOT (n)-[:DIRECTED]->(m) RETURN n.name AS name, count(DISTINCT m) AS movies ORDER BY movies DESC LIMIT 10",
+            "OPTIONAL MATCH p = shortestPath((a)-[*..5]-(b)) WITH a, [x IN nodes(p) WHERE x:City | x.name] AS cities \
+             UNWIND cities AS c RETURN c, CASE WHEN c STARTS WITH 'S' THEN 1 ELSE 0 END LIMIT $limit;",
+            "CALL db.labels() YIELD label WHERE label <> 'Hidden' RETURN collect(label)",
+            "MATCH (a) WHERE EXISTS { MATCH (a)<--(b) } AND all(v IN a.values WHERE v > 0) \
+             RETURN a {.name, .*, degree: size([(a)--() | 1])}, reduce(s = 0, v IN a.values | s + v), a.list[1..] // done",
+            "MERGE (n:Tag {name: $name}) ON CREATE SET n.created = timestamp(), n:New ON MATCH SET n += {seen: true}",
+            "MATCH (n) RETURN n UNION ALL MATCH (m) RETURN m AS n",
+            "CREATE INDEX FOR (p:Person) ON (p.name)",
+            "CYPHER name=\"Tom\" ids=[1, -2] weight=.5 opts={depth: 2} MATCH (n {name: $name}) RETURN n",
+            "CYPHER query_vector=[0.1, .25, 1e-3] EXPLAIN MATCH (n) RETURN n",
+        ] {
+            assert_eq!(check(query), Ok(()), "{query}");
+        }
+
+        let error = |query: &str| check(query).unwrap_err().to_string();
+        assert_eq!(
+            error("MATCH (n:Person WHERE n.name = 'John' RETURN n"),
+            "Syntax error at line 1, column 39: expected ')', found 'RETURN'"
+        );
+        assert_eq!(
+            error("MATCH (n)\nWHERE n.name = 'John RETURN n"),
+            "Syntax error at line 2, column 16: unterminated string literal"
+        );
+        assert_eq!(
+            error("MATHC (n) RETURN n"),
+            "Syntax error at line 1, column 1: unknown clause 'MATHC'"
+        );
+        assert_eq!(
+            error("MATCH (n) RETURN n LIMIT 5 )"),
+            "Syntax error at line 1, column 28: expected the end of the query, found ')'"
+        );
+        assert_eq!(
+            error("CYPHER limit= MATCH (n) RETURN n LIMIT $limit"),
+            "Syntax error at line 1, column 15: expected an expression, found 'MATCH'"
+        );
+        assert_eq!(
+            error("MATCH (n) WHERE RETURN n"),
+            "Syntax error at line 1, column 17: expected an expression, found 'RETURN'"
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Per-request debug bundles, kept alongside the audit trail.
+//!
+//! While a `/text_to_cypher` request runs inside [`traced`], the pipeline records what it did into a
+//! task-local [`DebugTrace`]: every prompt exactly as sent to the model with the raw output, every
+//! validation report, every executed query with a sample of its result, and stage timings. Once the
+//! request finishes the trace becomes a [`DebugBundle`], stored by [`crate::audit::AuditLog`] for
+//! [`DEBUG_BUNDLE_TTL`] so a reported wrong answer can be investigated after the fact. Recording
+//! outside [`traced`] is a no-op, so library and MCP callers pay nothing.
+
+use crate::session::SessionTurn;
+use crate::usage::TokenUsage;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Storage namespace holding debug bundles.
+pub const DEBUG_NAMESPACE: &str = "debug";
+
+/// How long a debug bundle is kept.
+pub const DEBUG_BUNDLE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximum number of characters of a query result kept in a bundle.
+const RESULT_SAMPLE_CHARS: usize = 2_000;
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+fn sample(result: &str) -> String {
+    result.chars().take(RESULT_SAMPLE_CHARS).collect()
+}
+
+/// One message of a rendered prompt.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct PromptMessage {
+    /// `system`, `user`, `assistant` or `tool`.
+    pub role: String,
+    /// The message text; non-text parts (tool calls and responses) are omitted.
+    pub content: String,
+}
+
+/// One model call: the prompt as sent and what came back.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct ModelCall {
+    /// Pipeline stage, e.g. `cypher_generation` or `answer`.
+    pub stage: String,
+    /// The system prompt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    pub messages: Vec<PromptMessage>,
+    /// The raw model output; missing if the call failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+/// A validation report for one generated query.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub query: String,
+    pub is_valid: bool,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Lint hint messages.
+    pub hints: Vec<String>,
+}
+
+/// One query execution.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct QueryExecution {
+    pub query: String,
+    /// The beginning of the formatted result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_sample: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+/// Time spent in one pipeline stage.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct StageTiming {
+    pub stage: String,
+    pub elapsed_ms: u64,
+}
+
+/// Everything recorded while a request ran. See the [module documentation](self).
+#[derive(Debug, Default)]
+pub struct DebugTrace {
+    model_calls: Vec<ModelCall>,
+    validations: Vec<ValidationReport>,
+    executions: Vec<QueryExecution>,
+    timings: Vec<StageTiming>,
+}
+
+impl DebugTrace {
+    /// Records a prompt about to be sent; [`Self::finish_model_call`] completes it.
+    pub fn begin_model_call(
+        &mut self,
+        stage: &str,
+        request: &genai::chat::ChatRequest,
+    ) {
+        self.model_calls.push(ModelCall {
+            stage: stage.to_string(),
+            system: request.system.clone(),
+            messages: request
+                .messages
+                .iter()
+                .map(|message| PromptMessage {
+                    role: format!("{:?}", message.role).to_ascii_lowercase(),
+                    content: message.content.joined_texts().unwrap_or_default(),
+                })
+                .collect(),
+            output: None,
+            elapsed_ms: 0,
+        });
+    }
+
+    /// Completes the last started model call.
+    pub fn finish_model_call(
+        &mut self,
+        output: Option<&str>,
+        elapsed: Duration,
+    ) {
+        if let Some(call) = self.model_calls.last_mut() {
+            call.output = output.map(str::to_string);
+            call.elapsed_ms = millis(elapsed);
+        }
+    }
+
+    pub fn record_validation(
+        &mut self,
+        report: ValidationReport,
+    ) {
+        self.validations.push(report);
+    }
+
+    pub fn record_execution(
+        &mut self,
+        query: &str,
+        outcome: Result<&str, &str>,
+        elapsed: Duration,
+    ) {
+        self.executions.push(QueryExecution {
+            query: query.to_string(),
+            result_sample: outcome.ok().map(sample),
+            error: outcome.err().map(str::to_string),
+            elapsed_ms: millis(elapsed),
+        });
+    }
+
+    pub fn record_timing(
+        &mut self,
+        stage: &str,
+        elapsed: Duration,
+    ) {
+        self.timings.push(StageTiming {
+            stage: stage.to_string(),
+            elapsed_ms: millis(elapsed),
+        });
+    }
+
+    /// Assembles the bundle for a finished request.
+    #[must_use]
+    pub fn into_bundle(
+        self,
+        request_id: &str,
+        graph_name: &str,
+        turn: &SessionTurn,
+        total: Duration,
+    ) -> DebugBundle {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX));
+        DebugBundle {
+            request_id: request_id.to_string(),
+            timestamp_ms,
+            graph_name: graph_name.to_string(),
+            question: turn.question.clone(),
+            model: turn.model.clone(),
+            model_calls: self.model_calls,
+            validations: self.validations,
+            executions: self.executions,
+            executed_query: turn.cypher_query.clone(),
+            answer: turn.answer.clone(),
+            error: turn.error.clone(),
+            token_usage: turn.token_usage.clone(),
+            timings: self.timings,
+            total_ms: millis(total),
+        }
+    }
+}
+
+tokio::task_local! {
+    static TRACE: Arc<Mutex<DebugTrace>>;
+}
+
+/// Runs `future` with a fresh [`DebugTrace`] and returns its output with the trace.
+pub async fn traced<F: Future>(future: F) -> (F::Output, DebugTrace) {
+    let trace = Arc::new(Mutex::new(DebugTrace::default()));
+    let output = TRACE.scope(trace.clone(), future).await;
+    let trace = trace.lock().map(|mut trace| std::mem::take(&mut *trace)).unwrap_or_default();
+    (output, trace)
+}
+
+/// Applies `f` to the current task's trace, if it runs inside [`traced`].
+pub fn record(f: impl FnOnce(&mut DebugTrace)) {
+    let _ = TRACE.try_with(|trace| {
+        if let Ok(mut trace) = trace.lock() {
+            f(&mut trace);
+        }
+    });
+}
+
+/// What happened during one `/text_to_cypher` request.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct DebugBundle {
+    pub request_id: String,
+    /// Unix timestamp (milliseconds) at which the request finished.
+    pub timestamp_ms: u64,
+    pub graph_name: String,
+    /// The user's question.
+    pub question: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Model calls in order, with the exact prompts and raw outputs.
+    pub model_calls: Vec<ModelCall>,
+    pub validations: Vec<ValidationReport>,
+    /// Query executions in order; a self-healed request has more than one.
+    pub executions: Vec<QueryExecution>,
+    /// The query whose result was answered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executed_query: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+    /// Error that ended the request, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+    pub timings: Vec<StageTiming>,
+    pub total_ms: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genai::chat::ChatMessage;
+
+    #[tokio::test]
+    async fn records_only_inside_traced() {
+        record(|trace| trace.record_timing("ignored", Duration::ZERO));
+
+        let ((), trace) = traced(async {
+            let request = genai::chat::ChatRequest::default()
+                .with_system("You write Cypher")
+                .append_message(ChatMessage::user("Who acted in Heat?"));
+            record(|trace| trace.begin_model_call("cypher_generation", &request));
+            record(|trace| trace.finish_model_call(Some("MATCH (n) RETURN n"), Duration::from_millis(12)));
+            record(|trace| trace.record_execution("MATCH (n) RETURN n", Ok("[]"), Duration::from_millis(3)));
+        })
+        .await;
+
+        let mut turn = SessionTurn::new("Who acted in Heat?");
+        turn.error = Some("boom".into());
+        let bundle = trace.into_bundle("r1", "movies", &turn, Duration::from_millis(20));
+        assert_eq!(bundle.model_calls.len(), 1);
+        let call = &bundle.model_calls[0];
+        assert_eq!(call.system.as_deref(), Some("You write Cypher"));
+        assert_eq!(call.messages[0].role, "user");
+        assert_eq!(call.output.as_deref(), Some("MATCH (n) RETURN n"));
+        assert_eq!(call.elapsed_ms, 12);
+        assert_eq!(bundle.executions[0].result_sample.as_deref(), Some("[]"));
+        assert!(bundle.timings.is_empty());
+        assert_eq!((bundle.error.as_deref(), bundle.total_ms), (Some("boom"), 20));
+    }
+}
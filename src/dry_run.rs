@@ -0,0 +1,117 @@
+//! Dry-run previews that must be confirmed before a destructive query executes.
+//!
+//! A destructive request first stages the generated query together with its execution plan and
+//! returns a one-time confirmation token. Only a follow-up request carrying that token executes the
+//! query — exactly the previewed text, never a regenerated one.
+
+use crate::storage::{self, Storage, StorageError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Storage namespace holding staged dry runs.
+pub const DRY_RUN_NAMESPACE: &str = "dry_runs";
+
+/// How long a staged dry run can be confirmed.
+pub const DRY_RUN_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// A staged destructive query awaiting confirmation.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct DryRun {
+    /// One-time token to send as `confirm` to execute the query.
+    pub confirmation_token: String,
+    /// Graph the query targets.
+    pub graph_name: String,
+    /// The query that will run on confirmation.
+    pub query: String,
+    /// Execution plan from `GRAPH.EXPLAIN`, one operation per line.
+    pub plan: Vec<String>,
+    /// Unix timestamp (seconds) after which the token is no longer accepted.
+    pub expires_at: u64,
+}
+
+/// Stages and redeems dry runs.
+#[derive(Debug, Clone)]
+pub struct DryRunStore {
+    storage: Arc<dyn Storage>,
+}
+
+impl DryRunStore {
+    /// Creates a store backed by `storage`.
+    #[must_use]
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Stages `query` for confirmation and returns the preview.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the dry run cannot be saved.
+    pub async fn stage(
+        &self,
+        graph_name: &str,
+        query: &str,
+        plan: Vec<String>,
+    ) -> Result<DryRun, StorageError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let dry_run = DryRun {
+            confirmation_token: Uuid::new_v4().to_string(),
+            graph_name: graph_name.to_string(),
+            query: query.to_string(),
+            plan,
+            expires_at: now + DRY_RUN_TTL.as_secs(),
+        };
+        storage::put_json(
+            self.storage.as_ref(),
+            DRY_RUN_NAMESPACE,
+            &dry_run.confirmation_token,
+            &dry_run,
+            Some(DRY_RUN_TTL),
+        )
+        .await?;
+        Ok(dry_run)
+    }
+
+    /// Redeems a confirmation token. Tokens are single-use: a redeemed token is removed, and expired
+    /// or unknown tokens return `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the dry run cannot be read or removed.
+    pub async fn take(
+        &self,
+        token: &str,
+    ) -> Result<Option<DryRun>, StorageError> {
+        let dry_run = storage::get_json(self.storage.as_ref(), DRY_RUN_NAMESPACE, token).await?;
+        if dry_run.is_some() && !self.storage.delete(DRY_RUN_NAMESPACE, token).await? {
+            // Redeemed concurrently by another request.
+            return Ok(None);
+        }
+        Ok(dry_run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn tokens_are_single_use() {
+        let store = DryRunStore::new(Arc::new(InMemoryStorage::new()));
+        let staged = store
+            .stage("g", "MATCH (n:Orphan) DETACH DELETE n", vec!["Delete".into()])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.take(&staged.confirmation_token).await.unwrap(),
+            Some(staged.clone())
+        );
+        assert_eq!(store.take(&staged.confirmation_token).await.unwrap(), None);
+        assert_eq!(store.take("unknown").await.unwrap(), None);
+    }
+}
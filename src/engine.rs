@@ -0,0 +1,1399 @@
+//! The pipeline steps shared by the server's streaming handler and the library's processor.
+//!
+//! Both generate a query, check it, execute it, heal it with the database's error when it fails,
+//! and write the answer. The steps that do not depend on how each side reports progress or
+//! configures a request live here, so the two paths cannot drift apart:
+//!
+//! - [`admissible_candidates`] and [`admit`] refuse a request an [`Admission`] does not take
+//!   before it calls the model, and [`Admission::record_tokens`] counts what an admitted one spent.
+//! - [`query_chat_request`] adds the mode instructions a request asks for ([`QueryModes`]).
+//! - [`validation_options`] checks generated queries against the request's schema and question.
+//! - [`prune_schema`] narrows a large schema to the part closest to the question.
+//! - [`generate_query`] writes a query through a [`QueryWriter`], falling back along the model
+//!   chain, keeping the best of several candidates, correcting a query that fails validation and
+//!   refining one with lint hints ([`Generation`]).
+//! - [`policy_violation`] names the checks a query fails that no strictness lets through.
+//! - [`heal_query`] asks the model to correct a failed query, and checks the new one.
+//! - [`execute_with_healing`] executes a query through a [`QueryRunner`], regenerating it after a
+//!   failure up to a bounded number of times, and reports each step to a [`ProgressReporter`].
+//! - [`write_answer`] answers from the result through an [`AnswerWriter`], then suggests follow-up
+//!   questions and checks the answer against the result.
+
+use crate::chat::{ChatMessage, ChatRequest, ChatRole};
+use crate::core::{GenerationOutcome, check_answer_faithfulness, generate_followup_questions};
+use crate::hooks::ResponseHooks;
+use crate::latency::LatencyMode;
+use crate::model_chain::{ModelChain, fallback_warning};
+use crate::routing::GraphCandidate;
+use crate::schema::pruning::SchemaPruner;
+use crate::streaming::{Progress, Warning, WarningCode};
+use crate::template::TemplateEngine;
+use crate::usage::TokenUsage;
+use crate::validation_rules::Severity;
+use crate::validator::{CypherValidator, LintHint, SchemaIssue, ValidationOptions, ValidationResult};
+use async_trait::async_trait;
+
+/// The client a progress report was sent to is gone; the request stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamClosed;
+
+impl std::fmt::Display for StreamClosed {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str("Client disconnected")
+    }
+}
+
+impl std::error::Error for StreamClosed {}
+
+/// Where a pipeline reports its steps: the server's SSE stream, or the library's progress stream.
+#[async_trait]
+pub trait ProgressReporter: Send + Sync {
+    /// Reports one step.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StreamClosed`] once nobody receives the reports.
+    async fn report(
+        &self,
+        progress: Progress,
+    ) -> Result<(), StreamClosed>;
+}
+
+/// Why a step of [`execute_with_healing`] produced no result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepError {
+    /// The step failed with this message; self-healing may fix it.
+    Failed(String),
+    /// A regenerated query was rejected before it ran; the next attempt corrects this query.
+    Rejected { query: String, message: String },
+    /// The step already reported why it failed, or the client is gone; the request stops.
+    Stopped,
+}
+
+impl From<StreamClosed> for StepError {
+    fn from(_: StreamClosed) -> Self {
+        Self::Stopped
+    }
+}
+
+/// Runs and regenerates the queries of one request, for [`execute_with_healing`].
+#[async_trait]
+pub trait QueryRunner: Send {
+    /// Executes `query` and returns its formatted result.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StepError::Failed`] with the database's error message when the query fails.
+    async fn execute(
+        &mut self,
+        query: &str,
+    ) -> Result<String, StepError>;
+
+    /// Regenerates `failed_query` from the `error` it failed with, and checks the new query as a
+    /// generated one is checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StepError::Rejected`] when the new query fails its checks, and
+    /// [`StepError::Failed`] when no query was written.
+    async fn heal(
+        &mut self,
+        failed_query: &str,
+        error: &str,
+    ) -> Result<String, StepError>;
+}
+
+/// A query that ran, after `heal_attempts` self-healing attempts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Executed {
+    pub query: String,
+    pub result: String,
+    pub heal_attempts: usize,
+}
+
+/// Executes `query`, and after a failure regenerates it with the error and executes the new query,
+/// up to `max_heal_attempts` times.
+///
+/// # Errors
+///
+/// Returns [`StepError::Failed`] with the message to end the request with when every attempt
+/// failed, or [`StepError::Stopped`] when a step stopped the request.
+pub async fn execute_with_healing<R, P>(
+    runner: &mut R,
+    progress: &P,
+    mut query: String,
+    max_heal_attempts: usize,
+) -> Result<Executed, StepError>
+where
+    R: QueryRunner + ?Sized,
+    P: ProgressReporter + ?Sized,
+{
+    let mut error = match runner.execute(&query).await {
+        Ok(result) => {
+            return Ok(Executed {
+                query,
+                result,
+                heal_attempts: 0,
+            });
+        }
+        Err(StepError::Failed(error) | StepError::Rejected { message: error, .. }) => error,
+        Err(StepError::Stopped) => return Err(StepError::Stopped),
+    };
+    for attempt in 1..=max_heal_attempts {
+        tracing::warn!("Query execution failed, attempting self-healing ({attempt}/{max_heal_attempts}): {error}");
+        progress
+            .report(Progress::Status(format!(
+                "Query failed ({error}), attempting self-healing ({attempt}/{max_heal_attempts})..."
+            )))
+            .await?;
+        let executed = match runner.heal(&query, &error).await {
+            Ok(healed) => {
+                progress.report(Progress::CypherQuery(format!("Fixed: {healed}"))).await?;
+                query = healed;
+                runner.execute(&query).await
+            }
+            Err(e) => Err(e),
+        };
+        match executed {
+            Ok(result) => {
+                tracing::info!("Self-healing successful");
+                progress.report(Progress::Status("Self-healing successful".to_string())).await?;
+                return Ok(Executed {
+                    query,
+                    result,
+                    heal_attempts: attempt,
+                });
+            }
+            Err(StepError::Failed(message)) => error = message,
+            Err(StepError::Rejected {
+                query: rejected,
+                message,
+            }) => {
+                query = rejected;
+                error = message;
+            }
+            Err(StepError::Stopped) => return Err(StepError::Stopped),
+        }
+    }
+    Err(StepError::Failed(if max_heal_attempts == 0 {
+        format!("Query execution failed: {error}")
+    } else {
+        format!("Query execution failed even after {max_heal_attempts} self-healing attempt(s): {error}")
+    }))
+}
+
+/// The instructions a request adds to query generation, each sent as a system message before the
+/// question.
+#[derive(Debug, Clone, Default)]
+pub struct QueryModes<'a> {
+    /// Curated examples of questions like this one and their queries ([`crate::few_shot::render`]).
+    pub examples: Option<String>,
+    /// Lifts the read-only constraint for the writes the request may make.
+    pub write_prompt: Option<String>,
+    /// The graph only answers with aggregates.
+    pub aggregate_only: bool,
+    /// Values from the question are written as `$name` parameters.
+    pub parameterized: bool,
+    /// The query that answered the session's previous question.
+    pub previous_query: Option<&'a str>,
+}
+
+/// The conversation to generate a query from: `chat_request` with the instructions of `modes`
+/// before its last message.
+#[must_use]
+pub fn query_chat_request(
+    chat_request: &ChatRequest,
+    modes: &QueryModes<'_>,
+) -> ChatRequest {
+    let mut chat_request = chat_request.clone();
+    let prompts = [
+        modes.examples.clone(),
+        modes.write_prompt.clone(),
+        modes
+            .aggregate_only
+            .then(|| TemplateEngine::aggregate_mode_prompt().to_string()),
+        modes
+            .parameterized
+            .then(|| TemplateEngine::parameterized_mode_prompt().to_string()),
+        modes.previous_query.map(TemplateEngine::render_session_context_prompt),
+    ];
+    for prompt in prompts.into_iter().flatten() {
+        let index = chat_request.messages.len().saturating_sub(1);
+        chat_request.messages.insert(
+            index,
+            ChatMessage {
+                role: ChatRole::System,
+                content: prompt,
+            },
+        );
+    }
+    chat_request
+}
+
+/// The conversation that asks the model to correct `failed_query`, which did not pass validation
+/// with `errors`: `query_request` (from [`query_chat_request`]) followed by the query and the errors.
+#[must_use]
+pub fn validation_feedback_request(
+    query_request: &ChatRequest,
+    failed_query: &str,
+    errors: &str,
+) -> ChatRequest {
+    let mut messages = query_request.messages.clone();
+    messages.push(ChatMessage {
+        role: ChatRole::Assistant,
+        content: failed_query.to_string(),
+    });
+    messages.push(ChatMessage {
+        role: ChatRole::User,
+        content: format!(
+            "The previous query has validation errors: {errors}. Please generate a corrected Cypher query."
+        ),
+    });
+    ChatRequest { messages }
+}
+
+/// The conversation that asks the model to correct `failed_query`.
+///
+/// `query_request` (from [`query_chat_request`]) is followed by the failed query and the `error`
+/// it failed with, naming the labels, relationship types and properties the schema does not have.
+#[must_use]
+pub fn heal_chat_request(
+    mut query_request: ChatRequest,
+    failed_query: &str,
+    error: &str,
+    schema_issues: &[SchemaIssue],
+) -> ChatRequest {
+    let mismatches = if schema_issues.is_empty() {
+        String::new()
+    } else {
+        let issues: Vec<String> = schema_issues.iter().map(ToString::to_string).collect();
+        format!(" It also does not match the schema: {}", issues.join("; "))
+    };
+    query_request.messages.push(ChatMessage {
+        role: ChatRole::Assistant,
+        content: failed_query.to_string(),
+    });
+    query_request.messages.push(ChatMessage {
+        role: ChatRole::User,
+        content: format!(
+            "The previous query failed with error: {error}.{mismatches} Please generate a corrected Cypher query that fixes this error and follows the schema more closely."
+        ),
+    });
+    query_request
+}
+
+/// The conversation that asks the model to rewrite `query`, which is valid but has lint `hints`:
+/// `query_request` (from [`query_chat_request`]) followed by the query and the hints.
+#[must_use]
+pub fn lint_feedback_request(
+    query_request: &ChatRequest,
+    query: &str,
+    hints: &[LintHint],
+) -> ChatRequest {
+    let mut messages = query_request.messages.clone();
+    messages.push(ChatMessage {
+        role: ChatRole::Assistant,
+        content: query.to_string(),
+    });
+    let hints = hints.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ");
+    messages.push(ChatMessage {
+        role: ChatRole::User,
+        content: format!(
+            "The previous query is valid but has these style or performance issues: {hints}. Please rewrite it to address them while returning the same data."
+        ),
+    });
+    ChatRequest { messages }
+}
+
+/// The checks every generated query of a request gets.
+///
+/// They are strictness, the labels, relationships and date layouts of `schema`, and the relative
+/// periods the question of `chat_request` asks about. Each side adds its deployment's policy,
+/// allowlist, rules, tenant and modes.
+#[must_use]
+pub fn validation_options(
+    chat_request: &ChatRequest,
+    schema: &str,
+    strict: bool,
+) -> ValidationOptions {
+    ValidationOptions::strict(strict)
+        .with_schema(schema)
+        .with_question(chat_request.last_user_question().unwrap_or_default())
+}
+
+/// Validates `query`, returning its warnings as validation hints when it is valid; the error lists
+/// why it is not.
+///
+/// # Errors
+///
+/// Returns the validation errors, joined with `; `, when the query is invalid.
+pub fn validate_query(
+    query: &str,
+    validation: &ValidationOptions,
+) -> Result<Vec<Warning>, String> {
+    validation_outcome(CypherValidator::validate_with_options(query, validation))
+}
+
+fn validation_outcome(result: ValidationResult) -> Result<Vec<Warning>, String> {
+    if !result.is_valid {
+        tracing::warn!("Query failed validation: {:?}", result.errors);
+        return Err(result.errors.join("; "));
+    }
+    if !result.warnings.is_empty() {
+        tracing::info!("Query validation warnings: {:?}", result.warnings);
+    }
+    Ok(result
+        .warnings
+        .into_iter()
+        .map(|warning| Warning::new(WarningCode::ValidationHint, warning))
+        .collect())
+}
+
+/// Why `query` may not run, whatever the request's strictness.
+///
+/// It calls a procedure outside the allowlist, uses a clause the policies forbid, escapes the
+/// tenant filter, returns more than aggregates from an aggregate-only graph, or breaks one of the
+/// deployment's rules.
+#[must_use]
+pub fn policy_violation(
+    query: &str,
+    validation: &ValidationOptions,
+) -> Option<String> {
+    let rejection = |checker: &str, errors: Vec<String>| {
+        (!errors.is_empty()).then(|| format!("{checker} rejected the query: {}", errors.join("; ")))
+    };
+    let clause_errors = validation.clause_policy.as_ref().map_or_else(Vec::new, |policy| {
+        policy
+            .disallowed(query)
+            .into_iter()
+            .map(|clause| format!("{clause} clauses are not allowed by the clause policy"))
+            .collect()
+    });
+    let rule_errors = validation
+        .rules
+        .check(query)
+        .into_iter()
+        .filter(|finding| matches!(finding.severity, Severity::Error))
+        .map(|finding| finding.message)
+        .collect();
+    rejection(
+        "Procedure allowlist",
+        validation
+            .procedure_allowlist
+            .as_ref()
+            .map(|allowlist| allowlist.check(query))
+            .unwrap_or_default(),
+    )
+    .or_else(|| {
+        rejection(
+            "Validation policy",
+            validation.policy.as_ref().map(|policy| policy.check(query)).unwrap_or_default(),
+        )
+    })
+    .or_else(|| rejection("Clause policy", clause_errors))
+    .or_else(|| {
+        rejection(
+            "Tenant filter",
+            validation
+                .tenant_filter
+                .as_ref()
+                .map(|filter| filter.check(query))
+                .unwrap_or_default(),
+        )
+    })
+    .or_else(|| {
+        rejection(
+            "Aggregate-only mode",
+            if validation.aggregate_only {
+                CypherValidator::aggregate_only_errors(query)
+            } else {
+                Vec::new()
+            },
+        )
+    })
+    .or_else(|| rejection("Validation rules", rule_errors))
+}
+
+/// Admits requests to graphs before they call the model, and counts the model tokens they spend,
+/// e.g. against per-graph quotas.
+#[async_trait]
+pub trait Admission: std::fmt::Debug + Send + Sync {
+    /// Why a request to `graph_name` would be refused now, without counting it.
+    async fn refusal(
+        &self,
+        graph_name: &str,
+    ) -> Option<String>;
+
+    /// Admits a request to `graph_name` and counts it.
+    ///
+    /// # Errors
+    ///
+    /// Returns why the request is refused.
+    async fn admit(
+        &self,
+        graph_name: &str,
+    ) -> Result<(), String>;
+
+    /// Counts `tokens` spent by a request to `graph_name`.
+    fn record_tokens(
+        &self,
+        graph_name: &str,
+        tokens: u64,
+    );
+}
+
+/// The routing candidates `admission` would admit a request to, so routing only calls the model
+/// when a graph can take the request.
+///
+/// # Errors
+///
+/// Returns the refusal of the first candidate when every candidate is refused.
+pub async fn admissible_candidates<A>(
+    admission: &A,
+    candidates: Vec<GraphCandidate>,
+) -> Result<Vec<GraphCandidate>, String>
+where
+    A: Admission + ?Sized,
+{
+    let mut admissible = Vec::with_capacity(candidates.len());
+    let mut first_refusal = None;
+    for candidate in candidates {
+        match admission.refusal(&candidate.name).await {
+            Some(refusal) => {
+                first_refusal.get_or_insert(refusal);
+            }
+            None => admissible.push(candidate),
+        }
+    }
+    match first_refusal {
+        Some(refusal) if admissible.is_empty() => Err(refusal),
+        _ => Ok(admissible),
+    }
+}
+
+/// Admits a request to `graph_name` before its first model call after routing. A refused request
+/// still counts the tokens in `token_usage`, spent routing it.
+///
+/// # Errors
+///
+/// Returns why the request is refused.
+pub async fn admit<A>(
+    admission: &A,
+    graph_name: &str,
+    token_usage: &TokenUsage,
+) -> Result<(), String>
+where
+    A: Admission + ?Sized,
+{
+    admission
+        .admit(graph_name)
+        .await
+        .inspect_err(|_| admission.record_tokens(graph_name, token_usage.total_tokens))
+}
+
+/// Narrows `schema` to the labels and relationships closest to `question` with `pruner`.
+///
+/// The narrowed schema is reported with a status. Returns `None` when the schema is sent whole:
+/// without a pruner or question, when it is small, or when pruning failed.
+///
+/// # Errors
+///
+/// Returns [`StreamClosed`] once nobody receives the reports.
+pub async fn prune_schema<P>(
+    pruner: Option<&SchemaPruner>,
+    client: &genai::Client,
+    schema: &str,
+    question: Option<&str>,
+    progress: &P,
+) -> Result<Option<String>, StreamClosed>
+where
+    P: ProgressReporter + ?Sized,
+{
+    let (Some(pruner), Some(question)) = (pruner, question) else {
+        return Ok(None);
+    };
+    match pruner.prune(client, schema, question).await {
+        Ok(Some(narrowed)) => {
+            progress
+                .report(Progress::Status(format!(
+                    "Pruned the schema to the labels and relationships closest to the question ({} of {} bytes)",
+                    narrowed.len(),
+                    schema.len()
+                )))
+                .await?;
+            Ok(Some(narrowed))
+        }
+        Ok(None) => Ok(None),
+        Err(e) => {
+            tracing::warn!("Sending the whole schema: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+/// Reports `warning` and adds it to `warnings`.
+async fn warn<P>(
+    progress: &P,
+    warnings: &mut Vec<Warning>,
+    warning: Warning,
+) -> Result<(), StreamClosed>
+where
+    P: ProgressReporter + ?Sized,
+{
+    progress.report(Progress::Warning(warning.clone())).await?;
+    warnings.push(warning);
+    Ok(())
+}
+
+/// Writes the queries of one request, for [`generate_query`] and [`heal_query`].
+#[async_trait]
+pub trait QueryWriter: Send {
+    /// Why a query could not be written.
+    type Error: std::fmt::Display + Send;
+
+    /// Asks `model` for a query answering `chat_request`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of a failed model call.
+    async fn write(
+        &mut self,
+        chat_request: &ChatRequest,
+        model: &str,
+    ) -> Result<GenerationOutcome, Self::Error>;
+
+    /// Whether the graph can plan `query`, for choosing among candidates.
+    async fn explains(
+        &mut self,
+        query: &str,
+    ) -> bool;
+
+    /// Whether a model chain moves on to its next model after `error`. Every error does by default.
+    fn falls_back(
+        &self,
+        _error: &Self::Error,
+    ) -> bool {
+        true
+    }
+
+    /// Called with the validation of each generated query, e.g. to trace it.
+    fn validated(
+        &mut self,
+        _query: &str,
+        _result: &ValidationResult,
+    ) {
+    }
+}
+
+/// What the queries of one request are generated from and checked against.
+#[derive(Debug, Clone)]
+pub struct Generation<'a> {
+    /// The conversation to write a query for, from [`query_chat_request`].
+    pub chat_request: ChatRequest,
+    /// What a generated query is checked against; its tenant filter and `max_var_length` also
+    /// rewrite the query.
+    pub validation: ValidationOptions,
+    /// Rewrite each generated query before it is checked.
+    pub response_hooks: &'a ResponseHooks,
+    /// How many candidates are written, and whether a query is corrected or refined.
+    pub latency_mode: LatencyMode,
+    /// A query with lint hints gets one rewrite, kept only if it validates with fewer hints.
+    pub refine_lint_hints: bool,
+}
+
+impl Generation<'_> {
+    /// Runs the response hooks and the tenant filter on a generated query, then caps its
+    /// variable-length patterns, returning a warning for each cap.
+    #[must_use]
+    pub fn prepare(
+        &self,
+        query: String,
+    ) -> (String, Vec<Warning>) {
+        let query = self.response_hooks.process_query(query);
+        let query = match &self.validation.tenant_filter {
+            Some(filter) => filter.apply(&query),
+            None => query,
+        };
+        let Some(max_depth) = self.validation.max_var_length else {
+            return (query, Vec::new());
+        };
+        let (capped, applied) = CypherValidator::cap_var_length(&query, max_depth);
+        let warnings = applied
+            .into_iter()
+            .map(|warning| {
+                tracing::warn!("{warning}");
+                Warning::new(WarningCode::VarLengthCapped, warning)
+            })
+            .collect();
+        (capped, warnings)
+    }
+
+    /// [`Self::prepare`], reporting the caps and adding them to `warnings`.
+    async fn prepare_reported<P>(
+        &self,
+        query: String,
+        progress: &P,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<String, StreamClosed>
+    where
+        P: ProgressReporter + ?Sized,
+    {
+        let (query, caps) = self.prepare(query);
+        for cap in caps {
+            warn(progress, warnings, cap).await?;
+        }
+        Ok(query)
+    }
+}
+
+/// Validates `query` as [`validate_query`] does, telling `writer`.
+fn validate_written<W>(
+    writer: &mut W,
+    query: &str,
+    validation: &ValidationOptions,
+) -> Result<Vec<Warning>, String>
+where
+    W: QueryWriter + ?Sized,
+{
+    let result = CypherValidator::validate_with_options(query, validation);
+    writer.validated(query, &result);
+    validation_outcome(result)
+}
+
+/// Why [`generate_query`] produced no query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerationError<E> {
+    /// The last model of the chain failed with this error.
+    Failed(E),
+    /// Strict validation rejected the query; the message says why.
+    Rejected(String),
+    /// The client is gone; the request stops.
+    Stopped,
+}
+
+impl<E> From<StreamClosed> for GenerationError<E> {
+    fn from(_: StreamClosed) -> Self {
+        Self::Stopped
+    }
+}
+
+/// Writes the query for a request with the primary model of `models`.
+///
+/// A model whose calls all fail is dropped for the next one with a `model_fallback` warning;
+/// `models` is left with the model that wrote the query as its primary.
+///
+/// The query is the best of the request's candidates, prepared ([`Generation::prepare`]) and
+/// validated. A query with errors is regenerated once with them as feedback, unless the latency
+/// mode never retries; when no valid query comes of it, strict validation rejects the request and
+/// otherwise the first query runs with a `validation_failed` warning. Its lint hints are then
+/// reported, after one rewrite when the generation refines them. Warnings are reported as they
+/// come and added to `warnings`.
+///
+/// # Errors
+///
+/// Returns [`GenerationError::Failed`] with the error of the last model,
+/// [`GenerationError::Rejected`] when strict validation refuses the query, or
+/// [`GenerationError::Stopped`] once nobody receives the reports.
+pub async fn generate_query<W, P>(
+    writer: &mut W,
+    progress: &P,
+    generation: &Generation<'_>,
+    models: &mut ModelChain,
+    warnings: &mut Vec<Warning>,
+) -> Result<GenerationOutcome, GenerationError<W::Error>>
+where
+    W: QueryWriter + ?Sized,
+    P: ProgressReporter + ?Sized,
+{
+    progress
+        .report(Progress::Status("Generating Cypher query using schema ...".to_string()))
+        .await?;
+    let query = loop {
+        match select_best_candidate(writer, progress, generation, models.primary()).await? {
+            Ok(GenerationOutcome::Query(query)) => break query,
+            Ok(outcome) => return Ok(outcome),
+            Err(e) => {
+                let failed = models.primary().to_string();
+                if !writer.falls_back(&e) || !models.fall_back() {
+                    return Err(GenerationError::Failed(e));
+                }
+                let message = e.to_string();
+                tracing::warn!(
+                    "Model {} failed, falling back to {}: {}",
+                    failed,
+                    models.primary(),
+                    message
+                );
+                warn(
+                    progress,
+                    warnings,
+                    fallback_warning(&failed, &message, models.primary()),
+                )
+                .await?;
+            }
+        }
+    };
+    tracing::info!("Cypher query generated: {}", query);
+    let model = models.primary();
+    let query = generation.prepare_reported(query, progress, warnings).await?;
+    let query = check_generated_query(writer, progress, generation, query, model, warnings).await?;
+    let query = lint_and_refine(writer, progress, generation, query, model, warnings).await?;
+    Ok(GenerationOutcome::Query(query))
+}
+
+/// Writes the request's candidate queries ([`LatencyMode::candidates`]) and returns the best one.
+/// A single candidate is returned as written. Otherwise a candidate that validates and the graph
+/// can plan beats one that does not, and ties go to the fewest lint hints; if no candidate is a
+/// query, the last `NoAnswer` is returned, or the error of the last call if every one failed.
+async fn select_best_candidate<W, P>(
+    writer: &mut W,
+    progress: &P,
+    generation: &Generation<'_>,
+    model: &str,
+) -> Result<Result<GenerationOutcome, W::Error>, StreamClosed>
+where
+    W: QueryWriter + ?Sized,
+    P: ProgressReporter + ?Sized,
+{
+    let candidates = generation.latency_mode.candidates();
+    if candidates <= 1 {
+        return Ok(writer.write(&generation.chat_request, model).await);
+    }
+    progress
+        .report(Progress::Status(format!(
+            "Generating {candidates} candidate queries ..."
+        )))
+        .await?;
+    let mut best: Option<((bool, bool, usize), String)> = None;
+    let mut no_answer = None;
+    let mut failure = None;
+    for _ in 0..candidates {
+        let query = match writer.write(&generation.chat_request, model).await {
+            Ok(GenerationOutcome::Query(query)) => query,
+            Ok(outcome) => {
+                no_answer = Some(outcome);
+                continue;
+            }
+            Err(e) => {
+                failure = Some(e);
+                continue;
+            }
+        };
+        let result = CypherValidator::validate_with_options(&query, &generation.validation);
+        let explained = result.is_valid && writer.explains(&query).await;
+        // Lower is better: failing EXPLAIN, then failing validation, then lint hints.
+        let score = (!explained, !result.is_valid, result.hints.len());
+        if best.as_ref().is_none_or(|(best_score, _)| score < *best_score) {
+            best = Some((score, query));
+        }
+        if score == (false, false, 0) {
+            break;
+        }
+    }
+    Ok(match (best, no_answer, failure) {
+        (Some((_, query)), _, _) => Ok(GenerationOutcome::Query(query)),
+        (None, Some(no_answer), _) => Ok(no_answer),
+        (None, None, Some(e)) => Err(e),
+        (None, None, None) => Ok(GenerationOutcome::NoAnswer {
+            reason: "No query was generated".to_string(),
+        }),
+    })
+}
+
+/// Validates a prepared query for [`generate_query`], correcting it once with the errors as
+/// feedback when the latency mode retries.
+async fn check_generated_query<W, P>(
+    writer: &mut W,
+    progress: &P,
+    generation: &Generation<'_>,
+    query: String,
+    model: &str,
+    warnings: &mut Vec<Warning>,
+) -> Result<String, GenerationError<W::Error>>
+where
+    W: QueryWriter + ?Sized,
+    P: ProgressReporter + ?Sized,
+{
+    let errors = match validate_written(writer, &query, &generation.validation) {
+        Ok(hints) => {
+            for hint in hints {
+                warn(progress, warnings, hint).await?;
+            }
+            return Ok(query);
+        }
+        Err(errors) => errors,
+    };
+
+    if generation.latency_mode.retries() {
+        progress
+            .report(Progress::Status(
+                "Query validation failed, attempting to regenerate...".to_string(),
+            ))
+            .await?;
+        let retry_request = validation_feedback_request(&generation.chat_request, &query, &errors);
+        match writer.write(&retry_request, model).await {
+            Ok(GenerationOutcome::Query(retry_query)) => {
+                let (retry_query, mut retry_warnings) = generation.prepare(retry_query);
+                if let Ok(hints) = validate_written(writer, &retry_query, &generation.validation) {
+                    tracing::info!("Retry query passed validation");
+                    retry_warnings.extend(hints);
+                    for warning in retry_warnings {
+                        warn(progress, warnings, warning).await?;
+                    }
+                    return Ok(retry_query);
+                }
+            }
+            Ok(GenerationOutcome::NoAnswer { reason }) => tracing::warn!("Regeneration wrote no query: {}", reason),
+            Err(e) => tracing::warn!("Regenerating the query failed: {}", e),
+        }
+    }
+
+    if generation.validation.strict {
+        tracing::warn!("Strict validation: refusing to execute a query that failed validation");
+        return Err(GenerationError::Rejected(format!(
+            "Strict validation rejected the query: {errors}"
+        )));
+    }
+    let warning = Warning::new(
+        WarningCode::ValidationFailed,
+        format!("Running a query that failed validation: {errors}"),
+    );
+    warn(progress, warnings, warning).await?;
+    Ok(query)
+}
+
+/// Lints a generated query and reports the hints as a `Lint` event. When the generation refines
+/// lint hints, the model is first asked to rewrite the query once; the rewrite replaces the query
+/// only if it passes validation and has fewer hints.
+async fn lint_and_refine<W, P>(
+    writer: &mut W,
+    progress: &P,
+    generation: &Generation<'_>,
+    query: String,
+    model: &str,
+    warnings: &mut Vec<Warning>,
+) -> Result<String, StreamClosed>
+where
+    W: QueryWriter + ?Sized,
+    P: ProgressReporter + ?Sized,
+{
+    let mut query = query;
+    let mut hints = CypherValidator::lint(&query);
+    if hints.is_empty() {
+        return Ok(query);
+    }
+
+    if generation.refine_lint_hints && generation.latency_mode.retries() {
+        progress
+            .report(Progress::Status(format!(
+                "Refining query to address {} lint hint(s) ...",
+                hints.len()
+            )))
+            .await?;
+        let refine_request = lint_feedback_request(&generation.chat_request, &query, &hints);
+        let refined = match writer.write(&refine_request, model).await {
+            Ok(GenerationOutcome::Query(refined)) => refined,
+            // No rewrite; the empty query fails validation below and the original is kept.
+            _ => String::new(),
+        };
+        let (refined, caps) = generation.prepare(refined);
+        let result = CypherValidator::validate_with_options(&refined, &generation.validation);
+        if result.is_valid && result.hints.len() < hints.len() {
+            tracing::info!(
+                "Lint refinement reduced hints from {} to {}",
+                hints.len(),
+                result.hints.len()
+            );
+            for cap in caps {
+                warn(progress, warnings, cap).await?;
+            }
+            query = refined;
+            hints = result.hints;
+        } else {
+            tracing::info!("Lint refinement did not improve the query; keeping the original");
+        }
+    }
+
+    if !hints.is_empty() {
+        tracing::info!("Query lint hints: {:?}", hints);
+        progress.report(Progress::Lint(hints)).await?;
+    }
+    Ok(query)
+}
+
+/// Regenerates `failed_query` from the `error` it failed with.
+///
+/// The request names the labels, relationship types and properties the schema does not have. The
+/// new query is prepared and validated as [`generate_query`] does; warnings are reported and added
+/// to `warnings`.
+///
+/// # Errors
+///
+/// Returns [`StepError::Failed`] when no query was written, [`StepError::Rejected`] when the new
+/// query fails validation, or [`StepError::Stopped`] once nobody receives the reports.
+pub async fn heal_query<W, P>(
+    writer: &mut W,
+    progress: &P,
+    generation: &Generation<'_>,
+    model: &str,
+    failed_query: &str,
+    error: &str,
+    warnings: &mut Vec<Warning>,
+) -> Result<String, StepError>
+where
+    W: QueryWriter + ?Sized,
+    P: ProgressReporter + ?Sized,
+{
+    tracing::info!("Attempting to self-heal failed query: {}", failed_query);
+    let schema_issues = CypherValidator::validate_with_options(failed_query, &generation.validation).schema_issues;
+    let retry_request = heal_chat_request(generation.chat_request.clone(), failed_query, error, &schema_issues);
+    let query = match writer.write(&retry_request, model).await {
+        Ok(GenerationOutcome::Query(query)) => query,
+        Ok(GenerationOutcome::NoAnswer { reason }) => {
+            tracing::warn!("Self-healing wrote no query: {}", reason);
+            return Err(StepError::Failed(error.to_string()));
+        }
+        Err(e) => return Err(StepError::Failed(e.to_string())),
+    };
+    tracing::info!("Self-healed query generated: {}", query);
+    let query = generation.prepare_reported(query, progress, warnings).await?;
+    match validate_written(writer, &query, &generation.validation) {
+        Ok(hints) => {
+            for hint in hints {
+                warn(progress, warnings, hint).await?;
+            }
+            Ok(query)
+        }
+        Err(errors) => Err(StepError::Rejected {
+            query,
+            message: format!("Query validation errors: {errors}"),
+        }),
+    }
+}
+
+/// Writes the answer of one request, for [`write_answer`].
+#[async_trait]
+pub trait AnswerWriter: Send {
+    /// Why no answer was written.
+    type Error: Send;
+
+    /// The answer and confidence written earlier for the same question and result, if answers are
+    /// kept. None are by default.
+    async fn reuse(&mut self) -> Option<(String, Option<u8>)> {
+        None
+    }
+
+    /// Writes the answer and its confidence with `model`, reporting it as it is written.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of a failed model call.
+    async fn write(
+        &mut self,
+        model: &str,
+        token_usage: &mut TokenUsage,
+    ) -> Result<(String, Option<u8>), Self::Error>;
+
+    /// Keeps a new answer the result supports, for [`Self::reuse`]. Nothing is kept by default.
+    async fn keep(
+        &mut self,
+        _answer: &str,
+        _confidence: Option<u8>,
+    ) {
+    }
+}
+
+/// What an answer is written from, for [`write_answer`].
+#[derive(Debug, Clone, Copy)]
+pub struct AnswerRequest<'a> {
+    /// The question being answered.
+    pub question: &'a str,
+    pub schema: &'a str,
+    /// The query that ran, and its formatted result.
+    pub query: &'a str,
+    pub result: &'a str,
+    /// Suggest follow-up questions after the answer.
+    pub followups: bool,
+    /// Whether the answer is checked against the result.
+    pub latency_mode: LatencyMode,
+    /// Rewrite the answer once it is written.
+    pub response_hooks: &'a ResponseHooks,
+}
+
+/// An answer from [`write_answer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answer {
+    /// The answer after the response hooks; it was reported as written.
+    pub text: String,
+    pub confidence: Option<u8>,
+    /// Suggested follow-up questions, when asked for and the suggestion call succeeded.
+    pub followups: Option<Vec<String>>,
+}
+
+/// Answers from the query result.
+///
+/// An answer `writer` kept for the same question and result is reused; otherwise it writes one.
+/// Follow-up questions are then suggested when asked for, and a new answer is
+/// checked against the result when the latency mode asks; an unsupported answer gets an
+/// `unsupported_answer` warning, reported and added to `warnings`, and is not kept. Both extra calls
+/// are conveniences: their failures are only logged.
+///
+/// # Errors
+///
+/// Returns [`StreamClosed`] once nobody receives the reports; the inner error is the writer's.
+#[allow(clippy::too_many_arguments)]
+pub async fn write_answer<A, P>(
+    writer: &mut A,
+    progress: &P,
+    request: &AnswerRequest<'_>,
+    client: &genai::Client,
+    model: &str,
+    token_usage: &mut TokenUsage,
+    warnings: &mut Vec<Warning>,
+) -> Result<Result<Answer, A::Error>, StreamClosed>
+where
+    A: AnswerWriter + ?Sized,
+    P: ProgressReporter + ?Sized,
+{
+    let reused = writer.reuse().await;
+    let fresh = reused.is_none();
+    let (text, confidence) = if let Some((text, confidence)) = reused {
+        // The same question got the same result: the answer written for it still holds
+        progress
+            .report(Progress::Status(
+                "Query result unchanged; reusing the previous answer".to_string(),
+            ))
+            .await?;
+        progress.report(Progress::ModelOutputChunk(text.clone())).await?;
+        (text, confidence)
+    } else {
+        progress
+            .report(Progress::Status(
+                "Generating answer from chat history and Cypher output using AI model...".to_string(),
+            ))
+            .await?;
+        match writer.write(model, token_usage).await {
+            Ok(written) => written,
+            Err(e) => return Ok(Err(e)),
+        }
+    };
+
+    let followups = if request.followups {
+        progress
+            .report(Progress::Status("Suggesting follow-up questions...".to_string()))
+            .await?;
+        generate_followup_questions(
+            request.question,
+            request.schema,
+            request.query,
+            request.result,
+            &text,
+            client,
+            model,
+            token_usage,
+        )
+        .await
+        .inspect_err(|e| tracing::warn!("Failed to generate follow-up questions: {}", e))
+        .ok()
+    } else {
+        None
+    };
+
+    // Reused answers were checked when they were written; unsupported answers are never kept
+    let mut supported = true;
+    if fresh && request.latency_mode.checks_faithfulness() {
+        progress
+            .report(Progress::Status(
+                "Checking the answer against the query result...".to_string(),
+            ))
+            .await?;
+        match check_answer_faithfulness(
+            request.question,
+            request.query,
+            request.result,
+            &text,
+            client,
+            model,
+            token_usage,
+        )
+        .await
+        {
+            Ok(faithfulness) => {
+                if let Some(warning) = faithfulness.warning() {
+                    supported = false;
+                    warn(
+                        progress,
+                        warnings,
+                        Warning::new(WarningCode::UnsupportedAnswer, warning),
+                    )
+                    .await?;
+                }
+            }
+            Err(e) => tracing::warn!("Failed to check answer faithfulness: {}", e),
+        }
+    }
+    if fresh && supported {
+        writer.keep(&text, confidence).await;
+    }
+
+    Ok(Ok(Answer {
+        text: request.response_hooks.process_answer(text),
+        confidence,
+        followups,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Reports(Mutex<Vec<Progress>>);
+
+    #[async_trait]
+    impl ProgressReporter for Reports {
+        async fn report(
+            &self,
+            progress: Progress,
+        ) -> Result<(), StreamClosed> {
+            self.0.lock().unwrap().push(progress);
+            Ok(())
+        }
+    }
+
+    /// Fails every query but `good`; heals to the next of `fixes`.
+    struct Runner {
+        good: &'static str,
+        fixes: Vec<Result<String, StepError>>,
+    }
+
+    #[async_trait]
+    impl QueryRunner for Runner {
+        async fn execute(
+            &mut self,
+            query: &str,
+        ) -> Result<String, StepError> {
+            if query == self.good {
+                Ok("[1]".to_string())
+            } else {
+                Err(StepError::Failed(format!("Unknown function in {query}")))
+            }
+        }
+
+        async fn heal(
+            &mut self,
+            _failed_query: &str,
+            _error: &str,
+        ) -> Result<String, StepError> {
+            self.fixes.remove(0)
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_queries_are_healed_a_bounded_number_of_times() {
+        let reports = Reports::default();
+        let mut runner = Runner {
+            good: "RETURN 1",
+            fixes: vec![
+                Err(StepError::Rejected {
+                    query: "RETURN x".to_string(),
+                    message: "Variable `x` not defined".to_string(),
+                }),
+                Ok("RETURN 1".to_string()),
+            ],
+        };
+        let executed = execute_with_healing(&mut runner, &reports, "RETURN foo()".to_string(), 2)
+            .await
+            .unwrap();
+        assert_eq!(executed.query, "RETURN 1");
+        assert_eq!(executed.heal_attempts, 2);
+        let reports = reports.0.into_inner().unwrap();
+        assert!(reports.contains(&Progress::CypherQuery("Fixed: RETURN 1".to_string())));
+        assert_eq!(
+            reports.last(),
+            Some(&Progress::Status("Self-healing successful".to_string()))
+        );
+
+        let mut runner = Runner {
+            good: "RETURN 1",
+            fixes: vec![Ok("RETURN bar()".to_string())],
+        };
+        let failed = execute_with_healing(&mut runner, &Reports::default(), "RETURN foo()".to_string(), 1).await;
+        assert_eq!(
+            failed,
+            Err(StepError::Failed(
+                "Query execution failed even after 1 self-healing attempt(s): Unknown function in RETURN bar()"
+                    .to_string()
+            ))
+        );
+        let failed = execute_with_healing(&mut runner, &Reports::default(), "RETURN foo()".to_string(), 0).await;
+        assert_eq!(
+            failed,
+            Err(StepError::Failed(
+                "Query execution failed: Unknown function in RETURN foo()".to_string()
+            ))
+        );
+    }
+    /// Writes the next of `replies` for each call, recording the model it was asked.
+    struct Writer {
+        replies: Vec<Result<&'static str, &'static str>>,
+        models: Vec<String>,
+    }
+
+    #[async_trait]
+    impl QueryWriter for Writer {
+        type Error = String;
+
+        async fn write(
+            &mut self,
+            _chat_request: &ChatRequest,
+            model: &str,
+        ) -> Result<GenerationOutcome, String> {
+            self.models.push(model.to_string());
+            self.replies
+                .remove(0)
+                .map(|query| GenerationOutcome::Query(query.to_string()))
+                .map_err(ToString::to_string)
+        }
+
+        async fn explains(
+            &mut self,
+            _query: &str,
+        ) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn generated_queries_fall_back_and_are_corrected_once() {
+        let hooks = ResponseHooks::default();
+        let generation = Generation {
+            chat_request: ChatRequest::default(),
+            validation: ValidationOptions::strict(true),
+            response_hooks: &hooks,
+            latency_mode: LatencyMode::Balanced,
+            refine_lint_hints: false,
+        };
+        let mut writer = Writer {
+            replies: vec![
+                Err("rate limited"),
+                Ok("MATCH (n:Person) RETURN n"),
+                Ok("MATCH (n:Person) RETURN n LIMIT 5"),
+            ],
+            models: Vec::new(),
+        };
+        let mut models = ModelChain::new("a").with_fallback("b");
+        let mut warnings = Vec::new();
+        let generated = generate_query(
+            &mut writer,
+            &Reports::default(),
+            &generation,
+            &mut models,
+            &mut warnings,
+        )
+        .await;
+        assert_eq!(
+            generated,
+            Ok(GenerationOutcome::Query(
+                "MATCH (n:Person) RETURN n LIMIT 5".to_string()
+            ))
+        );
+        assert_eq!(writer.models, ["a", "b", "b"]);
+        assert_eq!(models.primary(), "b");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, WarningCode::ModelFallback);
+
+        let mut writer = Writer {
+            replies: vec![Ok("MATCH (n:Person) RETURN n"), Ok("MATCH (n:Person) RETURN n.name")],
+            models: Vec::new(),
+        };
+        let generated = generate_query(
+            &mut writer,
+            &Reports::default(),
+            &generation,
+            &mut models,
+            &mut warnings,
+        )
+        .await;
+        assert!(
+            matches!(&generated, Err(GenerationError::Rejected(message)) if message.contains("LIMIT")),
+            "{generated:?}"
+        );
+    }
+
+    /// Refuses requests to `full`; records the tokens counted per graph.
+    #[derive(Debug, Default)]
+    struct Quotas {
+        full: &'static str,
+        tokens: Mutex<Vec<(String, u64)>>,
+    }
+
+    #[async_trait]
+    impl Admission for Quotas {
+        async fn refusal(
+            &self,
+            graph_name: &str,
+        ) -> Option<String> {
+            (graph_name == self.full).then(|| format!("{graph_name} is over its quota"))
+        }
+
+        async fn admit(
+            &self,
+            graph_name: &str,
+        ) -> Result<(), String> {
+            self.refusal(graph_name).await.map_or(Ok(()), Err)
+        }
+
+        fn record_tokens(
+            &self,
+            graph_name: &str,
+            tokens: u64,
+        ) {
+            self.tokens.lock().unwrap().push((graph_name.to_string(), tokens));
+        }
+    }
+
+    #[tokio::test]
+    async fn refused_graphs_are_not_routed_to_and_count_routing_tokens() {
+        let quotas = Quotas {
+            full: "sales",
+            ..Quotas::default()
+        };
+        let candidates = vec![GraphCandidate::new("sales"), GraphCandidate::new("hr")];
+        let admissible = admissible_candidates(&quotas, candidates).await.unwrap();
+        assert_eq!(admissible.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["hr"]);
+        assert_eq!(
+            admissible_candidates(&quotas, vec![GraphCandidate::new("sales")])
+                .await
+                .map(|c| c.len()),
+            Err("sales is over its quota".to_string())
+        );
+
+        let routing = TokenUsage {
+            total_tokens: 40,
+            ..TokenUsage::new()
+        };
+        assert_eq!(admit(&quotas, "hr", &routing).await, Ok(()));
+        assert!(quotas.tokens.lock().unwrap().is_empty());
+        assert!(admit(&quotas, "sales", &routing).await.is_err());
+        assert_eq!(*quotas.tokens.lock().unwrap(), [("sales".to_string(), 40)]);
+    }
+
+    #[test]
+    fn policy_violations_name_the_check() {
+        let validation = ValidationOptions::default().with_aggregate_only(true);
+        let violation = policy_violation("MATCH (p:Person) RETURN p.name", &validation).unwrap();
+        assert!(
+            violation.starts_with("Aggregate-only mode rejected the query"),
+            "{violation}"
+        );
+        assert_eq!(
+            policy_violation("MATCH (p:Person) RETURN p.name", &ValidationOptions::default()),
+            None
+        );
+    }
+}
@@ -19,6 +19,70 @@ pub struct ErrorResponse {
     pub status_code: u16,
 }
 
+/// Why a text-to-cypher request failed, by the step that failed.
+///
+/// Each variant carries the error message; `Display` shows the message alone, as the `error` of a
+/// [`TextToCypherResponse`](crate::processor::TextToCypherResponse) does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextToCypherError {
+    /// The graphs could not be listed or the schema discovered, or the graph does not exist.
+    SchemaDiscovery(String),
+    /// The model call writing the query failed, or it wrote no query.
+    Generation(String),
+    /// The generated query was rejected by validation, strict validation or the procedure allowlist.
+    Validation(String),
+    /// The query failed on `FalkorDB`, including after self-healing.
+    Execution(String),
+    /// The model call writing the answer (or follow-ups, or the faithfulness check) failed.
+    Answer(String),
+    /// The request cannot be served as configured, e.g. no model, an unresolvable provider or a
+    /// graph whose admission refused it.
+    Config(String),
+}
+
+impl TextToCypherError {
+    /// The error message.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        match self {
+            Self::SchemaDiscovery(message)
+            | Self::Generation(message)
+            | Self::Validation(message)
+            | Self::Execution(message)
+            | Self::Answer(message)
+            | Self::Config(message) => message,
+        }
+    }
+
+    /// The same error with `context` before its message, as in `Query execution failed: <message>`.
+    #[must_use]
+    pub fn context(
+        self,
+        context: &str,
+    ) -> Self {
+        let wrap = |message: String| format!("{context}: {message}");
+        match self {
+            Self::SchemaDiscovery(message) => Self::SchemaDiscovery(wrap(message)),
+            Self::Generation(message) => Self::Generation(wrap(message)),
+            Self::Validation(message) => Self::Validation(wrap(message)),
+            Self::Execution(message) => Self::Execution(wrap(message)),
+            Self::Answer(message) => Self::Answer(wrap(message)),
+            Self::Config(message) => Self::Config(wrap(message)),
+        }
+    }
+}
+
+impl fmt::Display for TextToCypherError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for TextToCypherError {}
+
 #[derive(Debug)]
 pub enum ApiError {
     GenAiError(genai::Error),
@@ -323,6 +387,17 @@ impl ApiError {
 mod tests {
     use super::*;
 
+    #[test]
+    fn text_to_cypher_error_displays_its_message_with_context() {
+        let error =
+            TextToCypherError::Execution("Unknown function 'foo'".to_string()).context("Query execution failed");
+        assert_eq!(
+            error,
+            TextToCypherError::Execution("Query execution failed: Unknown function 'foo'".to_string())
+        );
+        assert_eq!(error.to_string(), "Query execution failed: Unknown function 'foo'");
+    }
+
     #[test]
     fn test_is_ollama_error() {
         #[cfg(feature = "server")]
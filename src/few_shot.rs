@@ -0,0 +1,271 @@
+//! Curated (question, Cypher) examples that show the model how a graph is queried.
+//!
+//! Examples are kept per graph. A [`FewShotStore`] reads them from a directory of `<graph>.json`
+//! files, each an array of `{"question": ..., "cypher": ...}` objects, and from the shared
+//! [`Storage`] (the `FalkorDB`/Redis backend keeps them in a key), where they can be replaced at
+//! runtime. Before a query is generated, [`select`] picks the examples whose questions share the
+//! most words with the question asked, and [`render`] turns them into the instructions sent to the
+//! model ahead of it.
+
+use crate::storage::{self, Storage, StorageError};
+use crate::template::TemplateEngine;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Storage namespace holding the examples of each graph.
+pub const FEW_SHOT_NAMESPACE: &str = "few_shot_examples";
+
+/// Examples sent with a question by default.
+pub const DEFAULT_TOP_K: usize = 3;
+
+/// A question and the query that answers it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct FewShotExample {
+    pub question: String,
+    pub cypher: String,
+}
+
+/// The lowercase words of `text`, without one-letter ones.
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The `top_k` examples whose questions are most similar to `question` (Jaccard index of their
+/// words), most similar first. Examples sharing no word with the question are left out.
+#[must_use]
+pub fn select<'a>(
+    examples: &'a [FewShotExample],
+    question: &str,
+    top_k: usize,
+) -> Vec<&'a FewShotExample> {
+    let asked = words(question);
+    let mut scored: Vec<(f64, &FewShotExample)> = examples
+        .iter()
+        .filter_map(|example| {
+            let words = words(&example.question);
+            let shared = asked.intersection(&words).count();
+            #[allow(clippy::cast_precision_loss)]
+            let score = shared as f64 / asked.union(&words).count().max(1) as f64;
+            (shared > 0).then_some((score, example))
+        })
+        .collect();
+    // Stable, so equally similar examples keep their curated order
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().take(top_k).map(|(_, example)| example).collect()
+}
+
+/// The instructions that show `examples` to the model, or `None` when there are none.
+#[must_use]
+pub fn render(examples: &[&FewShotExample]) -> Option<String> {
+    if examples.is_empty() {
+        return None;
+    }
+    let examples: Vec<String> = examples
+        .iter()
+        .map(|example| {
+            format!(
+                "Question: {}\nCypher: {}",
+                example.question.trim(),
+                example.cypher.trim()
+            )
+        })
+        .collect();
+    Some(TemplateEngine::render_few_shot_prompt(&examples.join("\n\n")))
+}
+
+/// The examples of each graph: curated files, then the ones kept in storage.
+#[derive(Debug, Clone)]
+pub struct FewShotStore {
+    storage: Arc<dyn Storage>,
+    curated: Arc<BTreeMap<String, Vec<FewShotExample>>>,
+}
+
+impl FewShotStore {
+    /// Creates a store backed by `storage`, with no curated files.
+    #[must_use]
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            curated: Arc::default(),
+        }
+    }
+
+    /// The store with the curated examples of `directory`: one `<graph>.json` file per graph.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the file if the directory cannot be read or a file is not an array
+    /// of examples.
+    pub fn with_directory(
+        mut self,
+        directory: &Path,
+    ) -> Result<Self, String> {
+        let mut curated = BTreeMap::new();
+        let entries = std::fs::read_dir(directory).map_err(|e| format!("{}: {e}", directory.display()))?;
+        for entry in entries {
+            let path = entry.map_err(|e| format!("{}: {e}", directory.display()))?.path();
+            let Some(graph_name) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .filter(|_| path.extension().is_some_and(|extension| extension == "json"))
+            else {
+                continue;
+            };
+            let examples: Vec<FewShotExample> = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|raw| serde_json::from_str(&raw).map_err(|e| e.to_string()))
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+            curated.insert(graph_name.to_string(), examples);
+        }
+        self.curated = Arc::new(curated);
+        Ok(self)
+    }
+
+    /// Examples read from the curated files.
+    #[must_use]
+    pub fn curated_len(&self) -> usize {
+        self.curated.values().map(Vec::len).sum()
+    }
+
+    /// The examples of `graph_name`: its curated file's, then the stored ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the stored examples cannot be read.
+    pub async fn examples(
+        &self,
+        graph_name: &str,
+    ) -> Result<Vec<FewShotExample>, StorageError> {
+        let mut examples = self.curated.get(graph_name).cloned().unwrap_or_default();
+        examples.extend(self.stored(graph_name).await?);
+        Ok(examples)
+    }
+
+    /// The examples of `graph_name` kept in storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if they cannot be read.
+    pub async fn stored(
+        &self,
+        graph_name: &str,
+    ) -> Result<Vec<FewShotExample>, StorageError> {
+        Ok(storage::get_json(self.storage.as_ref(), FEW_SHOT_NAMESPACE, graph_name)
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Replaces the stored examples of `graph_name`; the curated ones are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the examples cannot be written.
+    pub async fn set(
+        &self,
+        graph_name: &str,
+        examples: &[FewShotExample],
+    ) -> Result<(), StorageError> {
+        storage::put_json(self.storage.as_ref(), FEW_SHOT_NAMESPACE, graph_name, &examples, None).await
+    }
+
+    /// Removes the stored examples of `graph_name`. Returns whether there were any.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if they cannot be removed.
+    pub async fn remove(
+        &self,
+        graph_name: &str,
+    ) -> Result<bool, StorageError> {
+        self.storage.delete(FEW_SHOT_NAMESPACE, graph_name).await
+    }
+
+    /// The `top_k` examples of `graph_name` most similar to `question`, rendered for the model, or
+    /// `None` when none is similar. A storage failure is logged and the curated examples are used.
+    pub async fn prompt_for(
+        &self,
+        graph_name: &str,
+        question: &str,
+        top_k: usize,
+    ) -> Option<String> {
+        let examples = self.examples(graph_name).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to read the stored examples of graph {graph_name}: {e}");
+            self.curated.get(graph_name).cloned().unwrap_or_default()
+        });
+        render(&select(&examples, question, top_k))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+
+    fn example(
+        question: &str,
+        cypher: &str,
+    ) -> FewShotExample {
+        FewShotExample {
+            question: question.to_string(),
+            cypher: cypher.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn the_most_similar_examples_are_sent_with_the_question() {
+        let directory = std::env::temp_dir().join(format!("few-shot-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join("movies.json"),
+            r#"[{"question": "Which actors played in The Matrix?",
+                 "cypher": "MATCH (a:Actor)-[:ACTED_IN]->(:Movie {title: 'The Matrix'}) RETURN a.name"}]"#,
+        )
+        .unwrap();
+        std::fs::write(directory.join("notes.txt"), "ignored").unwrap();
+        let store = FewShotStore::new(Arc::new(InMemoryStorage::new()))
+            .with_directory(&directory)
+            .unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(store.curated_len(), 1);
+
+        store
+            .set(
+                "movies",
+                &[
+                    example(
+                        "How many movies came out in 1999?",
+                        "MATCH (m:Movie {released: 1999}) RETURN count(m)",
+                    ),
+                    example(
+                        "Who directed Heat?",
+                        "MATCH (d:Person)-[:DIRECTED]->(:Movie {title: 'Heat'}) RETURN d.name",
+                    ),
+                ],
+            )
+            .await
+            .unwrap();
+        let examples = store.examples("movies").await.unwrap();
+        assert_eq!(examples.len(), 3);
+
+        let selected = select(&examples, "Which actors played in Heat?", 2);
+        assert_eq!(selected[0].question, "Which actors played in The Matrix?");
+        assert_eq!(selected[1].question, "Who directed Heat?");
+        assert!(select(&examples, "zzz", 3).is_empty());
+
+        let prompt = store.prompt_for("movies", "Which actors played in Heat?", 1).await.unwrap();
+        assert!(prompt.contains("Question: Which actors played in The Matrix?\nCypher: MATCH (a:Actor)"));
+        assert!(!prompt.contains("DIRECTED"));
+        assert!(store.prompt_for("people", "Which actors played in Heat?", 3).await.is_none());
+
+        assert!(store.remove("movies").await.unwrap());
+        assert_eq!(store.examples("movies").await.unwrap().len(), 1);
+    }
+}
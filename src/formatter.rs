@@ -12,38 +12,13 @@
 //! - Single value: `"John Doe"`
 //! - Single record: `[(:Person {name: "John"}), 25, "Engineer"]`
 //! - Multiple records: `1. (:Person {name: "John"})\n2. (:Person {name: "Jane"})`
+//!
+//! The rendering itself lives in [`crate::query_result`], which does not depend on the `FalkorDB`
+//! client types; the helpers here apply it to rows returned by the client.
 
-use falkordb::{
-    FalkorAsyncClient, FalkorClientBuilder, FalkorConnectionInfo, FalkorResult, FalkorValue, RetryPolicy, RowStream,
-};
-use std::fmt::Write;
-
-/// Builds an asynchronous `FalkorDB` client with the read-only retry policy applied.
-///
-/// Centralizing client construction here ensures every connection retries only idempotent
-/// read operations (queries issued via `ro_query` and schema discovery) on transient failures,
-/// using exponential backoff. Writes are never retried, so a failed write is surfaced
-/// immediately and can never be duplicated.
-///
-/// Kept `pub(crate)` so `FalkorDB` types are not exposed in this crate's public API. It lives in
-/// this module (compiled into both the library and the binary) so the binary can share it without
-/// a public re-export.
-///
-/// # Errors
-///
-/// Returns an error if the client cannot be built (for example, when the connection fails).
-// `pub(crate)` keeps FalkorDB types out of the public API in the library (this is a `pub mod`); it
-// looks redundant only in the binary, where `formatter` is a private `mod`.
-#[allow(clippy::redundant_pub_crate)]
-pub(crate) async fn build_falkordb_async_client(
-    connection_info: FalkorConnectionInfo
-) -> FalkorResult<FalkorAsyncClient> {
-    FalkorClientBuilder::new_async()
-        .with_connection_info(connection_info)
-        .with_retry_policy(RetryPolicy::read_only())
-        .build()
-        .await
-}
+use crate::query_result::{QueryResult, ResultFormat, ResultValue, compact_row};
+use falkordb::{FalkorValue, RowStream};
+use std::fmt::Write as _;
 
 /// Bridges a query result's rows back to the pre-0.7 `Vec<FalkorValue>` shape.
 ///
@@ -59,257 +34,115 @@ pub fn rows_lossy(data: RowStream) -> Vec<Vec<FalkorValue>> {
 }
 
 /// Formats query results in a compact, LLM-friendly format
+///
+/// Shorthand for rendering [`QueryResult::from_falkor_rows`] as [`ResultFormat::Compact`].
+#[must_use]
+#[allow(dead_code)] // The server formats rows as they are read, with `format_rows_within`
 pub fn format_query_records(records: &[Vec<FalkorValue>]) -> String {
-    if records.is_empty() {
-        return "No results returned.".to_string();
-    }
-
-    if records.len() == 1 {
-        // Single record: compact inline format
-        let record = &records[0];
-        if record.len() == 1 {
-            // Single field: just return the value
-            format_falkor_value(&record[0])
-        } else {
-            // Multiple fields: array format
-            let values: Vec<String> = record.iter().map(format_falkor_value).collect();
-            format!("[{}]", values.join(", "))
-        }
-    } else {
-        // Multiple records: numbered list format
-        let mut res = String::new();
+    QueryResult::from_falkor_rows(records).render(ResultFormat::Compact)
+}
 
-        for (idx, record) in records.iter().enumerate() {
-            write!(res, "{}. ", idx + 1).unwrap();
+/// Formatted result size, in bytes, past which a query fails rather than being held in memory,
+/// unless configured otherwise.
+pub const DEFAULT_MAX_RESULT_BYTES: usize = 16 * 1024 * 1024;
 
-            if record.len() == 1 {
-                // Single field: just the value
-                writeln!(res, "{}", format_falkor_value(&record[0])).unwrap();
-            } else {
-                // Multiple fields: array format
-                let values: Vec<String> = record.iter().map(format_falkor_value).collect();
-                writeln!(res, "[{}]", values.join(", ")).unwrap();
-            }
-        }
+/// A query result that grew past its in-memory limit while it was formatted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResultTooLarge {
+    pub max_bytes: usize,
+    /// The rows read when the limit was crossed.
+    pub rows: usize,
+}
 
-        res.trim_end().to_string()
+impl std::fmt::Display for ResultTooLarge {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(
+            f,
+            "The query result exceeds {} bytes after {} rows; add a LIMIT or return fewer values",
+            self.max_bytes, self.rows
+        )
     }
 }
 
-/// Formats a single `FalkorDB` value in a readable, compact format
-fn format_falkor_value(value: &FalkorValue) -> String {
-    match value {
-        FalkorValue::Bool(b) => b.to_string(),
-        FalkorValue::I64(i) => i.to_string(),
-        FalkorValue::F64(f) => f.to_string(),
-        FalkorValue::String(s) => format!("\"{s}\""),
-        FalkorValue::Node(node) => {
-            let labels = if node.labels.is_empty() {
-                String::new()
-            } else {
-                format!(":{}", node.labels.join(":"))
-            };
-
-            let props = if node.properties.is_empty() {
-                String::new()
-            } else {
-                let prop_strings: Vec<String> = node
-                    .properties
-                    .iter()
-                    .map(|(k, v)| format!("{}: {}", k, format_falkor_value(v)))
-                    .collect();
-                format!(" {{{}}}", prop_strings.join(", "))
-            };
-
-            format!("({labels}{props})")
-        }
-        FalkorValue::Edge(edge) => {
-            let props = if edge.properties.is_empty() {
-                String::new()
-            } else {
-                let prop_strings: Vec<String> = edge
-                    .properties
-                    .iter()
-                    .map(|(k, v)| format!("{}: {}", k, format_falkor_value(v)))
-                    .collect();
-                format!(" {{{}}}", prop_strings.join(", "))
-            };
+impl std::error::Error for ResultTooLarge {}
 
-            format!("-[:{}{props}]-", edge.relationship_type)
-        }
-        FalkorValue::Path(path) => {
-            let mut path_str = String::new();
-            for (i, node) in path.nodes.iter().enumerate() {
-                if i > 0
-                    && let Some(edge) = path.relationships.get(i - 1)
-                {
-                    path_str.push_str(&format_falkor_value(&FalkorValue::Edge(edge.clone())));
-                }
-                path_str.push_str(&format_falkor_value(&FalkorValue::Node(node.clone())));
+/// Formats rows as [`format_query_records`] does, one at a time as they are read, so a large
+/// result is never held both as values and as text.
+///
+/// # Errors
+///
+/// Returns [`ResultTooLarge`] as soon as the text exceeds `max_bytes`.
+pub fn format_rows_within(
+    rows: impl IntoIterator<Item = Vec<FalkorValue>>,
+    max_bytes: Option<usize>,
+) -> Result<String, ResultTooLarge> {
+    let max_bytes = max_bytes.unwrap_or(usize::MAX);
+    // A lone row is written without its number, so the first one waits for a second
+    let mut first: Option<String> = None;
+    let mut text = String::new();
+    let mut count = 0;
+    for row in rows {
+        count += 1;
+        let values: Vec<ResultValue> = row.iter().map(ResultValue::from).collect();
+        let rendered = compact_row(&values);
+        if count == 1 {
+            first = Some(rendered);
+        } else {
+            if let Some(first) = first.take() {
+                let _ = write!(text, "1. {first}");
             }
-            path_str
+            let _ = write!(text, "\n{count}. {rendered}");
         }
-        FalkorValue::Array(arr) => {
-            let elements: Vec<String> = arr.iter().map(format_falkor_value).collect();
-            format!("[{}]", elements.join(", "))
-        }
-        _ => {
-            // For all other types (strings, maps, etc.), use the debug representation
-            // but clean it up for better readability
-            let debug_str = format!("{value:?}");
-
-            // If it's a string-like value, try to extract just the content
-            if debug_str.starts_with("SimpleString(") && debug_str.ends_with(')') {
-                let content = &debug_str[13..debug_str.len() - 1];
-                format!("\"{}\"", content.trim_matches('"'))
-            } else if debug_str.starts_with("BulkString(") && debug_str.ends_with(')') {
-                let content = &debug_str[11..debug_str.len() - 1];
-                format!("\"{}\"", content.trim_matches('"'))
-            } else {
-                debug_str
-            }
+        if first.as_ref().map_or(text.len(), String::len) > max_bytes {
+            return Err(ResultTooLarge { max_bytes, rows: count });
         }
     }
+    Ok(match first {
+        Some(first) => first,
+        None if count == 0 => "No results returned.".to_string(),
+        None => text,
+    })
 }
 
 /// Formats a query result as JSON for programmatic consumption
+///
+/// Shorthand for rendering [`QueryResult::from_falkor_rows`] as [`ResultFormat::Json`].
 #[must_use]
 pub fn format_as_json(records: &[Vec<FalkorValue>]) -> String {
-    if records.is_empty() {
-        return "[]".to_string();
-    }
-
-    let mut result = String::from("[");
-    for (i, record) in records.iter().enumerate() {
-        if i > 0 {
-            result.push(',');
-        }
-
-        result.push('[');
-        for (j, value) in record.iter().enumerate() {
-            if j > 0 {
-                result.push(',');
-            }
-            result.push_str(&falkor_value_to_json(value));
-        }
-        result.push(']');
-    }
-    result.push(']');
-
-    result
-}
-
-/// Converts a `FalkorValue` to its JSON representation
-fn falkor_value_to_json(value: &FalkorValue) -> String {
-    match value {
-        FalkorValue::Bool(b) => b.to_string(),
-        FalkorValue::I64(i) => i.to_string(),
-        FalkorValue::F64(f) => f.to_string(),
-        FalkorValue::String(s) => format!("\"{}\"", escape_json_string(s)),
-        FalkorValue::Node(node) => {
-            let mut json = String::from("{\"type\":\"node\",\"id\":");
-            json.push_str(&node.entity_id.to_string());
-
-            json.push_str(",\"labels\":[");
-            for (i, label) in node.labels.iter().enumerate() {
-                if i > 0 {
-                    json.push(',');
-                }
-                write!(json, "\"{}\"", escape_json_string(label)).unwrap();
-            }
-            json.push_str("],\"properties\":{");
-
-            for (i, (k, v)) in node.properties.iter().enumerate() {
-                if i > 0 {
-                    json.push(',');
-                }
-                write!(json, "\"{}\":{}", escape_json_string(k), falkor_value_to_json(v)).unwrap();
-            }
-            json.push_str("}}");
-            json
-        }
-        FalkorValue::Edge(edge) => {
-            let mut json = String::from("{\"type\":\"edge\",\"id\":");
-            json.push_str(&edge.entity_id.to_string());
-            json.push_str(",\"relationship_type\":\"");
-            json.push_str(&escape_json_string(&edge.relationship_type));
-            json.push_str("\",\"src_node_id\":");
-            json.push_str(&edge.src_node_id.to_string());
-            json.push_str(",\"dst_node_id\":");
-            json.push_str(&edge.dst_node_id.to_string());
-            json.push_str(",\"properties\":{");
-
-            for (i, (k, v)) in edge.properties.iter().enumerate() {
-                if i > 0 {
-                    json.push(',');
-                }
-                write!(json, "\"{}\":{}", escape_json_string(k), falkor_value_to_json(v)).unwrap();
-            }
-            json.push_str("}}");
-            json
-        }
-        FalkorValue::Path(path) => {
-            let mut json = String::from("{\"type\":\"path\",\"nodes\":[");
-            for (i, node) in path.nodes.iter().enumerate() {
-                if i > 0 {
-                    json.push(',');
-                }
-                json.push_str(&falkor_value_to_json(&FalkorValue::Node(node.clone())));
-            }
-            json.push_str("],\"relationships\":[");
-            for (i, edge) in path.relationships.iter().enumerate() {
-                if i > 0 {
-                    json.push(',');
-                }
-                json.push_str(&falkor_value_to_json(&FalkorValue::Edge(edge.clone())));
-            }
-            json.push_str("]}");
-            json
-        }
-        FalkorValue::Array(arr) => {
-            let mut json = String::from("[");
-            for (i, item) in arr.iter().enumerate() {
-                if i > 0 {
-                    json.push(',');
-                }
-                json.push_str(&falkor_value_to_json(item));
-            }
-            json.push(']');
-            json
-        }
-        _ => {
-            // For other types, serialize as string representation
-            let debug_str = format!("{value:?}");
-            format!("\"{}\"", escape_json_string(&debug_str))
-        }
-    }
-}
-
-/// Escapes a string for JSON format
-fn escape_json_string(s: &str) -> String {
-    s.chars()
-        .map(|c| match c {
-            '"' => "\\\"".to_string(),
-            '\\' => "\\\\".to_string(),
-            '\n' => "\\n".to_string(),
-            '\r' => "\\r".to_string(),
-            '\t' => "\\t".to_string(),
-            c if c.is_control() => format!("\\u{:04x}", c as u32),
-            c => c.to_string(),
-        })
-        .collect()
+    QueryResult::from_falkor_rows(records).render(ResultFormat::Json)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::query_result::ResultValue;
     use falkordb::{Edge, Node};
     use std::collections::HashMap;
 
     #[test]
     fn test_string_formatting() {
         let value = FalkorValue::String("Hello, World!".to_string());
-        assert_eq!(format_falkor_value(&value), "\"Hello, World!\"");
+        assert_eq!(ResultValue::from(&value).to_compact(), "\"Hello, World!\"");
+    }
+
+    #[test]
+    fn rows_are_formatted_as_they_are_read_up_to_the_limit() {
+        let rows = |count: i64| (0..count).map(|i| vec![FalkorValue::I64(i), FalkorValue::String(format!("n{i}"))]);
+        for count in [0, 1, 2, 5] {
+            let records: Vec<_> = rows(count).collect();
+            assert_eq!(
+                format_rows_within(rows(count), None).unwrap(),
+                format_query_records(&records)
+            );
+        }
+        assert_eq!(
+            format_rows_within(rows(1000), Some(64)),
+            Err(ResultTooLarge { max_bytes: 64, rows: 6 })
+        );
+        assert!(format_rows_within(rows(5), Some(64)).is_ok());
     }
 
     #[test]
@@ -349,7 +182,7 @@ mod tests {
         };
 
         let value = FalkorValue::Node(node);
-        let formatted = format_falkor_value(&value);
+        let formatted = ResultValue::from(&value).to_compact();
         assert!(formatted.contains("(:Person"));
         assert!(formatted.contains("name: 42"));
     }
@@ -365,7 +198,7 @@ mod tests {
         };
 
         let value = FalkorValue::Edge(edge);
-        let formatted = format_falkor_value(&value);
+        let formatted = ResultValue::from(&value).to_compact();
         assert_eq!(formatted, "-[:KNOWS]-");
     }
 
@@ -374,7 +207,7 @@ mod tests {
         let array = vec![FalkorValue::I64(1), FalkorValue::I64(2), FalkorValue::I64(3)];
 
         let value = FalkorValue::Array(array);
-        let formatted = format_falkor_value(&value);
+        let formatted = ResultValue::from(&value).to_compact();
         assert_eq!(formatted, "[1, 2, 3]");
     }
 }
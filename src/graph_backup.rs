@@ -0,0 +1,230 @@
+//! Copies of a graph taken before destructive operations, and rolling a graph back to one.
+//!
+//! With `GRAPH_BACKUP_RETENTION` set, hard `/graph_delete` calls and confirmed destructive queries
+//! first copy the graph (`GRAPH.COPY`) to `{graph}__backup_{unix seconds}`. Only the newest backups
+//! of each graph are kept; older ones are deleted after every new backup. `POST /graphs/{name}/restore`
+//! replaces the graph with one of its backups, which stays available for another restore.
+
+use crate::connection::ConnectionTarget;
+use falkordb::FalkorAsyncClient;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Separates a graph's name from the creation time in its backups' names.
+pub const BACKUP_MARKER: &str = "__backup_";
+
+/// Why a backup could not be taken or restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackupError {
+    /// The graph has no such backup.
+    NotFound(String),
+    /// The server failed to list, copy or delete graphs.
+    Database(String),
+}
+
+impl std::fmt::Display for BackupError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::NotFound(message) | Self::Database(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for BackupError {}
+
+/// A backup of a graph.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct GraphBackup {
+    /// The backup's own graph name, e.g. `movies__backup_1760486400`.
+    pub name: String,
+    /// The graph it is a copy of.
+    pub graph_name: String,
+    /// Unix timestamp (seconds) of the copy.
+    pub created_at: u64,
+}
+
+impl GraphBackup {
+    /// The backup `name` stands for, if it is a backup's name.
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        let (graph_name, created_at) = name.rsplit_once(BACKUP_MARKER)?;
+        if graph_name.is_empty() || created_at.is_empty() || !created_at.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(Self {
+            name: name.to_string(),
+            graph_name: graph_name.to_string(),
+            created_at: created_at.parse().ok()?,
+        })
+    }
+}
+
+/// The name of the backup of `graph_name` taken at `created_at`.
+#[must_use]
+pub fn backup_name(
+    graph_name: &str,
+    created_at: u64,
+) -> String {
+    format!("{graph_name}{BACKUP_MARKER}{created_at}")
+}
+
+/// The backups of `graph_name` among `graphs`, newest first.
+#[must_use]
+pub fn backups_of(
+    graph_name: &str,
+    graphs: &[String],
+) -> Vec<GraphBackup> {
+    let mut backups: Vec<GraphBackup> = graphs
+        .iter()
+        .filter_map(|name| GraphBackup::parse(name))
+        .filter(|backup| backup.graph_name == graph_name)
+        .collect();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    backups
+}
+
+/// The graph names on the server.
+async fn graph_names(
+    client: &FalkorAsyncClient,
+    target: &ConnectionTarget,
+) -> Result<Vec<String>, BackupError> {
+    let keys = client
+        .list_graphs()
+        .await
+        .map_err(|e| BackupError::Database(format!("Failed to list graphs: {e}")))?;
+    Ok(target.graph_names(keys))
+}
+
+/// The backups of `graph_name` on the server, newest first.
+///
+/// # Errors
+///
+/// Returns [`BackupError::Database`] if the graphs cannot be listed.
+pub async fn list(
+    client: &FalkorAsyncClient,
+    target: &ConnectionTarget,
+    graph_name: &str,
+) -> Result<Vec<GraphBackup>, BackupError> {
+    Ok(backups_of(graph_name, &graph_names(client, target).await?))
+}
+
+/// Copies `graph_name` to a new backup, then deletes its backups beyond the newest `retention`.
+/// Failing to delete an old backup is logged, not returned: the new backup exists either way.
+///
+/// # Errors
+///
+/// Returns [`BackupError::Database`] if the graph cannot be copied.
+pub async fn snapshot(
+    client: &FalkorAsyncClient,
+    target: &ConnectionTarget,
+    graph_name: &str,
+    retention: usize,
+) -> Result<GraphBackup, BackupError> {
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let backup = GraphBackup {
+        name: backup_name(graph_name, created_at),
+        graph_name: graph_name.to_string(),
+        created_at,
+    };
+    client
+        .copy_graph_op(&target.graph_key(graph_name), &target.graph_key(&backup.name))
+        .wait()
+        .await
+        .map_err(|e| BackupError::Database(format!("Failed to back up graph '{graph_name}': {e}")))?;
+    tracing::info!("Backed up graph '{}' to '{}'", graph_name, backup.name);
+
+    match list(client, target, graph_name).await {
+        Ok(backups) => {
+            for old in backups.iter().skip(retention.max(1)) {
+                if let Err(e) = client.select_graph(target.graph_key(&old.name)).delete().await {
+                    tracing::warn!("Failed to delete old backup '{}': {}", old.name, e);
+                }
+            }
+        }
+        Err(e) => tracing::warn!("Failed to prune backups of '{}': {}", graph_name, e),
+    }
+    Ok(backup)
+}
+
+/// Replaces `graph_name` with a copy of its backup `backup`, or of its newest backup when `None`,
+/// and returns the backup restored.
+///
+/// # Errors
+///
+/// Returns [`BackupError::NotFound`] if the graph has no such backup, or
+/// [`BackupError::Database`] if the graph cannot be replaced.
+pub async fn restore(
+    client: &FalkorAsyncClient,
+    target: &ConnectionTarget,
+    graph_name: &str,
+    backup: Option<&str>,
+) -> Result<GraphBackup, BackupError> {
+    let graphs = graph_names(client, target).await?;
+    let backups = backups_of(graph_name, &graphs);
+    let backup = match backup {
+        Some(name) => backups
+            .into_iter()
+            .find(|b| b.name == name)
+            .ok_or_else(|| BackupError::NotFound(format!("Graph '{graph_name}' has no backup '{name}'")))?,
+        None => backups
+            .into_iter()
+            .next()
+            .ok_or_else(|| BackupError::NotFound(format!("Graph '{graph_name}' has no backups")))?,
+    };
+
+    if graphs.iter().any(|graph| graph == graph_name) {
+        client
+            .select_graph(target.graph_key(graph_name))
+            .delete()
+            .await
+            .map_err(|e| BackupError::Database(format!("Failed to delete graph '{graph_name}': {e}")))?;
+    }
+    client
+        .copy_graph_op(&target.graph_key(&backup.name), &target.graph_key(graph_name))
+        .wait()
+        .await
+        .map_err(|e| {
+            BackupError::Database(format!(
+                "Failed to restore graph '{graph_name}' from '{}': {e}",
+                backup.name
+            ))
+        })?;
+    tracing::info!("Restored graph '{}' from '{}'", graph_name, backup.name);
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backups_are_found_by_name_newest_first() {
+        let graphs: Vec<String> = [
+            "movies",
+            "movies__backup_100",
+            "movies__backup_300",
+            "movies__backup_200",
+            "movies_archive__backup_400",
+            "movies__backup_latest",
+            "__backup_500",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+        let backups = backups_of("movies", &graphs);
+        let names: Vec<&str> = backups.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["movies__backup_300", "movies__backup_200", "movies__backup_100"]
+        );
+        assert_eq!(backups[0].created_at, 300);
+        assert_eq!(backup_name("movies", 300), "movies__backup_300");
+        assert_eq!(backups_of("movies_archive", &graphs).len(), 1);
+        assert_eq!(GraphBackup::parse("__backup_500"), None);
+    }
+}
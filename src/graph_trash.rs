@@ -0,0 +1,346 @@
+//! Two-step, recoverable graph deletion.
+//!
+//! `/graph_delete` first issues a short-lived confirmation token; only a second call carrying that
+//! token deletes the graph. By default the graph is moved to the trash — copied (`GRAPH.COPY`) to
+//! `__trash_{unix seconds}_{graph}` and the original deleted — and trashed graphs are purged once
+//! older than `GRAPH_TRASH_TTL_SECS`. Until then a trashed graph can be copied back by hand.
+//!
+//! Every trashed graph is recorded in storage, and only recorded graphs are purged, so a graph
+//! that merely has a trash-like name is never deleted. A confirmation token is bound to the API
+//! key that requested it, so another caller cannot redeem it.
+
+use crate::connection::ConnectionTarget;
+use crate::storage::{self, Storage, StorageError};
+use aws_lc_rs::digest;
+use falkordb::FalkorAsyncClient;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Starts the names of trashed graphs.
+pub const TRASH_PREFIX: &str = "__trash_";
+
+/// How long trashed graphs are kept when `GRAPH_TRASH_TTL_SECS` is unset.
+pub const DEFAULT_TRASH_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Storage namespace recording the graphs in the trash, by graph key.
+pub const TRASH_NAMESPACE: &str = "graph_trash";
+
+/// How long a trash record outlives the purge that should have removed its graph.
+const TRASH_RECORD_GRACE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Storage namespace holding pending deletions.
+pub const DELETE_CONFIRMATION_NAMESPACE: &str = "graph_delete_confirmations";
+
+/// How long a deletion can be confirmed.
+pub const DELETE_CONFIRMATION_TTL: Duration = Duration::from_secs(60);
+
+/// A graph in the trash.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct TrashedGraph {
+    /// The trashed graph's own name, e.g. `__trash_1760486400_movies`.
+    pub name: String,
+    /// The name the graph had before it was deleted.
+    pub graph_name: String,
+    /// Unix timestamp (seconds) of the deletion.
+    pub deleted_at: u64,
+}
+
+impl TrashedGraph {
+    /// The trashed graph `name` stands for, if it is a trashed graph's name.
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        let (deleted_at, graph_name) = name.strip_prefix(TRASH_PREFIX)?.split_once('_')?;
+        if graph_name.is_empty() || deleted_at.is_empty() || !deleted_at.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(Self {
+            name: name.to_string(),
+            graph_name: graph_name.to_string(),
+            deleted_at: deleted_at.parse().ok()?,
+        })
+    }
+}
+
+/// The name `graph_name` gets in the trash when deleted at `deleted_at`.
+#[must_use]
+pub fn trash_name(
+    graph_name: &str,
+    deleted_at: u64,
+) -> String {
+    format!("{TRASH_PREFIX}{deleted_at}_{graph_name}")
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// The graphs moved to the trash, recorded so that only they are ever purged.
+#[derive(Debug, Clone)]
+pub struct GraphTrash {
+    storage: Arc<dyn Storage>,
+}
+
+impl GraphTrash {
+    /// Creates a trash recorded in `storage`.
+    #[must_use]
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Moves `graph_name` to the trash: copies it to its trash name, records it, then deletes the
+    /// original. `ttl` is how long the trash keeps it (`None` until purged by hand).
+    ///
+    /// # Errors
+    ///
+    /// Returns a message if the graph cannot be copied, recorded or deleted; if the copy or the
+    /// record fails the graph is left as it was.
+    pub async fn soft_delete(
+        &self,
+        client: &FalkorAsyncClient,
+        target: &ConnectionTarget,
+        graph_name: &str,
+        ttl: Option<Duration>,
+    ) -> Result<TrashedGraph, String> {
+        let deleted_at = now();
+        let trashed = TrashedGraph {
+            name: trash_name(graph_name, deleted_at),
+            graph_name: graph_name.to_string(),
+            deleted_at,
+        };
+        client
+            .copy_graph_op(&target.graph_key(graph_name), &target.graph_key(&trashed.name))
+            .wait()
+            .await
+            .map_err(|e| format!("Failed to move graph '{graph_name}' to the trash: {e}"))?;
+        if let Err(e) = self.record(target, &trashed, ttl).await {
+            // Unrecorded, the copy would never be purged
+            let _ = client.select_graph(target.graph_key(&trashed.name)).delete().await;
+            return Err(format!("Failed to record graph '{graph_name}' in the trash: {e}"));
+        }
+        client
+            .select_graph(target.graph_key(graph_name))
+            .delete()
+            .await
+            .map_err(|e| format!("Failed to delete graph '{graph_name}': {e}"))?;
+        tracing::info!("Moved graph '{}' to the trash as '{}'", graph_name, trashed.name);
+        Ok(trashed)
+    }
+
+    async fn record(
+        &self,
+        target: &ConnectionTarget,
+        trashed: &TrashedGraph,
+        ttl: Option<Duration>,
+    ) -> Result<(), StorageError> {
+        storage::put_json(
+            self.storage.as_ref(),
+            TRASH_NAMESPACE,
+            &target.graph_key(&trashed.name),
+            trashed,
+            ttl.map(|ttl| ttl + TRASH_RECORD_GRACE),
+        )
+        .await
+    }
+
+    /// The recorded trashed graphs among `graphs` (names on `target`) deleted before `cutoff`
+    /// (unix seconds).
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if a record cannot be read.
+    pub async fn expired(
+        &self,
+        target: &ConnectionTarget,
+        graphs: &[String],
+        cutoff: u64,
+    ) -> Result<Vec<TrashedGraph>, StorageError> {
+        let mut expired = Vec::new();
+        for name in graphs.iter().filter(|name| TrashedGraph::parse(name).is_some()) {
+            let recorded: Option<TrashedGraph> =
+                storage::get_json(self.storage.as_ref(), TRASH_NAMESPACE, &target.graph_key(name)).await?;
+            if let Some(trashed) = recorded.filter(|trashed| trashed.name == *name && trashed.deleted_at < cutoff) {
+                expired.push(trashed);
+            }
+        }
+        Ok(expired)
+    }
+
+    /// Deletes the recorded trashed graphs older than `ttl` and returns them. Failing to delete one
+    /// is logged, and the others are still purged.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message if the graphs or their records cannot be read.
+    pub async fn purge(
+        &self,
+        client: &FalkorAsyncClient,
+        target: &ConnectionTarget,
+        ttl: Duration,
+    ) -> Result<Vec<TrashedGraph>, String> {
+        let keys = client.list_graphs().await.map_err(|e| format!("Failed to list graphs: {e}"))?;
+        let expired = self
+            .expired(target, &target.graph_names(keys), now().saturating_sub(ttl.as_secs()))
+            .await
+            .map_err(|e| format!("Failed to read the trash records: {e}"))?;
+        let mut purged = Vec::new();
+        for trashed in expired {
+            match client.select_graph(target.graph_key(&trashed.name)).delete().await {
+                Ok(()) => {
+                    tracing::info!("Purged trashed graph '{}'", trashed.name);
+                    if let Err(e) = self.storage.delete(TRASH_NAMESPACE, &target.graph_key(&trashed.name)).await {
+                        tracing::warn!("Failed to remove the trash record of '{}': {}", trashed.name, e);
+                    }
+                    purged.push(trashed);
+                }
+                Err(e) => tracing::warn!("Failed to purge trashed graph '{}': {}", trashed.name, e),
+            }
+        }
+        Ok(purged)
+    }
+}
+
+/// Identifies the caller a confirmation token is issued to, without storing its API key.
+fn caller_digest(api_key: &str) -> String {
+    digest::digest(&digest::SHA256, api_key.as_bytes()).as_ref().iter().fold(
+        String::with_capacity(64),
+        |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        },
+    )
+}
+
+/// A deletion awaiting confirmation.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct PendingDeletion {
+    /// One-time token to send as `confirm` to delete the graph.
+    pub confirmation_token: String,
+    /// Graph to delete.
+    pub graph_name: String,
+    /// Whether the graph is deleted outright instead of moved to the trash.
+    pub hard: bool,
+    /// Unix timestamp (seconds) after which the token is no longer accepted.
+    pub expires_at: u64,
+    /// SHA-256 of the API key the token was issued to, which must also redeem it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(ignore)]
+    pub caller: Option<String>,
+}
+
+/// Issues and redeems deletion confirmation tokens.
+#[derive(Debug, Clone)]
+pub struct DeleteConfirmations {
+    storage: Arc<dyn Storage>,
+}
+
+impl DeleteConfirmations {
+    /// Creates a store backed by `storage`.
+    #[must_use]
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Records a pending deletion of `graph_name` requested with `api_key` and returns it with its
+    /// token.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the pending deletion cannot be saved.
+    pub async fn issue(
+        &self,
+        graph_name: &str,
+        hard: bool,
+        api_key: Option<&str>,
+    ) -> Result<PendingDeletion, StorageError> {
+        let pending = PendingDeletion {
+            confirmation_token: Uuid::new_v4().to_string(),
+            graph_name: graph_name.to_string(),
+            hard,
+            expires_at: now() + DELETE_CONFIRMATION_TTL.as_secs(),
+            caller: api_key.map(caller_digest),
+        };
+        storage::put_json(
+            self.storage.as_ref(),
+            DELETE_CONFIRMATION_NAMESPACE,
+            &pending.confirmation_token,
+            &pending,
+            Some(DELETE_CONFIRMATION_TTL),
+        )
+        .await?;
+        Ok(pending)
+    }
+
+    /// Redeems a confirmation token with the API key it was issued to. Tokens are single-use: a
+    /// redeemed token is removed, and expired or unknown tokens return `None`, as do tokens sent
+    /// with another key, which stay redeemable by their own caller.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the pending deletion cannot be read or removed.
+    pub async fn take(
+        &self,
+        token: &str,
+        api_key: Option<&str>,
+    ) -> Result<Option<PendingDeletion>, StorageError> {
+        let pending = storage::get_json::<PendingDeletion>(self.storage.as_ref(), DELETE_CONFIRMATION_NAMESPACE, token)
+            .await?
+            .filter(|pending| pending.caller == api_key.map(caller_digest));
+        if pending.is_some() && !self.storage.delete(DELETE_CONFIRMATION_NAMESPACE, token).await? {
+            // Redeemed concurrently by another request.
+            return Ok(None);
+        }
+        Ok(pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn only_recorded_trash_expires_and_tokens_are_single_use() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::new());
+        let target = ConnectionTarget::parse("falkor://127.0.0.1:6379").unwrap();
+        let trash = GraphTrash::new(storage.clone());
+        for name in ["__trash_100_movies", "__trash_300_my_graph"] {
+            let trashed = TrashedGraph::parse(name).unwrap();
+            trash.record(&target, &trashed, None).await.unwrap();
+        }
+        assert_eq!(trash_name("my_graph", 300), "__trash_300_my_graph");
+        assert_eq!(
+            TrashedGraph::parse("__trash_300_my_graph").map(|t| t.graph_name),
+            Some("my_graph".to_string())
+        );
+        // "__trash_50_audit" is a user graph with a trash-like name: it was never trashed
+        let graphs: Vec<String> = ["movies", "__trash_100_movies", "__trash_300_my_graph", "__trash_50_audit"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let names: Vec<String> = trash
+            .expired(&target, &graphs, 200)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, ["__trash_100_movies"]);
+        let other = ConnectionTarget::parse("falkor://127.0.0.1:6379?prefix=acme_").unwrap();
+        assert!(trash.expired(&other, &graphs, 200).await.unwrap().is_empty());
+
+        let confirmations = DeleteConfirmations::new(storage);
+        let pending = confirmations.issue("movies", false, Some("alice-key")).await.unwrap();
+        let token = &pending.confirmation_token;
+        assert_eq!(confirmations.take(token, Some("mallory-key")).await.unwrap(), None);
+        assert_eq!(confirmations.take(token, None).await.unwrap(), None);
+        assert_eq!(
+            confirmations.take(token, Some("alice-key")).await.unwrap(),
+            Some(pending.clone())
+        );
+        assert_eq!(confirmations.take(token, Some("alice-key")).await.unwrap(), None);
+    }
+}
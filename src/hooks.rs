@@ -0,0 +1,342 @@
+//! Post-processing hooks for generated queries and answers.
+//!
+//! A [`ResponseHook`] rewrites what the model produced at two points of the pipeline: each
+//! generated query (including regenerations) before it is validated and executed, and the final
+//! answer before it is returned. Streams send the answer's chunks as the model writes them; only the
+//! closing `Result` event carries the processed answer.
+//!
+//! Library users register their own hooks with `TextToCypherClient::builder().response_hook(...)`.
+//! The server configures the built-in hooks with `RESPONSE_HOOKS`, parsed by [`ResponseHooks::parse`]:
+//!
+//! - [`EnforceLimit`] — `enforce-limit=N`: caps reads at `N` rows,
+//! - [`StripChatter`] — `strip-chatter`: drops pleasantries around the answer,
+//! - [`PlainText`] — `plain-text`: converts markdown answers to plain text,
+//! - [`Disclaimer`] — `disclaimer=TEXT`: appends a paragraph to every answer.
+
+use crate::validator::{CypherValidator, QueryMode};
+use regex::Regex;
+use std::sync::{Arc, OnceLock};
+
+/// Rewrites generated queries and answers. Both methods return their input unchanged by default.
+pub trait ResponseHook: Send + Sync + std::fmt::Debug {
+    /// Short, stable hook name for logs (`"enforce-limit"`, `"disclaimer"`).
+    fn name(&self) -> &'static str;
+
+    /// Rewrites a generated query before it is validated and executed.
+    fn process_query(
+        &self,
+        query: String,
+    ) -> String {
+        query
+    }
+
+    /// Rewrites the final answer.
+    fn process_answer(
+        &self,
+        answer: String,
+    ) -> String {
+        answer
+    }
+}
+
+/// The hooks a pipeline runs, in registration order.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseHooks {
+    hooks: Vec<Arc<dyn ResponseHook>>,
+}
+
+impl ResponseHooks {
+    /// Parses a `;`-separated list of built-in hooks such as
+    /// `enforce-limit=100;strip-chatter;disclaimer=Figures are unaudited.` Blank entries are
+    /// ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending entry if a hook is unknown, `enforce-limit` has no
+    /// positive row count, or `disclaimer` has no text.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut hooks = Self::default();
+        for entry in spec.split(';').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (name, argument) = entry
+                .split_once('=')
+                .map_or((entry, None), |(name, argument)| (name.trim(), Some(argument.trim())));
+            match (name.to_ascii_lowercase().as_str(), argument) {
+                ("enforce-limit", Some(rows)) => {
+                    let rows = rows
+                        .parse()
+                        .ok()
+                        .filter(|rows| *rows > 0)
+                        .ok_or_else(|| format!("hook '{entry}' needs a positive row count"))?;
+                    hooks.push(EnforceLimit(rows));
+                }
+                ("disclaimer", Some(text)) if !text.is_empty() => hooks.push(Disclaimer(text.to_string())),
+                ("strip-chatter", None) => hooks.push(StripChatter),
+                ("plain-text", None) => hooks.push(PlainText),
+                _ => {
+                    return Err(format!(
+                        "unknown hook '{entry}'; expected enforce-limit=N, strip-chatter, plain-text or disclaimer=TEXT"
+                    ));
+                }
+            }
+        }
+        Ok(hooks)
+    }
+
+    /// Adds a hook, run after those already registered.
+    pub fn push(
+        &mut self,
+        hook: impl ResponseHook + 'static,
+    ) {
+        self.hooks.push(Arc::new(hook));
+    }
+
+    /// Returns true when no hook is registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// The hook names, in order, for logs.
+    #[must_use]
+    pub fn names(&self) -> Vec<&'static str> {
+        self.hooks.iter().map(|hook| hook.name()).collect()
+    }
+
+    /// Runs every hook's [`ResponseHook::process_query`] on `query`.
+    #[must_use]
+    pub fn process_query(
+        &self,
+        query: String,
+    ) -> String {
+        self.hooks.iter().fold(query, |query, hook| hook.process_query(query))
+    }
+
+    /// Runs every hook's [`ResponseHook::process_answer`] on `answer`.
+    #[must_use]
+    pub fn process_answer(
+        &self,
+        answer: String,
+    ) -> String {
+        self.hooks.iter().fold(answer, |answer, hook| hook.process_answer(answer))
+    }
+}
+
+static PATTERNS: OnceLock<HookPatterns> = OnceLock::new();
+
+struct HookPatterns {
+    /// Pattern to capture the row count of a LIMIT clause
+    limit: Regex,
+    /// Pattern to detect a RETURN clause
+    return_clause: Regex,
+    /// Pattern to detect UNION, whose parts each need their own LIMIT
+    union: Regex,
+    /// Pattern to match an opening pleasantry sentence such as "Sure! " or "Great question."
+    opener: Regex,
+    /// Pattern to match a lead-in such as "Based on the query results, "
+    lead_in: Regex,
+    /// Pattern to match a closing offer such as "Let me know if you need anything else."
+    closer: Regex,
+    /// Pattern to match a markdown heading marker
+    heading: Regex,
+    /// Pattern to match a markdown bullet marker
+    bullet: Regex,
+    /// Pattern to capture bold or italic text
+    emphasis: Regex,
+    /// Pattern to capture inline code
+    inline_code: Regex,
+    /// Pattern to capture a link's text and target
+    link: Regex,
+}
+
+impl HookPatterns {
+    fn get() -> &'static Self {
+        PATTERNS.get_or_init(|| Self {
+            limit: Regex::new(r"(?i)\bLIMIT\s+(\d+)").unwrap(),
+            return_clause: Regex::new(r"(?i)\bRETURN\b").unwrap(),
+            union: Regex::new(r"(?i)\bUNION\b").unwrap(),
+            opener: Regex::new(
+                r"(?i)^\s*(sure|certainly|of course|absolutely|great question|good question|happy to help)\b[^.!?\n]*[.!?:]\s*",
+            )
+            .unwrap(),
+            lead_in: Regex::new(
+                r"(?i)^\s*(based on|according to|looking at) (the )?(query |cypher |graph )?(results?|data|output)( provided| returned)?,\s*",
+            )
+            .unwrap(),
+            closer: Regex::new(
+                r"(?i)\s*(let me know|feel free|i hope this helps|hope this helps|if you have any (other|more|further) questions)[^.!?\n]*[.!?]?\s*$",
+            )
+            .unwrap(),
+            heading: Regex::new(r"(?m)^\s{0,3}#{1,6}\s+").unwrap(),
+            bullet: Regex::new(r"(?m)^(\s*)[*+]\s+").unwrap(),
+            emphasis: Regex::new(r"\*\*([^*\n]+)\*\*|__([^_\n]+)__|\*([^*\s][^*\n]*)\*").unwrap(),
+            inline_code: Regex::new(r"`([^`\n]+)`").unwrap(),
+            link: Regex::new(r"\[([^\]\n]+)\]\(([^)\s]+)\)").unwrap(),
+        })
+    }
+}
+
+/// Caps reads at a number of rows: adds `LIMIT N` to reads without one and lowers larger limits.
+/// Writes and UNION queries are left as generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnforceLimit(pub u64);
+
+impl ResponseHook for EnforceLimit {
+    fn name(&self) -> &'static str {
+        "enforce-limit"
+    }
+
+    fn process_query(
+        &self,
+        query: String,
+    ) -> String {
+        let patterns = HookPatterns::get();
+        let masked = CypherValidator::mask_string_literals(&query);
+        if CypherValidator::query_mode(&masked) == QueryMode::Write
+            || !patterns.return_clause.is_match(&masked)
+            || patterns.union.is_match(&masked)
+        {
+            return query;
+        }
+        let limits: Vec<_> = patterns
+            .limit
+            .captures_iter(&masked)
+            .filter_map(|captures| captures.get(1))
+            .map(|rows| rows.range())
+            .collect();
+        if limits.is_empty() {
+            return format!("{} LIMIT {}", query.trim_end().trim_end_matches(';').trim_end(), self.0);
+        }
+        let mut capped = query;
+        for range in limits.into_iter().rev() {
+            if capped[range.clone()].parse::<u64>().is_ok_and(|rows| rows > self.0) {
+                capped.replace_range(range, &self.0.to_string());
+            }
+        }
+        capped
+    }
+}
+
+/// Drops pleasantries the model wraps answers in: opening sentences such as "Sure!", lead-ins such
+/// as "Based on the query results," and closing offers such as "Let me know if you need more".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StripChatter;
+
+impl ResponseHook for StripChatter {
+    fn name(&self) -> &'static str {
+        "strip-chatter"
+    }
+
+    fn process_answer(
+        &self,
+        answer: String,
+    ) -> String {
+        let patterns = HookPatterns::get();
+        let mut text = answer.trim().to_string();
+        while let Some(opener) = patterns.opener.find(&text) {
+            text.replace_range(opener.range(), "");
+        }
+        if let Some(lead_in) = patterns.lead_in.find(&text) {
+            text.replace_range(lead_in.range(), "");
+            if let Some(first) = text.chars().next() {
+                text.replace_range(..first.len_utf8(), &first.to_uppercase().to_string());
+            }
+        }
+        while let Some(closer) = patterns.closer.find(&text) {
+            text.truncate(closer.start());
+        }
+        // An answer that is nothing but chatter is better returned as written
+        if text.trim().is_empty() {
+            answer
+        } else {
+            text.trim().to_string()
+        }
+    }
+}
+
+/// Converts markdown answers to plain text: headings, emphasis, inline code and code fences lose
+/// their markers, `*` and `+` bullets become `-`, and links become `text (url)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlainText;
+
+impl ResponseHook for PlainText {
+    fn name(&self) -> &'static str {
+        "plain-text"
+    }
+
+    fn process_answer(
+        &self,
+        answer: String,
+    ) -> String {
+        let patterns = HookPatterns::get();
+        let text: Vec<&str> = answer.lines().filter(|line| !line.trim_start().starts_with("```")).collect();
+        let text = text.join("\n");
+        let text = patterns.heading.replace_all(&text, "");
+        let text = patterns.bullet.replace_all(&text, "$1- ");
+        let text = patterns.link.replace_all(&text, "$1 ($2)");
+        let text = patterns.inline_code.replace_all(&text, "$1");
+        let text = patterns.emphasis.replace_all(&text, "$1$2$3");
+        text.into_owned()
+    }
+}
+
+/// Appends a paragraph, such as a legal notice, to every answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disclaimer(pub String);
+
+impl ResponseHook for Disclaimer {
+    fn name(&self) -> &'static str {
+        "disclaimer"
+    }
+
+    fn process_answer(
+        &self,
+        answer: String,
+    ) -> String {
+        format!("{}\n\n{}", answer.trim_end(), self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_hooks_rewrite_queries_and_answers() {
+        let hooks =
+            ResponseHooks::parse("enforce-limit=50; strip-chatter; plain-text; disclaimer=Data as of Q3.").unwrap();
+        assert_eq!(
+            hooks.names(),
+            vec!["enforce-limit", "strip-chatter", "plain-text", "disclaimer"]
+        );
+        assert!(ResponseHooks::parse("enforce-limit=0").is_err());
+        assert!(ResponseHooks::parse("shout").is_err());
+
+        for (query, expected) in [
+            (
+                "MATCH (n:Person) RETURN n.name;",
+                "MATCH (n:Person) RETURN n.name LIMIT 50",
+            ),
+            (
+                "MATCH (n:Person) WHERE n.bio = 'LIMIT 500' RETURN n LIMIT 500",
+                "MATCH (n:Person) WHERE n.bio = 'LIMIT 500' RETURN n LIMIT 50",
+            ),
+            ("MATCH (n:Person) RETURN n LIMIT 5", "MATCH (n:Person) RETURN n LIMIT 5"),
+            ("CREATE (n:Person) RETURN n", "CREATE (n:Person) RETURN n"),
+            (
+                "MATCH (a:A) RETURN a.x AS x UNION MATCH (b:B) RETURN b.x AS x",
+                "MATCH (a:A) RETURN a.x AS x UNION MATCH (b:B) RETURN b.x AS x",
+            ),
+        ] {
+            assert_eq!(hooks.process_query(query.to_string()), expected);
+        }
+
+        let answer = "Sure! Based on the query results, **Tom Hanks** acted in:\n\
+                      ## Movies\n* `Big` ([IMDb](https://imdb.com/title/tt0094737))\n\
+                      * Cast Away\n\nLet me know if you need anything else!";
+        assert_eq!(
+            hooks.process_answer(answer.to_string()),
+            "Tom Hanks acted in:\nMovies\n- Big (IMDb (https://imdb.com/title/tt0094737))\n- Cast Away\n\nData as of Q3."
+        );
+        assert_eq!(StripChatter.process_answer("Sure!".to_string()), "Sure!");
+    }
+}
@@ -0,0 +1,938 @@
+//! Batched CSV imports.
+//!
+//! A large `LOAD CSV` runs as one query that reports nothing until it ends. [`split_csv`] cuts the
+//! file into batches of whole records, each repeating the header row, so the same query can run
+//! once per batch and the caller can report [`ImportBatch`] progress in between.
+//!
+//! A failing `LOAD CSV` query writes nothing, so one bad row fails its whole batch. With
+//! [`OnBatchError::Skip`], a failed batch is [halved](CsvBatch::halves) until the failing rows are
+//! isolated; they are skipped and recorded in an [`ImportSummary`], and the other rows load.
+//!
+//! Before a file is imported, [`inspect_csv`] reads its first rows to detect the encoding,
+//! delimiter and header, infer each column's type and null rate, and suggest a `LOAD CSV` query
+//! that merges its nodes, so running it again does not duplicate them; [`normalize_csv`] rewrites
+//! the file into the UTF-8, comma-separated form that query reads.
+
+use crate::schema::attribute::{AttributeType, date_format};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Records per batch when the caller sets no batch size.
+pub const DEFAULT_BATCH_ROWS: usize = 10_000;
+
+/// Row errors kept in an [`ImportSummary`]; later ones are only counted.
+pub const MAX_REPORTED_ERRORS: usize = 10;
+
+/// Skipped rows after which an [`OnBatchError::Skip`] import gives up, since every row failing
+/// usually means the query itself is wrong.
+pub const MAX_SKIPPED_ROWS: usize = 1_000;
+
+/// Whether a `LOAD CSV` query reads its file's first row as a header (`WITH HEADERS`).
+#[must_use]
+pub fn reads_headers(query: &str) -> bool {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .windows(2)
+        .any(|words| words[0].eq_ignore_ascii_case("WITH") && words[1].eq_ignore_ascii_case("HEADERS"))
+}
+
+/// A batch of CSV records, ready to write as a file of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvBatch {
+    pub text: String,
+    /// Records in the batch, not counting the header row.
+    pub rows: usize,
+    /// 1-based number of the batch's first record in the whole file, not counting the header row.
+    pub first_row: usize,
+}
+
+impl CsvBatch {
+    /// The batch split in two, the first half taking the odd record; a single-record batch is
+    /// returned as is.
+    #[must_use]
+    pub fn halves(
+        &self,
+        has_header: bool,
+    ) -> Vec<Self> {
+        let mut halves = split_csv(&self.text, self.rows.div_ceil(2), has_header);
+        for half in &mut halves {
+            half.first_row += self.first_row - 1;
+        }
+        halves
+    }
+}
+
+/// Splits CSV text into batches of at most `rows_per_batch` records (at least one).
+///
+/// Each batch starts with the header row when `has_header`. Records end at line breaks outside double quotes, so a
+/// quoted value spanning lines stays in one batch. Blank lines are dropped.
+#[must_use]
+pub fn split_csv(
+    csv: &str,
+    rows_per_batch: usize,
+    has_header: bool,
+) -> Vec<CsvBatch> {
+    let mut records = csv_records(csv).filter(|record| !record.trim().is_empty());
+    let header = if has_header { records.next() } else { None };
+    let records: Vec<&str> = records.collect();
+    let rows_per_batch = rows_per_batch.max(1);
+    records
+        .chunks(rows_per_batch)
+        .enumerate()
+        .map(|(index, batch)| {
+            let mut text = String::new();
+            for record in header.iter().chain(batch) {
+                text.push_str(record.trim_end_matches(['\r', '\n']));
+                text.push('\n');
+            }
+            CsvBatch {
+                text,
+                rows: batch.len(),
+                first_row: index * rows_per_batch + 1,
+            }
+        })
+        .collect()
+}
+
+/// The records of CSV text, each with its line break.
+fn csv_records(csv: &str) -> impl Iterator<Item = &str> {
+    let mut rest = csv;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut quoted = false;
+        let end = rest
+            .char_indices()
+            .find_map(|(i, c)| {
+                if c == '"' {
+                    quoted = !quoted;
+                }
+                (c == '\n' && !quoted).then_some(i + 1)
+            })
+            .unwrap_or(rest.len());
+        let (record, tail) = rest.split_at(end);
+        rest = tail;
+        Some(record)
+    })
+}
+
+/// What a write query changed, from the statistics `FalkorDB` returns with its result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct WriteStats {
+    pub nodes_created: u64,
+    pub relationships_created: u64,
+    pub properties_set: u64,
+}
+
+impl WriteStats {
+    /// Reads `Nodes created: 3`-style statistics lines; other lines are ignored.
+    #[must_use]
+    pub fn from_stats(stats: &[String]) -> Self {
+        let mut write_stats = Self::default();
+        for line in stats {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let Ok(value) = value.trim().parse::<u64>() else {
+                continue;
+            };
+            match name.trim().to_ascii_lowercase().as_str() {
+                "nodes created" => write_stats.nodes_created += value,
+                "relationships created" => write_stats.relationships_created += value,
+                "properties set" => write_stats.properties_set += value,
+                _ => {}
+            }
+        }
+        write_stats
+    }
+
+    /// Adds another batch's statistics.
+    pub const fn add(
+        &mut self,
+        other: Self,
+    ) {
+        self.nodes_created += other.nodes_created;
+        self.relationships_created += other.relationships_created;
+        self.properties_set += other.properties_set;
+    }
+}
+
+impl std::fmt::Display for WriteStats {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(
+            f,
+            "{} nodes created, {} relationships created, {} properties set",
+            self.nodes_created, self.relationships_created, self.properties_set
+        )
+    }
+}
+
+/// What a batched import does when a batch fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum OnBatchError {
+    /// End the import; the batches before the failing one stay written.
+    #[default]
+    Stop,
+    /// Skip the failing rows and load the rest.
+    Skip,
+}
+
+impl FromStr for OnBatchError {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "stop" => Ok(Self::Stop),
+            "skip" => Ok(Self::Skip),
+            other => Err(format!("Unknown batch error mode '{other}' (expected stop or skip)")),
+        }
+    }
+}
+
+/// A row skipped by an [`OnBatchError::Skip`] import.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct RowError {
+    /// 1-based record number in the file, not counting the header row.
+    pub row: usize,
+    pub message: String,
+}
+
+/// The outcome of a batched import.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct ImportSummary {
+    pub rows_loaded: usize,
+    pub rows_skipped: usize,
+    pub written: WriteStats,
+    /// The first [`MAX_REPORTED_ERRORS`] skipped rows, in the order they failed.
+    pub errors: Vec<RowError>,
+}
+
+impl ImportSummary {
+    /// Counts a skipped row, keeping its error when fewer than [`MAX_REPORTED_ERRORS`] are kept.
+    pub fn skip_row(
+        &mut self,
+        row: usize,
+        message: impl Into<String>,
+    ) {
+        self.rows_skipped += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(RowError {
+                row,
+                message: message.into(),
+            });
+        }
+    }
+}
+
+impl std::fmt::Display for ImportSummary {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(
+            f,
+            "Loaded {} rows, skipped {} ({})",
+            self.rows_loaded, self.rows_skipped, self.written
+        )?;
+        for error in &self.errors {
+            write!(f, "\nRow {}: {}", error.row, error.message)?;
+        }
+        if self.rows_skipped > self.errors.len() {
+            write!(f, "\n...and {} more", self.rows_skipped - self.errors.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Progress of a batched import, sent after each batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct ImportBatch {
+    /// 1-based number of the finished batch.
+    pub batch: usize,
+    pub batches: usize,
+    /// Records imported so far, including this batch.
+    pub rows_done: usize,
+    /// Records skipped so far by an [`OnBatchError::Skip`] import.
+    pub rows_skipped: usize,
+    pub rows_total: usize,
+    /// Changes made so far, including this batch.
+    pub written: WriteStats,
+}
+
+/// Records an inspection reads when the caller sets no limit.
+pub const DEFAULT_INSPECT_ROWS: usize = 100;
+
+/// Example values kept per [`CsvColumn`].
+const MAX_COLUMN_EXAMPLES: usize = 3;
+
+/// Delimiters [`detect_delimiter`] chooses from, in order of preference.
+const DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+/// The text encoding of an uploaded file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub enum CsvEncoding {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    /// Bytes that are not valid UTF-8 and carry no byte order mark, read one character per byte.
+    #[serde(rename = "latin-1")]
+    Latin1,
+}
+
+/// Decodes an uploaded file, dropping its byte order mark.
+///
+/// UTF-16 is recognized by its byte order mark only; other bytes are read as UTF-8 when valid and
+/// as Latin-1 otherwise, which never fails.
+#[must_use]
+pub fn decode_csv(bytes: &[u8]) -> (String, CsvEncoding) {
+    let utf16 = |bytes: &[u8], unit: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]])).collect();
+        String::from_utf16_lossy(&units)
+    };
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return (utf16(rest, u16::from_le_bytes), CsvEncoding::Utf16Le);
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return (utf16(rest, u16::from_be_bytes), CsvEncoding::Utf16Be);
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    std::str::from_utf8(bytes).map_or_else(
+        |_| (bytes.iter().map(|&b| char::from(b)).collect(), CsvEncoding::Latin1),
+        |text| (text.to_string(), CsvEncoding::Utf8),
+    )
+}
+
+/// Splits one CSV record into its fields.
+///
+/// Double quotes around a field are removed and `""` inside them reads as one quote; a delimiter
+/// or line break inside quotes belongs to the field.
+#[must_use]
+pub fn parse_record(
+    record: &str,
+    delimiter: char,
+) -> Vec<String> {
+    let record = record.trim_end_matches(['\r', '\n']);
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = record.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// The delimiter that splits the first records into the same number of fields, preferring the
+/// one giving the most fields; a comma when none splits them evenly.
+#[must_use]
+pub fn detect_delimiter(csv: &str) -> char {
+    let records: Vec<&str> = csv_records(csv).filter(|record| !record.trim().is_empty()).take(20).collect();
+    DELIMITERS
+        .iter()
+        .filter_map(|&delimiter| {
+            let mut counts = records.iter().map(|record| parse_record(record, delimiter).len());
+            let first = counts.next()?;
+            (first > 1 && counts.all(|count| count == first)).then_some((first, delimiter))
+        })
+        // `max_by_key` keeps the last maximum, so walk the preference order backwards
+        .rev()
+        .max_by_key(|(fields, _)| *fields)
+        .map_or(',', |(_, delimiter)| delimiter)
+}
+
+/// Rewrites an uploaded file as UTF-8 comma-separated text, the form `LOAD CSV` reads, quoting
+/// fields that need it. `delimiter` is detected when `None`.
+#[must_use]
+pub fn normalize_csv(
+    bytes: &[u8],
+    delimiter: Option<char>,
+) -> String {
+    let (text, _) = decode_csv(bytes);
+    let delimiter = delimiter.unwrap_or_else(|| detect_delimiter(&text));
+    let mut csv = String::new();
+    for record in csv_records(&text).filter(|record| !record.trim().is_empty()) {
+        csv.push_str(&csv_line(&parse_record(record, delimiter)));
+    }
+    csv
+}
+
+/// Writes `fields` as one comma-separated record with its line break, quoting fields holding a
+/// comma, quote or line break.
+#[must_use]
+pub fn csv_line(fields: &[String]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+/// How [`inspect_csv`] reads a file; unset fields are detected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InspectOptions {
+    /// Records to read, not counting the header row.
+    pub rows: usize,
+    pub delimiter: Option<char>,
+    pub has_header: Option<bool>,
+}
+
+impl Default for InspectOptions {
+    fn default() -> Self {
+        Self {
+            rows: DEFAULT_INSPECT_ROWS,
+            delimiter: None,
+            has_header: None,
+        }
+    }
+}
+
+/// A column of an inspected file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct CsvColumn {
+    /// The header, or `column1`, `column2`, ... when the file has none.
+    pub name: String,
+    /// The suggested property name: the header with other characters than letters, digits and
+    /// `_` replaced by `_`.
+    pub property: String,
+    /// `Integer`, `Float` or `Boolean` when every non-null value reads as one, else `String`.
+    #[serde(rename = "type")]
+    pub r#type: AttributeType,
+    /// The layout shared by every non-null value when they are all dates, e.g. `YYYY-MM-DD`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// Share of the inspected records where the value is empty or `null`, from 0 to 1.
+    pub null_rate: f64,
+    /// Up to three distinct non-null values.
+    pub examples: Vec<String>,
+}
+
+impl CsvColumn {
+    /// The `LOAD CSV` expression reading this column from `row`, converted to its type.
+    fn load_expression(
+        &self,
+        index: usize,
+        has_header: bool,
+    ) -> String {
+        let value = if has_header {
+            format!("row.{}", cypher_identifier(&self.name))
+        } else {
+            format!("row[{index}]")
+        };
+        match self.r#type {
+            AttributeType::Integer => format!("toInteger({value})"),
+            AttributeType::Float => format!("toFloat({value})"),
+            AttributeType::Boolean => format!("toBoolean({value})"),
+            _ => value,
+        }
+    }
+}
+
+/// What [`inspect_csv`] found in a file, and the import it suggests.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct CsvInspection {
+    pub encoding: CsvEncoding,
+    pub delimiter: char,
+    pub has_header: bool,
+    /// Records read, not counting the header row.
+    pub rows_inspected: usize,
+    pub columns: Vec<CsvColumn>,
+    /// The suggested node label, from the file name.
+    pub label: String,
+    /// Properties the suggested query merges on: an `id`-like column whose inspected values are
+    /// all present and distinct, else every column without nulls. Empty when every column has
+    /// nulls, and the query then creates its nodes.
+    pub merge_on: Vec<String>,
+    /// A `LOAD CSV` query writing one `label` node per record, with each column converted to its
+    /// type. It merges on [`merge_on`](Self::merge_on), so running it again does not duplicate
+    /// nodes. It reads the file as [`normalize_csv`] writes it.
+    pub load_csv: String,
+}
+
+/// Reads the first [`InspectOptions::rows`] records of an uploaded file and describes its columns.
+///
+/// Without a header option, the first record is a header when its fields are distinct, none is
+/// empty, and none reads as a number, boolean or date.
+#[must_use]
+pub fn inspect_csv(
+    bytes: &[u8],
+    filename: &str,
+    options: &InspectOptions,
+) -> CsvInspection {
+    let (text, encoding) = decode_csv(bytes);
+    let delimiter = options.delimiter.unwrap_or_else(|| detect_delimiter(&text));
+    let mut records = csv_records(&text)
+        .filter(|record| !record.trim().is_empty())
+        .map(|record| parse_record(record, delimiter));
+    let first = records.next().unwrap_or_default();
+    let has_header = options.has_header.unwrap_or_else(|| looks_like_header(&first));
+    let (names, rows): (Vec<String>, Vec<Vec<String>>) = if has_header {
+        (first, records.take(options.rows).collect())
+    } else {
+        let rows: Vec<_> = std::iter::once(first).chain(records).take(options.rows).collect();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        ((1..=width).map(|n| format!("column{n}")).collect(), rows)
+    };
+
+    let columns: Vec<CsvColumn> = names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let values: Vec<&str> = rows.iter().map(|row| row.get(index).map_or("", |value| value.trim())).collect();
+            profile_column(name, &values)
+        })
+        .collect();
+
+    let label = label_from_filename(filename);
+    let merge_on = merge_columns(&columns, &rows);
+    let property = |index: usize| {
+        let column = &columns[index];
+        (
+            cypher_identifier(&column.property),
+            column.load_expression(index, has_header),
+        )
+    };
+    let write = if merge_on.is_empty() {
+        let properties: Vec<String> = (0..columns.len())
+            .map(|index| {
+                let (name, value) = property(index);
+                format!("{name}: {value}")
+            })
+            .collect();
+        format!("CREATE (:{} {{{}}})", cypher_identifier(&label), properties.join(", "))
+    } else {
+        let (keys, rest): (Vec<usize>, Vec<usize>) = (0..columns.len()).partition(|index| merge_on.contains(index));
+        let keys: Vec<String> = keys
+            .into_iter()
+            .map(|index| {
+                let (name, value) = property(index);
+                format!("{name}: {value}")
+            })
+            .collect();
+        let mut write = format!("MERGE (n:{} {{{}}})", cypher_identifier(&label), keys.join(", "));
+        if !rest.is_empty() {
+            let sets: Vec<String> = rest
+                .into_iter()
+                .map(|index| {
+                    let (name, value) = property(index);
+                    format!("n.{name} = {value}")
+                })
+                .collect();
+            write.push_str("\nSET ");
+            write.push_str(&sets.join(", "));
+        }
+        write
+    };
+    let load_csv = format!(
+        "LOAD CSV{} FROM 'file://{}' AS row\n{write}",
+        if has_header { " WITH HEADERS" } else { "" },
+        filename.replace('\'', "\\'"),
+    );
+
+    CsvInspection {
+        encoding,
+        delimiter,
+        has_header,
+        rows_inspected: rows.len(),
+        merge_on: merge_on.iter().map(|index| columns[*index].property.clone()).collect(),
+        columns,
+        label,
+        load_csv,
+    }
+}
+
+/// The indexes of the columns a suggested import merges on: the first `id`-like column (`id`,
+/// `..._id` or `...Id`) whose values are all present and distinct, else every column without
+/// nulls.
+fn merge_columns(
+    columns: &[CsvColumn],
+    rows: &[Vec<String>],
+) -> Vec<usize> {
+    let complete: Vec<usize> = (0..columns.len()).filter(|index| columns[*index].null_rate == 0.0).collect();
+    let id_like = |name: &str| {
+        name.eq_ignore_ascii_case("id") || name.to_ascii_lowercase().ends_with("_id") || name.ends_with("Id")
+    };
+    let key = complete.iter().copied().find(|index| {
+        let mut seen = std::collections::HashSet::new();
+        id_like(&columns[*index].property)
+            && rows
+                .iter()
+                .all(|row| seen.insert(row.get(*index).map_or("", |value| value.trim())))
+    });
+    key.map_or(complete, |key| vec![key])
+}
+
+/// Whether a value is missing.
+fn is_null(value: &str) -> bool {
+    value.is_empty() || value.eq_ignore_ascii_case("null")
+}
+
+/// The narrowest type `value` reads as, `String` when none; dates are told apart by [`date_format`].
+fn value_type(value: &str) -> AttributeType {
+    if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+        AttributeType::Boolean
+    } else if value.parse::<i64>().is_ok() {
+        AttributeType::Integer
+    } else if value.bytes().any(|b| b.is_ascii_digit())
+        && value.bytes().all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+        && value.parse::<f64>().is_ok_and(f64::is_finite)
+    {
+        AttributeType::Float
+    } else {
+        AttributeType::String
+    }
+}
+
+/// Whether a record reads as a header row rather than data.
+fn looks_like_header(record: &[String]) -> bool {
+    let mut seen = std::collections::HashSet::new();
+    record.iter().all(|field| {
+        let field = field.trim();
+        !is_null(field)
+            && seen.insert(field)
+            && matches!(value_type(field), AttributeType::String)
+            && date_format(field).is_none()
+    })
+}
+
+fn profile_column(
+    name: &str,
+    values: &[&str],
+) -> CsvColumn {
+    let present: Vec<&str> = values.iter().copied().filter(|value| !is_null(value)).collect();
+
+    let mut types = present.iter().map(|value| value_type(value));
+    let r#type = types.next().map_or(AttributeType::String, |first| {
+        types.fold(first, |joined, next| match (joined, next) {
+            (AttributeType::Integer, AttributeType::Integer) => AttributeType::Integer,
+            (AttributeType::Integer | AttributeType::Float, AttributeType::Integer | AttributeType::Float) => {
+                AttributeType::Float
+            }
+            (AttributeType::Boolean, AttributeType::Boolean) => AttributeType::Boolean,
+            _ => AttributeType::String,
+        })
+    });
+    let mut formats = present.iter().map(|value| date_format(value));
+    let date_format = match formats.next().flatten() {
+        Some(first) if formats.all(|format| format == Some(first)) => Some(first.to_string()),
+        _ => None,
+    };
+
+    let mut examples: Vec<String> = Vec::new();
+    for value in &present {
+        if examples.len() == MAX_COLUMN_EXAMPLES {
+            break;
+        }
+        if !examples.iter().any(|example| example == value) {
+            examples.push((*value).to_string());
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let null_rate = if values.is_empty() {
+        0.0
+    } else {
+        (values.len() - present.len()) as f64 / values.len() as f64
+    };
+
+    CsvColumn {
+        name: name.to_string(),
+        property: property_name(name),
+        r#type,
+        date_format,
+        null_rate,
+        examples,
+    }
+}
+
+/// A property name for a header: letters, digits and `_`, other runs of characters becoming one `_`.
+fn property_name(header: &str) -> String {
+    let mut property = String::new();
+    for c in header.trim().chars() {
+        if c.is_alphanumeric() || c == '_' {
+            property.push(c);
+        } else if !property.is_empty() && !property.ends_with('_') {
+            property.push('_');
+        }
+    }
+    let property = property.trim_end_matches('_');
+    if property.is_empty() {
+        "value".to_string()
+    } else {
+        property.to_string()
+    }
+}
+
+/// A node label for a file: its name without directories or extension, in `PascalCase`.
+fn label_from_filename(filename: &str) -> String {
+    let stem = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    let stem = stem.rsplit_once('.').map_or(stem, |(stem, _)| stem);
+    let label: String = stem
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+    if label.is_empty() { "Row".to_string() } else { label }
+}
+
+/// `name` as written in Cypher: as is when it is a plain identifier, else backtick-escaped.
+fn cypher_identifier(name: &str) -> String {
+    let plain = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_repeat_the_header_and_keep_quoted_line_breaks() {
+        let csv = "name,bio\nAlice,\"line one\nline two\"\r\nBob,x\n\nCarol,y";
+        let batches = split_csv(csv, 2, true);
+        assert_eq!(
+            batches.iter().map(|batch| batch.text.as_str()).collect::<Vec<_>>(),
+            vec!["name,bio\nAlice,\"line one\nline two\"\nBob,x\n", "name,bio\nCarol,y\n"]
+        );
+        assert_eq!(batches.iter().map(|batch| batch.rows).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(
+            batches.iter().map(|batch| batch.first_row).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(split_csv(csv, 0, false).len(), 4);
+        assert!(split_csv("", 10, true).is_empty());
+        assert!(split_csv("name\n", 10, true).is_empty());
+    }
+
+    #[test]
+    fn halving_keeps_file_row_numbers() {
+        let batch = &split_csv("id\n1\n2\n3\n4\n5\n6\n7\n", 5, true)[1];
+        assert_eq!((batch.first_row, batch.rows), (6, 2));
+
+        let batch = &split_csv("id\n1\n2\n3\n4\n5\n", 5, true)[0];
+        let halves = batch.halves(true);
+        assert_eq!(
+            halves
+                .iter()
+                .map(|half| (half.text.as_str(), half.first_row))
+                .collect::<Vec<_>>(),
+            vec![("id\n1\n2\n3\n", 1), ("id\n4\n5\n", 4)]
+        );
+        let single = &halves[1].halves(true)[1];
+        assert_eq!((single.text.as_str(), single.first_row, single.rows), ("id\n5\n", 5, 1));
+        assert_eq!(single.halves(true), vec![single.clone()]);
+    }
+
+    #[test]
+    fn summary_keeps_the_first_errors() {
+        let mut summary = ImportSummary {
+            rows_loaded: 5,
+            ..ImportSummary::default()
+        };
+        for row in 1..=MAX_REPORTED_ERRORS + 2 {
+            summary.skip_row(row, "bad value");
+        }
+        assert_eq!(summary.rows_skipped, MAX_REPORTED_ERRORS + 2);
+        assert_eq!(summary.errors.len(), MAX_REPORTED_ERRORS);
+        let text = summary.to_string();
+        assert!(text.starts_with("Loaded 5 rows, skipped 12 (0 nodes created"));
+        assert!(text.contains("\nRow 1: bad value"));
+        assert!(text.ends_with("\n...and 2 more"));
+        assert_eq!("Skip".parse(), Ok(OnBatchError::Skip));
+        assert!("retry".parse::<OnBatchError>().is_err());
+    }
+
+    #[test]
+    fn detects_header_reading_queries() {
+        assert!(reads_headers(
+            "LOAD CSV WITH  HEADERS FROM 'file://a.csv' AS row RETURN row"
+        ));
+        assert!(reads_headers(
+            "load csv with\nheaders from 'file://a.csv' as row return row"
+        ));
+        assert!(!reads_headers("LOAD CSV FROM 'file://a.csv' AS row RETURN row[0]"));
+    }
+
+    #[test]
+    fn reads_and_sums_write_statistics() {
+        let stats = [
+            "Nodes created: 3",
+            "Properties set: 6",
+            "Cached execution: 0",
+            "Query internal execution time: 0.5 milliseconds",
+        ]
+        .map(String::from);
+        let mut total = WriteStats::from_stats(&stats);
+        assert_eq!(
+            total,
+            WriteStats {
+                nodes_created: 3,
+                relationships_created: 0,
+                properties_set: 6,
+            }
+        );
+        total.add(WriteStats::from_stats(&["Relationships created: 2".to_string()]));
+        assert_eq!(
+            total.to_string(),
+            "3 nodes created, 2 relationships created, 6 properties set"
+        );
+    }
+
+    #[test]
+    fn decodes_byte_order_marks_and_latin_1() {
+        assert_eq!(
+            decode_csv(b"\xEF\xBB\xBFa,b\n"),
+            ("a,b\n".to_string(), CsvEncoding::Utf8)
+        );
+        assert_eq!(
+            decode_csv(b"\xFF\xFEa\0,\0b\0"),
+            ("a,b".to_string(), CsvEncoding::Utf16Le)
+        );
+        assert_eq!(
+            decode_csv(b"\xFE\xFF\0a\0,\0b"),
+            ("a,b".to_string(), CsvEncoding::Utf16Be)
+        );
+        assert_eq!(decode_csv(b"caf\xE9"), ("caf\u{e9}".to_string(), CsvEncoding::Latin1));
+    }
+
+    #[test]
+    fn parses_quoted_fields_and_detects_delimiters() {
+        assert_eq!(
+            parse_record("1,\"Smith, \"\"Jo\"\"\",\"a\nb\"\r\n", ','),
+            vec!["1", "Smith, \"Jo\"", "a\nb"]
+        );
+        assert_eq!(parse_record("", ','), vec![""]);
+        assert_eq!(detect_delimiter("name;city\nAda;\"London, UK\"\n"), ';');
+        assert_eq!(detect_delimiter("a\tb\tc\n1\t2\t3\n"), '\t');
+        assert_eq!(detect_delimiter("name\nAda\n"), ',');
+        assert_eq!(
+            normalize_csv(b"name;note\nAda;\"a, \"\"b\"\"\"\n\nBob;x\n", None),
+            "name,note\nAda,\"a, \"\"b\"\"\"\nBob,x\n"
+        );
+    }
+
+    #[test]
+    fn inspection_infers_types_nulls_and_a_load_query() {
+        let csv =
+            "id;Full Name;score;active;born\n1;Ada;9.5;true;1815-12-10\n2;;7;false;1906-12-09\n3;Bob;NULL;TRUE;\n";
+        let inspection = inspect_csv(csv.as_bytes(), "uploads/top-people.csv", &InspectOptions::default());
+        assert_eq!(inspection.delimiter, ';');
+        assert!(inspection.has_header);
+        assert_eq!(inspection.rows_inspected, 3);
+        assert_eq!(inspection.label, "TopPeople");
+
+        let columns = &inspection.columns;
+        assert_eq!(
+            columns
+                .iter()
+                .map(|column| (column.property.as_str(), column.r#type.to_string()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("id", "Integer".to_string()),
+                ("Full_Name", "String".to_string()),
+                ("score", "Float".to_string()),
+                ("active", "Boolean".to_string()),
+                ("born", "String".to_string()),
+            ]
+        );
+        assert!((columns[1].null_rate - 1.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(columns[2].examples, vec!["9.5", "7"]);
+        assert_eq!(columns[4].date_format.as_deref(), Some("YYYY-MM-DD"));
+        assert_eq!(
+            inspection.load_csv,
+            "LOAD CSV WITH HEADERS FROM 'file://uploads/top-people.csv' AS row\nMERGE (n:TopPeople {id: \
+             toInteger(row.id)})\nSET n.Full_Name = row.`Full Name`, n.score = toFloat(row.score), n.active = \
+             toBoolean(row.active), n.born = row.born"
+        );
+        assert_eq!(inspection.merge_on, vec!["id"]);
+    }
+
+    #[test]
+    fn inspection_detects_a_missing_header() {
+        let inspection = inspect_csv(
+            b"1,Ada\n2,Bob\n3,Cy\n",
+            "_.csv",
+            &InspectOptions {
+                rows: 2,
+                ..InspectOptions::default()
+            },
+        );
+        assert!(!inspection.has_header);
+        assert_eq!(inspection.rows_inspected, 2);
+        assert_eq!(inspection.label, "Row");
+        assert_eq!(
+            inspection.columns.iter().map(|column| column.name.as_str()).collect::<Vec<_>>(),
+            vec!["column1", "column2"]
+        );
+        assert!(inspection.load_csv.starts_with("LOAD CSV FROM 'file://_.csv' AS row\n"));
+        assert!(
+            inspection
+                .load_csv
+                .ends_with("MERGE (n:Row {column1: toInteger(row[0]), column2: row[1]})")
+        );
+
+        let sparse = inspect_csv(b"a,b\n1,\n,2\n", "_.csv", &InspectOptions::default());
+        assert!(sparse.merge_on.is_empty());
+        assert!(
+            sparse
+                .load_csv
+                .ends_with("CREATE (:Row {a: toInteger(row.a), b: toInteger(row.b)})")
+        );
+
+        let forced = inspect_csv(
+            b"1,Ada\n2,Bob\n",
+            "x.csv",
+            &InspectOptions {
+                has_header: Some(true),
+                ..InspectOptions::default()
+            },
+        );
+        assert_eq!(forced.columns[1].name, "Ada");
+        assert_eq!(forced.rows_inspected, 1);
+    }
+}
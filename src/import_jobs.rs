@@ -0,0 +1,174 @@
+//! Progress of streamed CSV imports, kept so an interrupted import can resume.
+//!
+//! Every `/graph_query_upload/{graph}/stream` import is an [`ImportJob`] saved in the shared
+//! [`Storage`] after each batch. When the import fails, or the server stops mid-way, re-sending the
+//! same file and query with the job's ID resumes it at the first unfinished batch instead of
+//! loading the file from the start. A failing `LOAD CSV` batch writes nothing, so only a batch cut
+//! short by a restart can run twice; a query that merges its nodes (as the one suggested by
+//! [`inspect_csv`](crate::import::inspect_csv) does) keeps that re-run from duplicating them.
+
+use crate::import::ImportSummary;
+use crate::storage::{self, Storage, StorageError};
+use aws_lc_rs::digest;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Storage namespace holding import jobs.
+pub const IMPORT_JOB_NAMESPACE: &str = "import_jobs";
+
+/// How long an import job can be looked up and resumed after its last batch.
+pub const IMPORT_JOB_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Where an import job stands.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportJobStatus {
+    /// Batches are loading, or the server stopped before the job ended.
+    Running,
+    /// A batch failed; the batches before it are loaded.
+    Failed,
+    Completed,
+}
+
+/// A streamed import and how far it got.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct ImportJob {
+    pub job_id: String,
+    pub graph_name: String,
+    pub cypher_query: String,
+    /// Hex SHA-256 of the imported CSV; a resumed job must be sent the same file.
+    pub sha256: String,
+    /// Records per batch; a resumed job keeps its batches.
+    pub batch_rows: usize,
+    pub batches: usize,
+    /// Batches finished; a resumed job starts with the next one.
+    pub batches_done: usize,
+    pub status: ImportJobStatus,
+    /// Rows loaded and skipped, and changes made, by the finished batches.
+    pub summary: ImportSummary,
+    /// Why the job failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix timestamp (seconds) of the last update.
+    pub updated_at: u64,
+}
+
+impl ImportJob {
+    /// Why this job cannot resume as an import of `csv` into `graph_name` with `cypher_query`, if
+    /// it cannot.
+    #[must_use]
+    pub fn resume_conflict(
+        &self,
+        graph_name: &str,
+        cypher_query: &str,
+        csv: &str,
+    ) -> Option<String> {
+        if self.graph_name != graph_name {
+            Some(format!(
+                "Import job '{}' imports into graph '{}'",
+                self.job_id, self.graph_name
+            ))
+        } else if self.cypher_query != cypher_query {
+            Some(format!("Import job '{}' was started with another query", self.job_id))
+        } else if self.sha256 != content_sha256(csv) {
+            Some(format!("Import job '{}' was started with another file", self.job_id))
+        } else {
+            None
+        }
+    }
+}
+
+/// Hex SHA-256 of an import's CSV text.
+#[must_use]
+pub fn content_sha256(csv: &str) -> String {
+    let hash = digest::digest(&digest::SHA256, csv.as_bytes());
+    hash.as_ref().iter().fold(String::with_capacity(64), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+/// Saves and loads import jobs.
+#[derive(Debug, Clone)]
+pub struct ImportJobStore {
+    storage: Arc<dyn Storage>,
+}
+
+impl ImportJobStore {
+    /// Creates a store backed by `storage`.
+    #[must_use]
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Saves `job`, stamping its update time.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the job cannot be saved.
+    pub async fn save(
+        &self,
+        job: &mut ImportJob,
+    ) -> Result<(), StorageError> {
+        job.updated_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        storage::put_json(
+            self.storage.as_ref(),
+            IMPORT_JOB_NAMESPACE,
+            &job.job_id,
+            job,
+            Some(IMPORT_JOB_TTL),
+        )
+        .await
+    }
+
+    /// The job with `job_id`, or `None` when it is unknown or expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the job cannot be read.
+    pub async fn get(
+        &self,
+        job_id: &str,
+    ) -> Result<Option<ImportJob>, StorageError> {
+        storage::get_json(self.storage.as_ref(), IMPORT_JOB_NAMESPACE, job_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn jobs_resume_only_with_the_same_graph_query_and_file() {
+        let store = ImportJobStore::new(Arc::new(InMemoryStorage::new()));
+        let csv = "id\n1\n2\n";
+        let query = "LOAD CSV WITH HEADERS FROM 'file://a.csv' AS row MERGE (:A {id: row.id})";
+        let mut job = ImportJob {
+            job_id: "job-1".to_string(),
+            graph_name: "g".to_string(),
+            cypher_query: query.to_string(),
+            sha256: content_sha256(csv),
+            batch_rows: 1,
+            batches: 2,
+            batches_done: 1,
+            status: ImportJobStatus::Failed,
+            summary: ImportSummary::default(),
+            error: Some("timeout".to_string()),
+            updated_at: 0,
+        };
+        store.save(&mut job).await.unwrap();
+        assert!(job.updated_at > 0);
+
+        let saved = store.get("job-1").await.unwrap().unwrap();
+        assert_eq!(saved, job);
+        assert_eq!(saved.resume_conflict("g", query, csv), None);
+        assert!(saved.resume_conflict("other", query, csv).is_some());
+        assert!(saved.resume_conflict("g", "MATCH (n) RETURN n", csv).is_some());
+        assert!(saved.resume_conflict("g", query, "id\n1\n3\n").is_some());
+        assert_eq!(store.get("unknown").await.unwrap(), None);
+    }
+}
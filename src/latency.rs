@@ -0,0 +1,168 @@
+//! Latency modes: one knob that trades answer quality for speed.
+//!
+//! - [`LatencyMode::Fast`] uses the fast model when one is configured, sends a compact schema
+//!   without example values, and never retries: a query that fails validation or execution fails
+//!   the request.
+//! - [`LatencyMode::Balanced`] (the default) is the regular pipeline.
+//! - [`LatencyMode::Thorough`] generates several candidate queries and keeps the best one. A
+//!   candidate must be valid, pass `GRAPH.EXPLAIN` and have the fewest lint hints. The final answer
+//!   is then checked against the query result, and a warning is reported if it makes
+//!   unsupported claims.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Candidate queries generated per request in [`LatencyMode::Thorough`].
+pub const THOROUGH_CANDIDATES: usize = 3;
+
+/// How much latency a request trades for answer quality.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum LatencyMode {
+    /// Fast model, compact schema, no retries.
+    Fast,
+    /// The regular pipeline.
+    #[default]
+    Balanced,
+    /// N-best candidates with `EXPLAIN` validation, plus an answer faithfulness check.
+    Thorough,
+}
+
+impl LatencyMode {
+    /// Whether failed validation or execution is retried with error feedback.
+    #[must_use]
+    pub const fn retries(self) -> bool {
+        !matches!(self, Self::Fast)
+    }
+
+    /// Whether example values are stripped from the schema sent to the model.
+    #[must_use]
+    pub const fn compact_schema(self) -> bool {
+        matches!(self, Self::Fast)
+    }
+
+    /// How many candidate queries to generate.
+    #[must_use]
+    pub const fn candidates(self) -> usize {
+        match self {
+            Self::Thorough => THOROUGH_CANDIDATES,
+            Self::Fast | Self::Balanced => 1,
+        }
+    }
+
+    /// Whether the answer is checked against the query result.
+    #[must_use]
+    pub const fn checks_faithfulness(self) -> bool {
+        matches!(self, Self::Thorough)
+    }
+}
+
+/// Removes attribute example values from a schema JSON document, which usually makes up most of
+/// its size. Returns the input unchanged if it is not JSON.
+#[must_use]
+pub fn compact_schema(schema: &str) -> String {
+    fn strip_examples(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                map.remove("examples");
+                map.values_mut().for_each(strip_examples);
+            }
+            serde_json::Value::Array(values) => values.iter_mut().for_each(strip_examples),
+            _ => {}
+        }
+    }
+
+    serde_json::from_str::<serde_json::Value>(schema).map_or_else(
+        |_| schema.to_string(),
+        |mut value| {
+            strip_examples(&mut value);
+            value.to_string()
+        },
+    )
+}
+
+/// Whether an answer is supported by the query result it was derived from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Faithfulness {
+    Supported,
+    /// The answer makes a claim the result does not support, described when the model said which.
+    Unsupported(Option<String>),
+}
+
+impl Faithfulness {
+    /// Parses the faithfulness check reply (`SUPPORTED` or `UNSUPPORTED: <claim>`). A reply that is
+    /// neither is treated as supported, so a confused checker never flags a good answer.
+    #[must_use]
+    pub fn parse(reply: &str) -> Self {
+        let reply = reply.trim();
+        let Some(rest) = reply
+            .get(.."UNSUPPORTED".len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case("UNSUPPORTED"))
+            .map(|_| &reply["UNSUPPORTED".len()..])
+        else {
+            return Self::Supported;
+        };
+        let claim = rest
+            .trim_start_matches([':', '-', ' '])
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim();
+        Self::Unsupported(Some(claim.to_string()).filter(|claim| !claim.is_empty()))
+    }
+
+    /// The warning to report for an unsupported answer.
+    #[must_use]
+    pub fn warning(&self) -> Option<String> {
+        match self {
+            Self::Supported => None,
+            Self::Unsupported(Some(claim)) => {
+                Some(format!("The answer may not be supported by the query result: {claim}"))
+            }
+            Self::Unsupported(None) => Some("The answer may not be supported by the query result".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_tune_the_pipeline() {
+        assert_eq!(LatencyMode::default(), LatencyMode::Balanced);
+        assert!(!LatencyMode::Fast.retries() && LatencyMode::Fast.compact_schema());
+        assert_eq!(LatencyMode::Balanced.candidates(), 1);
+        assert_eq!(LatencyMode::Thorough.candidates(), THOROUGH_CANDIDATES);
+        assert!(LatencyMode::Thorough.checks_faithfulness() && !LatencyMode::Balanced.checks_faithfulness());
+        assert_eq!(
+            serde_json::from_str::<LatencyMode>("\"thorough\"").unwrap(),
+            LatencyMode::Thorough
+        );
+    }
+
+    #[test]
+    fn compact_schema_drops_examples() {
+        let schema =
+            r#"{"entities":[{"label":"Movie","attributes":[{"name":"title","type":"String","examples":["Heat"]}]}]}"#;
+        assert_eq!(
+            compact_schema(schema),
+            r#"{"entities":[{"attributes":[{"name":"title","type":"String"}],"label":"Movie"}]}"#
+        );
+        assert_eq!(compact_schema("not json"), "not json");
+    }
+
+    #[test]
+    fn parses_faithfulness_replies() {
+        assert_eq!(Faithfulness::parse("SUPPORTED"), Faithfulness::Supported);
+        assert_eq!(Faithfulness::parse("Looks fine"), Faithfulness::Supported);
+        assert_eq!(
+            Faithfulness::parse("UNSUPPORTED: the count of 12 movies\nmore"),
+            Faithfulness::Unsupported(Some("the count of 12 movies".into()))
+        );
+        assert_eq!(Faithfulness::parse("unsupported"), Faithfulness::Unsupported(None));
+        assert!(Faithfulness::Supported.warning().is_none());
+    }
+}
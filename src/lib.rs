@@ -155,32 +155,98 @@
 //! ```
 
 // Core modules - always available
+pub mod aliases;
+pub mod auth;
+pub mod benchmark;
 pub mod chat;
+pub mod connection;
 pub mod core;
+pub mod cypher_syntax;
+pub mod engine;
 pub mod error;
+pub mod few_shot;
 pub mod formatter;
+pub mod hooks;
+pub mod import;
+pub mod latency;
+pub mod model_chain;
+pub mod model_options;
 pub mod models_catalog;
+pub mod pool;
 pub mod processor;
+pub mod prompts;
+pub mod query_result;
+pub mod routing;
 pub mod schema;
+pub mod search;
+pub mod shadow;
 pub mod skills;
+pub mod stats;
+pub mod storage;
+pub mod streaming;
+pub mod suggest;
 pub mod template;
+pub mod tenancy;
 pub mod udf;
 pub mod usage;
+pub mod validation_rules;
 pub mod validator;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
 
 // Re-export commonly used types for easier access
+pub use aliases::{GraphAlias, GraphAliases};
 pub use chat::{ChatMessage, ChatRequest, ChatRole};
-pub use error::ErrorResponse;
+pub use core::GenerationOutcome;
+pub use error::{ErrorResponse, TextToCypherError};
 pub use genai::adapter::AdapterKind;
+pub use latency::LatencyMode;
+pub use model_chain::ModelChain;
+pub use model_options::ModelOptions;
 pub use processor::{
-    TextToCypherRequest, TextToCypherResponse, process_text_to_cypher_with_context, process_text_to_cypher_with_skills,
+    ProcessorOptions, SchemaCache, TextToCypherRequest, TextToCypherResponse, process_text_to_cypher_stream,
+    process_text_to_cypher_with_context, process_text_to_cypher_with_options, process_text_to_cypher_with_skills,
 };
+pub use query_result::{QueryResult, ResultEdge, ResultFormat, ResultNode, ResultPath, ResultValue};
+pub use schema::discovery::DiscoveryOptions;
 pub use skills::{SkillCatalog, SkillProfile};
+pub use streaming::{Progress, StreamStatus, Warning, WarningCode};
+pub use template::Audience;
 pub use udf::{UdfCatalog, UdfError, UdfFunction, UdfLibrary, UdfSource};
 pub use usage::TokenUsage;
 // Server-specific modules - only when server feature is enabled
 #[cfg(feature = "server")]
+pub mod answer_cache;
+#[cfg(feature = "server")]
+pub mod audit;
+#[cfg(feature = "server")]
+pub mod debug_bundle;
+#[cfg(feature = "server")]
+pub mod dry_run;
+#[cfg(feature = "server")]
+pub mod event_signing;
+#[cfg(feature = "server")]
+pub mod graph_backup;
+#[cfg(feature = "server")]
+pub mod graph_trash;
+#[cfg(feature = "server")]
+pub mod import_jobs;
+#[cfg(feature = "server")]
 pub mod mcp;
+#[cfg(feature = "server")]
+pub mod notebook;
+#[cfg(feature = "server")]
+pub mod quota;
+#[cfg(feature = "server")]
+pub mod remote_file;
+#[cfg(feature = "server")]
+pub mod request_queue;
+#[cfg(feature = "server")]
+pub mod schema_store;
+#[cfg(feature = "server")]
+pub mod session;
+#[cfg(feature = "server")]
+pub mod usage_report;
 
 /// A high-level client for text-to-cypher operations.
 ///
@@ -213,6 +279,7 @@ pub mod mcp;
 ///     Ok(())
 /// }
 /// ```
+#[allow(clippy::struct_excessive_bools)]
 pub struct TextToCypherClient {
     model: String,
     api_key: String,
@@ -220,6 +287,21 @@ pub struct TextToCypherClient {
     llm_endpoint: Option<String>,
     skill_catalog: Option<SkillCatalog>,
     udf_source: UdfSource,
+    audience: Option<Audience>,
+    followups: bool,
+    strict_validation: bool,
+    refine_lint_hints: bool,
+    parameterized: bool,
+    max_var_length: Option<u32>,
+    graph_aliases: GraphAliases,
+    latency_mode: LatencyMode,
+    fast_model: Option<String>,
+    fallback_models: Vec<String>,
+    model_options: ModelOptions,
+    discovery: DiscoveryOptions,
+    schema: Option<serde_json::Value>,
+    graphs: Vec<String>,
+    options: ProcessorOptions,
 }
 
 impl TextToCypherClient {
@@ -260,9 +342,49 @@ impl TextToCypherClient {
             llm_endpoint: None,
             skill_catalog: Some(SkillCatalog::builtin()),
             udf_source: UdfSource::Off,
+            audience: None,
+            followups: false,
+            strict_validation: false,
+            refine_lint_hints: false,
+            parameterized: false,
+            max_var_length: None,
+            graph_aliases: GraphAliases::default(),
+            latency_mode: LatencyMode::Balanced,
+            fast_model: None,
+            fallback_models: Vec::new(),
+            model_options: ModelOptions::default(),
+            discovery: DiscoveryOptions::default(),
+            schema: None,
+            graphs: Vec::new(),
+            options: ProcessorOptions::default(),
         }
     }
 
+    /// Starts a [`TextToCypherClientBuilder`], which also sets per-step timeouts, the self-healing
+    /// budget and a schema cache.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use text_to_cypher::TextToCypherClient;
+    ///
+    /// let client = TextToCypherClient::builder()
+    ///     .model("gpt-4o-mini")
+    ///     .api_key("your-api-key")
+    ///     .falkordb_connection("falkor://127.0.0.1:6379")
+    ///     .generation_timeout(Duration::from_secs(30))
+    ///     .execution_timeout(Duration::from_secs(10))
+    ///     .max_heal_attempts(2)
+    ///     .schema_cache_ttl(Duration::from_secs(300))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub fn builder() -> TextToCypherClientBuilder {
+        TextToCypherClientBuilder::default()
+    }
+
     /// Sets a custom LLM provider endpoint/base URL.
     ///
     /// This is useful for OpenAI-compatible local providers such as LM Studio
@@ -382,6 +504,295 @@ impl TextToCypherClient {
         self
     }
 
+    /// Writes answers for `audience` (technical, analyst, or executive) instead of using the default
+    /// answer prompt.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use text_to_cypher::{Audience, TextToCypherClient};
+    ///
+    /// let client = TextToCypherClient::new("gpt-4o-mini", "key", "falkor://127.0.0.1:6379")
+    ///     .with_audience(Audience::Executive);
+    /// ```
+    #[must_use]
+    pub const fn with_audience(
+        mut self,
+        audience: Audience,
+    ) -> Self {
+        self.audience = Some(audience);
+        self
+    }
+
+    /// Suggests 2–3 follow-up questions with each answer, returned in
+    /// [`TextToCypherResponse::followups`]. Costs one extra LLM call per answered request.
+    #[must_use]
+    pub const fn with_followups(
+        mut self,
+        enabled: bool,
+    ) -> Self {
+        self.followups = enabled;
+        self
+    }
+
+    /// Refuses queries without RETURN or LIMIT, or using labels missing from the schema, instead of
+    /// executing them. Like any query failing validation, they are first regenerated once with the
+    /// errors; without strict validation a query still failing runs with a `validation_failed`
+    /// warning.
+    #[must_use]
+    pub const fn with_strict_validation(
+        mut self,
+        enabled: bool,
+    ) -> Self {
+        self.strict_validation = enabled;
+        self
+    }
+
+    /// Gives the model one chance to rewrite a query with lint hints (cartesian products, undirected
+    /// relationships, unbounded variable-length paths, OPTIONAL MATCH misuse). The rewrite is kept
+    /// only if it validates and has fewer hints; costs one extra LLM call per query with hints.
+    #[must_use]
+    pub const fn with_lint_refinement(
+        mut self,
+        enabled: bool,
+    ) -> Self {
+        self.refine_lint_hints = enabled;
+        self
+    }
+
+    /// Has the model write values from the question as `$name` parameters rather than literals, so
+    /// `FalkorDB` can reuse the plan of a query asked again with other values. The values are sent in
+    /// the query's `CYPHER name=value` preamble, encoded by the `FalkorDB` client.
+    #[must_use]
+    pub const fn with_parameterized_queries(
+        mut self,
+        enabled: bool,
+    ) -> Self {
+        self.parameterized = enabled;
+        self
+    }
+
+    /// Caps variable-length patterns at `max_depth` hops: `[*]` becomes `[*1..max_depth]`, and each
+    /// rewrite is listed in the response's `warnings`.
+    #[must_use]
+    pub const fn with_max_var_length(
+        mut self,
+        max_depth: u32,
+    ) -> Self {
+        self.max_var_length = Some(max_depth);
+        self
+    }
+
+    /// Lets callers name graphs by alias: a graph name matching an alias is replaced with the
+    /// physical graph it points to before any request is made.
+    #[must_use]
+    pub fn with_graph_aliases(
+        mut self,
+        aliases: GraphAliases,
+    ) -> Self {
+        self.graph_aliases = aliases;
+        self
+    }
+
+    /// Trades answer quality for latency on every request (see [`LatencyMode`]).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use text_to_cypher::{LatencyMode, TextToCypherClient};
+    ///
+    /// let client = TextToCypherClient::new("gpt-4o", "key", "falkor://127.0.0.1:6379")
+    ///     .with_latency_mode(LatencyMode::Fast)
+    ///     .with_fast_model("gpt-4o-mini");
+    /// ```
+    #[must_use]
+    pub const fn with_latency_mode(
+        mut self,
+        mode: LatencyMode,
+    ) -> Self {
+        self.latency_mode = mode;
+        self
+    }
+
+    /// Uses `model`, typically a cheaper one, instead of the client's model in
+    /// [`LatencyMode::Fast`].
+    #[must_use]
+    pub fn with_fast_model(
+        mut self,
+        model: impl Into<String>,
+    ) -> Self {
+        self.fast_model = Some(model.into());
+        self
+    }
+
+    /// Tries `models` in order when a model call writing the query fails, e.g. on a rate limit or
+    /// a timeout. The model that writes the query serves the rest of the request and is reported
+    /// in [`TextToCypherResponse::model`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use text_to_cypher::TextToCypherClient;
+    ///
+    /// let client = TextToCypherClient::new("gpt-4o-mini", "key", "falkor://127.0.0.1:6379")
+    ///     .with_fallback_models(["anthropic:claude-3-haiku"]);
+    /// ```
+    #[must_use]
+    pub fn with_fallback_models(
+        mut self,
+        models: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.fallback_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the temperature, output token limit and nucleus sampling of the calls that write the
+    /// query and the answer (see [`ModelOptions`]). A temperature of 0 makes query generation close
+    /// to deterministic.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use text_to_cypher::{ModelOptions, TextToCypherClient};
+    ///
+    /// let client = TextToCypherClient::new("gpt-4o-mini", "key", "falkor://127.0.0.1:6379")
+    ///     .with_model_options(ModelOptions::default().with_temperature(0.0).with_max_tokens(1024));
+    /// ```
+    #[must_use]
+    pub const fn with_model_options(
+        mut self,
+        options: ModelOptions,
+    ) -> Self {
+        self.model_options = options;
+        self
+    }
+
+    /// Sets the sample size of schema discovery, and whether relationships are paired with the
+    /// labels they connect (see [`DiscoveryOptions`]). Turning pairing off speeds up discovery of
+    /// huge graphs.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use text_to_cypher::{DiscoveryOptions, TextToCypherClient};
+    ///
+    /// let client = TextToCypherClient::new("gpt-4o-mini", "key", "falkor://127.0.0.1:6379").with_discovery_options(
+    ///     DiscoveryOptions {
+    ///         sample_size: 20,
+    ///         pair_relationships: false,
+    ///     },
+    /// );
+    /// ```
+    #[must_use]
+    pub const fn with_discovery_options(
+        mut self,
+        options: DiscoveryOptions,
+    ) -> Self {
+        self.discovery = options;
+        self
+    }
+
+    /// Sends `schema` to the model instead of discovering the graph's schema, for curated
+    /// ontologies or graphs too large to sample. A JSON string is used as is; any other value is
+    /// serialized.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use serde_json::json;
+    /// use text_to_cypher::TextToCypherClient;
+    ///
+    /// let client = TextToCypherClient::new("gpt-4o-mini", "key", "falkor://127.0.0.1:6379").with_schema(json!({
+    ///     "entities": [{"label": "Person", "attributes": [{"name": "name", "type": "String"}]}],
+    ///     "relations": [{"label": "KNOWS", "source": "Person", "target": "Person"}]
+    /// }));
+    /// ```
+    #[must_use]
+    pub fn with_schema(
+        mut self,
+        schema: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.schema = Some(schema.into());
+        self
+    }
+
+    /// Limits the graphs a question is routed to when it is asked with an empty graph name. By
+    /// default the model chooses among every graph on the server.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use text_to_cypher::{TextToCypherClient, ChatRequest, ChatMessage, ChatRole};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    /// let client = TextToCypherClient::new("gpt-4o-mini", "key", "falkor://127.0.0.1:6379")
+    ///     .with_graphs(["movies", "crm"]);
+    /// let request = ChatRequest {
+    ///     messages: vec![ChatMessage {
+    ///         role: ChatRole::User,
+    ///         content: "Who directed The Matrix?".to_string(),
+    ///     }],
+    /// };
+    /// let response = client.text_to_cypher("", request).await?;
+    /// println!("Answered from {:?}", response.graph_name);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_graphs(
+        mut self,
+        graphs: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.graphs = graphs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Builds the processor request shared by [`text_to_cypher`](Self::text_to_cypher) and
+    /// [`cypher_only`](Self::cypher_only).
+    fn build_request(
+        &self,
+        graph_name: &str,
+        chat_request: ChatRequest,
+        cypher_only: bool,
+    ) -> TextToCypherRequest {
+        TextToCypherRequest {
+            graph_name: self.graph_aliases.resolve(graph_name).to_string(),
+            graphs: self
+                .graphs
+                .iter()
+                .map(|graph| self.graph_aliases.resolve(graph).to_string())
+                .collect(),
+            chat_request,
+            model: Some(
+                self.fallback_models.iter().fold(
+                    ModelChain::new(
+                        self.fast_model
+                            .clone()
+                            .filter(|_| self.latency_mode == LatencyMode::Fast)
+                            .unwrap_or_else(|| self.model.clone()),
+                    ),
+                    |chain, model| chain.with_fallback(model.clone()),
+                ),
+            ),
+            key: Some(self.api_key.clone()),
+            falkordb_connection: Some(self.falkordb_connection.clone()),
+            llm_endpoint: self.llm_endpoint.clone(),
+            cypher_only,
+            dry_run: false,
+            audience: self.audience,
+            followups: self.followups,
+            strict_validation: self.strict_validation,
+            refine_lint_hints: self.refine_lint_hints,
+            max_var_length: self.max_var_length,
+            latency_mode: self.latency_mode,
+            query_vector: None,
+            parameterized: self.parameterized,
+            discovery: self.discovery,
+            model_options: self.model_options,
+            schema: self.schema.clone(),
+        }
+    }
+
     /// Converts natural language text to Cypher and executes the query.
     ///
     /// This is the main method for full text-to-cypher processing:
@@ -390,6 +801,9 @@ impl TextToCypherClient {
     /// 3. Executes the query
     /// 4. Generates a natural language answer
     ///
+    /// A question the schema cannot answer is not an error: the response then has status
+    /// `no_answer` and a [`TextToCypherResponse::no_answer_reason`].
+    ///
     /// # Arguments
     ///
     /// * `graph_name` - Name of the graph to query
@@ -397,7 +811,8 @@ impl TextToCypherClient {
     ///
     /// # Errors
     ///
-    /// Returns an error if schema discovery, query generation, execution, or answer generation fails.
+    /// Returns a [`TextToCypherError`] naming the step that failed: schema discovery, query
+    /// generation, validation, execution, or answer generation.
     ///
     /// # Example
     ///
@@ -423,38 +838,78 @@ impl TextToCypherClient {
         &self,
         graph_name: impl Into<String>,
         request: ChatRequest,
-    ) -> Result<TextToCypherResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let req = TextToCypherRequest {
-            graph_name: graph_name.into(),
-            chat_request: request,
-            model: Some(self.model.clone()),
-            key: Some(self.api_key.clone()),
-            falkordb_connection: Some(self.falkordb_connection.clone()),
-            llm_endpoint: self.llm_endpoint.clone(),
-            cypher_only: false,
-        };
+    ) -> Result<TextToCypherResponse, TextToCypherError> {
+        let req = self.build_request(&graph_name.into(), request, false);
 
-        let response = processor::process_text_to_cypher_with_context(
+        processor::process_text_to_cypher_with_options(
             req,
             Some(self.model.clone()),
             Some(self.api_key.clone()),
             self.falkordb_connection.clone(),
             self.skill_catalog.as_ref(),
             &self.udf_source,
+            &self.options,
         )
-        .await;
-
-        if response.is_error() {
-            return Err(response.error.unwrap_or_else(|| "Unknown error".to_string()).into());
-        }
+        .await
+        .into_result()
+    }
 
-        Ok(response)
+    /// Like [`Self::text_to_cypher`], but reports each step as it happens, as the server's
+    /// `/text_to_cypher` events do: statuses, the schema, the query, its result, and the answer in
+    /// `ModelOutputChunk`s as the model writes it. Failures arrive as an `Error` event; every stream
+    /// ends with `Done`. See [`processor::process_text_to_cypher_stream`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use text_to_cypher::{TextToCypherClient, ChatRequest, ChatMessage, ChatRole, Progress};
+    /// use futures::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let client = TextToCypherClient::new("gpt-4o-mini", "key", "falkor://127.0.0.1:6379");
+    /// let request = ChatRequest {
+    ///     messages: vec![
+    ///         ChatMessage {
+    ///             role: ChatRole::User,
+    ///             content: "Find all actors".to_string(),
+    ///         }
+    ///     ]
+    /// };
+    ///
+    /// let mut events = std::pin::pin!(client.text_to_cypher_stream("movies", request));
+    /// while let Some(event) = events.next().await {
+    ///     match event {
+    ///         Progress::Status(status) => println!("{status}"),
+    ///         Progress::ModelOutputChunk(chunk) => print!("{chunk}"),
+    ///         Progress::Error(error) => eprintln!("{error}"),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn text_to_cypher_stream(
+        &self,
+        graph_name: impl Into<String>,
+        request: ChatRequest,
+    ) -> impl futures::Stream<Item = Progress> + Send + '_ {
+        let req = self.build_request(&graph_name.into(), request, false);
+        processor::process_text_to_cypher_stream(
+            req,
+            Some(self.model.clone()),
+            Some(self.api_key.clone()),
+            self.falkordb_connection.clone(),
+            self.skill_catalog.as_ref(),
+            &self.udf_source,
+            &self.options,
+        )
     }
 
     /// Generates a Cypher query without executing it.
     ///
     /// Use this method when you only want to generate the query for inspection
-    /// or manual execution.
+    /// or manual execution. As with [`Self::text_to_cypher`], a question the schema cannot answer
+    /// yields a `no_answer` response rather than an error.
     ///
     /// # Arguments
     ///
@@ -463,7 +918,7 @@ impl TextToCypherClient {
     ///
     /// # Errors
     ///
-    /// Returns an error if schema discovery or query generation fails.
+    /// Returns a [`TextToCypherError`] if schema discovery, query generation, or validation fails.
     ///
     /// # Example
     ///
@@ -490,32 +945,49 @@ impl TextToCypherClient {
         &self,
         graph_name: impl Into<String>,
         request: ChatRequest,
-    ) -> Result<TextToCypherResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let req = TextToCypherRequest {
-            graph_name: graph_name.into(),
-            chat_request: request,
-            model: Some(self.model.clone()),
-            key: Some(self.api_key.clone()),
-            falkordb_connection: Some(self.falkordb_connection.clone()),
-            llm_endpoint: self.llm_endpoint.clone(),
-            cypher_only: true,
-        };
+    ) -> Result<TextToCypherResponse, TextToCypherError> {
+        let req = self.build_request(&graph_name.into(), request, true);
 
-        let response = processor::process_text_to_cypher_with_context(
+        processor::process_text_to_cypher_with_options(
             req,
             Some(self.model.clone()),
             Some(self.api_key.clone()),
             self.falkordb_connection.clone(),
             self.skill_catalog.as_ref(),
             &self.udf_source,
+            &self.options,
         )
-        .await;
+        .await
+        .into_result()
+    }
 
-        if response.is_error() {
-            return Err(response.error.unwrap_or_else(|| "Unknown error".to_string()).into());
-        }
+    /// Generates and validates a Cypher query like [`text_to_cypher`](Self::text_to_cypher), then
+    /// plans it with `GRAPH.EXPLAIN` instead of executing it, so the query and its plan can be
+    /// reviewed before anything runs. The plan is in [`TextToCypherResponse::query_plan`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`TextToCypherError`] of the step that failed: schema discovery, generation,
+    /// validation, or [`TextToCypherError::Execution`] when the query cannot be planned.
+    pub async fn dry_run(
+        &self,
+        graph_name: impl Into<String>,
+        request: ChatRequest,
+    ) -> Result<TextToCypherResponse, TextToCypherError> {
+        let mut req = self.build_request(&graph_name.into(), request, false);
+        req.dry_run = true;
 
-        Ok(response)
+        processor::process_text_to_cypher_with_options(
+            req,
+            Some(self.model.clone()),
+            Some(self.api_key.clone()),
+            self.falkordb_connection.clone(),
+            self.skill_catalog.as_ref(),
+            &self.udf_source,
+            &self.options,
+        )
+        .await
+        .into_result()
     }
 
     /// Discovers and returns the schema of a graph.
@@ -526,7 +998,7 @@ impl TextToCypherClient {
     ///
     /// # Errors
     ///
-    /// Returns an error if schema discovery fails.
+    /// Returns [`TextToCypherError::SchemaDiscovery`] if schema discovery fails.
     ///
     /// # Example
     ///
@@ -543,8 +1015,9 @@ impl TextToCypherClient {
     pub async fn discover_schema(
         &self,
         graph_name: impl Into<String>,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        core::discover_graph_schema(&self.falkordb_connection, &graph_name.into()).await
+    ) -> Result<String, TextToCypherError> {
+        core::discover_graph_schema_with_options(&self.falkordb_connection, &graph_name.into(), self.discovery, &|_| {})
+            .await
     }
 
     /// Lists all available model names for a specific AI provider
@@ -633,6 +1106,328 @@ impl TextToCypherClient {
     }
 }
 
+/// Builds a [`TextToCypherClient`] with tuned processing; see [`TextToCypherClient::builder`].
+///
+/// Only the model is required. The connection defaults to `falkor://127.0.0.1:6379`, and the
+/// processing defaults to no timeouts, two self-healing attempts and no schema cache. Everything
+/// else is set on the built client with its `with_*` methods.
+#[derive(Debug, Clone)]
+pub struct TextToCypherClientBuilder {
+    model: Option<String>,
+    fallback_models: Vec<String>,
+    model_options: ModelOptions,
+    api_key: String,
+    falkordb_connection: String,
+    discovery: DiscoveryOptions,
+    options: ProcessorOptions,
+}
+
+impl Default for TextToCypherClientBuilder {
+    fn default() -> Self {
+        Self {
+            model: None,
+            fallback_models: Vec::new(),
+            model_options: ModelOptions::default(),
+            api_key: String::new(),
+            falkordb_connection: "falkor://127.0.0.1:6379".to_string(),
+            discovery: DiscoveryOptions::default(),
+            options: ProcessorOptions::default(),
+        }
+    }
+}
+
+impl TextToCypherClientBuilder {
+    #[must_use]
+    pub fn model(
+        mut self,
+        model: impl Into<String>,
+    ) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Models tried in order after [`model`](Self::model) fails; see
+    /// [`TextToCypherClient::with_fallback_models`].
+    #[must_use]
+    pub fn fallback_models(
+        mut self,
+        models: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.fallback_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the temperature, output token limit and nucleus sampling of the query and answer calls
+    /// (see [`TextToCypherClient::with_model_options`]).
+    #[must_use]
+    pub const fn model_options(
+        mut self,
+        options: ModelOptions,
+    ) -> Self {
+        self.model_options = options;
+        self
+    }
+
+    #[must_use]
+    pub fn api_key(
+        mut self,
+        api_key: impl Into<String>,
+    ) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    #[must_use]
+    pub fn falkordb_connection(
+        mut self,
+        falkordb_connection: impl Into<String>,
+    ) -> Self {
+        self.falkordb_connection = falkordb_connection.into();
+        self
+    }
+
+    /// Sets the sample size and relationship pairing of schema discovery (see
+    /// [`TextToCypherClient::with_discovery_options`]).
+    #[must_use]
+    pub const fn discovery_options(
+        mut self,
+        options: DiscoveryOptions,
+    ) -> Self {
+        self.discovery = options;
+        self
+    }
+
+    /// Fails a request whose schema discovery takes longer than `timeout`.
+    #[must_use]
+    pub const fn schema_discovery_timeout(
+        mut self,
+        timeout: std::time::Duration,
+    ) -> Self {
+        self.options.schema_discovery_timeout = Some(timeout);
+        self
+    }
+
+    /// Fails a request when a model call writing a query or the answer takes longer than
+    /// `timeout`.
+    #[must_use]
+    pub const fn generation_timeout(
+        mut self,
+        timeout: std::time::Duration,
+    ) -> Self {
+        self.options.generation_timeout = Some(timeout);
+        self
+    }
+
+    /// Fails a query execution that takes longer than `timeout`, which then counts as a failed
+    /// query for self-healing.
+    #[must_use]
+    pub const fn execution_timeout(
+        mut self,
+        timeout: std::time::Duration,
+    ) -> Self {
+        self.options.execution_timeout = Some(timeout);
+        self
+    }
+
+    /// Regenerates a failed query up to `attempts` times (`0` disables self-healing).
+    #[must_use]
+    pub const fn max_heal_attempts(
+        mut self,
+        attempts: usize,
+    ) -> Self {
+        self.options.max_heal_attempts = attempts;
+        self
+    }
+
+    /// Fails a query whose formatted result exceeds `max_bytes` (`None` for no limit) instead of
+    /// holding it in memory; the query is then healed like any failed one.
+    #[must_use]
+    pub const fn max_result_bytes(
+        mut self,
+        max_bytes: Option<usize>,
+    ) -> Self {
+        self.options.max_result_bytes = max_bytes;
+        self
+    }
+
+    /// Estimates what each request cost from `prices`, reported in
+    /// [`TokenUsage::estimated_cost_usd`].
+    #[must_use]
+    pub fn model_prices(
+        mut self,
+        prices: usage::PriceTable,
+    ) -> Self {
+        self.options.model_prices = prices;
+        self
+    }
+
+    /// Reuses each discovered schema for `ttl` instead of discovering it on every request.
+    #[must_use]
+    pub fn schema_cache_ttl(
+        mut self,
+        ttl: std::time::Duration,
+    ) -> Self {
+        self.options.schema_cache = Some(SchemaCache::new(ttl));
+        self
+    }
+
+    /// Restricts the procedures generated queries may call, and leaves the others out of the
+    /// prompt. See [`validator::ProcedureAllowlist`] for the rules.
+    #[must_use]
+    pub fn procedure_allowlist(
+        mut self,
+        allowlist: validator::ProcedureAllowlist,
+    ) -> Self {
+        self.options.procedure_allowlist = Some(allowlist);
+        self
+    }
+
+    /// Sets what generated queries may do to the graph. Without a policy every query runs
+    /// read-only; writes the policy allows are offered to the model and executed read-write. See
+    /// [`validator::ValidationPolicy`].
+    #[must_use]
+    pub fn validation_policy(
+        mut self,
+        policy: validator::ValidationPolicy,
+    ) -> Self {
+        self.options.validation_policy = Some(policy);
+        self
+    }
+
+    /// Registers a hook that rewrites each generated query before it is validated, and the answer
+    /// before it is returned. Hooks run in registration order. See [`hooks::ResponseHook`].
+    #[must_use]
+    pub fn response_hook(
+        mut self,
+        hook: impl hooks::ResponseHook + 'static,
+    ) -> Self {
+        self.options.response_hooks.push(hook);
+        self
+    }
+
+    /// Registers a validation rule of the deployment's own, such as a naming convention or a
+    /// mandatory tenant filter. See [`validation_rules::ValidationRule`].
+    #[must_use]
+    pub fn validation_rule(
+        mut self,
+        rule: impl validation_rules::ValidationRule + 'static,
+    ) -> Self {
+        self.options.validation_rules.push(rule);
+        self
+    }
+
+    /// Registers every rule in `rules`, e.g. those of a rules file read with
+    /// [`validation_rules::ValidationRules::parse`].
+    #[must_use]
+    pub fn validation_rules(
+        mut self,
+        rules: &validation_rules::ValidationRules,
+    ) -> Self {
+        self.options.validation_rules.extend(rules);
+        self
+    }
+
+    /// Sets how much estimated cost a query may have before it runs. Queries likely to scan the
+    /// whole graph are rejected, or run with a warning when the budget only warns. See
+    /// [`validator::estimate_cost`].
+    #[must_use]
+    pub const fn cost_budget(
+        mut self,
+        budget: validator::CostBudget,
+    ) -> Self {
+        self.options.cost_budget = Some(budget);
+        self
+    }
+
+    /// Restricts every generated query to one tenant's nodes on a graph shared between tenants:
+    /// the tenant's predicate is added to each node pattern, and queries it cannot cover, such as
+    /// procedure calls, are rejected. See [`tenancy::TenantFilter`].
+    #[must_use]
+    pub fn tenant_filter(
+        mut self,
+        filter: tenancy::TenantFilter,
+    ) -> Self {
+        self.options.tenant_filter = Some(filter);
+        self
+    }
+
+    /// Sends the [`few_shot::DEFAULT_TOP_K`] of `examples` most similar to each question with it,
+    /// so the model follows how the graph is queried. See [`few_shot::FewShotStore`] to keep them.
+    #[must_use]
+    pub fn few_shot_examples(
+        mut self,
+        examples: Vec<few_shot::FewShotExample>,
+    ) -> Self {
+        self.options.few_shot_examples = examples;
+        self
+    }
+
+    /// Answers statistical questions only, for graphs holding sensitive data: the model is told to
+    /// return aggregates such as `count()` and `avg()`, and queries returning raw rows, or
+    /// aggregates over fewer than [`validator::MIN_AGGREGATE_GROUP`] rows, are rejected. See [`validator::CypherValidator::aggregate_only_errors`].
+    #[must_use]
+    pub const fn aggregate_only(
+        mut self,
+        aggregate_only: bool,
+    ) -> Self {
+        self.options.aggregate_only = aggregate_only;
+        self
+    }
+
+    /// Sets the units of numeric properties, e.g. `Order.total` in `USD millions`. Discovered
+    /// schemas carry them, and answers state them with the numbers.
+    #[must_use]
+    pub fn attribute_units(
+        mut self,
+        units: schema::units::AttributeUnits,
+    ) -> Self {
+        self.options.attribute_units = Some(units);
+        self
+    }
+
+    /// Narrows schemas with more labels and relationship types than the pruner's minimum to those
+    /// closest to each question, by embedding similarity, before they are sent to the model. See
+    /// [`schema::pruning::SchemaPruner`].
+    #[must_use]
+    pub fn schema_pruning(
+        mut self,
+        pruner: schema::pruning::SchemaPruner,
+    ) -> Self {
+        self.options.schema_pruning = Some(pruner);
+        self
+    }
+
+    /// Admits each request to its graph before its first model call, routing included, and counts
+    /// the tokens it spent, e.g. against per-graph quotas. Refused requests fail with a
+    /// [`TextToCypherError::Config`] error. See [`engine::Admission`].
+    #[must_use]
+    pub fn admission(
+        mut self,
+        admission: impl engine::Admission + 'static,
+    ) -> Self {
+        self.options.admission = Some(std::sync::Arc::new(admission));
+        self
+    }
+
+    /// Builds the client, with the built-in skills like [`TextToCypherClient::new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no model was set.
+    pub fn build(self) -> Result<TextToCypherClient, String> {
+        let model = self
+            .model
+            .filter(|model| !model.trim().is_empty())
+            .ok_or_else(|| "a model is required".to_string())?;
+        let mut client = TextToCypherClient::new(model, self.api_key, self.falkordb_connection)
+            .with_fallback_models(self.fallback_models)
+            .with_model_options(self.model_options);
+        client.discovery = self.discovery;
+        client.options = self.options;
+        Ok(client)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -663,6 +1458,74 @@ mod tests {
         assert!(client.skill_catalog.is_some());
     }
 
+    #[test]
+    fn test_builder_sets_processing_options() {
+        let client = TextToCypherClient::builder()
+            .model("gpt-4o-mini")
+            .fallback_models(["anthropic:claude-3-haiku"])
+            .model_options(ModelOptions::default().with_temperature(0.0))
+            .execution_timeout(std::time::Duration::from_secs(5))
+            .max_heal_attempts(3)
+            .max_result_bytes(Some(1 << 20))
+            .model_prices(usage::PriceTable::parse(r#"{"gpt-4o-mini": {"prompt": 0.15, "completion": 0.6}}"#).unwrap())
+            .schema_cache_ttl(std::time::Duration::from_secs(60))
+            .validation_policy(validator::ValidationPolicy::read_only())
+            .response_hook(hooks::EnforceLimit(100))
+            .validation_rules(
+                &validation_rules::ValidationRules::parse("rules:\n  - name: no-csv\n    forbid: LOAD CSV\n").unwrap(),
+            )
+            .tenant_filter(tenancy::TenantFilter::new("tenant_id", "acme").unwrap())
+            .aggregate_only(true)
+            .few_shot_examples(vec![few_shot::FewShotExample {
+                question: "Who acted in Heat?".to_string(),
+                cypher: "MATCH (p:Person)-[:ACTED_IN]->(:Movie {title: 'Heat'}) RETURN p.name".to_string(),
+            }])
+            .cost_budget(validator::CostBudget {
+                max_cost: 60,
+                reject: true,
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(client.falkordb_connection, "falkor://127.0.0.1:6379");
+        assert!(client.skill_catalog.is_some());
+        assert_eq!(
+            client.build_request("g", ChatRequest::default(), false).model,
+            Some(ModelChain::new("gpt-4o-mini").with_fallback("anthropic:claude-3-haiku"))
+        );
+        assert_eq!(
+            client
+                .build_request("g", ChatRequest::default(), false)
+                .model_options
+                .temperature,
+            Some(0.0)
+        );
+        assert_eq!(
+            client.options.execution_timeout,
+            Some(std::time::Duration::from_secs(5))
+        );
+        assert_eq!(client.options.generation_timeout, None);
+        assert_eq!(client.options.max_heal_attempts, 3);
+        assert_eq!(client.options.max_result_bytes, Some(1 << 20));
+        assert!(client.options.model_prices.price("gpt-4o-mini").is_some());
+        assert!(client.options.schema_cache.is_some());
+        assert_eq!(
+            client.options.validation_policy,
+            Some(validator::ValidationPolicy::read_only())
+        );
+        assert_eq!(client.options.response_hooks.names(), vec!["enforce-limit"]);
+        assert_eq!(client.options.validation_rules.names(), vec!["no-csv"]);
+        assert_eq!(client.options.cost_budget.map(|budget| budget.max_cost), Some(60));
+        assert_eq!(
+            client.options.tenant_filter.as_ref().map(tenancy::TenantFilter::tenant),
+            Some("acme")
+        );
+        assert!(client.options.aggregate_only);
+        assert_eq!(client.options.few_shot_examples.len(), 1);
+
+        assert!(TextToCypherClient::builder().api_key("k").build().is_err());
+    }
+
     #[test]
     fn test_client_with_llm_endpoint() {
         let client = TextToCypherClient::new("openai::local-model", "key", "falkor://localhost:6379")
@@ -728,6 +1591,43 @@ mod tests {
         assert_eq!(client.udf_source, UdfSource::Off);
     }
 
+    #[test]
+    fn test_with_audience_flows_into_requests() {
+        let client = TextToCypherClient::new("m", "k", "falkor://127.0.0.1:6379");
+        assert_eq!(client.build_request("g", ChatRequest::default(), false).audience, None);
+
+        let client = client.with_audience(Audience::Analyst);
+        let request = client.build_request("g", ChatRequest::default(), true);
+        assert_eq!(request.audience, Some(Audience::Analyst));
+        assert!(request.cypher_only);
+        assert_eq!(request.falkordb_connection.as_deref(), Some("falkor://127.0.0.1:6379"));
+        assert!(!request.followups);
+
+        let client = client.with_followups(true);
+        assert!(client.build_request("g", ChatRequest::default(), false).followups);
+
+        assert!(!request.strict_validation);
+        let client = client.with_strict_validation(true);
+        assert!(client.build_request("g", ChatRequest::default(), false).strict_validation);
+
+        assert!(!request.refine_lint_hints);
+        let client = client.with_lint_refinement(true);
+        assert!(client.build_request("g", ChatRequest::default(), false).refine_lint_hints);
+
+        assert_eq!(request.max_var_length, None);
+        let client = client.with_max_var_length(4);
+        assert_eq!(
+            client.build_request("g", ChatRequest::default(), false).max_var_length,
+            Some(4)
+        );
+
+        let client = client.with_graph_aliases(GraphAliases::parse("sales=crm_prod_v2").unwrap());
+        assert_eq!(
+            client.build_request("sales", ChatRequest::default(), false).graph_name,
+            "crm_prod_v2"
+        );
+    }
+
     #[test]
     fn test_with_additional_skills_keeps_builtin() {
         let client =
@@ -760,6 +1660,8 @@ mod tests {
         assert_eq!(request.messages.len(), 2);
         assert_eq!(request.messages[0].role, ChatRole::User);
         assert_eq!(request.messages[1].role, ChatRole::Assistant);
+        assert_eq!(request.last_user_question(), Some("Hello"));
+        assert_eq!(ChatRequest::default().last_user_question(), None);
     }
 
     #[test]
@@ -1,23 +1,73 @@
 #![recursion_limit = "256"]
 #![allow(clippy::needless_for_each)]
 
-use crate::usage::TokenUsage;
-use ::text_to_cypher::core::{clean_generated_cypher_response, create_genai_client_with_endpoint, discover_udfs};
+use crate::usage::{PriceTable, TokenUsage};
+use ::text_to_cypher::answer_cache::{AnswerCache, AnswerInputs, CachedAnswer};
+use ::text_to_cypher::audit::{
+    AuditLog, AuditOutcome, AuditRecord, DEFAULT_REQUEST_RETENTION, RequestUsage, TEXT_TO_CYPHER_ACTION,
+};
+use ::text_to_cypher::auth::{ApiKeys, AuthError, Scope};
+use ::text_to_cypher::core::{
+    GenerationOutcome, create_genai_client_with_endpoint, discover_graph_schema_with_options, discover_udfs,
+    embed_text, enrich_schema, explain_cypher_query, graph_not_found_message, list_graphs, route_question,
+    with_query_vector,
+};
+use ::text_to_cypher::debug_bundle::{self, DebugBundle, ModelCall, QueryExecution, ValidationReport};
+use ::text_to_cypher::dry_run::{DryRun, DryRunStore};
+use ::text_to_cypher::engine::{
+    self, Admission, AnswerRequest, Executed, Generation, GenerationError, ProgressReporter, QueryModes, QueryRunner,
+    QueryWriter, StepError, StreamClosed, execute_with_healing,
+};
+use ::text_to_cypher::event_signing::EventSigner;
+use ::text_to_cypher::few_shot::{self, FewShotExample, FewShotStore};
+use ::text_to_cypher::graph_backup::{self, GraphBackup};
+use ::text_to_cypher::graph_trash::{self, DEFAULT_TRASH_TTL, DeleteConfirmations, GraphTrash, TrashedGraph};
+use ::text_to_cypher::hooks::ResponseHooks;
+use ::text_to_cypher::import::{
+    CsvColumn, CsvEncoding, CsvInspection, DEFAULT_BATCH_ROWS, ImportBatch, ImportSummary, InspectOptions,
+    MAX_SKIPPED_ROWS, OnBatchError, RowError, WriteStats, inspect_csv, reads_headers, split_csv,
+};
+use ::text_to_cypher::import_jobs::{ImportJob, ImportJobStatus, ImportJobStore, content_sha256};
+use ::text_to_cypher::latency::{LatencyMode, compact_schema};
+use ::text_to_cypher::model_chain::ModelChain;
+use ::text_to_cypher::model_options::ModelOptions;
+use ::text_to_cypher::notebook::{self, NotebookFormat};
+use ::text_to_cypher::processor::{DEFAULT_MAX_HEAL_ATTEMPTS, provided_schema};
+use ::text_to_cypher::prompts;
+use ::text_to_cypher::quota::{GraphQuota, GraphQuotas, QuotaStatus};
+use ::text_to_cypher::remote_file::{self, RemoteFetcher, RemoteSource, S3Config, S3Credentials};
+use ::text_to_cypher::request_queue::{Permit, Priority, PrioritySemaphore};
+use ::text_to_cypher::routing::GraphCandidate;
+use ::text_to_cypher::schema::pruning::SchemaPruner;
+use ::text_to_cypher::schema_store::{CachedSchemas, DEFAULT_SCHEMA_CACHE_SIZE, SchemaCacheOptions, SchemaStore};
+use ::text_to_cypher::search::{SearchHit, SearchTargets, search};
+use ::text_to_cypher::session::{SessionExport, SessionStore, SessionTurn};
+use ::text_to_cypher::shadow::{Shadow, ShadowConfig, ShadowReport};
 use ::text_to_cypher::skills::{self, SkillCatalog, SkillProfile};
+use ::text_to_cypher::stats::{StatsSnapshot, UsageStats};
+use ::text_to_cypher::storage::{self, EncryptedStorage, Keyring, Storage, StorageConfig};
+use ::text_to_cypher::tenancy::{TenantFilter, TenantPolicy};
 use ::text_to_cypher::udf::UdfError;
+use ::text_to_cypher::usage_report::{UsageGroup, UsageGroupBy, UsageReport};
+use ::text_to_cypher::validation_rules::RulesFile;
+#[cfg(feature = "xlsx")]
+use ::text_to_cypher::xlsx;
 use actix_multipart::Multipart;
 use actix_web::HttpResponse;
 use actix_web::http::StatusCode;
-use actix_web::{App, HttpServer, Responder, Result, post};
+use actix_web::{App, Either, HttpRequest, HttpServer, Responder, Result, post, web};
 use actix_web_lab::sse::{self, Sse};
+use bytestring::ByteString;
 use falkordb::ConfigValue;
-use falkordb::FalkorConnectionInfo;
 use futures_util::StreamExt;
 use genai::chat::ChatMessage as GenAiChatMessage;
 use moka::sync::Cache;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::sync::OnceLock;
+use std::fmt::Write as _;
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use streaming::{Progress, StreamStatus, Warning, WarningCode};
 use tokio::sync::mpsc;
 use tracing_subscriber::fmt;
 use utoipa::OpenApi;
@@ -25,237 +75,986 @@ use utoipa::ToSchema;
 use utoipa_swagger_ui::SwaggerUi;
 use uuid::Uuid;
 
-// Macro for functions returning ()
-macro_rules! send {
-    ($tx:expr, $progress:expr) => {
-        match serde_json::to_string(&$progress) {
-            Ok(json) => {
-                let event = sse::Event::Data(sse::Data::new(json));
-                if $tx.send(event).await.is_err() {
-                    tracing::warn!("Client disconnected, stopping stream");
-                    return;
-                }
-            }
-            Err(e) => {
-                tracing::error!("Failed to serialize progress update: {}", e);
-                return;
-            }
-        }
-    };
-}
-
-// Macro for functions returning Option<T>
-macro_rules! send_option {
-    ($tx:expr, $progress:expr) => {
-        match serde_json::to_string(&$progress) {
-            Ok(json) => {
-                let event = sse::Event::Data(sse::Data::new(json));
-                if $tx.send(event).await.is_err() {
-                    tracing::warn!("Client disconnected, stopping stream");
-                    return None;
-                }
-            }
-            Err(e) => {
-                tracing::error!("Failed to serialize progress update: {}", e);
-                return None;
-            }
-        }
-    };
-}
-
-// Macro for functions returning Result<T, ()>
-macro_rules! send_result {
-    ($tx:expr, $progress:expr) => {
-        match serde_json::to_string(&$progress) {
-            Ok(json) => {
-                let event = sse::Event::Data(sse::Data::new(json));
-                if $tx.send(event).await.is_err() {
-                    tracing::warn!("Client disconnected, stopping stream");
-                    return Err(());
-                }
-            }
-            Err(e) => {
-                tracing::error!("Failed to serialize progress update: {}", e);
-                return Err(());
-            }
-        }
-    };
+/// Re-export the library's chat types so sessions, the binary, and the shared `mcp` module all
+/// exchange the same `ChatRequest`/`ChatMessage` definitions.
+mod aliases {
+    pub use ::text_to_cypher::aliases::*;
 }
-
-// Macro for functions returning Result<T, ()> - same name, different internal marker
-macro_rules! try_send {
-    ($tx:expr, $progress:expr) => {
-        match serde_json::to_string(&$progress) {
-            Ok(json) => {
-                let event = sse::Event::Data(sse::Data::new(json));
-                if $tx.send(event).await.is_err() {
-                    tracing::warn!("Client disconnected, stopping stream");
-                    return Err(());
-                }
-            }
-            Err(e) => {
-                tracing::error!("Failed to serialize progress update: {}", e);
-                return Err(());
-            }
-        }
-    };
+mod connection {
+    pub use ::text_to_cypher::connection::*;
 }
-
-// Macro for functions returning Result<String, Box<dyn Error>>
-macro_rules! try_send_boxed {
-    ($tx:expr, $progress:expr) => {
-        match serde_json::to_string(&$progress) {
-            Ok(json) => {
-                let event = sse::Event::Data(sse::Data::new(json));
-                if $tx.send(event).await.is_err() {
-                    tracing::warn!("Client disconnected, stopping stream");
-                    return Err("Client disconnected".into());
-                }
-            }
-            Err(e) => {
-                tracing::error!("Failed to serialize progress update: {}", e);
-                return Err(format!("Serialization failed: {}", e).into());
-            }
-        }
-    };
+mod chat {
+    pub use ::text_to_cypher::chat::*;
 }
-
-// Macro for functions returning String (returns empty string on error)
-macro_rules! send_or_empty {
-    ($tx:expr, $progress:expr) => {
-        match serde_json::to_string(&$progress) {
-            Ok(json) => {
-                let event = sse::Event::Data(sse::Data::new(json));
-                if $tx.send(event).await.is_err() {
-                    tracing::warn!("Client disconnected, stopping stream");
-                    return String::new();
-                }
-            }
-            Err(e) => {
-                tracing::error!("Failed to serialize progress update: {}", e);
-                return String::new();
-            }
-        }
-    };
+mod api_examples;
+mod check;
+/// Re-export the library's errors rather than compiling a second `TextToCypherError`.
+mod error {
+    pub use ::text_to_cypher::error::*;
 }
-
-mod chat;
-mod error;
 mod formatter;
 mod mcp;
-mod schema;
-mod template;
-mod validator;
+mod pool;
+/// Re-export the library's result types, which `formatter` renders through.
+mod query_result {
+    pub use ::text_to_cypher::query_result::*;
+}
+/// Re-export the library's schema types; discovery itself runs through `core`.
+mod schema {
+    pub use ::text_to_cypher::schema::*;
+}
+/// Re-export the library's progress events, which the shared `mcp` module decodes.
+mod streaming {
+    pub use ::text_to_cypher::streaming::*;
+}
+/// Re-export the library's templates so the prompts built by `prompts` and the binary share the
+/// same `Audience`.
+mod template {
+    pub use ::text_to_cypher::template::*;
+}
+/// Re-export the library's validator so the binary and the streamed `Lint` events share the same
+/// `LintHint`.
+mod validator {
+    pub use ::text_to_cypher::validator::*;
+}
 
 /// Re-export the library's `TokenUsage` so the binary and the shared `mcp`
 /// module share a single definition (referenced as `crate::usage::TokenUsage`)
 /// instead of compiling a duplicate copy of `src/usage.rs` into the bin crate.
 mod usage {
-    pub use ::text_to_cypher::usage::TokenUsage;
+    pub use ::text_to_cypher::usage::{CallUsage, PriceTable, TokenUsage};
 }
 
+use aliases::{GraphAlias, GraphAliases};
 use chat::{ChatMessage, ChatRequest, ChatRole};
-use formatter::{build_falkordb_async_client, format_as_json, format_query_records, rows_lossy};
-use mcp::run_mcp_server;
-use template::TemplateEngine;
-use validator::CypherValidator;
+use connection::{ConnectionTarget, NamedConnections};
+use formatter::{DEFAULT_MAX_RESULT_BYTES, format_as_json, format_rows_within, rows_lossy};
+use mcp::{McpServerOptions, run_mcp_server};
+use pool::{connect, execute_query_blocking, with_failover};
+use template::{Audience, TemplateEngine};
+use validator::{
+    ClausePolicies, ClausePolicy, CostBudget, CypherValidator, LintHint, ProcedureAllowlist, QueryMode,
+    ValidationOptions, ValidationPolicy, ValidationResult, estimate_cost,
+};
+
+use crate::schema::diff::{AttributeChanges, SchemaDiff, TypeChange};
+use crate::schema::discovery::{DiscoveryOptions, DiscoveryProgress, Schema};
+use crate::schema::units::{AttributeUnits, units_for_query};
+
+/// Where a server listens: a host name or IP (paired with the configured port), or a unix domain
+/// socket for sidecar deployments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BindAddress {
+    Host(String),
+    Unix(std::path::PathBuf),
+}
+
+impl BindAddress {
+    /// Reads `name` from the environment: `unix:/path/to.sock` selects a unix socket, anything else
+    /// is a host. Unset or empty values bind all interfaces.
+    fn from_env(name: &str) -> Self {
+        match std::env::var(name) {
+            Ok(value) if !value.trim().is_empty() => {
+                let value = value.trim();
+                value
+                    .strip_prefix("unix:")
+                    .map_or_else(|| Self::Host(value.to_string()), |path| Self::Unix(path.into()))
+            }
+            _ => Self::Host("0.0.0.0".to_string()),
+        }
+    }
+
+    /// The URL the server reaches its own REST API at when listening here on `port`: loopback for
+    /// an all-interfaces host, `None` for a unix socket.
+    fn local_url(
+        &self,
+        port: u16,
+    ) -> Option<String> {
+        let Self::Host(host) = self else {
+            return None;
+        };
+        let host = match host.trim_matches(['[', ']']).parse::<std::net::IpAddr>() {
+            Ok(ip) if ip.is_unspecified() && ip.is_ipv6() => "[::1]".to_string(),
+            Ok(ip) if ip.is_unspecified() => "127.0.0.1".to_string(),
+            Ok(std::net::IpAddr::V6(ip)) => format!("[{ip}]"),
+            Ok(std::net::IpAddr::V4(ip)) => ip.to_string(),
+            Err(_) => host.clone(),
+        };
+        Some(format!("http://{host}:{port}"))
+    }
+}
+
+impl std::fmt::Display for BindAddress {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::Host(host) => f.write_str(host),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
 
-use crate::schema::discovery::Schema;
+/// Shadow traffic from `SHADOW_MODEL`: the sampler with its divergence totals, and the client shadow
+/// queries are generated with.
+#[derive(Debug, Clone)]
+struct ShadowTraffic {
+    shadow: std::sync::Arc<Shadow>,
+    client: genai::Client,
+}
 
 // Configuration structure for default values from .env file
 #[derive(Debug, Clone)]
 struct AppConfig {
     falkordb_connection: String,
     default_model: Option<String>,
+    /// Embedding model from `EMBEDDING_MODEL`, used by `POST /embed` requests that do not name one.
+    embedding_model: Option<String>,
+    /// Cheaper model from `FAST_MODEL`, used for `fast` requests that do not name a model.
+    fast_model: Option<String>,
     default_key: Option<String>,
-    schema_cache: Cache<String, String>,
+    /// Discovered schemas by graph name, shared rather than copied into each request; sized and
+    /// expired by `SCHEMA_CACHE_SIZE` and `SCHEMA_CACHE_TTL_SECS`.
+    schema_cache: CachedSchemas,
+    /// Graph names per connection string, for the existence check that runs before any model call.
+    graph_lists: Cache<String, Vec<String>>,
     rest_port: u16,
     mcp_port: u16,
+    /// HTTP listen address from `BIND_ADDRESS`; `rest_port` applies to hosts only.
+    bind_address: BindAddress,
+    /// MCP listen host from `MCP_BIND_ADDRESS` (unix sockets are not supported by the MCP transport).
+    mcp_bind_address: BindAddress,
+    /// MCP pings, timeouts and resumption (`MCP_PING_INTERVAL_SECS`, ...).
+    mcp_options: McpServerOptions,
     skill_catalog: Option<SkillCatalog>,
     /// When true, the server runs `GRAPH.UDF LIST` and surfaces instance UDFs to the model.
     discover_udfs: bool,
     /// Instance-scoped cache of rendered UDF context, keyed by connection string. Holds a short TTL
     /// and negatively caches "no UDFs" so an unsupported server is only probed occasionally.
     udf_cache: Cache<String, String>,
+    /// Opt-in anonymous usage statistics served by `GET /stats` (`USAGE_STATS=true`).
+    usage_stats: std::sync::Arc<UsageStats>,
+    /// When true (`DEBUG_BUNDLES=true`), each `/text_to_cypher` request is traced and its debug
+    /// bundle is kept for `GET /requests/{id}/debug`.
+    debug_bundles: bool,
+    /// Shared persistence for stateful subsystems, selected with `STORAGE_BACKEND`.
+    #[allow(dead_code)]
+    storage: std::sync::Arc<dyn Storage>,
+    /// Conversation sessions, persisted in `storage`.
+    sessions: SessionStore,
+    /// API keys and their scopes from `API_KEYS`; destructive queries need a `write` key.
+    api_keys: ApiKeys,
+    /// Clauses generated queries may use, per API key and graph (`CLAUSE_POLICIES`,
+    /// `CLAUSE_POLICY_BINDINGS`).
+    clause_policies: ClausePolicies,
+    /// Procedures generated queries may call and their argument rules (`PROCEDURE_ALLOWLIST`);
+    /// `None` allows all.
+    procedure_allowlist: Option<ProcedureAllowlist>,
+    /// What generated queries may do to the graph (`VALIDATION_POLICY`); `None` only checks
+    /// deletions against `allow_destructive`.
+    validation_policy: Option<ValidationPolicy>,
+    /// Hooks that rewrite generated queries and final answers (`RESPONSE_HOOKS`).
+    response_hooks: ResponseHooks,
+    /// The deployment's own validation rules (`VALIDATION_RULES_FILE`), re-read when the file
+    /// changes; `None` runs only the built-in checks.
+    validation_rules: Option<Arc<RulesFile>>,
+    /// How much estimated cost a query may have before it runs (`QUERY_COST_BUDGET`); `None`
+    /// skips the estimate.
+    cost_budget: Option<CostBudget>,
+    /// Graphs shared between tenants and the tenant of each API key (`TENANT_POLICY`).
+    tenant_policy: TenantPolicy,
+    /// Graphs that only answer statistical questions (`AGGREGATE_ONLY_GRAPHS`).
+    aggregate_only_graphs: Vec<String>,
+    /// Units of numeric properties written into discovered schemas (`ATTRIBUTE_UNITS`).
+    attribute_units: AttributeUnits,
+    /// Indexes `POST /graphs/{name}/search` covers when a request names none (`SEARCH_TARGETS`).
+    search_targets: SearchTargets,
+    /// Audit trail of executed mutations, persisted in `storage`.
+    audit: AuditLog,
+    /// Per-graph request and token quotas set at `/admin/quotas`, persisted in `storage`.
+    quotas: Arc<GraphQuotas>,
+    /// Slots of requests calling the model at once (`LLM_CONCURRENCY`), handed out interactive
+    /// first; `None` admits every request.
+    llm_queue: Option<Arc<PrioritySemaphore>>,
+    /// API keys whose requests always queue as batch work (`BATCH_API_KEYS`).
+    batch_api_keys: Vec<String>,
+    /// Example questions and queries of each graph, curated in `FEW_SHOT_DIR` or set at
+    /// `/admin/examples`.
+    few_shot: FewShotStore,
+    /// Examples sent with each question (`FEW_SHOT_TOP_K`); 0 sends none.
+    few_shot_top_k: usize,
+    /// Destructive queries staged for confirmation, persisted in `storage`.
+    dry_runs: DryRunStore,
+    /// Answers reused while a question's query result is unchanged (`ANSWER_CACHE_TTL_SECS`),
+    /// persisted in `storage`; `None` writes every answer.
+    answer_cache: Option<AnswerCache>,
+    /// Progress of streamed imports, persisted in `storage` so they can resume.
+    import_jobs: ImportJobStore,
+    /// Last discovered schema per graph, persisted in `storage`; the fallback when discovery fails.
+    schema_snapshots: SchemaStore,
+    /// Alternate model/prompt that a sampled share of requests is shadowed with, if configured.
+    shadow: Option<ShadowTraffic>,
+    /// Maximum variable-length pattern depth from `MAX_VAR_LENGTH`; deeper patterns are rewritten.
+    max_var_length: Option<u32>,
+    /// Times a failed query is regenerated with the database's error (`MAX_HEAL_ATTEMPTS`).
+    max_heal_attempts: usize,
+    /// Bytes a formatted query result may take before the query fails as too large
+    /// (`MAX_RESULT_BYTES`); `None` allows any size.
+    max_result_bytes: Option<usize>,
+    /// Model prices from `MODEL_PRICES`, for the cost estimate in `Usage` events.
+    model_prices: PriceTable,
+    /// Narrows large schemas to the part closest to each question (`SCHEMA_PRUNING`).
+    schema_pruner: Option<SchemaPruner>,
+    /// Backups kept per graph (`GRAPH_BACKUP_RETENTION`); when set, graphs are copied before hard
+    /// `/graph_delete` calls and confirmed destructive queries.
+    graph_backup_retention: Option<usize>,
+    /// `/graph_delete` requests awaiting their confirmation, persisted in `storage`.
+    delete_confirmations: DeleteConfirmations,
+    /// The graphs soft-deleted by `/graph_delete`, recorded in `storage` so only they are purged.
+    graph_trash: GraphTrash,
+    /// How long soft-deleted graphs stay in the trash (`GRAPH_TRASH_TTL_SECS`); `None` keeps them.
+    graph_trash_ttl: Option<std::time::Duration>,
+    /// How long the audit records of served requests are kept (`REQUEST_AUDIT_TTL_SECS`); `None`
+    /// keeps them.
+    request_retention: Option<std::time::Duration>,
+    /// Friendly graph names from `GRAPH_ALIASES`, resolved wherever a request names a graph.
+    graph_aliases: GraphAliases,
+    /// Connections a request can select by name (`FALKORDB_CONNECTIONS`), each with its own
+    /// database index and graph-key prefix.
+    connections: NamedConnections,
+    /// Events buffered per SSE stream (`SSE_CHANNEL_CAPACITY`).
+    sse_channel_capacity: usize,
+    /// Signs every SSE event when `SSE_SIGNING_KEY` is set.
+    sse_signer: Option<EventSigner>,
+    /// Fetches `POST /import/remote` files (`REMOTE_IMPORT_MAX_BYTES`, `REMOTE_IMPORT_HOSTS`, and the
+    /// `AWS_*`/`S3_ENDPOINT` settings for S3 objects).
+    remote_fetcher: Option<RemoteFetcher>,
+    /// Settings that were invalid and fell back to a default, reported by `--check`.
+    config_errors: Vec<ConfigError>,
 }
 
 static APP_CONFIG: OnceLock<AppConfig> = OnceLock::new();
 
+/// Returns true when the environment variable `name` is set to a truthy value (`1`, `true`, `yes`, `on`).
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .ok()
+        .is_some_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+}
+
+/// Returns the model named by the environment variable `name`, unless it is unset or blank.
+fn env_model(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|model| !model.trim().is_empty())
+}
+
+/// Returns the physical graph key for a graph name that may be a `GRAPH_ALIASES` alias.
+fn resolve_graph_name(name: &str) -> String {
+    AppConfig::get().graph_aliases.resolve(name).to_string()
+}
+
+/// Returns the connection string for a request's `falkordb_connection`, which may name a
+/// `FALKORDB_CONNECTIONS` entry.
+fn resolve_connection(connection: &str) -> String {
+    AppConfig::get().connections.resolve(connection).to_string()
+}
+
 /// System prompt size above which the genai chat request log is summarized instead of pretty-printed.
 const CHAT_REQUEST_LOG_SUMMARY_THRESHOLD: usize = 4096;
 
+/// A setting that could not be used as given. Startup logs it and falls back to a safe default;
+/// `--check` reports it.
+#[derive(Debug, Clone)]
+struct ConfigError {
+    setting: &'static str,
+    message: String,
+}
+
+impl ConfigError {
+    fn new(
+        setting: &'static str,
+        message: impl std::fmt::Display,
+    ) -> Self {
+        Self {
+            setting,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{}: {}", self.setting, self.message)
+    }
+}
+
+/// The settings [`AppConfig::load`] fell back from.
+#[derive(Default)]
+struct ConfigErrors(Vec<ConfigError>);
+
+impl ConfigErrors {
+    /// The loaded value, or `fallback()` after logging and recording the error; `consequence` says
+    /// what falling back means for the deployment.
+    fn recover<T>(
+        &mut self,
+        loaded: Result<T, ConfigError>,
+        consequence: &str,
+        fallback: impl FnOnce() -> T,
+    ) -> T {
+        loaded.unwrap_or_else(|e| {
+            tracing::error!("Invalid {e}; {consequence}");
+            self.0.push(e);
+            fallback()
+        })
+    }
+}
+
+/// Parses the environment variable `name`, or returns `None` when it is unset.
+fn env_parse<T>(name: &'static str) -> Result<Option<T>, ConfigError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    std::env::var(name)
+        .ok()
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|e| ConfigError::new(name, format!("'{value}': {e}")))
+        })
+        .transpose()
+}
+
 impl AppConfig {
+    /// Loads `FALKORDB_CONNECTIONS`. Invalid named connections are dropped as a whole, like invalid
+    /// aliases.
+    fn load_connections() -> Result<NamedConnections, ConfigError> {
+        NamedConnections::parse(&std::env::var("FALKORDB_CONNECTIONS").unwrap_or_default())
+            .map_err(|e| ConfigError::new("FALKORDB_CONNECTIONS", e))
+    }
+
+    /// Reads the MCP connection settings; an invalid one leaves all of them at their defaults.
+    fn load_mcp_options() -> Result<McpServerOptions, ConfigError> {
+        let var = |name| std::env::var(name).ok();
+        McpServerOptions::parse(
+            var("MCP_PING_INTERVAL_SECS").as_deref(),
+            var("MCP_REQUEST_TIMEOUT_SECS").as_deref(),
+            var("MCP_REPLAY_EVENTS").as_deref(),
+            var("MCP_RESULT_TTL_SECS").as_deref(),
+        )
+        .map_err(|e| ConfigError::new("MCP server settings", e))
+    }
+
+    /// Reads `SSE_CHANNEL_CAPACITY`, which must leave room for chunks beside the
+    /// [`TERMINAL_EVENT_SLOTS`].
+    fn load_sse_channel_capacity() -> Result<usize, ConfigError> {
+        match env_parse("SSE_CHANNEL_CAPACITY")? {
+            None => Ok(DEFAULT_SSE_CHANNEL_CAPACITY),
+            Some(capacity) if capacity > TERMINAL_EVENT_SLOTS => Ok(capacity),
+            Some(capacity) => Err(ConfigError::new(
+                "SSE_CHANNEL_CAPACITY",
+                format!("{capacity} is not above {TERMINAL_EVENT_SLOTS}"),
+            )),
+        }
+    }
+
+    /// Reads `SESSION_TTL_SECS`, the idle time after which a session expires; `0` keeps sessions
+    /// forever.
+    fn load_session_ttl() -> Result<Option<std::time::Duration>, ConfigError> {
+        let secs = env_parse("SESSION_TTL_SECS")?.unwrap_or(DEFAULT_SESSION_TTL_SECS);
+        Ok((secs > 0).then(|| std::time::Duration::from_secs(secs)))
+    }
+
+    /// Reads `SCHEMA_CACHE_SIZE`, `SCHEMA_CACHE_TTL_SECS` (unset or 0 never expires),
+    /// `SCHEMA_CACHE_REFRESH` and `SCHEMA_CACHE_WARM`.
+    fn load_schema_cache_options() -> Result<SchemaCacheOptions, ConfigError> {
+        let options = SchemaCacheOptions {
+            capacity: env_parse("SCHEMA_CACHE_SIZE")?.unwrap_or(DEFAULT_SCHEMA_CACHE_SIZE),
+            ttl: env_parse("SCHEMA_CACHE_TTL_SECS")?
+                .filter(|secs| *secs > 0)
+                .map(std::time::Duration::from_secs),
+            refresh: env_flag("SCHEMA_CACHE_REFRESH"),
+            warm: env_flag("SCHEMA_CACHE_WARM"),
+        };
+        if options.refresh && options.ttl.is_none() {
+            tracing::warn!("SCHEMA_CACHE_REFRESH has no effect without SCHEMA_CACHE_TTL_SECS");
+        }
+        Ok(options)
+    }
+
+    /// Loads the skill catalog: the built-in skills, extended or overridden by `SKILLS_DIR`.
+    fn load_skill_catalog() -> Result<SkillCatalog, ConfigError> {
+        // Start from the built-in read-only FalkorDB skills, then let SKILLS_DIR override/extend them.
+        // External skills are filtered to the read-only profile so the read-only contract holds for the
+        // operator override too, not just the built-in set.
+        let builtin = SkillCatalog::builtin();
+        let Ok(dir) = std::env::var("SKILLS_DIR") else {
+            return Ok(builtin);
+        };
+        let catalog = SkillCatalog::from_directory(std::path::Path::new(&dir))
+            .map_err(|e| ConfigError::new("SKILLS_DIR", format!("cannot load skills from {dir}: {e}")))?;
+        if catalog.is_empty() {
+            tracing::warn!("SKILLS_DIR set to '{dir}' but no skills were found; using built-in skills");
+            return Ok(builtin);
+        }
+        let merged = builtin.merged_with(catalog.with_profile(SkillProfile::ReadOnly));
+        tracing::info!("Loaded {} skills (built-in + SKILLS_DIR {})", merged.len(), dir);
+        Ok(merged)
+    }
+
+    /// Loads shadow traffic from `SHADOW_MODEL`, `SHADOW_PERCENT` and `SHADOW_SYSTEM_PROMPT` (a template
+    /// file). Shadow queries authenticate with `SHADOW_KEY`, else `DEFAULT_KEY`.
+    fn load_shadow(default_key: Option<&str>) -> Result<Option<ShadowTraffic>, ConfigError> {
+        let Some(model) = env_model("SHADOW_MODEL") else {
+            return Ok(None);
+        };
+        let system_template = std::env::var("SHADOW_SYSTEM_PROMPT")
+            .ok()
+            .map(|path| {
+                std::fs::read_to_string(&path)
+                    .map_err(|e| ConfigError::new("SHADOW_SYSTEM_PROMPT", format!("cannot read {path}: {e}")))
+            })
+            .transpose()?;
+        let config = ShadowConfig::new(&model, std::env::var("SHADOW_PERCENT").ok().as_deref(), system_template)
+            .map_err(|e| ConfigError::new("SHADOW_MODEL/SHADOW_PERCENT", e))?;
+        tracing::info!("Shadowing {}% of requests with model {}", config.percent, config.model);
+        let key = std::env::var("SHADOW_KEY").ok();
+        Ok(Some(ShadowTraffic {
+            shadow: std::sync::Arc::new(Shadow::new(config)),
+            client: create_genai_client_with_endpoint(key.as_deref().or(default_key), None),
+        }))
+    }
+
+    /// Opens the storage backend shared by server state (sessions, jobs, audit records, ...),
+    /// encrypted with the keys in `STORAGE_ENCRYPTION_KEYS` or the file named by
+    /// `STORAGE_ENCRYPTION_KEYS_FILE`. With `STORAGE_ENCRYPTION_STRICT`, values stored in clear
+    /// text are refused.
+    fn load_storage(falkordb_connection: &str) -> Result<std::sync::Arc<dyn Storage>, ConfigError> {
+        let (backend, keyring) = Self::open_storage(falkordb_connection)?;
+        let strict = env_flag("STORAGE_ENCRYPTION_STRICT");
+        let Some(keyring) = keyring else {
+            return if strict {
+                Err(ConfigError::new(
+                    "STORAGE_ENCRYPTION_STRICT",
+                    "needs STORAGE_ENCRYPTION_KEYS or STORAGE_ENCRYPTION_KEYS_FILE",
+                ))
+            } else {
+                Ok(backend)
+            };
+        };
+        tracing::info!("Encrypting stored values with key '{}'", keyring.current_key_id());
+        Ok(std::sync::Arc::new(
+            EncryptedStorage::new(backend, keyring).strict(strict),
+        ))
+    }
+
+    /// Opens the storage backend and parses its encryption keys, if any are configured.
+    fn open_storage(falkordb_connection: &str) -> Result<(std::sync::Arc<dyn Storage>, Option<Keyring>), ConfigError> {
+        let keys =
+            match std::env::var("STORAGE_ENCRYPTION_KEYS_FILE") {
+                Ok(path) => Some(std::fs::read_to_string(&path).map_err(|e| {
+                    ConfigError::new("STORAGE_ENCRYPTION_KEYS_FILE", format!("cannot read {path}: {e}"))
+                })?),
+                Err(_) => std::env::var("STORAGE_ENCRYPTION_KEYS").ok(),
+            };
+        let keyring = keys
+            .as_deref()
+            .map(Keyring::parse)
+            .transpose()
+            .map_err(|e| ConfigError::new("STORAGE_ENCRYPTION_KEYS", e))?;
+        let backend = StorageConfig::parse(
+            &std::env::var("STORAGE_BACKEND").unwrap_or_default(),
+            std::env::var("STORAGE_URL").ok().as_deref(),
+            std::env::var("STORAGE_KEY_PREFIX").ok().as_deref(),
+            falkordb_connection,
+        )
+        .and_then(|storage_config| storage::open(&storage_config))
+        .map_err(|e| ConfigError::new("STORAGE_BACKEND", e))?;
+        Ok((backend, keyring))
+    }
+
+    /// Reads `CLAUSE_POLICIES` and `CLAUSE_POLICY_BINDINGS`.
+    fn load_clause_policies() -> Result<ClausePolicies, ConfigError> {
+        let clause_policies = ClausePolicies::parse(
+            &std::env::var("CLAUSE_POLICIES").unwrap_or_default(),
+            &std::env::var("CLAUSE_POLICY_BINDINGS").unwrap_or_default(),
+        )
+        .map_err(|e| ConfigError::new("CLAUSE_POLICIES/CLAUSE_POLICY_BINDINGS", e))?;
+        if !clause_policies.is_empty() {
+            tracing::info!("Clause policies restrict generated queries for some API keys or graphs");
+        }
+        Ok(clause_policies)
+    }
+
+    /// Reads `PROCEDURE_ALLOWLIST`. Unset allows every procedure.
+    fn load_procedure_allowlist() -> Result<Option<ProcedureAllowlist>, ConfigError> {
+        std::env::var("PROCEDURE_ALLOWLIST")
+            .ok()
+            .map(|spec| ProcedureAllowlist::parse(&spec).map_err(|e| ConfigError::new("PROCEDURE_ALLOWLIST", e)))
+            .transpose()
+    }
+
+    /// Reads `VALIDATION_POLICY`. Unset leaves writes to `allow_destructive`.
+    fn load_validation_policy() -> Result<Option<ValidationPolicy>, ConfigError> {
+        std::env::var("VALIDATION_POLICY")
+            .ok()
+            .map(|spec| ValidationPolicy::parse(&spec).map_err(|e| ConfigError::new("VALIDATION_POLICY", e)))
+            .transpose()
+    }
+
+    /// Reads `RESPONSE_HOOKS`.
+    fn load_response_hooks() -> Result<ResponseHooks, ConfigError> {
+        let hooks = ResponseHooks::parse(&std::env::var("RESPONSE_HOOKS").unwrap_or_default())
+            .map_err(|e| ConfigError::new("RESPONSE_HOOKS", e))?;
+        if !hooks.is_empty() {
+            tracing::info!("Response hooks: {}", hooks.names().join(", "));
+        }
+        Ok(hooks)
+    }
+
+    /// Reads the rules file named by `VALIDATION_RULES_FILE`.
+    fn load_validation_rules() -> Result<Option<Arc<RulesFile>>, ConfigError> {
+        let Ok(path) = std::env::var("VALIDATION_RULES_FILE") else {
+            return Ok(None);
+        };
+        let file = RulesFile::open(&path).map_err(|e| ConfigError::new("VALIDATION_RULES_FILE", e))?;
+        tracing::info!("Validation rules: {}", file.rules().names().join(", "));
+        Ok(Some(Arc::new(file)))
+    }
+
+    /// Reads `QUERY_COST_BUDGET`. Unset leaves query costs unchecked.
+    fn load_cost_budget() -> Result<Option<CostBudget>, ConfigError> {
+        std::env::var("QUERY_COST_BUDGET")
+            .ok()
+            .map(|spec| CostBudget::parse(&spec).map_err(|e| ConfigError::new("QUERY_COST_BUDGET", e)))
+            .transpose()
+    }
+
+    /// Reads `TENANT_POLICY`.
+    fn load_tenant_policy() -> Result<TenantPolicy, ConfigError> {
+        TenantPolicy::parse(&std::env::var("TENANT_POLICY").unwrap_or_default())
+            .map_err(|e| ConfigError::new("TENANT_POLICY", e))
+    }
+
+    /// Reads `AGGREGATE_ONLY_GRAPHS`, a comma-separated list of graph names.
+    fn load_aggregate_only_graphs() -> Vec<String> {
+        std::env::var("AGGREGATE_ONLY_GRAPHS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|graph| !graph.is_empty())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Whether `graph_name` only answers statistical questions.
+    fn aggregate_only(
+        &self,
+        graph_name: &str,
+    ) -> bool {
+        self.aggregate_only_graphs.iter().any(|graph| graph == graph_name)
+    }
+
+    /// Reads `ANSWER_CACHE_TTL_SECS`. Unset or `0` caches no answers.
+    fn load_answer_cache(storage: &std::sync::Arc<dyn Storage>) -> Result<Option<AnswerCache>, ConfigError> {
+        Ok(env_parse("ANSWER_CACHE_TTL_SECS")?
+            .filter(|secs| *secs > 0)
+            .map(|secs| AnswerCache::new(storage.clone(), std::time::Duration::from_secs(secs))))
+    }
+
+    /// Reads `ATTRIBUTE_UNITS`.
+    fn load_attribute_units() -> Result<AttributeUnits, ConfigError> {
+        std::env::var("ATTRIBUTE_UNITS").map_or_else(
+            |_| Ok(AttributeUnits::default()),
+            |spec| AttributeUnits::parse(&spec).map_err(|e| ConfigError::new("ATTRIBUTE_UNITS", e)),
+        )
+    }
+
+    /// Reads `SEARCH_TARGETS`.
+    fn load_search_targets() -> Result<SearchTargets, ConfigError> {
+        std::env::var("SEARCH_TARGETS").map_or_else(
+            |_| Ok(SearchTargets::default()),
+            |spec| SearchTargets::parse(&spec).map_err(|e| ConfigError::new("SEARCH_TARGETS", e)),
+        )
+    }
+
+    /// Reads `SCHEMA_PRUNING`, and the embedding model (`SCHEMA_PRUNING_MODEL`, or
+    /// `EMBEDDING_MODEL`), `SCHEMA_PRUNING_TOP_K` and `SCHEMA_PRUNING_MIN_ELEMENTS` it prunes with.
+    fn load_schema_pruner() -> Result<Option<SchemaPruner>, ConfigError> {
+        if !env_flag("SCHEMA_PRUNING") {
+            return Ok(None);
+        }
+        let model = env_model("SCHEMA_PRUNING_MODEL")
+            .or_else(|| env_model("EMBEDDING_MODEL"))
+            .ok_or_else(|| ConfigError::new("SCHEMA_PRUNING", "needs SCHEMA_PRUNING_MODEL or EMBEDDING_MODEL"))?;
+        let mut pruner = SchemaPruner::new(model);
+        if let Some(top_k) = env_parse("SCHEMA_PRUNING_TOP_K")? {
+            pruner = pruner.with_top_k(top_k);
+        }
+        if let Some(min_elements) = env_parse("SCHEMA_PRUNING_MIN_ELEMENTS")? {
+            pruner = pruner.with_min_elements(min_elements);
+        }
+        Ok(Some(pruner))
+    }
+
+    /// Reads the remote import settings: `REMOTE_IMPORT_MAX_BYTES`, the `REMOTE_IMPORT_HOSTS`
+    /// allowlist, and the S3 credentials, region and endpoint.
+    fn load_remote_fetcher() -> Result<RemoteFetcher, ConfigError> {
+        let var = |name| std::env::var(name).ok().filter(|value: &String| !value.trim().is_empty());
+        let max_bytes = env_parse("REMOTE_IMPORT_MAX_BYTES")?.unwrap_or(remote_file::DEFAULT_MAX_BYTES);
+        let allowed_hosts = var("REMOTE_IMPORT_HOSTS").map(|hosts| {
+            hosts
+                .split(',')
+                .map(|host| host.trim().to_string())
+                .filter(|host| !host.is_empty())
+                .collect()
+        });
+        let credentials = match (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY")) {
+            (Some(access_key_id), Some(secret_access_key)) => Some(S3Credentials {
+                access_key_id,
+                secret_access_key,
+                session_token: var("AWS_SESSION_TOKEN"),
+            }),
+            (None, None) => None,
+            _ => {
+                return Err(ConfigError::new(
+                    "AWS_ACCESS_KEY_ID/AWS_SECRET_ACCESS_KEY",
+                    "set both or neither",
+                ));
+            }
+        };
+        let endpoint = var("S3_ENDPOINT")
+            .map(|endpoint| {
+                reqwest::Url::parse(endpoint.trim())
+                    .map_err(|e| ConfigError::new("S3_ENDPOINT", format!("'{endpoint}': {e}")))
+            })
+            .transpose()?;
+        let s3 = S3Config {
+            credentials,
+            region: var("AWS_REGION")
+                .or_else(|| var("AWS_DEFAULT_REGION"))
+                .unwrap_or_else(|| remote_file::DEFAULT_S3_REGION.to_string()),
+            endpoint,
+        };
+        RemoteFetcher::new(s3, max_bytes, allowed_hosts).map_err(|e| ConfigError::new("remote imports", e))
+    }
+
+    #[allow(clippy::too_many_lines)]
     fn load() -> Self {
         // Load .env file if it exists, but don't fail if it doesn't
         let env_loaded = dotenvy::dotenv().is_ok();
+        let mut errors = ConfigErrors::default();
         let falkordb_connection =
             std::env::var("FALKORDB_CONNECTION").unwrap_or_else(|_| "falkor://127.0.0.1:6379".to_string());
+        errors.recover(
+            ConnectionTarget::parse(&falkordb_connection)
+                .map(drop)
+                .map_err(|e| ConfigError::new("FALKORDB_CONNECTION", e)),
+            "connecting to FalkorDB will fail",
+            || (),
+        );
         let default_model = std::env::var("DEFAULT_MODEL").ok();
         let default_key = std::env::var("DEFAULT_KEY").ok();
-        let schema_cache = Cache::new(100);
+        let schema_cache = CachedSchemas::new(errors.recover(
+            Self::load_schema_cache_options(),
+            "using the default schema cache settings",
+            SchemaCacheOptions::default,
+        ));
+        let graph_lists = Cache::builder()
+            .time_to_live(std::time::Duration::from_secs(60))
+            .max_capacity(100)
+            .build();
+
+        let rest_port = errors.recover(env_parse("REST_PORT"), "using 8080", || None).unwrap_or(8080);
 
-        let rest_port = std::env::var("REST_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(8080);
+        let mcp_port = errors.recover(env_parse("MCP_PORT"), "using 3001", || None).unwrap_or(3001);
 
-        let mcp_port = std::env::var("MCP_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(3001);
+        let bind_address = BindAddress::from_env("BIND_ADDRESS");
+        let mcp_bind_address = BindAddress::from_env("MCP_BIND_ADDRESS");
+        let mcp_options = errors.recover(
+            Self::load_mcp_options(),
+            "using the defaults",
+            McpServerOptions::default,
+        );
 
         // UDF context is opt-in (the server-side UDF feature is not yet in a stable FalkorDB
         // release). Enable with DISCOVER_UDFS=true. Discovered UDFs are cached per connection with a
         // short TTL so changes (UDF LOAD/DELETE/FLUSH) are eventually picked up without per-request
         // GRAPH.UDF LIST calls.
-        let discover_udfs = std::env::var("DISCOVER_UDFS")
-            .ok()
-            .is_some_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"));
+        let discover_udfs = env_flag("DISCOVER_UDFS");
         let udf_cache = Cache::builder()
             .time_to_live(std::time::Duration::from_secs(300))
             .max_capacity(100)
             .build();
 
-        // Start from the built-in read-only FalkorDB skills, then let SKILLS_DIR override/extend them.
-        // External skills are filtered to the read-only profile so the read-only contract holds for the
-        // operator override too, not just the built-in set.
-        let builtin = SkillCatalog::builtin();
-        let skill_catalog = match std::env::var("SKILLS_DIR").ok() {
-            Some(dir) => {
-                let path = std::path::Path::new(&dir);
-                match SkillCatalog::from_directory(path) {
-                    Ok(catalog) if !catalog.is_empty() => {
-                        let external = catalog.with_profile(SkillProfile::ReadOnly);
-                        let merged = builtin.merged_with(external);
-                        tracing::info!("Loaded {} skills (built-in + SKILLS_DIR {})", merged.len(), dir);
-                        Some(merged)
-                    }
-                    Ok(_) => {
-                        tracing::warn!("SKILLS_DIR set to '{dir}' but no skills were found; using built-in skills");
-                        Some(builtin)
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to load skills from {dir}: {e}; using built-in skills");
-                        Some(builtin)
-                    }
-                }
-            }
-            None => Some(builtin),
+        let skill_catalog = Some(errors.recover(
+            Self::load_skill_catalog(),
+            "using built-in skills",
+            SkillCatalog::builtin,
+        ));
+
+        // Usage statistics are anonymous and disabled by default; deployment owners opt in with
+        // USAGE_STATS=true.
+        let usage_stats = std::sync::Arc::new(UsageStats::new(env_flag("USAGE_STATS")));
+
+        // Debug bundles hold full prompts and result samples, so they are opt-in too.
+        let debug_bundles = env_flag("DEBUG_BUNDLES");
+
+        // A bad storage configuration falls back to in-memory storage rather than refusing to
+        // start, and never to unencrypted persistent storage.
+        let storage = errors.recover(
+            Self::load_storage(&falkordb_connection),
+            "using in-memory storage",
+            || std::sync::Arc::new(storage::InMemoryStorage::new()),
+        );
+
+        // An invalid key list leaves no keys configured, so write access stays denied.
+        let api_keys = errors.recover(
+            ApiKeys::parse(&std::env::var("API_KEYS").unwrap_or_default()).map_err(|e| ConfigError::new("API_KEYS", e)),
+            "no API keys configured",
+            ApiKeys::default,
+        );
+
+        // An invalid policy list lifts the restrictions the operator meant to impose, so it is
+        // logged as loudly as the others.
+        let clause_policies = errors.recover(
+            Self::load_clause_policies(),
+            "no clause policies configured",
+            ClausePolicies::default,
+        );
+        // An invalid allowlist allows no procedures rather than all of them.
+        let procedure_allowlist = errors.recover(Self::load_procedure_allowlist(), "no procedures are allowed", || {
+            Some(ProcedureAllowlist::default())
+        });
+        // An invalid policy allows no writes rather than guessing which were meant.
+        let validation_policy = errors.recover(Self::load_validation_policy(), "no writes are allowed", || {
+            Some(ValidationPolicy::read_only())
+        });
+        // An invalid tenant policy shares every graph and binds no key, so requests are refused
+        // rather than left unfiltered.
+        let tenant_policy = errors.recover(
+            Self::load_tenant_policy(),
+            "every request is refused",
+            TenantPolicy::refuse_all,
+        );
+
+        // Unset (or 0) leaves variable-length patterns as generated.
+        let max_var_length = errors
+            .recover(
+                env_parse("MAX_VAR_LENGTH"),
+                "variable-length patterns are not bounded",
+                || None,
+            )
+            .filter(|depth| *depth > 0);
+
+        // Unset keeps the library default; 0 reports the first execution error without healing.
+        let max_heal_attempts = errors
+            .recover(
+                env_parse("MAX_HEAL_ATTEMPTS"),
+                &format!("using {DEFAULT_MAX_HEAL_ATTEMPTS}"),
+                || None,
+            )
+            .unwrap_or(DEFAULT_MAX_HEAL_ATTEMPTS);
+
+        // Unset keeps the library default; 0 allows results of any size.
+        let max_result_bytes = match errors.recover(
+            env_parse("MAX_RESULT_BYTES"),
+            &format!("using {DEFAULT_MAX_RESULT_BYTES}"),
+            || None,
+        ) {
+            None => Some(DEFAULT_MAX_RESULT_BYTES),
+            Some(0) => None,
+            Some(bytes) => Some(bytes),
+        };
+
+        // Unset or invalid estimates no costs.
+        let model_prices = errors.recover(
+            std::env::var("MODEL_PRICES").map_or_else(
+                |_| Ok(PriceTable::default()),
+                |document| PriceTable::parse(&document).map_err(|e| ConfigError::new("MODEL_PRICES", e)),
+            ),
+            "estimating no costs",
+            PriceTable::default,
+        );
+
+        // Unset (or 0) takes no backups.
+        let graph_backup_retention = errors
+            .recover(env_parse("GRAPH_BACKUP_RETENTION"), "no backups are taken", || None)
+            .filter(|count| *count > 0);
+
+        // Unset keeps trashed graphs for a week; 0 keeps them until purged by hand.
+        let graph_trash_ttl = match errors.recover(
+            env_parse("GRAPH_TRASH_TTL_SECS"),
+            "trashed graphs are kept for a week",
+            || None,
+        ) {
+            None => Some(DEFAULT_TRASH_TTL),
+            Some(0) => None,
+            Some(secs) => Some(std::time::Duration::from_secs(secs)),
+        };
+
+        // Unset keeps request records for 30 days; 0 keeps them until the storage is cleared.
+        let request_retention = match errors.recover(
+            env_parse("REQUEST_AUDIT_TTL_SECS"),
+            "request records are kept for 30 days",
+            || None,
+        ) {
+            None => Some(DEFAULT_REQUEST_RETENTION),
+            Some(0) => None,
+            Some(secs) => Some(std::time::Duration::from_secs(secs)),
         };
 
+        // Invalid aliases are dropped as a whole so a typo cannot silently route to the wrong graph.
+        let graph_aliases = errors.recover(
+            GraphAliases::parse(&std::env::var("GRAPH_ALIASES").unwrap_or_default())
+                .map_err(|e| ConfigError::new("GRAPH_ALIASES", e)),
+            "no graph aliases configured",
+            GraphAliases::default,
+        );
+
+        let connections = errors.recover(
+            Self::load_connections(),
+            "no named connections configured",
+            NamedConnections::default,
+        );
+        let sse_channel_capacity = errors.recover(
+            Self::load_sse_channel_capacity(),
+            &format!("using {DEFAULT_SSE_CHANNEL_CAPACITY}"),
+            || DEFAULT_SSE_CHANNEL_CAPACITY,
+        );
+        // An invalid key disables signing; clients that verify signatures then reject the stream.
+        let sse_signer = errors.recover(
+            std::env::var("SSE_SIGNING_KEY")
+                .ok()
+                .map(|key| EventSigner::new(key.as_bytes()).map_err(|e| ConfigError::new("SSE_SIGNING_KEY", e)))
+                .transpose(),
+            "SSE events are not signed",
+            || None,
+        );
+        // Any invalid setting disables shadowing rather than shadowing with a different
+        // configuration than intended.
+        let shadow = errors.recover(
+            Self::load_shadow(default_key.as_deref()),
+            "shadow traffic disabled",
+            || None,
+        );
+        let session_ttl = errors.recover(
+            Self::load_session_ttl(),
+            &format!("sessions expire after {DEFAULT_SESSION_TTL_SECS} seconds"),
+            || Some(std::time::Duration::from_secs(DEFAULT_SESSION_TTL_SECS)),
+        );
+        let response_hooks = errors.recover(
+            Self::load_response_hooks(),
+            "no response hooks registered",
+            ResponseHooks::default,
+        );
+        let validation_rules = errors.recover(Self::load_validation_rules(), "no validation rules registered", || None);
+        let cost_budget = errors.recover(Self::load_cost_budget(), "query costs are not checked", || None);
+        let attribute_units = errors.recover(
+            Self::load_attribute_units(),
+            "no units are set",
+            AttributeUnits::default,
+        );
+        let search_targets = errors.recover(
+            Self::load_search_targets(),
+            "no search targets are set",
+            SearchTargets::default,
+        );
+        let answer_cache = errors.recover(Self::load_answer_cache(&storage), "answers are not cached", || None);
+        let llm_queue = errors.recover(Self::load_llm_queue(), "requests are not queued", || None);
+        let few_shot = errors.recover(Self::load_few_shot(&storage), "no curated examples are sent", || {
+            FewShotStore::new(storage.clone())
+        });
+        let few_shot_top_k = errors.recover(
+            Self::load_few_shot_top_k(),
+            &format!("sending {} examples", few_shot::DEFAULT_TOP_K),
+            || few_shot::DEFAULT_TOP_K,
+        );
+        let schema_pruner = errors.recover(Self::load_schema_pruner(), "schemas are not pruned", || None);
+        let remote_fetcher = errors.recover(
+            Self::load_remote_fetcher().map(Some),
+            "remote imports are disabled",
+            || None,
+        );
+
         tracing::info!(
-            "Loaded configuration - env_file_loaded: {}, default_model: {:?}, rest_port: {}, mcp_port: {}, skills_loaded: {}",
+            "Loaded configuration - env_file_loaded: {}, default_model: {:?}, rest_port: {}, mcp_port: {}, skills_loaded: {}, storage: {}",
             env_loaded,
             default_model,
             rest_port,
             mcp_port,
             skill_catalog.as_ref().map_or(0, SkillCatalog::len),
+            storage.backend_name(),
         );
 
         Self {
             falkordb_connection,
             default_model,
+            embedding_model: env_model("EMBEDDING_MODEL"),
+            fast_model: env_model("FAST_MODEL"),
             default_key,
             schema_cache,
+            graph_lists,
             rest_port,
             mcp_port,
+            bind_address,
+            mcp_bind_address,
+            mcp_options,
             skill_catalog,
             discover_udfs,
             udf_cache,
+            usage_stats,
+            debug_bundles,
+            sessions: SessionStore::new(storage.clone()).with_ttl(session_ttl),
+            api_keys,
+            clause_policies,
+            procedure_allowlist,
+            validation_policy,
+            response_hooks,
+            validation_rules,
+            cost_budget,
+            tenant_policy,
+            aggregate_only_graphs: Self::load_aggregate_only_graphs(),
+            attribute_units,
+            search_targets,
+            audit: AuditLog::new(storage.clone()),
+            quotas: Arc::new(GraphQuotas::new(storage.clone())),
+            llm_queue,
+            batch_api_keys: std::env::var("BATCH_API_KEYS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+                .collect(),
+            few_shot,
+            few_shot_top_k,
+            dry_runs: DryRunStore::new(storage.clone()),
+            answer_cache,
+            import_jobs: ImportJobStore::new(storage.clone()),
+            schema_snapshots: SchemaStore::new(storage.clone()),
+            delete_confirmations: DeleteConfirmations::new(storage.clone()),
+            graph_trash: GraphTrash::new(storage.clone()),
+            shadow,
+            storage,
+            max_var_length,
+            max_heal_attempts,
+            max_result_bytes,
+            model_prices,
+            schema_pruner,
+            graph_backup_retention,
+            graph_trash_ttl,
+            request_retention,
+            graph_aliases,
+            connections,
+            sse_channel_capacity,
+            sse_signer,
+            remote_fetcher,
+            config_errors: errors.0,
         }
     }
 
@@ -263,6 +1062,49 @@ impl AppConfig {
         APP_CONFIG.get_or_init(Self::load)
     }
 
+    /// Reads `LLM_CONCURRENCY`; unset (or 0) lets every request call the model at once.
+    fn load_llm_queue() -> Result<Option<Arc<PrioritySemaphore>>, ConfigError> {
+        Ok(env_parse("LLM_CONCURRENCY")?
+            .filter(|slots| *slots > 0)
+            .map(PrioritySemaphore::new))
+    }
+
+    /// The examples stored in `storage`, plus the curated files of `FEW_SHOT_DIR` when it is set.
+    fn load_few_shot(storage: &Arc<dyn Storage>) -> Result<FewShotStore, ConfigError> {
+        let store = FewShotStore::new(storage.clone());
+        let Ok(directory) = std::env::var("FEW_SHOT_DIR") else {
+            return Ok(store);
+        };
+        let store = store
+            .with_directory(std::path::Path::new(&directory))
+            .map_err(|e| ConfigError::new("FEW_SHOT_DIR", e))?;
+        tracing::info!(
+            "Loaded {} curated few-shot examples from {}",
+            store.curated_len(),
+            directory
+        );
+        Ok(store)
+    }
+
+    /// Reads `FEW_SHOT_TOP_K`, the examples sent with each question.
+    fn load_few_shot_top_k() -> Result<usize, ConfigError> {
+        Ok(env_parse("FEW_SHOT_TOP_K")?.unwrap_or(few_shot::DEFAULT_TOP_K))
+    }
+
+    /// The queue priority of a request sent with `api_key`: the requested one, lowered to batch
+    /// for keys in `BATCH_API_KEYS`.
+    fn request_priority(
+        &self,
+        api_key: Option<&str>,
+        requested: Priority,
+    ) -> Priority {
+        if api_key.is_some_and(|key| self.batch_api_keys.iter().any(|batch| batch == key)) {
+            Priority::Batch
+        } else {
+            requested
+        }
+    }
+
     /// Check if MCP server should be started based on configuration completeness
     #[allow(clippy::cognitive_complexity)]
     fn should_start_mcp_server(&self) -> bool {
@@ -285,10 +1127,20 @@ impl AppConfig {
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Clone)]
+#[allow(clippy::struct_excessive_bools)]
 struct TextToCypherRequest {
+    /// The graph to query. Leave it out to route the question to the graph it is about, among
+    /// `graphs`.
+    #[serde(default)]
     graph_name: String,
+    /// Graphs a request without a `graph_name` may be routed to; empty considers every graph.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    graphs: Vec<String>,
     chat_request: ChatRequest,
-    model: Option<String>,
+    /// The model, or a list of models tried in order when the model writing the query fails; a
+    /// fallback is reported as a `model_fallback` warning
+    #[schema(value_type = Option<Object>)]
+    model: Option<ModelChain>,
     key: Option<String>,
     falkordb_connection: Option<String>,
     /// Optional LLM provider endpoint/base URL override.
@@ -298,6 +1150,114 @@ struct TextToCypherRequest {
     #[serde(default)]
     #[schema(default = false)]
     cypher_only: bool,
+    /// When true, plans the validated query with `GRAPH.EXPLAIN` instead of executing it: a
+    /// `QueryPlan` event with the plan is sent, and the query is the result. Ignored with
+    /// `cypher_only`
+    #[serde(default)]
+    #[schema(default = false)]
+    dry_run: bool,
+    /// Who the answer is written for (`technical`, `analyst`, `executive`); selects the answer
+    /// template variant. Omit for the default answer.
+    #[serde(default)]
+    audience: Option<Audience>,
+    /// When true, a `Followups` event with 2–3 suggested follow-up questions is sent before the result
+    #[serde(default)]
+    #[schema(default = false)]
+    followups: bool,
+    /// Session (from `POST /sessions`) to record this turn in. The request still carries the full
+    /// chat history; the session keeps it so the conversation can be exported later.
+    #[serde(default)]
+    session_id: Option<String>,
+    /// When true, queries without RETURN or LIMIT, or using labels missing from the schema, are
+    /// refused with an error instead of executed
+    #[serde(default)]
+    #[schema(default = false)]
+    strict_validation: bool,
+    /// When true and the linter reports hints (cartesian products, undirected relationships,
+    /// unbounded variable-length paths, OPTIONAL MATCH misuse), the model gets one chance to
+    /// rewrite the query; the rewrite is kept only if it validates and has fewer hints
+    #[serde(default)]
+    #[schema(default = false)]
+    refine_lint_hints: bool,
+    /// Allows generating DELETE / DETACH DELETE queries. Requires an API key with `write` scope. A
+    /// destructive query is never executed directly: a `DryRun` preview with a confirmation token is
+    /// returned instead
+    #[serde(default)]
+    #[schema(default = false)]
+    allow_destructive: bool,
+    /// Confirmation token from a `DryRun` event; executes exactly the previewed query (no model
+    /// call). Requires an API key with `write` scope
+    #[serde(default)]
+    confirm: Option<String>,
+    /// `fast` uses `FAST_MODEL` and a compact schema and never retries; `thorough` keeps the best of
+    /// several candidate queries (checked with EXPLAIN) and checks the answer against the result
+    #[serde(default)]
+    latency_mode: LatencyMode,
+    /// Embedding of the text a similarity question compares against, from `POST /embed`; passed to
+    /// the query as `$query_vector`
+    #[serde(default)]
+    query_vector: Option<Vec<f32>>,
+    /// When true, the model writes values from the question as `$name` parameters and gives their
+    /// values separately; they reach the database in the query's `CYPHER` preamble, so the plan of
+    /// a query asked again with other values is reused
+    #[serde(default)]
+    #[schema(default = false)]
+    parameterized: bool,
+    /// Sample size and relationship pairing of schema discovery; a schema discovered with other
+    /// than the defaults is not cached
+    #[serde(default)]
+    discovery: DiscoveryOptions,
+    /// Temperature, output token limit and nucleus sampling of the calls that write the query and
+    /// the answer; unset parameters keep the provider's defaults
+    #[serde(default)]
+    model_options: ModelOptions,
+    /// Order of the request among those waiting for a model slot under `LLM_CONCURRENCY`; keys
+    /// listed in `BATCH_API_KEYS` always queue as `batch`
+    #[serde(default)]
+    priority: Priority,
+    /// Schema sent to the model instead of a discovered one, for curated ontologies or graphs too
+    /// large to sample: a JSON document in the discovered schema's shape, or a string used as is.
+    /// Discovery and the schema cache are skipped
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    schema: Option<serde_json::Value>,
+    /// Clauses the generated query may use, from the policies bound to the caller's API key and
+    /// graph; set by the server, never by the client.
+    #[serde(skip)]
+    #[schema(ignore)]
+    clause_policy: Option<ClausePolicy>,
+    /// The tenant the generated query is restricted to on a graph shared between tenants, from the
+    /// caller's API key; set by the server, never by the client.
+    #[serde(skip)]
+    #[schema(ignore)]
+    tenant_filter: Option<TenantFilter>,
+    /// The graph only answers statistical questions (`AGGREGATE_ONLY_GRAPHS`), so the generated
+    /// query must return aggregates only; set by the server, never by the client.
+    #[serde(skip)]
+    #[schema(ignore)]
+    aggregate_only: bool,
+    /// The query that answered the session's previous question, sent to the model as context; set
+    /// by `POST /sessions/{id}/ask`, never by the client.
+    #[serde(skip)]
+    #[schema(ignore)]
+    previous_query: Option<String>,
+    /// The graph's examples most similar to the question, rendered for the model; set by the
+    /// server from `FEW_SHOT_DIR` and `/admin/examples`, never by the client.
+    #[serde(skip)]
+    #[schema(ignore)]
+    few_shot_prompt: Option<String>,
+}
+
+impl TextToCypherRequest {
+    /// `query` with the request's query vector declared, as it is sent to the database.
+    fn executable(
+        &self,
+        query: &str,
+    ) -> String {
+        self.query_vector
+            .as_deref()
+            .map_or_else(|| query.to_string(), |vector| with_query_vector(query, vector))
+    }
 }
 
 impl std::fmt::Debug for TextToCypherRequest {
@@ -308,9 +1268,29 @@ impl std::fmt::Debug for TextToCypherRequest {
         let mut debug_struct = f.debug_struct("TextToCypherRequest");
         debug_struct
             .field("graph_name", &self.graph_name)
+            .field("graphs", &self.graphs)
             .field("chat_request", &self.chat_request)
             .field("model", &self.model)
-            .field("cypher_only", &self.cypher_only);
+            .field("cypher_only", &self.cypher_only)
+            .field("dry_run", &self.dry_run)
+            .field("audience", &self.audience)
+            .field("followups", &self.followups)
+            .field("session_id", &self.session_id)
+            .field("strict_validation", &self.strict_validation)
+            .field("refine_lint_hints", &self.refine_lint_hints)
+            .field("allow_destructive", &self.allow_destructive)
+            .field("latency_mode", &self.latency_mode)
+            .field("query_vector", &self.query_vector.as_ref().map(Vec::len))
+            .field("parameterized", &self.parameterized)
+            .field("discovery", &self.discovery)
+            .field("model_options", &self.model_options)
+            .field("priority", &self.priority)
+            .field("schema", &self.schema.is_some())
+            .field("clause_policy", &self.clause_policy)
+            .field("tenant_filter", &self.tenant_filter)
+            .field("aggregate_only", &self.aggregate_only)
+            .field("previous_query", &self.previous_query)
+            .field("few_shot_prompt", &self.few_shot_prompt.is_some());
 
         if self.key.is_some() {
             debug_struct.field("key", &"***");
@@ -321,22 +1301,205 @@ impl std::fmt::Debug for TextToCypherRequest {
         if self.llm_endpoint.is_some() {
             debug_struct.field("llm_endpoint", &self.llm_endpoint);
         }
+        if self.confirm.is_some() {
+            debug_struct.field("confirm", &"***");
+        }
 
         debug_struct.finish()
     }
 }
 
-#[derive(Serialize, Deserialize, ToSchema)]
-enum Progress {
-    Status(String),
-    Schema(String),
-    CypherQuery(String),
-    CypherResult(String),
-    ModelOutputChunk(String),
-    Result(String),
-    Confidence(u8),
-    Usage(TokenUsage),
-    Error(String),
+/// Idle seconds after which a session expires when `SESSION_TTL_SECS` is not set (one day).
+const DEFAULT_SESSION_TTL_SECS: u64 = 86_400;
+
+/// Events buffered per stream when `SSE_CHANNEL_CAPACITY` is not set.
+const DEFAULT_SSE_CHANNEL_CAPACITY: usize = 100;
+
+/// Channel slots answer chunks leave free, so the events that end a stream (the rest of the
+/// answer, usage, confidence and the result) fit even when the client has stopped reading.
+const TERMINAL_EVENT_SLOTS: usize = 8;
+
+/// Status events with fixed text, serialized once and shared by every request.
+static STATUS_EVENTS: std::sync::LazyLock<std::sync::Mutex<std::collections::HashMap<&'static str, ByteString>>> =
+    std::sync::LazyLock::new(Default::default);
+
+/// The sending half of a request's SSE stream.
+///
+/// Every event owns its bytes, so an update costs exactly one serialization into a buffer that
+/// becomes the event without copying. Events become SSE messages in [`progress_stream`], which
+/// signs them in the order the client receives them. Fixed status messages are serialized once per process and
+/// shared ([`Self::status`]), and answer chunks are escaped straight into their event
+/// ([`Self::chunk`]) rather than built as a `Progress` first.
+#[derive(Clone)]
+struct ProgressSender {
+    tx: mpsc::Sender<ByteString>,
+    /// Set by the last terminal event sent, for [`Self::finish`].
+    status: Arc<std::sync::Mutex<StreamStatus>>,
+}
+
+impl ProgressSender {
+    /// Creates a sender and the receiver to build the `Sse` response from.
+    fn channel(capacity: usize) -> (Self, mpsc::Receiver<ByteString>) {
+        let (tx, rx) = mpsc::channel(capacity);
+        let status = Arc::new(std::sync::Mutex::new(StreamStatus::Error));
+        (Self { tx, status }, rx)
+    }
+
+    fn event(json: Vec<u8>) -> Result<ByteString, StreamClosed> {
+        // serde_json only writes UTF-8, so this never copies or fails.
+        String::from_utf8(json).map(ByteString::from).map_err(|_| StreamClosed)
+    }
+
+    fn serialize(progress: &Progress) -> Result<ByteString, StreamClosed> {
+        let json = serde_json::to_vec(progress).map_err(|e| {
+            tracing::error!("Failed to serialize progress update: {}", e);
+            StreamClosed
+        })?;
+        Self::event(json)
+    }
+
+    async fn send_event(
+        &self,
+        event: ByteString,
+    ) -> Result<(), StreamClosed> {
+        self.tx.send(event).await.map_err(|_| {
+            tracing::warn!("Client disconnected, stopping stream");
+            StreamClosed
+        })
+    }
+
+    /// Sends an update, waiting for room in the channel. `Usage` is sent with its estimated cost.
+    async fn send(
+        &self,
+        mut progress: Progress,
+    ) -> Result<(), StreamClosed> {
+        if let Progress::Usage(usage) = &mut progress {
+            usage.estimate_cost(&AppConfig::get().model_prices);
+        }
+        let status = progress.terminal_status();
+        self.send_event(Self::serialize(&progress)?).await?;
+        if let (Some(status), Ok(mut current)) = (status, self.status.lock()) {
+            *current = status;
+        }
+        Ok(())
+    }
+
+    /// Ends the stream with a `Done` event carrying the status of the last terminal event sent.
+    async fn finish(
+        &self,
+        request_id: &str,
+    ) {
+        let status = self.status.lock().map_or(StreamStatus::Error, |status| *status);
+        let done = Progress::Done {
+            status,
+            request_id: request_id.to_string(),
+        };
+        if let Ok(event) = Self::serialize(&done) {
+            let _ = self.send_event(event).await;
+        }
+    }
+
+    /// Sends a `Status` update with fixed text.
+    async fn status(
+        &self,
+        message: &'static str,
+    ) -> Result<(), StreamClosed> {
+        let cached = STATUS_EVENTS.lock().ok().and_then(|events| events.get(message).cloned());
+        let event = if let Some(event) = cached {
+            event
+        } else {
+            let event = Self::serialize(&Progress::Status(message.to_string()))?;
+            if let Ok(mut events) = STATUS_EVENTS.lock() {
+                events.insert(message, event.clone());
+            }
+            event
+        };
+        self.send_event(event).await
+    }
+
+    fn chunk_event(text: &str) -> Result<ByteString, StreamClosed> {
+        const PREFIX: &[u8] = br#"{"ModelOutputChunk":"#;
+        // Room for the wrapper, the quotes and a little escaping.
+        let mut json = Vec::with_capacity(PREFIX.len() + text.len() + 8);
+        json.extend_from_slice(PREFIX);
+        serde_json::to_writer(&mut json, text).map_err(|_| StreamClosed)?;
+        json.push(b'}');
+        Self::event(json)
+    }
+
+    /// Sends a `ModelOutputChunk` update for a piece of the streamed answer.
+    async fn chunk(
+        &self,
+        text: &str,
+    ) -> Result<(), StreamClosed> {
+        self.send_event(Self::chunk_event(text)?).await
+    }
+
+    /// Sends a `ModelOutputChunk` update only if the client is keeping up, leaving
+    /// [`TERMINAL_EVENT_SLOTS`] free. Returns `false` if the chunk was held back; the caller sends
+    /// the text with the next chunk, so a slow client gets fewer, larger chunks instead of
+    /// stalling the model stream.
+    fn try_chunk(
+        &self,
+        text: &str,
+    ) -> Result<bool, StreamClosed> {
+        if self.tx.is_closed() {
+            tracing::warn!("Client disconnected, stopping stream");
+            return Err(StreamClosed);
+        }
+        if self.tx.capacity() <= TERMINAL_EVENT_SLOTS {
+            return Ok(false);
+        }
+        match self.tx.try_send(Self::chunk_event(text)?) {
+            Ok(()) => Ok(true),
+            Err(mpsc::error::TrySendError::Full(_)) => Ok(false),
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                tracing::warn!("Client disconnected, stopping stream");
+                Err(StreamClosed)
+            }
+        }
+    }
+
+    /// Sends an update only if the channel has room, for best-effort milestones that must not
+    /// stall the work they report on.
+    fn try_send(
+        &self,
+        progress: &Progress,
+    ) {
+        if let Ok(event) = Self::serialize(progress) {
+            let _ = self.tx.try_send(event);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ProgressReporter for ProgressSender {
+    async fn report(
+        &self,
+        progress: Progress,
+    ) -> Result<(), StreamClosed> {
+        self.send(progress).await
+    }
+}
+
+/// The SSE response for a request's events. With `SSE_SIGNING_KEY` set, each event gets a signed
+/// `id:` (see [`::text_to_cypher::event_signing`]) numbering it within this stream.
+fn progress_stream(
+    rx: mpsc::Receiver<ByteString>
+) -> Sse<impl futures_util::Stream<Item = Result<sse::Event, actix_web::Error>>> {
+    let signer = AppConfig::get().sse_signer.clone();
+    let stream_id = Uuid::new_v4().simple().to_string();
+    let mut seq = 0;
+    let stream = tokio_stream::wrappers::ReceiverStream::new(rx).map(move |data| {
+        let id = signer.as_ref().map(|signer| signer.sign(&stream_id, seq, &data));
+        seq += 1;
+        let mut event = sse::Data::new(data);
+        if let Some(id) = id {
+            event.set_id(id);
+        }
+        Ok(sse::Event::Data(event))
+    });
+    Sse::from_stream(stream)
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -344,11 +1507,119 @@ struct ConfiguredModelResponse {
     model: String,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+struct EmbedRequest {
+    /// Text to embed, e.g. the passage a "find documents similar to ..." question refers to.
+    text: String,
+    /// Embedding model, e.g. `openai:text-embedding-3-small`. Defaults to `EMBEDDING_MODEL`; its
+    /// dimension must match the vector property's `dimension` in the schema.
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    key: Option<String>,
+    /// Optional LLM provider endpoint/base URL override.
+    #[serde(default, alias = "endpoint", alias = "base_url", alias = "baseUrl")]
+    llm_endpoint: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct EmbedResponse {
+    /// Pass as `query_vector` in a `/text_to_cypher` request.
+    query_vector: Vec<f32>,
+    dimension: usize,
+    model: String,
+}
+
+/// Most nodes one search returns.
+const MAX_SEARCH_LIMIT: usize = 100;
+
+const fn default_search_limit() -> usize {
+    10
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct SearchRequest {
+    /// Keywords for the full-text targets, also embedded for the vector targets unless
+    /// `query_vector` is given.
+    text: String,
+    /// Targets such as `Movie;Movie.embedding` (a label searches its full-text index,
+    /// `Label.property` a vector property). Defaults to `SEARCH_TARGETS`.
+    #[serde(default)]
+    targets: Option<String>,
+    /// Most nodes returned, up to 100.
+    #[serde(default = "default_search_limit")]
+    #[schema(default = 10)]
+    limit: usize,
+    /// Vector to compare the vector targets against instead of embedding `text`.
+    #[serde(default)]
+    query_vector: Option<Vec<f32>>,
+    /// Embedding model for the vector targets. Defaults to `EMBEDDING_MODEL`; without one, only
+    /// the full-text targets are searched.
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    key: Option<String>,
+    /// Optional LLM provider endpoint/base URL override.
+    #[serde(default, alias = "endpoint", alias = "base_url", alias = "baseUrl")]
+    llm_endpoint: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct SearchResponse {
+    /// Best first.
+    hits: Vec<SearchHit>,
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 struct ErrorResponse {
     error: String,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+struct CreateSessionRequest {
+    /// Graph the conversation is about.
+    graph_name: String,
+}
+
+/// A follow-up question in a session; the server supplies the chat history and graph.
+#[derive(Serialize, Deserialize, ToSchema)]
+struct AskRequest {
+    question: String,
+    /// The model, or a list of models tried in order
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    model: Option<ModelChain>,
+    #[serde(default)]
+    key: Option<String>,
+    /// Optional LLM provider endpoint/base URL override.
+    #[serde(default, alias = "endpoint", alias = "base_url", alias = "baseUrl")]
+    llm_endpoint: Option<String>,
+    /// When true, returns only the validated Cypher query without executing it
+    #[serde(default)]
+    #[schema(default = false)]
+    cypher_only: bool,
+    /// Who the answer is written for (`technical`, `analyst`, `executive`)
+    #[serde(default)]
+    audience: Option<Audience>,
+    /// When true, a `Followups` event with 2–3 suggested follow-up questions is sent before the result
+    #[serde(default)]
+    #[schema(default = false)]
+    followups: bool,
+    #[serde(default)]
+    latency_mode: LatencyMode,
+    /// Temperature, output token limit and nucleus sampling of the query and answer calls
+    #[serde(default)]
+    model_options: ModelOptions,
+    /// Order of the question among those waiting for a model slot
+    #[serde(default)]
+    priority: Priority,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct SessionCreatedResponse {
+    session_id: String,
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 struct GraphQueryRequest {
     data: Vec<serde_json::Value>,
@@ -370,6 +1641,32 @@ struct LoadCsvRequest {
     data: Vec<serde_json::Value>,
 }
 
+/// A remote file to fetch into `IMPORT_FOLDER`, and the query that loads it.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct RemoteImportRequest {
+    /// `https://` URL or `s3://bucket/key` of a `.csv` or `.json` file.
+    url: String,
+    /// Query reading the file as `file://<name>.csv` (or `.json`); the name is replaced with the
+    /// fetched file's.
+    cypher_query: String,
+    /// Expected hex SHA-256 of the file; the import fails when the download differs.
+    #[serde(default)]
+    sha256: Option<String>,
+    /// Largest accepted file in bytes, at most `REMOTE_IMPORT_MAX_BYTES`.
+    #[serde(default)]
+    max_bytes: Option<u64>,
+}
+
+/// The fetched file and the rows its query returned.
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct RemoteImportResponse {
+    bytes: u64,
+    /// Hex SHA-256 of the fetched file.
+    sha256: String,
+    /// The query's rows, as `/graph_query` returns them.
+    result: serde_json::Value,
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
 struct EchoRequest {
     #[serde(flatten)]
@@ -402,10 +1699,14 @@ fn process_clear_udf_cache() {
     path = "/get_schema/{graph_name}",
     params(
         ("graph_name" = String, Path, description = "Name of the graph to get schema for"),
-        ("falkordb_connection" = Option<String>, Query, description = "Optional FalkorDB connection string to override default")
+        ("falkordb_connection" = Option<String>, Query, description = "Optional FalkorDB connection string to override default"),
+        ("sample_size" = Option<usize>, Query, description = "Nodes (or relationships) sampled per label; default 100"),
+        ("pair_relationships" = Option<bool>, Query, description = "Set to false to list relationship types without \
+            the labels they connect, which skips a full scan per type on huge graphs; default true")
     ),
     responses(
-        (status = 200, description = "Graph schema as JSON string", body = String)
+        (status = 200, description = "Graph schema as JSON string", body = String,
+            example = json!(api_examples::get_schema_response()))
     )
 )]
 #[actix_web::get("/get_schema/{graph_name}")]
@@ -413,16 +1714,16 @@ async fn get_schema_endpoint(
     graph_name: actix_web::web::Path<String>,
     query: actix_web::web::Query<GetSchemaQuery>,
 ) -> Result<impl Responder, actix_web::Error> {
-    let graph_name = graph_name.into_inner();
+    let graph_name = resolve_graph_name(&graph_name);
     let falkordb_connection = query
         .falkordb_connection
-        .as_ref()
-        .unwrap_or_else(|| &AppConfig::get().falkordb_connection);
+        .as_deref()
+        .map_or_else(|| AppConfig::get().falkordb_connection.clone(), resolve_connection);
 
     tracing::info!("Getting schema for graph: {}", graph_name);
 
-    match get_graph_schema_string(falkordb_connection, &graph_name).await {
-        Ok(schema) => Ok(HttpResponse::Ok().json(schema)),
+    match get_graph_schema_string(&falkordb_connection, &graph_name, query.discovery()).await {
+        Ok(schema) => Ok(HttpResponse::Ok().json(&*schema)),
         Err(e) => {
             tracing::error!("Failed to get schema for graph {}: {}", graph_name, e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
@@ -454,64 +1755,1021 @@ async fn configured_model_endpoint() -> Result<impl Responder, actix_web::Error>
     )
 }
 
-#[allow(clippy::cognitive_complexity)]
 #[utoipa::path(
     post,
-    path = "/graph_query",
-    request_body = GraphQueryRequest,
+    path = "/embed",
+    request_body(content = EmbedRequest, example = json!(api_examples::embed_request())),
     responses(
-        (status = 200, description = "Query executed successfully", body = String, content_type = "application/json"),
-        (status = 400, description = "Query execution failed", body = ErrorResponse)
+        (status = 200, description = "Embedding to pass as the query vector of a similarity question",
+            body = EmbedResponse, example = json!(api_examples::embed_response())),
+        (status = 400, description = "No model given and EMBEDDING_MODEL is not set", body = ErrorResponse),
+        (status = 502, description = "The embedding request failed", body = ErrorResponse)
     )
 )]
-#[post("/graph_query")]
-async fn graph_query_endpoint(
-    req: actix_web::web::Json<GraphQueryRequest>
-) -> Result<impl Responder, actix_web::Error> {
-    let raw_request = req.into_inner();
-
-    // Log the incoming Snowflake format request
-    tracing::info!("Received graph_query request with Snowflake format");
-    tracing::info!(
-        "Raw JSON payload: {}",
-        serde_json::to_string_pretty(&raw_request).unwrap_or_else(|_| "Failed to serialize".to_string())
-    );
+#[post("/embed")]
+async fn embed_endpoint(
+    state: web::Data<AppState>,
+    req: actix_web::web::Json<EmbedRequest>,
+) -> impl Responder {
+    let request = req.into_inner();
+    let Some(model) = request.model.or_else(|| AppConfig::get().embedding_model.clone()) else {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "No embedding model given and EMBEDDING_MODEL is not set".to_string(),
+        });
+    };
+    let client = state.genai_client(request.key.as_deref(), request.llm_endpoint.as_deref());
+    match embed_text(&client, &model, &request.text).await {
+        Ok(query_vector) => HttpResponse::Ok().json(EmbedResponse {
+            dimension: query_vector.len(),
+            query_vector,
+            model,
+        }),
+        Err(e) => {
+            tracing::error!("Failed to embed text with {}: {}", model, e);
+            HttpResponse::BadGateway().json(ErrorResponse { error: e.to_string() })
+        }
+    }
+}
 
-    // Validate the Snowflake format: data should be an array with at least one entry
-    if raw_request.data.is_empty() {
-        tracing::error!("Empty data array in Snowflake request");
-        return Ok(create_snowflake_error_response("Data array cannot be empty"));
+#[utoipa::path(
+    post,
+    path = "/graphs/{graph_name}/search",
+    params(
+        ("graph_name" = String, Path, description = "Graph to search")
+    ),
+    request_body(content = SearchRequest, example = json!(api_examples::search_request())),
+    responses(
+        (status = 200, description = "Nodes ranked by keyword and vector similarity together", body = SearchResponse,
+            example = json!(api_examples::search_response())),
+        (status = 400, description = "Invalid targets, or none given and SEARCH_TARGETS is not set", body = ErrorResponse),
+        (status = 403, description = "The graph is shared between tenants (`TENANT_POLICY`) or aggregate-only (`AGGREGATE_ONLY_GRAPHS`)", body = ErrorResponse),
+        (status = 500, description = "No target could be searched", body = ErrorResponse),
+        (status = 502, description = "The text could not be embedded", body = ErrorResponse)
+    )
+)]
+#[post("/graphs/{graph_name}/search")]
+async fn search_endpoint(
+    state: web::Data<AppState>,
+    graph_name: web::Path<String>,
+    req: actix_web::web::Json<SearchRequest>,
+) -> impl Responder {
+    let request = req.into_inner();
+    let config = AppConfig::get();
+    let graph_name = resolve_graph_name(&graph_name);
+    // Index queries return every tenant's nodes
+    if config.tenant_policy.covers(&graph_name) {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: format!("Graph '{graph_name}' is shared between tenants and cannot be searched"),
+        });
+    }
+    // Search returns the matching nodes themselves
+    if config.aggregate_only(&graph_name) {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: format!("Graph '{graph_name}' only answers aggregate queries and cannot be searched"),
+        });
+    }
+    let targets = match request.targets.as_deref().map(SearchTargets::parse) {
+        Some(Ok(targets)) => targets,
+        Some(Err(e)) => return HttpResponse::BadRequest().json(ErrorResponse { error: e }),
+        None => config.search_targets.clone(),
+    };
+    if targets.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "No search targets given and SEARCH_TARGETS is not set".to_string(),
+        });
     }
 
-    // Get the first entry from the data array
-    let first_entry = &raw_request.data[0];
+    let mut query_vector = request.query_vector;
+    if query_vector.is_none()
+        && targets.needs_vector()
+        && let Some(model) = request.model.or_else(|| config.embedding_model.clone())
+    {
+        let client = state.genai_client(request.key.as_deref(), request.llm_endpoint.as_deref());
+        match embed_text(&client, &model, &request.text).await {
+            Ok(vector) => query_vector = Some(vector),
+            Err(e) => {
+                tracing::error!("Failed to embed search text with {}: {}", model, e);
+                return HttpResponse::BadGateway().json(ErrorResponse { error: e.to_string() });
+            }
+        }
+    }
 
-    // Snowflake format: data[0] should be an array where [0] is index and [1] is the actual data
-    let data_array = first_entry.as_array().ok_or_else(|| {
-        tracing::error!("First data entry is not an array");
-        actix_web::error::ErrorBadRequest("First data entry must be an array")
-    })?;
+    let limit = request.limit.clamp(1, MAX_SEARCH_LIMIT);
+    match search(
+        &state.falkordb_connection,
+        &graph_name,
+        &targets,
+        &request.text,
+        query_vector.as_deref(),
+        limit,
+    )
+    .await
+    {
+        Ok(hits) => HttpResponse::Ok().json(SearchResponse { hits }),
+        Err(e) => {
+            tracing::error!("Failed to search graph {}: {}", graph_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse { error: e.to_string() })
+        }
+    }
+}
 
-    if data_array.len() < 2 {
-        tracing::error!("Data array must have at least 2 elements [index, data]");
-        return Ok(create_snowflake_error_response(
-            "Data array must have at least 2 elements [index, data]",
-        ));
+#[utoipa::path(
+    get,
+    path = "/graphs/{graph_name}/backups",
+    params(
+        ("graph_name" = String, Path, description = "Graph whose backups to list")
+    ),
+    responses(
+        (status = 200, description = "The graph's backups, newest first", body = Vec<GraphBackup>),
+        (status = 500, description = "The graphs could not be listed", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/graphs/{graph_name}/backups")]
+async fn graph_backups_endpoint(
+    state: web::Data<AppState>,
+    graph_name: web::Path<String>,
+) -> impl Responder {
+    let graph_name = resolve_graph_name(&graph_name);
+    let backups = match state.falkordb().await {
+        Ok((client, target)) => graph_backup::list(&client, &target, &graph_name)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    match backups {
+        Ok(backups) => HttpResponse::Ok().json(backups),
+        Err(e) => {
+            tracing::error!("Failed to list backups of graph {}: {}", graph_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse { error: e })
+        }
     }
+}
 
-    // Extract the actual data object (second element in the array)
-    let data_object = &data_array[1];
+/// Request body of `POST /graphs/{graph_name}/restore`.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Default)]
+struct RestoreRequest {
+    /// Backup to restore, from `GET /graphs/{graph_name}/backups`; the newest when omitted
+    #[serde(default)]
+    backup: Option<String>,
+}
 
-    tracing::info!(
-        "Extracted data object: {}",
-        serde_json::to_string_pretty(data_object).unwrap_or_else(|_| "Failed to serialize".to_string())
+#[utoipa::path(
+    post,
+    path = "/graphs/{graph_name}/restore",
+    params(
+        ("graph_name" = String, Path, description = "Graph to roll back")
+    ),
+    request_body(content = RestoreRequest),
+    responses(
+        (status = 200, description = "The graph was replaced with a copy of the backup, which is kept",
+            body = GraphBackup),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks write scope", body = ErrorResponse),
+        (status = 404, description = "The graph has no such backup", body = ErrorResponse),
+        (status = 500, description = "The graph could not be replaced", body = ErrorResponse)
+    )
+)]
+#[post("/graphs/{graph_name}/restore")]
+async fn graph_restore_endpoint(
+    state: web::Data<AppState>,
+    api_key: RequestApiKey,
+    graph_name: web::Path<String>,
+    req: Option<actix_web::web::Json<RestoreRequest>>,
+) -> impl Responder {
+    if let Err(e) = authorize(api_key.0.as_deref(), Scope::Write) {
+        let mut response = if matches!(e, AuthError::InsufficientScope { .. }) {
+            HttpResponse::Forbidden()
+        } else {
+            HttpResponse::Unauthorized()
+        };
+        return response.json(ErrorResponse { error: e.to_string() });
+    }
+    let config = AppConfig::get();
+    let graph_name = resolve_graph_name(&graph_name);
+    let request = req.map(actix_web::web::Json::into_inner).unwrap_or_default();
+    let restored = match state.falkordb().await {
+        Ok((client, target)) => graph_backup::restore(&client, &target, &graph_name, request.backup.as_deref()).await,
+        Err(e) => Err(graph_backup::BackupError::Database(e)),
+    };
+
+    let record = AuditRecord::new(
+        api_key.0.as_deref().map(ApiKeys::key_id),
+        "graph_restore",
+        &graph_name,
+        "",
+        if restored.is_ok() {
+            AuditOutcome::Success
+        } else {
+            AuditOutcome::Failure
+        },
+        Some(match &restored {
+            Ok(backup) => backup.name.clone(),
+            Err(e) => e.to_string(),
+        }),
     );
+    if let Err(e) = config.audit.record(&record).await {
+        tracing::error!("Failed to write audit record {}: {}", record.id, e);
+    }
 
-    // Extract the required fields from the data object
-    let graph_name = data_object
-        .get("graph_name")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| {
+    match restored {
+        Ok(backup) => {
+            // The restored graph may have another schema than the one cached.
+            process_clear_schema_cache(&graph_name);
+            HttpResponse::Ok().json(backup)
+        }
+        Err(e @ graph_backup::BackupError::NotFound(_)) => {
+            HttpResponse::NotFound().json(ErrorResponse { error: e.to_string() })
+        }
+        Err(e) => {
+            tracing::error!("Failed to restore graph {}: {}", graph_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse { error: e.to_string() })
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats",
+    responses(
+        (status = 200, description = "Anonymous usage statistics", body = StatsSnapshot),
+        (status = 404, description = "Usage statistics are disabled", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/stats")]
+async fn stats_endpoint() -> impl Responder {
+    let stats = &AppConfig::get().usage_stats;
+    if stats.is_enabled() {
+        HttpResponse::Ok().json(stats.snapshot())
+    } else {
+        HttpResponse::NotFound().json(ErrorResponse {
+            error: "Usage statistics are disabled; set USAGE_STATS=true to enable".to_string(),
+        })
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/shadow",
+    responses(
+        (status = 200, description = "Divergence between production and shadow queries", body = ShadowReport),
+        (status = 404, description = "Shadow traffic is disabled", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/shadow")]
+async fn shadow_endpoint() -> impl Responder {
+    AppConfig::get().shadow.as_ref().map_or_else(
+        || {
+            HttpResponse::NotFound().json(ErrorResponse {
+                error: "Shadow traffic is disabled; set SHADOW_MODEL to enable".to_string(),
+            })
+        },
+        |traffic| HttpResponse::Ok().json(traffic.shadow.report()),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/usage",
+    params(
+        ("from" = Option<u64>, Query, description = "Start of the report, as Unix milliseconds; default 30 days ago"),
+        ("to" = Option<u64>, Query, description = "End of the report (exclusive), as Unix milliseconds; default now"),
+        ("group_by" = Option<String>, Query, description = "`graph` (default), `model`, or `key` (the API key ID)")
+    ),
+    responses(
+        (status = 200, description = "Requests, error rate, tokens, estimated cost, p95 latency and self-healing rate \
+            of each group", body = UsageReport),
+        (status = 400, description = "Unknown group_by, or `from` not before `to`", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks admin scope", body = ErrorResponse),
+        (status = 500, description = "Audit records could not be read", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/admin/usage")]
+async fn usage_report_endpoint(
+    api_key: RequestApiKey,
+    query: actix_web::web::Query<UsageQuery>,
+) -> impl Responder {
+    if let Err(response) = require_admin(&api_key) {
+        return response;
+    }
+    let group_by = match query.group_by.as_deref().map(str::parse::<UsageGroupBy>).transpose() {
+        Ok(group_by) => group_by.unwrap_or_default(),
+        Err(error) => return HttpResponse::BadRequest().json(ErrorResponse { error }),
+    };
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX));
+    let to_ms = query.to.unwrap_or(now_ms);
+    let from_ms = query.from.unwrap_or_else(|| {
+        to_ms.saturating_sub(u64::try_from(DEFAULT_REQUEST_RETENTION.as_millis()).unwrap_or(u64::MAX))
+    });
+    if from_ms >= to_ms {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("'from' ({from_ms}) must be before 'to' ({to_ms})"),
+        });
+    }
+
+    match AppConfig::get().audit.list_between(from_ms, to_ms).await {
+        Ok(records) => HttpResponse::Ok().json(UsageReport::new(&records, from_ms, to_ms, group_by)),
+        Err(e) => {
+            tracing::error!("Failed to read audit records: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to read audit records: {e}"),
+            })
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UsageQuery {
+    from: Option<u64>,
+    to: Option<u64>,
+    group_by: Option<String>,
+}
+
+/// Checks that the request carries an admin API key; otherwise the 401 or 403 response to send.
+fn require_admin(api_key: &RequestApiKey) -> Result<(), HttpResponse> {
+    authorize(api_key.0.as_deref(), Scope::Admin).map_err(|e| {
+        let mut response = if matches!(e, AuthError::InsufficientScope { .. }) {
+            HttpResponse::Forbidden()
+        } else {
+            HttpResponse::Unauthorized()
+        };
+        response.json(ErrorResponse { error: e.to_string() })
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/quotas",
+    responses(
+        (status = 200, description = "Each graph's quota, with the requests and tokens it used this hour and day on \
+            this server", body = Vec<QuotaStatus>),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks admin scope", body = ErrorResponse),
+        (status = 500, description = "Quotas could not be read", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/admin/quotas")]
+async fn list_quotas_endpoint(api_key: RequestApiKey) -> impl Responder {
+    if let Err(response) = require_admin(&api_key) {
+        return response;
+    }
+    match AppConfig::get().quotas.list().await {
+        Ok(quotas) => HttpResponse::Ok().json(quotas),
+        Err(e) => {
+            tracing::error!("Failed to list quotas: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to list quotas: {e}"),
+            })
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/quotas/{graph_name}",
+    params(
+        ("graph_name" = String, Path, description = "Graph (or alias) the quota applies to")
+    ),
+    request_body = GraphQuota,
+    responses(
+        (status = 200, description = "The graph's new quota and its usage", body = QuotaStatus),
+        (status = 400, description = "The quota sets no limit", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks admin scope", body = ErrorResponse),
+        (status = 500, description = "Quota could not be saved", body = ErrorResponse)
+    )
+)]
+#[actix_web::put("/admin/quotas/{graph_name}")]
+async fn set_quota_endpoint(
+    api_key: RequestApiKey,
+    graph_name: actix_web::web::Path<String>,
+    quota: actix_web::web::Json<GraphQuota>,
+) -> impl Responder {
+    if let Err(response) = require_admin(&api_key) {
+        return response;
+    }
+    let quota = quota.into_inner();
+    if quota.is_unlimited() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Set requests_per_hour, tokens_per_day or both; DELETE the quota to lift it".to_string(),
+        });
+    }
+    let graph_name = resolve_graph_name(&graph_name);
+    let quotas = &AppConfig::get().quotas;
+    match quotas.set(&graph_name, quota).await {
+        Ok(()) => {
+            tracing::info!("Quota of graph {} set to {:?}", graph_name, quota);
+            HttpResponse::Ok().json(quotas.status(&graph_name, quota))
+        }
+        Err(e) => {
+            tracing::error!("Failed to save the quota of graph {}: {}", graph_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to save quota: {e}"),
+            })
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/quotas/{graph_name}",
+    params(
+        ("graph_name" = String, Path, description = "Graph (or alias) whose quota is lifted")
+    ),
+    responses(
+        (status = 204, description = "Quota lifted"),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks admin scope", body = ErrorResponse),
+        (status = 404, description = "The graph has no quota", body = ErrorResponse),
+        (status = 500, description = "Quota could not be removed", body = ErrorResponse)
+    )
+)]
+#[actix_web::delete("/admin/quotas/{graph_name}")]
+async fn delete_quota_endpoint(
+    api_key: RequestApiKey,
+    graph_name: actix_web::web::Path<String>,
+) -> impl Responder {
+    if let Err(response) = require_admin(&api_key) {
+        return response;
+    }
+    let graph_name = resolve_graph_name(&graph_name);
+    match AppConfig::get().quotas.remove(&graph_name).await {
+        Ok(true) => {
+            tracing::info!("Quota of graph {} lifted", graph_name);
+            HttpResponse::NoContent().finish()
+        }
+        Ok(false) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Graph '{graph_name}' has no quota"),
+        }),
+        Err(e) => {
+            tracing::error!("Failed to remove the quota of graph {}: {}", graph_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to remove quota: {e}"),
+            })
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/examples/{graph_name}",
+    params(
+        ("graph_name" = String, Path, description = "Graph (or alias) whose examples are listed")
+    ),
+    responses(
+        (status = 200, description = "The graph's curated examples, then the stored ones", body = Vec<FewShotExample>),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks admin scope", body = ErrorResponse),
+        (status = 500, description = "Examples could not be read", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/admin/examples/{graph_name}")]
+async fn get_examples_endpoint(
+    api_key: RequestApiKey,
+    graph_name: actix_web::web::Path<String>,
+) -> impl Responder {
+    if let Err(response) = require_admin(&api_key) {
+        return response;
+    }
+    let graph_name = resolve_graph_name(&graph_name);
+    match AppConfig::get().few_shot.examples(&graph_name).await {
+        Ok(examples) => HttpResponse::Ok().json(examples),
+        Err(e) => {
+            tracing::error!("Failed to read the examples of graph {}: {}", graph_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to read examples: {e}"),
+            })
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/examples/{graph_name}",
+    params(
+        ("graph_name" = String, Path, description = "Graph (or alias) the examples show")
+    ),
+    request_body = Vec<FewShotExample>,
+    responses(
+        (status = 200, description = "The graph's curated examples, then the new stored ones", body = Vec<FewShotExample>),
+        (status = 400, description = "An example has an empty question or query", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks admin scope", body = ErrorResponse),
+        (status = 500, description = "Examples could not be saved", body = ErrorResponse)
+    )
+)]
+#[actix_web::put("/admin/examples/{graph_name}")]
+async fn set_examples_endpoint(
+    api_key: RequestApiKey,
+    graph_name: actix_web::web::Path<String>,
+    examples: actix_web::web::Json<Vec<FewShotExample>>,
+) -> impl Responder {
+    if let Err(response) = require_admin(&api_key) {
+        return response;
+    }
+    let examples = examples.into_inner();
+    if examples
+        .iter()
+        .any(|example| example.question.trim().is_empty() || example.cypher.trim().is_empty())
+    {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Every example needs a question and a cypher query".to_string(),
+        });
+    }
+    let graph_name = resolve_graph_name(&graph_name);
+    let few_shot = &AppConfig::get().few_shot;
+    if let Err(e) = few_shot.set(&graph_name, &examples).await {
+        tracing::error!("Failed to save the examples of graph {}: {}", graph_name, e);
+        return HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Failed to save examples: {e}"),
+        });
+    }
+    tracing::info!("Stored {} examples for graph {}", examples.len(), graph_name);
+    match few_shot.examples(&graph_name).await {
+        Ok(examples) => HttpResponse::Ok().json(examples),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Failed to read examples: {e}"),
+        }),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/examples/{graph_name}",
+    params(
+        ("graph_name" = String, Path, description = "Graph (or alias) whose stored examples are removed")
+    ),
+    responses(
+        (status = 204, description = "Stored examples removed; curated ones are kept"),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks admin scope", body = ErrorResponse),
+        (status = 404, description = "The graph has no stored examples", body = ErrorResponse),
+        (status = 500, description = "Examples could not be removed", body = ErrorResponse)
+    )
+)]
+#[actix_web::delete("/admin/examples/{graph_name}")]
+async fn delete_examples_endpoint(
+    api_key: RequestApiKey,
+    graph_name: actix_web::web::Path<String>,
+) -> impl Responder {
+    if let Err(response) = require_admin(&api_key) {
+        return response;
+    }
+    let graph_name = resolve_graph_name(&graph_name);
+    match AppConfig::get().few_shot.remove(&graph_name).await {
+        Ok(true) => {
+            tracing::info!("Stored examples of graph {} removed", graph_name);
+            HttpResponse::NoContent().finish()
+        }
+        Ok(false) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Graph '{graph_name}' has no stored examples"),
+        }),
+        Err(e) => {
+            tracing::error!("Failed to remove the examples of graph {}: {}", graph_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to remove examples: {e}"),
+            })
+        }
+    }
+}
+
+/// The health of one endpoint of a `FalkorDB` connection.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+struct EndpointHealth {
+    /// The endpoint URL, without credentials.
+    endpoint: String,
+    /// False while the endpoint is passed over after it could not be reached.
+    healthy: bool,
+    /// Seconds until an unhealthy endpoint is tried first again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_in_secs: Option<u64>,
+}
+
+/// The endpoints of one `FalkorDB` connection.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+struct ConnectionHealth {
+    /// `default` for `FALKORDB_CONNECTION`, or the name from `FALKORDB_CONNECTIONS`.
+    name: String,
+    /// In order of preference.
+    endpoints: Vec<EndpointHealth>,
+}
+
+/// The `FalkorDB` endpoints as the connection pool last found them.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+struct HealthResponse {
+    /// `ok`, or `degraded` while any endpoint is unhealthy.
+    status: String,
+    connections: Vec<ConnectionHealth>,
+}
+
+impl ConnectionHealth {
+    fn new(
+        name: &str,
+        falkordb_connection: &str,
+    ) -> Self {
+        let endpoints = connection::endpoints(falkordb_connection)
+            .into_iter()
+            .map(|endpoint| {
+                // The library's pool and this binary's each track the endpoints they used
+                let cooldown =
+                    pool::endpoint_cooldown(endpoint).max(::text_to_cypher::pool::endpoint_cooldown(endpoint));
+                EndpointHealth {
+                    endpoint: connection::redact_endpoint(endpoint),
+                    healthy: cooldown.is_none(),
+                    retry_in_secs: cooldown.map(|cooldown| cooldown.as_secs().max(1)),
+                }
+            })
+            .collect();
+        Self {
+            name: name.to_string(),
+            endpoints,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses(
+        (status = 200, description = "Each FalkorDB connection's endpoints and whether the connection pool passes \
+            them over after a connectivity failure; the endpoints are not probed", body = HealthResponse)
+    )
+)]
+#[actix_web::get("/health")]
+async fn health_endpoint() -> impl Responder {
+    let config = AppConfig::get();
+    let connections: Vec<ConnectionHealth> =
+        std::iter::once(ConnectionHealth::new("default", &config.falkordb_connection))
+            .chain(
+                config
+                    .connections
+                    .names()
+                    .map(|name| ConnectionHealth::new(name, config.connections.resolve(name))),
+            )
+            .collect();
+    let healthy = connections
+        .iter()
+        .all(|connection| connection.endpoints.iter().all(|endpoint| endpoint.healthy));
+    HttpResponse::Ok().json(HealthResponse {
+        status: if healthy { "ok" } else { "degraded" }.to_string(),
+        connections,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Per-graph quota counters in the Prometheus text format: admitted requests, \
+            spent tokens and refusals per limit; with `LLM_CONCURRENCY`, also the model slots in use and the \
+            requests queued per priority", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks admin scope", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/metrics")]
+async fn metrics_endpoint(api_key: RequestApiKey) -> impl Responder {
+    if let Err(response) = require_admin(&api_key) {
+        return response;
+    }
+    let config = AppConfig::get();
+    let mut metrics = config.quotas.render_metrics();
+    if let Some(queue) = &config.llm_queue {
+        metrics.push_str(&queue.render_metrics());
+    }
+    HttpResponse::Ok().content_type("text/plain; version=0.0.4").body(metrics)
+}
+
+#[utoipa::path(
+    get,
+    path = "/requests/{request_id}/debug",
+    params(
+        ("request_id" = String, Path, description = "ID from the request's `RequestId` event")
+    ),
+    responses(
+        (status = 200, description = "Prompts, model outputs, validation reports, executions and timings of the request",
+            body = DebugBundle),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks admin scope", body = ErrorResponse),
+        (status = 404, description = "Debug bundles are disabled, or the request is unknown or expired",
+            body = ErrorResponse),
+        (status = 500, description = "Bundle could not be loaded", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/requests/{request_id}/debug")]
+async fn debug_bundle_endpoint(
+    api_key: RequestApiKey,
+    request_id: actix_web::web::Path<String>,
+) -> impl Responder {
+    if let Err(response) = require_admin(&api_key) {
+        return response;
+    }
+    let config = AppConfig::get();
+    if !config.debug_bundles {
+        return HttpResponse::NotFound().json(ErrorResponse {
+            error: "Debug bundles are disabled; set DEBUG_BUNDLES=true to enable".to_string(),
+        });
+    }
+
+    let request_id = request_id.into_inner();
+    match config.audit.debug_bundle(&request_id).await {
+        Ok(Some(bundle)) => HttpResponse::Ok().json(bundle),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("No debug bundle for request '{request_id}'; it is unknown or has expired"),
+        }),
+        Err(e) => {
+            tracing::error!("Failed to load debug bundle {}: {}", request_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to load debug bundle: {e}"),
+            })
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions",
+    request_body(content = CreateSessionRequest, example = json!(api_examples::create_session_request())),
+    responses(
+        (status = 201, description = "Session created", body = SessionCreatedResponse,
+            example = json!(api_examples::session_created_response())),
+        (status = 500, description = "Session could not be stored", body = ErrorResponse)
+    )
+)]
+#[post("/sessions")]
+async fn create_session_endpoint(req: actix_web::web::Json<CreateSessionRequest>) -> impl Responder {
+    match AppConfig::get().sessions.create(&req.graph_name).await {
+        Ok(session) => HttpResponse::Created().json(SessionCreatedResponse { session_id: session.id }),
+        Err(e) => {
+            tracing::error!("Failed to create session: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to create session: {e}"),
+            })
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/export",
+    params(
+        ("session_id" = String, Path, description = "Session to export")
+    ),
+    responses(
+        (status = 200, description = "Portable session document", body = SessionExport,
+            example = json!(api_examples::session_export())),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+        (status = 500, description = "Session could not be loaded", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/sessions/{session_id}/export")]
+async fn export_session_endpoint(session_id: actix_web::web::Path<String>) -> impl Responder {
+    let session_id = session_id.into_inner();
+    match AppConfig::get().sessions.export(&session_id).await {
+        Ok(Some(export)) => HttpResponse::Ok().json(export),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Session '{session_id}' not found"),
+        }),
+        Err(e) => {
+            tracing::error!("Failed to export session {}: {}", session_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to export session: {e}"),
+            })
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/notebook",
+    params(
+        ("session_id" = String, Path, description = "Session to export"),
+        ("format" = Option<String>, Query, description = "`markdown` (default), with a `cypher` code block per \
+            query, or `jupyter`, a notebook whose Python cells rerun each query through `/graph_query`")
+    ),
+    responses(
+        (status = 200, description = "The session's questions, queries, result previews and answers as a notebook \
+            file", body = String, content_type = "text/markdown"),
+        (status = 400, description = "Unknown format", body = ErrorResponse),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+        (status = 500, description = "Session could not be loaded", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/sessions/{session_id}/notebook")]
+#[allow(clippy::future_not_send)]
+async fn session_notebook_endpoint(
+    req: HttpRequest,
+    session_id: actix_web::web::Path<String>,
+    query: actix_web::web::Query<NotebookQuery>,
+) -> impl Responder {
+    let format = match query.format.as_deref().map(str::parse::<NotebookFormat>).transpose() {
+        Ok(format) => format.unwrap_or_default(),
+        Err(error) => return HttpResponse::BadRequest().json(ErrorResponse { error }),
+    };
+    let session_id = session_id.into_inner();
+    let session = match AppConfig::get().sessions.get(&session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: format!("Session '{session_id}' not found"),
+            });
+        }
+        Err(e) => {
+            tracing::error!("Failed to load session {}: {}", session_id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to load session: {e}"),
+            });
+        }
+    };
+
+    let body = match format {
+        NotebookFormat::Markdown => notebook::to_markdown(&session),
+        NotebookFormat::Jupyter => {
+            let connection = req.connection_info();
+            let base_url = format!("{}://{}", connection.scheme(), connection.host());
+            notebook::to_jupyter(&session, &base_url).to_string()
+        }
+    };
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            actix_web::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"session-{session_id}.{}\"", format.extension()),
+        ))
+        .body(body)
+}
+
+#[derive(Deserialize)]
+struct NotebookQuery {
+    format: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/import",
+    request_body(content = SessionExport, example = json!(api_examples::session_export())),
+    responses(
+        (status = 201, description = "Session imported under a new ID", body = SessionCreatedResponse,
+            example = json!(api_examples::session_created_response())),
+        (status = 400, description = "Unsupported export document", body = ErrorResponse),
+        (status = 500, description = "Session could not be stored", body = ErrorResponse)
+    )
+)]
+#[post("/sessions/import")]
+async fn import_session_endpoint(req: actix_web::web::Json<SessionExport>) -> impl Responder {
+    match AppConfig::get().sessions.import(req.into_inner()).await {
+        Ok(session) => HttpResponse::Created().json(SessionCreatedResponse { session_id: session.id }),
+        Err(e @ storage::StorageError::Serialization(_)) => HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Failed to import session: {e}"),
+        }),
+        Err(e) => {
+            tracing::error!("Failed to import session: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to import session: {e}"),
+            })
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/ask",
+    params(
+        ("session_id" = String, Path, description = "Session to continue")
+    ),
+    request_body(content = AskRequest, example = json!(api_examples::ask_request())),
+    responses(
+        (status = 200, description = "Stream the answer, as for /text_to_cypher", body = String,
+            content_type = "text/event-stream", example = json!(api_examples::text_to_cypher_stream())),
+        (status = 404, description = "Session not found or expired", body = ErrorResponse),
+        (status = 500, description = "Session could not be loaded", body = ErrorResponse)
+    )
+)]
+#[post("/sessions/{session_id}/ask")]
+async fn ask_session_endpoint(
+    state: web::Data<AppState>,
+    api_key: RequestApiKey,
+    session_id: actix_web::web::Path<String>,
+    req: actix_web::web::Json<AskRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let session_id = session_id.into_inner();
+    let session = match AppConfig::get().sessions.get(&session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => {
+            return Ok(Either::Left(HttpResponse::NotFound().json(ErrorResponse {
+                error: format!("Session '{session_id}' not found; it is unknown or has expired"),
+            })));
+        }
+        Err(e) => {
+            tracing::error!("Failed to load session {}: {}", session_id, e);
+            return Ok(Either::Left(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to load session: {e}"),
+            })));
+        }
+    };
+    let previous_query = session.last_query().map(str::to_string);
+    let ask = req.into_inner();
+    let mut messages = session.messages;
+    messages.push(ChatMessage {
+        role: ChatRole::User,
+        content: ask.question,
+    });
+    let request = TextToCypherRequest {
+        graph_name: session.graph_name,
+        graphs: Vec::new(),
+        chat_request: ChatRequest { messages },
+        model: ask.model,
+        key: ask.key,
+        falkordb_connection: None,
+        llm_endpoint: ask.llm_endpoint,
+        cypher_only: ask.cypher_only,
+        dry_run: false,
+        audience: ask.audience,
+        followups: ask.followups,
+        session_id: Some(session.id),
+        strict_validation: false,
+        refine_lint_hints: false,
+        allow_destructive: false,
+        confirm: None,
+        latency_mode: ask.latency_mode,
+        query_vector: None,
+        parameterized: false,
+        discovery: DiscoveryOptions::default(),
+        model_options: ask.model_options,
+        priority: ask.priority,
+        schema: None,
+        clause_policy: None,
+        tenant_filter: None,
+        aggregate_only: false,
+        previous_query,
+        few_shot_prompt: None,
+    };
+    Ok(Either::Right(stream_text_to_cypher(&state, api_key.0, request).await?))
+}
+
+#[allow(clippy::cognitive_complexity)]
+#[utoipa::path(
+    post,
+    path = "/graph_query",
+    request_body(content = GraphQueryRequest, example = json!(api_examples::graph_query_request())),
+    responses(
+        (status = 200, description = "Query executed successfully", body = String, content_type = "application/json",
+            example = json!(api_examples::graph_query_response())),
+        (status = 400, description = "Query execution failed", body = ErrorResponse)
+    )
+)]
+#[post("/graph_query")]
+async fn graph_query_endpoint(
+    state: web::Data<AppState>,
+    api_key: RequestApiKey,
+    req: actix_web::web::Json<GraphQueryRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let raw_request = req.into_inner();
+
+    // Log the incoming Snowflake format request
+    tracing::info!("Received graph_query request with Snowflake format");
+    tracing::info!(
+        "Raw JSON payload: {}",
+        serde_json::to_string_pretty(&raw_request).unwrap_or_else(|_| "Failed to serialize".to_string())
+    );
+
+    // Validate the Snowflake format: data should be an array with at least one entry
+    if raw_request.data.is_empty() {
+        tracing::error!("Empty data array in Snowflake request");
+        return Ok(create_snowflake_error_response("Data array cannot be empty"));
+    }
+
+    // Get the first entry from the data array
+    let first_entry = &raw_request.data[0];
+
+    // Snowflake format: data[0] should be an array where [0] is index and [1] is the actual data
+    let data_array = first_entry.as_array().ok_or_else(|| {
+        tracing::error!("First data entry is not an array");
+        actix_web::error::ErrorBadRequest("First data entry must be an array")
+    })?;
+
+    if data_array.len() < 2 {
+        tracing::error!("Data array must have at least 2 elements [index, data]");
+        return Ok(create_snowflake_error_response(
+            "Data array must have at least 2 elements [index, data]",
+        ));
+    }
+
+    // Extract the actual data object (second element in the array)
+    let data_object = &data_array[1];
+
+    tracing::info!(
+        "Extracted data object: {}",
+        serde_json::to_string_pretty(data_object).unwrap_or_else(|_| "Failed to serialize".to_string())
+    );
+
+    // Extract the required fields from the data object
+    let graph_name = data_object
+        .get("graph_name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| {
             tracing::error!("Missing or invalid 'graph_name' field in data object");
             actix_web::error::ErrorBadRequest("Missing or invalid 'graph_name' field")
         })?
@@ -527,6 +2785,7 @@ async fn graph_query_endpoint(
         .to_string();
 
     tracing::info!("Successfully extracted: graph_name={}, query={}", graph_name, query);
+    let graph_name = resolve_graph_name(&graph_name);
 
     // Validate the extracted data
     if graph_name.is_empty() {
@@ -539,8 +2798,13 @@ async fn graph_query_endpoint(
         return Ok(create_snowflake_error_response("Query cannot be empty"));
     }
 
+    let query = match scoped_graph_query(api_key.0.as_deref(), &graph_name, query) {
+        Ok(query) => query,
+        Err(e) => return Ok(create_snowflake_error_response(&e)),
+    };
+
     // Execute the query
-    match graph_query(&query, &graph_name, false).await {
+    match graph_query(&state, &query, &graph_name, false).await {
         Ok(json_result) => {
             tracing::info!("Successfully executed graph_query for graph: {}", graph_name);
             tracing::debug!("Raw query result: {}", json_result);
@@ -582,20 +2846,51 @@ async fn graph_query_endpoint(
     }
 }
 
+/// Restricts a query to what the caller may read of the graph: on a graph shared between tenants,
+/// the nodes of the tenant of `api_key`, and on an aggregate-only graph, aggregates. Other graphs'
+/// queries are returned as they are.
+fn scoped_graph_query(
+    api_key: Option<&str>,
+    graph_name: &str,
+    query: String,
+) -> Result<String, String> {
+    let config = AppConfig::get();
+    if config.aggregate_only(graph_name) {
+        let errors = CypherValidator::aggregate_only_errors(&query);
+        if !errors.is_empty() {
+            return Err(format!("Aggregate-only mode rejected the query: {}", errors.join("; ")));
+        }
+    }
+    let Some(filter) = config.tenant_policy.filter_for(api_key, graph_name)? else {
+        return Ok(query);
+    };
+    let filtered = filter.apply(&query);
+    let errors = filter.check(&filtered);
+    if errors.is_empty() {
+        Ok(filtered)
+    } else {
+        Err(format!("Tenant filter rejected the query: {}", errors.join("; ")))
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/graph_list",
-    request_body = GraphListRequest,
+    request_body(content = GraphListRequest, example = json!(api_examples::graph_list_request())),
     responses(
-        (status = 200, description = "List of available graphs", body = String, content_type = "application/json"),
+        (status = 200, description = "List of available graphs", body = String, content_type = "application/json",
+            example = json!(api_examples::graph_list_response())),
         (status = 400, description = "Failed to list graphs", body = ErrorResponse)
     )
 )]
 #[post("/graph_list")]
 #[allow(clippy::cognitive_complexity)]
-async fn graph_list_endpoint(_req: actix_web::web::Json<GraphListRequest>) -> Result<impl Responder, actix_web::Error> {
+async fn graph_list_endpoint(
+    state: web::Data<AppState>,
+    _req: actix_web::web::Json<GraphListRequest>,
+) -> Result<impl Responder, actix_web::Error> {
     // Get the list of graphs
-    match get_graphs_list().await {
+    match get_graphs_list(&state).await {
         Ok(graphs) => {
             tracing::info!("Successfully retrieved {} graphs", graphs.len());
             tracing::debug!("Graph list: {:?}", graphs);
@@ -624,16 +2919,22 @@ async fn graph_list_endpoint(_req: actix_web::web::Json<GraphListRequest>) -> Re
 #[utoipa::path(
     post,
     path = "/graph_delete",
-    request_body = GraphDeleteRequest,
+    request_body(content = GraphDeleteRequest, description = "Without 'confirm', issues a confirmation token \
+        valid for 60 seconds; resending with 'confirm' set to it moves the graph to the trash, or deletes it \
+        outright with 'hard': true (admin scope)", example = json!(api_examples::graph_delete_request())),
     responses(
-        (status = 200, description = "Graph deleted successfully", body = String, content_type = "application/json"),
-        (status = 400, description = "Failed to delete graph", body = ErrorResponse)
+        (status = 200, description = "Confirmation token issued, or graph deleted", body = String, content_type = "application/json"),
+        (status = 400, description = "Failed to delete graph", body = ErrorResponse),
+        (status = 401, description = "A hard delete without an API key", body = ErrorResponse),
+        (status = 403, description = "A hard delete without an admin key", body = ErrorResponse)
     )
 )]
 #[post("/graph_delete")]
-#[allow(clippy::cognitive_complexity)]
+#[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
 async fn graph_delete_endpoint(
-    req: actix_web::web::Json<GraphDeleteRequest>
+    state: web::Data<AppState>,
+    api_key: RequestApiKey,
+    req: actix_web::web::Json<GraphDeleteRequest>,
 ) -> Result<impl Responder, actix_web::Error> {
     let raw_request = req.into_inner();
 
@@ -684,122 +2985,611 @@ async fn graph_delete_endpoint(
         })?
         .to_string();
 
-    tracing::info!("Successfully extracted graph_name: {}", graph_name);
+    tracing::info!("Successfully extracted graph_name: {}", graph_name);
+
+    // Validate the extracted data
+    if graph_name.is_empty() {
+        tracing::warn!("Empty graph name provided");
+        return Ok(create_snowflake_error_response("Graph name cannot be empty"));
+    }
+
+    let hard = data_object.get("hard").and_then(serde_json::Value::as_bool).unwrap_or(false);
+    // Deleting outright cannot be undone, so it needs an admin key on both steps.
+    if hard {
+        if let Err(response) = require_admin(&api_key) {
+            return Ok(response);
+        }
+    }
+
+    let config = AppConfig::get();
+    // First step: issue the token the deletion must be confirmed with.
+    let Some(token) = data_object.get("confirm").and_then(|v| v.as_str()) else {
+        return Ok(
+            match config.delete_confirmations.issue(&graph_name, hard, api_key.0.as_deref()).await {
+                Ok(pending) => HttpResponse::Ok().json(serde_json::json!({
+                    "data": [
+                        [0, {
+                            "message": format!(
+                                "Resend with \"confirm\": \"{}\" within {} seconds to delete graph '{}'",
+                                pending.confirmation_token,
+                                graph_trash::DELETE_CONFIRMATION_TTL.as_secs(),
+                                graph_name
+                            ),
+                            "success": false,
+                            "confirmation_required": true,
+                            "confirmation_token": pending.confirmation_token,
+                            "expires_at": pending.expires_at,
+                            "hard": hard,
+                        }]
+                    ]
+                })),
+                Err(e) => {
+                    tracing::error!("Failed to issue a delete confirmation for {}: {}", graph_name, e);
+                    create_snowflake_error_response(&format!("Failed to issue a confirmation token: {e}"))
+                }
+            },
+        );
+    };
+    match config.delete_confirmations.take(token, api_key.0.as_deref()).await {
+        Ok(Some(pending)) if pending.graph_name == graph_name && pending.hard == hard => {}
+        Ok(Some(_)) => {
+            return Ok(create_snowflake_error_response(
+                "Confirmation token was issued for a different deletion",
+            ));
+        }
+        Ok(None) => return Ok(create_snowflake_error_response("Unknown or expired confirmation token")),
+        Err(e) => {
+            return Ok(create_snowflake_error_response(&format!(
+                "Failed to read the confirmation token: {e}"
+            )));
+        }
+    }
+
+    let deleted = if hard {
+        hard_delete_graph(&state, &graph_name).await
+    } else {
+        match state.falkordb().await {
+            Ok((client, target)) => config
+                .graph_trash
+                .soft_delete(&client, &target, &graph_name, config.graph_trash_ttl)
+                .await
+                .map(|trashed| serde_json::json!({ "trash": trashed.name })),
+            Err(e) => Err(e),
+        }
+    };
+
+    let record = AuditRecord::new(
+        api_key.0.as_deref().map(ApiKeys::key_id),
+        "graph_delete",
+        &graph_name,
+        "",
+        if deleted.is_ok() {
+            AuditOutcome::Success
+        } else {
+            AuditOutcome::Failure
+        },
+        Some(match &deleted {
+            Ok(details) => details.to_string(),
+            Err(e) => e.clone(),
+        }),
+    );
+    if let Err(e) = config.audit.record(&record).await {
+        tracing::error!("Failed to write audit record {}: {}", record.id, e);
+    }
+
+    match deleted {
+        Ok(details) => {
+            tracing::info!("Successfully deleted graph: {}", graph_name);
+            // A deleted graph must not come back as a stale-schema fallback.
+            if let Err(e) = config.schema_snapshots.remove(&graph_name).await {
+                tracing::warn!("Failed to remove schema snapshot for {graph_name}: {e}");
+            }
+
+            // Convert the result to Snowflake format: { "data": [ [0, result] ] }
+            let mut result = serde_json::json!({
+                "message": format!("Graph '{}' deleted successfully", graph_name),
+                "success": true,
+            });
+            if let (Some(result), Some(details)) = (result.as_object_mut(), details.as_object()) {
+                result.extend(details.clone());
+            }
+            let snowflake_response = serde_json::json!({ "data": [[0, result]] });
+
+            tracing::info!(
+                "Converted to Snowflake format: {}",
+                serde_json::to_string_pretty(&snowflake_response).unwrap_or_else(|_| "Failed to serialize".to_string())
+            );
+
+            Ok(HttpResponse::Ok().json(snowflake_response))
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete graph {}: {}", graph_name, e);
+            Ok(create_snowflake_error_response(&e))
+        }
+    }
+}
+
+/// Backs `graph_name` up, if configured, then deletes it for good; without the backup the graph
+/// is not deleted. Returns the backup taken, as `{"backup": name}`.
+async fn hard_delete_graph(
+    state: &AppState,
+    graph_name: &str,
+) -> Result<serde_json::Value, String> {
+    let backup = backup_graph(&state.falkordb_connection, graph_name)
+        .await
+        .map_err(|e| format!("Graph '{graph_name}' was not deleted: {e}"))?;
+    let result = delete_graph(state, graph_name)
+        .await
+        .map_err(|e| format!("Failed to delete graph '{graph_name}': {e}"))?;
+    tracing::debug!("Delete result: {}", result);
+    Ok(serde_json::json!({ "backup": backup.map(|backup| backup.name) }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/graph_query_upload/{graph_name}",
+    params(
+        ("graph_name" = String, Path, description = "Name of the graph to execute query on")
+    ),
+    request_body(content = String, description = "Multipart form data with 'file' and 'cypher' fields; an .xlsx \
+        'file' is converted to CSV from its first sheet, or the optional 'sheet' (name or 1-based number) and \
+        'range' (e.g. 'A1:D100')", content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Query executed successfully with uploaded CSV", body = String, content_type = "application/json"),
+        (status = 400, description = "Query execution failed or invalid form data", body = ErrorResponse)
+    )
+)]
+#[post("/graph_query_upload/{graph_name}")]
+#[allow(clippy::future_not_send)]
+async fn graph_query_upload_endpoint(
+    state: web::Data<AppState>,
+    graph_name: actix_web::web::Path<String>,
+    payload: Multipart,
+) -> Result<impl Responder, actix_web::Error> {
+    let graph_name = graph_name.into_inner();
+    let upload = UploadForm::read(payload).await?;
+
+    // Execute the query with uploaded CSV data
+    match graph_query_with_csv(&state, &upload.cypher_query, &graph_name, &upload.csv_content).await {
+        Ok(json_result) => Ok(HttpResponse::Ok().content_type("application/json").body(json_result)),
+        Err(e) => Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e.to_string() })),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/graph_query_upload/{graph_name}/stream",
+    params(
+        ("graph_name" = String, Path, description = "Name of the graph to import into")
+    ),
+    request_body(content = String, description = "Multipart form data with 'file' and 'cypher' fields, and optionally \
+        'batch_rows' (records per batch, default 10000), 'on_error' ('stop', the default, or 'skip' to skip \
+        failing rows and load the rest) and 'job_id' (a failed or interrupted job to resume with the same file and \
+        query, or the ID to give a new job); an .xlsx 'file' is converted to CSV from the optional 'sheet' and \
+        'range'", content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Stream an `ImportBatch` event per batch, then an `ImportSummary` and a `Result`, \
+            or the `Error` that stopped the import", body = String, content_type = "text/event-stream"),
+        (status = 400, description = "Invalid form data", body = ErrorResponse)
+    )
+)]
+#[post("/graph_query_upload/{graph_name}/stream")]
+#[allow(clippy::future_not_send)]
+async fn graph_query_upload_stream_endpoint(
+    state: web::Data<AppState>,
+    graph_name: actix_web::web::Path<String>,
+    payload: Multipart,
+) -> Result<impl Responder, actix_web::Error> {
+    let graph_name = graph_name.into_inner();
+    let upload = UploadForm::read(payload).await?;
+
+    let (tx, rx) = ProgressSender::channel(AppConfig::get().sse_channel_capacity);
+    let state = state.into_inner();
+    tokio::spawn(async move {
+        stream_csv_import(&state, &graph_name, upload, &tx).await;
+        tx.finish(&Uuid::new_v4().to_string()).await;
+    });
+    Ok(progress_stream(rx))
+}
+
+/// The fields of a `/graph_query_upload` form.
+struct UploadForm {
+    csv_content: String,
+    cypher_query: String,
+    /// Records per batch of a streamed import.
+    batch_rows: usize,
+    /// What a streamed import does when a batch fails.
+    on_error: OnBatchError,
+    /// Streamed import job to resume, or the ID to give a new one.
+    job_id: Option<String>,
+}
+
+impl UploadForm {
+    /// Reads the `file` and `cypher` fields, and the optional `batch_rows`, `on_error`, `job_id`,
+    /// `sheet` and `range`.
+    #[allow(clippy::future_not_send)]
+    async fn read(mut payload: Multipart) -> Result<Self, actix_web::Error> {
+        let mut file: Option<(Vec<u8>, String)> = None;
+        let mut cypher_query: Option<String> = None;
+        let mut batch_rows = DEFAULT_BATCH_ROWS;
+        let mut on_error = OnBatchError::default();
+        let mut job_id = None;
+        let mut sheet = SheetFields::default();
+
+        // Process multipart data field by field
+        while let Some(item) = futures_util::stream::StreamExt::next(&mut payload).await {
+            let mut field =
+                item.map_err(|e| actix_web::error::ErrorBadRequest(format!("Failed to read multipart field: {e}")))?;
+
+            // Get the field name, and the file name of the upload
+            let (field_name, filename) = field.content_disposition().map_or((None, None), |cd| {
+                (
+                    cd.get_name().map(ToString::to_string),
+                    cd.get_filename().map(ToString::to_string),
+                )
+            });
+
+            if let Some(field_name) = field_name {
+                // Read the field data into bytes
+                let mut bytes = actix_web::web::BytesMut::new();
+                while let Some(chunk) = futures_util::stream::StreamExt::next(&mut field).await {
+                    let data = chunk
+                        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Failed to read field chunk: {e}")))?;
+                    bytes.extend_from_slice(&data);
+                }
+
+                // The file stays bytes until it is known to be CSV rather than a workbook
+                if field_name == "file" {
+                    file = Some((bytes.to_vec(), filename.unwrap_or_default()));
+                    continue;
+                }
+
+                // Convert to string
+                let content = String::from_utf8(bytes.to_vec()).map_err(|e| {
+                    actix_web::error::ErrorBadRequest(format!("Invalid UTF-8 in field '{field_name}': {e}"))
+                })?;
+
+                // Store the content based on field name
+                match field_name.as_str() {
+                    "cypher" => cypher_query = Some(content),
+                    "sheet" => sheet.sheet = Some(content),
+                    "range" => sheet.range = Some(content),
+                    "batch_rows" => {
+                        batch_rows = content.trim().parse().ok().filter(|rows| *rows > 0).ok_or_else(|| {
+                            actix_web::error::ErrorBadRequest("'batch_rows' must be a positive number")
+                        })?;
+                    }
+                    "on_error" => on_error = content.parse().map_err(actix_web::error::ErrorBadRequest)?,
+                    "job_id" => job_id = Some(content.trim().to_string()).filter(|id| !id.is_empty()),
+                    _ => tracing::warn!("Unexpected field in multipart data: {}", field_name),
+                }
+            }
+        }
+
+        // Validate that we have both required fields
+        let (bytes, filename) =
+            file.ok_or_else(|| actix_web::error::ErrorBadRequest("Missing 'file' field in multipart data"))?;
+        let csv_content = match xlsx_upload(&bytes, &filename, sheet)? {
+            Some((csv, _)) => csv,
+            None => String::from_utf8(bytes)
+                .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid UTF-8 in field 'file': {e}")))?,
+        };
+        Ok(Self {
+            csv_content,
+            cypher_query: cypher_query
+                .ok_or_else(|| actix_web::error::ErrorBadRequest("Missing 'cypher' field in multipart data"))?,
+            batch_rows,
+            on_error,
+            job_id,
+        })
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/import/remote/{graph_name}",
+    params(
+        ("graph_name" = String, Path, description = "Name of the graph to import into")
+    ),
+    request_body(content = RemoteImportRequest, example = json!(api_examples::remote_import_request())),
+    responses(
+        (status = 200, description = "File fetched into IMPORT_FOLDER and its query executed; the file is removed \
+            afterwards", body = RemoteImportResponse),
+        (status = 400, description = "Invalid or disallowed URL, unsupported file, file over the size limit, \
+            checksum mismatch, or query execution failed", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks write scope", body = ErrorResponse),
+        (status = 502, description = "The file could not be fetched", body = ErrorResponse)
+    )
+)]
+#[post("/import/remote/{graph_name}")]
+async fn import_remote_endpoint(
+    state: web::Data<AppState>,
+    api_key: RequestApiKey,
+    graph_name: actix_web::web::Path<String>,
+    req: actix_web::web::Json<RemoteImportRequest>,
+) -> impl Responder {
+    if let Err(e) = authorize(api_key.0.as_deref(), Scope::Write) {
+        let mut response = if matches!(e, AuthError::InsufficientScope { .. }) {
+            HttpResponse::Forbidden()
+        } else {
+            HttpResponse::Unauthorized()
+        };
+        return response.json(ErrorResponse { error: e.to_string() });
+    }
+    let graph_name = resolve_graph_name(&graph_name);
+    match import_remote_file(&state, &graph_name, &req.into_inner()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(RemoteImportError::Fetch(e)) if !e.is_client_error() => {
+            tracing::error!("Failed to fetch remote import file: {}", e);
+            let mut response = if matches!(e, remote_file::RemoteFileError::Io(_)) {
+                HttpResponse::InternalServerError()
+            } else {
+                HttpResponse::BadGateway()
+            };
+            response.json(ErrorResponse { error: e.to_string() })
+        }
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse { error: e.to_string() }),
+    }
+}
+
+/// Why a remote import failed: fetching the file, or running its query.
+#[derive(Debug)]
+enum RemoteImportError {
+    Fetch(remote_file::RemoteFileError),
+    Query(String),
+}
 
-    // Validate the extracted data
-    if graph_name.is_empty() {
-        tracing::warn!("Empty graph name provided");
-        return Ok(create_snowflake_error_response("Graph name cannot be empty"));
+impl std::fmt::Display for RemoteImportError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::Fetch(e) => e.fmt(f),
+            Self::Query(e) => f.write_str(e),
+        }
     }
+}
 
-    // Delete the graph
-    match delete_graph(&graph_name).await {
-        Ok(result) => {
-            tracing::info!("Successfully deleted graph: {}", graph_name);
-            tracing::debug!("Delete result: {}", result);
-
-            // Convert the result to Snowflake format: { "data": [ [0, result] ] }
-            let snowflake_response = serde_json::json!({
-                "data": [
-                    [0, {"message": format!("Graph '{}' deleted successfully", graph_name), "success": true}]
-                ]
-            });
+/// Fetches the request's file into the import folder under a random name, runs its query against
+/// it and removes it again.
+async fn import_remote_file(
+    state: &AppState,
+    graph_name: &str,
+    request: &RemoteImportRequest,
+) -> Result<RemoteImportResponse, RemoteImportError> {
+    let fetcher = AppConfig::get()
+        .remote_fetcher
+        .as_ref()
+        .ok_or_else(|| RemoteImportError::Query("Remote imports are disabled".to_string()))?;
+    let source = RemoteSource::parse(&request.url).map_err(RemoteImportError::Fetch)?;
+    let extension = source.extension().map_err(RemoteImportError::Fetch)?;
 
-            tracing::info!(
-                "Converted to Snowflake format: {}",
-                serde_json::to_string_pretty(&snowflake_response).unwrap_or_else(|_| "Failed to serialize".to_string())
-            );
+    let (client, target) = state
+        .falkordb()
+        .await
+        .map_err(|e| RemoteImportError::Query(format!("Failed to connect to FalkorDB: {e}")))?;
+    let import_folder = get_import_folder(&client)
+        .await
+        .map_err(|e| RemoteImportError::Query(e.to_string()))?;
+
+    let file_name = format!("{}.{extension}", Uuid::new_v4());
+    tracing::info!("Fetching {} into {}/{}", request.url, import_folder, file_name);
+    let download = fetcher
+        .fetch(
+            &source,
+            std::path::Path::new(&import_folder),
+            &file_name,
+            request.max_bytes,
+            request.sha256.as_deref(),
+        )
+        .await
+        .map_err(RemoteImportError::Fetch)?;
+    tracing::info!("Fetched {} bytes, SHA-256 {}", download.bytes, download.sha256);
+
+    let re = Regex::new(&format!(r"file://.*\.{extension}"))
+        .map_err(|e| RemoteImportError::Query(format!("Invalid file pattern: {e}")))?;
+    let query = re.replace_all(&request.cypher_query, format!("file://{file_name}")).to_string();
+    let graph_key = target.graph_key(graph_name);
+    let executed_file = file_name.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        execute_query_with_existing_csv_blocking(&client, &graph_key, &query, &executed_file)
+    })
+    .await;
 
-            Ok(HttpResponse::Ok().json(snowflake_response))
-        }
-        Err(e) => {
-            tracing::error!("Failed to delete graph {}: {}", graph_name, e);
-            Ok(create_snowflake_error_response(&format!(
-                "Failed to delete graph '{graph_name}': {e}"
-            )))
-        }
+    if let Err(e) = std::fs::remove_file(std::path::Path::new(&import_folder).join(&file_name)) {
+        tracing::warn!("Failed to remove {} from import folder: {}", file_name, e);
     }
+
+    let records = result
+        .map_err(|e| RemoteImportError::Query(format!("Failed to execute blocking task: {e}")))?
+        .map_err(|e| RemoteImportError::Query(format!("Query execution failed: {e}")))?;
+    Ok(RemoteImportResponse {
+        bytes: download.bytes,
+        sha256: download.sha256,
+        result: serde_json::from_str(&format_as_json(&records)).unwrap_or(serde_json::Value::Null),
+    })
 }
 
 #[utoipa::path(
-    post,
-    path = "/graph_query_upload/{graph_name}",
+    get,
+    path = "/import/jobs/{job_id}",
     params(
-        ("graph_name" = String, Path, description = "Name of the graph to execute query on")
+        ("job_id" = String, Path, description = "ID from the streamed import's first `Status` event")
     ),
-    request_body(content = String, description = "Multipart form data with 'file' and 'cypher' fields", content_type = "multipart/form-data"),
     responses(
-        (status = 200, description = "Query executed successfully with uploaded CSV", body = String, content_type = "application/json"),
-        (status = 400, description = "Query execution failed or invalid form data", body = ErrorResponse)
+        (status = 200, description = "The streamed import's status, batches done and summary so far", body = ImportJob),
+        (status = 404, description = "Unknown or expired import job", body = ErrorResponse),
+        (status = 500, description = "Import job could not be loaded", body = ErrorResponse)
     )
 )]
-#[post("/graph_query_upload/{graph_name}")]
-#[allow(clippy::future_not_send)]
-async fn graph_query_upload_endpoint(
-    graph_name: actix_web::web::Path<String>,
-    mut payload: Multipart,
-) -> Result<impl Responder, actix_web::Error> {
-    let graph_name = graph_name.into_inner();
+#[actix_web::get("/import/jobs/{job_id}")]
+async fn import_job_endpoint(job_id: actix_web::web::Path<String>) -> impl Responder {
+    let job_id = job_id.into_inner();
+    match AppConfig::get().import_jobs.get(&job_id).await {
+        Ok(Some(job)) => HttpResponse::Ok().json(job),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("No import job '{job_id}'; it is unknown or has expired"),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Failed to load import job: {e}"),
+        }),
+    }
+}
 
-    let mut csv_content: Option<String> = None;
-    let mut cypher_query: Option<String> = None;
+#[utoipa::path(
+    post,
+    path = "/import/inspect",
+    request_body(content = String, description = "Multipart form data with a 'file' field, and optionally 'rows' \
+        (records to read, default 100), 'delimiter' and 'has_header' ('true' or 'false'); the delimiter and header \
+        are detected when not given. An .xlsx 'file' is converted to CSV first, from the optional 'sheet' and \
+        'range'", content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "The file's encoding, delimiter, header and column types and null rates, with \
+            a suggested `LOAD CSV` query", body = CsvInspection,
+            example = json!(api_examples::csv_inspection())),
+        (status = 400, description = "Invalid form data", body = ErrorResponse)
+    )
+)]
+#[post("/import/inspect")]
+#[allow(clippy::future_not_send)]
+async fn import_inspect_endpoint(payload: Multipart) -> Result<impl Responder, actix_web::Error> {
+    let form = InspectForm::read(payload).await?;
+    Ok(HttpResponse::Ok().json(inspect_csv(&form.bytes, &form.filename, &form.options)))
+}
 
-    // Process multipart data field by field
-    while let Some(item) = futures_util::stream::StreamExt::next(&mut payload).await {
-        let mut field =
-            item.map_err(|e| actix_web::error::ErrorBadRequest(format!("Failed to read multipart field: {e}")))?;
+/// The fields of an `/import/inspect` form.
+struct InspectForm {
+    /// The uploaded file, undecoded.
+    bytes: Vec<u8>,
+    filename: String,
+    options: InspectOptions,
+}
 
-        // Get the field name
-        let field_name = field
-            .content_disposition()
-            .and_then(|cd| cd.get_name().map(ToString::to_string));
+impl InspectForm {
+    /// Reads the `file` field, and the optional `rows`, `delimiter`, `has_header`, `sheet` and `range`.
+    #[allow(clippy::future_not_send)]
+    async fn read(mut payload: Multipart) -> Result<Self, actix_web::Error> {
+        let mut file: Option<(Vec<u8>, String)> = None;
+        let mut options = InspectOptions::default();
+        let mut sheet = SheetFields::default();
+
+        while let Some(item) = futures_util::stream::StreamExt::next(&mut payload).await {
+            let mut field =
+                item.map_err(|e| actix_web::error::ErrorBadRequest(format!("Failed to read multipart field: {e}")))?;
+            let (field_name, filename) = field.content_disposition().map_or((None, None), |cd| {
+                (
+                    cd.get_name().map(ToString::to_string),
+                    cd.get_filename().map(ToString::to_string),
+                )
+            });
+            let Some(field_name) = field_name else {
+                continue;
+            };
 
-        if let Some(field_name) = field_name {
-            // Read the field data into bytes
-            let mut bytes = actix_web::web::BytesMut::new();
+            let mut bytes = Vec::new();
             while let Some(chunk) = futures_util::stream::StreamExt::next(&mut field).await {
                 let data =
                     chunk.map_err(|e| actix_web::error::ErrorBadRequest(format!("Failed to read field chunk: {e}")))?;
                 bytes.extend_from_slice(&data);
             }
+            if field_name == "file" {
+                file = Some((bytes, filename.unwrap_or_else(|| "upload.csv".to_string())));
+                continue;
+            }
 
-            // Convert to string
-            let content = String::from_utf8(bytes.to_vec()).map_err(|e| {
+            let content = String::from_utf8(bytes).map_err(|e| {
                 actix_web::error::ErrorBadRequest(format!("Invalid UTF-8 in field '{field_name}': {e}"))
             })?;
-
-            // Store the content based on field name
             match field_name.as_str() {
-                "file" => csv_content = Some(content),
-                "cypher" => cypher_query = Some(content),
+                "sheet" => sheet.sheet = Some(content),
+                "range" => sheet.range = Some(content),
+                "rows" => {
+                    options.rows = content
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|rows| *rows > 0)
+                        .ok_or_else(|| actix_web::error::ErrorBadRequest("'rows' must be a positive number"))?;
+                }
+                "delimiter" => {
+                    let delimiter = match content.as_str() {
+                        "\\t" | "tab" => Some('\t'),
+                        other => {
+                            let mut chars = other.chars();
+                            chars.next().filter(|_| chars.next().is_none())
+                        }
+                    };
+                    options.delimiter =
+                        Some(delimiter.ok_or_else(|| {
+                            actix_web::error::ErrorBadRequest("'delimiter' must be a single character")
+                        })?);
+                }
+                "has_header" => {
+                    options.has_header =
+                        Some(content.trim().parse().map_err(|_| {
+                            actix_web::error::ErrorBadRequest("'has_header' must be 'true' or 'false'")
+                        })?);
+                }
                 _ => tracing::warn!("Unexpected field in multipart data: {}", field_name),
             }
         }
+
+        let (bytes, filename) =
+            file.ok_or_else(|| actix_web::error::ErrorBadRequest("Missing 'file' field in multipart data"))?;
+        let (bytes, filename) = match xlsx_upload(&bytes, &filename, sheet)? {
+            Some((csv, csv_filename)) => (csv.into_bytes(), csv_filename),
+            None => (bytes, filename),
+        };
+        Ok(Self {
+            bytes,
+            filename,
+            options,
+        })
     }
+}
 
-    // Validate that we have both required fields
-    let csv_content =
-        csv_content.ok_or_else(|| actix_web::error::ErrorBadRequest("Missing 'file' field in multipart data"))?;
-    let cypher_query =
-        cypher_query.ok_or_else(|| actix_web::error::ErrorBadRequest("Missing 'cypher' field in multipart data"))?;
+/// The `sheet` and `range` fields of an upload form, picking the cells of an `.xlsx` file.
+#[derive(Default)]
+struct SheetFields {
+    sheet: Option<String>,
+    range: Option<String>,
+}
 
-    // Execute the query with uploaded CSV data
-    match graph_query_with_csv(&cypher_query, &graph_name, &csv_content).await {
-        Ok(json_result) => Ok(HttpResponse::Ok().content_type("application/json").body(json_result)),
-        Err(e) => Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e.to_string() })),
+/// An uploaded `.xlsx` workbook converted to CSV text, with the `.csv` file name it is imported
+/// under; `None` for any other file.
+fn xlsx_upload(
+    bytes: &[u8],
+    filename: &str,
+    sheet: SheetFields,
+) -> Result<Option<(String, String)>, actix_web::Error> {
+    #[cfg(feature = "xlsx")]
+    if xlsx::is_xlsx(filename, bytes) {
+        let selection = xlsx::SheetSelection {
+            sheet: sheet.sheet,
+            range: sheet.range,
+        };
+        let csv = xlsx::xlsx_to_csv(bytes, &selection).map_err(actix_web::error::ErrorBadRequest)?;
+        let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+        return Ok(Some((csv, format!("{stem}.csv"))));
     }
+    #[cfg(not(feature = "xlsx"))]
+    let _ = (bytes, filename, sheet);
+    Ok(None)
 }
 
 #[utoipa::path(
     get,
     path = "/list_graphs",
     responses(
-        (status = 200, description = "List of available graphs", body = Vec<String>)
+        (status = 200, description = "List of available graphs", body = Vec<String>,
+            example = json!(api_examples::graph_names()))
     )
 )]
 #[actix_web::get("/list_graphs")]
-async fn list_graphs_endpoint() -> Result<impl Responder, actix_web::Error> {
-    match get_graphs_list().await {
+async fn list_graphs_endpoint(state: web::Data<AppState>) -> Result<impl Responder, actix_web::Error> {
+    match get_graphs_list(&state).await {
         Ok(graphs) => Ok(HttpResponse::Ok().json(graphs)),
         Err(e) => {
             tracing::error!("Failed to list graphs: {}", e);
@@ -810,6 +3600,19 @@ async fn list_graphs_endpoint() -> Result<impl Responder, actix_web::Error> {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/graph_aliases",
+    responses(
+        (status = 200, description = "Configured graph aliases (GRAPH_ALIASES) with their graphs and descriptions",
+            body = Vec<GraphAlias>)
+    )
+)]
+#[actix_web::get("/graph_aliases")]
+async fn graph_aliases_endpoint() -> impl Responder {
+    HttpResponse::Ok().json(AppConfig::get().graph_aliases.aliases())
+}
+
 #[utoipa::path(
     post,
     path = "/clear_schema_cache/{graph_name}",
@@ -822,12 +3625,181 @@ async fn list_graphs_endpoint() -> Result<impl Responder, actix_web::Error> {
 )]
 #[post("/clear_schema_cache/{graph_name}")]
 async fn clear_schema_cache(graph_name: actix_web::web::Path<String>) -> impl Responder {
-    let graph_name = graph_name.into_inner();
+    let graph_name = resolve_graph_name(&graph_name);
     tracing::info!("Clearing schema cache for graph: {}", graph_name);
     process_clear_schema_cache(&graph_name);
     HttpResponse::new(StatusCode::OK)
 }
 
+#[utoipa::path(
+    get,
+    path = "/schema_diff/{graph_name}",
+    params(
+        ("graph_name" = String, Path, description = "Name of the graph to re-discover"),
+        ("invalidate" = Option<bool>, Query, description = "Replace the cached schema with the re-discovered one \
+            when they differ; default false")
+    ),
+    responses(
+        (status = 200, description = "Labels, relationships and attributes added, removed or retyped since the \
+            cached schema was discovered", body = SchemaDiffResponse),
+        (status = 500, description = "Discovery failed, or the cached schema could not be read", body = ErrorResponse)
+    )
+)]
+#[actix_web::get("/schema_diff/{graph_name}")]
+async fn schema_diff_endpoint(
+    graph_name: actix_web::web::Path<String>,
+    query: actix_web::web::Query<SchemaDiffQuery>,
+) -> impl Responder {
+    let config = AppConfig::get();
+    let graph_name = resolve_graph_name(&graph_name);
+    let error = |error: String| {
+        tracing::error!("Failed to diff the schema of {}: {}", graph_name, error);
+        HttpResponse::InternalServerError().json(ErrorResponse { error })
+    };
+
+    let discovered = discover_graph_schema_with_options(
+        &config.falkordb_connection,
+        &graph_name,
+        DiscoveryOptions::default(),
+        &|_| {},
+    )
+    .await;
+    let fresh: Arc<str> = match discovered {
+        Ok(schema) => config.attribute_units.annotate(&schema).into(),
+        Err(e) => return error(format!("Failed to discover schema: {e}")),
+    };
+    let cached = config.schema_cache.get(&graph_name);
+    let parse = |json: &str| serde_json::from_str::<Schema>(json);
+    let (old, new) = match (cached.as_deref().map(parse).transpose(), parse(&fresh)) {
+        (Ok(old), Ok(new)) => (old.unwrap_or_default(), new),
+        (Err(e), _) | (_, Err(e)) => return error(format!("Failed to read schema: {e}")),
+    };
+
+    let diff = SchemaDiff::between(&old, &new);
+    let cache_updated = query.invalidate && cached.is_some() && !diff.is_empty();
+    if cache_updated {
+        if let Err(e) = config.schema_snapshots.save(&graph_name, &fresh).await {
+            tracing::warn!("Failed to save schema snapshot for {graph_name}: {e}");
+        }
+        config.schema_cache.insert(graph_name.clone(), fresh);
+        tracing::info!("Replaced the changed cached schema of {graph_name}");
+    }
+    HttpResponse::Ok().json(SchemaDiffResponse {
+        cached: cached.is_some(),
+        graph_name,
+        diff,
+        cache_updated,
+    })
+}
+
+#[derive(Deserialize)]
+struct SchemaDiffQuery {
+    #[serde(default)]
+    invalidate: bool,
+}
+
+/// A graph's schema changes since its cached schema was discovered.
+#[derive(Serialize, Deserialize, ToSchema)]
+struct SchemaDiffResponse {
+    graph_name: String,
+    /// Whether a cached schema was compared; without one, every part of the schema is listed as added.
+    cached: bool,
+    diff: SchemaDiff,
+    /// Whether the cached schema was replaced with the re-discovered one.
+    cache_updated: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/enrich_schema/{graph_name}",
+    params(
+        ("graph_name" = String, Path, description = "Name of the graph whose schema to describe")
+    ),
+    request_body(content = EnrichSchemaRequest),
+    responses(
+        (status = 200, description = "The cached schema with model-written descriptions of its labels, \
+            relationships and properties", body = EnrichSchemaResponse),
+        (status = 400, description = "No model given and DEFAULT_MODEL is not set", body = ErrorResponse),
+        (status = 500, description = "Discovery failed, or the model wrote no descriptions", body = ErrorResponse)
+    )
+)]
+#[post("/enrich_schema/{graph_name}")]
+async fn enrich_schema_endpoint(
+    state: web::Data<AppState>,
+    graph_name: actix_web::web::Path<String>,
+    req: Option<actix_web::web::Json<EnrichSchemaRequest>>,
+) -> impl Responder {
+    let config = AppConfig::get();
+    let graph_name = resolve_graph_name(&graph_name);
+    let request = req.map(actix_web::web::Json::into_inner).unwrap_or_default();
+    let Some(model) = request.model.or_else(|| config.default_model.clone()) else {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "No model given and DEFAULT_MODEL is not set".to_string(),
+        });
+    };
+    let error = |error: String| {
+        tracing::error!("Failed to enrich the schema of {}: {}", graph_name, error);
+        HttpResponse::InternalServerError().json(ErrorResponse { error })
+    };
+
+    let schema =
+        match get_graph_schema_string(&config.falkordb_connection, &graph_name, DiscoveryOptions::default()).await {
+            Ok(json) => match serde_json::from_str::<Schema>(&json) {
+                Ok(schema) => schema,
+                Err(e) => return error(format!("Failed to read schema: {e}")),
+            },
+            Err(e) => return error(format!("Failed to get schema: {e}")),
+        };
+    let client = state.genai_client(request.key.as_deref(), request.llm_endpoint.as_deref());
+    let mut token_usage = TokenUsage::new();
+    let (schema, described) = match enrich_schema(&schema, &client, &model, &mut token_usage).await {
+        Ok(enriched) => enriched,
+        Err(e) => return error(e.to_string()),
+    };
+
+    // Questions asked from now on see the descriptions, until the schema is discovered again.
+    if described > 0 {
+        match serde_json::to_string(&schema) {
+            Ok(json) => {
+                if let Err(e) = config.schema_snapshots.save(&graph_name, &json).await {
+                    tracing::warn!("Failed to save schema snapshot for {graph_name}: {e}");
+                }
+                config.schema_cache.insert(graph_name.clone(), json.into());
+            }
+            Err(e) => return error(format!("Failed to serialize schema: {e}")),
+        }
+    }
+    tracing::info!("Described {} parts of the schema of {}", described, graph_name);
+    HttpResponse::Ok().json(EnrichSchemaResponse {
+        graph_name,
+        described,
+        schema,
+        token_usage,
+    })
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Default)]
+struct EnrichSchemaRequest {
+    /// Model writing the descriptions; defaults to `DEFAULT_MODEL`.
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    key: Option<String>,
+    /// Optional LLM provider endpoint/base URL override.
+    #[serde(default, alias = "endpoint", alias = "base_url", alias = "baseUrl")]
+    llm_endpoint: Option<String>,
+}
+
+/// A graph's schema with descriptions written by the model.
+#[derive(Serialize, Deserialize, ToSchema)]
+struct EnrichSchemaResponse {
+    graph_name: String,
+    /// Descriptions added; those already in the schema are kept.
+    described: usize,
+    schema: Schema,
+    token_usage: TokenUsage,
+}
+
 #[utoipa::path(
     post,
     path = "/clear_udf_cache",
@@ -844,7 +3816,7 @@ async fn clear_udf_cache() -> impl Responder {
 #[utoipa::path(
     post,
     path = "/load_csv",
-    request_body = LoadCsvRequest,
+    request_body(content = LoadCsvRequest, example = json!(api_examples::load_csv_request())),
     responses(
         (status = 200, description = "CSV file loaded and query executed successfully", body = String, content_type = "application/json"),
         (status = 400, description = "Invalid request format, CSV file not found, or query execution failed", body = ErrorResponse)
@@ -853,7 +3825,10 @@ async fn clear_udf_cache() -> impl Responder {
 #[allow(clippy::too_many_lines)]
 #[allow(clippy::cognitive_complexity)]
 #[post("/load_csv")]
-async fn load_csv_endpoint(req: actix_web::web::Json<LoadCsvRequest>) -> Result<impl Responder, actix_web::Error> {
+async fn load_csv_endpoint(
+    state: web::Data<AppState>,
+    req: actix_web::web::Json<LoadCsvRequest>,
+) -> Result<impl Responder, actix_web::Error> {
     let raw_request = req.into_inner();
 
     // Log the incoming Snowflake format request
@@ -864,26 +3839,26 @@ async fn load_csv_endpoint(req: actix_web::web::Json<LoadCsvRequest>) -> Result<
     );
 
     // List all files in IMPORT_FOLDER at the start
-    if let Ok(connection_info) = AppConfig::get().falkordb_connection.as_str().try_into() {
-        if let Ok(client) = build_falkordb_async_client(connection_info).await {
-            match list_import_folder_files(&client).await {
-                Ok(files) => {
-                    tracing::info!("Files currently in IMPORT_FOLDER: {:?}", files);
-                    if files.is_empty() {
-                        tracing::info!("IMPORT_FOLDER is empty");
-                    } else {
-                        tracing::info!("Total files in IMPORT_FOLDER: {}", files.len());
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to list IMPORT_FOLDER files: {}", e);
+    match state.falkordb().await {
+        Ok((client, _)) => match list_import_folder_files(&client).await {
+            Ok(files) => {
+                tracing::info!("Files currently in IMPORT_FOLDER: {:?}", files);
+                if files.is_empty() {
+                    tracing::info!("IMPORT_FOLDER is empty");
+                } else {
+                    tracing::info!("Total files in IMPORT_FOLDER: {}", files.len());
                 }
             }
-        } else {
-            tracing::warn!("Failed to create FalkorDB client for listing IMPORT_FOLDER files");
+            Err(e) => {
+                tracing::warn!("Failed to list IMPORT_FOLDER files: {}", e);
+            }
+        },
+        Err(e) => {
+            tracing::warn!(
+                "Failed to create FalkorDB client for listing IMPORT_FOLDER files: {}",
+                e
+            );
         }
-    } else {
-        tracing::warn!("Invalid FalkorDB connection string for listing IMPORT_FOLDER files");
     }
 
     // Validate the Snowflake format: data should be an array with at least one entry
@@ -977,7 +3952,7 @@ async fn load_csv_endpoint(req: actix_web::web::Json<LoadCsvRequest>) -> Result<
     }
 
     // Execute the query with the existing CSV file using the new logic
-    match graph_query_with_existing_csv(&cypher_query, &graph_name, &csv_file).await {
+    match graph_query_with_existing_csv(&state, &cypher_query, &graph_name, &csv_file).await {
         Ok(json_result) => {
             tracing::info!("Successfully executed load_csv for graph: {}", graph_name);
             tracing::debug!("Raw query result: {}", json_result);
@@ -1022,7 +3997,7 @@ async fn load_csv_endpoint(req: actix_web::web::Json<LoadCsvRequest>) -> Result<
 #[utoipa::path(
     post,
     path = "/echo",
-    request_body = EchoRequest,
+    request_body(content = EchoRequest, example = json!(api_examples::echo_request())),
     responses(
         (status = 200, description = "Echo back the received JSON", content_type = "application/json")
     )
@@ -1039,286 +4014,1079 @@ async fn echo_endpoint(req: actix_web::web::Json<serde_json::Value>) -> Result<i
     // Simply return the received JSON back
     let response = HttpResponse::Ok().json(&*req);
 
-    tracing::info!("Echo endpoint responding with same payload");
-    Ok(response)
+    tracing::info!("Echo endpoint responding with same payload");
+    Ok(response)
+}
+
+#[utoipa::path(
+    post,
+    path = "/text_to_cypher",
+    request_body(content = TextToCypherRequest, example = json!(api_examples::text_to_cypher_request())),
+    responses(
+        (status = 200, description = "Stream text to Cypher conversion progress", body = String,
+            content_type = "text/event-stream", example = json!(api_examples::text_to_cypher_stream()))
+    )
+)]
+#[post("/text_to_cypher")]
+async fn text_to_cypher(
+    state: web::Data<AppState>,
+    api_key: RequestApiKey,
+    req: actix_web::web::Json<TextToCypherRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    stream_text_to_cypher(&state, api_key.0, req.into_inner()).await
+}
+
+/// Answers a text-to-cypher request as a progress stream, recording the turn in its session.
+#[allow(clippy::too_many_lines)]
+async fn stream_text_to_cypher(
+    state: &AppState,
+    api_key: Option<String>,
+    mut request: TextToCypherRequest,
+) -> Result<impl Responder + use<>, actix_web::Error> {
+    let config = AppConfig::get();
+    request.graph_name = resolve_graph_name(&request.graph_name);
+    request.graphs = request.graphs.iter().map(|graph| resolve_graph_name(graph)).collect();
+    request.falkordb_connection = request.falkordb_connection.as_deref().map(resolve_connection);
+    request.clause_policy = config.clause_policies.policy_for(api_key.as_deref(), &request.graph_name);
+    request.aggregate_only = config.aggregate_only(&request.graph_name);
+    request.tenant_filter = match config.tenant_policy.filter_for(api_key.as_deref(), &request.graph_name) {
+        Ok(filter) => filter,
+        Err(e) => {
+            let (tx, rx) = ProgressSender::channel(1);
+            spawn_sse_error(tx, e);
+            return Ok(progress_stream(rx));
+        }
+    };
+
+    // Mutations need a write-scoped key; the key's ID (never the key) is recorded in the audit trail.
+    let actor = if request.allow_destructive || request.confirm.is_some() {
+        match authorize(api_key.as_deref(), Scope::Write) {
+            Ok(()) => api_key.as_deref().map(ApiKeys::key_id),
+            Err(e) => {
+                let (tx, rx) = ProgressSender::channel(1);
+                spawn_sse_error(tx, format!("Destructive queries not allowed: {e}"));
+                return Ok(progress_stream(rx));
+            }
+        }
+    } else {
+        None
+    };
+
+    // A confirmation executes a staged query as-is, so it needs no model.
+    if let Some(token) = request.confirm.clone() {
+        let (tx, rx) = ProgressSender::channel(config.sse_channel_capacity);
+        tokio::spawn(async move {
+            execute_confirmed_dry_run(&request, &token, actor, &tx).await;
+            tx.finish(&Uuid::new_v4().to_string()).await;
+        });
+        return Ok(progress_stream(rx));
+    }
+
+    // Apply defaults from .env file if values are not provided
+    if request.model.is_none() && request.latency_mode == LatencyMode::Fast {
+        request.model = config.fast_model.clone().map(ModelChain::new);
+    }
+    if request.model.is_none() {
+        request.model = config.default_model.clone().map(ModelChain::new);
+    }
+
+    if request.key.is_none() {
+        request.key.clone_from(&config.default_key);
+    }
+
+    let (tx, rx) = ProgressSender::channel(config.sse_channel_capacity);
+
+    // Ensure we have a model after applying defaults
+    let Some(models) = request.model.clone() else {
+        drop(config.usage_stats.start_request("none"));
+        // Send error via SSE instead of returning HTTP error
+        spawn_sse_error(
+            tx,
+            "Model must be provided either in request or as DEFAULT_MODEL in .env file".to_string(),
+        );
+        return Ok(progress_stream(rx));
+    };
+    let model = models.primary();
+
+    let client = state.genai_client(request.key.as_deref(), request.llm_endpoint.as_deref());
+
+    if let Some(session_id) = &request.session_id {
+        let error = match config.sessions.get(session_id).await {
+            Ok(Some(_)) => None,
+            Ok(None) => Some(format!("Session '{session_id}' not found")),
+            Err(e) => Some(format!("Failed to load session: {e}")),
+        };
+        if let Some(error) = error {
+            drop(config.usage_stats.start_request(model));
+            spawn_sse_error(tx, error);
+            return Ok(progress_stream(rx));
+        }
+    }
+
+    let priority = config.request_priority(api_key.as_deref(), request.priority);
+    tokio::spawn(async move {
+        let started = std::time::Instant::now();
+        // Held until the request is answered; routing, generation and the answer all call the model.
+        let _slot = acquire_model_slot(priority, &tx).await;
+        let session_id = request.session_id.clone();
+        let messages = request.chat_request.messages.clone();
+        let mut turn = SessionTurn::new(request.chat_request.last_user_question().unwrap_or_default());
+        turn.model = request.model.as_ref().map(|models| models.primary().to_string());
+
+        // A request that names no graph is routed to the one its question is about first.
+        let mut token_usage = TokenUsage::new();
+        let routed = if request.graph_name.is_empty() {
+            route_request(&request, &client, &tx, &mut token_usage).await
+        } else {
+            Ok(request.graph_name.clone())
+        };
+
+        // A routed request gets the tenant filter of the graph it was routed to
+        let routed = routed.and_then(|graph_name| {
+            if graph_name != request.graph_name {
+                request.tenant_filter = AppConfig::get().tenant_policy.filter_for(api_key.as_deref(), &graph_name)?;
+            }
+            Ok(graph_name)
+        });
+
+        let request_id = Uuid::new_v4().to_string();
+        let mut graph_name = request.graph_name.clone();
+        match routed {
+            Ok(routed) => {
+                if routed != request.graph_name {
+                    request.clause_policy = AppConfig::get().clause_policies.policy_for(api_key.as_deref(), &routed);
+                    request.aggregate_only = AppConfig::get().aggregate_only(&routed);
+                    request.graph_name.clone_from(&routed);
+                    graph_name = routed;
+                }
+                if let Err(e) = engine::admit(&GraphQuotaAdmission, &graph_name, &token_usage).await {
+                    turn.token_usage = Some(token_usage.clone());
+                    turn.error = Some(e.clone());
+                    drop(
+                        AppConfig::get()
+                            .usage_stats
+                            .start_request(request.model.as_ref().map_or("", ModelChain::primary)),
+                    );
+                    if tx.send(Progress::Usage(token_usage)).await.is_ok() {
+                        let _ = tx.send(Progress::Error(e)).await;
+                    }
+                } else {
+                    let config = AppConfig::get();
+                    if config.few_shot_top_k > 0
+                        && let Some(question) = request.chat_request.last_user_question()
+                    {
+                        request.few_shot_prompt =
+                            config.few_shot.prompt_for(&graph_name, question, config.few_shot_top_k).await;
+                    }
+                    if config.debug_bundles {
+                        process_with_debug_bundle(request, client, tx.clone(), &request_id, &mut turn, token_usage)
+                            .await;
+                    } else {
+                        process_text_to_cypher_request(request, client, tx.clone(), &mut turn, token_usage).await;
+                    }
+                    let tokens = turn.token_usage.as_ref().map_or(0, |usage| usage.total_tokens);
+                    GraphQuotaAdmission.record_tokens(&graph_name, tokens);
+                }
+            }
+            Err(message) => {
+                turn.token_usage = Some(token_usage.clone());
+                turn.error = Some(message.clone());
+                drop(
+                    AppConfig::get()
+                        .usage_stats
+                        .start_request(request.model.as_ref().map_or("", ModelChain::primary)),
+                );
+                if tx.send(Progress::Usage(token_usage)).await.is_ok() {
+                    let _ = tx.send(Progress::Error(message)).await;
+                }
+            }
+        }
+
+        record_request_usage(api_key.as_deref(), &graph_name, &turn, started.elapsed()).await;
+        // Recorded before `Done`, so a client may send the next turn as soon as the stream ends.
+        if let Some(session_id) = session_id {
+            record_session_turn(&session_id, messages, turn).await;
+        }
+        tx.finish(&request_id).await;
+    });
+
+    Ok(progress_stream(rx))
+}
+
+/// Admits requests against the graph quotas of `AppConfig::quotas`.
+#[derive(Debug)]
+struct GraphQuotaAdmission;
+
+#[async_trait::async_trait]
+impl Admission for GraphQuotaAdmission {
+    async fn refusal(
+        &self,
+        graph_name: &str,
+    ) -> Option<String> {
+        AppConfig::get().quotas.check(graph_name).await.err().map(|e| e.to_string())
+    }
+
+    async fn admit(
+        &self,
+        graph_name: &str,
+    ) -> Result<(), String> {
+        AppConfig::get().quotas.admit(graph_name).await.map_err(|e| e.to_string())
+    }
+
+    fn record_tokens(
+        &self,
+        graph_name: &str,
+        tokens: u64,
+    ) {
+        AppConfig::get().quotas.record_tokens(graph_name, tokens);
+    }
 }
 
-#[utoipa::path(
-    post,
-    path = "/text_to_cypher",
-    request_body = TextToCypherRequest,
-    responses(
-        (status = 200, description = "Stream text to Cypher conversion progress", content_type = "text/event-stream")
+/// Waits for a model slot when `LLM_CONCURRENCY` is set, telling the client when it has to queue.
+async fn acquire_model_slot(
+    priority: Priority,
+    tx: &ProgressSender,
+) -> Option<Permit> {
+    let queue = AppConfig::get().llm_queue.as_ref()?;
+    if let Some(permit) = queue.try_acquire() {
+        return Some(permit);
+    }
+    let _ = tx
+        .send(Progress::Status(format!(
+            "Waiting for a model slot ({} priority)",
+            priority.as_str()
+        )))
+        .await;
+    Some(queue.acquire(priority).await)
+}
+
+/// Writes the audit record of a served request, for `GET /admin/usage`. Failures are logged only.
+async fn record_request_usage(
+    api_key: Option<&str>,
+    graph_name: &str,
+    turn: &SessionTurn,
+    latency: std::time::Duration,
+) {
+    let config = AppConfig::get();
+    let mut token_usage = turn.token_usage.clone().unwrap_or_default();
+    token_usage.estimate_cost(&config.model_prices);
+    let outcome = if turn.error.is_some() {
+        AuditOutcome::Failure
+    } else {
+        AuditOutcome::Success
+    };
+    let record = AuditRecord::new(
+        api_key.map(ApiKeys::key_id),
+        TEXT_TO_CYPHER_ACTION,
+        graph_name,
+        turn.cypher_query.as_deref().unwrap_or_default(),
+        outcome,
+        turn.error.clone(),
     )
-)]
-#[post("/text_to_cypher")]
-async fn text_to_cypher(req: actix_web::web::Json<TextToCypherRequest>) -> Result<impl Responder, actix_web::Error> {
-    let mut request = req.into_inner();
+    .with_usage(
+        turn.model.clone(),
+        RequestUsage {
+            latency_ms: u64::try_from(latency.as_millis()).unwrap_or(u64::MAX),
+            prompt_tokens: token_usage.prompt_tokens,
+            completion_tokens: token_usage.completion_tokens,
+            estimated_cost_usd: token_usage.estimated_cost_usd,
+            heal_attempts: turn.heal_attempts,
+        },
+    );
+    if let Err(e) = config.audit.record_request(&record, config.request_retention).await {
+        tracing::error!("Failed to write audit record {}: {}", record.id, e);
+    }
+}
+
+/// Chooses the graph a request without a `graph_name` is about, among its `graphs` or every graph on
+/// its connection but backups and trashed graphs, and announces it with a `Graph` event. The model
+/// sees each graph's alias description and cached schema, when there are.
+async fn route_request(
+    request: &TextToCypherRequest,
+    client: &genai::Client,
+    tx: &ProgressSender,
+    token_usage: &mut TokenUsage,
+) -> Result<String, String> {
     let config = AppConfig::get();
+    tx.status("Choosing the graph the question is about...")
+        .await
+        .map_err(|e| e.to_string())?;
+    let names = if request.graphs.is_empty() {
+        let falkordb_connection = request
+            .falkordb_connection
+            .clone()
+            .unwrap_or_else(|| config.falkordb_connection.clone());
+        list_graphs(&falkordb_connection)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|name| GraphBackup::parse(name).is_none() && TrashedGraph::parse(name).is_none())
+            .collect()
+    } else {
+        request.graphs.clone()
+    };
+    let candidates: Vec<GraphCandidate> = names
+        .into_iter()
+        .map(|name| {
+            let description = config
+                .graph_aliases
+                .alias_for(&name)
+                .and_then(|alias| alias.description.clone());
+            let schema = config.schema_cache.get(&name).map(|schema| schema.to_string());
+            GraphCandidate::new(name).with_description(description).with_schema(schema)
+        })
+        .collect();
+    // Graphs over their quota are not offered, so a request none can take never calls the model
+    let candidates = engine::admissible_candidates(&GraphQuotaAdmission, candidates).await?;
+    let graph_name = route_question(
+        request.chat_request.last_user_question().unwrap_or_default(),
+        &candidates,
+        client,
+        request.model.as_ref().map_or("", ModelChain::primary),
+        token_usage,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    tracing::info!("Routed the question to graph: {}", graph_name);
+    tx.send(Progress::Graph(graph_name.clone())).await.map_err(|e| e.to_string())?;
+    Ok(graph_name)
+}
 
-    // Apply defaults from .env file if values are not provided
-    if request.model.is_none() {
-        request.model.clone_from(&config.default_model);
+/// Runs a request inside a debug trace, announcing its ID with a `RequestId` event, and stores the
+/// resulting bundle for `GET /requests/{id}/debug`.
+async fn process_with_debug_bundle(
+    request: TextToCypherRequest,
+    client: genai::Client,
+    tx: ProgressSender,
+    request_id: &str,
+    turn: &mut SessionTurn,
+    token_usage: TokenUsage,
+) {
+    let graph_name = request.graph_name.clone();
+    let started = std::time::Instant::now();
+    if tx.send(Progress::RequestId(request_id.to_string())).await.is_err() {
+        return;
     }
 
-    if request.key.is_none() {
-        request.key.clone_from(&config.default_key);
+    let ((), trace) = debug_bundle::traced(Box::pin(process_text_to_cypher_request(
+        request,
+        client,
+        tx,
+        turn,
+        token_usage,
+    )))
+    .await;
+    let bundle = trace.into_bundle(request_id, &graph_name, turn, started.elapsed());
+    if let Err(e) = AppConfig::get().audit.record_debug_bundle(&bundle).await {
+        tracing::error!("Failed to store debug bundle {}: {}", request_id, e);
     }
+}
 
-    let (tx, rx) = mpsc::channel(100);
+/// Sends a single error event, then `Done`, from a background task, for requests rejected before
+/// processing.
+fn spawn_sse_error(
+    tx: ProgressSender,
+    message: String,
+) {
+    tokio::spawn(async move {
+        let _ = tx.send(Progress::Error(message)).await;
+        tx.finish(&Uuid::new_v4().to_string()).await;
+    });
+}
 
-    // Ensure we have a model after applying defaults
-    if request.model.is_none() {
-        // Send error via SSE instead of returning HTTP error
-        tokio::spawn(async move {
-            let error_event = sse::Event::Data(sse::Data::new(
-                serde_json::to_string(&Progress::Error(
-                    "Model must be provided either in request or as DEFAULT_MODEL in .env file".to_string(),
-                ))
-                .unwrap_or_else(|_| r#"{"Error":"Serialization failed"}"#.to_string()),
-            ));
-            let _ = tx.send(error_event).await;
-        });
-        let stream = tokio_stream::wrappers::ReceiverStream::new(rx).map(Ok::<_, actix_web::Error>);
-        return Ok(Sse::from_stream(stream));
+/// The API key sent as `X-API-Key` or `Authorization: Bearer <key>`, if any.
+struct RequestApiKey(Option<String>);
+
+impl actix_web::FromRequest for RequestApiKey {
+    type Error = actix_web::Error;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(
+        req: &HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let headers = req.headers();
+        let key = headers
+            .get("x-api-key")
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| {
+                headers
+                    .get(actix_web::http::header::AUTHORIZATION)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.strip_prefix("Bearer "))
+            })
+            .map(|key| key.trim().to_string());
+        std::future::ready(Ok(Self(key)))
     }
+}
 
-    let model = request.model.as_ref().unwrap(); // Safe to unwrap after the check above
-
-    let client = create_genai_client_with_endpoint(request.key.as_deref(), request.llm_endpoint.as_deref());
+/// Checks `key` against the configured `API_KEYS`.
+fn authorize(
+    key: Option<&str>,
+    required: Scope,
+) -> Result<(), AuthError> {
+    AppConfig::get().api_keys.authorize(key, required).map(|_| ())
+}
 
-    // Handle service target resolution errors via SSE
-    let service_target = match client.resolve_service_target(model).await {
-        Ok(target) => target,
+/// Executes a staged destructive query after its dry run was confirmed, and audits the outcome.
+async fn execute_confirmed_dry_run(
+    request: &TextToCypherRequest,
+    token: &str,
+    actor: Option<String>,
+    tx: &ProgressSender,
+) {
+    let config = AppConfig::get();
+    let dry_run = match config.dry_runs.take(token).await {
+        Ok(Some(dry_run)) if dry_run.graph_name == request.graph_name => dry_run,
+        Ok(Some(_)) => {
+            if tx
+                .send(Progress::Error(
+                    "Confirmation token was issued for a different graph".to_string(),
+                ))
+                .await
+                .is_err()
+            {
+                return;
+            }
+            return;
+        }
+        Ok(None) => {
+            if tx
+                .send(Progress::Error("Unknown or expired confirmation token".to_string()))
+                .await
+                .is_err()
+            {
+                return;
+            }
+            return;
+        }
         Err(e) => {
-            // Send error via SSE instead of returning HTTP error
-            tokio::spawn(async move {
-                let error_event = sse::Event::Data(sse::Data::new(
-                    serde_json::to_string(&Progress::Error(format!("Failed to resolve service target: {e}")))
-                        .unwrap_or_else(|_| r#"{"Error":"Serialization failed"}"#.to_string()),
-                ));
-                let _ = tx.send(error_event).await;
-            });
-            let stream = tokio_stream::wrappers::ReceiverStream::new(rx).map(Ok::<_, actix_web::Error>);
-            return Ok(Sse::from_stream(stream));
+            if tx.send(Progress::Error(format!("Failed to load dry run: {e}"))).await.is_err() {
+                return;
+            }
+            return;
         }
     };
 
-    tokio::spawn(async move {
-        process_text_to_cypher_request(request, client, service_target, tx).await;
-    });
+    let falkordb_connection = request
+        .falkordb_connection
+        .clone()
+        .unwrap_or_else(|| config.falkordb_connection.clone());
+
+    if tx.send(Progress::CypherQuery(dry_run.query.clone())).await.is_err() {
+        return;
+    }
+    match backup_graph(&falkordb_connection, &dry_run.graph_name).await {
+        Ok(Some(backup)) => {
+            if tx
+                .send(Progress::Status(format!(
+                    "Backed up graph to '{}' before executing",
+                    backup.name
+                )))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            let _ = tx.send(Progress::Error(format!("Query not executed: {e}"))).await;
+            return;
+        }
+    }
+    if tx.status("Executing confirmed destructive query...").await.is_err() {
+        return;
+    }
+    let outcome = execute_query(&dry_run.query, &dry_run.graph_name, &falkordb_connection, false).await;
+
+    let record = match &outcome {
+        Ok(result) => AuditRecord::new(
+            actor,
+            "destructive_query",
+            &dry_run.graph_name,
+            &dry_run.query,
+            AuditOutcome::Success,
+            Some(result.clone()),
+        ),
+        Err(e) => AuditRecord::new(
+            actor,
+            "destructive_query",
+            &dry_run.graph_name,
+            &dry_run.query,
+            AuditOutcome::Failure,
+            Some(e.to_string()),
+        ),
+    };
+    if let Err(e) = config.audit.record(&record).await {
+        tracing::error!("Failed to write audit record {}: {}", record.id, e);
+    }
 
-    let stream = tokio_stream::wrappers::ReceiverStream::new(rx).map(Ok::<_, actix_web::Error>);
+    match outcome {
+        Ok(result) => {
+            if tx.send(Progress::CypherResult(result.clone())).await.is_ok() {
+                let _ = tx.send(Progress::Result(result)).await;
+            }
+        }
+        Err(e) => {
+            let _ = tx.send(Progress::Error(format!("Query execution failed: {e}"))).await;
+        }
+    }
+}
 
-    Ok(Sse::from_stream(stream))
+/// Stages a destructive query for confirmation instead of executing it, and streams the preview.
+/// Returns whether the preview was staged; a failure has been reported.
+async fn stage_dry_run(
+    query: &str,
+    graph_name: &str,
+    falkordb_connection: &str,
+    tx: &ProgressSender,
+) -> Result<bool, StreamClosed> {
+    tx.status("Destructive query generated; preparing dry run...").await?;
+    let plan = match explain_cypher_query(query, graph_name, falkordb_connection).await {
+        Ok(plan) => plan,
+        Err(e) => {
+            tx.send(Progress::Error(format!("Failed to plan destructive query: {e}")))
+                .await?;
+            return Ok(false);
+        }
+    };
+    let dry_run = match AppConfig::get().dry_runs.stage(graph_name, query, plan).await {
+        Ok(dry_run) => dry_run,
+        Err(e) => {
+            tx.send(Progress::Error(format!("Failed to stage dry run: {e}"))).await?;
+            return Ok(false);
+        }
+    };
+    let message = format!(
+        "Dry run only: the query was not executed. Resend with \"confirm\": \"{}\" to execute it.",
+        dry_run.confirmation_token
+    );
+    tx.send(Progress::DryRun(dry_run)).await?;
+    tx.send(Progress::Result(message)).await?;
+    Ok(true)
 }
 
-#[allow(clippy::cognitive_complexity)]
+#[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
 async fn process_text_to_cypher_request(
     request: TextToCypherRequest,
     client: genai::Client,
-    service_target: genai::ServiceTarget,
-    tx: mpsc::Sender<sse::Event>,
+    tx: ProgressSender,
+    turn: &mut SessionTurn,
+    mut token_usage: TokenUsage,
 ) {
     tracing::info!("Processing text to Cypher request: {request:?}");
 
-    let model = request
-        .model
-        .as_ref()
-        .expect("Model should be available after applying defaults");
+    // The handler only spawns this task once a model is resolved
+    let Some(mut models) = request.model.clone() else {
+        if tx
+            .send(Progress::Error("No model available for the request".to_string()))
+            .await
+            .is_err()
+        {
+            return;
+        }
+        return;
+    };
+
+    // Counted as a failure on any early return below unless marked successful.
+    let mut stats_timer = AppConfig::get().usage_stats.start_request(models.primary());
 
     let falkordb_connection = request
-        .clone()
         .falkordb_connection
+        .clone()
         .unwrap_or_else(|| AppConfig::get().falkordb_connection.clone());
 
-    // Resolve instance UDF context once per request (cached, opt-in). Reused for self-healing.
-    let udfs = resolve_udf_context(&falkordb_connection).await;
+    // Step 1: Resolve the service target, the instance UDF context (cached, opt-in; reused for
+    // self-healing) and the schema. They are independent, so they run concurrently.
+    let started = std::time::Instant::now();
+    let (service_target, udfs, schema) = tokio::join!(
+        client.resolve_service_target(models.primary()),
+        resolve_udf_context(&falkordb_connection),
+        lookup_schema(&request, &falkordb_connection, &tx),
+    );
+    tracing::info!("Request setup took {} ms", started.elapsed().as_millis());
+    debug_bundle::record(|trace| trace.record_timing("setup", started.elapsed()));
+
+    let service_target = match service_target {
+        Ok(target) => target,
+        Err(e) => {
+            let message = format!("Failed to resolve service target: {e}");
+            turn.error = Some(message.clone());
+            if tx.send(Progress::Error(message)).await.is_err() {
+                return;
+            }
+            return;
+        }
+    };
 
-    // Step 1: Send processing status
+    // Step 2: Send processing status
     send_processing_status(&request, &service_target, &tx).await;
 
-    // Step 2: Discover schema
-    let Some(schema) = get_or_discover_schema(&falkordb_connection, &request.graph_name, &tx).await else {
-        send!(tx, Progress::Error("Failed to discover schema".to_string()));
+    let schema = match schema {
+        Ok(schema) => schema,
+        Err(message) => {
+            turn.error = Some(message.clone());
+            if tx.send(Progress::Error(message)).await.is_err() {
+                return;
+            }
+            return;
+        }
+    };
+    let schema = if request.latency_mode.compact_schema() {
+        compact_schema(&schema).into()
+    } else {
+        schema
+    };
+    let Some(schema) = prune_schema(&request, schema, &client, &tx).await else {
         return;
     };
 
-    // Track token usage across every LLM call made for this request.
-    let mut token_usage = TokenUsage::new();
-
     // Step 3: Generate and execute cypher query with self-healing retry
-    let Some(initial_query) =
-        generate_cypher_query(&request, &schema, &udfs, &client, model, &tx, &mut token_usage).await
-    else {
+    let started = std::time::Instant::now();
+    let generation = generation(&request, &schema);
+    let initial_query = generate_cypher_query(
+        &request,
+        &generation,
+        &schema,
+        &udfs,
+        &client,
+        &mut models,
+        &tx,
+        &mut token_usage,
+    )
+    .await;
+    debug_bundle::record(|trace| trace.record_timing("cypher_generation", started.elapsed()));
+    // The model that wrote the query serves the rest of the request
+    let model = models.primary();
+    turn.model = Some(model.to_string());
+    let Some(initial_query) = initial_query else {
+        turn.token_usage = Some(token_usage.clone());
+        turn.error = Some("Failed to generate a valid Cypher query".to_string());
         return;
     };
-    let mut executed_query = initial_query.clone();
+    // Queries breaking the graph's policies never run, even without strict validation
+    if let Some(message) = engine::policy_violation(&initial_query, &generation.validation) {
+        turn.token_usage = Some(token_usage.clone());
+        turn.error = Some(message.clone());
+        if tx.send(Progress::Usage(token_usage)).await.is_ok() {
+            let _ = tx.send(Progress::Error(message)).await;
+        }
+        return;
+    }
+    spawn_shadow(&request, &schema, &udfs, &initial_query);
+    let executed_query = initial_query;
+    turn.cypher_query = Some(executed_query.clone());
 
     // If cypher_only is true, stop here and return just the validated query
     if request.cypher_only {
         tracing::info!("cypher_only mode: returning query without execution");
-        send!(tx, Progress::Usage(token_usage));
-        send!(tx, Progress::Result(executed_query));
+        turn.token_usage = Some(token_usage.clone());
+        if tx.send(Progress::Usage(token_usage)).await.is_err() {
+            return;
+        }
+        if tx.send(Progress::Result(executed_query)).await.is_err() {
+            return;
+        }
+        stats_timer.succeed();
         return;
     }
 
-    // Step 4: Execute the query and get results, with self-healing on failure
-    let query_result = if let Ok(result) =
-        execute_cypher_query(&executed_query, &request.graph_name, falkordb_connection.as_str(), &tx).await
-    {
-        result
-    } else {
-        // Try self-healing: regenerate query with error feedback
-        tracing::info!("First query execution failed, attempting self-healing...");
-        send!(
-            tx,
-            Progress::Status(String::from("Query failed, attempting self-healing..."))
-        );
-
-        // Use a generic error message since we don't capture specific errors
-        let error_msg = "Query execution failed - see logs for details";
-
-        // Attempt to get a fixed query with error context
-        if let Some(fixed_query) = attempt_query_self_healing(
-            &request,
-            &schema,
-            &executed_query,
-            error_msg,
-            &client,
-            model,
-            &udfs,
-            &tx,
-            &mut token_usage,
+    // A dry run plans the query instead of executing it
+    if request.dry_run {
+        turn.token_usage = Some(token_usage.clone());
+        if tx.send(Progress::Usage(token_usage)).await.is_err() {
+            return;
+        }
+        match explain_cypher_query(
+            &request.executable(&executed_query),
+            &request.graph_name,
+            &falkordb_connection,
         )
         .await
         {
-            // Try executing the fixed query
-            if let Ok(result) =
-                execute_cypher_query(&fixed_query, &request.graph_name, falkordb_connection.as_str(), &tx).await
-            {
-                tracing::info!("Self-healed query executed successfully");
-                send!(tx, Progress::Status(String::from("Self-healing successful")));
-                executed_query = fixed_query;
-                result
-            } else {
-                tracing::error!("Self-healing failed");
-                send!(tx, Progress::Usage(token_usage));
-                send!(
-                    tx,
-                    Progress::Error("Query execution failed even after self-healing attempt".to_string())
-                );
-                return;
+            Ok(plan) => {
+                if tx.send(Progress::QueryPlan(plan)).await.is_ok()
+                    && tx.send(Progress::Result(executed_query)).await.is_ok()
+                {
+                    stats_timer.succeed();
+                }
+            }
+            Err(e) => {
+                let message = format!("Query planning failed: {e}");
+                turn.error = Some(message.clone());
+                let _ = tx.send(Progress::Error(message)).await;
             }
+        }
+        return;
+    }
+
+    // Destructive queries are previewed, never executed directly; see `execute_confirmed_dry_run`.
+    if request.allow_destructive && CypherValidator::query_mode(&executed_query) == QueryMode::Write {
+        turn.token_usage = Some(token_usage.clone());
+        if tx.send(Progress::Usage(token_usage)).await.is_err() {
+            return;
+        }
+        if stage_dry_run(&executed_query, &request.graph_name, &falkordb_connection, &tx).await == Ok(true) {
+            stats_timer.succeed();
         } else {
-            tracing::error!("Self-healing failed: no valid query was generated");
-            send!(tx, Progress::Usage(token_usage));
-            send!(
-                tx,
-                Progress::Error("Self-healing failed: no valid query was generated".to_string())
-            );
+            turn.error = Some("Failed to stage dry run".to_string());
+        }
+        return;
+    }
+
+    // Queries over the complexity budget are rejected before they run
+    match cost_budget_rejection(&request, &executed_query, &tx).await {
+        Ok(None) => {}
+        Ok(Some(message)) => {
+            turn.token_usage = Some(token_usage.clone());
+            turn.error = Some(message.clone());
+            if tx.send(Progress::Usage(token_usage)).await.is_ok() {
+                let _ = tx.send(Progress::Error(message)).await;
+            }
+            return;
+        }
+        Err(StreamClosed) => return,
+    }
+
+    // Step 4: Execute the query and get results. A failed query is regenerated with the database's
+    // error, up to `MAX_HEAL_ATTEMPTS` times; fast mode does not retry.
+    let max_heal_attempts = if request.latency_mode.retries() {
+        AppConfig::get().max_heal_attempts
+    } else {
+        0
+    };
+    let mut runner = ServerRunner {
+        writer: ServerWriter {
+            request: &request,
+            schema: &schema,
+            client: &client,
+            udfs: &udfs,
+            falkordb_connection: &falkordb_connection,
+            tx: &tx,
+            token_usage: &mut token_usage,
+        },
+        generation: &generation,
+        model,
+    };
+    let executed = execute_with_healing(&mut runner, &tx, executed_query, max_heal_attempts).await;
+    let Executed {
+        query: executed_query,
+        result: query_result,
+        heal_attempts,
+    } = match executed {
+        Ok(executed) => executed,
+        Err(StepError::Failed(message)) => {
+            turn.heal_attempts = max_heal_attempts;
+            turn.token_usage = Some(token_usage.clone());
+            turn.error = Some(message.clone());
+            if tx.send(Progress::Usage(token_usage)).await.is_ok() {
+                let _ = tx.send(Progress::Error(message)).await;
+            }
+            return;
+        }
+        Err(_) => {
+            turn.token_usage = Some(token_usage.clone());
+            turn.error = Some("Self-healing failed: no valid query was generated".to_string());
             return;
         }
     };
 
+    turn.heal_attempts = heal_attempts;
+    turn.cypher_query = Some(executed_query.clone());
+    turn.set_result(&query_result);
+
     // Step 5: Generate final answer using AI
-    generate_final_answer(
+    let started = std::time::Instant::now();
+    let answer = generate_final_answer(
         &request,
+        &schema,
         &executed_query,
         &query_result,
         &client,
         model,
         &tx,
         &mut token_usage,
+        turn,
     )
     .await;
+    debug_bundle::record(|trace| trace.record_timing("answer", started.elapsed()));
+    turn.token_usage = Some(token_usage.clone());
+    if answer.is_empty() {
+        turn.error = Some("Failed to generate an answer".to_string());
+    } else {
+        stats_timer.succeed();
+        turn.answer = Some(answer);
+    }
 }
 
-/// Validates a query and returns it if valid, None otherwise
-#[allow(clippy::cognitive_complexity)]
-async fn validate_and_log_query(
-    query: &str,
-    tx: &mpsc::Sender<sse::Event>,
-) -> Option<String> {
-    let validation_result = CypherValidator::validate(query);
+/// Appends a finished turn (and the assistant's answer) to a session. Failures are logged only: the
+/// response has already been streamed to the client.
+async fn record_session_turn(
+    session_id: &str,
+    mut messages: Vec<ChatMessage>,
+    turn: SessionTurn,
+) {
+    if let Some(answer) = &turn.answer {
+        messages.push(ChatMessage {
+            role: ChatRole::Assistant,
+            content: answer.clone(),
+        });
+    }
+    match AppConfig::get().sessions.record_turn(session_id, messages, turn).await {
+        Ok(true) => {}
+        Ok(false) => tracing::warn!("Session {session_id} disappeared before the turn could be recorded"),
+        Err(e) => tracing::error!("Failed to record turn in session {session_id}: {e}"),
+    }
+}
 
-    if !validation_result.is_valid {
-        tracing::warn!("Query failed validation: {:?}", validation_result.errors);
-        send_option!(
-            tx,
-            Progress::Error(format!(
-                "Query validation errors: {}",
-                validation_result.errors.join("; ")
-            ))
+/// Shadows a sampled share of requests: generates the query again with the shadow model/prompt off
+/// the request path and writes the comparison to the audit log. Nothing reaches the client.
+fn spawn_shadow(
+    request: &TextToCypherRequest,
+    schema: &Arc<str>,
+    udfs: &str,
+    production_query: &str,
+) {
+    let Some(traffic) = AppConfig::get().shadow.clone() else {
+        return;
+    };
+    if !traffic.shadow.sample() {
+        return;
+    }
+    let chat_request = query_chat_request(request);
+    let graph_name = request.graph_name.clone();
+    let (schema, udfs, production_query) = (Arc::clone(schema), udfs.to_string(), production_query.to_string());
+    tokio::spawn(async move {
+        let config = AppConfig::get();
+        let comparison = traffic
+            .shadow
+            .compare(
+                &traffic.client,
+                &chat_request,
+                &schema,
+                config.skill_catalog.as_ref(),
+                &udfs,
+                &production_query,
+            )
+            .await;
+        let outcome = if comparison.shadow_error.is_some() {
+            AuditOutcome::Failure
+        } else {
+            AuditOutcome::Success
+        };
+        let record = AuditRecord::new(
+            None,
+            "shadow_comparison",
+            &graph_name,
+            &production_query,
+            outcome,
+            serde_json::to_string(&comparison).ok(),
         );
-        return None;
+        if let Err(e) = config.audit.record(&record).await {
+            tracing::warn!("Failed to write shadow comparison {}: {}", record.id, e);
+        }
+    });
+}
+
+/// The chat history used for query generation. The graph's closest examples, system messages for
+/// the request's modes (lifting the read-only constraint, aggregates only, parameters) and a
+/// session's previous query precede the latest question.
+fn query_chat_request(request: &TextToCypherRequest) -> ChatRequest {
+    let modes = QueryModes {
+        write_prompt: request
+            .allow_destructive
+            .then(|| TemplateEngine::destructive_mode_prompt().to_string()),
+        aggregate_only: request.aggregate_only,
+        parameterized: request.parameterized,
+        previous_query: request.previous_query.as_deref(),
+        examples: request.few_shot_prompt.clone(),
+    };
+    engine::query_chat_request(&request.chat_request, &modes)
+}
+
+/// What a request's queries are generated from and checked against: the conversation from
+/// [`query_chat_request`], the checks from [`validation_options`] and the `RESPONSE_HOOKS`.
+fn generation(
+    request: &TextToCypherRequest,
+    schema: &str,
+) -> Generation<'static> {
+    Generation {
+        chat_request: query_chat_request(request),
+        validation: validation_options(request, schema),
+        response_hooks: &AppConfig::get().response_hooks,
+        latency_mode: request.latency_mode,
+        refine_lint_hints: request.refine_lint_hints,
+    }
+}
+
+/// Builds the validation options for a request: the checks of [`engine::validation_options`], the
+/// clause policy bound to the caller, the tenant filter and aggregate-only mode of its graph, and
+/// the server's `MAX_VAR_LENGTH`, validation policy, procedure allowlist and rules. The policy only
+/// bounds what `allow_destructive` requests may write: other requests get none of its writes, as
+/// they never run read-write.
+fn validation_options(
+    request: &TextToCypherRequest,
+    schema: &str,
+) -> ValidationOptions {
+    let config = AppConfig::get();
+    let policy = config.validation_policy.clone().map(|mut policy| {
+        policy.allow_create &= request.allow_destructive;
+        policy.allow_delete &= request.allow_destructive;
+        policy
+    });
+    engine::validation_options(&request.chat_request, schema, request.strict_validation)
+        .with_allow_destructive(request.allow_destructive)
+        .with_max_var_length(config.max_var_length)
+        .with_clause_policy(request.clause_policy.clone())
+        .with_procedure_allowlist(config.procedure_allowlist.clone())
+        .with_policy(policy)
+        .with_rules(config.validation_rules.as_ref().map(|file| file.rules()).unwrap_or_default())
+        .with_tenant_filter(request.tenant_filter.clone())
+        .with_aggregate_only(request.aggregate_only)
+}
+
+/// Writes the queries of one request with the server's skills and the instance's UDF context,
+/// recording each model call and validation in the debug trace.
+struct ServerWriter<'a> {
+    request: &'a TextToCypherRequest,
+    schema: &'a str,
+    client: &'a genai::Client,
+    udfs: &'a str,
+    falkordb_connection: &'a str,
+    tx: &'a ProgressSender,
+    token_usage: &'a mut TokenUsage,
+}
+
+#[async_trait::async_trait]
+impl QueryWriter for ServerWriter<'_> {
+    type Error = String;
+
+    async fn write(
+        &mut self,
+        chat_request: &ChatRequest,
+        model: &str,
+    ) -> Result<GenerationOutcome, String> {
+        execute_chat_with_skills(
+            self.client,
+            model,
+            &self.request.model_options,
+            chat_request,
+            self.schema,
+            AppConfig::get().skill_catalog.as_ref(),
+            self.udfs,
+            self.tx,
+            self.token_usage,
+        )
+        .await
     }
 
-    // Log any warnings even if query is valid
-    if !validation_result.warnings.is_empty() {
-        tracing::info!("Query validation warnings: {:?}", validation_result.warnings);
+    async fn explains(
+        &mut self,
+        query: &str,
+    ) -> bool {
+        explain_cypher_query(
+            &self.request.executable(query),
+            &self.request.graph_name,
+            self.falkordb_connection,
+        )
+        .await
+        .inspect_err(|e| tracing::info!("Candidate rejected by EXPLAIN: {e}"))
+        .is_ok()
     }
 
-    Some(query.to_string())
+    fn validated(
+        &mut self,
+        query: &str,
+        result: &ValidationResult,
+    ) {
+        debug_bundle::record(|trace| {
+            trace.record_validation(ValidationReport {
+                query: query.to_string(),
+                is_valid: result.is_valid,
+                errors: result.errors.clone(),
+                warnings: result.warnings.clone(),
+                hints: result.hints.iter().map(|hint| hint.message.clone()).collect(),
+            });
+        });
+    }
 }
 
-/// Attempts to self-heal a failed query by regenerating with error context
-#[allow(clippy::cognitive_complexity)]
-#[allow(clippy::too_many_arguments)]
-async fn attempt_query_self_healing(
-    request: &TextToCypherRequest,
-    schema: &str,
-    failed_query: &str,
-    error_message: &str,
-    client: &genai::Client,
-    model: &str,
-    udfs: &str,
-    tx: &mpsc::Sender<sse::Event>,
-    token_usage: &mut TokenUsage,
-) -> Option<String> {
-    tracing::info!("Attempting to self-heal failed query: {}", failed_query);
-
-    // Create a feedback message with specific error context
-    let mut retry_request = request.chat_request.clone();
-    retry_request.messages.push(ChatMessage {
-        role: ChatRole::Assistant,
-        content: failed_query.to_string(),
-    });
-    retry_request.messages.push(ChatMessage {
-        role: ChatRole::User,
-        content: format!(
-            "The previous query failed with error: {error_message}. Please generate a corrected Cypher query that fixes this error and follows the schema more closely."
-        ),
-    });
-
-    // Generate new query using the same skill-loading path as the initial request.
-    let skill_catalog = AppConfig::get().skill_catalog.as_ref();
-    let retry_query = execute_chat_with_skills(
-        client,
-        model,
-        &retry_request,
-        schema,
-        skill_catalog,
-        udfs,
-        tx,
-        token_usage,
-    )
-    .await;
+/// Executes and heals the queries of one request for [`execute_with_healing`], streaming each step.
+struct ServerRunner<'a> {
+    writer: ServerWriter<'a>,
+    generation: &'a Generation<'a>,
+    model: &'a str,
+}
 
-    if retry_query.trim().is_empty() || retry_query.trim() == "NO ANSWER" {
-        tracing::warn!("Self-healing failed: no valid query generated");
-        return None;
+#[async_trait::async_trait]
+impl QueryRunner for ServerRunner<'_> {
+    async fn execute(
+        &mut self,
+        query: &str,
+    ) -> Result<String, StepError> {
+        let writer = &self.writer;
+        execute_cypher_query(
+            &writer.request.executable(query),
+            &writer.request.graph_name,
+            writer.falkordb_connection,
+            writer.tx,
+        )
+        .await
     }
 
-    let clean_query = clean_generated_cypher_response(&retry_query);
+    /// Regenerates the query as [`engine::heal_query`] does, then checks its cost against
+    /// `QUERY_COST_BUDGET`.
+    async fn heal(
+        &mut self,
+        failed_query: &str,
+        error: &str,
+    ) -> Result<String, StepError> {
+        let tx = self.writer.tx;
+        let query = engine::heal_query(
+            &mut self.writer,
+            tx,
+            self.generation,
+            self.model,
+            failed_query,
+            error,
+            &mut Vec::new(),
+        )
+        .await?;
+        match cost_budget_rejection(self.writer.request, &query, tx).await? {
+            Some(message) => Err(StepError::Rejected { query, message }),
+            None => Ok(query),
+        }
+    }
+}
 
-    // Validate the regenerated query using shared validation logic
-    if let Some(validated) = validate_and_log_query(&clean_query, tx).await {
-        send_option!(tx, Progress::CypherQuery(format!("Fixed: {validated}")));
-        Some(validated)
-    } else {
-        None
+/// Estimates the cost of a query about to run against `QUERY_COST_BUDGET`, from its
+/// `GRAPH.EXPLAIN` plan when the graph can plan it and from the query alone otherwise, and streams
+/// the estimate as a status. A query over budget is reported as an `expensive_query` warning, or,
+/// when the budget rejects, the error to end the request with is returned.
+async fn cost_budget_rejection(
+    request: &TextToCypherRequest,
+    query: &str,
+    tx: &ProgressSender,
+) -> Result<Option<String>, StreamClosed> {
+    let config = AppConfig::get();
+    let Some(budget) = config.cost_budget else {
+        return Ok(None);
+    };
+    let falkordb_connection = request.falkordb_connection.as_deref().unwrap_or(&config.falkordb_connection);
+    let plan = explain_cypher_query(&request.executable(query), &request.graph_name, falkordb_connection)
+        .await
+        .ok();
+    let estimate = estimate_cost(query, plan.as_deref());
+    if !budget.allows(&estimate) && budget.reject {
+        return Ok(Some(budget.exceeded(&estimate)));
+    }
+    tx.send(Progress::Status(budget.status(&estimate))).await?;
+    if !budget.allows(&estimate) {
+        let warning = Warning::new(WarningCode::ExpensiveQuery, budget.exceeded(&estimate));
+        tx.send(Progress::Warning(warning)).await?;
     }
+    Ok(None)
 }
 
 /// Resolve the rendered UDF context block for the server, honoring `DISCOVER_UDFS` and the
@@ -1360,105 +5128,188 @@ async fn resolve_udf_context(falkordb_connection: &str) -> String {
     }
 }
 
+/// Returns the cached schema, or discovers it. When discovery fails and `allow_stale` is set
+/// (`cypher_only` requests, which never touch the database otherwise), falls back to the last
+/// discovered schema with a staleness warning; only a graph that was never discovered fails.
+///
+/// Discovery with other than the default `discovery` options neither uses nor updates the cache
+/// and the snapshots.
 async fn get_or_discover_schema(
     falkordb_connection: &str,
     graph_name: &str,
-    tx: &mpsc::Sender<sse::Event>,
-) -> Option<String> {
-    let cache = AppConfig::get().schema_cache.clone();
-    let schema = match cache.get(graph_name) {
-        Some(schema) => schema,
-        None => match discover_and_send_schema(falkordb_connection, graph_name, tx).await {
-            Ok(schema) => schema,
-            Err(()) => return None,
-        },
+    discovery: DiscoveryOptions,
+    allow_stale: bool,
+    tx: &ProgressSender,
+) -> Option<Arc<str>> {
+    let config = AppConfig::get();
+    let cacheable = discovery == DiscoveryOptions::default();
+    if let Some(schema) = config.schema_cache.get(graph_name).filter(|_| cacheable) {
+        tx.send(Progress::Schema(schema.to_string())).await.ok()?;
+        return Some(schema);
+    }
+    let schema: Arc<str> = match discover_and_send_schema(falkordb_connection, graph_name, discovery, tx).await {
+        Ok(schema) if !cacheable => schema.into(),
+        Ok(schema) => {
+            if let Err(e) = config.schema_snapshots.save(graph_name, &schema).await {
+                tracing::warn!("Failed to save schema snapshot for {graph_name}: {e}");
+            }
+            schema.into()
+        }
+        Err(error) => {
+            let snapshot = if allow_stale {
+                config.schema_snapshots.load(graph_name).await.unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load schema snapshot for {graph_name}: {e}");
+                    None
+                })
+            } else {
+                None
+            };
+            let Some(snapshot) = snapshot else {
+                tx.send(Progress::Error(error)).await.ok()?;
+                return None;
+            };
+            tracing::warn!("Schema discovery failed for {graph_name}; using the last known schema");
+            tx.send(Progress::Warning(Warning::new(
+                WarningCode::StaleSchema,
+                format!(
+                    "Schema discovery failed ({error}); using the schema discovered {} ago, which may be stale",
+                    format_age(snapshot.age_secs())
+                ),
+            )))
+            .await
+            .ok()?;
+            // Not cached, so the next request retries discovery.
+            tx.send(Progress::Schema(snapshot.schema.clone())).await.ok()?;
+            return Some(snapshot.schema.into());
+        }
     };
-    send_option!(tx, Progress::Schema(schema.clone()));
-    cache.insert(graph_name.to_string(), schema.clone());
-    Some(schema.clone())
+    tx.send(Progress::Schema(schema.to_string())).await.ok()?;
+    if cacheable {
+        config.schema_cache.insert(graph_name.to_string(), schema.clone());
+    }
+    Some(schema)
 }
 
-#[allow(clippy::cognitive_complexity)]
+/// Narrows a large schema to the part closest to the request's question when `SCHEMA_PRUNING` is
+/// on ([`engine::prune_schema`]). A failed pruning sends the whole schema. `None` when the client
+/// disconnected.
+async fn prune_schema(
+    request: &TextToCypherRequest,
+    schema: Arc<str>,
+    client: &genai::Client,
+    tx: &ProgressSender,
+) -> Option<Arc<str>> {
+    let Some(pruner) = AppConfig::get().schema_pruner.as_ref() else {
+        return Some(schema);
+    };
+    let started = std::time::Instant::now();
+    let question = request.chat_request.last_user_question();
+    let narrowed = engine::prune_schema(Some(pruner), client, &schema, question, tx).await.ok()?;
+    debug_bundle::record(|trace| trace.record_timing("schema_pruning", started.elapsed()));
+    Some(narrowed.map_or(schema, Into::into))
+}
+
+/// Returns the request's schema once the graph is known to exist (a cached schema implies it does).
+/// The error message has not been sent yet.
+///
+/// A session's questions keep using the schema its earlier questions used, while the session is active.
+async fn lookup_schema(
+    request: &TextToCypherRequest,
+    falkordb_connection: &str,
+    tx: &ProgressSender,
+) -> Result<Arc<str>, String> {
+    let config = AppConfig::get();
+    if let Some(schema) = &request.schema {
+        let schema: Arc<str> = config.attribute_units.annotate(&provided_schema(schema)).into();
+        tx.send(Progress::Schema(schema.to_string()))
+            .await
+            .map_err(|_| "Client disconnected".to_string())?;
+        return Ok(schema);
+    }
+    if let Some(schema) = request.session_id.as_deref().and_then(|id| config.sessions.last_schema(id)) {
+        tx.send(Progress::Schema(schema.to_string()))
+            .await
+            .map_err(|_| "Client disconnected".to_string())?;
+        return Ok(schema);
+    }
+    if !config.schema_cache.contains_key(&request.graph_name)
+        && let Some(message) = missing_graph_message(falkordb_connection, &request.graph_name).await
+    {
+        return Err(message);
+    }
+    let schema = get_or_discover_schema(
+        falkordb_connection,
+        &request.graph_name,
+        request.discovery,
+        request.cypher_only,
+        tx,
+    )
+    .await
+    .ok_or_else(|| "Failed to discover schema".to_string())?;
+    if let Some(id) = &request.session_id {
+        config.sessions.set_last_schema(id, schema.clone());
+    }
+    Ok(schema)
+}
+
+/// Formats an age in seconds as a rough human duration ("42 seconds", "3 hours").
+fn format_age(secs: u64) -> String {
+    let (value, unit) = match secs {
+        0..60 => (secs, "second"),
+        60..3_600 => (secs / 60, "minute"),
+        3_600..86_400 => (secs / 3_600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    format!("{value} {unit}{}", if value == 1 { "" } else { "s" })
+}
+
+/// Generates, checks and reports the request's query with [`engine::generate_query`]. A model whose
+/// chat requests fail is dropped from `models` for the next one, which then serves the rest of the
+/// request. `None` once the request has ended.
+#[allow(clippy::too_many_arguments)]
 async fn generate_cypher_query(
     request: &TextToCypherRequest,
+    generation: &Generation<'_>,
     schema: &str,
     udfs: &str,
     client: &genai::Client,
-    model: &str,
-    tx: &mpsc::Sender<sse::Event>,
+    models: &mut ModelChain,
+    tx: &ProgressSender,
     token_usage: &mut TokenUsage,
 ) -> Option<String> {
-    let skill_catalog = AppConfig::get().skill_catalog.as_ref();
-
-    send_option!(
-        tx,
-        Progress::Status(String::from("Generating Cypher query using schema ..."))
-    );
-
-    let query = execute_chat_with_skills(
-        client,
-        model,
-        &request.chat_request,
+    let config = AppConfig::get();
+    let mut writer = ServerWriter {
+        request,
         schema,
-        skill_catalog,
+        client,
         udfs,
+        falkordb_connection: request.falkordb_connection.as_deref().unwrap_or(&config.falkordb_connection),
         tx,
         token_usage,
-    )
-    .await;
-
-    if query.trim().is_empty() || query.trim() == "NO ANSWER" {
-        tracing::warn!("No query generated from AI model");
-        send_option!(tx, Progress::Usage(*token_usage));
-        send_option!(tx, Progress::Error("No valid query was generated".to_string()));
-        return None;
-    }
-
-    let clean_query = clean_generated_cypher_response(&query);
-
-    // Validate the generated query using shared validation logic
-    if validate_and_log_query(&clean_query, tx).await.is_none() {
-        send_option!(
-            tx,
-            Progress::Status(String::from("Query validation failed, attempting to regenerate..."))
-        );
-
-        // Try to regenerate with error feedback
-        let validation_result = CypherValidator::validate(&clean_query);
-        let error_feedback = validation_result.errors.join("; ");
-        let retry_request = append_validation_feedback(&request.chat_request, &clean_query, &error_feedback);
-        let retry_query = execute_chat_with_skills(
-            client,
-            model,
-            &retry_request,
-            schema,
-            skill_catalog,
-            udfs,
-            tx,
-            token_usage,
-        )
-        .await;
-
-        if !retry_query.trim().is_empty() && retry_query.trim() != "NO ANSWER" {
-            let retry_clean = clean_generated_cypher_response(&retry_query);
-
-            // Use shared validation for retry as well
-            if let Some(validated) = validate_and_log_query(&retry_clean, tx).await {
-                tracing::info!("Retry query passed validation");
-                send_option!(tx, Progress::CypherQuery(validated.clone()));
-                return Some(validated);
-            }
+    };
+    let generated = engine::generate_query(&mut writer, tx, generation, models, &mut Vec::new()).await;
+    match generated {
+        Ok(GenerationOutcome::Query(query)) => {
+            tx.send(Progress::CypherQuery(query.clone())).await.ok()?;
+            Some(query)
         }
-
-        // If retry failed, still use original but warn
-        send_option!(
-            tx,
-            Progress::Status(String::from("Warning: Query validation issues detected"))
-        );
+        Ok(GenerationOutcome::NoAnswer { reason }) => {
+            tracing::info!("No query generated from AI model: {}", reason);
+            tx.send(Progress::Usage(token_usage.clone())).await.ok()?;
+            tx.send(Progress::NoAnswer(reason)).await.ok()?;
+            None
+        }
+        Err(GenerationError::Failed(message)) => {
+            report_chat_failure(tx, token_usage, message).await.ok()?;
+            None
+        }
+        Err(GenerationError::Rejected(message)) => {
+            tx.send(Progress::Usage(token_usage.clone())).await.ok()?;
+            tx.send(Progress::Error(message)).await.ok()?;
+            None
+        }
+        Err(GenerationError::Stopped) => None,
     }
-
-    send_option!(tx, Progress::CypherQuery(clean_query.clone()));
-    Some(clean_query)
 }
 
 #[allow(clippy::cognitive_complexity)]
@@ -1466,68 +5317,247 @@ async fn execute_cypher_query(
     query: &str,
     graph_name: &str,
     falkordb_connection: &str,
-    tx: &mpsc::Sender<sse::Event>,
-) -> Result<String, ()> {
-    send_result!(tx, Progress::Status(String::from("Executing Cypher query...")));
+    tx: &ProgressSender,
+) -> Result<String, StepError> {
+    tx.status("Executing Cypher query...").await?;
     tracing::info!("Executing Cypher Query: {}", query);
 
-    match execute_query(query, graph_name, falkordb_connection, true, tx).await {
+    let started = std::time::Instant::now();
+    let outcome = execute_query(query, graph_name, falkordb_connection, true).await;
+    debug_bundle::record(|trace| match &outcome {
+        Ok(result) => trace.record_execution(query, Ok(result), started.elapsed()),
+        Err(e) => trace.record_execution(query, Err(&e.to_string()), started.elapsed()),
+    });
+    match outcome {
         Ok(result) => {
             tracing::info!("Query executed successfully, result: {}", result);
-            send_result!(tx, Progress::CypherResult(result.clone()));
+            tx.send(Progress::CypherResult(result.clone())).await?;
             Ok(result)
         }
         Err(e) => {
             let error_msg = e.to_string();
             tracing::error!("Query execution failed: {}", error_msg);
-            send_result!(tx, Progress::Error(format!("Query execution failed: {error_msg}")));
-            Err(())
+            Err(StepError::Failed(error_msg))
         }
     }
 }
 
+/// Writes the answer of one request as a streamed chat, reusing answers from `ANSWER_CACHE_TTL_SECS`.
+struct ServerAnswerWriter<'a> {
+    request: &'a TextToCypherRequest,
+    inputs: AnswerInputs<'a>,
+    client: &'a genai::Client,
+    tx: &'a ProgressSender,
+}
+
+#[async_trait::async_trait]
+impl engine::AnswerWriter for ServerAnswerWriter<'_> {
+    /// Always [`StepError::Stopped`]: the failure was reported, or the client is gone.
+    type Error = StepError;
+
+    async fn reuse(&mut self) -> Option<(String, Option<u8>)> {
+        cached_answer(&self.inputs)
+            .await
+            .map(|cached| (cached.answer, cached.confidence))
+    }
+
+    async fn write(
+        &mut self,
+        model: &str,
+        token_usage: &mut TokenUsage,
+    ) -> Result<(String, Option<u8>), StepError> {
+        let inputs = &self.inputs;
+        let genai_chat_request = generate_answer_chat_request(
+            inputs.chat_request,
+            inputs.query,
+            inputs.query_result,
+            inputs.audience,
+            inputs.units,
+        );
+        execute_chat_stream(
+            self.client,
+            model,
+            &self.request.model_options,
+            genai_chat_request,
+            self.tx,
+            token_usage,
+        )
+        .await
+        .ok_or(StepError::Stopped)
+    }
+
+    async fn keep(
+        &mut self,
+        answer: &str,
+        confidence: Option<u8>,
+    ) {
+        cache_answer(&self.inputs, answer, confidence).await;
+    }
+}
+
+/// Writes and streams the answer with [`engine::write_answer`], then the terminal events. Returns
+/// the answer, or an empty string once the request has ended without one.
+#[allow(clippy::too_many_arguments)]
 async fn generate_final_answer(
     request: &TextToCypherRequest,
+    schema: &str,
     query: &str,
     query_result: &str,
     client: &genai::Client,
     model: &str,
-    tx: &mpsc::Sender<sse::Event>,
+    tx: &ProgressSender,
     token_usage: &mut TokenUsage,
-) {
-    send!(
+    turn: &mut SessionTurn,
+) -> String {
+    let units = units_for_query(schema, query);
+    let mut writer = ServerAnswerWriter {
+        request,
+        inputs: AnswerInputs {
+            graph_name: &request.graph_name,
+            model,
+            audience: request.audience,
+            chat_request: &request.chat_request,
+            query,
+            query_result,
+            units: &units,
+        },
+        client,
         tx,
-        Progress::Status(String::from(
-            "Generating answer from chat history and Cypher output using AI model..."
-        ))
-    );
+    };
+    let answer_request = AnswerRequest {
+        question: request.chat_request.last_user_question().unwrap_or_default(),
+        schema,
+        query,
+        result: query_result,
+        followups: request.followups,
+        latency_mode: request.latency_mode,
+        response_hooks: &AppConfig::get().response_hooks,
+    };
+    let answered = engine::write_answer(
+        &mut writer,
+        tx,
+        &answer_request,
+        client,
+        model,
+        token_usage,
+        &mut Vec::new(),
+    )
+    .await;
+    let Ok(Ok(answer)) = answered else {
+        return String::new();
+    };
+    turn.confidence = answer.confidence;
+
+    // Emit the aggregated token usage before the terminal Result event so consumers
+    // that treat Result as terminal still receive the usage.
+    if tx.send(Progress::Usage(token_usage.clone())).await.is_err() {
+        return String::new();
+    }
+    if let Some(confidence) = answer.confidence
+        && tx.send(Progress::Confidence(confidence)).await.is_err()
+    {
+        return String::new();
+    }
+    if let Some(followups) = answer.followups.filter(|followups| !followups.is_empty())
+        && tx.send(Progress::Followups(followups)).await.is_err()
+    {
+        return String::new();
+    }
+    if tx.send(Progress::Result(answer.text.clone())).await.is_err() {
+        return String::new();
+    }
+    answer.text
+}
+
+/// The `ANSWER_CACHE_TTL_SECS` answer written for `inputs`, if any. Storage failures are logged
+/// and treated as a miss.
+async fn cached_answer(inputs: &AnswerInputs<'_>) -> Option<CachedAnswer> {
+    let cache = AppConfig::get().answer_cache.as_ref()?;
+    cache.get(inputs).await.unwrap_or_else(|e| {
+        tracing::warn!("Failed to read the answer cache: {e}");
+        None
+    })
+}
+
+/// Stores the answer written for `inputs` when answers are cached.
+async fn cache_answer(
+    inputs: &AnswerInputs<'_>,
+    answer: &str,
+    confidence: Option<u8>,
+) {
+    let Some(cache) = &AppConfig::get().answer_cache else {
+        return;
+    };
+    let cached = CachedAnswer {
+        answer: answer.to_string(),
+        confidence,
+    };
+    if let Err(e) = cache.put(inputs, &cached).await {
+        tracing::warn!("Failed to cache the answer: {e}");
+    }
+}
+
+/// State shared by the REST endpoints through `web::Data`, so they reuse the server's `FalkorDB`
+/// client and LLM client instead of building their own per request.
+struct AppState {
+    /// `FALKORDB_CONNECTION`, whose pooled client [`Self::falkordb`] returns.
+    falkordb_connection: String,
+    /// `DEFAULT_KEY`, which requests fall back to when they bring no key of their own.
+    default_key: Option<String>,
+    /// LLM client for `default_key` on the provider's own endpoint.
+    genai: genai::Client,
+}
+
+impl AppState {
+    fn new(config: &AppConfig) -> Self {
+        Self {
+            falkordb_connection: config.falkordb_connection.clone(),
+            default_key: config.default_key.clone(),
+            genai: create_genai_client_with_endpoint(config.default_key.as_deref(), None),
+        }
+    }
+
+    /// The client and target for `FALKORDB_CONNECTION`. Actix runs each worker on its own runtime,
+    /// so the pool keeps one client per worker rather than one for the whole server.
+    async fn falkordb(&self) -> Result<(falkordb::FalkorAsyncClient, connection::ConnectionTarget), String> {
+        connect(&self.falkordb_connection).await
+    }
 
-    let genai_chat_request = generate_answer_chat_request(&request.chat_request, query, query_result);
-    execute_chat_stream(client, model, genai_chat_request, tx, token_usage).await;
+    /// The shared LLM client when a request uses the default key and endpoint, or a client for the
+    /// key and endpoint it brought.
+    fn genai_client(
+        &self,
+        key: Option<&str>,
+        llm_endpoint: Option<&str>,
+    ) -> genai::Client {
+        if key == self.default_key.as_deref() && llm_endpoint.is_none_or(|endpoint| endpoint.trim().is_empty()) {
+            return self.genai.clone();
+        }
+        create_genai_client_with_endpoint(key, llm_endpoint)
+    }
 }
 
 #[allow(dead_code)]
 async fn graph_query(
+    state: &AppState,
     query: &str,
     graph_name: &str,
     read_only: bool,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let connection_info: FalkorConnectionInfo = AppConfig::get()
-        .falkordb_connection
-        .as_str()
-        .try_into()
-        .map_err(|e| format!("Invalid connection info: {e}"))?;
-
-    let client = build_falkordb_async_client(connection_info)
-        .await
-        .map_err(|e| format!("Failed to build client: {e}"))?;
-    let graph_name = graph_name.to_string();
+    let (client, target) = state.falkordb().await?;
+    let graph_name = target.graph_key(graph_name);
     let query = query.to_string();
 
     // Run the FalkorDB operations in a blocking context
-    let result = tokio::task::spawn_blocking(move || execute_query_blocking(&client, &graph_name, &query, read_only))
-        .await
-        .map_err(|e| format!("Failed to execute blocking task: {e}"))?;
+    let result = tokio::task::spawn_blocking(move || {
+        execute_query_blocking(&client, &graph_name, &query, read_only, |data| {
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(rows_lossy(data))
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to execute blocking task: {e}"))?
+    .map_err(|e| format!("Failed to create runtime: {e}"))?
+    .unwrap_or_else(|e| Err(e.into()));
 
     let json_result = match result {
         Ok(records) => format_as_json(&records),
@@ -1540,6 +5570,7 @@ async fn graph_query(
 }
 
 async fn graph_query_with_csv(
+    state: &AppState,
     query: &str,
     graph_name: &str,
     csv_content: &str,
@@ -1551,47 +5582,206 @@ async fn graph_query_with_csv(
         csv_content.len()
     );
 
-    let connection_info: FalkorConnectionInfo = AppConfig::get()
-        .falkordb_connection
-        .as_str()
-        .try_into()
-        .map_err(|e| format!("Invalid connection info: {e}"))?;
+    let (client, target) = state.falkordb().await?;
 
-    let client = build_falkordb_async_client(connection_info)
-        .await
-        .map_err(|e| format!("Failed to build client: {e}"))?;
+    let graph_name = target.graph_key(graph_name);
+    let (records, _) = run_csv_import(&client, &graph_name, query, csv_content).await?;
+    Ok(format_as_json(&records))
+}
 
-    let graph_name = graph_name.to_string();
-    let query = query.to_string();
+/// The rows of a `LOAD CSV` query, and the statistics `FalkorDB` returned with them.
+type CsvImportOutput = (Vec<Vec<falkordb::FalkorValue>>, Vec<String>);
+
+/// Runs `query` with `csv_content` as the file it loads, returning its rows and statistics.
+async fn run_csv_import(
+    client: &falkordb::FalkorAsyncClient,
+    graph_key: &str,
+    query: &str,
+    csv_content: &str,
+) -> Result<CsvImportOutput, Box<dyn std::error::Error + Send + Sync>> {
+    let client = client.clone();
+    let graph_key = graph_key.to_string();
     let csv_content = csv_content.to_string();
 
     // replace filename in the query with a random uuid.
     let uuid = Uuid::new_v4().to_string();
     let filename = format!("{uuid}.csv");
-    let re = Regex::new(r"file://.*\.csv").unwrap();
-    let query = re.replace(&query, format!("file://{uuid}.csv")).to_string();
+    let re = Regex::new(r"file://.*\.csv").map_err(|e| format!("Invalid CSV file pattern: {e}"))?;
+    let query = re.replace(query, format!("file://{uuid}.csv")).to_string();
 
     tracing::info!("Extracted CSV filename from query: {filename}");
     tracing::info!("query is: {query}");
 
     // Run the FalkorDB operations in a blocking context
     let result = tokio::task::spawn_blocking(move || {
-        execute_query_with_csv_import_blocking(&client, &graph_name, &query, &csv_content, &filename)
+        execute_query_with_csv_import_blocking(&client, &graph_key, &query, &csv_content, &filename)
     })
     .await
     .map_err(|e| format!("Failed to execute blocking task: {e}"))?;
 
-    let json_result = match result {
-        Ok(records) => format_as_json(&records),
-        Err(e) => {
-            let error_msg = format!("Query execution failed: {e}");
-            return Err(error_msg.into());
+    result.map_err(|e| format!("Query execution failed: {e}").into())
+}
+
+/// Runs an uploaded `LOAD CSV` query once per batch of the file, streaming an `ImportBatch` event
+/// after each and an `ImportSummary` at the end.
+///
+/// A failing batch ends the import unless the form asked to skip failing rows: the batch is then
+/// halved until each failing row runs alone, and those rows are skipped. The import still ends
+/// once `MAX_SKIPPED_ROWS` rows were skipped.
+///
+/// The import runs as an `ImportJob` saved after each batch, so a form naming a failed or
+/// interrupted job resumes it at its first unfinished batch, and one naming a completed job only
+/// reports its summary again.
+#[allow(clippy::too_many_lines)]
+async fn stream_csv_import(
+    state: &AppState,
+    graph_name: &str,
+    upload: UploadForm,
+    tx: &ProgressSender,
+) {
+    let (client, target) = match state.falkordb().await {
+        Ok(connection) => connection,
+        Err(e) => {
+            let _ = tx.send(Progress::Error(format!("Failed to connect to FalkorDB: {e}"))).await;
+            return;
+        }
+    };
+    let graph_key = target.graph_key(graph_name);
+
+    let mut job = match import_job(graph_name, &upload).await {
+        Ok(job) => job,
+        Err(e) => {
+            let _ = tx.send(Progress::Error(e)).await;
+            return;
+        }
+    };
+    if job.status == ImportJobStatus::Completed {
+        if tx.send(Progress::ImportSummary(job.summary.clone())).await.is_ok() {
+            let _ = tx.send(Progress::Result(job.summary.to_string())).await;
+        }
+        return;
+    }
+
+    let has_header = reads_headers(&upload.cypher_query);
+    let batches = split_csv(&upload.csv_content, job.batch_rows, has_header);
+    let rows_total = batches.iter().map(|batch| batch.rows).sum();
+    job.batches = batches.len();
+    job.status = ImportJobStatus::Running;
+    job.error = None;
+    save_import_job(&mut job).await;
+    let status = if job.batches_done > 0 {
+        format!(
+            "Resuming import job {} at batch {} of {}",
+            job.job_id,
+            job.batches_done + 1,
+            batches.len()
+        )
+    } else {
+        format!(
+            "Import job {}: importing {rows_total} rows in {} batches",
+            job.job_id,
+            batches.len()
+        )
+    };
+    if tx.send(Progress::Status(status)).await.is_err() {
+        return;
+    }
+
+    let mut summary = job.summary.clone();
+    for (index, batch) in batches.iter().enumerate().skip(job.batches_done) {
+        let mut pending = vec![batch.clone()];
+        while let Some(chunk) = pending.pop() {
+            match run_csv_import(&client, &graph_key, &upload.cypher_query, &chunk.text).await {
+                Ok((_, query_stats)) => {
+                    summary.rows_loaded += chunk.rows;
+                    summary.written.add(WriteStats::from_stats(&query_stats));
+                }
+                Err(e) if upload.on_error == OnBatchError::Stop || summary.rows_skipped >= MAX_SKIPPED_ROWS => {
+                    let message = format!(
+                        "Batch {} of {} failed after {} of {rows_total} rows ({} skipped): {e}",
+                        index + 1,
+                        batches.len(),
+                        summary.rows_loaded,
+                        summary.rows_skipped
+                    );
+                    job.status = ImportJobStatus::Failed;
+                    job.error = Some(message.clone());
+                    job.summary = summary;
+                    save_import_job(&mut job).await;
+                    let _ = tx
+                        .send(Progress::Error(format!("{message}; resume with job_id {}", job.job_id)))
+                        .await;
+                    return;
+                }
+                Err(e) if chunk.rows == 1 => summary.skip_row(chunk.first_row, e.to_string()),
+                // Popped last-first, so the first half runs next and rows load in file order.
+                Err(_) => pending.extend(chunk.halves(has_header).into_iter().rev()),
+            }
+        }
+        job.batches_done = index + 1;
+        job.summary = summary.clone();
+        save_import_job(&mut job).await;
+        let progress = ImportBatch {
+            batch: index + 1,
+            batches: batches.len(),
+            rows_done: summary.rows_loaded,
+            rows_skipped: summary.rows_skipped,
+            rows_total,
+            written: summary.written,
+        };
+        if tx.send(Progress::ImportBatch(progress)).await.is_err() {
+            return;
+        }
+    }
+    job.status = ImportJobStatus::Completed;
+    save_import_job(&mut job).await;
+    if tx.send(Progress::ImportSummary(summary.clone())).await.is_ok() {
+        let _ = tx.send(Progress::Result(summary.to_string())).await;
+    }
+}
+
+/// The job a streamed import runs as: the job the form's `job_id` names, when it can resume with
+/// this upload, else a new job (with that ID, if given).
+async fn import_job(
+    graph_name: &str,
+    upload: &UploadForm,
+) -> Result<ImportJob, String> {
+    if let Some(job_id) = &upload.job_id {
+        let existing = AppConfig::get()
+            .import_jobs
+            .get(job_id)
+            .await
+            .map_err(|e| format!("Failed to load import job '{job_id}': {e}"))?;
+        if let Some(job) = existing {
+            return job
+                .resume_conflict(graph_name, &upload.cypher_query, &upload.csv_content)
+                .map_or(Ok(job), Err);
         }
-    };
-    Ok(json_result)
+    }
+    Ok(ImportJob {
+        job_id: upload.job_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string()),
+        graph_name: graph_name.to_string(),
+        cypher_query: upload.cypher_query.clone(),
+        sha256: content_sha256(&upload.csv_content),
+        batch_rows: upload.batch_rows,
+        batches: 0,
+        batches_done: 0,
+        status: ImportJobStatus::Running,
+        summary: ImportSummary::default(),
+        error: None,
+        updated_at: 0,
+    })
+}
+
+/// Saves an import job's progress; a failed save only costs the ability to resume from it.
+async fn save_import_job(job: &mut ImportJob) {
+    if let Err(e) = AppConfig::get().import_jobs.save(job).await {
+        tracing::warn!("Failed to save import job {}: {}", job.job_id, e);
+    }
 }
 
 async fn graph_query_with_existing_csv(
+    state: &AppState,
     query: &str,
     graph_name: &str,
     csv_filename: &str,
@@ -1603,21 +5793,13 @@ async fn graph_query_with_existing_csv(
         csv_filename
     );
 
-    let connection_info: FalkorConnectionInfo = AppConfig::get()
-        .falkordb_connection
-        .as_str()
-        .try_into()
-        .map_err(|e| format!("Invalid connection info: {e}"))?;
-
-    let client = build_falkordb_async_client(connection_info)
-        .await
-        .map_err(|e| format!("Failed to build client: {e}"))?;
+    let (client, target) = state.falkordb().await?;
 
-    let graph_name = graph_name.to_string();
+    let graph_name = target.graph_key(graph_name);
     let csv_filename = csv_filename.to_string();
 
     // Replace filename patterns in the query with the actual CSV filename
-    let re = Regex::new(r"file://.*\.csv").unwrap();
+    let re = Regex::new(r"file://.*\.csv").map_err(|e| format!("Invalid CSV file pattern: {e}"))?;
     let updated_query = re.replace_all(query, format!("file://{csv_filename}")).to_string();
 
     tracing::info!("Original query: {}", query);
@@ -1645,77 +5827,119 @@ async fn execute_query(
     graph_name: &str,
     falkordb_connection: &str,
     read_only: bool,
-    tx: &mpsc::Sender<sse::Event>,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let connection_info: FalkorConnectionInfo = falkordb_connection
-        .try_into()
-        .map_err(|e| format!("Invalid connection info: {e}"))?;
-
-    let client = build_falkordb_async_client(connection_info)
-        .await
-        .map_err(|e| format!("Failed to build client: {e}"))?;
-
-    let graph_name = graph_name.to_string();
-    let query = query.to_string();
-
-    // Run the FalkorDB operations in a blocking context
-    let result = tokio::task::spawn_blocking(move || execute_query_blocking(&client, &graph_name, &query, read_only))
-        .await
-        .map_err(|e| format!("Failed to execute blocking task: {e}"))?;
-
-    let formatted_result = match result {
-        Ok(records) => format_query_records(&records),
-        Err(e) => {
-            let error_msg = format!("Query execution failed: {e}");
-            try_send_boxed!(tx, Progress::Error(error_msg.clone()));
-            return Err(error_msg.into());
+    let max_result_bytes = AppConfig::get().max_result_bytes;
+
+    // Run the FalkorDB operations in a blocking context, formatting rows as they are read; a
+    // read-only query runs again on the next failover endpoint when its endpoint is unreachable
+    let result = with_failover(falkordb_connection, read_only, |client, target| {
+        let graph_name = target.graph_key(graph_name);
+        let query = query.to_string();
+        async move {
+            tokio::task::spawn_blocking(move || {
+                execute_query_blocking(&client, &graph_name, &query, read_only, |data| {
+                    Ok::<_, Box<dyn std::error::Error + Send + Sync>>(format_rows_within(
+                        data.into_values_lossy(),
+                        max_result_bytes,
+                    )?)
+                })
+                .unwrap_or_else(|e| Ok(Err(format!("Failed to create runtime: {e}").into())))
+            })
+            .await
+            .unwrap_or_else(|e| Ok(Err(format!("Failed to execute blocking task: {e}").into())))
         }
-    };
+    })
+    .await?;
 
-    Ok(formatted_result)
+    // The caller reports the failure: a failed generated query may still be healed
+    result.map_err(|e| format!("Query execution failed: {e}"))?
 }
 
 async fn get_graph_schema_string(
     falkordb_connection: &str,
     graph_name: &str,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let cache = AppConfig::get().schema_cache.clone();
+    discovery: DiscoveryOptions,
+) -> Result<Arc<str>, Box<dyn std::error::Error + Send + Sync>> {
+    let cache = &AppConfig::get().schema_cache;
+    let cacheable = discovery == DiscoveryOptions::default();
 
     // Check cache first
-    if let Some(cached_schema) = cache.get(graph_name) {
+    if let Some(cached_schema) = cache.get(graph_name).filter(|_| cacheable) {
         return Ok(cached_schema);
     }
 
     // If not in cache, discover it
-    let schema = discover_graph_schema(falkordb_connection, graph_name).await?;
-    let schema_json = serde_json::to_string(&schema).map_err(|e| format!("Failed to serialize schema: {e}"))?;
+    let schema_json = discover_graph_schema_with_options(falkordb_connection, graph_name, discovery, &|_| {}).await?;
+    let schema_json: Arc<str> = AppConfig::get().attribute_units.annotate(&schema_json).into();
 
     // Cache the result
-    cache.insert(graph_name.to_string(), schema_json.clone());
+    if cacheable {
+        cache.insert(graph_name.to_string(), schema_json.clone());
+    }
 
     Ok(schema_json)
 }
 
-async fn get_graphs_list() -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
-    let connection_info: FalkorConnectionInfo = AppConfig::get()
-        .falkordb_connection
-        .as_str()
-        .try_into()
-        .map_err(|e| format!("Invalid connection info: {e}"))?;
+async fn get_graphs_list(state: &AppState) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+    list_graphs(&state.falkordb_connection).await.map_err(Into::into)
+}
 
-    let client = build_falkordb_async_client(connection_info)
-        .await
-        .map_err(|e| format!("Failed to build client: {e}"))?;
+/// Returns the "not found" message (with suggestions) when `graph_name` does not exist, checked
+/// against the cached graph list. A miss is re-checked against a fresh listing so graphs created
+/// since the last listing are found. If the graphs cannot be listed the check is skipped.
+async fn missing_graph_message(
+    falkordb_connection: &str,
+    graph_name: &str,
+) -> Option<String> {
+    let config = AppConfig::get();
+    let cache = &config.graph_lists;
+    if let Some(graphs) = cache.get(falkordb_connection)
+        && graph_not_found_message(graph_name, &graphs).is_none()
+    {
+        return None;
+    }
+
+    match list_graphs(falkordb_connection).await {
+        Ok(graphs) => {
+            // Misspelled aliases get alias suggestions too
+            let mut candidates = graphs.clone();
+            candidates.extend(config.graph_aliases.aliases().iter().map(|alias| alias.alias.clone()));
+            let message = graph_not_found_message(graph_name, &candidates);
+            cache.insert(falkordb_connection.to_string(), graphs);
+            message
+        }
+        Err(e) => {
+            tracing::warn!("Skipping graph existence check: {}", e);
+            None
+        }
+    }
+}
 
-    // Call the async version directly
-    let graphs = client.list_graphs().await.map_err(|e| format!("Failed to list graphs: {e}"))?;
-    Ok(graphs)
+/// Copies `graph_name` to a new backup when `GRAPH_BACKUP_RETENTION` is set, pruning its oldest
+/// backups. Returns `None` when backups are off or the graph does not exist yet.
+async fn backup_graph(
+    falkordb_connection: &str,
+    graph_name: &str,
+) -> Result<Option<GraphBackup>, String> {
+    let Some(retention) = AppConfig::get().graph_backup_retention else {
+        return Ok(None);
+    };
+    let graphs = list_graphs(falkordb_connection).await.map_err(|e| e.to_string())?;
+    if !graphs.iter().any(|graph| graph == graph_name) {
+        return Ok(None);
+    }
+    let (client, target) = connect(falkordb_connection).await?;
+    graph_backup::snapshot(&client, &target, graph_name, retention)
+        .await
+        .map(Some)
+        .map_err(|e| e.to_string())
 }
 
 /// Deletes a graph from `FalkorDB`
 ///
 /// # Arguments
 ///
+/// * `state` - The server state whose `FalkorDB` client performs the deletion
 /// * `graph_name` - The name of the graph to delete
 ///
 /// # Returns
@@ -1728,18 +5952,14 @@ async fn get_graphs_list() -> Result<Vec<String>, Box<dyn std::error::Error + Se
 /// - The connection to `FalkorDB` fails
 /// - The graph deletion operation fails
 /// - The graph does not exist
-async fn delete_graph(graph_name: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let connection_info: FalkorConnectionInfo = AppConfig::get()
-        .falkordb_connection
-        .as_str()
-        .try_into()
-        .map_err(|e| format!("Invalid connection info: {e}"))?;
-
-    let client = build_falkordb_async_client(connection_info)
-        .await
-        .map_err(|e| format!("Failed to build client: {e}"))?;
+async fn delete_graph(
+    state: &AppState,
+    graph_name: &str,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let (client, target) = state.falkordb().await?;
 
     let graph_name_owned = graph_name.to_string();
+    let graph_key = target.graph_key(graph_name);
 
     // Run the FalkorDB operations in a blocking context
     tokio::task::spawn_blocking(move || {
@@ -1748,7 +5968,7 @@ async fn delete_graph(graph_name: &str) -> Result<String, Box<dyn std::error::Er
 
         rt.block_on(async {
             // Select the graph and call delete on it
-            let mut graph = client.select_graph(&graph_name_owned);
+            let mut graph = client.select_graph(&graph_key);
             graph.delete().await.map_err(|e| format!("Failed to delete graph: {e}"))?;
 
             Ok::<String, Box<dyn std::error::Error + Send + Sync>>(format!(
@@ -1760,42 +5980,13 @@ async fn delete_graph(graph_name: &str) -> Result<String, Box<dyn std::error::Er
     .map_err(|e| format!("Failed to execute blocking task: {e}"))?
 }
 
-fn execute_query_blocking(
-    client: &falkordb::FalkorAsyncClient,
-    graph_name: &str,
-    query: &str,
-    read_only: bool,
-) -> Result<Vec<Vec<falkordb::FalkorValue>>, Box<dyn std::error::Error + Send + Sync>> {
-    // Create a new Tokio runtime for this blocking operation
-    let rt = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to create runtime: {e}"))?;
-
-    rt.block_on(async {
-        let mut graph = client.select_graph(graph_name);
-        let query_result = if read_only {
-            graph
-                .ro_query(query)
-                .execute()
-                .await
-                .map_err(|e| format!("Query execution failed: {e}"))?
-        } else {
-            graph
-                .query(query)
-                .execute()
-                .await
-                .map_err(|e| format!("Query execution failed: {e}"))?
-        };
-
-        Ok(rows_lossy(query_result.data))
-    })
-}
-
 fn execute_query_with_csv_import_blocking(
     client: &falkordb::FalkorAsyncClient,
     graph_name: &str,
     query: &str,
     csv_content: &str,
     filename: &str,
-) -> Result<Vec<Vec<falkordb::FalkorValue>>, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<CsvImportOutput, Box<dyn std::error::Error + Send + Sync>> {
     use std::fs;
     use std::path::PathBuf;
 
@@ -1848,6 +6039,7 @@ fn execute_query_with_csv_import_blocking(
         tracing::info!("Query {query} executed, processing results...");
 
         let records = rows_lossy(query_result.data);
+        let stats = query_result.stats;
 
         tracing::info!(
             "Query executed successfully with CSV import, records count: {}",
@@ -1859,7 +6051,7 @@ fn execute_query_with_csv_import_blocking(
             tracing::warn!("Failed to remove CSV file from import folder: {}", e);
         }
 
-        Ok(records)
+        Ok((records, stats))
     })
 }
 
@@ -1995,31 +6187,9 @@ async fn list_import_folder_files(
     Ok(files)
 }
 
-/// Appends validation feedback to a chat request for query regeneration
-fn append_validation_feedback(
-    chat_request: &ChatRequest,
-    failed_query: &str,
-    error_message: &str,
-) -> ChatRequest {
-    let mut messages = chat_request.messages.clone();
-
-    // Add the failed query as an assistant message
-    messages.push(ChatMessage {
-        role: ChatRole::Assistant,
-        content: failed_query.to_string(),
-    });
-
-    // Add validation error as user feedback
-    messages.push(ChatMessage {
-        role: ChatRole::User,
-        content: format!(
-            "The previous query has validation errors: {error_message}. Please generate a corrected Cypher query."
-        ),
-    });
-
-    ChatRequest { messages }
-}
-
+/// Builds the query generation request with [`prompts::create_cypher_query_chat_request`], without
+/// the procedures `PROCEDURE_ALLOWLIST` forbids, logging it in full unless the system prompt is
+/// large.
 #[must_use]
 fn generate_create_cypher_query_chat_request_with_skills(
     chat_request: &ChatRequest,
@@ -2029,44 +6199,15 @@ fn generate_create_cypher_query_chat_request_with_skills(
     use_tools: bool,
     model: &str,
 ) -> genai::chat::ChatRequest {
-    let mut chat_req = genai::chat::ChatRequest::default();
-    for (index, message) in chat_request.messages.iter().enumerate() {
-        let is_last_user_message = index == chat_request.messages.len() - 1 && message.role == ChatRole::User;
-
-        let genai_message = match message.role {
-            ChatRole::User => {
-                if is_last_user_message {
-                    // Special processing for the last user message
-                    let processed_content = process_last_user_message(&message.content);
-                    genai::chat::ChatMessage::user(processed_content)
-                } else {
-                    genai::chat::ChatMessage::user(message.content.clone())
-                }
-            }
-            ChatRole::Assistant => genai::chat::ChatMessage::assistant(message.content.clone()),
-            ChatRole::System => genai::chat::ChatMessage::system(message.content.clone()),
-        };
-
-        chat_req = chat_req.append_message(genai_message);
+    let mut chat_req =
+        prompts::create_cypher_query_chat_request(chat_request, ontology, skill_catalog, udfs, use_tools);
+    if let Some(allowlist) = &AppConfig::get().procedure_allowlist {
+        chat_req = prompts::restrict_procedures(chat_req, allowlist);
     }
-
-    // Build skills catalog text for the prompt
-    let skills_text = match skill_catalog {
-        Some(catalog) if !catalog.is_empty() => {
-            if use_tools {
-                catalog.render_catalog()
-            } else {
-                catalog.render_all_content()
-            }
-        }
-        _ => String::new(),
-    };
-
-    let system_prompt = TemplateEngine::render_system_prompt_with_context(ontology, &skills_text, udfs);
-    let system_prompt_len = system_prompt.len();
-    let should_summarize_log = !skills_text.is_empty() || system_prompt_len > CHAT_REQUEST_LOG_SUMMARY_THRESHOLD;
+    let system_prompt_len = chat_req.system.as_ref().map_or(0, String::len);
+    let has_skills = skill_catalog.is_some_and(|catalog| !catalog.is_empty());
+    let should_summarize_log = has_skills || system_prompt_len > CHAT_REQUEST_LOG_SUMMARY_THRESHOLD;
     let expected_tool_count = usize::from(use_tools);
-    chat_req = chat_req.with_system(system_prompt);
 
     if should_summarize_log {
         tracing::info!(
@@ -2089,27 +6230,10 @@ fn generate_answer_chat_request(
     chat_request: &ChatRequest,
     cypher_query: &str,
     cypher_result: &str,
+    audience: Option<Audience>,
+    units: &str,
 ) -> genai::chat::ChatRequest {
-    let mut chat_req = genai::chat::ChatRequest::default();
-    for (index, message) in chat_request.messages.iter().enumerate() {
-        let is_last_user_message = index == chat_request.messages.len() - 1 && message.role == ChatRole::User;
-
-        let genai_message = match message.role {
-            ChatRole::User => {
-                if is_last_user_message {
-                    // Special processing for the last user message
-                    let processed_content = process_last_request_prompt(&message.content, cypher_query, cypher_result);
-                    genai::chat::ChatMessage::user(processed_content)
-                } else {
-                    genai::chat::ChatMessage::user(message.content.clone())
-                }
-            }
-            ChatRole::Assistant => genai::chat::ChatMessage::assistant(message.content.clone()),
-            ChatRole::System => genai::chat::ChatMessage::system(message.content.clone()),
-        };
-
-        chat_req = chat_req.append_message(genai_message);
-    }
+    let chat_req = prompts::create_answer_chat_request(chat_request, cypher_query, cypher_result, audience, units);
 
     // Pretty print the chat request as JSON for logging
     if let Ok(pretty_json) = serde_json::to_string_pretty(&chat_req) {
@@ -2120,14 +6244,6 @@ fn generate_answer_chat_request(
     chat_req
 }
 
-fn process_last_request_prompt(
-    content: &str,
-    cypher_query: &str,
-    cypher_result: &str,
-) -> String {
-    TemplateEngine::render_last_request_prompt(content, cypher_query, cypher_result)
-}
-
 #[allow(clippy::pedantic)]
 #[derive(OpenApi)]
 #[openapi(
@@ -2138,20 +6254,108 @@ fn process_last_request_prompt(
         load_csv_endpoint,
         echo_endpoint,
         list_graphs_endpoint,
+        graph_aliases_endpoint,
         graph_list_endpoint,
         graph_delete_endpoint,
         get_schema_endpoint,
         configured_model_endpoint,
+        embed_endpoint,
+        search_endpoint,
+        graph_backups_endpoint,
+        graph_restore_endpoint,
+        stats_endpoint,
+        shadow_endpoint,
+        usage_report_endpoint,
+        list_quotas_endpoint,
+        set_quota_endpoint,
+        delete_quota_endpoint,
+        get_examples_endpoint,
+        set_examples_endpoint,
+        delete_examples_endpoint,
+        metrics_endpoint,
+        health_endpoint,
+        debug_bundle_endpoint,
+        create_session_endpoint,
+        ask_session_endpoint,
+        export_session_endpoint,
+        session_notebook_endpoint,
+        import_session_endpoint,
         graph_query_endpoint,
-        graph_query_upload_endpoint
+        graph_query_upload_endpoint,
+        graph_query_upload_stream_endpoint,
+        import_inspect_endpoint,
+        import_remote_endpoint,
+        import_job_endpoint,
+        schema_diff_endpoint,
+        enrich_schema_endpoint
     ),
     components(schemas(
         TextToCypherRequest,
         Progress,
+        StreamStatus,
+        Warning,
+        WarningCode,
         ChatRequest,
         ChatMessage,
         ChatRole,
+        Audience,
+        LatencyMode,
+        DiscoveryOptions,
+        ModelOptions,
+        Priority,
         ConfiguredModelResponse,
+        EmbedRequest,
+        EmbedResponse,
+        SearchRequest,
+        SearchResponse,
+        GraphBackup,
+        RestoreRequest,
+        SearchHit,
+        StatsSnapshot,
+        ShadowReport,
+        UsageReport,
+        UsageGroup,
+        UsageGroupBy,
+        GraphQuota,
+        QuotaStatus,
+        FewShotExample,
+        HealthResponse,
+        ConnectionHealth,
+        EndpointHealth,
+        DebugBundle,
+        ModelCall,
+        debug_bundle::PromptMessage,
+        ValidationReport,
+        QueryExecution,
+        debug_bundle::StageTiming,
+        CreateSessionRequest,
+        AskRequest,
+        SessionCreatedResponse,
+        SessionExport,
+        ::text_to_cypher::session::Session,
+        SessionTurn,
+        DryRun,
+        ImportBatch,
+        ImportSummary,
+        OnBatchError,
+        RowError,
+        WriteStats,
+        CsvInspection,
+        RemoteImportRequest,
+        RemoteImportResponse,
+        ImportJob,
+        ImportJobStatus,
+        SchemaDiffResponse,
+        EnrichSchemaRequest,
+        EnrichSchemaResponse,
+        SchemaDiff,
+        AttributeChanges,
+        TypeChange,
+        CsvColumn,
+        CsvEncoding,
+        LintHint,
+        validator::LintRule,
+        GraphAlias,
         ErrorResponse,
         GraphQueryRequest,
         GraphListRequest,
@@ -2163,12 +6367,151 @@ fn process_last_request_prompt(
 )]
 struct ApiDoc;
 
+/// The `OpenAPI` spec as YAML, or as pretty-printed JSON.
+fn openapi_spec(yaml: bool) -> Result<String, String> {
+    let openapi = ApiDoc::openapi();
+    if yaml {
+        serde_yaml_ng::to_string(&openapi).map_err(|e| format!("Failed to render the OpenAPI spec: {e}"))
+    } else {
+        openapi
+            .to_pretty_json()
+            .map_err(|e| format!("Failed to render the OpenAPI spec: {e}"))
+    }
+}
+
+/// Writes the `OpenAPI` spec for `--export-openapi <path>`: YAML for `.yaml` and `.yml` paths, JSON
+/// otherwise, and JSON on standard output for `-`.
+fn export_openapi(path: &str) -> Result<(), String> {
+    if path == "-" {
+        let spec = openapi_spec(false)?;
+        return std::io::Write::write_all(&mut std::io::stdout(), format!("{spec}\n").as_bytes())
+            .map_err(|e| format!("Failed to write the spec: {e}"));
+    }
+    let yaml = std::path::Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("yaml") || extension.eq_ignore_ascii_case("yml"));
+    std::fs::write(path, openapi_spec(yaml)?).map_err(|e| format!("Failed to write {path}: {e}"))
+}
+
+/// The `OpenAPI` spec as YAML, for generators that prefer it to `/api-doc/openapi.json`.
+#[actix_web::get("/api-doc/openapi.yaml")]
+async fn openapi_yaml() -> impl Responder {
+    match openapi_spec(true) {
+        Ok(yaml) => HttpResponse::Ok().content_type("application/yaml").body(yaml),
+        Err(error) => HttpResponse::InternalServerError().json(ErrorResponse { error }),
+    }
+}
+
+/// The playground page: graph selector, chat, streamed progress, Cypher preview and result table,
+/// all talking to this server's REST API.
+#[cfg(feature = "ui")]
+const PLAYGROUND_HTML: &str = include_str!("../ui/index.html");
+
+#[cfg(feature = "ui")]
+#[actix_web::get("/")]
+async fn playground() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(PLAYGROUND_HTML)
+}
+
+/// Removes a unix socket left behind by a previous run so `path` can be bound again. A socket
+/// that still accepts connections belongs to a running instance and is reported as in use;
+/// anything other than a socket at `path` is left alone and makes the bind fail instead.
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if !std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        return Ok(());
+    }
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::AddrInUse,
+            format!("{} is in use by another process", path.display()),
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => std::fs::remove_file(path),
+        Err(e) => Err(e),
+    }
+}
+
+/// Every namespace the server keeps state in.
+const STORAGE_NAMESPACES: [&str; 11] = [
+    ::text_to_cypher::session::SESSIONS_NAMESPACE,
+    ::text_to_cypher::audit::AUDIT_NAMESPACE,
+    ::text_to_cypher::debug_bundle::DEBUG_NAMESPACE,
+    ::text_to_cypher::dry_run::DRY_RUN_NAMESPACE,
+    ::text_to_cypher::answer_cache::ANSWER_CACHE_NAMESPACE,
+    ::text_to_cypher::import_jobs::IMPORT_JOB_NAMESPACE,
+    ::text_to_cypher::schema_store::SCHEMA_NAMESPACE,
+    ::text_to_cypher::quota::QUOTA_NAMESPACE,
+    ::text_to_cypher::few_shot::FEW_SHOT_NAMESPACE,
+    graph_trash::DELETE_CONFIRMATION_NAMESPACE,
+    graph_trash::TRASH_NAMESPACE,
+];
+
+/// Rewrites every stored value with the first key of `STORAGE_ENCRYPTION_KEYS`, for
+/// `--reencrypt`, and returns how many values of each namespace were rewritten.
+async fn reencrypt_storage(falkordb_connection: &str) -> Result<String, String> {
+    let (backend, keyring) = AppConfig::open_storage(falkordb_connection).map_err(|e| e.to_string())?;
+    if backend.backend_name() == "memory" {
+        return Err(
+            "--reencrypt needs a persistent STORAGE_BACKEND; in-memory storage holds nothing to rewrite".into(),
+        );
+    }
+    let keyring = keyring.ok_or("--reencrypt needs STORAGE_ENCRYPTION_KEYS or STORAGE_ENCRYPTION_KEYS_FILE")?;
+    let current_key_id = keyring.current_key_id().to_string();
+    let storage = EncryptedStorage::new(backend, keyring);
+    let mut report = String::new();
+    for namespace in STORAGE_NAMESPACES {
+        let rewritten = storage
+            .reencrypt(namespace)
+            .await
+            .map_err(|e| format!("Failed to re-encrypt {namespace}: {e}"))?;
+        let _ = writeln!(report, "{namespace}: {rewritten} value(s) rewritten");
+    }
+    let _ = writeln!(report, "Every stored value is encrypted with key '{current_key_id}'");
+    Ok(report)
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     fmt().with_max_level(tracing::Level::INFO).init();
 
+    // Exporting the spec needs no configuration, so CI can generate clients without a deployment.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--export-openapi") {
+        let exported = args
+            .get(index + 1)
+            .ok_or_else(|| "--export-openapi needs a file path, or - for standard output".to_string())
+            .and_then(|path| export_openapi(path));
+        if let Err(e) = exported {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Initialize configuration from .env file
     let config = AppConfig::get();
+
+    if args.iter().any(|arg| arg == "--check") {
+        let (report, passed) = check::run(config).await;
+        println!("{report}");
+        std::process::exit(i32::from(!passed));
+    }
+    if args.iter().any(|arg| arg == "--reencrypt") {
+        match reencrypt_storage(&config.falkordb_connection).await {
+            Ok(report) => {
+                print!("{report}");
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
     let rest_port = config.rest_port;
     let mcp_port = config.mcp_port;
 
@@ -2179,14 +6522,27 @@ async fn main() -> std::io::Result<()> {
     );
 
     // Conditionally start MCP server based on configuration
-    let mcp_handle = if config.should_start_mcp_server() {
-        Some(tokio::spawn(async move {
-            if let Err(e) = run_mcp_server(mcp_port).await {
+    let mcp_handle = match (&config.mcp_bind_address, config.bind_address.local_url(rest_port)) {
+        _ if !config.should_start_mcp_server() => None,
+        (BindAddress::Host(host), Some(rest_url)) => Some(tokio::spawn(async move {
+            if let Err(e) = run_mcp_server(host, mcp_port, rest_url, config.mcp_options).await {
                 tracing::error!("MCP server error: {}", e);
             }
-        }))
-    } else {
-        None
+        })),
+        (BindAddress::Host(_), None) => {
+            tracing::error!(
+                "MCP server not started: its tools call the REST API over TCP, which BIND_ADDRESS={} does not listen on",
+                config.bind_address
+            );
+            None
+        }
+        (BindAddress::Unix(path), _) => {
+            tracing::error!(
+                "MCP server not started: MCP_BIND_ADDRESS=unix:{} is not supported, use a host",
+                path.display()
+            );
+            None
+        }
     };
 
     // Start the HTTP server with Swagger UI at /swagger-ui/
@@ -2194,25 +6550,83 @@ async fn main() -> std::io::Result<()> {
     // Swagger UI will be accessible at:
     // http://localhost:{rest_port}/swagger-ui/
 
-    tracing::info!("Starting HTTP server on 0.0.0.0:{}", rest_port);
+    if config.schema_cache.options().warm {
+        warm_schema_cache(config).await;
+    }
+    if let Some(interval) = config.schema_cache.refresh_interval() {
+        tokio::spawn(refresh_schema_cache(config, interval));
+    }
+    if let Some(ttl) = config.graph_trash_ttl {
+        tokio::spawn(purge_graph_trash(config, ttl));
+    }
 
-    let http_server = HttpServer::new(|| {
-        App::new()
-            .service(text_to_cypher)
+    let state = web::Data::new(AppState::new(config));
+    let http_server = HttpServer::new(move || {
+        let app = App::new().app_data(state.clone());
+        #[cfg(feature = "ui")]
+        let app = app.service(playground);
+        app.service(text_to_cypher)
             .service(clear_schema_cache)
             .service(clear_udf_cache)
             .service(load_csv_endpoint)
             .service(echo_endpoint)
             .service(list_graphs_endpoint)
+            .service(graph_aliases_endpoint)
             .service(graph_list_endpoint)
             .service(graph_delete_endpoint)
             .service(get_schema_endpoint)
             .service(configured_model_endpoint)
+            .service(embed_endpoint)
+            .service(search_endpoint)
+            .service(graph_backups_endpoint)
+            .service(graph_restore_endpoint)
+            .service(stats_endpoint)
+            .service(shadow_endpoint)
+            .service(usage_report_endpoint)
+            .service(list_quotas_endpoint)
+            .service(set_quota_endpoint)
+            .service(delete_quota_endpoint)
+            .service(get_examples_endpoint)
+            .service(set_examples_endpoint)
+            .service(delete_examples_endpoint)
+            .service(metrics_endpoint)
+            .service(health_endpoint)
+            .service(debug_bundle_endpoint)
+            .service(create_session_endpoint)
+            .service(ask_session_endpoint)
+            .service(export_session_endpoint)
+            .service(session_notebook_endpoint)
+            .service(import_session_endpoint)
             .service(graph_query_endpoint)
             .service(graph_query_upload_endpoint)
+            .service(graph_query_upload_stream_endpoint)
+            .service(import_inspect_endpoint)
+            .service(import_remote_endpoint)
+            .service(import_job_endpoint)
+            .service(schema_diff_endpoint)
+            .service(enrich_schema_endpoint)
+            .service(openapi_yaml)
             .service(SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-doc/openapi.json", ApiDoc::openapi()))
-    })
-    .bind(("0.0.0.0", rest_port))?
+    });
+    let http_server = match &config.bind_address {
+        BindAddress::Host(host) => {
+            tracing::info!("Starting HTTP server on {}:{}", host, rest_port);
+            http_server.bind((host.as_str(), rest_port))?
+        }
+        BindAddress::Unix(path) => {
+            tracing::info!("Starting HTTP server on unix socket {}", path.display());
+            #[cfg(unix)]
+            {
+                remove_stale_socket(path)?;
+                http_server.bind_uds(path)?
+            }
+            #[cfg(not(unix))]
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("BIND_ADDRESS=unix:{} requires a unix platform", path.display()),
+            ));
+        }
+    }
     .run();
 
     // Run server(s) concurrently
@@ -2240,65 +6654,119 @@ async fn main() -> std::io::Result<()> {
 #[derive(Deserialize)]
 struct GetSchemaQuery {
     falkordb_connection: Option<String>,
+    sample_size: Option<usize>,
+    pair_relationships: Option<bool>,
 }
 
-async fn discover_graph_schema(
-    falkordb_connection: &str,
-    graph_name: &str,
-) -> Result<Schema, Box<dyn std::error::Error + Send + Sync>> {
-    let connection_info: FalkorConnectionInfo = falkordb_connection
-        .try_into()
-        .map_err(|e| format!("Invalid connection info: {e}"))?;
-
-    let client = build_falkordb_async_client(connection_info)
-        .await
-        .map_err(|e| format!("Failed to build client: {e}"))?;
+impl GetSchemaQuery {
+    /// The discovery options the query sets, defaulting the others.
+    fn discovery(&self) -> DiscoveryOptions {
+        let defaults = DiscoveryOptions::default();
+        DiscoveryOptions {
+            sample_size: self.sample_size.unwrap_or(defaults.sample_size),
+            pair_relationships: self.pair_relationships.unwrap_or(defaults.pair_relationships),
+        }
+    }
+}
 
-    // Select the specified graph
-    let mut graph = client.select_graph(graph_name);
-    let schema = Schema::discover_from_graph(&mut graph, 100)
-        .await
-        .map_err(|e| format!("Failed to discover schema from graph: {e}"))?;
+/// Fills the schema cache with the stored schema snapshots younger than its TTL, so the first
+/// requests after a restart skip discovery.
+async fn warm_schema_cache(config: &AppConfig) {
+    match config.schema_snapshots.all().await {
+        Ok(snapshots) => {
+            let total = snapshots.len();
+            let warmed = snapshots
+                .into_iter()
+                .filter(|snapshot| config.schema_cache.insert_snapshot(snapshot.clone()))
+                .count();
+            tracing::info!("Warmed the schema cache with {warmed} of {total} stored schemas");
+        }
+        Err(e) => tracing::warn!("Failed to load stored schemas into the schema cache: {e}"),
+    }
+}
 
-    // Print the discovered schema
-    tracing::info!("Discovered schema: {schema}");
-    Ok(schema)
+/// Every `interval`, re-discovers the cached schemas nearing their TTL and replaces them and their
+/// snapshots. A failed discovery leaves the old schema to expire.
+async fn refresh_schema_cache(
+    config: &'static AppConfig,
+    interval: std::time::Duration,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+        for graph_name in config.schema_cache.due_for_refresh() {
+            let discovered = discover_graph_schema_with_options(
+                &config.falkordb_connection,
+                &graph_name,
+                DiscoveryOptions::default(),
+                &|_| {},
+            )
+            .await;
+            match discovered {
+                Ok(schema) => {
+                    let schema: Arc<str> = config.attribute_units.annotate(&schema).into();
+                    if let Err(e) = config.schema_snapshots.save(&graph_name, &schema).await {
+                        tracing::warn!("Failed to save schema snapshot for {graph_name}: {e}");
+                    }
+                    config.schema_cache.insert(graph_name.clone(), schema);
+                    tracing::info!("Refreshed the cached schema of {graph_name}");
+                }
+                Err(e) => tracing::warn!("Failed to refresh the cached schema of {graph_name}: {e}"),
+            }
+        }
+    }
 }
 
-fn process_last_user_message(question: &str) -> String {
-    TemplateEngine::render_user_prompt(question)
+/// Every hour, deletes the graphs that have been in the trash longer than `ttl`, on the default
+/// connection and every named one in `FALKORDB_CONNECTIONS`.
+async fn purge_graph_trash(
+    config: &'static AppConfig,
+    ttl: std::time::Duration,
+) {
+    let interval = std::time::Duration::from_secs(60 * 60).min(ttl.max(std::time::Duration::from_secs(1)));
+    let mut connections: Vec<(&str, &str)> = vec![("default", config.falkordb_connection.as_str())];
+    for name in config.connections.names() {
+        let url = config.connections.resolve(name);
+        if connections.iter().all(|(_, purged)| *purged != url) {
+            connections.push((name, url));
+        }
+    }
+    loop {
+        tokio::time::sleep(interval).await;
+        for (name, url) in &connections {
+            let purged = match connect(url).await {
+                Ok((client, target)) => config.graph_trash.purge(&client, &target, ttl).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = purged {
+                tracing::warn!("Failed to purge the graph trash of connection '{name}': {e}");
+            }
+        }
+    }
 }
 
 #[allow(clippy::cognitive_complexity)]
 async fn discover_and_send_schema(
     falkordb_connection: &str,
     graph_name: &str,
-    tx: &mpsc::Sender<sse::Event>,
-) -> Result<String, ()> {
-    try_send!(
-        tx,
-        Progress::Status(format!("Discovering schema for graph: {graph_name}"))
-    );
-
-    let schema = match discover_graph_schema(falkordb_connection, graph_name).await {
-        Ok(s) => s,
-        Err(e) => {
-            tracing::error!("Failed to discover schema: {}", e);
-            try_send!(tx, Progress::Error(format!("Failed to discover schema: {e}")));
-            return Err(());
-        }
-    };
+    discovery: DiscoveryOptions,
+    tx: &ProgressSender,
+) -> Result<String, String> {
+    tx.send(Progress::Status(format!("Discovering schema for graph: {graph_name}")))
+        .await
+        .map_err(|e| e.to_string())?;
 
-    // Serialize and handle errors inline
-    let json_schema = match serde_json::to_string(&schema) {
-        Ok(s) => s,
-        Err(e) => {
-            tracing::error!("Failed to serialize schema to JSON: {}", e);
-            try_send!(tx, Progress::Error("Failed to serialize schema".to_string()));
-            return Err(());
-        }
-    };
+    // Milestones are best-effort: a full channel drops them rather than stalling discovery.
+    let on_progress = |progress: DiscoveryProgress| tx.try_send(&Progress::Status(progress.to_string()));
+    let json_schema =
+        match discover_graph_schema_with_options(falkordb_connection, graph_name, discovery, &on_progress).await {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("Failed to discover schema: {}", e);
+                return Err(e.to_string());
+            }
+        };
 
+    let json_schema = AppConfig::get().attribute_units.annotate(&json_schema);
     tracing::info!("Discovered schema: {}", json_schema);
     Ok(json_schema)
 }
@@ -2306,34 +6774,76 @@ async fn discover_and_send_schema(
 async fn send_processing_status(
     request: &TextToCypherRequest,
     service_target: &genai::ServiceTarget,
-    tx: &mpsc::Sender<sse::Event>,
+    tx: &ProgressSender,
 ) {
     let adapter_kind = service_target.model.adapter_kind;
-    let model_name = request.model.as_deref().unwrap_or("unknown");
-    send!(
-        tx,
-        Progress::Status(format!(
+    let model_name = request.model.as_ref().map_or("unknown", ModelChain::primary);
+    let _ = tx
+        .send(Progress::Status(format!(
             "Processing query for graph: {} using model: {} ({:?})",
             request.graph_name, model_name, adapter_kind
-        ))
-    );
+        )))
+        .await;
 }
 
 /// Execute a chat request with optional skill tool-calling support.
 ///
 /// If skills are present and the model supports tool calling, registers a `read_skill`
-/// tool and handles the tool-call loop. Otherwise falls back to standard chat.
+/// tool and handles the tool-call loop. Otherwise falls back to standard chat. The call is recorded
+/// in the debug trace. Returns the error, not yet reported, if the chat request failed.
 #[allow(clippy::too_many_arguments)]
 async fn execute_chat_with_skills(
     client: &genai::Client,
     model: &str,
+    options: &ModelOptions,
     chat_request: &ChatRequest,
     schema: &str,
     skill_catalog: Option<&SkillCatalog>,
     udfs: &str,
-    tx: &mpsc::Sender<sse::Event>,
+    tx: &ProgressSender,
     token_usage: &mut TokenUsage,
-) -> String {
+) -> Result<GenerationOutcome, String> {
+    let started = std::time::Instant::now();
+    let reply = run_chat_with_skills(
+        client,
+        model,
+        options,
+        chat_request,
+        schema,
+        skill_catalog,
+        udfs,
+        tx,
+        token_usage,
+    )
+    .await;
+    debug_bundle::record(|trace| trace.finish_model_call(reply.as_deref().ok(), started.elapsed()));
+    reply.map(|reply| GenerationOutcome::from_reply(Some(&reply)))
+}
+
+/// Ends the request with the error of a failed chat request, after the token usage so far.
+async fn report_chat_failure(
+    tx: &ProgressSender,
+    token_usage: &TokenUsage,
+    message: String,
+) -> Result<(), StreamClosed> {
+    tx.send(Progress::Usage(token_usage.clone())).await?;
+    tx.send(Progress::Error(message)).await
+}
+
+/// The tool-call loop behind [`execute_chat_with_skills`]. Returns the model's reply, or the error
+/// of a failed chat request.
+#[allow(clippy::too_many_arguments)]
+async fn run_chat_with_skills(
+    client: &genai::Client,
+    model: &str,
+    options: &ModelOptions,
+    chat_request: &ChatRequest,
+    schema: &str,
+    skill_catalog: Option<&SkillCatalog>,
+    udfs: &str,
+    tx: &ProgressSender,
+    token_usage: &mut TokenUsage,
+) -> Result<String, String> {
     let use_tools = skill_catalog.is_some_and(|c| !c.is_empty()) && skills::supports_tool_calling(model);
 
     let mut genai_request = generate_create_cypher_query_chat_request_with_skills(
@@ -2345,6 +6855,8 @@ async fn execute_chat_with_skills(
         model,
     );
 
+    debug_bundle::record(|trace| trace.begin_model_call("cypher_generation", &genai_request));
+
     // Register the read_skill tool if supported
     if use_tools {
         if let Some(catalog) = skill_catalog {
@@ -2352,15 +6864,15 @@ async fn execute_chat_with_skills(
         }
     }
 
+    let chat_options = options.chat_options();
     for round in 0..skills::MAX_TOOL_ROUNDS {
-        let chat_response = match client.exec_chat(model, genai_request.clone(), None).await {
+        let chat_response = match client.exec_chat(model, genai_request.clone(), Some(&chat_options)).await {
             Ok(response) => response,
             Err(e) if use_tools => {
                 tracing::warn!("Tool-enabled chat request failed; retrying without tools: {}", e);
-                send_or_empty!(
-                    tx,
-                    Progress::Status("Tool calling failed; retrying query generation without tools...".to_string())
-                );
+                tx.status("Tool calling failed; retrying query generation without tools...")
+                    .await
+                    .map_err(|e| e.to_string())?;
                 let fallback_request = generate_create_cypher_query_chat_request_with_skills(
                     chat_request,
                     schema,
@@ -2369,34 +6881,22 @@ async fn execute_chat_with_skills(
                     false,
                     model,
                 );
-                match client.exec_chat(model, fallback_request, None).await {
-                    Ok(response) => {
-                        token_usage.add_genai_usage(&response.usage);
-                        return response.into_first_text().unwrap_or_else(|| String::from("NO ANSWER"));
-                    }
-                    Err(fallback_err) => {
-                        let error_update =
-                            Progress::Error(format!("Chat request failed: {e}; fallback failed: {fallback_err}"));
-                        send_or_empty!(tx, Progress::Usage(*token_usage));
-                        send_or_empty!(tx, error_update);
-                        return String::from("NO ANSWER");
-                    }
-                }
-            }
-            Err(e) => {
-                let error_update = Progress::Error(format!("Chat request failed: {e}"));
-                send_or_empty!(tx, Progress::Usage(*token_usage));
-                send_or_empty!(tx, error_update);
-                return String::from("NO ANSWER");
+                let response = client
+                    .exec_chat(model, fallback_request, Some(&chat_options))
+                    .await
+                    .map_err(|fallback_err| format!("Chat request failed: {e}; fallback failed: {fallback_err}"))?;
+                token_usage.add_genai_usage(model, &response.usage);
+                return Ok(response.into_first_text().unwrap_or_default());
             }
+            Err(e) => return Err(format!("Chat request failed: {e}")),
         };
 
-        token_usage.add_genai_usage(&chat_response.usage);
+        token_usage.add_genai_usage(model, &chat_response.usage);
 
         let tool_calls = chat_response.tool_calls().into_iter().cloned().collect::<Vec<_>>();
 
         if tool_calls.is_empty() {
-            return chat_response.into_first_text().unwrap_or_else(|| String::from("NO ANSWER"));
+            return Ok(chat_response.into_first_text().unwrap_or_default());
         }
 
         let tool_call_count = tool_calls.len();
@@ -2408,10 +6908,11 @@ async fn execute_chat_with_skills(
             skills::MAX_TOOL_ROUNDS,
             tool_call_count
         );
-        send_or_empty!(
-            tx,
-            Progress::Status(format!("Loading {tool_call_count} skill(s) for query generation..."))
-        );
+        tx.send(Progress::Status(format!(
+            "Loading {tool_call_count} skill(s) for query generation..."
+        )))
+        .await
+        .map_err(|e| e.to_string())?;
 
         let tool_responses = skills::resolve_skill_tool_calls(&tool_calls, skill_catalog);
         genai_request = genai_request.append_message(GenAiChatMessage::from(tool_calls));
@@ -2423,52 +6924,56 @@ async fn execute_chat_with_skills(
 
     // Final attempt after exhausting tool rounds
     genai_request.tools = None;
-    match client.exec_chat(model, genai_request, None).await {
-        Ok(response) => {
-            token_usage.add_genai_usage(&response.usage);
-            response.into_first_text().unwrap_or_else(|| String::from("NO ANSWER"))
-        }
-        Err(e) => {
-            let error_update = Progress::Error(format!("Chat request failed after tool rounds: {e}"));
-            send_or_empty!(tx, Progress::Usage(*token_usage));
-            send_or_empty!(tx, error_update);
-            String::from("NO ANSWER")
-        }
-    }
+    let response = client
+        .exec_chat(model, genai_request, Some(&chat_options))
+        .await
+        .map_err(|e| format!("Chat request failed after tool rounds: {e}"))?;
+    token_usage.add_genai_usage(model, &response.usage);
+    Ok(response.into_first_text().unwrap_or_default())
 }
 
 async fn execute_chat_stream(
     client: &genai::Client,
     model: &str,
+    options: &ModelOptions,
     genai_chat_request: genai::chat::ChatRequest,
-    tx: &mpsc::Sender<sse::Event>,
+    tx: &ProgressSender,
     token_usage: &mut TokenUsage,
-) -> String {
+) -> Option<(String, Option<u8>)> {
     // Enable usage capture so the StreamEnd event carries token counts.
-    let options = genai::chat::ChatOptions::default().with_capture_usage(true);
+    let chat_options = options.chat_options().with_capture_usage(true);
+    let started = std::time::Instant::now();
+    debug_bundle::record(|trace| trace.begin_model_call("answer", &genai_chat_request));
 
     // Make the actual request to the model
-    let chat_response = match client.exec_chat_stream(model, genai_chat_request, Some(&options)).await {
+    let chat_response = match client.exec_chat_stream(model, genai_chat_request, Some(&chat_options)).await {
         Ok(response) => response,
         Err(e) => {
             // Report usage accumulated so far before signalling the terminal error,
             // so consumers that treat Error as terminal still receive the usage.
-            send_or_empty!(tx, Progress::Usage(*token_usage));
+            tx.send(Progress::Usage(token_usage.clone())).await.ok()?;
             let error_update = Progress::Error(format!("Chat request failed: {e}"));
-            send_or_empty!(tx, error_update);
-            return String::new();
+            tx.send(error_update).await.ok()?;
+            return None;
         }
     };
 
-    process_chat_stream(chat_response, tx, token_usage).await
+    let output = process_chat_stream(chat_response, model, tx, token_usage).await;
+    debug_bundle::record(|trace| {
+        trace.finish_model_call(output.as_ref().map(|(answer, _)| answer.as_str()), started.elapsed());
+    });
+    output
 }
 
+/// Streams the answer as `ModelOutputChunk` events and returns the clean answer with its parsed
+/// confidence. The caller emits the terminal `Usage`/`Confidence`/`Result` events.
 #[allow(clippy::cognitive_complexity)]
 async fn process_chat_stream(
     chat_response: genai::chat::ChatStreamResponse,
-    tx: &mpsc::Sender<sse::Event>,
+    model: &str,
+    tx: &ProgressSender,
     token_usage: &mut TokenUsage,
-) -> String {
+) -> Option<(String, Option<u8>)> {
     // Number of trailing bytes withheld from live streaming so a trailing
     // `CONFIDENCE: <0-100>` marker is never surfaced to the client mid-stream.
     const HOLD_BYTES: usize = 48;
@@ -2483,9 +6988,9 @@ async fn process_chat_stream(
             Ok(event) => event,
             Err(e) => {
                 tracing::error!("Streaming answer failed: {}", e);
-                send_or_empty!(tx, Progress::Usage(*token_usage));
-                send_or_empty!(tx, Progress::Error(format!("Answer streaming failed: {e}")));
-                return String::new();
+                tx.send(Progress::Usage(token_usage.clone())).await.ok()?;
+                tx.send(Progress::Error(format!("Answer streaming failed: {e}"))).await.ok()?;
+                return None;
             }
         };
         match stream_event {
@@ -2495,15 +7000,14 @@ async fn process_chat_stream(
                 // Stream everything except the last HOLD_BYTES so the marker,
                 // which may be split across chunks, is caught before emission.
                 let safe_end = floor_char_boundary(&full, full.len().saturating_sub(HOLD_BYTES));
-                if safe_end > sent {
-                    send_or_empty!(tx, Progress::ModelOutputChunk(full[sent..safe_end].to_string()));
+                if safe_end > sent && tx.try_chunk(&full[sent..safe_end]).ok()? {
                     sent = safe_end;
                 }
             }
             genai::chat::ChatStreamEvent::ReasoningChunk(_chunk) => {}
             genai::chat::ChatStreamEvent::End(end_event) => {
                 if let Some(usage) = end_event.captured_usage.as_ref() {
-                    token_usage.add_genai_usage(usage);
+                    token_usage.add_genai_usage(model, usage);
                 }
             }
             genai::chat::ChatStreamEvent::ThoughtSignatureChunk(_stream_chunk) => {}
@@ -2513,21 +7017,15 @@ async fn process_chat_stream(
 
     let (answer, confidence) = ::text_to_cypher::core::parse_answer_confidence(&full);
 
-    // Flush any remaining clean answer text that was held back during streaming.
+    // Flush any remaining clean answer text that was held back during streaming, including chunks
+    // coalesced while the client was slow.
     let start = floor_char_boundary(&answer, sent.min(answer.len()));
     if start < answer.len() {
-        send_or_empty!(tx, Progress::ModelOutputChunk(answer[start..].to_string()));
+        tx.chunk(&answer[start..]).await.ok()?;
     }
 
     tracing::info!("Final answer: {} (confidence: {:?})", answer, confidence);
-    // Emit the aggregated token usage before the terminal Result event so consumers
-    // that treat Result as terminal still receive the usage.
-    send_or_empty!(tx, Progress::Usage(*token_usage));
-    if let Some(confidence) = confidence {
-        send_or_empty!(tx, Progress::Confidence(confidence));
-    }
-    send_or_empty!(tx, Progress::Result(answer.clone()));
-    answer
+    Some((answer, confidence))
 }
 
 /// Returns the largest byte index `<= index` that lies on a UTF-8 char boundary.
@@ -1,5 +1,8 @@
+use std::sync::Arc;
 use std::time::Duration;
 
+use rust_mcp_sdk::TransportOptions;
+use rust_mcp_sdk::event_store::{EventStore, InMemoryEventStore};
 use rust_mcp_sdk::mcp_server::{HyperServerOptions, hyper_server};
 
 use crate::mcp::server_handler::MyServerHandler;
@@ -10,12 +13,89 @@ use rust_mcp_sdk::schema::{
 
 use rust_mcp_sdk::error::SdkResult;
 
-/// Run the MCP server.
+/// Connection handling of the MCP server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct McpServerOptions {
+    /// Interval between pings that detect dead connections.
+    pub ping_interval: Duration,
+    /// How long a request may take before it times out.
+    pub request_timeout: Duration,
+    /// Messages kept per session and replayed to a client that reconnects with `Last-Event-ID`;
+    /// 0 disables session resumption.
+    pub replay_events: usize,
+    /// How long the result of a tool call made with a `request_id` is kept for a repeated call.
+    pub result_ttl: Duration,
+}
+
+impl Default for McpServerOptions {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(300),
+            replay_events: 64,
+            result_ttl: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl McpServerOptions {
+    /// Builds options from their settings (seconds, or a message count for `replay_events`),
+    /// using the default for each one that is unset.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first setting that is not a number, or is 0 where a duration is
+    /// expected.
+    pub fn parse(
+        ping_interval: Option<&str>,
+        request_timeout: Option<&str>,
+        replay_events: Option<&str>,
+        result_ttl: Option<&str>,
+    ) -> Result<Self, String> {
+        fn seconds(
+            name: &str,
+            value: Option<&str>,
+            default: Duration,
+        ) -> Result<Duration, String> {
+            match value.map(str::trim) {
+                None | Some("") => Ok(default),
+                Some(value) => value
+                    .parse()
+                    .ok()
+                    .filter(|secs| *secs > 0)
+                    .map(Duration::from_secs)
+                    .ok_or_else(|| format!("{name} '{value}' must be a positive number of seconds")),
+            }
+        }
+
+        let defaults = Self::default();
+        let replay_events = match replay_events.map(str::trim) {
+            None | Some("") => defaults.replay_events,
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("replay event count '{value}' must be a number"))?,
+        };
+        Ok(Self {
+            ping_interval: seconds("ping interval", ping_interval, defaults.ping_interval)?,
+            request_timeout: seconds("request timeout", request_timeout, defaults.request_timeout)?,
+            replay_events,
+            result_ttl: seconds("result TTL", result_ttl, defaults.result_ttl)?,
+        })
+    }
+}
+
+/// Run the MCP server. Its tools forward to the REST API at `rest_url`, e.g.
+/// `http://127.0.0.1:8080`.
 ///
 /// # Errors
 ///
 /// Returns an error if the server fails to start or encounters a runtime error.
-pub async fn run_mcp_server(port: u16) -> SdkResult<()> {
+pub async fn run_mcp_server(
+    host: &str,
+    port: u16,
+    rest_url: String,
+    options: McpServerOptions,
+) -> SdkResult<()> {
     // Note: Tracing is already initialized in main, no need to initialize it again
 
     // STEP 1: Define server details and capabilities
@@ -62,18 +142,23 @@ Example: First check resources, then ask 'Who are all the people?' for a social
     };
 
     // STEP 2: instantiate our custom handler for handling MCP messages
-    let handler = MyServerHandler {};
+    let handler = MyServerHandler::new(rest_url, options.result_ttl);
 
     // STEP 3: instantiate HyperServer, providing `server_details` , `handler` and HyperServerOptions
-    tracing::info!("Starting MCP server on 0.0.0.0:{}", port);
+    tracing::info!("Starting MCP server on {}:{}", host, port);
 
     let server = hyper_server::create_server(
         server_details,
         handler,
         HyperServerOptions {
-            host: "0.0.0.0".to_string(),
+            host: host.to_string(),
             port,
-            ping_interval: Duration::from_secs(5),
+            ping_interval: options.ping_interval,
+            transport_options: Arc::new(TransportOptions {
+                timeout: options.request_timeout,
+            }),
+            event_store: (options.replay_events > 0)
+                .then(|| Arc::new(InMemoryEventStore::new(Some(options.replay_events))) as Arc<dyn EventStore>),
             ..Default::default()
         },
     );
@@ -82,3 +167,23 @@ Example: First check resources, then ask 'Who are all the people?' for a social
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_options_with_defaults() {
+        assert_eq!(
+            McpServerOptions::parse(None, None, None, None).unwrap(),
+            McpServerOptions::default()
+        );
+        let options = McpServerOptions::parse(Some("10"), Some(" 600 "), Some("0"), Some("")).unwrap();
+        assert_eq!(options.ping_interval, Duration::from_secs(10));
+        assert_eq!(options.request_timeout, Duration::from_secs(600));
+        assert_eq!(options.replay_events, 0);
+        assert_eq!(options.result_ttl, McpServerOptions::default().result_ttl);
+        assert!(McpServerOptions::parse(Some("0"), None, None, None).is_err());
+        assert!(McpServerOptions::parse(None, Some("soon"), None, None).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 pub mod mcp_server;
+pub mod resumable;
 pub mod server_handler;
 pub mod tools;
 
-pub use mcp_server::run_mcp_server;
+pub use mcp_server::{McpServerOptions, run_mcp_server};
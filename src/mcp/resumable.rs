@@ -0,0 +1,121 @@
+//! Tool-call results that survive a dropped connection.
+//!
+//! A `talk_with_a_graph` call made with a `request_id` runs in its own task, and its result is kept
+//! under that ID for a while. If the connection drops mid-call, the client reconnects and repeats
+//! the call with the same ID: it joins the original run, or gets its result straight away, instead
+//! of starting the question over. Failed calls are not kept, so repeating one runs it again.
+
+use moka::sync::Cache;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// What a tool call produced: its text result, or why it failed.
+pub type CallResult = Result<String, String>;
+
+/// Maximum number of tool calls tracked at once.
+const MAX_TRACKED_CALLS: u64 = 1_000;
+
+/// Tool calls by request ID. See the [module documentation](self).
+#[derive(Clone)]
+pub struct ResumableCalls {
+    calls: Cache<String, watch::Receiver<Option<CallResult>>>,
+}
+
+impl ResumableCalls {
+    /// Keeps results for `ttl` after a call starts.
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            calls: Cache::builder().time_to_live(ttl).max_capacity(MAX_TRACKED_CALLS).build(),
+        }
+    }
+
+    /// Runs `call` under `request_id`, or waits for the call already started under it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the call's error, or an error if the task running it panicked.
+    pub async fn run<F>(
+        &self,
+        request_id: &str,
+        call: F,
+    ) -> CallResult
+    where
+        F: Future<Output = CallResult> + Send + 'static,
+    {
+        let mut started = None;
+        let mut rx = self.calls.get_with(request_id.to_string(), || {
+            let (tx, rx) = watch::channel(None);
+            started = Some(tx);
+            rx
+        });
+        if let Some(tx) = started {
+            let (calls, request_id) = (self.calls.clone(), request_id.to_string());
+            tokio::spawn(async move {
+                let result = call.await;
+                if result.is_err() {
+                    calls.invalidate(&request_id);
+                }
+                let _ = tx.send(Some(result));
+            });
+        } else {
+            tracing::info!("Resuming tool call {}", request_id);
+        }
+
+        let result = rx.wait_for(Option::is_some).await.map(|result| result.clone());
+        result
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| Err("The tool call was interrupted".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn counted(
+        runs: &Arc<AtomicUsize>,
+        result: CallResult,
+    ) -> impl Future<Output = CallResult> + Send + 'static {
+        let runs = runs.clone();
+        async move {
+            runs.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            result
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_calls_join_the_first_run() {
+        let calls = ResumableCalls::new(Duration::from_secs(60));
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let (first, second) = tokio::join!(
+            calls.run("r1", counted(&runs, Ok("42".into()))),
+            calls.run("r1", counted(&runs, Ok("other".into())))
+        );
+        assert_eq!((first.as_deref(), second.as_deref()), (Ok("42"), Ok("42")));
+        assert_eq!(
+            calls.run("r1", counted(&runs, Ok("later".into()))).await.as_deref(),
+            Ok("42")
+        );
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_calls_run_again() {
+        let calls = ResumableCalls::new(Duration::from_secs(60));
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        assert!(calls.run("r1", counted(&runs, Err("timeout".into()))).await.is_err());
+        assert_eq!(
+            calls.run("r1", counted(&runs, Ok("42".into()))).await.as_deref(),
+            Ok("42")
+        );
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,6 +1,10 @@
+use crate::aliases::GraphAlias;
 use crate::chat::{ChatMessage, ChatRequest, ChatRole};
+use crate::mcp::resumable::ResumableCalls;
 use crate::mcp::tools::TextToCypherTool;
+use crate::streaming::{Progress, Warning};
 use crate::usage::TokenUsage;
+use crate::validator::LintHint;
 use async_trait::async_trait;
 use futures_util::StreamExt;
 use rust_mcp_sdk::schema::TextContent;
@@ -13,7 +17,26 @@ use std::fmt::Write;
 use std::sync::Arc;
 
 // Custom Handler to handle MCP Messages
-pub struct MyServerHandler;
+pub struct MyServerHandler {
+    calls: ResumableCalls,
+    /// Base URL of the REST API the tools forward to, e.g. `http://127.0.0.1:8080`.
+    rest_url: String,
+}
+
+impl MyServerHandler {
+    /// Creates a handler that forwards to the REST API at `rest_url` and keeps tool-call results
+    /// for `result_ttl`.
+    #[must_use]
+    pub fn new(
+        rest_url: String,
+        result_ttl: std::time::Duration,
+    ) -> Self {
+        Self {
+            calls: ResumableCalls::new(result_ttl),
+            rest_url,
+        }
+    }
+}
 
 #[async_trait]
 impl ServerHandler for MyServerHandler {
@@ -37,21 +60,13 @@ impl ServerHandler for MyServerHandler {
     ) -> std::result::Result<ListResourcesResult, RpcError> {
         tracing::info!("Handling List Resources Request");
 
-        match get_falkordb_graphs().await {
+        match get_falkordb_graphs(&self.rest_url).await {
             Ok(graphs) => {
-                let resources: Vec<Resource> = graphs
-                    .into_iter()
-                    .map(|graph_name| Resource {
-                        uri: format!("falkordb://graph/{graph_name}"),
-                        name: format!("Graph: {graph_name}"),
-                        description: Some(format!("FalkorDB graph database: {graph_name}")),
-                        mime_type: Some("application/json".to_string()),
-                        annotations: None,
-                        meta: None,
-                        size: None,
-                        title: None,
-                    })
-                    .collect();
+                let aliases = get_graph_aliases(&self.rest_url).await.unwrap_or_else(|e| {
+                    tracing::warn!("Failed to list graph aliases, listing graph keys only: {}", e);
+                    Vec::new()
+                });
+                let resources = graph_resources(graphs, &aliases);
 
                 Ok(ListResourcesResult {
                     meta: None,
@@ -75,7 +90,7 @@ impl ServerHandler for MyServerHandler {
 
         // Parse the URI to extract graph name
         if let Some(graph_name) = request.params.uri.strip_prefix("falkordb://graph/") {
-            match get_graph_schema_via_api(graph_name).await {
+            match get_graph_schema_via_api(&self.rest_url, graph_name).await {
                 Ok(schema_info) => {
                     let text_content = TextResourceContents {
                         uri: request.params.uri,
@@ -113,11 +128,23 @@ impl ServerHandler for MyServerHandler {
             match serde_json::from_value::<TextToCypherTool>(arguments_value.clone()) {
                 Ok(tool_args) => {
                     tracing::info!("TextToCypherTool called with arguments:");
-                    tracing::info!("  graph_name: {}", tool_args.graph_name);
+                    tracing::info!(
+                        "  graph_name: {}",
+                        tool_args.graph_name.as_deref().unwrap_or("(routed)")
+                    );
                     tracing::info!("  question: {}", tool_args.question);
-
-                    // Forward the request to the HTTP endpoint
-                    match forward_to_http_endpoint(tool_args).await {
+                    let request_id = tool_args.request_id.clone();
+
+                    // Forward the request to the HTTP endpoint; a call with a request ID survives
+                    // the connection and can be resumed by repeating it.
+                    let rest_url = self.rest_url.clone();
+                    let forward =
+                        async move { forward_to_http_endpoint(&rest_url, tool_args).await.map_err(|e| e.to_string()) };
+                    let result = match request_id {
+                        Some(request_id) => self.calls.run(&request_id, forward).await,
+                        None => forward.await,
+                    };
+                    match result {
                         Ok(result) => Ok(CallToolResult::text_content(vec![TextContent::from(result)])),
                         Err(e) => {
                             tracing::error!("Failed to forward request to HTTP endpoint: {}", e);
@@ -146,10 +173,11 @@ impl ServerHandler for MyServerHandler {
 
 // Helper function to forward MCP tool request to HTTP endpoint
 async fn forward_to_http_endpoint(
-    tool_args: TextToCypherTool
+    rest_url: &str,
+    tool_args: TextToCypherTool,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     let http_request = create_http_request_payload(tool_args);
-    let response = send_http_request(&http_request).await?;
+    let response = send_http_request(rest_url, &http_request).await?;
     process_sse_response(response).await
 }
 
@@ -163,7 +191,7 @@ fn create_http_request_payload(tool_args: TextToCypherTool) -> serde_json::Value
     };
 
     serde_json::json!({
-        "graph_name": tool_args.graph_name,
+        "graph_name": tool_args.graph_name.unwrap_or_default(),
         "chat_request": chat_request,
         "model": null,
         "key": null
@@ -172,11 +200,12 @@ fn create_http_request_payload(tool_args: TextToCypherTool) -> serde_json::Value
 
 // Send HTTP request to the text-to-cypher endpoint
 async fn send_http_request(
-    http_request: &serde_json::Value
+    rest_url: &str,
+    http_request: &serde_json::Value,
 ) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
     let client = reqwest::Client::new();
     let response = client
-        .post("http://127.0.0.1:8080/text_to_cypher")
+        .post(format!("{rest_url}/text_to_cypher"))
         .header("Content-Type", "application/json")
         .json(http_request)
         .send()
@@ -197,19 +226,21 @@ async fn process_sse_response(response: reqwest::Response) -> Result<String, Box
     let mut token_usage: Option<TokenUsage> = None;
     let mut confidence: Option<u8> = None;
 
-    while let Some(chunk_result) = stream.next().await {
+    'stream: while let Some(chunk_result) = stream.next().await {
         let chunk = chunk_result?;
         let chunk_str = String::from_utf8_lossy(&chunk);
 
         for line in chunk_str.lines() {
-            if let Some(data) = line.strip_prefix("data: ") {
-                process_sse_event(
+            if let Some(data) = line.strip_prefix("data: ")
+                && process_sse_event(
                     data,
                     &mut result_buffer,
                     &mut final_result,
                     &mut token_usage,
                     &mut confidence,
-                )?;
+                )?
+            {
+                break 'stream;
             }
         }
     }
@@ -222,42 +253,72 @@ async fn process_sse_response(response: reqwest::Response) -> Result<String, Box
     ))
 }
 
-// Process individual SSE event
+// Process individual SSE event; returns true for the terminal `Done` event
+//
+// Events are decoded into the `Progress` the server streamed, and every variant is matched by
+// name: a new variant fails to compile here until the forwarder decides what to do with it.
 fn process_sse_event(
     data: &str,
     result_buffer: &mut String,
     final_result: &mut String,
     token_usage: &mut Option<TokenUsage>,
     confidence: &mut Option<u8>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    if let Ok(progress) = serde_json::from_str::<serde_json::Value>(data)
-        && let Some(event_type) = progress.as_object().and_then(|obj| obj.keys().next())
-    {
-        match event_type.as_str() {
-            "Status" => handle_status_event(&progress, result_buffer),
-            "Schema" => handle_schema_event(result_buffer),
-            "CypherQuery" => handle_cypher_query_event(&progress, result_buffer),
-            "CypherResult" => handle_cypher_result_event(&progress, result_buffer),
-            "ModelOutputChunk" => handle_model_output_chunk(&progress, final_result),
-            "Result" => handle_result_event(&progress, final_result),
-            "Confidence" => handle_confidence_event(&progress, confidence),
-            "Usage" => handle_usage_event(&progress, token_usage),
-            "Error" => return handle_error_event(&progress),
-            _ => tracing::debug!("Unknown event type: {}", event_type),
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let progress = match serde_json::from_str::<Progress>(data) {
+        Ok(progress) => progress,
+        Err(e) => {
+            tracing::warn!("Skipping an SSE event that is not a progress event: {}", e);
+            return Ok(false);
         }
+    };
+    match progress {
+        Progress::RequestId(request_id) => tracing::debug!("Request ID: {}", request_id),
+        Progress::Status(status) => handle_status_event(&status, result_buffer),
+        Progress::Graph(graph_name) => handle_graph_event(&graph_name, result_buffer),
+        Progress::Warning(warning) => handle_warning_event(&warning, result_buffer),
+        Progress::Schema(_) => handle_schema_event(result_buffer),
+        Progress::CypherQuery(query) => handle_cypher_query_event(&query, result_buffer),
+        Progress::Lint(hints) => handle_lint_event(&hints, result_buffer),
+        Progress::QueryPlan(plan) => handle_query_plan_event(&plan, result_buffer),
+        Progress::CypherResult(cypher_result) => handle_cypher_result_event(&cypher_result, result_buffer),
+        Progress::ModelOutputChunk(chunk) => final_result.push_str(&chunk),
+        Progress::Result(result) => handle_result_event(result, final_result),
+        Progress::Confidence(value) => handle_confidence_event(value, confidence),
+        Progress::Followups(questions) => handle_followups_event(&questions, result_buffer),
+        Progress::DryRun(dry_run) => handle_query_plan_event(&dry_run.plan, result_buffer),
+        Progress::Usage(usage) => handle_usage_event(usage, token_usage),
+        // Only CSV uploads stream these, never a text-to-cypher request
+        Progress::ImportBatch(_) | Progress::ImportSummary(_) => {}
+        Progress::NoAnswer(reason) => handle_no_answer_event(&reason, final_result),
+        Progress::Error(error) => return Err(handle_error_event(&error)),
+        Progress::Done { .. } => return Ok(true),
     }
-    Ok(())
+    Ok(false)
 }
 
 // Handle different types of SSE events
 fn handle_status_event(
-    progress: &serde_json::Value,
+    status: &str,
     result_buffer: &mut String,
 ) {
-    if let Some(status) = progress.get("Status").and_then(|v| v.as_str()) {
-        tracing::info!("Status: {}", status);
-        writeln!(result_buffer, "Status: {status}").unwrap();
-    }
+    tracing::info!("Status: {}", status);
+    writeln!(result_buffer, "Status: {status}").unwrap();
+}
+
+fn handle_graph_event(
+    graph_name: &str,
+    result_buffer: &mut String,
+) {
+    tracing::info!("Routed to graph: {}", graph_name);
+    writeln!(result_buffer, "Graph: {graph_name}").unwrap();
+}
+
+fn handle_warning_event(
+    warning: &Warning,
+    result_buffer: &mut String,
+) {
+    tracing::info!("Warning: {}", warning);
+    writeln!(result_buffer, "Warning: {warning}").unwrap();
 }
 
 fn handle_schema_event(result_buffer: &mut String) {
@@ -266,79 +327,87 @@ fn handle_schema_event(result_buffer: &mut String) {
 }
 
 fn handle_cypher_query_event(
-    progress: &serde_json::Value,
+    query: &str,
+    result_buffer: &mut String,
+) {
+    tracing::info!("Generated Cypher: {}", query);
+    writeln!(result_buffer, "Cypher Query: {query}").unwrap();
+}
+
+fn handle_lint_event(
+    hints: &[LintHint],
     result_buffer: &mut String,
 ) {
-    if let Some(query) = progress.get("CypherQuery").and_then(|v| v.as_str()) {
-        tracing::info!("Generated Cypher: {}", query);
-        writeln!(result_buffer, "Cypher Query: {query}").unwrap();
+    for hint in hints {
+        writeln!(result_buffer, "Lint: {}", hint.message).unwrap();
     }
 }
 
+fn handle_query_plan_event(
+    plan: &[String],
+    result_buffer: &mut String,
+) {
+    writeln!(result_buffer, "Query Plan:\n{}", plan.join("\n")).unwrap();
+}
+
 fn handle_cypher_result_event(
-    progress: &serde_json::Value,
+    cypher_result: &str,
     result_buffer: &mut String,
 ) {
-    if let Some(cypher_result) = progress.get("CypherResult").and_then(|v| v.as_str()) {
-        tracing::info!("Cypher result: {}", cypher_result);
-        writeln!(result_buffer, "Query Result: {cypher_result}").unwrap();
-    }
+    tracing::info!("Cypher result: {}", cypher_result);
+    writeln!(result_buffer, "Query Result: {cypher_result}").unwrap();
 }
 
-fn handle_model_output_chunk(
-    progress: &serde_json::Value,
+fn handle_result_event(
+    result: String,
     final_result: &mut String,
 ) {
-    if let Some(chunk) = progress.get("ModelOutputChunk").and_then(|v| v.as_str()) {
-        final_result.push_str(chunk);
-    }
+    tracing::info!("Final result received");
+    *final_result = result;
 }
 
-fn handle_result_event(
-    progress: &serde_json::Value,
+fn handle_no_answer_event(
+    reason: &str,
     final_result: &mut String,
 ) {
-    if let Some(result) = progress.get("Result").and_then(|v| v.as_str()) {
-        tracing::info!("Final result received");
-        *final_result = result.to_string();
-    }
+    tracing::info!("No query generated: {}", reason);
+    *final_result = format!("No answer: {reason}");
 }
 
 fn handle_confidence_event(
-    progress: &serde_json::Value,
+    value: u8,
     confidence: &mut Option<u8>,
 ) {
-    if let Some(value) = progress.get("Confidence").and_then(serde_json::Value::as_u64) {
-        let value = u8::try_from(value.min(100)).unwrap_or(100);
-        tracing::info!("Answer confidence: {}", value);
-        *confidence = Some(value);
-    }
+    let value = value.min(100);
+    tracing::info!("Answer confidence: {}", value);
+    *confidence = Some(value);
 }
 
-fn handle_error_event(progress: &serde_json::Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    if let Some(error) = progress.get("Error").and_then(|v| v.as_str()) {
-        tracing::error!("Error from HTTP endpoint: {}", error);
-        return Err(format!("Error from text-to-cypher service: {error}").into());
+fn handle_followups_event(
+    questions: &[String],
+    result_buffer: &mut String,
+) {
+    for question in questions {
+        writeln!(result_buffer, "Follow-up: {question}").unwrap();
     }
-    Ok(())
+}
+
+fn handle_error_event(error: &str) -> Box<dyn std::error::Error + Send + Sync> {
+    tracing::error!("Error from HTTP endpoint: {}", error);
+    format!("Error from text-to-cypher service: {error}").into()
 }
 
 fn handle_usage_event(
-    progress: &serde_json::Value,
+    usage: TokenUsage,
     token_usage: &mut Option<TokenUsage>,
 ) {
-    if let Some(usage) = progress
-        .get("Usage")
-        .and_then(|v| serde_json::from_value::<TokenUsage>(v.clone()).ok())
-    {
-        tracing::info!(
-            "Token usage: prompt={}, completion={}, total={}",
-            usage.prompt_tokens,
-            usage.completion_tokens,
-            usage.total_tokens
-        );
-        *token_usage = Some(usage);
-    }
+    tracing::info!(
+        "Token usage: prompt={}, completion={}, total={}",
+        usage.prompt_tokens,
+        usage.completion_tokens,
+        usage.total_tokens
+    );
+    *token_usage = Some(usage);
 }
 
 // Build the complete response from buffer and final result
@@ -371,11 +440,11 @@ fn build_complete_response(
 }
 
 // Helper function to get list of graphs from FalkorDB via REST API
-async fn get_falkordb_graphs() -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+async fn get_falkordb_graphs(rest_url: &str) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
     // Call the local REST API endpoint
     let client = reqwest::Client::new();
     let response = client
-        .get("http://localhost:8080/list_graphs")
+        .get(format!("{rest_url}/list_graphs"))
         .send()
         .await
         .map_err(|e| format!("Failed to call list_graphs API: {e}"))?;
@@ -388,12 +457,58 @@ async fn get_falkordb_graphs() -> Result<Vec<String>, Box<dyn std::error::Error
     }
 }
 
+// Builds one resource per graph. Aliased graphs are listed under their alias and description, so
+// agents see the friendly name instead of the physical key.
+fn graph_resources(
+    graphs: Vec<String>,
+    aliases: &[GraphAlias],
+) -> Vec<Resource> {
+    graphs
+        .into_iter()
+        .map(|graph_name| {
+            let alias = aliases.iter().find(|alias| alias.graph == graph_name);
+            let name = alias.map_or_else(|| graph_name.clone(), |alias| alias.alias.clone());
+            let description = alias
+                .and_then(|alias| alias.description.clone())
+                .unwrap_or_else(|| format!("FalkorDB graph database: {name}"));
+            Resource {
+                uri: format!("falkordb://graph/{name}"),
+                name: format!("Graph: {name}"),
+                description: Some(description),
+                mime_type: Some("application/json".to_string()),
+                annotations: None,
+                meta: None,
+                size: None,
+                title: None,
+            }
+        })
+        .collect()
+}
+
+// Helper function to get the configured graph aliases via REST API
+async fn get_graph_aliases(rest_url: &str) -> Result<Vec<GraphAlias>, Box<dyn std::error::Error + Send + Sync>> {
+    let response = reqwest::Client::new()
+        .get(format!("{rest_url}/graph_aliases"))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to call graph_aliases API: {e}"))?;
+
+    if response.status().is_success() {
+        Ok(response.json().await.map_err(|e| format!("Failed to parse response: {e}"))?)
+    } else {
+        Err(format!("API returned error status: {}", response.status()).into())
+    }
+}
+
 // Helper function to get schema information for a specific graph via REST API
-async fn get_graph_schema_via_api(graph_name: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+async fn get_graph_schema_via_api(
+    rest_url: &str,
+    graph_name: &str,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     // Call the local REST API endpoint
     let client = reqwest::Client::new();
     let response = client
-        .get(format!("http://localhost:8080/get_schema/{graph_name}"))
+        .get(format!("{rest_url}/get_schema/{graph_name}"))
         .send()
         .await
         .map_err(|e| format!("Failed to call get_schema API: {e}"))?;
@@ -409,6 +524,7 @@ async fn get_graph_schema_via_api(graph_name: &str) -> Result<String, Box<dyn st
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::streaming::WarningCode;
 
     // Feeds the SSE events a real run emits and returns the assembled MCP response.
     fn assemble(events: &[&str]) -> String {
@@ -444,4 +560,69 @@ mod tests {
         let clamped = assemble(&[r#"{"Result":"An answer."}"#, r#"{"Confidence":250}"#]);
         assert!(clamped.contains("Confidence: 100%"));
     }
+
+    #[test]
+    fn no_answer_event_becomes_the_final_answer() {
+        let response = assemble(&[r#"{"NoAnswer":"The graph has no salary data"}"#]);
+        assert!(response.contains("Final Answer:\nNo answer: The graph has no salary data"));
+    }
+
+    #[test]
+    fn done_event_ends_the_stream() {
+        let (mut result_buffer, mut final_result) = (String::new(), String::new());
+        let (mut token_usage, mut confidence) = (None, None);
+        let mut feed = |data| {
+            process_sse_event(
+                data,
+                &mut result_buffer,
+                &mut final_result,
+                &mut token_usage,
+                &mut confidence,
+            )
+            .unwrap()
+        };
+        assert!(!feed(r#"{"Result":"42"}"#));
+        assert!(feed(r#"{"Done":{"status":"success","request_id":"r1"}}"#));
+        assert_eq!(final_result, "42");
+    }
+
+    #[test]
+    fn streamed_progress_round_trips_into_the_response() {
+        let events = [
+            Progress::Status("Executing Cypher query...".to_string()),
+            Progress::Warning(Warning::new(WarningCode::ValidationHint, "Query has no LIMIT")),
+            Progress::CypherQuery("MATCH (c:City) RETURN c.name".to_string()),
+            Progress::Followups(vec!["Which city is largest?".to_string()]),
+            Progress::Result("A, B".to_string()),
+        ];
+        let data: Vec<String> = events.iter().map(|event| serde_json::to_string(event).unwrap()).collect();
+        let response = assemble(&data.iter().map(String::as_str).collect::<Vec<_>>());
+        assert!(response.contains("Status: Executing Cypher query..."));
+        assert!(response.contains("Warning: Query has no LIMIT"));
+        assert!(response.contains("Cypher Query: MATCH (c:City) RETURN c.name"));
+        assert!(response.contains("Follow-up: Which city is largest?"));
+        assert!(response.ends_with("Final Answer:\nA, B"));
+
+        let error = serde_json::to_string(&Progress::Error("Graph 'x' not found.".to_string())).unwrap();
+        let (mut result_buffer, mut final_result) = (String::new(), String::new());
+        let failed = process_sse_event(&error, &mut result_buffer, &mut final_result, &mut None, &mut None);
+        assert!(failed.unwrap_err().to_string().contains("Graph 'x' not found."));
+    }
+
+    #[test]
+    fn aliased_graphs_are_listed_by_alias() {
+        let aliases = vec![GraphAlias {
+            alias: "sales".to_string(),
+            graph: "crm_prod_v2".to_string(),
+            description: Some("Accounts and deals".to_string()),
+        }];
+        let resources = graph_resources(vec!["crm_prod_v2".to_string(), "social".to_string()], &aliases);
+        assert_eq!(resources[0].uri, "falkordb://graph/sales");
+        assert_eq!(resources[0].description.as_deref(), Some("Accounts and deals"));
+        assert_eq!(resources[1].uri, "falkordb://graph/social");
+        assert_eq!(
+            resources[1].description.as_deref(),
+            Some("FalkorDB graph database: social")
+        );
+    }
 }
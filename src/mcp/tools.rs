@@ -39,12 +39,14 @@ pub struct TextToCypherTool {
     ///
     /// IMPORTANT: Always check available resources first to see what graphs exist!
     ///
-    /// Required: Yes
+    /// Leave it empty when unsure which graph holds the data: the server then chooses the graph
+    /// the question is about and reports it as "Graph: ..." in the result.
+    ///
+    /// Required: No
     /// Type: String
-    /// Min length: 1
     /// Max length: 100
-    #[serde(rename = "graph_name")]
-    pub graph_name: String,
+    #[serde(rename = "graph_name", default)]
+    pub graph_name: Option<String>,
 
     /// Natural language question to be converted to Cypher and answered using the graph data
     ///
@@ -73,4 +75,15 @@ pub struct TextToCypherTool {
     /// Min length: 5
     /// Max length: 1000
     pub question: String,
+
+    /// Optional client-chosen ID that makes the call resumable
+    ///
+    /// If the connection drops before the answer arrives, repeat the call with the same
+    /// `request_id` to receive the answer of the original run instead of asking again. Answers
+    /// are kept for 15 minutes by default. Use a new ID (for example a UUID) for every question.
+    ///
+    /// Required: No
+    /// Type: String
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
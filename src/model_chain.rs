@@ -0,0 +1,162 @@
+//! Per-request model fallback.
+//!
+//! A request's `model` is one model name, or a list tried in order:
+//!
+//! ```json
+//! { "model": ["gpt-4o-mini", "anthropic:claude-3-haiku"] }
+//! ```
+//!
+//! When the model call writing the query fails (a rate limit, a timeout, a rejected key, an
+//! unavailable provider), the query is generated again with the next model of the list, which then
+//! serves the rest of the request. A question the model declines, or a query that fails validation,
+//! is not a model failure and does not fall back. Each fallback is reported as a `model_fallback`
+//! warning.
+
+use crate::streaming::{Warning, WarningCode};
+use serde::{Deserialize, Serialize};
+
+/// The models a request may use, in order of preference; never empty.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ModelList", into = "ModelList")]
+pub struct ModelChain {
+    models: Vec<String>,
+}
+
+/// A [`ModelChain`] as written in a request: one name, or a list.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ModelList {
+    One(String),
+    Chain(Vec<String>),
+}
+
+impl ModelChain {
+    /// A chain of `model` alone.
+    #[must_use]
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            models: vec![model.into()],
+        }
+    }
+
+    /// The chain with `model` tried after the models already in it.
+    #[must_use]
+    pub fn with_fallback(
+        mut self,
+        model: impl Into<String>,
+    ) -> Self {
+        self.models.push(model.into());
+        self
+    }
+
+    /// The model tried first.
+    #[must_use]
+    pub fn primary(&self) -> &str {
+        &self.models[0]
+    }
+
+    /// Drops the primary model after it failed, making the next model primary. Returns false, and
+    /// keeps the chain, when there is no model left to fall back to.
+    pub fn fall_back(&mut self) -> bool {
+        if self.models.len() < 2 {
+            return false;
+        }
+        self.models.remove(0);
+        true
+    }
+}
+
+impl From<String> for ModelChain {
+    fn from(model: String) -> Self {
+        Self::new(model)
+    }
+}
+
+impl From<&str> for ModelChain {
+    fn from(model: &str) -> Self {
+        Self::new(model)
+    }
+}
+
+impl TryFrom<ModelList> for ModelChain {
+    type Error = String;
+
+    fn try_from(list: ModelList) -> Result<Self, Self::Error> {
+        let models = match list {
+            ModelList::One(model) => vec![model],
+            ModelList::Chain(models) => models,
+        };
+        if models.is_empty() {
+            return Err("the model list is empty".to_string());
+        }
+        if models.iter().any(|model| model.trim().is_empty()) {
+            return Err("a model name is empty".to_string());
+        }
+        Ok(Self { models })
+    }
+}
+
+impl From<ModelChain> for ModelList {
+    fn from(mut chain: ModelChain) -> Self {
+        if chain.models.len() == 1 {
+            Self::One(chain.models.remove(0))
+        } else {
+            Self::Chain(chain.models)
+        }
+    }
+}
+
+impl std::fmt::Display for ModelChain {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(&self.models.join(", "))
+    }
+}
+
+/// The warning reporting that `failed` failed with `error` and `next` takes over.
+#[must_use]
+pub fn fallback_warning(
+    failed: &str,
+    error: &str,
+    next: &str,
+) -> Warning {
+    Warning::new(
+        WarningCode::ModelFallback,
+        format!("Model {failed} failed ({error}); falling back to {next}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_model_is_one_name_or_a_list() {
+        let one: ModelChain = serde_json::from_str(r#""gpt-4o-mini""#).unwrap();
+        assert_eq!(one, ModelChain::new("gpt-4o-mini"));
+        assert_eq!(serde_json::to_string(&one).unwrap(), r#""gpt-4o-mini""#);
+
+        let chain: ModelChain = serde_json::from_str(r#"["gpt-4o-mini", "anthropic:claude-3-haiku"]"#).unwrap();
+        assert_eq!(chain.primary(), "gpt-4o-mini");
+        assert_eq!(
+            chain,
+            ModelChain::new("gpt-4o-mini").with_fallback("anthropic:claude-3-haiku")
+        );
+        assert_eq!(
+            serde_json::to_string(&chain).unwrap(),
+            r#"["gpt-4o-mini","anthropic:claude-3-haiku"]"#
+        );
+        assert_eq!(chain.to_string(), "gpt-4o-mini, anthropic:claude-3-haiku");
+
+        let mut chain = chain;
+        assert!(chain.fall_back());
+        assert_eq!(chain.primary(), "anthropic:claude-3-haiku");
+        assert!(!chain.fall_back());
+        assert_eq!(chain, ModelChain::new("anthropic:claude-3-haiku"));
+
+        assert!(serde_json::from_str::<ModelChain>("[]").is_err());
+        assert!(serde_json::from_str::<ModelChain>(r#"["gpt-4o-mini", " "]"#).is_err());
+    }
+}
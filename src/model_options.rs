@@ -0,0 +1,134 @@
+//! Sampling parameters of the model calls that write a request's query and answer.
+//!
+//! ```json
+//! { "model_options": { "temperature": 0, "max_tokens": 1024, "top_p": 1 } }
+//! ```
+//!
+//! An unset parameter keeps the provider's default. A low temperature makes query generation close
+//! to deterministic, so a question gets the same query each time it is asked.
+
+use genai::chat::ChatOptions;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Temperature, output token limit and nucleus sampling of the generation and answer calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[serde(try_from = "Parameters")]
+pub struct ModelOptions {
+    /// Sampling temperature, from 0 (most deterministic) to 2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Most tokens a call may write (at least 1).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Nucleus sampling: the probability mass the tokens are drawn from, above 0 and at most 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+}
+
+/// [`ModelOptions`] as written in a request, before their ranges are checked.
+#[derive(Deserialize)]
+struct Parameters {
+    #[serde(default)]
+    temperature: Option<f64>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+    #[serde(default)]
+    top_p: Option<f64>,
+}
+
+impl ModelOptions {
+    /// The options with `temperature`.
+    #[must_use]
+    pub const fn with_temperature(
+        mut self,
+        temperature: f64,
+    ) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// The options with `max_tokens`.
+    #[must_use]
+    pub const fn with_max_tokens(
+        mut self,
+        max_tokens: u32,
+    ) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// The options with `top_p`.
+    #[must_use]
+    pub const fn with_top_p(
+        mut self,
+        top_p: f64,
+    ) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// The genai options of a call made with these parameters.
+    #[must_use]
+    pub fn chat_options(&self) -> ChatOptions {
+        ChatOptions {
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            top_p: self.top_p,
+            ..ChatOptions::default()
+        }
+    }
+}
+
+impl TryFrom<Parameters> for ModelOptions {
+    type Error = String;
+
+    fn try_from(parameters: Parameters) -> Result<Self, Self::Error> {
+        if let Some(temperature) = parameters.temperature.filter(|t| !(0.0..=2.0).contains(t)) {
+            return Err(format!("temperature must be between 0 and 2, got {temperature}"));
+        }
+        if parameters.max_tokens == Some(0) {
+            return Err("max_tokens must be at least 1".to_string());
+        }
+        if let Some(top_p) = parameters.top_p.filter(|p| *p <= 0.0 || *p > 1.0) {
+            return Err(format!("top_p must be above 0 and at most 1, got {top_p}"));
+        }
+        Ok(Self {
+            temperature: parameters.temperature,
+            max_tokens: parameters.max_tokens,
+            top_p: parameters.top_p,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_are_range_checked_and_passed_to_genai() {
+        let options: ModelOptions = serde_json::from_str(r#"{"temperature": 0, "max_tokens": 512}"#).unwrap();
+        assert_eq!(
+            options,
+            ModelOptions::default().with_temperature(0.0).with_max_tokens(512)
+        );
+        let chat_options = options.chat_options();
+        assert_eq!(chat_options.temperature, Some(0.0));
+        assert_eq!(chat_options.max_tokens, Some(512));
+        assert_eq!(chat_options.top_p, None);
+        assert_eq!(
+            serde_json::to_string(&options).unwrap(),
+            r#"{"temperature":0.0,"max_tokens":512}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ModelOptions>("{}").unwrap(),
+            ModelOptions::default()
+        );
+
+        for invalid in [r#"{"temperature": 2.5}"#, r#"{"max_tokens": 0}"#, r#"{"top_p": 0}"#] {
+            assert!(serde_json::from_str::<ModelOptions>(invalid).is_err(), "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Notebooks reproducing a session's questions, queries and answers.
+//!
+//! A session exported as a notebook lists each turn's question, the Cypher query that answered it,
+//! a preview of its result and the answer, so an analyst can rerun and extend the exploration
+//! outside the chat. [`NotebookFormat::Markdown`] writes the queries as `cypher` code blocks;
+//! [`NotebookFormat::Jupyter`] writes an nbformat 4 notebook whose code cells run each query
+//! through the server's `/graph_query` endpoint.
+
+use crate::session::{Session, SessionTurn};
+use serde_json::{Value, json};
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+/// The kind of notebook a session is exported as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NotebookFormat {
+    /// Markdown with a `cypher` code block per query.
+    #[default]
+    Markdown,
+    /// A Jupyter notebook calling the REST API from Python.
+    Jupyter,
+}
+
+impl NotebookFormat {
+    /// The file extension of the format.
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Jupyter => "ipynb",
+        }
+    }
+
+    /// The media type of the format.
+    #[must_use]
+    pub const fn content_type(self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Jupyter => "application/x-ipynb+json",
+        }
+    }
+}
+
+impl FromStr for NotebookFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "jupyter" | "ipynb" => Ok(Self::Jupyter),
+            other => Err(format!(
+                "Unknown notebook format '{other}': expected 'markdown' or 'jupyter'"
+            )),
+        }
+    }
+}
+
+/// The session as a Markdown document.
+#[must_use]
+pub fn to_markdown(session: &Session) -> String {
+    let mut out = format!("# Session {}\n\nGraph: `{}`\n", session.id, session.graph_name);
+    for (index, turn) in session.turns.iter().enumerate() {
+        let _ = write!(out, "\n## {}. {}\n", index + 1, turn.question);
+        if let Some(query) = &turn.cypher_query {
+            let _ = write!(out, "\n```cypher\n{}\n```\n", query.trim());
+        }
+        if let Some(preview) = &turn.result_preview {
+            let _ = write!(
+                out,
+                "\nResult{}:\n\n```text\n{}\n```\n",
+                truncation_note(turn),
+                preview.trim_end()
+            );
+        }
+        if let Some(answer) = &turn.answer {
+            let _ = write!(out, "\n{}\n", answer.trim());
+        }
+        if let Some(error) = &turn.error {
+            let _ = write!(out, "\n> Error: {}\n", error.trim());
+        }
+    }
+    out
+}
+
+/// The session as an nbformat 4 notebook whose code cells query the server at `base_url`.
+#[must_use]
+pub fn to_jupyter(
+    session: &Session,
+    base_url: &str,
+) -> Value {
+    let setup = format!(
+        "import requests\n\nBASE_URL = {}\nGRAPH = {}\n\n\ndef run_cypher(query, graph=GRAPH):\n    \
+         response = requests.post(\n        f\"{{BASE_URL}}/graph_query\",\n        json={{\"data\": [[0, \
+         {{\"graph_name\": graph, \"query\": query}}]]}},\n    )\n    response.raise_for_status()\n    return \
+         response.json()[\"data\"][0][1]",
+        python_string(base_url.trim_end_matches('/')),
+        python_string(&session.graph_name)
+    );
+    let mut cells = vec![
+        markdown_cell(&format!(
+            "# Session {}\n\nGraph: `{}`. Each query below reruns against the server with `run_cypher`.",
+            session.id, session.graph_name
+        )),
+        code_cell(&setup),
+    ];
+    for (index, turn) in session.turns.iter().enumerate() {
+        let mut text = format!("## {}. {}", index + 1, turn.question);
+        if let Some(answer) = &turn.answer {
+            let _ = write!(text, "\n\n{}", answer.trim());
+        }
+        if let Some(error) = &turn.error {
+            let _ = write!(text, "\n\n> Error: {}", error.trim());
+        }
+        cells.push(markdown_cell(&text));
+        if let Some(query) = &turn.cypher_query {
+            cells.push(code_cell(&format!("run_cypher({})", python_string(query.trim()))));
+        }
+    }
+    json!({
+        "nbformat": 4,
+        "nbformat_minor": 5,
+        "metadata": {
+            "kernelspec": {"name": "python3", "display_name": "Python 3", "language": "python"},
+            "language_info": {"name": "python"}
+        },
+        "cells": cells,
+    })
+}
+
+/// `" (truncated)"` when the turn's result preview is shorter than its result.
+fn truncation_note(turn: &SessionTurn) -> &'static str {
+    match (&turn.result_preview, turn.result_bytes) {
+        (Some(preview), Some(bytes)) if preview.len() < bytes => " (truncated)",
+        _ => "",
+    }
+}
+
+/// Notebook cell sources are lists of lines, each but the last keeping its line break.
+fn source_lines(text: &str) -> Vec<String> {
+    text.split_inclusive('\n').map(ToString::to_string).collect()
+}
+
+fn markdown_cell(text: &str) -> Value {
+    json!({"cell_type": "markdown", "id": cell_id(text), "metadata": {}, "source": source_lines(text)})
+}
+
+fn code_cell(code: &str) -> Value {
+    json!({
+        "cell_type": "code",
+        "id": cell_id(code),
+        "metadata": {},
+        "execution_count": null,
+        "outputs": [],
+        "source": source_lines(code),
+    })
+}
+
+/// A cell ID derived from its content, so exporting the same session twice gives the same notebook.
+fn cell_id(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// `text` as a Python string literal; JSON string escapes are valid Python.
+fn python_string(text: &str) -> String {
+    Value::String(text.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        Session {
+            id: "s1".to_string(),
+            graph_name: "movies".to_string(),
+            created_at: 0,
+            updated_at: 0,
+            messages: Vec::new(),
+            turns: vec![
+                SessionTurn {
+                    question: "Who acted in The Matrix?".to_string(),
+                    cypher_query: Some(
+                        "MATCH (a:Actor)-[:ACTED_IN]->(:Movie {title: \"The Matrix\"}) RETURN a.name".to_string(),
+                    ),
+                    result_bytes: Some(2_000),
+                    result_preview: Some("1. \"Keanu Reeves\"".to_string()),
+                    answer: Some("Keanu Reeves, among others.".to_string()),
+                    ..SessionTurn::default()
+                },
+                SessionTurn {
+                    question: "And in Speed?".to_string(),
+                    error: Some("Graph not found".to_string()),
+                    ..SessionTurn::default()
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn markdown_lists_each_turn_with_its_query() {
+        let markdown = to_markdown(&session());
+        assert!(markdown.starts_with("# Session s1\n\nGraph: `movies`\n"));
+        assert!(markdown.contains(
+            "## 1. Who acted in The Matrix?\n\n```cypher\nMATCH (a:Actor)-[:ACTED_IN]->(:Movie {title: \"The \
+             Matrix\"}) RETURN a.name\n```\n\nResult (truncated):\n"
+        ));
+        assert!(markdown.contains("## 2. And in Speed?\n\n> Error: Graph not found\n"));
+    }
+
+    #[test]
+    fn jupyter_cells_rerun_the_queries_through_the_api() {
+        let notebook = to_jupyter(&session(), "http://localhost:8080/");
+        assert_eq!(notebook["nbformat"], 4);
+        let cells = notebook["cells"].as_array().unwrap();
+        // Title, setup, then a markdown cell per turn and a code cell per query.
+        assert_eq!(cells.len(), 5);
+        let setup: String = serde_json::from_value::<Vec<String>>(cells[1]["source"].clone())
+            .unwrap()
+            .concat();
+        assert!(setup.contains("BASE_URL = \"http://localhost:8080\"\nGRAPH = \"movies\"\n"));
+        assert_eq!(
+            cells[3]["source"][0],
+            "run_cypher(\"MATCH (a:Actor)-[:ACTED_IN]->(:Movie {title: \\\"The Matrix\\\"}) RETURN a.name\")"
+        );
+        assert_eq!(cells[4]["cell_type"], "markdown");
+        assert_eq!("markdown".parse::<NotebookFormat>(), Ok(NotebookFormat::Markdown));
+        assert!("pdf".parse::<NotebookFormat>().is_err());
+    }
+}
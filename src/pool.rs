@@ -0,0 +1,340 @@
+//! `FalkorDB` Client Pool
+//!
+//! Requests share one lazily-built client per connection string instead of connecting each time.
+//! A client's connections are driven by the runtime that opened them, so clients are pooled per
+//! runtime, and a runtime's clients are dropped with it.
+//!
+//! A connection string listing several endpoints fails over between them: [`connect`] and
+//! [`with_failover`] pass over an endpoint that could not be reached for [`ENDPOINT_COOLDOWN`].
+
+use crate::connection::{self, ConnectionTarget};
+use falkordb::{
+    FalkorAsyncClient, FalkorClientBuilder, FalkorConnectionInfo, FalkorDBError, FalkorResult, RetryPolicy, RowStream,
+};
+use std::collections::HashMap;
+use std::num::NonZeroU8;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::runtime;
+use tokio::sync::OnceCell;
+
+/// Connections each client keeps open. Schema discovery sizes its concurrency to this pool, so
+/// parallel probes queue on the client rather than on the server.
+pub const CONNECTION_POOL_SIZE: NonZeroU8 = NonZeroU8::new(8).unwrap();
+
+/// Builds an asynchronous `FalkorDB` client with the read-only retry policy applied.
+///
+/// Centralizing client construction here ensures every connection retries only idempotent
+/// read operations (queries issued via `ro_query` and schema discovery) on transient failures,
+/// using exponential backoff. Writes are never retried, so a failed write is surfaced
+/// immediately and can never be duplicated.
+///
+/// Kept `pub(crate)` so `FalkorDB` types are not exposed in this crate's public API. It lives in
+/// this module (compiled into both the library and the binary) so the binary can share it without
+/// a public re-export.
+///
+/// # Errors
+///
+/// Returns an error if the client cannot be built (for example, when the connection fails).
+// `pub(crate)` keeps FalkorDB types out of the public API in the library (this is a `pub mod`); it
+// looks redundant only in the binary, where `pool` is a private `mod`.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) async fn build_falkordb_async_client(
+    connection_info: FalkorConnectionInfo
+) -> FalkorResult<FalkorAsyncClient> {
+    FalkorClientBuilder::new_async()
+        .with_connection_info(connection_info)
+        .with_num_connections(CONNECTION_POOL_SIZE)
+        .with_retry_policy(RetryPolicy::read_only())
+        .build()
+        .await
+}
+
+/// How long a failover endpoint that could not be reached is tried only after the others.
+pub const ENDPOINT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Failover endpoints that could not be reached, and until when they are passed over.
+static UNHEALTHY: LazyLock<Mutex<HashMap<String, Instant>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Passes `endpoint` over for [`ENDPOINT_COOLDOWN`].
+fn mark_unhealthy(endpoint: &str) {
+    tracing::warn!(
+        "FalkorDB endpoint {} is unreachable; failing over for {} seconds",
+        connection::redact_endpoint(endpoint),
+        ENDPOINT_COOLDOWN.as_secs()
+    );
+    UNHEALTHY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(endpoint.to_string(), Instant::now() + ENDPOINT_COOLDOWN);
+}
+
+/// How long `endpoint` is still passed over, or `None` when it is healthy.
+#[must_use]
+pub fn endpoint_cooldown(endpoint: &str) -> Option<Duration> {
+    let until = UNHEALTHY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(endpoint)
+        .copied()?;
+    let now = Instant::now();
+    if until <= now {
+        // Expired: drop every endpoint whose cooldown is over
+        UNHEALTHY
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|_, until| *until > now);
+        return None;
+    }
+    Some(until - now)
+}
+
+/// Whether `error` means the endpoint could not be reached, rather than the operation failing on it.
+const fn is_unreachable(error: &FalkorDBError) -> bool {
+    matches!(
+        error,
+        FalkorDBError::ConnectionDown
+            | FalkorDBError::NoConnection
+            | FalkorDBError::EmptyConnection
+            | FalkorDBError::SentinelConnection(_)
+    )
+}
+
+/// Returns the pooled client and target for a connection string.
+///
+/// For a failover list, the first healthy endpoint that connects is used; an endpoint that fails
+/// to connect is passed over for [`ENDPOINT_COOLDOWN`].
+///
+/// # Errors
+///
+/// Returns an error if the connection string is invalid or no endpoint's client can be built.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) async fn connect(falkordb_connection: &str) -> Result<(FalkorAsyncClient, ConnectionTarget), String> {
+    connect_endpoint(falkordb_connection)
+        .await
+        .map(|(client, target, _)| (client, target))
+}
+
+/// Connects like [`connect`], also returning the endpoint connected to.
+async fn connect_endpoint(falkordb_connection: &str) -> Result<(FalkorAsyncClient, ConnectionTarget, String), String> {
+    let mut endpoints = connection::endpoints(falkordb_connection);
+    if endpoints.len() < 2 {
+        let (client, target) = connect_pooled(falkordb_connection).await?;
+        return Ok((client, target, falkordb_connection.to_string()));
+    }
+    // Healthy endpoints in order of preference, then the unhealthy ones soonest back first
+    endpoints.sort_by_key(|endpoint| endpoint_cooldown(endpoint));
+    let mut last_error = String::new();
+    for endpoint in endpoints {
+        match connect_pooled(endpoint).await {
+            Ok((client, target)) => return Ok((client, target, endpoint.to_string())),
+            Err(e) => {
+                mark_unhealthy(endpoint);
+                last_error = e;
+            }
+        }
+    }
+    Err(format!("No FalkorDB endpoint is reachable: {last_error}"))
+}
+
+/// Runs `operation` with the client of a connection string. When the endpoint turns out to be
+/// unreachable, it is passed over for [`ENDPOINT_COOLDOWN`] and, if `retry` is set, the operation
+/// runs again on the next endpoint of a failover list.
+///
+/// Only idempotent operations should be retried: a write may have been applied before its
+/// connection dropped.
+///
+/// # Errors
+///
+/// Returns an error if no endpoint can be connected to; the operation's own result otherwise.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) async fn with_failover<T, Fut>(
+    falkordb_connection: &str,
+    retry: bool,
+    mut operation: impl FnMut(FalkorAsyncClient, ConnectionTarget) -> Fut,
+) -> Result<FalkorResult<T>, String>
+where
+    Fut: Future<Output = FalkorResult<T>>,
+{
+    let failover = connection::endpoints(falkordb_connection).len() > 1;
+    let attempts = if failover && retry {
+        connection::endpoints(falkordb_connection).len()
+    } else {
+        1
+    };
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let (client, target, endpoint) = connect_endpoint(falkordb_connection).await?;
+        match operation(client, target).await {
+            Err(e) if failover && is_unreachable(&e) => {
+                mark_unhealthy(&endpoint);
+                if attempt >= attempts {
+                    return Ok(Err(e));
+                }
+            }
+            result => return Ok(result),
+        }
+    }
+}
+
+/// Runs a query on a dedicated runtime, for callers on a blocking thread. The outer error is the
+/// runtime's, the middle one the driver's, so an unreachable endpoint can be failed over, and
+/// the result is whatever `consume` makes of the rows.
+///
+/// # Errors
+///
+/// Returns an error if the runtime cannot be created.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn execute_query_blocking<T>(
+    client: &FalkorAsyncClient,
+    graph_name: &str,
+    query: &str,
+    read_only: bool,
+    consume: impl FnOnce(RowStream) -> T,
+) -> std::io::Result<FalkorResult<T>> {
+    let rt = runtime::Runtime::new()?;
+    Ok(rt.block_on(async {
+        let mut graph = client.select_graph(graph_name);
+        let query_result = if read_only {
+            graph.ro_query(query).execute().await?
+        } else {
+            graph.query(query).execute().await?
+        };
+        Ok(consume(query_result.data))
+    }))
+}
+
+/// One lazily-built client per connection string, shared by every request that uses it.
+type ClientCell = Arc<OnceCell<(FalkorAsyncClient, ConnectionTarget)>>;
+
+/// The pooled clients of each runtime (`None` outside any runtime), by connection string.
+type Clients = HashMap<Option<runtime::Id>, HashMap<String, ClientCell>>;
+
+static CLIENTS: LazyLock<Mutex<Clients>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Drops the clients of a runtime. It is held by a task that never finishes, so it is dropped
+/// when the runtime drops its tasks on shutdown.
+struct RuntimeClients(runtime::Id);
+
+impl Drop for RuntimeClients {
+    fn drop(&mut self) {
+        let clients = CLIENTS.lock().unwrap_or_else(PoisonError::into_inner).remove(&Some(self.0));
+        // The clients close their connections outside the lock
+        drop(clients);
+    }
+}
+
+/// Returns the pooled client and target of a single endpoint, connecting on first use on the
+/// current runtime.
+///
+/// Each client holds [`CONNECTION_POOL_SIZE`] connections, so requests share those instead of
+/// paying the connect cost and opening new Redis connections each time. Concurrent first calls
+/// wait on a single connect; a failed connect is not cached, so the next call tries again.
+///
+/// # Errors
+///
+/// Returns an error if the connection string is invalid or the client cannot be built.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) async fn connect_pooled(falkordb_connection: &str) -> Result<(FalkorAsyncClient, ConnectionTarget), String> {
+    let handle = runtime::Handle::try_current().ok();
+    let (cell, first_on_runtime) = pooled_cell(handle.as_ref().map(runtime::Handle::id), falkordb_connection);
+    // Spawned outside the lock: on a runtime that is shutting down, the guard is dropped at once
+    if first_on_runtime && let Some(handle) = handle {
+        let guard = RuntimeClients(handle.id());
+        handle.spawn(async move {
+            let _guard = guard;
+            std::future::pending::<()>().await;
+        });
+    }
+    let (client, target) = cell.get_or_try_init(|| Box::pin(connect_new(falkordb_connection))).await?;
+    Ok((client.clone(), target.clone()))
+}
+
+/// The cell of a connection string on a runtime, and whether it is the runtime's first.
+fn pooled_cell(
+    runtime_id: Option<runtime::Id>,
+    falkordb_connection: &str,
+) -> (ClientCell, bool) {
+    let mut clients = CLIENTS.lock().unwrap_or_else(PoisonError::into_inner);
+    let first_on_runtime = !clients.contains_key(&runtime_id);
+    let cell = clients
+        .entry(runtime_id)
+        .or_default()
+        .entry(falkordb_connection.to_string())
+        .or_default()
+        .clone();
+    drop(clients);
+    (cell, first_on_runtime)
+}
+
+/// Parses a connection string and connects to its server and database, returning the client and
+/// the target, which maps graph names to (possibly prefixed) graph keys.
+async fn connect_new(falkordb_connection: &str) -> Result<(FalkorAsyncClient, ConnectionTarget), String> {
+    let target = ConnectionTarget::parse(falkordb_connection).map_err(|e| format!("Invalid connection info: {e}"))?;
+    let connection_info =
+        FalkorConnectionInfo::try_from(target.url()).map_err(|e| format!("Invalid connection info: {e}"))?;
+    let client = build_falkordb_async_client(connection_info)
+        .await
+        .map_err(|e| format!("Failed to build client: {e}"))?;
+    Ok((client, target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn unreachable_endpoints_are_failed_over() {
+        // Nothing listens on ports 1 and 2
+        let connection = "falkor://127.0.0.1:1,falkor://127.0.0.1:2";
+        let Err(error) = connect(connection).await else {
+            panic!("connected to a closed port");
+        };
+        assert!(error.starts_with("No FalkorDB endpoint is reachable"), "{error}");
+        for endpoint in connection::endpoints(connection) {
+            assert!(endpoint_cooldown(endpoint).is_some_and(|cooldown| cooldown <= ENDPOINT_COOLDOWN));
+        }
+
+        mark_unhealthy("falkor://primary:6379");
+        let mut endpoints = connection::endpoints("falkor://primary:6379,falkor://replica:6379");
+        endpoints.sort_by_key(|endpoint| endpoint_cooldown(endpoint));
+        assert_eq!(endpoints, vec!["falkor://replica:6379", "falkor://primary:6379"]);
+        assert!(endpoint_cooldown("falkor://replica:6379").is_none());
+    }
+
+    #[tokio::test]
+    async fn failed_connects_are_not_pooled() {
+        let connection = "not-a-connection-string";
+        assert!(connect_pooled(connection).await.is_err());
+        assert!(connect_pooled(connection).await.is_err());
+        let pooled = CLIENTS
+            .lock()
+            .unwrap()
+            .get(&Some(runtime::Handle::current().id()))
+            .and_then(|clients| clients.get(connection).cloned());
+        assert!(pooled.is_some_and(|cell| !cell.initialized()));
+    }
+
+    #[test]
+    fn dropped_runtimes_release_their_clients() {
+        let runtime = runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let runtime_id = runtime.handle().id();
+        // Nothing listens on port 1, but the cell is pooled before the connect fails
+        let _ = runtime.block_on(connect_pooled("falkor://127.0.0.1:1"));
+        assert!(
+            CLIENTS
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .contains_key(&Some(runtime_id))
+        );
+
+        drop(runtime);
+        assert!(
+            !CLIENTS
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .contains_key(&Some(runtime_id))
+        );
+    }
+}
@@ -5,21 +5,59 @@
 
 use crate::chat::ChatRequest;
 use crate::core::{
-    create_genai_client_with_endpoint, discover_graph_schema, discover_udfs, execute_cypher_query,
-    generate_cypher_query_with_context_and_usage, generate_final_answer_with_confidence,
+    GenerationOutcome, create_genai_client_with_endpoint, discover_graph_schema_with_options, discover_udfs,
+    execute_cypher_query_within, explain_cypher_query, generate_cypher_outcome_with_template,
+    generate_final_answer_for_audience, graph_not_found_message, list_graphs, route_question,
+    stream_final_answer_for_audience, with_query_vector,
 };
+use crate::engine::{
+    self, Admission, AnswerRequest, AnswerWriter, Executed, Generation, GenerationError, ProgressReporter, QueryModes,
+    QueryRunner, QueryWriter, StepError, StreamClosed, execute_with_healing, generate_query, heal_query,
+    policy_violation, prune_schema, write_answer,
+};
+use crate::error::TextToCypherError;
+use crate::few_shot::{self, FewShotExample};
+use crate::formatter::DEFAULT_MAX_RESULT_BYTES;
+use crate::hooks::ResponseHooks;
+use crate::latency::{LatencyMode, compact_schema};
+use crate::model_chain::ModelChain;
+use crate::model_options::ModelOptions;
+use crate::routing::GraphCandidate;
+use crate::schema::discovery::DiscoveryOptions;
+use crate::schema::pruning::SchemaPruner;
+use crate::schema::units::{AttributeUnits, units_for_query};
 use crate::skills::SkillCatalog;
+use crate::streaming::{Progress, StreamStatus, Warning, WarningCode};
+use crate::template::{Audience, TemplateEngine};
+use crate::tenancy::TenantFilter;
 use crate::udf::{UdfError, UdfSource};
-use crate::usage::TokenUsage;
+use crate::usage::{PriceTable, TokenUsage};
+use crate::validation_rules::ValidationRules;
+use crate::validator::{
+    CostBudget, CypherValidator, ProcedureAllowlist, QueryMode, ValidationOptions, ValidationPolicy, estimate_cost,
+};
+use futures::channel::mpsc;
+use futures::{FutureExt, Stream, StreamExt, stream};
 use serde::{Deserialize, Serialize};
-use std::error::Error;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Request structure for text-to-cypher conversion
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct TextToCypherRequest {
+    /// The graph to query. Empty routes the question to the graph it is about, among `graphs`.
+    #[serde(default)]
     pub graph_name: String,
+    /// Graphs a request without a `graph_name` may be routed to; empty considers every graph.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub graphs: Vec<String>,
     pub chat_request: ChatRequest,
-    pub model: Option<String>,
+    /// The model, or models tried in order when a model call fails (see [`ModelChain`]).
+    pub model: Option<ModelChain>,
     pub key: Option<String>,
     pub falkordb_connection: Option<String>,
     /// Optional LLM provider endpoint/base URL override.
@@ -28,6 +66,66 @@ pub struct TextToCypherRequest {
     /// When true, returns only the generated Cypher query without executing it
     #[serde(default)]
     pub cypher_only: bool,
+    /// When true, plans the generated query with `GRAPH.EXPLAIN` instead of executing it and
+    /// returns the plan in [`TextToCypherResponse::query_plan`]. Ignored with `cypher_only`.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Who the answer is written for; selects the answer-template variant. `None` uses the default.
+    #[serde(default)]
+    pub audience: Option<Audience>,
+    /// When true, suggests 2–3 follow-up questions after the answer (one extra LLM call).
+    #[serde(default)]
+    pub followups: bool,
+    /// When true, queries without RETURN or LIMIT, or using labels missing from the schema, are
+    /// refused instead of executed.
+    #[serde(default)]
+    pub strict_validation: bool,
+    /// When true and the linter reports hints (cartesian products, undirected relationships,
+    /// unbounded variable-length paths, OPTIONAL MATCH misuse), the model gets one chance to
+    /// rewrite the query; the rewrite is kept only if it validates and has fewer hints.
+    #[serde(default)]
+    pub refine_lint_hints: bool,
+    /// Maximum depth for variable-length patterns. Deeper or unbounded patterns (e.g. `[*]`) are
+    /// rewritten to end at this depth and the cap is reported in `warnings`. `None` leaves them as
+    /// generated.
+    #[serde(default)]
+    pub max_var_length: Option<u32>,
+    /// Trades answer quality for latency: `fast` sends a compact schema and never retries,
+    /// `thorough` keeps the best of several candidate queries and checks the answer against the
+    /// result (see [`LatencyMode`]).
+    #[serde(default)]
+    pub latency_mode: LatencyMode,
+    /// Embedding of the text a similarity question compares against (see
+    /// [`crate::core::embed_text`]), passed to the query as `$query_vector`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_vector: Option<Vec<f32>>,
+    /// When true, the model writes values from the question as `$name` parameters and gives their
+    /// values separately; they reach the database in the query's `CYPHER` preamble.
+    #[serde(default)]
+    pub parameterized: bool,
+    /// Sample size and relationship pairing of schema discovery. Schemas discovered with other than
+    /// the default options bypass the schema cache.
+    #[serde(default)]
+    pub discovery: DiscoveryOptions,
+    /// Temperature, output token limit and nucleus sampling of the calls that write the query and
+    /// the answer; unset parameters keep the provider's defaults.
+    #[serde(default)]
+    pub model_options: ModelOptions,
+    /// Schema sent to the model instead of a discovered one, for curated ontologies or graphs too
+    /// large to sample: a JSON document, or a string used as is (see [`provided_schema`]).
+    /// Discovery is skipped entirely.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
+}
+
+/// The text of a schema supplied with a request: a string is used as is, any other JSON value is
+/// serialized.
+#[must_use]
+pub fn provided_schema(schema: &serde_json::Value) -> String {
+    match schema {
+        serde_json::Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
 }
 
 /// Response structure for text-to-cypher conversion
@@ -46,11 +144,33 @@ pub struct TextToCypherResponse {
     /// Model self-reported confidence (0-100) that the answer is correct given the data.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub confidence: Option<u8>,
+    /// Suggested follow-up questions, when requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub followups: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// The failed step behind `error`; only set on responses built in this process.
+    #[serde(skip)]
+    pub failure: Option<TextToCypherError>,
+    /// Why no query was generated, for `no_answer` responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_answer_reason: Option<String>,
     /// Aggregated token usage across all LLM calls made while serving the request.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token_usage: Option<TokenUsage>,
+    /// Non-fatal issues, such as variable-length depth caps applied to the generated query; each
+    /// was also streamed as a `Progress::Warning`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// The graph a request without a `graph_name` was routed to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_name: Option<String>,
+    /// The execution plan of a `dry_run` request's query, one operation per line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_plan: Option<Vec<String>>,
+    /// The model that wrote the query, from the request's model list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
 }
 
 impl TextToCypherResponse {
@@ -66,6 +186,26 @@ impl TextToCypherResponse {
         self.status == "error"
     }
 
+    /// Turns an error response into its [`TextToCypherError`], keeping any other response.
+    ///
+    /// # Errors
+    ///
+    /// Returns the step that failed when [`Self::is_error`] holds.
+    pub fn into_result(mut self) -> Result<Self, TextToCypherError> {
+        if !self.is_error() {
+            return Ok(self);
+        }
+        Err(self.failure.take().unwrap_or_else(|| {
+            TextToCypherError::Generation(self.error.unwrap_or_else(|| "Unknown error".to_string()))
+        }))
+    }
+
+    /// Checks if the model declined to write a query, e.g. for a question outside the schema
+    #[must_use]
+    pub fn is_no_answer(&self) -> bool {
+        self.status == "no_answer"
+    }
+
     #[must_use]
     pub fn success(
         schema: String,
@@ -91,14 +231,21 @@ impl TextToCypherResponse {
             cypher_result,
             answer,
             confidence: None,
+            followups: None,
             error: None,
+            failure: None,
+            no_answer_reason: None,
             token_usage,
+            warnings: Vec::new(),
+            graph_name: None,
+            query_plan: None,
+            model: None,
         }
     }
 
     #[must_use]
-    pub fn error(error_message: String) -> Self {
-        Self::error_with_usage(error_message, None)
+    pub fn error(error: TextToCypherError) -> Self {
+        Self::error_with_usage(error, None)
     }
 
     /// Creates an error response that also reports the token usage consumed before failure.
@@ -107,7 +254,7 @@ impl TextToCypherResponse {
     /// can still account for the tokens the request spent.
     #[must_use]
     pub fn error_with_usage(
-        error_message: String,
+        error: TextToCypherError,
         token_usage: Option<TokenUsage>,
     ) -> Self {
         Self {
@@ -117,8 +264,41 @@ impl TextToCypherResponse {
             cypher_result: None,
             answer: None,
             confidence: None,
-            error: Some(error_message),
+            followups: None,
+            error: Some(error.to_string()),
+            failure: Some(error),
+            no_answer_reason: None,
             token_usage,
+            warnings: Vec::new(),
+            graph_name: None,
+            query_plan: None,
+            model: None,
+        }
+    }
+
+    /// Creates the response for a question the model could not write a query for.
+    #[must_use]
+    pub fn no_answer(
+        schema: String,
+        reason: String,
+        token_usage: Option<TokenUsage>,
+    ) -> Self {
+        Self {
+            status: "no_answer".to_string(),
+            schema: Some(schema),
+            cypher_query: None,
+            cypher_result: None,
+            answer: None,
+            confidence: None,
+            followups: None,
+            error: None,
+            failure: None,
+            no_answer_reason: Some(reason),
+            token_usage,
+            warnings: Vec::new(),
+            graph_name: None,
+            query_plan: None,
+            model: None,
         }
     }
 }
@@ -179,6 +359,169 @@ pub async fn process_text_to_cypher_with_skills(
     .await
 }
 
+/// Cached schemas with their discovery time, by connection and graph.
+type SchemaEntries = HashMap<(String, String), (Instant, String)>;
+
+/// Schemas discovered within the last `ttl`, by connection and graph.
+///
+/// Cloning shares the cache, so every clone of a client sees the same entries.
+#[derive(Debug, Clone)]
+pub struct SchemaCache {
+    ttl: Duration,
+    entries: Arc<Mutex<SchemaEntries>>,
+}
+
+impl SchemaCache {
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::default(),
+        }
+    }
+
+    /// The cached schema of `graph_name`, unless it is older than the TTL.
+    #[must_use]
+    pub fn get(
+        &self,
+        falkordb_connection: &str,
+        graph_name: &str,
+    ) -> Option<String> {
+        let mut entries = self.entries.lock().ok()?;
+        let key = (falkordb_connection.to_string(), graph_name.to_string());
+        match entries.get(&key) {
+            Some((discovered, schema)) if discovered.elapsed() < self.ttl => Some(schema.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(
+        &self,
+        falkordb_connection: &str,
+        graph_name: &str,
+        schema: String,
+    ) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                (falkordb_connection.to_string(), graph_name.to_string()),
+                (Instant::now(), schema),
+            );
+        }
+    }
+
+    /// Drops the cached schema of `graph_name`, e.g. after changing the graph's structure.
+    pub fn invalidate(
+        &self,
+        falkordb_connection: &str,
+        graph_name: &str,
+    ) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(&(falkordb_connection.to_string(), graph_name.to_string()));
+        }
+    }
+}
+
+/// Self-healing attempts per request when [`ProcessorOptions`] does not say otherwise.
+pub const DEFAULT_MAX_HEAL_ATTEMPTS: usize = 2;
+
+/// How the processor runs each step. The default has no timeouts, no schema cache and two
+/// self-healing attempts.
+#[derive(Debug, Clone)]
+pub struct ProcessorOptions {
+    /// Limit for schema discovery (listing the graphs and sampling the schema).
+    pub schema_discovery_timeout: Option<Duration>,
+    /// Limit for each model call that writes a query or the answer.
+    pub generation_timeout: Option<Duration>,
+    /// Limit for each query execution.
+    pub execution_timeout: Option<Duration>,
+    /// How many times a failed query is regenerated with the error as feedback. `0` disables
+    /// self-healing; [`LatencyMode::Fast`] never heals.
+    pub max_heal_attempts: usize,
+    /// Bytes a formatted query result may take before the query fails as too large (and is
+    /// healed, usually with a `LIMIT`). `None` allows any size.
+    pub max_result_bytes: Option<usize>,
+    /// Reuses discovered schemas instead of discovering them on every request.
+    pub schema_cache: Option<SchemaCache>,
+    /// Procedures generated queries may call, with their argument rules. Forbidden procedures are
+    /// also left out of the prompt. `None` allows all.
+    pub procedure_allowlist: Option<ProcedureAllowlist>,
+    /// Units written into discovered schemas, which answers then state with the numbers.
+    pub attribute_units: Option<AttributeUnits>,
+    /// Narrows large schemas to the labels and relationships closest to the question before they
+    /// are sent to the model. `None` sends schemas whole.
+    pub schema_pruning: Option<SchemaPruner>,
+    /// What generated queries may do to the graph. Writes the policy allows are offered to the
+    /// model and executed read-write; `None` executes every query read-only.
+    pub validation_policy: Option<ValidationPolicy>,
+    /// Rewrite each generated query before it is validated, and the answer before it is returned.
+    pub response_hooks: ResponseHooks,
+    /// A deployment's own rules. Generated queries with error findings are rejected; warning
+    /// findings are reported as validation hints.
+    pub validation_rules: ValidationRules,
+    /// How much estimated cost a query may have before it runs (see [`crate::validator::estimate_cost`]).
+    /// The estimate is reported as a status; queries over budget are rejected or run with a
+    /// warning. `None` skips the estimate.
+    pub cost_budget: Option<CostBudget>,
+    /// The tenant queries on a shared graph are restricted to. Its predicate is added to every
+    /// generated query, and queries it cannot cover are rejected; `None` leaves queries unfiltered.
+    pub tenant_filter: Option<TenantFilter>,
+    /// The graph only answers statistical questions: the model is told to return aggregates only,
+    /// and queries returning raw rows are rejected.
+    pub aggregate_only: bool,
+    /// Model prices for [`TokenUsage::estimated_cost_usd`]; an empty table estimates no costs.
+    pub model_prices: PriceTable,
+    /// Curated questions and queries of the graph; the [`few_shot::DEFAULT_TOP_K`] most similar to
+    /// the question are sent with it.
+    pub few_shot_examples: Vec<FewShotExample>,
+    /// Admits each request to its graph before the first model call, and counts the tokens it
+    /// spent, routing included. `None` admits every request.
+    pub admission: Option<Arc<dyn Admission>>,
+}
+
+impl Default for ProcessorOptions {
+    fn default() -> Self {
+        Self {
+            schema_discovery_timeout: None,
+            generation_timeout: None,
+            execution_timeout: None,
+            max_heal_attempts: DEFAULT_MAX_HEAL_ATTEMPTS,
+            max_result_bytes: Some(DEFAULT_MAX_RESULT_BYTES),
+            schema_cache: None,
+            procedure_allowlist: None,
+            attribute_units: None,
+            schema_pruning: None,
+            validation_policy: None,
+            response_hooks: ResponseHooks::default(),
+            validation_rules: ValidationRules::default(),
+            cost_budget: None,
+            tenant_filter: None,
+            aggregate_only: false,
+            model_prices: PriceTable::default(),
+            few_shot_examples: Vec::new(),
+            admission: None,
+        }
+    }
+}
+
+/// Runs one step of the pipeline, failing it with `timed_out` once `limit` has elapsed.
+async fn within<T, E>(
+    limit: Option<Duration>,
+    step: &str,
+    timed_out: fn(String) -> E,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    match limit {
+        Some(limit) => tokio::time::timeout(limit, future)
+            .await
+            .unwrap_or_else(|_| Err(timed_out(format!("{step} timed out after {}s", limit.as_secs_f64())))),
+        None => future.await,
+    }
+}
+
 /// Process a text-to-cypher request with optional skills and optional UDF context.
 ///
 /// In addition to the behavior of [`process_text_to_cypher_with_skills`], the `udf_source`
@@ -186,6 +529,8 @@ pub async fn process_text_to_cypher_with_skills(
 /// [`UdfSource::Off`] adds nothing, [`UdfSource::Provided`] uses a caller-supplied catalog, and
 /// [`UdfSource::Discover`] runs `GRAPH.UDF LIST` (degrading to no UDF context when unsupported).
 ///
+/// This is equivalent to [`process_text_to_cypher_with_options`] with the default options.
+///
 /// # Errors
 ///
 /// This function does not return errors. All errors are captured and returned
@@ -195,7 +540,6 @@ pub async fn process_text_to_cypher_with_skills(
 ///
 /// This function does not panic. All errors are handled gracefully and returned
 /// as error responses within the `TextToCypherResponse` structure.
-#[allow(clippy::too_many_lines)]
 pub async fn process_text_to_cypher_with_context(
     request: TextToCypherRequest,
     default_model: Option<String>,
@@ -203,182 +547,642 @@ pub async fn process_text_to_cypher_with_context(
     default_connection: String,
     skill_catalog: Option<&SkillCatalog>,
     udf_source: &UdfSource,
+) -> TextToCypherResponse {
+    process_text_to_cypher_with_options(
+        request,
+        default_model,
+        default_key,
+        default_connection,
+        skill_catalog,
+        udf_source,
+        &ProcessorOptions::default(),
+    )
+    .await
+}
+
+/// Process a text-to-cypher request like [`process_text_to_cypher_with_context`], with per-step
+/// timeouts, a self-healing budget and a schema cache from `options`.
+///
+/// # Errors
+///
+/// This function does not return errors. All errors, including timeouts, are captured and
+/// returned as `TextToCypherResponse::error` with appropriate error messages.
+///
+/// # Panics
+///
+/// This function does not panic. All errors are handled gracefully and returned
+/// as error responses within the `TextToCypherResponse` structure.
+pub async fn process_text_to_cypher_with_options(
+    request: TextToCypherRequest,
+    default_model: Option<String>,
+    default_key: Option<String>,
+    default_connection: String,
+    skill_catalog: Option<&SkillCatalog>,
+    udf_source: &UdfSource,
+    options: &ProcessorOptions,
+) -> TextToCypherResponse {
+    let response = run_pipeline(
+        request,
+        default_model,
+        default_key,
+        default_connection,
+        skill_catalog,
+        udf_source,
+        options,
+        &ProgressSink::default(),
+    )
+    .await;
+    with_estimated_cost(response, options)
+}
+
+/// Processes a request like [`process_text_to_cypher_with_options`], reporting each step as a
+/// [`Progress`] event.
+///
+/// Events cover statuses, the schema, the query, its result and the answer as the model writes it.
+/// The stream ends with `Usage` (once a model was called), then `Result` (the answer, or
+/// the query when there is none, as for `cypher_only`), `NoAnswer` or `Error`, then `Done`.
+///
+/// The request runs as the stream is polled; dropping the stream cancels it.
+#[allow(clippy::too_many_arguments)]
+pub fn process_text_to_cypher_stream<'a>(
+    request: TextToCypherRequest,
+    default_model: Option<String>,
+    default_key: Option<String>,
+    default_connection: String,
+    skill_catalog: Option<&'a SkillCatalog>,
+    udf_source: &'a UdfSource,
+    options: &'a ProcessorOptions,
+) -> impl Stream<Item = Progress> + Send + 'a {
+    let (tx, rx) = mpsc::unbounded();
+    let run = async move {
+        let progress = ProgressSink(Some(tx));
+        let response = run_pipeline(
+            request,
+            default_model,
+            default_key,
+            default_connection,
+            skill_catalog,
+            udf_source,
+            options,
+            &progress,
+        )
+        .await;
+        for event in terminal_events(with_estimated_cost(response, options)) {
+            progress.send(event);
+        }
+    };
+    // The pipeline yields nothing itself; it runs while the stream waits for its events, and the
+    // channel closes when it is done.
+    stream::select(rx, run.into_stream().filter_map(|()| async { None }))
+}
+
+/// Estimates the cost of the response's token usage from the options' model prices.
+fn with_estimated_cost(
+    mut response: TextToCypherResponse,
+    options: &ProcessorOptions,
+) -> TextToCypherResponse {
+    if let Some(token_usage) = &mut response.token_usage {
+        token_usage.estimate_cost(&options.model_prices);
+    }
+    response
+}
+
+/// The events that end a stream, derived from the pipeline's response.
+fn terminal_events(mut response: TextToCypherResponse) -> Vec<Progress> {
+    let mut events = Vec::new();
+    events.extend(response.token_usage.take().map(Progress::Usage));
+    let terminal = if response.is_success() {
+        events.extend(response.confidence.map(Progress::Confidence));
+        events.extend(
+            response
+                .followups
+                .filter(|followups| !followups.is_empty())
+                .map(Progress::Followups),
+        );
+        Progress::Result(response.answer.or(response.cypher_query).unwrap_or_default())
+    } else if response.is_no_answer() {
+        Progress::NoAnswer(response.no_answer_reason.unwrap_or_default())
+    } else {
+        Progress::Error(response.error.unwrap_or_default())
+    };
+    let status = terminal.terminal_status().unwrap_or(StreamStatus::Error);
+    events.push(terminal);
+    events.push(Progress::Done {
+        status,
+        request_id: new_request_id(),
+    });
+    events
+}
+
+/// A random ID for a streamed request, as 32 hex digits.
+fn new_request_id() -> String {
+    let mut bytes = [0u8; 16];
+    // Without randomness the ID is all zeros; it only labels the stream.
+    let _ = aws_lc_rs::rand::fill(&mut bytes);
+    bytes.iter().fold(String::with_capacity(32), |mut id, byte| {
+        let _ = write!(id, "{byte:02x}");
+        id
+    })
+}
+
+/// Where the pipeline reports its steps; the non-streaming entry points discard them.
+#[derive(Default)]
+struct ProgressSink(Option<mpsc::UnboundedSender<Progress>>);
+
+impl ProgressSink {
+    const fn is_streaming(&self) -> bool {
+        self.0.is_some()
+    }
+
+    fn send(
+        &self,
+        progress: Progress,
+    ) {
+        if let Some(tx) = &self.0 {
+            // The receiver is gone only once the stream, and with it the pipeline, is dropped.
+            let _ = tx.unbounded_send(progress);
+        }
+    }
+
+    fn status(
+        &self,
+        status: impl Into<String>,
+    ) {
+        self.send(Progress::Status(status.into()));
+    }
+
+    fn warnings(
+        &self,
+        warnings: &[Warning],
+    ) {
+        for warning in warnings {
+            self.send(Progress::Warning(warning.clone()));
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ProgressReporter for ProgressSink {
+    async fn report(
+        &self,
+        progress: Progress,
+    ) -> Result<(), StreamClosed> {
+        self.send(progress);
+        Ok(())
+    }
+}
+
+/// The pipeline behind every entry point of this module.
+#[allow(clippy::too_many_lines, clippy::too_many_arguments)]
+async fn run_pipeline(
+    mut request: TextToCypherRequest,
+    default_model: Option<String>,
+    default_key: Option<String>,
+    default_connection: String,
+    skill_catalog: Option<&SkillCatalog>,
+    udf_source: &UdfSource,
+    options: &ProcessorOptions,
+    progress: &ProgressSink,
 ) -> TextToCypherResponse {
     // Apply defaults
-    let model = request.model.clone().or(default_model);
+    let models = request.model.clone().or_else(|| default_model.map(ModelChain::new));
     let key = request.key.clone().or(default_key);
 
     // Track if user provided custom connection
     let has_custom_connection = request.falkordb_connection.is_some();
     let falkordb_connection = request.falkordb_connection.clone().unwrap_or(default_connection);
 
-    let Some(model) = model else {
-        return TextToCypherResponse::error("Model must be provided either in request or as DEFAULT_MODEL".to_string());
+    let Some(mut models) = models else {
+        return TextToCypherResponse::error(TextToCypherError::Config(
+            "Model must be provided either in request or as DEFAULT_MODEL".to_string(),
+        ));
     };
 
     // Create GenAI client
     let client = create_genai_client_with_endpoint(key.as_deref(), request.llm_endpoint.as_deref());
 
     // Resolve service target
-    let service_target = match client.resolve_service_target(&model).await {
+    let service_target = match client.resolve_service_target(models.primary()).await {
         Ok(target) => target,
         Err(e) => {
-            return TextToCypherResponse::error(format!("Failed to resolve service target: {e}"));
+            return TextToCypherResponse::error(TextToCypherError::Config(format!(
+                "Failed to resolve service target: {e}"
+            )));
         }
     };
 
+    // Track token usage across every LLM call made for this request.
+    let mut token_usage = TokenUsage::new();
+
+    // Step 0: Route a request that names no graph to the graph its question is about
+    let routed = request.graph_name.is_empty();
+    if routed {
+        progress.status("Choosing the graph the question is about...");
+        match route_graph(
+            &request,
+            &falkordb_connection,
+            &client,
+            models.primary(),
+            options,
+            &mut token_usage,
+        )
+        .await
+        {
+            Ok(graph_name) => {
+                progress.send(Progress::Graph(graph_name.clone()));
+                request.graph_name = graph_name;
+            }
+            Err(e) => return TextToCypherResponse::error_with_usage(e, Some(token_usage)),
+        }
+    }
+
+    if let Some(admission) = &options.admission
+        && let Err(refusal) = engine::admit(admission.as_ref(), &request.graph_name, &token_usage).await
+    {
+        return TextToCypherResponse::error_with_usage(TextToCypherError::Config(refusal), Some(token_usage));
+    }
+
     tracing::info!(
         "Processing text-to-cypher for graph: {} using model: {} ({:?})",
         request.graph_name,
-        model,
+        models,
         service_target.model.adapter_kind
     );
+    let admitted_graph = request.graph_name.clone();
+    let graph_name = routed.then(|| request.graph_name.clone());
+    let mut response = answer_question(
+        request,
+        &mut models,
+        &client,
+        &falkordb_connection,
+        has_custom_connection,
+        skill_catalog,
+        udf_source,
+        options,
+        progress,
+        token_usage,
+    )
+    .await;
+    if let Some(admission) = &options.admission {
+        let tokens = response.token_usage.as_ref().map_or(0, |usage| usage.total_tokens);
+        admission.record_tokens(&admitted_graph, tokens);
+    }
+    response.graph_name = graph_name;
+    response.model = Some(models.primary().to_string());
+    response
+}
 
-    // Step 1: Discover schema (skip if cypher_only and no custom connection provided)
-    let schema = if request.cypher_only && !has_custom_connection {
+/// The graph a request without a `graph_name` is about, among its `graphs` or every graph on the
+/// server. Cached schemas are shown to the model with the graph names.
+async fn route_graph(
+    request: &TextToCypherRequest,
+    falkordb_connection: &str,
+    client: &genai::Client,
+    model: &str,
+    options: &ProcessorOptions,
+    token_usage: &mut TokenUsage,
+) -> Result<String, TextToCypherError> {
+    let names = if request.graphs.is_empty() {
+        within(
+            options.schema_discovery_timeout,
+            "Listing graphs",
+            TextToCypherError::SchemaDiscovery,
+            list_graphs(falkordb_connection),
+        )
+        .await?
+    } else {
+        request.graphs.clone()
+    };
+    let candidates: Vec<GraphCandidate> = names
+        .into_iter()
+        .map(|name| {
+            let schema = options
+                .schema_cache
+                .as_ref()
+                .and_then(|cache| cache.get(falkordb_connection, &name));
+            GraphCandidate::new(name).with_schema(schema)
+        })
+        .collect();
+    let candidates = match &options.admission {
+        Some(admission) => engine::admissible_candidates(admission.as_ref(), candidates)
+            .await
+            .map_err(TextToCypherError::Config)?,
+        None => candidates,
+    };
+    within(
+        options.generation_timeout,
+        "Graph routing",
+        TextToCypherError::Generation,
+        route_question(
+            request.chat_request.last_user_question().unwrap_or_default(),
+            &candidates,
+            client,
+            model,
+            token_usage,
+        ),
+    )
+    .await
+}
+
+/// The pipeline from schema discovery on, for the graph the request names. `models` is left with
+/// the model that wrote the query as its primary.
+#[allow(clippy::too_many_lines, clippy::too_many_arguments)]
+async fn answer_question(
+    request: TextToCypherRequest,
+    models: &mut ModelChain,
+    client: &genai::Client,
+    falkordb_connection: &str,
+    has_custom_connection: bool,
+    skill_catalog: Option<&SkillCatalog>,
+    udf_source: &UdfSource,
+    options: &ProcessorOptions,
+    progress: &ProgressSink,
+    mut token_usage: TokenUsage,
+) -> TextToCypherResponse {
+    // Step 1: Discover schema (skip if one was provided, or if cypher_only and no custom connection
+    // provided)
+    let schema = if let Some(schema) = &request.schema {
+        tracing::info!("Using the schema provided with the request");
+        provided_schema(schema)
+    } else if request.cypher_only && !has_custom_connection {
         // Use empty schema for cypher_only mode without FalkorDB
         tracing::info!("Skipping schema discovery in cypher_only mode");
         "{}".to_string()
     } else {
-        match discover_graph_schema(&falkordb_connection, &request.graph_name).await {
-            Ok(s) => {
-                tracing::info!("Schema discovered successfully");
-                s
-            }
-            Err(e) => {
-                return TextToCypherResponse::error(format!("Failed to discover schema: {e}"));
-            }
+        progress.status(format!("Discovering schema for graph: {}", request.graph_name));
+        match discover_schema(&request.graph_name, falkordb_connection, request.discovery, options).await {
+            Ok(s) => s,
+            Err(e) => return TextToCypherResponse::error(e),
         }
     };
+    let schema = match &options.attribute_units {
+        Some(units) => units.annotate(&schema),
+        None => schema,
+    };
+
+    let schema = if request.latency_mode.compact_schema() {
+        compact_schema(&schema)
+    } else {
+        schema
+    };
+    let pruned = prune_schema(
+        options.schema_pruning.as_ref(),
+        client,
+        &schema,
+        request.chat_request.last_user_question(),
+        progress,
+    )
+    .await;
+    let schema = pruned.ok().flatten().unwrap_or(schema);
+    progress.send(Progress::Schema(schema.clone()));
 
     // Step 1b: Resolve UDF context (instance-global). Discovery degrades to empty on
     // servers without UDF support; an empty string adds no UDF section to the prompt.
     let udfs_text = resolve_udfs(
         udf_source,
-        &falkordb_connection,
+        falkordb_connection,
         request.cypher_only,
         has_custom_connection,
     )
     .await;
 
-    // Track token usage across every LLM call made for this request.
-    let mut token_usage = TokenUsage::new();
-
-    // Step 2: Generate Cypher query
-    let cypher_query = match generate_cypher_query_with_context_and_usage(
-        &request.chat_request,
-        &schema,
-        &client,
-        &model,
+    // Step 2: Generate Cypher query. Candidates are checked with EXPLAIN only when there is a
+    // database to ask.
+    let explain_connection = (!request.cypher_only || has_custom_connection).then_some(falkordb_connection);
+    let generation = generation(&request, &schema, options);
+    let mut warnings = Vec::new();
+    let mut writer = LibraryWriter {
+        request: &request,
+        schema: &schema,
+        client,
+        explain_connection,
         skill_catalog,
-        &udfs_text,
-        &mut token_usage,
-    )
-    .await
-    {
-        Ok(q) => q,
-        Err(e) => {
-            return TextToCypherResponse::error_with_usage(format!("Failed to generate query: {e}"), Some(token_usage));
+        udfs: &udfs_text,
+        options,
+        token_usage: &mut token_usage,
+    };
+    let generated = generate_query(&mut writer, progress, &generation, models, &mut warnings).await;
+    // The model that wrote the query serves the rest of the request
+    let model = models.primary();
+    let cypher_query = match generated {
+        Ok(GenerationOutcome::Query(q)) => q,
+        Ok(GenerationOutcome::NoAnswer { reason }) => {
+            tracing::info!("No query generated: {}", reason);
+            let mut response = TextToCypherResponse::no_answer(schema, reason, Some(token_usage));
+            response.warnings = warnings;
+            return response;
+        }
+        Err(GenerationError::Failed(e)) => {
+            let mut response =
+                TextToCypherResponse::error_with_usage(e.context("Failed to generate query"), Some(token_usage));
+            response.warnings = warnings;
+            return response;
+        }
+        Err(GenerationError::Rejected(message)) => {
+            return TextToCypherResponse::error_with_usage(TextToCypherError::Validation(message), Some(token_usage));
+        }
+        Err(GenerationError::Stopped) => {
+            return TextToCypherResponse::error_with_usage(
+                TextToCypherError::Generation(StreamClosed.to_string()),
+                Some(token_usage),
+            );
         }
     };
 
-    tracing::info!("Cypher query generated: {}", cypher_query);
+    // Validation may run a query that failed it; these checks never let one through.
+    if let Some(message) = policy_violation(&cypher_query, &generation.validation) {
+        return TextToCypherResponse::error_with_usage(TextToCypherError::Validation(message), Some(token_usage));
+    }
+    progress.send(Progress::CypherQuery(cypher_query.clone()));
 
     // If cypher_only mode, return just the query
     if request.cypher_only {
-        return TextToCypherResponse::success_with_usage(schema, cypher_query, None, None, Some(token_usage));
+        let mut response =
+            TextToCypherResponse::success_with_usage(schema, cypher_query, None, None, Some(token_usage));
+        response.warnings = warnings;
+        return response;
+    }
+
+    // A dry run plans the query instead of executing it
+    if request.dry_run {
+        progress.status("Dry run: planning the Cypher query...");
+        let planned = within(
+            options.execution_timeout,
+            "Query planning",
+            TextToCypherError::Execution,
+            explain_cypher_query(
+                &executable(&request, &cypher_query),
+                &request.graph_name,
+                falkordb_connection,
+            ),
+        )
+        .await;
+        let plan = match planned {
+            Ok(plan) => plan,
+            Err(e) => {
+                return TextToCypherResponse::error_with_usage(e.context("Query planning failed"), Some(token_usage));
+            }
+        };
+        progress.send(Progress::QueryPlan(plan.clone()));
+        let mut response =
+            TextToCypherResponse::success_with_usage(schema, cypher_query, None, None, Some(token_usage));
+        response.query_plan = Some(plan);
+        response.warnings = warnings;
+        return response;
     }
 
     // Step 3: Execute query
-    let cypher_result = match execute_cypher_query(&cypher_query, &request.graph_name, &falkordb_connection, true).await
-    {
-        Ok(r) => r,
-        Err(e) => {
-            // Try self-healing once
-            tracing::warn!("Query execution failed, attempting self-healing: {}", e);
-
-            match attempt_self_healing(
-                &request,
-                &schema,
-                &cypher_query,
-                &e.to_string(),
-                &client,
-                &model,
-                &falkordb_connection,
-                skill_catalog,
-                &udfs_text,
-                &mut token_usage,
-            )
-            .await
-            {
-                Ok((healed_query, healed_result)) => {
-                    tracing::info!("Self-healing successful");
-                    // Return the healed version
-                    let (answer, confidence) = match generate_final_answer_with_confidence(
-                        &request.chat_request,
-                        &healed_query,
-                        &healed_result,
-                        &client,
-                        &model,
-                        &mut token_usage,
-                    )
-                    .await
-                    {
-                        Ok((a, c)) => (Some(a), c),
-                        Err(e) => {
-                            tracing::error!("Failed to generate answer: {}", e);
-                            (None, None)
-                        }
-                    };
-
-                    let mut response = TextToCypherResponse::success_with_usage(
-                        schema,
-                        healed_query,
-                        Some(healed_result),
-                        answer,
-                        Some(token_usage),
-                    );
-                    response.confidence = confidence;
-                    return response;
-                }
-                Err(heal_error) => {
-                    return TextToCypherResponse::error_with_usage(
-                        format!("Query execution failed: {e}. Self-healing also failed: {heal_error}"),
-                        Some(token_usage),
-                    );
-                }
+    let max_heal_attempts = if request.latency_mode.retries() {
+        options.max_heal_attempts
+    } else {
+        0
+    };
+    let warned = warnings.len();
+    match check_query_cost(&request, &cypher_query, falkordb_connection, options, &mut warnings).await {
+        Ok(status) => {
+            if let Some(status) = status {
+                progress.status(status);
             }
+            progress.warnings(&warnings[warned..]);
+        }
+        Err(e) => return TextToCypherResponse::error_with_usage(e, Some(token_usage)),
+    }
+    progress.status("Executing Cypher query...");
+    let mut runner = LibraryRunner {
+        writer: LibraryWriter {
+            request: &request,
+            schema: &schema,
+            client,
+            explain_connection,
+            skill_catalog,
+            udfs: &udfs_text,
+            options,
+            token_usage: &mut token_usage,
+        },
+        generation: &generation,
+        model,
+        falkordb_connection,
+        warnings: &mut warnings,
+        progress,
+    };
+    let executed = execute_with_healing(&mut runner, progress, cypher_query, max_heal_attempts).await;
+    let Executed {
+        query: cypher_query,
+        result: cypher_result,
+        heal_attempts,
+    } = match executed {
+        Ok(executed) => executed,
+        Err(StepError::Failed(message)) => {
+            return TextToCypherResponse::error_with_usage(TextToCypherError::Execution(message), Some(token_usage));
+        }
+        Err(_) => {
+            return TextToCypherResponse::error_with_usage(
+                TextToCypherError::Execution("Query execution failed".to_string()),
+                Some(token_usage),
+            );
         }
     };
+    let healed = heal_attempts > 0;
 
     tracing::info!("Query executed successfully");
-
-    // Step 4: Generate final answer
-    let (answer, confidence) = match generate_final_answer_with_confidence(
-        &request.chat_request,
-        &cypher_query,
-        &cypher_result,
-        &client,
-        &model,
+    progress.send(Progress::CypherResult(cypher_result.clone()));
+
+    // Step 4: Generate final answer. A healed query already cost extra calls, so a failed answer
+    // then still returns its result. Streams receive the answer as the model writes it.
+    let units = units_for_query(&schema, &cypher_query);
+    let mut answer_writer = LibraryAnswerWriter {
+        request: &request,
+        query: &cypher_query,
+        result: &cypher_result,
+        units: &units,
+        client,
+        options,
+        progress,
+    };
+    let answer_request = AnswerRequest {
+        question: request.chat_request.last_user_question().unwrap_or_default(),
+        schema: &schema,
+        query: &cypher_query,
+        result: &cypher_result,
+        followups: request.followups,
+        latency_mode: request.latency_mode,
+        response_hooks: &options.response_hooks,
+    };
+    let answered = write_answer(
+        &mut answer_writer,
+        progress,
+        &answer_request,
+        client,
+        model,
         &mut token_usage,
+        &mut warnings,
     )
     .await
-    {
-        Ok((a, c)) => (Some(a), c),
+    .unwrap_or_else(|closed| Err(TextToCypherError::Answer(closed.to_string())));
+    let (answer, confidence, followups) = match answered {
+        Ok(answer) => (Some(answer.text), answer.confidence, answer.followups),
+        Err(e) if healed => {
+            tracing::error!("Failed to generate answer: {}", e);
+            (None, None, None)
+        }
         Err(e) => {
-            return TextToCypherResponse::error_with_usage(
-                format!("Failed to generate answer: {e}"),
-                Some(token_usage),
-            );
+            return TextToCypherResponse::error_with_usage(e.context("Failed to generate answer"), Some(token_usage));
         }
     };
 
     let mut response =
         TextToCypherResponse::success_with_usage(schema, cypher_query, Some(cypher_result), answer, Some(token_usage));
     response.confidence = confidence;
+    response.followups = followups;
+    response.warnings = warnings;
     response
 }
 
+/// Discovers the schema of `graph_name`, or takes it from the options' schema cache when
+/// `discovery` has the default options.
+///
+/// A misspelled graph would otherwise be discovered as an empty schema and still cost an LLM call,
+/// so the graph must exist; if the graphs cannot be listed, discovery reports the connection
+/// problem.
+async fn discover_schema(
+    graph_name: &str,
+    falkordb_connection: &str,
+    discovery: DiscoveryOptions,
+    options: &ProcessorOptions,
+) -> Result<String, TextToCypherError> {
+    let schema_cache = options
+        .schema_cache
+        .as_ref()
+        .filter(|_| discovery == DiscoveryOptions::default());
+    if let Some(schema) = schema_cache.and_then(|cache| cache.get(falkordb_connection, graph_name)) {
+        tracing::info!("Using cached schema");
+        return Ok(schema);
+    }
+
+    let schema = within(
+        options.schema_discovery_timeout,
+        "Schema discovery",
+        TextToCypherError::SchemaDiscovery,
+        async {
+            if let Ok(graphs) = list_graphs(falkordb_connection).await
+                && let Some(message) = graph_not_found_message(graph_name, &graphs)
+            {
+                return Err(TextToCypherError::SchemaDiscovery(message));
+            }
+            discover_graph_schema_with_options(falkordb_connection, graph_name, discovery, &|_| {})
+                .await
+                .map_err(|e| e.context("Failed to discover schema"))
+        },
+    )
+    .await?;
+
+    tracing::info!("Schema discovered successfully");
+    if let Some(cache) = schema_cache {
+        cache.insert(falkordb_connection, graph_name, schema.clone());
+    }
+    Ok(schema)
+}
+
 /// Resolve the UDF context block for a request based on its [`UdfSource`].
 ///
 /// Returns the rendered prompt block (empty string for no UDF context). [`UdfSource::Discover`]
@@ -415,66 +1219,324 @@ async fn resolve_udfs(
     }
 }
 
-/// Attempts to self-heal a failed query by regenerating with error context
+/// Writes the queries of one request with the request's skills, UDF context and model options,
+/// each call limited to the options' generation timeout.
 ///
-/// Token usage from the regeneration call is accumulated into `token_usage` even when the
-/// subsequent execution fails, so the caller can report it on error responses.
-#[allow(clippy::too_many_arguments)]
-async fn attempt_self_healing(
-    request: &TextToCypherRequest,
-    schema: &str,
-    failed_query: &str,
-    error_message: &str,
-    client: &genai::Client,
-    model: &str,
-    falkordb_connection: &str,
-    skill_catalog: Option<&SkillCatalog>,
-    udfs: &str,
-    token_usage: &mut TokenUsage,
-) -> Result<(String, String), Box<dyn Error + Send + Sync>> {
-    use crate::chat::{ChatMessage, ChatRole};
+/// Token usage is accumulated into `token_usage` even when the request fails, so the caller can
+/// report it on error responses.
+struct LibraryWriter<'a> {
+    request: &'a TextToCypherRequest,
+    schema: &'a str,
+    client: &'a genai::Client,
+    /// The database candidates are planned on; without one every candidate counts as planned.
+    explain_connection: Option<&'a str>,
+    skill_catalog: Option<&'a SkillCatalog>,
+    udfs: &'a str,
+    options: &'a ProcessorOptions,
+    token_usage: &'a mut TokenUsage,
+}
 
-    tracing::info!("Attempting self-healing for failed query");
+#[async_trait::async_trait]
+impl QueryWriter for LibraryWriter<'_> {
+    type Error = TextToCypherError;
+
+    async fn write(
+        &mut self,
+        chat_request: &ChatRequest,
+        model: &str,
+    ) -> Result<GenerationOutcome, TextToCypherError> {
+        within(
+            self.options.generation_timeout,
+            "Query generation",
+            TextToCypherError::Generation,
+            generate_cypher_outcome_with_template(
+                chat_request,
+                self.schema,
+                self.client,
+                model,
+                &self.request.model_options,
+                self.skill_catalog,
+                self.udfs,
+                None,
+                self.options.procedure_allowlist.as_ref(),
+                self.token_usage,
+            ),
+        )
+        .await
+    }
 
-    // Create a new chat request with error feedback
-    let mut retry_request = request.chat_request.clone();
-    retry_request.messages.push(ChatMessage {
-        role: ChatRole::Assistant,
-        content: failed_query.to_string(),
-    });
-    retry_request.messages.push(ChatMessage {
-        role: ChatRole::User,
-        content: format!(
-            "The previous query failed with error: {error_message}. Please generate a corrected Cypher query."
-        ),
-    });
+    async fn explains(
+        &mut self,
+        query: &str,
+    ) -> bool {
+        let Some(connection) = self.explain_connection else {
+            return true;
+        };
+        explain_cypher_query(&executable(self.request, query), &self.request.graph_name, connection)
+            .await
+            .inspect_err(|e| tracing::info!("Candidate rejected by EXPLAIN: {e}"))
+            .is_ok()
+    }
 
-    // Generate new query (include skill catalog and UDF context for consistent prompt).
-    // Usage is accumulated into `token_usage` even if generation/execution below fails.
-    let healed_query = generate_cypher_query_with_context_and_usage(
-        &retry_request,
-        schema,
-        client,
-        model,
-        skill_catalog,
-        udfs,
-        token_usage,
-    )
-    .await?;
+    /// Only model failures move on to the next model.
+    fn falls_back(
+        &self,
+        error: &TextToCypherError,
+    ) -> bool {
+        matches!(error, TextToCypherError::Generation(_))
+    }
+}
 
-    tracing::info!("Self-healed query generated: {}", healed_query);
+/// Executes and heals the queries of one request for [`execute_with_healing`].
+///
+/// Warnings about regenerated queries are added to `warnings` and reported as they come.
+struct LibraryRunner<'a> {
+    writer: LibraryWriter<'a>,
+    generation: &'a Generation<'a>,
+    model: &'a str,
+    falkordb_connection: &'a str,
+    warnings: &'a mut Vec<Warning>,
+    progress: &'a ProgressSink,
+}
 
-    // Try executing the healed query
-    let result = execute_cypher_query(&healed_query, &request.graph_name, falkordb_connection, true).await?;
+#[async_trait::async_trait]
+impl QueryRunner for LibraryRunner<'_> {
+    async fn execute(
+        &mut self,
+        query: &str,
+    ) -> Result<String, StepError> {
+        let (request, options) = (self.writer.request, self.writer.options);
+        within(
+            options.execution_timeout,
+            "Query execution",
+            TextToCypherError::Execution,
+            execute_cypher_query_within(
+                &executable(request, query),
+                &request.graph_name,
+                self.falkordb_connection,
+                read_only(query, options),
+                options.max_result_bytes,
+            ),
+        )
+        .await
+        .map_err(|e| StepError::Failed(e.to_string()))
+    }
+
+    /// Regenerates the query as [`heal_query`] does, then checks its cost against the options'
+    /// budget.
+    async fn heal(
+        &mut self,
+        failed_query: &str,
+        error: &str,
+    ) -> Result<String, StepError> {
+        let healed_query = heal_query(
+            &mut self.writer,
+            self.progress,
+            self.generation,
+            self.model,
+            failed_query,
+            error,
+            self.warnings,
+        )
+        .await?;
+        let (request, options) = (self.writer.request, self.writer.options);
+        let warned = self.warnings.len();
+        let checked = check_query_cost(request, &healed_query, self.falkordb_connection, options, self.warnings).await;
+        self.progress.warnings(&self.warnings[warned..]);
+        match checked {
+            Ok(_) => Ok(healed_query),
+            Err(e) => Err(StepError::Rejected {
+                query: healed_query,
+                message: e.to_string(),
+            }),
+        }
+    }
+}
 
-    Ok((healed_query, result))
+/// Writes the answer of one request, streaming it when the pipeline streams, limited to the
+/// options' generation timeout.
+struct LibraryAnswerWriter<'a> {
+    request: &'a TextToCypherRequest,
+    query: &'a str,
+    result: &'a str,
+    units: &'a str,
+    client: &'a genai::Client,
+    options: &'a ProcessorOptions,
+    progress: &'a ProgressSink,
+}
+
+#[async_trait::async_trait]
+impl AnswerWriter for LibraryAnswerWriter<'_> {
+    type Error = TextToCypherError;
+
+    async fn write(
+        &mut self,
+        model: &str,
+        token_usage: &mut TokenUsage,
+    ) -> Result<(String, Option<u8>), TextToCypherError> {
+        let (request, progress) = (self.request, self.progress);
+        if progress.is_streaming() {
+            within(
+                self.options.generation_timeout,
+                "Answer generation",
+                TextToCypherError::Answer,
+                stream_final_answer_for_audience(
+                    &request.chat_request,
+                    self.query,
+                    self.result,
+                    self.client,
+                    model,
+                    &request.model_options,
+                    request.audience,
+                    self.units,
+                    |chunk| progress.send(Progress::ModelOutputChunk(chunk.to_string())),
+                    token_usage,
+                ),
+            )
+            .await
+        } else {
+            within(
+                self.options.generation_timeout,
+                "Answer generation",
+                TextToCypherError::Answer,
+                generate_final_answer_for_audience(
+                    &request.chat_request,
+                    self.query,
+                    self.result,
+                    self.client,
+                    model,
+                    &request.model_options,
+                    request.audience,
+                    self.units,
+                    token_usage,
+                ),
+            )
+            .await
+        }
+    }
+}
+
+/// The conversation to generate a query from, with the options' examples closest to the question,
+/// the write-mode instructions when the options' validation policy allows writes, the
+/// aggregate-mode instructions when they set `aggregate_only`, and the parameterized-mode
+/// instructions when the request sets `parameterized`, before the question.
+fn query_chat_request(
+    request: &TextToCypherRequest,
+    options: &ProcessorOptions,
+) -> ChatRequest {
+    let allowed_writes: Vec<String> = options
+        .validation_policy
+        .as_ref()
+        .map(ValidationPolicy::allowed_writes)
+        .unwrap_or_default()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let question = request.chat_request.last_user_question().unwrap_or_default();
+    let modes = QueryModes {
+        examples: few_shot::render(&few_shot::select(
+            &options.few_shot_examples,
+            question,
+            few_shot::DEFAULT_TOP_K,
+        )),
+        write_prompt: (!allowed_writes.is_empty())
+            .then(|| TemplateEngine::render_write_mode_prompt(&allowed_writes.join(", "))),
+        aggregate_only: options.aggregate_only,
+        parameterized: request.parameterized,
+        previous_query: None,
+    };
+    crate::engine::query_chat_request(&request.chat_request, &modes)
+}
+
+/// Whether `query` runs read-only: unless it writes and the options' validation policy allows
+/// writes, which [`check_validation_policy`] has already narrowed to the allowed ones.
+fn read_only(
+    query: &str,
+    options: &ProcessorOptions,
+) -> bool {
+    !(options.validation_policy.as_ref().is_some_and(ValidationPolicy::allows_writes)
+        && CypherValidator::query_mode(query) == QueryMode::Write)
+}
+
+/// `query` with the request's query vector declared, as it is sent to the database.
+fn executable(
+    request: &TextToCypherRequest,
+    query: &str,
+) -> String {
+    request
+        .query_vector
+        .as_deref()
+        .map_or_else(|| query.to_string(), |vector| with_query_vector(query, vector))
+}
+
+/// Estimates the cost of `query` against the options' budget, from its `GRAPH.EXPLAIN` plan when
+/// the graph can plan it and from the query alone otherwise. Returns the status reporting the
+/// estimate, or `None` without a budget. A query over budget is rejected, or added to `warnings`
+/// when the budget only warns.
+async fn check_query_cost(
+    request: &TextToCypherRequest,
+    query: &str,
+    falkordb_connection: &str,
+    options: &ProcessorOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Option<String>, TextToCypherError> {
+    let Some(budget) = options.cost_budget else {
+        return Ok(None);
+    };
+    let plan = explain_cypher_query(&executable(request, query), &request.graph_name, falkordb_connection)
+        .await
+        .ok();
+    let estimate = estimate_cost(query, plan.as_deref());
+    if budget.allows(&estimate) {
+        Ok(Some(budget.status(&estimate)))
+    } else if budget.reject {
+        Err(TextToCypherError::Validation(budget.exceeded(&estimate)))
+    } else {
+        warnings.push(Warning::new(WarningCode::ExpensiveQuery, budget.exceeded(&estimate)));
+        Ok(Some(budget.status(&estimate)))
+    }
+}
+
+/// What a request's queries are generated from and checked against: the conversation from
+/// [`query_chat_request`], the checks from [`validation_options`] and the options' response hooks.
+fn generation<'a>(
+    request: &TextToCypherRequest,
+    schema: &str,
+    options: &'a ProcessorOptions,
+) -> Generation<'a> {
+    Generation {
+        chat_request: query_chat_request(request, options),
+        validation: validation_options(request, schema, options),
+        response_hooks: &options.response_hooks,
+        latency_mode: request.latency_mode,
+        refine_lint_hints: request.refine_lint_hints,
+    }
+}
+
+/// Builds the validation options for a request's generated queries: the checks of
+/// [`crate::engine::validation_options`], the request's `max_var_length`, and the options' policy,
+/// procedure allowlist, rules, tenant filter and aggregate-only mode.
+fn validation_options(
+    request: &TextToCypherRequest,
+    schema: &str,
+    options: &ProcessorOptions,
+) -> ValidationOptions {
+    crate::engine::validation_options(&request.chat_request, schema, request.strict_validation)
+        .with_max_var_length(request.max_var_length)
+        .with_procedure_allowlist(options.procedure_allowlist.clone())
+        .with_policy(options.validation_policy.clone())
+        .with_rules(options.validation_rules.clone())
+        .with_tenant_filter(options.tenant_filter.clone())
+        .with_aggregate_only(options.aggregate_only)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::chat::{ChatMessage, ChatRole};
+    use crate::engine::validate_query;
     use crate::udf::{UdfCatalog, UdfFunction, UdfLibrary};
+    use crate::validator::Clause;
 
     #[tokio::test]
     async fn resolve_udfs_off_returns_empty() {
@@ -499,6 +1561,48 @@ mod tests {
         assert!(text.is_empty());
     }
 
+    #[test]
+    fn schema_cache_expires_entries() {
+        let cache = SchemaCache::new(Duration::from_secs(60));
+        cache.insert("falkor://db:6379", "movies", "{}".to_string());
+        assert_eq!(cache.get("falkor://db:6379", "movies").as_deref(), Some("{}"));
+        assert_eq!(cache.get("falkor://other:6379", "movies"), None);
+        cache.invalidate("falkor://db:6379", "movies");
+        assert_eq!(cache.get("falkor://db:6379", "movies"), None);
+
+        let expired = SchemaCache::new(Duration::ZERO);
+        expired.insert("falkor://db:6379", "movies", "{}".to_string());
+        assert_eq!(expired.get("falkor://db:6379", "movies"), None);
+    }
+
+    #[tokio::test]
+    async fn steps_fail_after_their_timeout() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok::<_, TextToCypherError>(())
+        };
+        let result = within(
+            Some(Duration::from_millis(10)),
+            "Query execution",
+            TextToCypherError::Execution,
+            slow,
+        )
+        .await;
+        assert_eq!(
+            result,
+            Err(TextToCypherError::Execution(
+                "Query execution timed out after 0.01s".to_string()
+            ))
+        );
+        assert_eq!(
+            within(None, "Query execution", TextToCypherError::Execution, async {
+                Ok::<_, TextToCypherError>(1)
+            })
+            .await,
+            Ok(1)
+        );
+    }
+
     #[test]
     fn test_response_is_success() {
         let response = TextToCypherResponse::success(
@@ -511,9 +1615,19 @@ mod tests {
         assert!(!response.is_error());
     }
 
+    #[test]
+    fn test_response_is_no_answer() {
+        let response = TextToCypherResponse::no_answer("schema".to_string(), "No salary data".to_string(), None);
+        assert!(response.is_no_answer());
+        assert!(!response.is_error() && !response.is_success());
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["status"], "no_answer");
+        assert_eq!(json["no_answer_reason"], "No salary data");
+    }
+
     #[test]
     fn test_response_is_error() {
-        let response = TextToCypherResponse::error("Something went wrong".to_string());
+        let response = TextToCypherResponse::error(TextToCypherError::Execution("Something went wrong".to_string()));
         assert!(response.is_error());
         assert!(!response.is_success());
     }
@@ -537,7 +1651,7 @@ mod tests {
 
     #[test]
     fn test_error_response_structure() {
-        let response = TextToCypherResponse::error("Test error".to_string());
+        let response = TextToCypherResponse::error(TextToCypherError::Generation("Test error".to_string()));
 
         assert_eq!(response.status, "error");
         assert_eq!(response.schema, None);
@@ -545,6 +1659,10 @@ mod tests {
         assert_eq!(response.cypher_result, None);
         assert_eq!(response.answer, None);
         assert_eq!(response.error, Some("Test error".to_string()));
+        assert_eq!(
+            response.failure,
+            Some(TextToCypherError::Generation("Test error".to_string()))
+        );
         assert_eq!(response.token_usage, None);
     }
 
@@ -554,12 +1672,14 @@ mod tests {
             prompt_tokens: 30,
             completion_tokens: 0,
             total_tokens: 30,
+            ..TokenUsage::new()
         };
-        let response = TextToCypherResponse::error_with_usage("boom".to_string(), Some(usage));
+        let response =
+            TextToCypherResponse::error_with_usage(TextToCypherError::Answer("boom".to_string()), Some(usage.clone()));
 
         assert!(response.is_error());
         assert_eq!(response.error, Some("boom".to_string()));
-        assert_eq!(response.token_usage, Some(usage));
+        assert_eq!(response.token_usage, Some(usage.clone()));
 
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains("token_usage"), "token_usage should be present: {json}");
@@ -567,6 +1687,99 @@ mod tests {
         assert_eq!(deserialized.token_usage, Some(usage));
     }
 
+    #[test]
+    fn parameterized_requests_get_the_mode_prompt_before_the_question() {
+        let mut request = TextToCypherRequest {
+            chat_request: ChatRequest {
+                messages: vec![ChatMessage {
+                    role: ChatRole::User,
+                    content: "Movies with Tom Hanks".to_string(),
+                }],
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            query_chat_request(&request, &ProcessorOptions::default()).messages.len(),
+            1
+        );
+
+        request.parameterized = true;
+        let messages = query_chat_request(&request, &ProcessorOptions::default()).messages;
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[0].role, ChatRole::System) && messages[0].content.contains("PARAMETERS:"));
+        assert_eq!(messages[1].content, "Movies with Tom Hanks");
+
+        let options = ProcessorOptions {
+            few_shot_examples: vec![FewShotExample {
+                question: "Movies with Meg Ryan".to_string(),
+                cypher: "MATCH (:Person {name: 'Meg Ryan'})-[:ACTED_IN]->(m:Movie) RETURN m.title".to_string(),
+            }],
+            ..Default::default()
+        };
+        let messages = query_chat_request(&request, &options).messages;
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].content.contains("Question: Movies with Meg Ryan"));
+    }
+
+    #[test]
+    fn write_policies_offer_and_run_the_allowed_writes() {
+        let request = TextToCypherRequest {
+            chat_request: ChatRequest {
+                messages: vec![ChatMessage {
+                    role: ChatRole::User,
+                    content: "Add Alice".to_string(),
+                }],
+            },
+            ..Default::default()
+        };
+        let policy = ValidationPolicy {
+            allow_create: true,
+            blocked_clauses: vec![Clause::Remove],
+            ..Default::default()
+        };
+        let options = ProcessorOptions {
+            validation_policy: Some(policy.clone()),
+            ..Default::default()
+        };
+        let messages = query_chat_request(&request, &options).messages;
+        assert_eq!(messages.len(), 2);
+        assert!(
+            messages[0].content.contains("for CREATE, MERGE, SET only"),
+            "{}",
+            messages[0].content
+        );
+
+        let create = "CREATE (p:Person {name: 'Alice'}) RETURN p";
+        let mut options = ProcessorOptions::default();
+        assert!(read_only(create, &options));
+        assert_eq!(
+            policy_violation(create, &validation_options(&request, "{}", &options)),
+            None
+        );
+
+        options.validation_policy = Some(policy);
+        let validation = validation_options(&request, "{}", &options);
+        assert!(!read_only(create, &options));
+        assert!(read_only("MATCH (p:Person) RETURN p LIMIT 5", &options));
+        assert_eq!(policy_violation(create, &validation), None);
+        let error = policy_violation("MATCH (p:Person) DETACH DELETE p", &validation).unwrap();
+        assert!(error.contains("DELETE clauses are not allowed"), "{error}");
+    }
+
+    #[test]
+    fn provided_schemas_are_used_as_sent() {
+        let request: TextToCypherRequest = serde_json::from_str(
+            r#"{"graph_name": "g", "chat_request": {"messages": []}, "model": null, "key": null,
+                "falkordb_connection": null, "schema": {"entities": [{"label": "Person"}]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            request.schema.as_ref().map(provided_schema).as_deref(),
+            Some(r#"{"entities":[{"label":"Person"}]}"#)
+        );
+        assert_eq!(provided_schema(&serde_json::json!("Person(name)")), "Person(name)");
+    }
+
     #[test]
     fn test_request_serialization() {
         let request = TextToCypherRequest {
@@ -577,20 +1790,23 @@ mod tests {
                     content: "Find all nodes".to_string(),
                 }],
             },
-            model: Some("gpt-4o-mini".to_string()),
+            model: Some("gpt-4o-mini".into()),
             key: Some("test-key".to_string()),
             falkordb_connection: Some("falkor://localhost:6379".to_string()),
             llm_endpoint: Some("http://localhost:1234/v1".to_string()),
-            cypher_only: false,
+            audience: Some(Audience::Analyst),
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&request).unwrap();
         let deserialized: TextToCypherRequest = serde_json::from_str(&json).unwrap();
 
         assert_eq!(deserialized.graph_name, "test_graph");
-        assert_eq!(deserialized.model, Some("gpt-4o-mini".to_string()));
+        assert_eq!(deserialized.model, Some("gpt-4o-mini".into()));
         assert_eq!(deserialized.llm_endpoint, Some("http://localhost:1234/v1".to_string()));
         assert!(!deserialized.cypher_only);
+        assert!(!deserialized.dry_run);
+        assert_eq!(deserialized.audience, Some(Audience::Analyst));
     }
 
     #[test]
@@ -624,6 +1840,8 @@ mod tests {
         assert_eq!(request.key, None);
         assert_eq!(request.llm_endpoint, None);
         assert!(!request.cypher_only);
+        assert_eq!(request.audience, None);
+        assert!(!request.followups);
     }
 
     #[test]
@@ -643,6 +1861,22 @@ mod tests {
         assert_eq!(deserialized.cypher_result, None);
     }
 
+    #[test]
+    fn test_followups_serialized_only_when_present() {
+        let mut response = TextToCypherResponse::success(
+            "schema".to_string(),
+            "MATCH (n) RETURN n".to_string(),
+            None,
+            Some("answer".to_string()),
+        );
+        assert!(!serde_json::to_string(&response).unwrap().contains("followups"));
+
+        response.followups = Some(vec!["How many?".to_string()]);
+        let json = serde_json::to_string(&response).unwrap();
+        let deserialized: TextToCypherResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.followups, Some(vec!["How many?".to_string()]));
+    }
+
     #[test]
     fn test_token_usage_omitted_when_absent() {
         let response = TextToCypherResponse::success(
@@ -666,13 +1900,14 @@ mod tests {
             prompt_tokens: 12,
             completion_tokens: 8,
             total_tokens: 20,
+            ..TokenUsage::new()
         };
         let response = TextToCypherResponse::success_with_usage(
             "schema".to_string(),
             "MATCH (n) RETURN n".to_string(),
             Some("result".to_string()),
             Some("answer".to_string()),
-            Some(usage),
+            Some(usage.clone()),
         );
 
         let json = serde_json::to_string(&response).unwrap();
@@ -687,11 +1922,11 @@ mod tests {
         let request = TextToCypherRequest {
             graph_name: "test".to_string(),
             chat_request: ChatRequest { messages: vec![] },
-            model: Some("gpt-4".to_string()),
+            model: Some("gpt-4".into()),
             key: None,
             falkordb_connection: None,
-            llm_endpoint: None,
             cypher_only: true,
+            ..Default::default()
         };
 
         let cloned = request.clone();
@@ -699,4 +1934,124 @@ mod tests {
         assert_eq!(cloned.model, request.model);
         assert_eq!(cloned.cypher_only, request.cypher_only);
     }
+
+    #[test]
+    fn test_strict_validation_only_applies_when_requested() {
+        let mut request = TextToCypherRequest::default();
+        let options = ProcessorOptions::default();
+        let unbounded = "MATCH (n:Person) RETURN n";
+        let warnings = validate_query(unbounded, &validation_options(&request, "{}", &options)).unwrap();
+        assert!(
+            warnings.iter().any(|warning| warning.message.contains("LIMIT")),
+            "{warnings:?}"
+        );
+
+        request.strict_validation = true;
+        let validation = validation_options(&request, "{}", &options);
+        let error = validate_query(unbounded, &validation).unwrap_err();
+        assert!(error.contains("LIMIT"), "{error}");
+        assert!(validate_query("MATCH (n:Person) RETURN n LIMIT 5", &validation).is_ok());
+    }
+
+    #[test]
+    fn validation_covers_the_options_checks() {
+        let options = ProcessorOptions {
+            aggregate_only: true,
+            validation_rules: ValidationRules::parse("rules:\n  - name: no-csv\n    forbid: LOAD CSV\n").unwrap(),
+            ..Default::default()
+        };
+        let validation = validation_options(&TextToCypherRequest::default(), "{}", &options);
+        let load_csv = "LOAD CSV FROM 'file:///people.csv' AS row RETURN row LIMIT 5";
+        let error = validate_query(load_csv, &validation).unwrap_err();
+        assert!(error.contains("no-csv"), "{error}");
+        let violation = policy_violation(load_csv, &validation).unwrap();
+        assert!(
+            violation.starts_with("Aggregate-only mode rejected the query"),
+            "{violation}"
+        );
+        let guarded = "MATCH (p:Person) WITH count(p) AS people WHERE people >= 10 RETURN people";
+        assert!(validate_query(guarded, &validation).is_ok());
+        assert_eq!(policy_violation(guarded, &validation), None);
+    }
+
+    #[tokio::test]
+    async fn stream_reports_failures_and_ends_with_done() {
+        let request = TextToCypherRequest {
+            graph_name: "movies".to_string(),
+            ..Default::default()
+        };
+        let options = ProcessorOptions::default();
+        let events: Vec<Progress> = process_text_to_cypher_stream(
+            request,
+            None,
+            None,
+            "falkor://127.0.0.1:6379".to_string(),
+            None,
+            &UdfSource::Off,
+            &options,
+        )
+        .collect()
+        .await;
+
+        assert!(matches!(&events[0], Progress::Error(e) if e.contains("Model must be provided")));
+        assert!(matches!(
+            &events[1],
+            Progress::Done {
+                status: StreamStatus::Error,
+                ..
+            }
+        ));
+        assert_eq!(events.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn missing_model_fails_with_a_config_error() {
+        let request = TextToCypherRequest {
+            graph_name: "movies".to_string(),
+            ..Default::default()
+        };
+        let response = process_text_to_cypher_with_options(
+            request,
+            None,
+            None,
+            "falkor://127.0.0.1:6379".to_string(),
+            None,
+            &UdfSource::Off,
+            &ProcessorOptions::default(),
+        )
+        .await;
+
+        let error = response.into_result().unwrap_err();
+        assert!(matches!(error, TextToCypherError::Config(_)), "{error:?}");
+        assert!(error.to_string().starts_with("Model must be provided"));
+    }
+
+    #[test]
+    fn terminal_events_follow_the_response() {
+        let mut response = TextToCypherResponse::success_with_usage(
+            "{}".to_string(),
+            "MATCH (n) RETURN n".to_string(),
+            Some("[]".to_string()),
+            Some("Nothing".to_string()),
+            Some(TokenUsage::new()),
+        );
+        response.confidence = Some(80);
+        let events = terminal_events(response);
+        assert!(matches!(events[0], Progress::Usage(_)));
+        assert_eq!(events[1], Progress::Confidence(80));
+        assert_eq!(events[2], Progress::Result("Nothing".to_string()));
+        assert!(matches!(
+            events[3],
+            Progress::Done {
+                status: StreamStatus::Success,
+                ..
+            }
+        ));
+
+        let cypher_only = TextToCypherResponse::success("{}".to_string(), "MATCH (n) RETURN n".to_string(), None, None);
+        assert_eq!(
+            terminal_events(cypher_only)[0],
+            Progress::Result("MATCH (n) RETURN n".to_string())
+        );
+    }
 }
@@ -0,0 +1,178 @@
+//! The chat requests the pipeline sends to the model.
+//!
+//! These builders produce the exact prompts used by [`crate::core`] and the server, for callers that
+//! run the model themselves, e.g. to attach their own tools or chat options. Templates are rendered
+//! by [`TemplateEngine`], so the prompts follow any template change without caller updates.
+//!
+//! ```rust,no_run
+//! use text_to_cypher::{ChatMessage, ChatRequest, ChatRole, prompts};
+//!
+//! # async fn run(client: genai::Client, schema: &str) -> Result<(), genai::Error> {
+//! let conversation = ChatRequest {
+//!     messages: vec![ChatMessage {
+//!         role: ChatRole::User,
+//!         content: "Who acted in Heat?".to_string(),
+//!     }],
+//! };
+//! let request = prompts::create_cypher_query_chat_request(&conversation, schema, None, "", false);
+//! let response = client.exec_chat("gpt-4o-mini", request, None).await?;
+//! println!("{:?}", response.first_text());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::chat::{ChatRequest, ChatRole};
+use crate::skills::SkillCatalog;
+use crate::template::{Audience, TemplateEngine};
+use crate::validator::ProcedureAllowlist;
+use genai::chat::{ChatMessage as GenAiChatMessage, ChatRequest as GenAiChatRequest};
+
+/// Converts the conversation, rendering the last message through `render_last` if the user sent it.
+fn conversation(
+    chat_request: &ChatRequest,
+    render_last: impl Fn(&str) -> String,
+) -> GenAiChatRequest {
+    let last_index = chat_request.messages.len().saturating_sub(1);
+    chat_request
+        .messages
+        .iter()
+        .enumerate()
+        .fold(GenAiChatRequest::default(), |request, (index, message)| {
+            let message = match message.role {
+                ChatRole::User if index == last_index => GenAiChatMessage::user(render_last(&message.content)),
+                ChatRole::User => GenAiChatMessage::user(message.content.clone()),
+                ChatRole::Assistant => GenAiChatMessage::assistant(message.content.clone()),
+                ChatRole::System => GenAiChatMessage::system(message.content.clone()),
+            };
+            request.append_message(message)
+        })
+}
+
+/// The skills section of the system prompt: the compact catalog when the model can fetch a skill
+/// with the `read_skill` tool, or the content of every skill when it cannot.
+fn skills_section(
+    skill_catalog: Option<&SkillCatalog>,
+    use_tools: bool,
+) -> String {
+    match skill_catalog {
+        Some(catalog) if !catalog.is_empty() && use_tools => catalog.render_catalog(),
+        Some(catalog) if !catalog.is_empty() => catalog.render_all_content(),
+        _ => String::new(),
+    }
+}
+
+/// Builds the request asking the model for a Cypher query that answers the last user message.
+///
+/// The system prompt carries the schema, the skills and the `udfs` context (empty to omit it). Set
+/// `use_tools` when the `read_skill` tool ([`SkillCatalog::tool_definition`]) will be attached;
+/// the prompt then lists the skills instead of including their content.
+#[must_use]
+pub fn create_cypher_query_chat_request(
+    chat_request: &ChatRequest,
+    schema: &str,
+    skill_catalog: Option<&SkillCatalog>,
+    udfs: &str,
+    use_tools: bool,
+) -> GenAiChatRequest {
+    create_cypher_query_chat_request_with_template(chat_request, schema, skill_catalog, udfs, None, use_tools)
+}
+
+/// Like [`create_cypher_query_chat_request`], with an alternate system prompt template using the
+/// same placeholders as the built-in one; `None` uses the built-in template.
+#[must_use]
+pub fn create_cypher_query_chat_request_with_template(
+    chat_request: &ChatRequest,
+    schema: &str,
+    skill_catalog: Option<&SkillCatalog>,
+    udfs: &str,
+    system_template: Option<&str>,
+    use_tools: bool,
+) -> GenAiChatRequest {
+    let skills = skills_section(skill_catalog, use_tools);
+    let system_prompt = system_template.map_or_else(
+        || TemplateEngine::render_system_prompt_with_context(schema, &skills, udfs),
+        |template| TemplateEngine::render_system_prompt_template(template, schema, &skills, udfs),
+    );
+    conversation(chat_request, TemplateEngine::render_user_prompt).with_system(system_prompt)
+}
+
+/// Drops the procedures `allowlist` forbids from the `FalkorDB` reference in the request's system
+/// prompt, so the model does not suggest them (see [`TemplateEngine::falkordb_reference`]).
+#[must_use]
+pub fn restrict_procedures(
+    mut request: GenAiChatRequest,
+    allowlist: &ProcedureAllowlist,
+) -> GenAiChatRequest {
+    request.system = request
+        .system
+        .map(|system| TemplateEngine::restrict_procedures(&system, |procedure| allowlist.allows(procedure)));
+    request
+}
+
+/// Builds the request asking the model to answer the last user message from the result of
+/// `cypher_query`.
+///
+/// The answer is written for `audience` (`None` for the default answer style) and states the
+/// `units` of the values read (see [`TemplateEngine::render_answer_prompt`]; empty for none).
+#[must_use]
+pub fn create_answer_chat_request(
+    chat_request: &ChatRequest,
+    cypher_query: &str,
+    cypher_result: &str,
+    audience: Option<Audience>,
+    units: &str,
+) -> GenAiChatRequest {
+    conversation(chat_request, |question| {
+        TemplateEngine::render_answer_prompt(question, cypher_query, cypher_result, audience, units)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ChatMessage;
+
+    fn conversation_of(messages: &[(ChatRole, &str)]) -> ChatRequest {
+        ChatRequest {
+            messages: messages
+                .iter()
+                .map(|(role, content)| ChatMessage {
+                    role: role.clone(),
+                    content: (*content).to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn renders_only_the_last_user_message() {
+        let chat_request = conversation_of(&[
+            (ChatRole::User, "Who acted in Heat?"),
+            (
+                ChatRole::Assistant,
+                "MATCH (a)-[:ACTED_IN]->(:Movie {title: 'Heat'}) RETURN a",
+            ),
+            (ChatRole::User, "And in Ronin?"),
+        ]);
+        let request = create_cypher_query_chat_request(&chat_request, r#"{"entities":[]}"#, None, "", false);
+
+        assert!(request.system.as_deref().unwrap().contains(r#"{"entities":[]}"#));
+        let texts: Vec<String> = request
+            .messages
+            .iter()
+            .map(|message| message.content.joined_texts().unwrap_or_default())
+            .collect();
+        assert_eq!(texts[0], "Who acted in Heat?");
+        assert_eq!(texts[2], TemplateEngine::render_user_prompt("And in Ronin?"));
+    }
+
+    #[test]
+    fn answer_request_carries_the_query_result() {
+        let chat_request = conversation_of(&[(ChatRole::User, "How many movies?")]);
+        let request = create_answer_chat_request(&chat_request, "MATCH (m:Movie) RETURN count(m)", "[[42]]", None, "");
+
+        assert!(request.system.is_none());
+        let text = request.messages[0].content.joined_texts().unwrap_or_default();
+        assert!(text.contains("[[42]]") && text.contains("How many movies?"));
+    }
+}
@@ -0,0 +1,507 @@
+//! Query results and how they are rendered.
+//!
+//! [`QueryResult`] holds the rows of a query result as [`ResultValue`]s, which do not depend on the
+//! `FalkorDB` client types, so callers that run queries themselves (with any client) can render
+//! results exactly as the pipeline does. [`ResultFormat`] picks the rendering:
+//!
+//! - [`ResultFormat::Compact`]: the LLM-friendly format sent to the model, using Cypher-like syntax
+//!   for nodes and edges (`1. (:Person {name: "John"})`).
+//! - [`ResultFormat::Json`]: an array of rows, each an array of values; nodes, edges and paths are
+//!   objects with a `type` field. This is what the REST API returns.
+//! - [`ResultFormat::Table`]: an aligned text table with a header row when the columns are known.
+//! - [`ResultFormat::Csv`]: RFC 4180 CSV with a header row when the columns are known.
+//!
+//! ```rust
+//! use text_to_cypher::{QueryResult, ResultFormat, ResultValue};
+//!
+//! let result = QueryResult::new(
+//!     vec!["name".to_string(), "born".to_string()],
+//!     vec![vec![ResultValue::String("Al Pacino".to_string()), ResultValue::Integer(1940)]],
+//! );
+//! assert_eq!(result.render(ResultFormat::Compact), r#"["Al Pacino", 1940]"#);
+//! assert_eq!(result.render(ResultFormat::Json), r#"[["Al Pacino",1940]]"#);
+//! assert_eq!(result.render(ResultFormat::Csv), "name,born\nAl Pacino,1940");
+//! ```
+
+use falkordb::FalkorValue;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// A node in a query result.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultNode {
+    pub id: i64,
+    pub labels: Vec<String>,
+    pub properties: BTreeMap<String, ResultValue>,
+}
+
+/// A relationship in a query result.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultEdge {
+    pub id: i64,
+    pub relationship_type: String,
+    pub src_node_id: i64,
+    pub dst_node_id: i64,
+    pub properties: BTreeMap<String, ResultValue>,
+}
+
+/// A path in a query result: its nodes in order, and the relationships between them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultPath {
+    pub nodes: Vec<ResultNode>,
+    pub relationships: Vec<ResultEdge>,
+}
+
+/// A single value in a query result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResultValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    List(Vec<Self>),
+    Map(BTreeMap<String, Self>),
+    Node(ResultNode),
+    Edge(ResultEdge),
+    Path(ResultPath),
+    /// Any other value (points, vectors, temporal values), as its textual representation.
+    Other(String),
+}
+
+impl From<&FalkorValue> for ResultValue {
+    fn from(value: &FalkorValue) -> Self {
+        match value {
+            FalkorValue::None => Self::Null,
+            FalkorValue::Bool(b) => Self::Bool(*b),
+            FalkorValue::I64(i) => Self::Integer(*i),
+            FalkorValue::F64(f) => Self::Float(*f),
+            FalkorValue::String(s) => Self::String(s.clone()),
+            FalkorValue::Array(values) => Self::List(values.iter().map(Self::from).collect()),
+            FalkorValue::Map(map) => Self::Map(properties(map)),
+            FalkorValue::Node(node) => Self::Node(node.into()),
+            FalkorValue::Edge(edge) => Self::Edge(edge.into()),
+            FalkorValue::Path(path) => Self::Path(ResultPath {
+                nodes: path.nodes.iter().map(ResultNode::from).collect(),
+                relationships: path.relationships.iter().map(ResultEdge::from).collect(),
+            }),
+            other => Self::Other(format!("{other:?}")),
+        }
+    }
+}
+
+impl From<&falkordb::Node> for ResultNode {
+    fn from(node: &falkordb::Node) -> Self {
+        Self {
+            id: node.entity_id,
+            labels: node.labels.clone(),
+            properties: properties(&node.properties),
+        }
+    }
+}
+
+impl From<&falkordb::Edge> for ResultEdge {
+    fn from(edge: &falkordb::Edge) -> Self {
+        Self {
+            id: edge.entity_id,
+            relationship_type: edge.relationship_type.clone(),
+            src_node_id: edge.src_node_id,
+            dst_node_id: edge.dst_node_id,
+            properties: properties(&edge.properties),
+        }
+    }
+}
+
+fn properties<'a>(map: impl IntoIterator<Item = (&'a String, &'a FalkorValue)>) -> BTreeMap<String, ResultValue> {
+    map.into_iter().map(|(k, v)| (k.clone(), v.into())).collect()
+}
+
+/// How a [`QueryResult`] is rendered. See the [module documentation](self).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultFormat {
+    #[default]
+    Compact,
+    Json,
+    Table,
+    Csv,
+}
+
+impl FromStr for ResultFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "compact" => Ok(Self::Compact),
+            "json" => Ok(Self::Json),
+            "table" => Ok(Self::Table),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!(
+                "Unknown result format '{other}' (expected compact, json, table or csv)"
+            )),
+        }
+    }
+}
+
+/// The columns and rows of a query result.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResult {
+    /// Column names, in order; empty when unknown.
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<ResultValue>>,
+}
+
+impl QueryResult {
+    #[must_use]
+    pub const fn new(
+        columns: Vec<String>,
+        rows: Vec<Vec<ResultValue>>,
+    ) -> Self {
+        Self { columns, rows }
+    }
+
+    /// Converts rows returned by the `FalkorDB` client, without column names.
+    #[must_use]
+    pub fn from_falkor_rows(rows: &[Vec<FalkorValue>]) -> Self {
+        Self {
+            columns: Vec::new(),
+            rows: rows.iter().map(|row| row.iter().map(ResultValue::from).collect()).collect(),
+        }
+    }
+
+    /// Sets the column names, used as the header of the table and CSV formats.
+    #[must_use]
+    pub fn with_columns(
+        mut self,
+        columns: Vec<String>,
+    ) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    #[must_use]
+    pub fn render(
+        &self,
+        format: ResultFormat,
+    ) -> String {
+        match format {
+            ResultFormat::Compact => self.to_compact(),
+            ResultFormat::Json => self.to_json(),
+            ResultFormat::Table => self.to_table(),
+            ResultFormat::Csv => self.to_csv(),
+        }
+    }
+
+    fn to_compact(&self) -> String {
+        match self.rows.as_slice() {
+            [] => "No results returned.".to_string(),
+            [values] => compact_row(values),
+            rows => {
+                let mut res = String::new();
+                for (idx, values) in rows.iter().enumerate() {
+                    writeln!(res, "{}. {}", idx + 1, compact_row(values)).unwrap();
+                }
+                res.trim_end().to_string()
+            }
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let rows: Vec<String> = self
+            .rows
+            .iter()
+            .map(|values| format!("[{}]", join(values, ResultValue::to_json, ",")))
+            .collect();
+        format!("[{}]", rows.join(","))
+    }
+
+    fn to_table(&self) -> String {
+        let mut lines: Vec<Vec<String>> = Vec::with_capacity(self.rows.len() + 1);
+        if !self.columns.is_empty() {
+            lines.push(self.columns.clone());
+        }
+        lines.extend(self.rows.iter().map(|values| values.iter().map(ResultValue::to_text).collect()));
+        if lines.is_empty() {
+            return "No results returned.".to_string();
+        }
+
+        let width_count = lines.iter().map(Vec::len).max().unwrap_or_default();
+        let mut widths = vec![0; width_count];
+        for line in &lines {
+            for (width, cell) in widths.iter_mut().zip(line) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let render_line = |line: &[String]| {
+            let cells: Vec<String> = line
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        };
+        let mut rendered: Vec<String> = lines.iter().map(|line| render_line(line)).collect();
+        if !self.columns.is_empty() {
+            let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+            rendered.insert(1, format!("|-{}-|", rule.join("-|-")));
+        }
+        rendered.join("\n")
+    }
+
+    fn to_csv(&self) -> String {
+        fn field(text: &str) -> String {
+            if text.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                text.to_string()
+            }
+        }
+
+        let header = (!self.columns.is_empty()).then(|| join(&self.columns, |column| field(column), ","));
+        header
+            .into_iter()
+            .chain(
+                self.rows
+                    .iter()
+                    .map(|values| join(values, |value| field(&value.to_text()), ",")),
+            )
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// One row in the compact format: a lone value as is, several as a list.
+#[must_use]
+pub fn compact_row(values: &[ResultValue]) -> String {
+    if let [value] = values {
+        value.to_compact()
+    } else {
+        format!("[{}]", join(values, ResultValue::to_compact, ", "))
+    }
+}
+
+fn join<T>(
+    items: &[T],
+    render: impl Fn(&T) -> String,
+    separator: &str,
+) -> String {
+    items.iter().map(render).collect::<Vec<_>>().join(separator)
+}
+
+impl ResultValue {
+    /// The value in the compact format, e.g. `"John"` or `(:Person {name: "John"})`.
+    #[must_use]
+    pub fn to_compact(&self) -> String {
+        fn entries(map: &BTreeMap<String, ResultValue>) -> String {
+            let entries: Vec<String> = map.iter().map(|(k, v)| format!("{k}: {}", v.to_compact())).collect();
+            entries.join(", ")
+        }
+        fn properties(properties: &BTreeMap<String, ResultValue>) -> String {
+            if properties.is_empty() {
+                String::new()
+            } else {
+                format!(" {{{}}}", entries(properties))
+            }
+        }
+        fn node(node: &ResultNode) -> String {
+            let labels = if node.labels.is_empty() {
+                String::new()
+            } else {
+                format!(":{}", node.labels.join(":"))
+            };
+            format!("({labels}{})", properties(&node.properties))
+        }
+        fn edge(edge: &ResultEdge) -> String {
+            format!("-[:{}{}]-", edge.relationship_type, properties(&edge.properties))
+        }
+
+        match self {
+            Self::Null => "null".to_string(),
+            Self::Bool(b) => b.to_string(),
+            Self::Integer(i) => i.to_string(),
+            Self::Float(f) => f.to_string(),
+            Self::String(s) => format!("\"{s}\""),
+            Self::List(values) => format!("[{}]", join(values, Self::to_compact, ", ")),
+            Self::Map(map) => format!("{{{}}}", entries(map)),
+            Self::Node(n) => node(n),
+            Self::Edge(e) => edge(e),
+            Self::Path(path) => {
+                let mut path_str = String::new();
+                for (i, n) in path.nodes.iter().enumerate() {
+                    if i > 0
+                        && let Some(e) = path.relationships.get(i - 1)
+                    {
+                        // Paths from algorithm procedures follow relationships either way, so the
+                        // arrow shows which way each one points
+                        let previous = path.nodes[i - 1].id;
+                        if e.dst_node_id == previous && e.src_node_id == n.id {
+                            path_str.push('<');
+                        }
+                        path_str.push_str(&edge(e));
+                        if e.src_node_id == previous && e.dst_node_id == n.id {
+                            path_str.push('>');
+                        }
+                    }
+                    path_str.push_str(&node(n));
+                }
+                path_str
+            }
+            Self::Other(text) => text.clone(),
+        }
+    }
+
+    /// The value as JSON.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        fn properties(properties: &BTreeMap<String, ResultValue>) -> String {
+            let props: Vec<String> = properties
+                .iter()
+                .map(|(k, v)| format!("\"{}\":{}", escape_json_string(k), v.to_json()))
+                .collect();
+            format!("{{{}}}", props.join(","))
+        }
+        fn node(node: &ResultNode) -> String {
+            let labels = join(&node.labels, |label| format!("\"{}\"", escape_json_string(label)), ",");
+            format!(
+                "{{\"type\":\"node\",\"id\":{},\"labels\":[{labels}],\"properties\":{}}}",
+                node.id,
+                properties(&node.properties)
+            )
+        }
+        fn edge(edge: &ResultEdge) -> String {
+            format!(
+                "{{\"type\":\"edge\",\"id\":{},\"relationship_type\":\"{}\",\"src_node_id\":{},\"dst_node_id\":{},\"properties\":{}}}",
+                edge.id,
+                escape_json_string(&edge.relationship_type),
+                edge.src_node_id,
+                edge.dst_node_id,
+                properties(&edge.properties)
+            )
+        }
+
+        match self {
+            Self::Null => "null".to_string(),
+            Self::Bool(b) => b.to_string(),
+            Self::Integer(i) => i.to_string(),
+            Self::Float(f) if f.is_finite() => f.to_string(),
+            Self::Float(f) => format!("\"{f}\""),
+            Self::String(s) | Self::Other(s) => format!("\"{}\"", escape_json_string(s)),
+            Self::List(values) => format!("[{}]", join(values, Self::to_json, ",")),
+            Self::Map(map) => properties(map),
+            Self::Node(n) => node(n),
+            Self::Edge(e) => edge(e),
+            Self::Path(path) => format!(
+                "{{\"type\":\"path\",\"nodes\":[{}],\"relationships\":[{}]}}",
+                join(&path.nodes, node, ","),
+                join(&path.relationships, edge, ",")
+            ),
+        }
+    }
+
+    /// The value as a table or CSV cell: strings unquoted, anything else compact.
+    fn to_text(&self) -> String {
+        match self {
+            Self::String(s) => s.clone(),
+            other => other.to_compact(),
+        }
+    }
+}
+
+/// Escapes a string for JSON format
+fn escape_json_string(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            '\t' => "\\t".to_string(),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person(name: &str) -> ResultValue {
+        ResultValue::Node(ResultNode {
+            id: 1,
+            labels: vec!["Person".to_string()],
+            properties: BTreeMap::from([("name".to_string(), ResultValue::String(name.to_string()))]),
+        })
+    }
+
+    #[test]
+    fn compact_numbers_multiple_rows() {
+        let result = QueryResult::new(Vec::new(), vec![vec![person("John")], vec![person("Jane")]]);
+        assert_eq!(
+            result.render(ResultFormat::Compact),
+            "1. (:Person {name: \"John\"})\n2. (:Person {name: \"Jane\"})"
+        );
+    }
+
+    #[test]
+    fn compact_paths_show_relationship_directions() {
+        let node = |id: i64| ResultNode {
+            id,
+            ..ResultNode::default()
+        };
+        let edge = |src_node_id: i64, dst_node_id: i64| ResultEdge {
+            relationship_type: "KNOWS".to_string(),
+            src_node_id,
+            dst_node_id,
+            ..ResultEdge::default()
+        };
+        let path = ResultValue::Path(ResultPath {
+            nodes: vec![node(1), node(2), node(3)],
+            relationships: vec![edge(1, 2), edge(3, 2)],
+        });
+        assert_eq!(path.to_compact(), "()-[:KNOWS]->()<-[:KNOWS]-()");
+    }
+
+    #[test]
+    fn json_renders_entities_and_nulls() {
+        let result = QueryResult::new(Vec::new(), vec![vec![person("John"), ResultValue::Null]]);
+        assert_eq!(
+            result.render(ResultFormat::Json),
+            r#"[[{"type":"node","id":1,"labels":["Person"],"properties":{"name":"John"}},null]]"#
+        );
+        assert_eq!(QueryResult::default().render(ResultFormat::Json), "[]");
+    }
+
+    #[test]
+    fn table_aligns_columns_under_a_header() {
+        let result = QueryResult::new(
+            vec!["name".to_string(), "movies".to_string()],
+            vec![
+                vec![ResultValue::String("Al Pacino".to_string()), ResultValue::Integer(42)],
+                vec![ResultValue::String("Val".to_string()), ResultValue::Integer(7)],
+            ],
+        );
+        assert_eq!(
+            result.render(ResultFormat::Table),
+            "| name      | movies |\n|-----------|--------|\n| Al Pacino | 42     |\n| Val       | 7      |"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_fields_that_need_it() {
+        let result = QueryResult::new(
+            vec!["title".to_string()],
+            vec![vec![ResultValue::String("Heat, \"1995\"".to_string())]],
+        );
+        assert_eq!(result.render(ResultFormat::Csv), "title\n\"Heat, \"\"1995\"\"\"");
+    }
+
+    #[test]
+    fn parses_format_names() {
+        assert_eq!("CSV".parse::<ResultFormat>(), Ok(ResultFormat::Csv));
+        assert!("yaml".parse::<ResultFormat>().is_err());
+    }
+}
@@ -0,0 +1,471 @@
+//! Per-graph request and token quotas, so one team's graph cannot spend the whole model budget.
+//!
+//! A [`GraphQuota`] caps a graph's `/text_to_cypher` requests per hour and model tokens per day.
+//! Quotas are set through the admin API and kept in the shared [`Storage`]; the usage they are
+//! checked against is counted by each server instance over fixed UTC hours and days. A request is
+//! admitted while both counts are under their limits, so the request that reaches the token limit
+//! completes and the next one is refused with a [`QuotaExceeded`] error.
+//!
+//! Each graph with a quota also gets counters of its admitted requests, spent tokens and refusals,
+//! rendered in the Prometheus text format by [`GraphQuotas::render_metrics`].
+
+use crate::storage::{self, Storage, StorageError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Storage namespace holding graph quotas.
+pub const QUOTA_NAMESPACE: &str = "quotas";
+
+const HOUR_SECS: u64 = 60 * 60;
+const DAY_SECS: u64 = 24 * HOUR_SECS;
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// The limits of one graph; an unset limit does not apply.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GraphQuota {
+    /// Requests admitted per UTC hour.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_hour: Option<u64>,
+    /// Model tokens (prompt and completion) spent per UTC day.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_per_day: Option<u64>,
+}
+
+impl GraphQuota {
+    /// Whether the quota sets no limit.
+    #[must_use]
+    pub const fn is_unlimited(&self) -> bool {
+        self.requests_per_hour.is_none() && self.tokens_per_day.is_none()
+    }
+}
+
+/// Which limit of a [`GraphQuota`] was reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaLimit {
+    RequestsPerHour,
+    TokensPerDay,
+}
+
+impl QuotaLimit {
+    /// The limit's name, as in [`GraphQuota`] and the `limit` label of the metrics.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::RequestsPerHour => "requests_per_hour",
+            Self::TokensPerDay => "tokens_per_day",
+        }
+    }
+}
+
+/// A request was refused because its graph reached a limit of its quota.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    pub graph_name: String,
+    pub limit: QuotaLimit,
+    /// The value of the limit.
+    pub quota: u64,
+    /// Seconds until the hour or day ends and the count starts over.
+    pub retry_after_secs: u64,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        let limit = match self.limit {
+            QuotaLimit::RequestsPerHour => "requests per hour",
+            QuotaLimit::TokensPerDay => "tokens per day",
+        };
+        write!(
+            f,
+            "Quota exceeded for graph '{}': {} {limit}; retry in {} seconds",
+            self.graph_name, self.quota, self.retry_after_secs
+        )
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// A graph's quota and what it used of it on this server instance.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct QuotaStatus {
+    pub graph_name: String,
+    pub quota: GraphQuota,
+    /// Requests admitted this UTC hour.
+    pub requests_this_hour: u64,
+    /// Tokens spent this UTC day.
+    pub tokens_today: u64,
+}
+
+/// Usage of one graph in the current hour and day.
+#[derive(Debug, Clone, Copy, Default)]
+struct Window {
+    hour: u64,
+    requests: u64,
+    day: u64,
+    tokens: u64,
+}
+
+impl Window {
+    /// Starts the counts over when `now` is in a later hour or day.
+    const fn roll(
+        &mut self,
+        now: u64,
+    ) {
+        if now / HOUR_SECS != self.hour {
+            self.hour = now / HOUR_SECS;
+            self.requests = 0;
+        }
+        if now / DAY_SECS != self.day {
+            self.day = now / DAY_SECS;
+            self.tokens = 0;
+        }
+    }
+}
+
+/// Totals since startup of one graph, for the metrics.
+#[derive(Debug, Clone, Copy, Default)]
+struct Counters {
+    requests: u64,
+    tokens: u64,
+    requests_per_hour_exceeded: u64,
+    tokens_per_day_exceeded: u64,
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    window: Window,
+    counters: Counters,
+}
+
+impl Usage {
+    /// The limit of `quota` a request at `now` is over, if any, without counting it.
+    fn check(
+        &mut self,
+        graph_name: &str,
+        quota: GraphQuota,
+        now: u64,
+    ) -> Result<(), QuotaExceeded> {
+        self.window.roll(now);
+        let exceeded = |limit, quota, period| QuotaExceeded {
+            graph_name: graph_name.to_string(),
+            limit,
+            quota,
+            retry_after_secs: period - now % period,
+        };
+        if let Some(limit) = quota.requests_per_hour.filter(|&limit| self.window.requests >= limit) {
+            return Err(exceeded(QuotaLimit::RequestsPerHour, limit, HOUR_SECS));
+        }
+        if let Some(limit) = quota.tokens_per_day.filter(|&limit| self.window.tokens >= limit) {
+            return Err(exceeded(QuotaLimit::TokensPerDay, limit, DAY_SECS));
+        }
+        Ok(())
+    }
+
+    /// Counts a request at `now`, unless it is over a limit of `quota`.
+    fn admit(
+        &mut self,
+        graph_name: &str,
+        quota: GraphQuota,
+        now: u64,
+    ) -> Result<(), QuotaExceeded> {
+        if let Err(exceeded) = self.check(graph_name, quota, now) {
+            match exceeded.limit {
+                QuotaLimit::RequestsPerHour => self.counters.requests_per_hour_exceeded += 1,
+                QuotaLimit::TokensPerDay => self.counters.tokens_per_day_exceeded += 1,
+            }
+            return Err(exceeded);
+        }
+        self.window.requests += 1;
+        self.counters.requests += 1;
+        Ok(())
+    }
+}
+
+/// Stores graph quotas and admits requests against them.
+#[derive(Debug)]
+pub struct GraphQuotas {
+    storage: Arc<dyn Storage>,
+    usage: Mutex<BTreeMap<String, Usage>>,
+}
+
+impl GraphQuotas {
+    /// Creates a quota store backed by `storage`.
+    #[must_use]
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            usage: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// The quota of `graph_name`, if it has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the quota cannot be read.
+    pub async fn get(
+        &self,
+        graph_name: &str,
+    ) -> Result<Option<GraphQuota>, StorageError> {
+        storage::get_json(self.storage.as_ref(), QUOTA_NAMESPACE, graph_name).await
+    }
+
+    /// Sets the quota of `graph_name`, replacing any previous one.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the quota cannot be saved.
+    pub async fn set(
+        &self,
+        graph_name: &str,
+        quota: GraphQuota,
+    ) -> Result<(), StorageError> {
+        storage::put_json(self.storage.as_ref(), QUOTA_NAMESPACE, graph_name, &quota, None).await
+    }
+
+    /// Removes the quota of `graph_name`. Returns whether it had one.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the quota cannot be removed.
+    pub async fn remove(
+        &self,
+        graph_name: &str,
+    ) -> Result<bool, StorageError> {
+        self.storage.delete(QUOTA_NAMESPACE, graph_name).await
+    }
+
+    /// Every graph's quota and its usage, by graph name.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if quotas cannot be listed or read.
+    pub async fn list(&self) -> Result<Vec<QuotaStatus>, StorageError> {
+        let mut statuses = Vec::new();
+        for graph_name in self.storage.list_keys(QUOTA_NAMESPACE).await? {
+            if let Some(quota) = self.get(&graph_name).await? {
+                statuses.push(self.status(&graph_name, quota));
+            }
+        }
+        Ok(statuses)
+    }
+
+    /// `quota` of `graph_name` with what the graph used of it.
+    #[must_use]
+    pub fn status(
+        &self,
+        graph_name: &str,
+        quota: GraphQuota,
+    ) -> QuotaStatus {
+        let now = now_secs();
+        let mut window = self.lock().get(graph_name).map(|usage| usage.window).unwrap_or_default();
+        window.roll(now);
+        QuotaStatus {
+            graph_name: graph_name.to_string(),
+            quota,
+            requests_this_hour: window.requests,
+            tokens_today: window.tokens,
+        }
+    }
+
+    /// Admits a request to `graph_name` and counts it, unless the graph reached a limit of its quota.
+    /// A quota that cannot be read admits the request, so a storage outage does not stop serving.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QuotaExceeded`] when the graph reached a limit.
+    pub async fn admit(
+        &self,
+        graph_name: &str,
+    ) -> Result<(), QuotaExceeded> {
+        match self.get(graph_name).await {
+            Ok(Some(quota)) => self.admit_at(graph_name, quota, now_secs()),
+            Ok(None) => Ok(()),
+            Err(e) => {
+                tracing::warn!("Failed to read the quota of graph {graph_name}: {e}; admitting the request");
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether a request to `graph_name` would be admitted now, without counting it. A quota that
+    /// cannot be read admits the request, as [`Self::admit`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QuotaExceeded`] when the graph reached a limit.
+    pub async fn check(
+        &self,
+        graph_name: &str,
+    ) -> Result<(), QuotaExceeded> {
+        match self.get(graph_name).await {
+            Ok(Some(quota)) => self.check_at(graph_name, quota, now_secs()),
+            Ok(None) | Err(_) => Ok(()),
+        }
+    }
+
+    fn check_at(
+        &self,
+        graph_name: &str,
+        quota: GraphQuota,
+        now: u64,
+    ) -> Result<(), QuotaExceeded> {
+        self.lock()
+            .entry(graph_name.to_string())
+            .or_default()
+            .check(graph_name, quota, now)
+    }
+
+    fn admit_at(
+        &self,
+        graph_name: &str,
+        quota: GraphQuota,
+        now: u64,
+    ) -> Result<(), QuotaExceeded> {
+        self.lock()
+            .entry(graph_name.to_string())
+            .or_default()
+            .admit(graph_name, quota, now)
+    }
+
+    /// Counts `tokens` spent by an admitted request to `graph_name`; graphs without a quota are not
+    /// counted.
+    pub fn record_tokens(
+        &self,
+        graph_name: &str,
+        tokens: u64,
+    ) {
+        self.record_tokens_at(graph_name, tokens, now_secs());
+    }
+
+    fn record_tokens_at(
+        &self,
+        graph_name: &str,
+        tokens: u64,
+        now: u64,
+    ) {
+        if let Some(usage) = self.lock().get_mut(graph_name) {
+            usage.window.roll(now);
+            usage.window.tokens = usage.window.tokens.saturating_add(tokens);
+            usage.counters.tokens = usage.counters.tokens.saturating_add(tokens);
+        }
+    }
+
+    /// The counters of the graphs with a quota, in the Prometheus text format.
+    #[must_use]
+    pub fn render_metrics(&self) -> String {
+        let counters: Vec<(String, Counters)> = self
+            .lock()
+            .iter()
+            .map(|(graph_name, usage)| (escape_label(graph_name), usage.counters))
+            .collect();
+        let mut metrics = String::new();
+        let mut family = |name: &str, help: &str, value: fn(&Counters) -> u64| {
+            let _ = writeln!(metrics, "# HELP {name} {help}\n# TYPE {name} counter");
+            for (graph_name, counters) in &counters {
+                let _ = writeln!(metrics, "{name}{{graph=\"{graph_name}\"}} {}", value(counters));
+            }
+        };
+        family(
+            "text_to_cypher_quota_requests_total",
+            "Requests admitted to graphs with a quota.",
+            |counters| counters.requests,
+        );
+        family(
+            "text_to_cypher_quota_tokens_total",
+            "Model tokens spent on graphs with a quota.",
+            |counters| counters.tokens,
+        );
+        let _ = writeln!(
+            metrics,
+            "# HELP text_to_cypher_quota_exceeded_total Requests refused because their graph reached a quota \
+             limit.\n# TYPE text_to_cypher_quota_exceeded_total counter"
+        );
+        for (graph_name, counters) in &counters {
+            for (limit, count) in [
+                (QuotaLimit::RequestsPerHour, counters.requests_per_hour_exceeded),
+                (QuotaLimit::TokensPerDay, counters.tokens_per_day_exceeded),
+            ] {
+                let _ = writeln!(
+                    metrics,
+                    "text_to_cypher_quota_exceeded_total{{graph=\"{graph_name}\",limit=\"{}\"}} {count}",
+                    limit.as_str()
+                );
+            }
+        }
+        metrics
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Usage>> {
+        self.usage.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// `value` as a Prometheus label value: backslashes, quotes and newlines escaped.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn requests_over_a_limit_are_refused_until_the_window_ends() {
+        let quotas = GraphQuotas::new(Arc::new(InMemoryStorage::new()));
+        let quota = GraphQuota {
+            requests_per_hour: Some(2),
+            tokens_per_day: Some(1_000),
+        };
+        quotas.set("sales", quota).await.unwrap();
+        assert_eq!(quotas.get("sales").await.unwrap(), Some(quota));
+        assert_eq!(quotas.admit("hr").await, Ok(()));
+
+        let now = 10 * DAY_SECS + 30 * 60;
+        assert_eq!(quotas.admit_at("sales", quota, now), Ok(()));
+        // Checking a request counts neither the request nor its refusal
+        assert_eq!(quotas.check_at("sales", quota, now), Ok(()));
+        assert_eq!(quotas.admit_at("sales", quota, now), Ok(()));
+        assert_eq!(
+            quotas.check_at("sales", quota, now).map_err(|refused| refused.limit),
+            Err(QuotaLimit::RequestsPerHour)
+        );
+        let refused = quotas.admit_at("sales", quota, now).unwrap_err();
+        assert_eq!(refused.limit, QuotaLimit::RequestsPerHour);
+        assert_eq!(refused.retry_after_secs, 30 * 60);
+        assert_eq!(
+            refused.to_string(),
+            "Quota exceeded for graph 'sales': 2 requests per hour; retry in 1800 seconds"
+        );
+
+        // The next hour admits requests again until the day's tokens are spent
+        let next_hour = now + HOUR_SECS;
+        assert_eq!(quotas.admit_at("sales", quota, next_hour), Ok(()));
+        quotas.record_tokens_at("sales", 1_200, next_hour);
+        let refused = quotas.admit_at("sales", quota, next_hour).unwrap_err();
+        assert_eq!(refused.limit, QuotaLimit::TokensPerDay);
+        assert_eq!(quotas.admit_at("sales", quota, 11 * DAY_SECS), Ok(()));
+
+        let metrics = quotas.render_metrics();
+        assert!(metrics.contains("text_to_cypher_quota_requests_total{graph=\"sales\"} 4\n"));
+        assert!(metrics.contains("text_to_cypher_quota_tokens_total{graph=\"sales\"} 1200\n"));
+        assert!(
+            metrics.contains("text_to_cypher_quota_exceeded_total{graph=\"sales\",limit=\"requests_per_hour\"} 1\n")
+        );
+        assert!(metrics.contains("text_to_cypher_quota_exceeded_total{graph=\"sales\",limit=\"tokens_per_day\"} 1\n"));
+        assert!(!metrics.contains("graph=\"hr\""));
+
+        assert!(quotas.remove("sales").await.unwrap());
+        assert_eq!(quotas.list().await.unwrap(), Vec::new());
+    }
+}
@@ -0,0 +1,236 @@
+//! Admission of `/text_to_cypher` requests to the model, by priority.
+//!
+//! With `LLM_CONCURRENCY` set, at most that many requests call the model at once. The others wait in
+//! a [`PrioritySemaphore`], and a freed slot goes to the longest-waiting [`Priority::Interactive`]
+//! request before any [`Priority::Batch`] one, so chat users are not stuck behind evaluation runs
+//! and scheduled jobs. A request's priority comes from its `priority` option, lowered to `batch`
+//! for the keys listed in `BATCH_API_KEYS`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::oneshot;
+use utoipa::ToSchema;
+
+/// Which requests get a model slot first when every slot is taken.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Evaluation runs, scheduled jobs and other work nobody is waiting on.
+    Batch,
+    /// A user waiting for the answer.
+    #[default]
+    Interactive,
+}
+
+impl Priority {
+    /// The name of the priority, as written in requests and metric labels.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Batch => "batch",
+            Self::Interactive => "interactive",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    available: usize,
+    interactive: VecDeque<oneshot::Sender<()>>,
+    batch: VecDeque<oneshot::Sender<()>>,
+}
+
+impl State {
+    const fn waiters(
+        &mut self,
+        priority: Priority,
+    ) -> &mut VecDeque<oneshot::Sender<()>> {
+        match priority {
+            Priority::Batch => &mut self.batch,
+            Priority::Interactive => &mut self.interactive,
+        }
+    }
+}
+
+/// A counting semaphore whose waiters are served interactive first, then in arrival order.
+#[derive(Debug)]
+pub struct PrioritySemaphore {
+    permits: usize,
+    state: Mutex<State>,
+}
+
+impl PrioritySemaphore {
+    /// A semaphore with `permits` slots.
+    #[must_use]
+    pub fn new(permits: usize) -> Arc<Self> {
+        Arc::new(Self {
+            permits,
+            state: Mutex::new(State {
+                available: permits,
+                ..State::default()
+            }),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// A slot if one is free now.
+    #[must_use]
+    pub fn try_acquire(self: &Arc<Self>) -> Option<Permit> {
+        let mut state = self.lock();
+        if state.available == 0 {
+            return None;
+        }
+        state.available -= 1;
+        drop(state);
+        Some(Permit {
+            semaphore: Arc::clone(self),
+        })
+    }
+
+    /// Waits for a slot, behind the waiters of the same or a higher priority.
+    ///
+    /// Cancel-safe: a waiter dropped before it is served leaves the queue, and one dropped after a
+    /// slot was handed to it passes the slot on.
+    pub async fn acquire(
+        self: &Arc<Self>,
+        priority: Priority,
+    ) -> Permit {
+        if let Some(permit) = self.try_acquire() {
+            return permit;
+        }
+        let (sender, receiver) = oneshot::channel();
+        self.lock().waiters(priority).push_back(sender);
+        let mut waiter = Waiter {
+            receiver,
+            semaphore: Some(Arc::clone(self)),
+        };
+        // The sender is only dropped unsent once this waiter is gone, so the slot always arrives.
+        let _ = (&mut waiter.receiver).await;
+        Permit {
+            semaphore: waiter.semaphore.take().unwrap_or_else(|| Arc::clone(self)),
+        }
+    }
+
+    /// Requests of `priority` waiting for a slot.
+    #[must_use]
+    pub fn queued(
+        &self,
+        priority: Priority,
+    ) -> usize {
+        self.lock()
+            .waiters(priority)
+            .iter()
+            .filter(|sender| !sender.is_closed())
+            .count()
+    }
+
+    /// The slots, slots in use and waiting requests per priority, in the Prometheus text format.
+    #[must_use]
+    pub fn render_metrics(&self) -> String {
+        let available = self.lock().available;
+        let mut out = String::new();
+        out.push_str("# HELP text_to_cypher_llm_slots Requests that may call the model at once.\n");
+        out.push_str("# TYPE text_to_cypher_llm_slots gauge\n");
+        let _ = writeln!(out, "text_to_cypher_llm_slots {}", self.permits);
+        out.push_str("# HELP text_to_cypher_llm_slots_in_use Requests calling the model.\n");
+        out.push_str("# TYPE text_to_cypher_llm_slots_in_use gauge\n");
+        let _ = writeln!(out, "text_to_cypher_llm_slots_in_use {}", self.permits - available);
+        out.push_str("# HELP text_to_cypher_llm_queued Requests waiting for a model slot.\n");
+        out.push_str("# TYPE text_to_cypher_llm_queued gauge\n");
+        for priority in [Priority::Interactive, Priority::Batch] {
+            let _ = writeln!(
+                out,
+                "text_to_cypher_llm_queued{{priority=\"{}\"}} {}",
+                priority.as_str(),
+                self.queued(priority)
+            );
+        }
+        out
+    }
+
+    /// Hands a freed slot to the next live waiter, or returns it to the pool.
+    fn release(&self) {
+        let mut state = self.lock();
+        while let Some(sender) = state.interactive.pop_front().or_else(|| state.batch.pop_front()) {
+            if sender.send(()).is_ok() {
+                return;
+            }
+        }
+        state.available += 1;
+    }
+}
+
+/// A waiter's place in the queue; gives back a slot it was handed but never took.
+struct Waiter {
+    receiver: oneshot::Receiver<()>,
+    semaphore: Option<Arc<PrioritySemaphore>>,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        if let Some(semaphore) = self.semaphore.take() {
+            self.receiver.close();
+            if self.receiver.try_recv().is_ok() {
+                semaphore.release();
+            }
+        }
+    }
+}
+
+/// A model slot, freed when dropped.
+#[derive(Debug)]
+pub struct Permit {
+    semaphore: Arc<PrioritySemaphore>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.semaphore.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn interactive_waiters_are_served_before_batch_ones() {
+        let semaphore = PrioritySemaphore::new(1);
+        let held = semaphore.try_acquire().unwrap();
+        assert!(semaphore.try_acquire().is_none());
+
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut waiters = Vec::new();
+        for (name, priority) in [
+            ("batch", Priority::Batch),
+            ("cancelled", Priority::Interactive),
+            ("interactive", Priority::Interactive),
+        ] {
+            let (semaphore, order_tx) = (Arc::clone(&semaphore), order_tx.clone());
+            waiters.push(tokio::spawn(async move {
+                let _permit = semaphore.acquire(priority).await;
+                order_tx.send(name).unwrap();
+            }));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        waiters.remove(1).abort();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(semaphore.queued(Priority::Interactive), 1);
+        assert_eq!(semaphore.queued(Priority::Batch), 1);
+        assert!(semaphore.render_metrics().contains("text_to_cypher_llm_slots_in_use 1\n"));
+
+        drop(held);
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        assert_eq!(order_rx.recv().await, Some("interactive"));
+        assert_eq!(order_rx.recv().await, Some("batch"));
+        assert!(semaphore.try_acquire().is_some());
+    }
+}
@@ -0,0 +1,142 @@
+//! Choosing the graph a question is about, for requests that name none.
+//!
+//! A request without a `graph_name` is routed before its schema is discovered:
+//! [`crate::core::route_question`] shows the model each candidate graph — its name, its alias
+//! description and the labels and relationship types of its cached schema, when known — and the
+//! model names the graph holding the data. Candidates are the request's `graphs`, or every graph
+//! on the server.
+
+use crate::schema::discovery::Schema;
+use std::fmt::Write as _;
+
+/// A graph a question may be routed to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphCandidate {
+    pub name: String,
+    /// What the graph holds, e.g. from its `GRAPH_ALIASES` entry.
+    pub description: Option<String>,
+    /// The graph's schema, when one is cached; only its labels and relationship types are shown.
+    pub schema: Option<String>,
+}
+
+impl GraphCandidate {
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_description(
+        mut self,
+        description: Option<String>,
+    ) -> Self {
+        self.description = description;
+        self
+    }
+
+    #[must_use]
+    pub fn with_schema(
+        mut self,
+        schema: Option<String>,
+    ) -> Self {
+        self.schema = schema;
+        self
+    }
+}
+
+/// The candidates as the routing prompt lists them, one line per graph.
+#[must_use]
+pub fn describe(candidates: &[GraphCandidate]) -> String {
+    let mut out = String::new();
+    for candidate in candidates {
+        let _ = write!(out, "- {}", candidate.name);
+        if let Some(description) = candidate.description.as_deref().filter(|d| !d.trim().is_empty()) {
+            let _ = write!(out, ": {}", description.trim());
+        }
+        if let Some(outline) = candidate.schema.as_deref().and_then(outline) {
+            let _ = write!(out, " ({outline})");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The labels and relationship types of a schema, or `None` if it is not a discovered schema.
+fn outline(schema: &str) -> Option<String> {
+    let schema: Schema = serde_json::from_str(schema).ok()?;
+    let labels: Vec<&str> = schema.entities.iter().map(|entity| entity.label.as_str()).collect();
+    let mut relations: Vec<&str> = schema.relations.iter().map(|relation| relation.label.as_str()).collect();
+    relations.sort_unstable();
+    relations.dedup();
+    Some(format!(
+        "labels: {}; relationships: {}",
+        labels.join(", "),
+        relations.join(", ")
+    ))
+}
+
+/// The candidate the model's reply names: the reply itself, ignoring case and surrounding quotes,
+/// or else the longest candidate name it mentions. `None` when it names none, e.g. `NONE`.
+#[must_use]
+pub fn parse_choice<'a>(
+    reply: &str,
+    candidates: &'a [GraphCandidate],
+) -> Option<&'a str> {
+    let reply = reply.trim().trim_matches(|c: char| matches!(c, '`' | '"' | '\'' | '.' | '*'));
+    candidates
+        .iter()
+        .find(|candidate| candidate.name.eq_ignore_ascii_case(reply))
+        .or_else(|| {
+            candidates
+                .iter()
+                .filter(|candidate| mentions(reply, &candidate.name))
+                .max_by_key(|candidate| candidate.name.len())
+        })
+        .map(|candidate| candidate.name.as_str())
+}
+
+/// Whether `text` has `name` as a whole word.
+fn mentions(
+    text: &str,
+    name: &str,
+) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    text.match_indices(name).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + name.len()..].chars().next();
+        !before.is_some_and(is_word) && !after.is_some_and(is_word)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates_are_described_and_chosen_by_name() {
+        let candidates = vec![
+            GraphCandidate::new("movies").with_schema(Some(
+                r#"{"entities": [{"label": "Movie"}, {"label": "Person"}], "relations": [
+                    {"label": "ACTED_IN", "source": "Person", "target": "Movie"}]}"#
+                    .to_string(),
+            )),
+            GraphCandidate::new("movies_archive").with_description(Some("Movies before 1950".to_string())),
+            GraphCandidate::new("crm"),
+        ];
+        assert_eq!(
+            describe(&candidates),
+            "- movies (labels: Movie, Person; relationships: ACTED_IN)\n- movies_archive: Movies before 1950\n- crm\n"
+        );
+
+        assert_eq!(parse_choice("`CRM`", &candidates), Some("crm"));
+        assert_eq!(
+            parse_choice("The graph is movies_archive.", &candidates),
+            Some("movies_archive")
+        );
+        assert_eq!(parse_choice("movies", &candidates), Some("movies"));
+        assert_eq!(parse_choice("NONE", &candidates), None);
+    }
+}
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "server")]
 use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize, strum::EnumString, strum::Display)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, strum::EnumString, strum::Display)]
 #[cfg_attr(feature = "server", derive(ToSchema))]
 pub enum AttributeType {
     String,
@@ -13,6 +13,8 @@ pub enum AttributeType {
     DateTime,
     List,
     Map,
+    /// A `vecf32` embedding; `typeof` reports it as `Vectorf32`.
+    #[strum(to_string = "Vector", serialize = "Vectorf32")]
     Vector,
     Point,
 }
@@ -23,14 +25,31 @@ pub struct Attribute {
     pub name: String,
     #[serde(rename = "type")]
     pub r#type: AttributeType,
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub count: i64,
-    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub unique: bool,
-    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub required: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub examples: Option<Vec<String>>,
+    /// How the sampled values write dates, e.g. `YYYY-MM-DD`, when they are dates stored as text
+    /// or numbers; see [`detect_date_format`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// What a numeric value measures, e.g. `USD millions`, written into the schema by hand or from
+    /// [`super::units::AttributeUnits`]; answers state it with the number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// Number of components of a [`AttributeType::Vector`] property; query vectors must match it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimension: Option<usize>,
+    /// Whether a vector index covers this property, so `db.idx.vector.queryNodes` can search it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub vector_index: bool,
+    /// What the property holds, written by hand or by [`crate::core::enrich_schema`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 impl Attribute {
@@ -49,6 +68,11 @@ impl Attribute {
             unique,
             required,
             examples: None,
+            date_format: None,
+            unit: None,
+            dimension: None,
+            vector_index: false,
+            description: None,
         }
     }
 
@@ -70,10 +94,121 @@ impl Attribute {
             unique,
             required,
             examples,
+            date_format: None,
+            unit: None,
+            dimension: None,
+            vector_index: false,
+            description: None,
         }
     }
 }
 
+/// Text date layouts recognized in sampled values, with `9` standing for any digit.
+const DATE_SHAPES: &[(&str, &str)] = &[
+    ("9999-99-99", "YYYY-MM-DD"),
+    ("9999/99/99", "YYYY/MM/DD"),
+    ("9999-99-99 99:99:99", "YYYY-MM-DD HH:MM:SS"),
+    ("9999-99-99T99:99:99", "YYYY-MM-DDTHH:MM:SS"),
+    ("9999-99-99T99:99", "YYYY-MM-DDTHH:MM"),
+    ("99.99.9999", "DD.MM.YYYY"),
+];
+
+/// The layout reported for slashed dates whose day and month are both 12 or less.
+const AMBIGUOUS_SLASHED: &str = "DD/MM/YYYY or MM/DD/YYYY";
+
+/// The layout of a date written as text, e.g. `YYYY-MM-DD` for `"2024-03-01"`.
+///
+/// `None` when `value` is not a date. Slashed dates are told apart by a day above 12; when neither
+/// part is, the layout is reported as `DD/MM/YYYY or MM/DD/YYYY`.
+#[must_use]
+pub fn date_format(value: &str) -> Option<&'static str> {
+    let shape: String = value.chars().map(|c| if c.is_ascii_digit() { '9' } else { c }).collect();
+    let number = |range: std::ops::Range<usize>| value.get(range).and_then(|part| part.parse::<u32>().ok());
+
+    if shape == "99/99/9999" {
+        let (first, second) = (number(0..2)?, number(3..5)?);
+        return match (first, second) {
+            (13..=31, 1..=12) => Some("DD/MM/YYYY"),
+            (1..=12, 13..=31) => Some("MM/DD/YYYY"),
+            (1..=12, 1..=12) => Some(AMBIGUOUS_SLASHED),
+            _ => None,
+        };
+    }
+    if shape == "99.99.9999" {
+        return (number(0..2).is_some_and(|day| (1..=31).contains(&day))
+            && number(3..5).is_some_and(|month| (1..=12).contains(&month)))
+        .then_some("DD.MM.YYYY");
+    }
+
+    // Year-first layouts; ISO datetimes may carry fractional seconds and an offset
+    let valid = number(5..7).is_some_and(|month| (1..=12).contains(&month))
+        && number(8..10).is_some_and(|day| (1..=31).contains(&day));
+    if shape.len() > 19 && shape.starts_with("9999-99-99T99:99:99") {
+        let rest = &value[19..];
+        return (valid && rest.chars().all(|c| c.is_ascii_digit() || ".:+-Z".contains(c)))
+            .then_some("ISO 8601 (YYYY-MM-DDTHH:MM:SS with fraction or offset)");
+    }
+    DATE_SHAPES
+        .iter()
+        .find(|(date_shape, _)| *date_shape == shape)
+        .and_then(|(_, format)| valid.then_some(*format))
+}
+
+/// The date layout shared by every sampled value of the attribute `name`.
+///
+/// `None` when they are not all dates in one layout. Numbers count as dates only when the name suggests a date or
+/// time (`created_at`, `birthDate`, ...): ten digits as Unix seconds, thirteen as Unix
+/// milliseconds, and eight as `YYYYMMDD`.
+#[must_use]
+pub fn detect_date_format(
+    name: &str,
+    examples: &[String],
+) -> Option<&'static str> {
+    let lower = name.to_lowercase();
+    let temporal_name = ["date", "time", "day", "created", "updated", "modified"]
+        .iter()
+        .any(|word| lower.contains(word))
+        || lower.ends_with("_at")
+        || name.ends_with("At");
+
+    let mut detected: Option<&'static str> = None;
+    for example in examples {
+        let format = if temporal_name && !example.is_empty() && example.bytes().all(|b| b.is_ascii_digit()) {
+            match example.len() {
+                10 => "Unix timestamp (seconds)",
+                13 => "Unix timestamp (milliseconds)",
+                8 => date_format(&format!("{}-{}-{}", &example[..4], &example[4..6], &example[6..]))
+                    .map(|_| "YYYYMMDD")?,
+                _ => return None,
+            }
+        } else {
+            date_format(example)?
+        };
+        detected = Some(match detected {
+            None => format,
+            Some(seen) if seen == format => seen,
+            // An ambiguous slashed date agrees with either resolved order
+            Some(AMBIGUOUS_SLASHED) if matches!(format, "DD/MM/YYYY" | "MM/DD/YYYY") => format,
+            Some(seen @ ("DD/MM/YYYY" | "MM/DD/YYYY")) if format == AMBIGUOUS_SLASHED => seen,
+            Some(_) => return None,
+        });
+    }
+    detected
+}
+
+/// The number of components of a vector written by `toString`, e.g. 3 for `<0.1, 0.2, 0.3>`.
+///
+/// `None` when `value` is not a list of numbers.
+#[must_use]
+pub fn vector_dimension(value: &str) -> Option<usize> {
+    let inner = value.trim().trim_start_matches(['<', '[']).trim_end_matches(['>', ']']);
+    let components = inner.split(',').map(str::trim).collect::<Vec<_>>();
+    components
+        .iter()
+        .all(|component| component.parse::<f64>().is_ok())
+        .then_some(components.len())
+}
+
 impl std::fmt::Display for Attribute {
     fn fmt(
         &self,
@@ -86,3 +221,83 @@ impl std::fmt::Display for Attribute {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn examples(values: &[&str]) -> Vec<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn recognizes_text_date_layouts() {
+        assert_eq!(date_format("2024-03-01"), Some("YYYY-MM-DD"));
+        assert_eq!(date_format("2024/03/01"), Some("YYYY/MM/DD"));
+        assert_eq!(date_format("2024-03-01 12:30:00"), Some("YYYY-MM-DD HH:MM:SS"));
+        assert_eq!(date_format("2024-03-01T12:30:00"), Some("YYYY-MM-DDTHH:MM:SS"));
+        assert_eq!(
+            date_format("2024-03-01T12:30:00.250+02:00"),
+            Some("ISO 8601 (YYYY-MM-DDTHH:MM:SS with fraction or offset)")
+        );
+        assert_eq!(date_format("25/03/2024"), Some("DD/MM/YYYY"));
+        assert_eq!(date_format("03/25/2024"), Some("MM/DD/YYYY"));
+        assert_eq!(date_format("03/04/2024"), Some("DD/MM/YYYY or MM/DD/YYYY"));
+        assert_eq!(date_format("01.03.2024"), Some("DD.MM.YYYY"));
+
+        assert_eq!(date_format("2024-13-01"), None);
+        assert_eq!(date_format("1234-5678"), None);
+        assert_eq!(date_format("Heat"), None);
+    }
+
+    #[test]
+    fn detects_one_layout_across_examples() {
+        assert_eq!(
+            detect_date_format("released", &examples(&["1995-12-15", "1999-03-31"])),
+            Some("YYYY-MM-DD")
+        );
+        // The unambiguous sample settles the day/month order
+        assert_eq!(
+            detect_date_format("signed", &examples(&["03/04/2024", "25/03/2024"])),
+            Some("DD/MM/YYYY")
+        );
+        assert_eq!(
+            detect_date_format("mixed", &examples(&["1995-12-15", "15/12/1995"])),
+            None
+        );
+        assert_eq!(detect_date_format("title", &examples(&["Heat", "1995-12-15"])), None);
+        assert_eq!(detect_date_format("released", &[]), None);
+    }
+
+    #[test]
+    fn numbers_are_dates_only_for_temporal_names() {
+        assert_eq!(
+            detect_date_format("created_at", &examples(&["1700000000"])),
+            Some("Unix timestamp (seconds)")
+        );
+        assert_eq!(
+            detect_date_format("updatedAt", &examples(&["1700000000000"])),
+            Some("Unix timestamp (milliseconds)")
+        );
+        assert_eq!(
+            detect_date_format("birthDate", &examples(&["19700101"])),
+            Some("YYYYMMDD")
+        );
+        assert_eq!(detect_date_format("phone", &examples(&["1700000000"])), None);
+        assert_eq!(detect_date_format("birthDate", &examples(&["19701301"])), None);
+    }
+
+    #[test]
+    fn reads_vector_types_and_dimensions() {
+        assert!(matches!(
+            "Vectorf32".parse::<AttributeType>(),
+            Ok(AttributeType::Vector)
+        ));
+        assert_eq!(AttributeType::Vector.to_string(), "Vector");
+
+        assert_eq!(vector_dimension("<0.1, -0.25, 3e-2>"), Some(3));
+        assert_eq!(vector_dimension("[1.5]"), Some(1));
+        assert_eq!(vector_dimension("<>"), None);
+        assert_eq!(vector_dimension("<0.1, abc>"), None);
+    }
+}
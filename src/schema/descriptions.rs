@@ -0,0 +1,130 @@
+//! Natural-language descriptions of a schema's labels, relationships and properties.
+//!
+//! Discovery leaves descriptions empty. [`crate::core::enrich_schema`] asks the model to write
+//! them, as a [`SchemaDescriptions`] JSON document, and applies them with
+//! [`SchemaDescriptions::apply`]. Descriptions already in the schema, such as hand-written ones,
+//! are kept.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use super::attribute::Attribute;
+use super::discovery::Schema;
+
+/// The description of a label or relationship type and of its properties.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Described {
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Property descriptions by property name.
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// Descriptions by label and by relationship type, as the model writes them:
+/// `{"entities": {"Person": {"description": "...", "attributes": {"name": "..."}}}, "relations": {...}}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct SchemaDescriptions {
+    #[serde(default)]
+    pub entities: BTreeMap<String, Described>,
+    #[serde(default)]
+    pub relations: BTreeMap<String, Described>,
+}
+
+impl SchemaDescriptions {
+    /// Parses the model's reply: the JSON document alone, or inside a code fence or other text.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reply holds no JSON object of this shape.
+    pub fn parse(reply: &str) -> Result<Self, String> {
+        let start = reply.find('{').ok_or("the reply holds no JSON object")?;
+        let end = reply
+            .rfind('}')
+            .filter(|end| *end > start)
+            .ok_or("the reply holds no JSON object")?;
+        serde_json::from_str(&reply[start..=end]).map_err(|e| format!("the reply is not valid descriptions: {e}"))
+    }
+
+    /// Fills the empty descriptions of `schema`; a relationship type's description applies to each
+    /// of its patterns. Returns how many descriptions were filled.
+    pub fn apply(
+        &self,
+        schema: &mut Schema,
+    ) -> usize {
+        let mut filled = 0;
+        for entity in &mut schema.entities {
+            if let Some(described) = self.entities.get(&entity.label) {
+                filled += fill(&mut entity.description, described.description.as_deref());
+                filled += fill_attributes(&mut entity.attributes, described);
+            }
+        }
+        for relation in &mut schema.relations {
+            if let Some(described) = self.relations.get(&relation.label) {
+                filled += fill(&mut relation.description, described.description.as_deref());
+                filled += fill_attributes(&mut relation.attributes, described);
+            }
+        }
+        filled
+    }
+}
+
+/// Sets `slot` to `description` when it is empty and `description` is not blank; 1 if it did.
+fn fill(
+    slot: &mut Option<String>,
+    description: Option<&str>,
+) -> usize {
+    match description.map(str::trim) {
+        Some(description) if slot.is_none() && !description.is_empty() => {
+            *slot = Some(description.to_string());
+            1
+        }
+        _ => 0,
+    }
+}
+
+fn fill_attributes(
+    attributes: &mut [Attribute],
+    described: &Described,
+) -> usize {
+    attributes
+        .iter_mut()
+        .map(|attribute| {
+            let description = described.attributes.get(&attribute.name).map(String::as_str);
+            fill(&mut attribute.description, description)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_only_missing_descriptions() {
+        let mut schema: Schema = serde_json::from_str(
+            r#"{"entities": [
+                {"label": "Person", "attributes": [{"name": "name", "type": "String"},
+                    {"name": "born", "type": "Integer", "description": "Year of birth"}]}
+            ], "relations": [
+                {"label": "ACTED_IN", "source": "Person", "target": "Movie"},
+                {"label": "ACTED_IN", "source": "Person", "target": "Show"}
+            ]}"#,
+        )
+        .unwrap();
+        let descriptions = SchemaDescriptions::parse(
+            "```json\n{\"entities\": {\"Person\": {\"description\": \"An actor or director\", \"attributes\": \
+             {\"name\": \"Full name\", \"born\": \"Birth year\"}}, \"Ghost\": {\"description\": \"?\"}},\n\
+             \"relations\": {\"ACTED_IN\": {\"description\": \"  \"}}}\n```",
+        )
+        .unwrap();
+
+        assert_eq!(descriptions.apply(&mut schema), 2);
+        let person = &schema.entities[0];
+        assert_eq!(person.description.as_deref(), Some("An actor or director"));
+        assert_eq!(person.attributes[0].description.as_deref(), Some("Full name"));
+        assert_eq!(person.attributes[1].description.as_deref(), Some("Year of birth"));
+        assert_eq!(schema.relations[0].description, None);
+        assert!(SchemaDescriptions::parse("I cannot help with that").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Differences between two discovered schemas, e.g. a cached schema and a fresh discovery.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+use super::attribute::{Attribute, AttributeType};
+use super::discovery::Schema;
+use super::relation::Relation;
+
+/// An attribute whose type differs between the two schemas.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct TypeChange {
+    pub name: String,
+    pub from: AttributeType,
+    pub to: AttributeType,
+}
+
+/// The attribute changes of a label or relationship present in both schemas.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct AttributeChanges {
+    /// A node label, or a relationship pattern such as `(:Person)-[:ACTED_IN]->(:Movie)`.
+    pub owner: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retyped: Vec<TypeChange>,
+}
+
+/// What changed from an old schema to a new one. Labels and attributes are listed by name, and
+/// relationships by pattern, each sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct SchemaDiff {
+    pub added_labels: Vec<String>,
+    pub removed_labels: Vec<String>,
+    pub added_relations: Vec<String>,
+    pub removed_relations: Vec<String>,
+    /// Labels and relationships in both schemas whose attributes changed.
+    pub attributes: Vec<AttributeChanges>,
+}
+
+impl SchemaDiff {
+    /// The changes from `old` to `new`.
+    #[must_use]
+    pub fn between(
+        old: &Schema,
+        new: &Schema,
+    ) -> Self {
+        fn labels(schema: &Schema) -> Vec<(String, &[Attribute])> {
+            schema
+                .entities
+                .iter()
+                .map(|entity| (entity.label.clone(), entity.attributes.as_slice()))
+                .collect()
+        }
+        fn relations(schema: &Schema) -> Vec<(String, &[Attribute])> {
+            schema
+                .relations
+                .iter()
+                .map(|relation| (relation_key(relation), relation.attributes.as_slice()))
+                .collect()
+        }
+
+        let (added_labels, removed_labels, mut attributes) = compare(&labels(old), &labels(new));
+        let (added_relations, removed_relations, relation_attributes) = compare(&relations(old), &relations(new));
+        attributes.extend(relation_attributes);
+        Self {
+            added_labels,
+            removed_labels,
+            added_relations,
+            removed_relations,
+            attributes,
+        }
+    }
+
+    /// Whether the schemas are the same, as far as labels, relationships and attributes go.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added_labels.is_empty()
+            && self.removed_labels.is_empty()
+            && self.added_relations.is_empty()
+            && self.removed_relations.is_empty()
+            && self.attributes.is_empty()
+    }
+}
+
+/// How a relationship is told apart: its pattern, or its type when discovery did not pair it
+/// with labels.
+fn relation_key(relation: &Relation) -> String {
+    if relation.pattern.is_empty() {
+        format!("[:{}]", relation.label)
+    } else {
+        relation.pattern.clone()
+    }
+}
+
+/// The owners only in `new`, those only in `old`, and the attribute changes of those in both.
+fn compare(
+    old: &[(String, &[Attribute])],
+    new: &[(String, &[Attribute])],
+) -> (Vec<String>, Vec<String>, Vec<AttributeChanges>) {
+    fn find<'a>(
+        owners: &[(String, &'a [Attribute])],
+        name: &str,
+    ) -> Option<&'a [Attribute]> {
+        owners
+            .iter()
+            .find(|(owner, _)| owner == name)
+            .map(|(_, attributes)| *attributes)
+    }
+    let mut added: Vec<String> = new
+        .iter()
+        .filter(|(owner, _)| find(old, owner).is_none())
+        .map(|(owner, _)| owner.clone())
+        .collect();
+    let mut removed: Vec<String> = old
+        .iter()
+        .filter(|(owner, _)| find(new, owner).is_none())
+        .map(|(owner, _)| owner.clone())
+        .collect();
+    let mut changes: Vec<AttributeChanges> = new
+        .iter()
+        .filter_map(|(owner, new_attributes)| {
+            let old_attributes = find(old, owner)?;
+            let changes = attribute_changes(owner, old_attributes, new_attributes);
+            (!changes.added.is_empty() || !changes.removed.is_empty() || !changes.retyped.is_empty()).then_some(changes)
+        })
+        .collect();
+    added.sort();
+    removed.sort();
+    changes.sort_by(|a, b| a.owner.cmp(&b.owner));
+    (added, removed, changes)
+}
+
+fn attribute_changes(
+    owner: &str,
+    old: &[Attribute],
+    new: &[Attribute],
+) -> AttributeChanges {
+    fn find<'a>(
+        attributes: &'a [Attribute],
+        name: &str,
+    ) -> Option<&'a AttributeType> {
+        attributes.iter().find(|a| a.name == name).map(|a| &a.r#type)
+    }
+    let mut added: Vec<String> = new
+        .iter()
+        .filter(|a| find(old, &a.name).is_none())
+        .map(|a| a.name.clone())
+        .collect();
+    let mut removed: Vec<String> = old
+        .iter()
+        .filter(|a| find(new, &a.name).is_none())
+        .map(|a| a.name.clone())
+        .collect();
+    let mut retyped: Vec<TypeChange> = new
+        .iter()
+        .filter_map(|a| {
+            let from = find(old, &a.name)?;
+            (*from != a.r#type).then(|| TypeChange {
+                name: a.name.clone(),
+                from: from.clone(),
+                to: a.r#type.clone(),
+            })
+        })
+        .collect();
+    added.sort();
+    removed.sort();
+    retyped.sort_by(|a, b| a.name.cmp(&b.name));
+    AttributeChanges {
+        owner: owner.to_string(),
+        added,
+        removed,
+        retyped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(json: &str) -> Schema {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn lists_added_removed_and_retyped_parts() {
+        let old = schema(
+            r#"{"entities": [
+                {"label": "Person", "attributes": [{"name": "name", "type": "String"}, {"name": "age", "type": "String"}]},
+                {"label": "Legacy"}
+            ], "relations": [
+                {"label": "KNOWS", "source": "Person", "target": "Person", "pattern": "(:Person)-[:KNOWS]->(:Person)"}
+            ]}"#,
+        );
+        let new = schema(
+            r#"{"entities": [
+                {"label": "Person", "attributes": [{"name": "name", "type": "String"}, {"name": "age", "type": "Integer"},
+                    {"name": "email", "type": "String"}]},
+                {"label": "Movie"}
+            ], "relations": [
+                {"label": "KNOWS", "source": "Person", "target": "Person", "pattern": "(:Person)-[:KNOWS]->(:Person)"},
+                {"label": "ACTED_IN"}
+            ]}"#,
+        );
+
+        let diff = SchemaDiff::between(&old, &new);
+        assert_eq!(diff.added_labels, vec!["Movie"]);
+        assert_eq!(diff.removed_labels, vec!["Legacy"]);
+        assert_eq!(diff.added_relations, vec!["[:ACTED_IN]"]);
+        assert!(diff.removed_relations.is_empty());
+        assert_eq!(
+            diff.attributes,
+            vec![AttributeChanges {
+                owner: "Person".to_string(),
+                added: vec!["email".to_string()],
+                removed: Vec::new(),
+                retyped: vec![TypeChange {
+                    name: "age".to_string(),
+                    from: AttributeType::String,
+                    to: AttributeType::Integer,
+                }],
+            }]
+        );
+        assert!(!diff.is_empty());
+        assert!(SchemaDiff::between(&new, &new).is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 use std::time::Instant;
 
 use crate::formatter::rows_lossy;
+use crate::pool::CONNECTION_POOL_SIZE;
 use falkordb::{AsyncGraph, FalkorDBError, FalkorValue};
 use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
@@ -8,16 +9,81 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 use crate::schema::{
-    attribute::{Attribute, AttributeType},
+    attribute::{Attribute, AttributeType, detect_date_format, vector_dimension},
     entity::Entity,
-    relation::Relation,
+    relation::{Cardinality, Relation},
 };
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Characters of an example value kept in the schema; longer values (descriptions, documents) are
+/// cut, as the model needs their format, not their content.
+const MAX_EXAMPLE_CHARS: usize = 100;
+
+/// Discovery queries in flight at once: one per pooled connection.
+const DISCOVERY_CONCURRENCY: usize = CONNECTION_POOL_SIZE.get() as usize;
+
+/// Nodes (or relationships) sampled per label when [`DiscoveryOptions`] are not set.
+pub const DEFAULT_SAMPLE_SIZE: usize = 100;
+
+/// How much of a graph schema discovery reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[serde(default)]
+pub struct DiscoveryOptions {
+    /// Nodes (or relationships) sampled per label for attribute types, example values and
+    /// cardinalities (at least 1).
+    pub sample_size: usize,
+    /// Whether each relationship type is paired with the labels it connects. Pairing scans every
+    /// relationship of the type, so huge graphs can turn it off; relations are then listed
+    /// without `source` and `target`.
+    pub pair_relationships: bool,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            sample_size: DEFAULT_SAMPLE_SIZE,
+            pair_relationships: true,
+        }
+    }
+}
+
+/// A discovery milestone, reported through the callback of
+/// [`Schema::discover_from_graph_with_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoveryProgress {
+    /// Attributes of `done` of `total` node labels have been collected.
+    Labels { done: usize, total: usize },
+    /// `done` of `total` relationship probes have finished; each relationship type is probed twice
+    /// (attributes, then endpoints).
+    Relations { done: usize, total: usize },
+}
+
+impl std::fmt::Display for DiscoveryProgress {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match *self {
+            Self::Labels { done, total } => write!(f, "discovered {done}/{total} labels"),
+            Self::Relations { done, total } => write!(f, "probing relations {}%", done * 100 / total.max(1)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[cfg_attr(feature = "server", derive(ToSchema))]
 pub struct Schema {
     pub entities: Vec<Entity>,
     pub relations: Vec<Relation>,
+    /// Label sets carried together by multi-label nodes (e.g. `["Customer", "Person"]`), each sorted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub label_combinations: Vec<Vec<String>>,
+    /// Operational indexes, so queries can anchor on indexed properties.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub indexes: Vec<Index>,
+    /// Operational constraints, including composite keys no single attribute can show.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<Constraint>,
 }
 
 impl std::fmt::Display for Schema {
@@ -39,6 +105,9 @@ impl Schema {
         Self {
             entities: Vec::new(),
             relations: Vec::new(),
+            label_combinations: Vec::new(),
+            indexes: Vec::new(),
+            constraints: Vec::new(),
         }
     }
 
@@ -61,27 +130,12 @@ impl Schema {
         label: &str,
         sample_size: usize,
     ) -> Result<Vec<Attribute>, FalkorDBError> {
-        let query = format!(
-            r"
-            MATCH (a:{label})
-            CALL {{
-                WITH a
-                RETURN [k IN keys(a) | [k, typeof(a[k])]] AS types
-            }}
-            WITH types
-            LIMIT {sample_size}
-            UNWIND types AS kt
-            RETURN kt, count(1)
-            ORDER BY kt[0]
-            "
-        );
+        let query = Self::attribute_types_query(label, sample_size, false);
 
-        let mut attributes = Self::collect_attributes(graph, label, &query).await?;
+        let attributes = Self::collect_attributes(graph, label, &query).await?;
 
         // Collect example values for each attribute
-        Self::collect_example_values(graph, label, &mut attributes, sample_size, false).await?;
-
-        Ok(attributes)
+        Ok(Self::collect_example_values(graph, label, attributes, sample_size, false).await)
     }
 
     async fn collect_relationship_attributes(
@@ -89,9 +143,40 @@ impl Schema {
         label: &str,
         sample_size: usize,
     ) -> Result<Vec<Attribute>, FalkorDBError> {
-        let query = format!(
+        let query = Self::attribute_types_query(label, sample_size, true);
+
+        let attributes = Self::collect_attributes(graph, label, &query).await?;
+
+        // Collect example values (e.g. rel_type = "MARRIED_TO") so the model can filter on
+        // structured relationship properties instead of fuzzy-matching free-text fields.
+        Ok(Self::collect_example_values(graph, label, attributes, sample_size, true).await)
+    }
+
+    /// Builds the `MATCH` clause binding `variable` to nodes labeled `label`, or to relationships
+    /// of type `label` when `is_relationship` is set. The label is always backtick-escaped.
+    fn match_clause(
+        variable: &str,
+        label: &str,
+        is_relationship: bool,
+    ) -> String {
+        let escaped_label = Self::escape_property_name(label);
+        if is_relationship {
+            format!("MATCH ()-[{variable}:{escaped_label}]->()")
+        } else {
+            format!("MATCH ({variable}:{escaped_label})")
+        }
+    }
+
+    /// Builds the query sampling the property keys and types of `label`'s nodes or relationships
+    fn attribute_types_query(
+        label: &str,
+        sample_size: usize,
+        is_relationship: bool,
+    ) -> String {
+        let match_clause = Self::match_clause("a", label, is_relationship);
+        format!(
             r"
-            MATCH ()-[a:{label}]->()
+            {match_clause}
             CALL {{
                 WITH a
                 RETURN [k IN keys(a) | [k, typeof(a[k])]] AS types
@@ -102,15 +187,7 @@ impl Schema {
             RETURN kt, count(1)
             ORDER BY kt[0]
             "
-        );
-
-        let mut attributes = Self::collect_attributes(graph, label, &query).await?;
-
-        // Collect example values (e.g. rel_type = "MARRIED_TO") so the model can filter on
-        // structured relationship properties instead of fuzzy-matching free-text fields.
-        Self::collect_example_values(graph, label, &mut attributes, sample_size, true).await?;
-
-        Ok(attributes)
+        )
     }
 
     async fn collect_attributes(
@@ -148,79 +225,104 @@ impl Schema {
         Ok(attributes)
     }
 
-    /// Collects example values for entity attributes to improve schema understanding
-    #[allow(clippy::cognitive_complexity)]
+    /// Collects example values for entity attributes to improve schema understanding, querying up
+    /// to [`DISCOVERY_CONCURRENCY`] attributes at once
     async fn collect_example_values(
-        graph: &mut AsyncGraph,
+        graph: &AsyncGraph,
         label: &str,
-        attributes: &mut [Attribute],
+        attributes: Vec<Attribute>,
         sample_size: usize,
         is_relationship: bool,
-    ) -> Result<(), FalkorDBError> {
+    ) -> Vec<Attribute> {
         // Validate label to prevent injection attacks
         // Labels should start with letter/underscore and contain alphanumeric/underscore
         if !Self::is_valid_identifier(label) {
             tracing::warn!("Skipping example collection for invalid label: {}", label);
-            return Ok(());
+            return attributes;
         }
 
         // Limit the number of examples to collect
         let max_examples = 3.min(sample_size);
 
-        for attribute in attributes {
-            // Validate attribute name to prevent injection
-            // Be permissive but safe - allow common valid patterns
-            // Note: More complex property paths are rarely used in actual schemas
-            if !Self::is_valid_property_name(&attribute.name) {
-                tracing::warn!(
-                    "Skipping example collection for attribute '{}' - potentially unsafe characters",
-                    attribute.name
-                );
-                continue;
-            }
+        // `buffered` keeps the attributes in the order they were discovered
+        stream::iter(attributes)
+            .map(|mut attribute| {
+                let mut graph = graph.clone();
+                async move {
+                    Self::collect_attribute_examples(&mut graph, label, &mut attribute, max_examples, is_relationship)
+                        .await;
+                    attribute
+                }
+            })
+            .buffered(DISCOVERY_CONCURRENCY)
+            .collect()
+            .await
+    }
 
-            // Use backtick escaping for property names and labels to prevent injection
-            // Even with validation, this provides defense-in-depth
-            let escaped_name = Self::escape_property_name(&attribute.name);
-            let escaped_label = Self::escape_property_name(label);
-            let match_clause = if is_relationship {
-                format!("MATCH ()-[n:{escaped_label}]->()")
-            } else {
-                format!("MATCH (n:{escaped_label})")
-            };
-            let query = format!(
-                r"{match_clause}
-                WHERE n.{escaped_name} IS NOT NULL
-                RETURN DISTINCT toString(n.{escaped_name}) AS value
-                LIMIT {max_examples}"
+    /// Collects up to `max_examples` distinct values of one attribute; a failed query leaves the
+    /// attribute without examples
+    #[allow(clippy::cognitive_complexity)]
+    async fn collect_attribute_examples(
+        graph: &mut AsyncGraph,
+        label: &str,
+        attribute: &mut Attribute,
+        max_examples: usize,
+        is_relationship: bool,
+    ) {
+        // Validate attribute name to prevent injection
+        // Be permissive but safe - allow common valid patterns
+        // Note: More complex property paths are rarely used in actual schemas
+        if !Self::is_valid_property_name(&attribute.name) {
+            tracing::warn!(
+                "Skipping example collection for attribute '{}' - potentially unsafe characters",
+                attribute.name
             );
+            return;
+        }
 
-            match graph.ro_query(&query).execute().await {
-                Ok(result) => {
-                    let mut examples = Vec::new();
-                    for record in rows_lossy(result.data) {
-                        if let Some(FalkorValue::String(value)) = record.first() {
-                            examples.push(value.clone());
-                        }
-                    }
-                    if !examples.is_empty() {
-                        attribute.examples = Some(examples);
-                        tracing::debug!(
-                            "Collected {} examples for {}.{}: {:?}",
-                            attribute.examples.as_ref().map_or(0, std::vec::Vec::len),
-                            label,
-                            attribute.name,
-                            attribute.examples
-                        );
+        // Use backtick escaping for property names and labels to prevent injection
+        // Even with validation, this provides defense-in-depth
+        let escaped_name = Self::escape_property_name(&attribute.name);
+        let match_clause = Self::match_clause("n", label, is_relationship);
+        let query = format!(
+            r"{match_clause}
+            WHERE n.{escaped_name} IS NOT NULL
+            RETURN DISTINCT toString(n.{escaped_name}) AS value
+            LIMIT {max_examples}"
+        );
+
+        match graph.ro_query(&query).execute().await {
+            Ok(result) => {
+                let mut examples = Vec::new();
+                for record in rows_lossy(result.data) {
+                    if let Some(FalkorValue::String(value)) = record.first() {
+                        examples.push(value.clone());
                     }
                 }
-                Err(e) => {
-                    tracing::warn!("Failed to collect examples for {}.{}: {}", label, attribute.name, e);
+                if matches!(attribute.r#type, AttributeType::Vector) {
+                    // Embeddings are hundreds of numbers; only their length helps write a query
+                    attribute.dimension = examples.first().and_then(|example| vector_dimension(example));
+                } else if !examples.is_empty() {
+                    // Native temporal values are compared with date functions, not as text
+                    if !matches!(attribute.r#type, AttributeType::DateTime) {
+                        attribute.date_format = detect_date_format(&attribute.name, &examples).map(str::to_string);
+                    }
+                    let mut examples: Vec<String> = examples.iter().map(|example| cap_example(example)).collect();
+                    examples.dedup();
+                    attribute.examples = Some(examples);
+                    tracing::debug!(
+                        "Collected {} examples for {}.{}: {:?}",
+                        attribute.examples.as_ref().map_or(0, std::vec::Vec::len),
+                        label,
+                        attribute.name,
+                        attribute.examples
+                    );
                 }
             }
+            Err(e) => {
+                tracing::warn!("Failed to collect examples for {}.{}: {}", label, attribute.name, e);
+            }
         }
-
-        Ok(())
     }
 
     /// Validates that an identifier (label, relationship type) is safe to use in queries
@@ -262,8 +364,8 @@ impl Schema {
         allowed && no_sql_comments && no_cypher_comment && no_semicolon && no_backtick && no_union
     }
 
-    /// Escapes a property name for safe use in Cypher queries using backtick notation
-    /// This provides defense-in-depth even with prior validation
+    /// Escapes a property name, label or relationship type for safe use in Cypher queries using
+    /// backtick notation. This provides defense-in-depth even with prior validation
     fn escape_property_name(name: &str) -> String {
         // For Cypher, we use backticks to escape property names
         // Any internal backticks are escaped by doubling them
@@ -286,6 +388,127 @@ impl Schema {
         Ok(entity_labels)
     }
 
+    /// Returns the distinct label sets (sorted) of multi-label nodes carrying `label`
+    async fn collect_label_combinations(
+        graph: &mut AsyncGraph,
+        label: &str,
+    ) -> Result<Vec<Vec<String>>, FalkorDBError> {
+        let query = format!(
+            "MATCH (n:{}) WHERE size(labels(n)) > 1 RETURN DISTINCT labels(n) LIMIT {MAX_LABEL_COMBINATIONS}",
+            Self::escape_property_name(label)
+        );
+        let result = graph.ro_query(&query).execute().await?;
+        Ok(rows_lossy(result.data)
+            .into_iter()
+            .filter_map(|record| record.into_iter().next().and_then(label_set))
+            .collect())
+    }
+
+    /// Records multi-label combinations on the schema and on each participating entity
+    fn apply_label_combinations(
+        &mut self,
+        combinations: Vec<Vec<String>>,
+    ) {
+        for combination in combinations {
+            if combination.len() < 2 || self.label_combinations.contains(&combination) {
+                continue;
+            }
+            for entity in &mut self.entities {
+                if combination.contains(&entity.label) {
+                    for other in combination.iter().filter(|l| **l != entity.label) {
+                        if !entity.co_labels.contains(other) {
+                            entity.co_labels.push(other.clone());
+                        }
+                    }
+                    entity.co_labels.sort();
+                }
+            }
+            self.label_combinations.push(combination);
+        }
+        self.label_combinations.sort();
+    }
+
+    /// Lists the graph's operational constraints
+    async fn get_constraints(graph: &mut AsyncGraph) -> Result<Vec<Constraint>, FalkorDBError> {
+        let result = graph.ro_query("CALL db.constraints()").execute().await?;
+        let header = result.header.clone();
+        Ok(rows_lossy(result.data)
+            .into_iter()
+            .filter_map(|record| Constraint::from_record(&header, &record))
+            .collect())
+    }
+
+    /// Marks attributes covered by single-property unique constraints as `unique`, and attributes
+    /// covered by mandatory constraints as `required`
+    fn apply_constraints(
+        &mut self,
+        constraints: &[Constraint],
+    ) {
+        for constraint in constraints {
+            let attributes = if constraint.on_relationships {
+                self.relations
+                    .iter_mut()
+                    .filter(|relation| relation.label == constraint.label)
+                    .flat_map(|relation| relation.attributes.iter_mut())
+                    .collect::<Vec<_>>()
+            } else {
+                self.entities
+                    .iter_mut()
+                    .filter(|entity| entity.label == constraint.label)
+                    .flat_map(|entity| entity.attributes.iter_mut())
+                    .collect::<Vec<_>>()
+            };
+            for attribute in attributes {
+                if !constraint.properties.contains(&attribute.name) {
+                    continue;
+                }
+                match constraint.kind {
+                    // A composite key is only unique as a whole, so no single attribute is marked.
+                    ConstraintKind::Unique if constraint.properties.len() == 1 => attribute.unique = true,
+                    ConstraintKind::Unique => {}
+                    ConstraintKind::Mandatory => attribute.required = true,
+                }
+            }
+        }
+    }
+
+    /// Lists the graph's operational indexes
+    async fn get_indexes(graph: &mut AsyncGraph) -> Result<Vec<Index>, FalkorDBError> {
+        let result = graph.ro_query("CALL db.indexes()").execute().await?;
+        let header = result.header.clone();
+        Ok(rows_lossy(result.data)
+            .into_iter()
+            .flat_map(|record| Index::from_record(&header, &record))
+            .collect())
+    }
+
+    /// Marks the attributes covered by vector indexes as `vector_index`
+    fn apply_vector_indexes(
+        &mut self,
+        indexes: &[Index],
+    ) {
+        for index in indexes.iter().filter(|index| index.kind == IndexKind::Vector) {
+            let attributes: Vec<&mut Attribute> = if index.on_relationships {
+                self.relations
+                    .iter_mut()
+                    .filter(|relation| relation.label == index.label)
+                    .flat_map(|relation| relation.attributes.iter_mut())
+                    .collect()
+            } else {
+                self.entities
+                    .iter_mut()
+                    .filter(|entity| entity.label == index.label)
+                    .flat_map(|entity| entity.attributes.iter_mut())
+                    .collect()
+            };
+            for attribute in attributes {
+                if index.properties.contains(&attribute.name) {
+                    attribute.vector_index = true;
+                }
+            }
+        }
+    }
+
     async fn get_relationship_labels(graph: &mut AsyncGraph) -> Result<Vec<String>, FalkorDBError> {
         let relations_result = graph.ro_query("CALL db.relationshipTypes()").execute().await?;
 
@@ -303,6 +526,7 @@ impl Schema {
         graph: &AsyncGraph,
         relationship_labels: &[String],
         sample_size: usize,
+        on_done: &(dyn Fn(usize) + Send + Sync),
     ) -> Result<Vec<(String, Vec<Attribute>)>, FalkorDBError> {
         // Use common parallel collection pattern
         let relationship_attributes = Self::collect_attributes_parallel(
@@ -315,21 +539,26 @@ impl Schema {
                     .map(|attributes| (relationship_label, attributes))
                     .ok()
             },
+            on_done,
         )
         .await;
 
         Ok(relationship_attributes)
     }
 
-    /// Collect attributes for either entities or relationships in parallel
-    async fn collect_attributes_parallel<T, F, Fut>(
-        graph: &AsyncGraph,
+    /// Collect attributes for either entities or relationships in parallel, with at most
+    /// [`DISCOVERY_CONCURRENCY`] labels probed at once. `on_done` receives the number of labels
+    /// finished so far (successfully or not) after each one completes.
+    async fn collect_attributes_parallel<G, T, F, Fut>(
+        graph: &G,
         labels: Vec<String>,
         sample_size: usize,
         collector: F,
+        on_done: &(dyn Fn(usize) + Send + Sync),
     ) -> Vec<T>
     where
-        F: Fn(AsyncGraph, String, usize) -> Fut + Send + Sync + Clone + 'static,
+        G: Clone + Send + Sync,
+        F: Fn(G, String, usize) -> Fut + Send + Sync + Clone + 'static,
         Fut: std::future::Future<Output = Option<T>> + Send + 'static,
         T: Send + 'static,
     {
@@ -339,8 +568,12 @@ impl Schema {
                 let collector = collector.clone();
                 async move { collector(graph, label, sample_size).await }
             })
-            .buffer_unordered(usize::MAX)
-            .filter_map(|result| async move { result })
+            .buffer_unordered(DISCOVERY_CONCURRENCY)
+            .enumerate()
+            .filter_map(|(index, result)| {
+                on_done(index + 1);
+                async move { result }
+            })
             .collect()
             .await
     }
@@ -354,9 +587,43 @@ impl Schema {
         graph: &mut AsyncGraph,
         sample_size: usize,
     ) -> Result<Self, FalkorDBError> {
+        Self::discover_from_graph_with_progress(graph, sample_size, &|_| {}).await
+    }
+
+    /// Discover the schema from a graph database, calling `on_progress` as labels and relationship
+    /// types are processed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph operations fail.
+    pub async fn discover_from_graph_with_progress(
+        graph: &mut AsyncGraph,
+        sample_size: usize,
+        on_progress: &(dyn Fn(DiscoveryProgress) + Send + Sync),
+    ) -> Result<Self, FalkorDBError> {
+        let options = DiscoveryOptions {
+            sample_size,
+            ..DiscoveryOptions::default()
+        };
+        Self::discover_from_graph_with_options(graph, options, on_progress).await
+    }
+
+    /// Discover the schema from a graph database as `options` set, calling `on_progress` as labels
+    /// and relationship types are processed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph operations fail.
+    pub async fn discover_from_graph_with_options(
+        graph: &mut AsyncGraph,
+        options: DiscoveryOptions,
+        on_progress: &(dyn Fn(DiscoveryProgress) + Send + Sync),
+    ) -> Result<Self, FalkorDBError> {
+        let sample_size = options.sample_size.max(1);
         let mut schema: Self = Self::empty();
 
         let entity_labels = Self::get_entity_labels(graph).await?;
+        let total = entity_labels.len();
 
         // Parallel entity collection using common pattern
         let entities = Self::collect_attributes_parallel(
@@ -369,6 +636,7 @@ impl Schema {
                     .map(|attributes| Entity::new(label, attributes, None))
                     .ok()
             },
+            &|done| on_progress(DiscoveryProgress::Labels { done, total }),
         )
         .await;
 
@@ -376,55 +644,398 @@ impl Schema {
             schema.add_entity(entity);
         }
 
+        // Label combinations of multi-label nodes, so `(:Person:Customer)` is described as one kind
+        // of node rather than two unrelated ones.
+        let labels = schema.entities.iter().map(|entity| entity.label.clone()).collect();
+        let combinations = Self::collect_attributes_parallel(
+            graph,
+            labels,
+            sample_size,
+            |mut graph, label, _| async move { Self::collect_label_combinations(&mut graph, &label).await.ok() },
+            &|_| {},
+        )
+        .await;
+        schema.apply_label_combinations(combinations.into_iter().flatten().collect());
+
         // Get relationship types
         let relationship_labels = Self::get_relationship_labels(graph).await?;
 
+        let total = relationship_labels.len() * if options.pair_relationships { 2 } else { 1 };
         let relationship_attributes =
-            Self::get_relationship_attributes(graph, &relationship_labels, sample_size).await?;
+            Self::get_relationship_attributes(graph, &relationship_labels, sample_size, &|done| {
+                on_progress(DiscoveryProgress::Relations { done, total });
+            })
+            .await?;
 
-        let entities = schema.entities.clone();
+        if !options.pair_relationships {
+            for (label, attributes) in relationship_attributes {
+                schema.add_relation(Relation::new(label, String::new(), String::new(), attributes));
+            }
+            schema.add_operational_metadata(graph).await;
+            return Ok(schema);
+        }
 
         let start = Instant::now();
-        let queries = process_relationships(graph, &mut schema, relationship_attributes, entities).await?;
+        let queries = process_relationships(graph, &mut schema, relationship_attributes, &|done| {
+            on_progress(DiscoveryProgress::Relations {
+                done: total / 2 + done,
+                total,
+            });
+        })
+        .await?;
         let duration = start.elapsed();
         tracing::info!("Processed relationships ({} queries)  in {:?}", queries, duration);
 
+        estimate_cardinalities(graph, &mut schema, sample_size).await;
+        schema.add_operational_metadata(graph).await;
+
         Ok(schema)
     }
+
+    /// Adds the graph's constraints and indexes.
+    async fn add_operational_metadata(
+        &mut self,
+        graph: &mut AsyncGraph,
+    ) {
+        // Constraints are optional metadata; servers without `db.constraints()` just skip them.
+        match Self::get_constraints(graph).await {
+            Ok(mut constraints) => {
+                self.apply_constraints(&constraints);
+                constraints.sort_by(|a, b| (&a.label, &a.properties).cmp(&(&b.label, &b.properties)));
+                self.constraints = constraints;
+            }
+            Err(e) => tracing::warn!("Failed to list constraints; uniqueness is not reported: {}", e),
+        }
+        match Self::get_indexes(graph).await {
+            Ok(mut indexes) => {
+                self.apply_vector_indexes(&indexes);
+                indexes.sort_by(|a, b| (&a.label, a.kind).cmp(&(&b.label, b.kind)));
+                self.indexes = indexes;
+            }
+            Err(e) => tracing::warn!("Failed to list indexes; indexes are not reported: {}", e),
+        }
+    }
 }
 
-async fn process_relationships(
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ConstraintKind {
+    /// No two nodes (or relationships) share the values of the constrained properties.
+    Unique,
+    /// The constrained properties are always set.
+    Mandatory,
+}
+
+/// An operational constraint reported by `db.constraints()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct Constraint {
+    #[serde(rename = "type")]
+    pub kind: ConstraintKind,
+    /// Node label, or relationship type when `on_relationships`.
+    pub label: String,
+    /// Constrained properties; a unique constraint on several holds for their combination.
+    pub properties: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub on_relationships: bool,
+}
+
+impl Constraint {
+    /// Parses one `db.constraints()` row (`type`, `label`, `properties`, `entitytype`, `status`),
+    /// locating columns by name. Constraints still being built or that failed are skipped.
+    fn from_record(
+        header: &[String],
+        record: &[FalkorValue],
+    ) -> Option<Self> {
+        let column = |name: &str| header.iter().position(|column| column == name);
+        let string_at = |index: Option<usize>| match index.and_then(|i| record.get(i)) {
+            Some(FalkorValue::String(value)) => Some(value.clone()),
+            _ => None,
+        };
+
+        let kind = match string_at(column("type"))?.to_ascii_uppercase().as_str() {
+            "UNIQUE" => ConstraintKind::Unique,
+            "MANDATORY" => ConstraintKind::Mandatory,
+            _ => return None,
+        };
+        let status = string_at(column("status")).unwrap_or_default();
+        if !status.is_empty() && !status.eq_ignore_ascii_case("OPERATIONAL") {
+            return None;
+        }
+        let properties = match column("properties").and_then(|i| record.get(i)) {
+            Some(FalkorValue::Array(values)) => values
+                .iter()
+                .filter_map(|value| match value {
+                    FalkorValue::String(property) => Some(property.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => return None,
+        };
+
+        Some(Self {
+            kind,
+            label: string_at(column("label"))?,
+            properties,
+            on_relationships: string_at(column("entitytype")).is_some_and(|t| t.eq_ignore_ascii_case("RELATIONSHIP")),
+        })
+    }
+}
+
+/// Kind of an index, as `db.indexes()` reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[serde(rename_all = "UPPERCASE")]
+pub enum IndexKind {
+    /// Equality and range lookups.
+    Range,
+    /// Text search with `db.idx.fulltext.queryNodes`.
+    Fulltext,
+    /// Similarity search with `db.idx.vector.queryNodes`.
+    Vector,
+}
+
+impl IndexKind {
+    /// The kind's name in `db.indexes()` rows.
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Range => "RANGE",
+            Self::Fulltext => "FULLTEXT",
+            Self::Vector => "VECTOR",
+        }
+    }
+}
+
+/// The properties one operational index of a label covers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct Index {
+    #[serde(rename = "type")]
+    pub kind: IndexKind,
+    /// Node label, or relationship type when `on_relationships`.
+    pub label: String,
+    pub properties: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub on_relationships: bool,
+}
+
+impl Index {
+    /// Parses one `db.indexes()` row, whose `types` map each property to its index types (e.g.
+    /// `{title: ['RANGE', 'FULLTEXT']}`), into one index per kind, locating columns by name.
+    /// Indexes still being built yield none.
+    fn from_record(
+        header: &[String],
+        record: &[FalkorValue],
+    ) -> Vec<Self> {
+        let column = |name: &str| header.iter().position(|column| column == name).and_then(|i| record.get(i));
+        let string_at = |name: &str| match column(name) {
+            Some(FalkorValue::String(value)) => Some(value.clone()),
+            _ => None,
+        };
+
+        let status = string_at("status").unwrap_or_default();
+        if !status.is_empty() && !status.eq_ignore_ascii_case("OPERATIONAL") {
+            return Vec::new();
+        }
+        let (Some(label), Some(FalkorValue::Map(types))) = (string_at("label"), column("types")) else {
+            return Vec::new();
+        };
+        let on_relationships = string_at("entitytype").is_some_and(|t| t.eq_ignore_ascii_case("RELATIONSHIP"));
+        [IndexKind::Range, IndexKind::Fulltext, IndexKind::Vector]
+            .into_iter()
+            .filter_map(|kind| {
+                let mut properties: Vec<String> = types
+                    .iter()
+                    .filter(|(_, kinds)| match kinds {
+                        FalkorValue::Array(kinds) => kinds
+                            .iter()
+                            .any(|k| matches!(k, FalkorValue::String(k) if k.eq_ignore_ascii_case(kind.name()))),
+                        _ => false,
+                    })
+                    .map(|(property, _)| property.clone())
+                    .collect();
+                properties.sort();
+                (!properties.is_empty()).then(|| Self {
+                    kind,
+                    label: label.clone(),
+                    properties,
+                    on_relationships,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `example` cut to [`MAX_EXAMPLE_CHARS`] characters, with `...` marking a cut.
+fn cap_example(example: &str) -> String {
+    match example.char_indices().nth(MAX_EXAMPLE_CHARS) {
+        Some((end, _)) => format!("{}...", &example[..end]),
+        None => example.to_string(),
+    }
+}
+
+/// Renders a label set as an escaped label chain, e.g. ``:`Customer`:`Person` ``
+fn label_chain(labels: &[String]) -> String {
+    labels.iter().fold(String::new(), |mut chain, label| {
+        chain.push(':');
+        chain.push_str(&Schema::escape_property_name(label));
+        chain
+    })
+}
+
+/// Returns the largest number of `other` nodes connected to any of `sample_size` sampled `anchor`
+/// nodes through `relationship`, following the edge forward (`outgoing`) or backward
+async fn sample_max_degree(
+    graph: &mut AsyncGraph,
+    anchor: &[String],
+    relationship: &str,
+    other: &[String],
+    outgoing: bool,
+    sample_size: usize,
+) -> Result<i64, FalkorDBError> {
+    let (left, right) = if outgoing { ("-", "->") } else { ("<-", "-") };
+    let query = format!(
+        "MATCH (a{}) WITH a LIMIT {sample_size} MATCH (a){left}[:{}]{right}(b{}) WITH a, count(b) AS degree RETURN max(degree)",
+        label_chain(anchor),
+        Schema::escape_property_name(relationship),
+        label_chain(other)
+    );
+    let result = graph.ro_query(&query).execute().await?;
+    Ok(rows_lossy(result.data)
+        .into_iter()
+        .next()
+        .and_then(|record| match record.first() {
+            Some(FalkorValue::I64(degree)) => Some(*degree),
+            _ => None,
+        })
+        .unwrap_or(0))
+}
+
+/// Estimates each relation's cardinality from sampled out- and in-degrees. Relations whose samples
+/// fail keep `cardinality: None`.
+async fn estimate_cardinalities(
     graph: &AsyncGraph,
     schema: &mut Schema,
-    relationship_attributes: Vec<(String, Vec<Attribute>)>,
-    entities: Vec<Entity>,
-) -> Result<usize, FalkorDBError> {
-    // Create all combinations first to avoid borrowing issues
-    let mut queries = Vec::new();
-    for (label, attributes) in &relationship_attributes {
-        for source_entity in &entities {
-            for target_entity in &entities {
-                queries.push((
-                    label.clone(),
-                    source_entity.label.clone(),
-                    target_entity.label.clone(),
-                    attributes.clone(),
-                ));
+    sample_size: usize,
+) {
+    let probes: Vec<_> = schema
+        .relations
+        .iter()
+        .enumerate()
+        .map(|(index, relation)| {
+            (
+                index,
+                relation.label.clone(),
+                relation.source_label_set(),
+                relation.target_label_set(),
+            )
+        })
+        .collect();
+    let estimates: Vec<(usize, Cardinality)> = stream::iter(probes)
+        .map(|(index, label, source, target)| {
+            let mut graph = graph.clone();
+            async move {
+                let out_degree = sample_max_degree(&mut graph, &source, &label, &target, true, sample_size).await;
+                let in_degree = sample_max_degree(&mut graph, &target, &label, &source, false, sample_size).await;
+                match (out_degree, in_degree) {
+                    (Ok(out_degree), Ok(in_degree)) => Some((index, Cardinality::from_degrees(out_degree, in_degree))),
+                    (Err(e), _) | (_, Err(e)) => {
+                        tracing::warn!("Cardinality estimate for {} failed: {}", label, e);
+                        None
+                    }
+                }
             }
+        })
+        .buffer_unordered(DISCOVERY_CONCURRENCY)
+        .filter_map(|result| async move { result })
+        .collect()
+        .await;
+
+    for (index, cardinality) in estimates {
+        schema.relations[index].cardinality = Some(cardinality);
+    }
+}
+
+/// Maximum number of distinct label combinations collected per label.
+const MAX_LABEL_COMBINATIONS: usize = 50;
+
+/// Converts a `labels(n)` value into a sorted label set; `None` for anything else or no labels.
+fn label_set(value: FalkorValue) -> Option<Vec<String>> {
+    let FalkorValue::Array(values) = value else {
+        return None;
+    };
+    let mut labels: Vec<String> = values
+        .into_iter()
+        .filter_map(|value| match value {
+            FalkorValue::String(label) => Some(label),
+            _ => None,
+        })
+        .collect();
+    labels.sort();
+    labels.dedup();
+    (!labels.is_empty()).then_some(labels)
+}
+
+/// Builds one relation per distinct (source label set, target label set) pair observed for a
+/// relationship type.
+fn relations_from_endpoints(
+    label: &str,
+    attributes: &[Attribute],
+    endpoints: Vec<(Vec<String>, Vec<String>)>,
+) -> Vec<Relation> {
+    let mut relations: Vec<Relation> = Vec::new();
+    for (source_labels, target_labels) in endpoints {
+        if let Some(relation) =
+            Relation::between_label_sets(label.to_string(), source_labels, target_labels, attributes.to_vec())
+            && !relations.iter().any(|existing| {
+                existing.source == relation.source
+                    && existing.target == relation.target
+                    && existing.source_labels == relation.source_labels
+                    && existing.target_labels == relation.target_labels
+            })
+        {
+            relations.push(relation);
         }
     }
-    let ret = queries.len();
-    // Convert to stream and process with limited concurrency
-    let relations: Vec<Relation> = stream::iter(queries)
-        .map(|(label, source_label, target_label, attributes)| {
+    relations
+}
+
+/// The label sets joined by `relationship`, read in one pass over its edges rather than by probing
+/// every (source label, target label) pair.
+fn endpoints_query(relationship: &str) -> String {
+    format!(
+        "MATCH (s)-[:{}]->(t) RETURN DISTINCT labels(s), labels(t)",
+        Schema::escape_property_name(relationship)
+    )
+}
+
+/// Discovers the endpoints of every relationship type with one query per type, returning the
+/// number of queries run. Endpoints are described by their full label sets, so relations between
+/// multi-label nodes appear once instead of once per label pair. `on_done` receives the number of
+/// types probed so far.
+async fn process_relationships(
+    graph: &AsyncGraph,
+    schema: &mut Schema,
+    relationship_attributes: Vec<(String, Vec<Attribute>)>,
+    on_done: &(dyn Fn(usize) + Send + Sync),
+) -> Result<usize, FalkorDBError> {
+    let ret = relationship_attributes.len();
+    let relations: Vec<Vec<Relation>> = stream::iter(relationship_attributes)
+        .map(|(label, attributes)| {
             let mut graph = graph.clone();
             async move {
-                let query = format!("MATCH (s:{source_label})-[a:{label}]->(t:{target_label}) return a limit 1");
-                match graph.ro_query(&query).execute().await {
-                    Ok(query_result) if !query_result.data.is_empty() => {
-                        Some(Relation::new(label, source_label, target_label, attributes))
+                match graph.ro_query(&endpoints_query(&label)).execute().await {
+                    Ok(query_result) => {
+                        let endpoints = rows_lossy(query_result.data)
+                            .into_iter()
+                            .filter_map(|record| {
+                                let mut values = record.into_iter();
+                                Some((label_set(values.next()?)?, label_set(values.next()?)?))
+                            })
+                            .collect();
+                        Some(relations_from_endpoints(&label, &attributes, endpoints))
                     }
-                    Ok(_) => None,
                     Err(e) => {
                         tracing::warn!("Query failed but ignored: {:?}", e);
                         None
@@ -432,13 +1043,17 @@ async fn process_relationships(
                 }
             }
         })
-        .buffer_unordered(1000)
-        .filter_map(|result| async move { result })
+        .buffer_unordered(DISCOVERY_CONCURRENCY)
+        .enumerate()
+        .filter_map(|(index, result)| {
+            on_done(index + 1);
+            async move { result }
+        })
         .collect()
         .await;
 
     // Add all relations to schema
-    for relation in relations {
+    for relation in relations.into_iter().flatten() {
         schema.add_relation(relation);
     }
 
@@ -502,4 +1117,338 @@ mod tests {
         // Empty string
         assert_eq!(Schema::escape_property_name(""), "``");
     }
+
+    #[test]
+    fn test_discovery_queries_escape_exotic_labels() {
+        let exotic = ["Has Space", "Dash-Label", "Ünïcødé", "Tick`Label", "X) DETACH DELETE (y", "A:B"];
+        for label in exotic {
+            let escaped = Schema::escape_property_name(label);
+            for is_relationship in [false, true] {
+                let query = Schema::attribute_types_query(label, 10, is_relationship);
+                assert!(query.contains(&format!("a:{escaped}")), "{query}");
+                assert!(!query.contains(&format!("a:{label}")), "{query}");
+            }
+            assert_eq!(Schema::match_clause("n", label, false), format!("MATCH (n:{escaped})"));
+            assert_eq!(
+                Schema::match_clause("n", label, true),
+                format!("MATCH ()-[n:{escaped}]->()")
+            );
+            assert_eq!(
+                endpoints_query(label),
+                format!("MATCH (s)-[:{escaped}]->(t) RETURN DISTINCT labels(s), labels(t)")
+            );
+        }
+
+        // The embedded backtick is doubled so the identifier cannot be terminated early
+        assert_eq!(
+            Schema::match_clause("n", "Tick`Label", false),
+            "MATCH (n:`Tick``Label`)"
+        );
+        assert_eq!(
+            label_chain(&labels(&["Has Space", "Tick`Label"])),
+            ":`Has Space`:`Tick``Label`"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parallel_collection_is_bounded() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let labels = (0..40).map(|i| format!("L{i}")).collect();
+        let finished = AtomicUsize::new(0);
+        let on_done = |done: usize| {
+            assert_eq!(finished.fetch_add(1, Ordering::SeqCst) + 1, done);
+        };
+        let collected = Schema::collect_attributes_parallel(
+            &(),
+            labels,
+            1,
+            {
+                let in_flight = Arc::clone(&in_flight);
+                let peak = Arc::clone(&peak);
+                move |(), label, _| {
+                    let in_flight = Arc::clone(&in_flight);
+                    let peak = Arc::clone(&peak);
+                    async move {
+                        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        Some(label)
+                    }
+                }
+            },
+            &on_done,
+        )
+        .await;
+
+        assert_eq!(collected.len(), 40);
+        assert_eq!(finished.load(Ordering::SeqCst), 40);
+        assert_eq!(peak.load(Ordering::SeqCst), DISCOVERY_CONCURRENCY);
+    }
+
+    #[test]
+    fn test_discovery_progress_display() {
+        assert_eq!(
+            DiscoveryProgress::Labels { done: 12, total: 40 }.to_string(),
+            "discovered 12/40 labels"
+        );
+        assert_eq!(
+            DiscoveryProgress::Relations { done: 3, total: 10 }.to_string(),
+            "probing relations 30%"
+        );
+        assert_eq!(
+            DiscoveryProgress::Relations { done: 0, total: 0 }.to_string(),
+            "probing relations 0%"
+        );
+    }
+
+    #[test]
+    fn test_serialized_schema_round_trips() {
+        let mut schema = Schema::empty();
+        schema.add_entity(Entity::new(
+            "Person".to_string(),
+            vec![
+                Attribute::new("name".to_string(), AttributeType::String, 12, true, false),
+                Attribute::new("age".to_string(), AttributeType::Integer, 7, false, false),
+            ],
+            None,
+        ));
+
+        let json = serde_json::to_string(&schema).unwrap();
+        let parsed: Schema = serde_json::from_str(&json).unwrap();
+        let attributes = &parsed.entities[0].attributes;
+        assert_eq!(attributes.len(), 2);
+        assert!(attributes[0].unique);
+        assert!(!attributes[1].unique && !attributes[1].required);
+        assert_eq!(attributes[0].count, 0, "counts are not serialized");
+    }
+
+    fn labels(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_label_set_sorts_and_rejects_non_labels() {
+        let value = FalkorValue::Array(vec![
+            FalkorValue::String("Person".into()),
+            FalkorValue::String("Customer".into()),
+        ]);
+        assert_eq!(label_set(value), Some(labels(&["Customer", "Person"])));
+        assert_eq!(label_set(FalkorValue::Array(vec![])), None);
+        assert_eq!(label_set(FalkorValue::I64(1)), None);
+    }
+
+    #[test]
+    fn test_relations_from_multi_label_endpoints() {
+        let relations = relations_from_endpoints(
+            "BOUGHT",
+            &[],
+            vec![
+                (labels(&["Customer", "Person"]), labels(&["Product"])),
+                (labels(&["Customer", "Person"]), labels(&["Product"])),
+                (labels(&["Person"]), labels(&["Product"])),
+            ],
+        );
+        assert_eq!(relations.len(), 2);
+        assert_eq!(relations[0].source, "Customer");
+        assert_eq!(relations[0].source_labels, labels(&["Customer", "Person"]));
+        assert!(relations[0].target_labels.is_empty());
+        assert_eq!(relations[1].source, "Person");
+        assert!(relations[1].source_labels.is_empty());
+    }
+
+    #[test]
+    fn test_apply_label_combinations() {
+        let mut schema = Schema::empty();
+        for label in ["Customer", "Person", "Product"] {
+            schema.add_entity(Entity::new(label.to_string(), Vec::new(), None));
+        }
+        schema.apply_label_combinations(vec![labels(&["Customer", "Person"]), labels(&["Customer", "Person"])]);
+        assert_eq!(schema.label_combinations, vec![labels(&["Customer", "Person"])]);
+        assert_eq!(schema.entities[0].co_labels, labels(&["Person"]));
+        assert_eq!(schema.entities[1].co_labels, labels(&["Customer"]));
+        assert!(schema.entities[2].co_labels.is_empty());
+
+        let json = serde_json::to_string(&schema).unwrap();
+        assert!(json.contains("label_combinations"));
+    }
+
+    fn constraint_row(
+        kind: &str,
+        label: &str,
+        properties: &[&str],
+        entity_type: &str,
+        status: &str,
+    ) -> Vec<FalkorValue> {
+        vec![
+            FalkorValue::String(kind.into()),
+            FalkorValue::String(label.into()),
+            FalkorValue::Array(properties.iter().map(|p| FalkorValue::String((*p).into())).collect()),
+            FalkorValue::String(entity_type.into()),
+            FalkorValue::String(status.into()),
+        ]
+    }
+
+    #[test]
+    fn test_constraints_mark_unique_and_required() {
+        let header = labels(&["type", "label", "properties", "entitytype", "status"]);
+        let constraints: Vec<Constraint> = [
+            constraint_row("UNIQUE", "Person", &["email"], "NODE", "OPERATIONAL"),
+            constraint_row("UNIQUE", "Person", &["first", "last"], "NODE", "OPERATIONAL"),
+            constraint_row("MANDATORY", "Person", &["name"], "NODE", "OPERATIONAL"),
+            constraint_row("UNIQUE", "Person", &["name"], "NODE", "UNDER CONSTRUCTION"),
+            constraint_row("MANDATORY", "KNOWS", &["since"], "RELATIONSHIP", "OPERATIONAL"),
+        ]
+        .into_iter()
+        .filter_map(|row| Constraint::from_record(&header, &row))
+        .collect();
+        assert_eq!(constraints.len(), 4);
+
+        let attribute = |name: &str| Attribute::new(name.to_string(), AttributeType::String, 1, false, false);
+        let mut schema = Schema::empty();
+        schema.add_entity(Entity::new(
+            "Person".into(),
+            vec![attribute("email"), attribute("first"), attribute("name")],
+            None,
+        ));
+        schema.add_relation(Relation::new(
+            "KNOWS".into(),
+            "Person".into(),
+            "Person".into(),
+            vec![attribute("since")],
+        ));
+        schema.apply_constraints(&constraints);
+
+        let person = &schema.entities[0].attributes;
+        assert!(person[0].unique && !person[0].required);
+        assert!(!person[1].unique, "composite unique keys mark no single attribute");
+        assert!(person[2].required && !person[2].unique);
+        assert!(schema.relations[0].attributes[0].required);
+
+        schema.constraints = constraints;
+        let json = serde_json::to_string(&schema).unwrap();
+        assert!(json.contains(r#"{"type":"UNIQUE","label":"Person","properties":["first","last"]}"#));
+        assert!(
+            json.contains(r#"{"type":"MANDATORY","label":"KNOWS","properties":["since"],"on_relationships":true}"#)
+        );
+    }
+
+    #[test]
+    fn test_indexes_by_kind_mark_vector_attributes() {
+        let header = labels(&["label", "properties", "types", "entitytype", "status"]);
+        let row = |label: &str, types: &[(&str, &[&str])], status: &str| {
+            vec![
+                FalkorValue::String(label.into()),
+                FalkorValue::Array(types.iter().map(|(p, _)| FalkorValue::String((*p).into())).collect()),
+                FalkorValue::Map(
+                    types
+                        .iter()
+                        .map(|(property, kinds)| {
+                            let kinds = kinds.iter().map(|k| FalkorValue::String((*k).into())).collect();
+                            ((*property).to_string(), FalkorValue::Array(kinds))
+                        })
+                        .collect(),
+                ),
+                FalkorValue::String("NODE".into()),
+                FalkorValue::String(status.into()),
+            ]
+        };
+        let indexes: Vec<Index> = [
+            row(
+                "Document",
+                &[("embedding", &["VECTOR"]), ("title", &["RANGE"])],
+                "OPERATIONAL",
+            ),
+            row("Document", &[("title", &["RANGE", "FULLTEXT"])], "OPERATIONAL"),
+            row("Image", &[("embedding", &["VECTOR"])], "UNDER CONSTRUCTION"),
+        ]
+        .into_iter()
+        .flat_map(|row| Index::from_record(&header, &row))
+        .collect();
+        let kinds: Vec<(IndexKind, Vec<String>)> =
+            indexes.iter().map(|index| (index.kind, index.properties.clone())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (IndexKind::Range, labels(&["title"])),
+                (IndexKind::Vector, labels(&["embedding"])),
+                (IndexKind::Range, labels(&["title"])),
+                (IndexKind::Fulltext, labels(&["title"])),
+            ]
+        );
+
+        let attribute = |name: &str, r#type| Attribute::new(name.to_string(), r#type, 1, false, false);
+        let mut schema = Schema::empty();
+        schema.add_entity(Entity::new(
+            "Document".into(),
+            vec![
+                attribute("embedding", AttributeType::Vector),
+                attribute("title", AttributeType::String),
+            ],
+            None,
+        ));
+        schema.apply_vector_indexes(&indexes);
+
+        let document = &schema.entities[0].attributes;
+        assert!(document[0].vector_index);
+        assert!(!document[1].vector_index);
+        assert!(serde_json::to_string(&schema).unwrap().contains(r#""vector_index":true"#));
+
+        schema.indexes = indexes;
+        let json = serde_json::to_string(&schema).unwrap();
+        assert!(json.contains(r#"{"type":"FULLTEXT","label":"Document","properties":["title"]}"#));
+        let parsed: Schema = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.indexes, schema.indexes);
+    }
+
+    #[test]
+    fn test_discovery_options_default_unset_fields() {
+        let options: DiscoveryOptions = serde_json::from_str(r#"{"pair_relationships": false}"#).unwrap();
+        assert_eq!(options.sample_size, DEFAULT_SAMPLE_SIZE);
+        assert!(!options.pair_relationships);
+        assert_eq!(
+            serde_json::from_str::<DiscoveryOptions>("{}").unwrap(),
+            DiscoveryOptions::default()
+        );
+
+        // Unpaired relations are listed without endpoints.
+        let relation = Relation::new("KNOWS".to_string(), String::new(), String::new(), Vec::new());
+        assert_eq!(serde_json::to_string(&relation).unwrap(), r#"{"label":"KNOWS"}"#);
+    }
+
+    #[test]
+    fn test_long_examples_are_capped() {
+        assert_eq!(cap_example("United States"), "United States");
+        let long = "é".repeat(MAX_EXAMPLE_CHARS + 5);
+        let capped = cap_example(&long);
+        assert_eq!(capped.chars().count(), MAX_EXAMPLE_CHARS + 3);
+        assert!(capped.ends_with("é..."));
+        assert_eq!(
+            cap_example(&long[..MAX_EXAMPLE_CHARS * 2]),
+            long[..MAX_EXAMPLE_CHARS * 2]
+        );
+    }
+
+    #[test]
+    fn test_direction_pattern_and_cardinality() {
+        let relation = Relation::between_label_sets(
+            "BOUGHT".into(),
+            labels(&["Customer", "Person"]),
+            labels(&["Product"]),
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(relation.pattern, "(:Customer:Person)-[:BOUGHT]->(:Product)");
+        assert_eq!(label_chain(&relation.source_label_set()), ":`Customer`:`Person`");
+
+        assert_eq!(Cardinality::from_degrees(1, 0), Cardinality::OneToOne);
+        assert_eq!(Cardinality::from_degrees(5, 1), Cardinality::OneToMany);
+        assert_eq!(Cardinality::from_degrees(1, 5), Cardinality::ManyToOne);
+        assert_eq!(Cardinality::from_degrees(2, 2), Cardinality::ManyToMany);
+        assert_eq!(serde_json::to_string(&Cardinality::OneToMany).unwrap(), r#""1:N""#);
+    }
 }
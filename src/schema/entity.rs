@@ -8,10 +8,14 @@ pub use super::attribute::Attribute;
 #[cfg_attr(feature = "server", derive(ToSchema))]
 pub struct Entity {
     pub label: String,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub attributes: Vec<Attribute>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Other labels that nodes with this label also carry (multi-label nodes such as
+    /// `:Person:Customer`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub co_labels: Vec<String>,
 }
 
 impl Entity {
@@ -25,6 +29,7 @@ impl Entity {
             label,
             attributes,
             description,
+            co_labels: Vec::new(),
         }
     }
 }
@@ -1,4 +1,8 @@
 pub mod attribute;
+pub mod descriptions;
+pub mod diff;
 pub mod discovery;
 pub mod entity;
+pub mod pruning;
 pub mod relation;
+pub mod units;
@@ -0,0 +1,365 @@
+//! Narrowing a very large schema down to the part a question is about.
+//!
+//! A graph with hundreds of labels has a schema too large for the system prompt. A
+//! [`SchemaPruner`] embeds each label and relationship type (with its description and property
+//! names) and the question, and keeps the `top_k` elements closest to the question, plus the labels
+//! the kept relationships connect and the relationships between kept labels. Element embeddings
+//! are cached, so after the first question only the question is embedded. Schemas with no more
+//! than `min_elements` elements are sent whole.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+
+use genai::Client as GenAiClient;
+
+use super::discovery::Schema;
+use crate::error::TextToCypherError;
+
+/// Labels and relationship types kept by default.
+pub const DEFAULT_TOP_K: usize = 20;
+
+/// Schemas with at most this many labels and relationship types are not pruned by default.
+pub const DEFAULT_MIN_ELEMENTS: usize = 50;
+
+/// Element embeddings kept before the cache is emptied.
+const MAX_CACHED_EMBEDDINGS: usize = 20_000;
+
+/// Texts embedded in one request.
+const EMBED_BATCH_SIZE: usize = 256;
+
+/// A label or relationship type of a schema.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SchemaElement {
+    Entity(String),
+    Relation(String),
+}
+
+/// The schema's labels and relationship types, each with the text it is embedded as.
+#[must_use]
+pub fn schema_elements(schema: &Schema) -> Vec<(SchemaElement, String)> {
+    fn text(
+        kind: &str,
+        name: &str,
+        description: Option<&str>,
+        attributes: impl Iterator<Item = String>,
+    ) -> String {
+        let mut text = format!("{kind} {name}");
+        if let Some(description) = description {
+            text.push_str(": ");
+            text.push_str(description);
+        }
+        let attributes: Vec<String> = attributes.collect();
+        if !attributes.is_empty() {
+            text.push_str(" (");
+            text.push_str(&attributes.join(", "));
+            text.push(')');
+        }
+        text
+    }
+
+    let mut elements: Vec<(SchemaElement, String)> = schema
+        .entities
+        .iter()
+        .map(|entity| {
+            let attributes = entity.attributes.iter().map(|a| a.name.clone());
+            (
+                SchemaElement::Entity(entity.label.clone()),
+                text("Node", &entity.label, entity.description.as_deref(), attributes),
+            )
+        })
+        .collect();
+    // A relationship type is one element, however many label pairs it connects.
+    let mut seen = BTreeSet::new();
+    for relation in &schema.relations {
+        if !seen.insert(relation.label.as_str()) {
+            continue;
+        }
+        let attributes: BTreeSet<String> = schema
+            .relations
+            .iter()
+            .filter(|r| r.label == relation.label)
+            .flat_map(|r| r.attributes.iter().map(|a| a.name.clone()))
+            .collect();
+        elements.push((
+            SchemaElement::Relation(relation.label.clone()),
+            text(
+                "Relationship",
+                &relation.label,
+                relation.description.as_deref(),
+                attributes.into_iter(),
+            ),
+        ));
+    }
+    elements
+}
+
+/// Cosine similarity of two vectors; 0 when either is zero or their lengths differ.
+#[must_use]
+pub fn cosine_similarity(
+    a: &[f32],
+    b: &[f32],
+) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norm == 0.0 { 0.0 } else { dot / norm }
+}
+
+/// The part of `schema` around `keep`.
+///
+/// That is the kept labels, the relationships of the kept types, the labels those connect, and
+/// every relationship between kept labels. Indexes, constraints and label combinations are
+/// narrowed to the kept labels and types.
+#[must_use]
+pub fn prune(
+    schema: &Schema,
+    keep: &[SchemaElement],
+) -> Schema {
+    let mut labels: BTreeSet<&str> = BTreeSet::new();
+    let mut types: BTreeSet<&str> = BTreeSet::new();
+    for element in keep {
+        match element {
+            SchemaElement::Entity(label) => labels.insert(label.as_str()),
+            SchemaElement::Relation(label) => types.insert(label.as_str()),
+        };
+    }
+    // Relations discovered without pairing have no labels to add.
+    for relation in schema.relations.iter().filter(|r| types.contains(r.label.as_str())) {
+        labels.extend(
+            [relation.source.as_str(), relation.target.as_str()]
+                .into_iter()
+                .filter(|l| !l.is_empty()),
+        );
+    }
+
+    let relations: Vec<_> = schema
+        .relations
+        .iter()
+        .filter(|r| {
+            types.contains(r.label.as_str())
+                || (labels.contains(r.source.as_str()) && labels.contains(r.target.as_str()))
+        })
+        .cloned()
+        .collect();
+    let kept_types: BTreeSet<&str> = relations.iter().map(|r| r.label.as_str()).collect();
+    let kept = |label: &str, on_relationships: bool| {
+        if on_relationships {
+            kept_types.contains(label)
+        } else {
+            labels.contains(label)
+        }
+    };
+    Schema {
+        entities: schema
+            .entities
+            .iter()
+            .filter(|e| labels.contains(e.label.as_str()))
+            .cloned()
+            .collect(),
+        label_combinations: schema
+            .label_combinations
+            .iter()
+            .filter(|combination| combination.iter().any(|label| labels.contains(label.as_str())))
+            .cloned()
+            .collect(),
+        indexes: schema
+            .indexes
+            .iter()
+            .filter(|index| kept(&index.label, index.on_relationships))
+            .cloned()
+            .collect(),
+        constraints: schema
+            .constraints
+            .iter()
+            .filter(|constraint| kept(&constraint.label, constraint.on_relationships))
+            .cloned()
+            .collect(),
+        relations,
+    }
+}
+
+/// Prunes large schemas to the elements closest to each question, embedding with `model`.
+///
+/// Cloning shares the embedding cache.
+#[derive(Debug, Clone)]
+pub struct SchemaPruner {
+    model: String,
+    top_k: usize,
+    min_elements: usize,
+    embeddings: Arc<Mutex<HashMap<String, Vec<f32>>>>,
+}
+
+impl SchemaPruner {
+    /// A pruner embedding with `model` (e.g. `openai:text-embedding-3-small`), keeping
+    /// [`DEFAULT_TOP_K`] elements of schemas with more than [`DEFAULT_MIN_ELEMENTS`].
+    #[must_use]
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            top_k: DEFAULT_TOP_K,
+            min_elements: DEFAULT_MIN_ELEMENTS,
+            embeddings: Arc::default(),
+        }
+    }
+
+    /// Keeps the `top_k` labels and relationship types closest to the question (at least one).
+    #[must_use]
+    pub fn with_top_k(
+        mut self,
+        top_k: usize,
+    ) -> Self {
+        self.top_k = top_k.max(1);
+        self
+    }
+
+    /// Sends schemas with at most `min_elements` labels and relationship types whole.
+    #[must_use]
+    pub const fn with_min_elements(
+        mut self,
+        min_elements: usize,
+    ) -> Self {
+        self.min_elements = min_elements;
+        self
+    }
+
+    /// The embedding model.
+    #[must_use]
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// The part of `schema_json` relevant to `question`, or `None` when the schema is small enough
+    /// to send whole or is not a discovered schema's JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TextToCypherError::Generation`] if an embedding request fails.
+    pub async fn prune(
+        &self,
+        client: &GenAiClient,
+        schema_json: &str,
+        question: &str,
+    ) -> Result<Option<String>, TextToCypherError> {
+        let Ok(schema) = serde_json::from_str::<Schema>(schema_json) else {
+            return Ok(None);
+        };
+        let elements = schema_elements(&schema);
+        if elements.len() <= self.min_elements.max(self.top_k) {
+            return Ok(None);
+        }
+
+        let texts: Vec<&str> = elements.iter().map(|(_, text)| text.as_str()).collect();
+        let vectors = self.embed_cached(client, &texts).await?;
+        let question = crate::core::embed_text(client, &self.model, question).await?;
+
+        let mut scored: Vec<(f32, &SchemaElement)> = elements
+            .iter()
+            .zip(&vectors)
+            .map(|((element, _), vector)| (cosine_similarity(&question, vector), element))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let keep: Vec<SchemaElement> = scored
+            .into_iter()
+            .take(self.top_k)
+            .map(|(_, element)| element.clone())
+            .collect();
+        let pruned = prune(&schema, &keep);
+        serde_json::to_string(&pruned)
+            .map(Some)
+            .map_err(|e| TextToCypherError::Generation(format!("Failed to serialize the pruned schema: {e}")))
+    }
+
+    /// The embeddings of `texts`, in order, embedding only those not cached yet.
+    async fn embed_cached(
+        &self,
+        client: &GenAiClient,
+        texts: &[&str],
+    ) -> Result<Vec<Vec<f32>>, TextToCypherError> {
+        let missing: Vec<String> = {
+            let cache = self.embeddings.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            let missing: BTreeSet<&str> = texts.iter().copied().filter(|text| !cache.contains_key(*text)).collect();
+            missing.into_iter().map(ToString::to_string).collect()
+        };
+        let mut embedded = Vec::with_capacity(missing.len());
+        for batch in missing.chunks(EMBED_BATCH_SIZE) {
+            let response = client
+                .embed_batch(self.model.as_str(), batch.to_vec(), None)
+                .await
+                .map_err(|e| TextToCypherError::Generation(format!("Embedding request failed: {e}")))?;
+            let mut vectors = response.embeddings;
+            vectors.sort_by_key(|embedding| embedding.index);
+            if vectors.len() != batch.len() {
+                return Err(TextToCypherError::Generation(format!(
+                    "The embedding model returned {} vectors for {} texts",
+                    vectors.len(),
+                    batch.len()
+                )));
+            }
+            embedded.extend(batch.iter().cloned().zip(vectors.into_iter().map(|e| e.vector)));
+        }
+
+        let mut cache = self.embeddings.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if cache.len() + embedded.len() > MAX_CACHED_EMBEDDINGS {
+            cache.clear();
+        }
+        cache.extend(embedded);
+        let vectors = texts.iter().map(|text| cache.get(*text).cloned().unwrap_or_default()).collect();
+        drop(cache);
+        Ok(vectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        serde_json::from_str(
+            r#"{"entities": [
+                {"label": "Person", "attributes": [{"name": "name", "type": "String"}]},
+                {"label": "Movie", "description": "A feature film"},
+                {"label": "Invoice"},
+                {"label": "Customer"}
+            ], "relations": [
+                {"label": "ACTED_IN", "source": "Person", "target": "Movie"},
+                {"label": "DIRECTED", "source": "Person", "target": "Movie"},
+                {"label": "BILLED", "source": "Invoice", "target": "Customer"}
+            ], "indexes": [
+                {"type": "RANGE", "label": "Invoice", "properties": ["id"]},
+                {"type": "RANGE", "label": "Movie", "properties": ["title"]}
+            ]}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn elements_describe_labels_and_relationship_types() {
+        let elements = schema_elements(&schema());
+        assert_eq!(elements.len(), 7);
+        assert_eq!(elements[0].1, "Node Person (name)");
+        assert_eq!(elements[1].1, "Node Movie: A feature film");
+        assert_eq!(
+            elements[4],
+            (
+                SchemaElement::Relation("ACTED_IN".to_string()),
+                "Relationship ACTED_IN".to_string()
+            )
+        );
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < f32::EPSILON);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 0.0]).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn pruning_keeps_connected_labels_and_relationships() {
+        let pruned = prune(&schema(), &[SchemaElement::Relation("ACTED_IN".to_string())]);
+        let labels: Vec<&str> = pruned.entities.iter().map(|e| e.label.as_str()).collect();
+        let types: Vec<&str> = pruned.relations.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, ["Person", "Movie"]);
+        // DIRECTED also connects two kept labels.
+        assert_eq!(types, ["ACTED_IN", "DIRECTED"]);
+        assert_eq!(pruned.indexes.len(), 1);
+        assert_eq!(pruned.indexes[0].label, "Movie");
+    }
+}
@@ -4,14 +4,69 @@ use utoipa::ToSchema;
 
 use crate::schema::entity::Attribute;
 
+/// How many nodes on each side of a relationship type typically connect, estimated from samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub enum Cardinality {
+    /// Each source has at most one target and each target at most one source.
+    #[serde(rename = "1:1")]
+    OneToOne,
+    /// A source can have many targets; each target has at most one source.
+    #[serde(rename = "1:N")]
+    OneToMany,
+    /// Many sources can share a target; each source has at most one target.
+    #[serde(rename = "N:1")]
+    ManyToOne,
+    /// Both sides can have many.
+    #[serde(rename = "N:M")]
+    ManyToMany,
+}
+
+impl Cardinality {
+    /// Classifies from the largest sampled out-degree (targets per source) and in-degree (sources
+    /// per target).
+    #[must_use]
+    pub const fn from_degrees(
+        max_out_degree: i64,
+        max_in_degree: i64,
+    ) -> Self {
+        match (max_out_degree > 1, max_in_degree > 1) {
+            (false, false) => Self::OneToOne,
+            (true, false) => Self::OneToMany,
+            (false, true) => Self::ManyToOne,
+            (true, true) => Self::ManyToMany,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "server", derive(ToSchema))]
 pub struct Relation {
     pub label: String,
+    /// Label of the source nodes; empty when discovery did not pair relationships with labels.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub source: String,
+    /// Label of the target nodes; empty when discovery did not pair relationships with labels.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub target: String,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub attributes: Vec<Attribute>,
+    /// All labels of the source nodes when they carry more than one (e.g. `["Customer", "Person"]`);
+    /// `source` is one of them. Empty for single-labeled sources.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_labels: Vec<String>,
+    /// All labels of the target nodes when they carry more than one; `target` is one of them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub target_labels: Vec<String>,
+    /// The directed match pattern, e.g. `(:Person)-[:ACTED_IN]->(:Movie)`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub pattern: String,
+    /// Estimated cardinality, when discovery could sample it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cardinality: Option<Cardinality>,
+    /// What the relationship means, written by hand or by [`crate::core::enrich_schema`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 impl Relation {
@@ -27,6 +82,72 @@ impl Relation {
             source,
             target,
             attributes,
+            source_labels: Vec::new(),
+            target_labels: Vec::new(),
+            pattern: String::new(),
+            cardinality: None,
+            description: None,
+        }
+    }
+
+    /// Creates a relation between nodes identified by their full label sets. Single-label sets are
+    /// stored in `source`/`target` only; multi-label sets also fill `source_labels`/`target_labels`.
+    ///
+    /// Returns `None` if either label set is empty (unlabeled nodes cannot be described).
+    #[must_use]
+    pub fn between_label_sets(
+        label: String,
+        source_labels: Vec<String>,
+        target_labels: Vec<String>,
+        attributes: Vec<Attribute>,
+    ) -> Option<Self> {
+        let source = source_labels.first()?.clone();
+        let target = target_labels.first()?.clone();
+        let mut relation = Self {
+            label,
+            source,
+            target,
+            attributes,
+            source_labels: if source_labels.len() > 1 {
+                source_labels
+            } else {
+                Vec::new()
+            },
+            target_labels: if target_labels.len() > 1 {
+                target_labels
+            } else {
+                Vec::new()
+            },
+            pattern: String::new(),
+            cardinality: None,
+            description: None,
+        };
+        relation.pattern = format!(
+            "(:{})-[:{}]->(:{})",
+            relation.source_label_set().join(":"),
+            relation.label,
+            relation.target_label_set().join(":")
+        );
+        Some(relation)
+    }
+
+    /// All labels of the source nodes (`source_labels`, or just `source`).
+    #[must_use]
+    pub fn source_label_set(&self) -> Vec<String> {
+        if self.source_labels.is_empty() {
+            vec![self.source.clone()]
+        } else {
+            self.source_labels.clone()
+        }
+    }
+
+    /// All labels of the target nodes (`target_labels`, or just `target`).
+    #[must_use]
+    pub fn target_label_set(&self) -> Vec<String> {
+        if self.target_labels.is_empty() {
+            vec![self.target.clone()]
+        } else {
+            self.target_labels.clone()
         }
     }
 }
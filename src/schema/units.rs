@@ -0,0 +1,192 @@
+//! Units of numeric properties, so answers say "$42M" rather than "42".
+//!
+//! A unit is the `unit` of an attribute in the schema JSON. It can be written into a hand-made
+//! schema, or applied to discovered schemas from an [`AttributeUnits`] list such as
+//! `ATTRIBUTE_UNITS=Order.total=USD millions;Movie.runtime=minutes`.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Units of properties, by label (of an entity or a relationship type) and property name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttributeUnits {
+    units: BTreeMap<(String, String), String>,
+}
+
+impl AttributeUnits {
+    /// Parses `Label.property=unit` pairs separated by `;`, e.g.
+    /// `Order.total=USD millions;Movie.runtime=minutes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first pair without a label, a property or a unit.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut units = BTreeMap::new();
+        for pair in spec.split(';').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let parsed = pair.split_once('=').and_then(|(property, unit)| {
+                let (label, name) = property.trim().split_once('.')?;
+                let (label, name, unit) = (label.trim(), name.trim(), unit.trim());
+                (!label.is_empty() && !name.is_empty() && !unit.is_empty())
+                    .then(|| ((label.to_string(), name.to_string()), unit.to_string()))
+            });
+            let Some((key, unit)) = parsed else {
+                return Err(format!("'{pair}' is not of the form Label.property=unit"));
+            };
+            units.insert(key, unit);
+        }
+        Ok(Self { units })
+    }
+
+    /// Whether no unit is set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// The unit of `label`'s `property`, if set.
+    #[must_use]
+    pub fn unit(
+        &self,
+        label: &str,
+        property: &str,
+    ) -> Option<&str> {
+        self.units.get(&(label.to_string(), property.to_string())).map(String::as_str)
+    }
+
+    /// Sets the `unit` of each listed attribute in a schema's JSON. Units the schema already has
+    /// are kept, and a schema that cannot be parsed is returned unchanged.
+    #[must_use]
+    pub fn annotate(
+        &self,
+        schema_json: &str,
+    ) -> String {
+        if self.is_empty() {
+            return schema_json.to_string();
+        }
+        let Ok(mut schema) = serde_json::from_str::<Value>(schema_json) else {
+            return schema_json.to_string();
+        };
+        let mut changed = false;
+        for (label, attribute) in attributes_mut(&mut schema) {
+            let Some(unit) = attribute
+                .get("name")
+                .and_then(Value::as_str)
+                .and_then(|name| self.unit(&label, name))
+            else {
+                continue;
+            };
+            if attribute.get("unit").is_none() {
+                attribute.insert("unit".to_string(), Value::String(unit.to_string()));
+                changed = true;
+            }
+        }
+        if changed {
+            schema.to_string()
+        } else {
+            schema_json.to_string()
+        }
+    }
+}
+
+/// The units of the properties `query` reads, one `Label.property is in unit` line each, for the
+/// answer prompt. Empty when the schema gives none of them a unit.
+#[must_use]
+pub fn units_for_query(
+    schema_json: &str,
+    query: &str,
+) -> String {
+    let Ok(mut schema) = serde_json::from_str::<Value>(schema_json) else {
+        return String::new();
+    };
+    let mut lines: Vec<String> = attributes_mut(&mut schema)
+        .filter_map(|(label, attribute)| {
+            let name = attribute.get("name")?.as_str()?;
+            let unit = attribute.get("unit")?.as_str()?;
+            reads_property(query, name).then(|| format!("{label}.{name} is in {unit}"))
+        })
+        .collect();
+    lines.dedup();
+    lines.join("\n")
+}
+
+/// The attributes of every entity and relation in a schema's JSON, with their label.
+fn attributes_mut(schema: &mut Value) -> impl Iterator<Item = (String, &mut serde_json::Map<String, Value>)> {
+    schema
+        .as_object_mut()
+        .into_iter()
+        .flat_map(|schema| schema.iter_mut())
+        .filter(|(kind, _)| *kind == "entities" || *kind == "relations")
+        .filter_map(|(_, items)| items.as_array_mut())
+        .flatten()
+        .filter_map(|item| {
+            let label = item.get("label")?.as_str()?.to_string();
+            let attributes = item.get_mut("attributes")?.as_array_mut()?;
+            Some(
+                attributes
+                    .iter_mut()
+                    .filter_map(Value::as_object_mut)
+                    .map(move |attribute| (label.clone(), attribute)),
+            )
+        })
+        .flatten()
+}
+
+/// Whether `query` reads a property called `name`, as `x.name` or ``x.`name` ``.
+fn reads_property(
+    query: &str,
+    name: &str,
+) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    [format!(".{name}"), format!(".`{name}`")].iter().any(|access| {
+        query
+            .match_indices(access.as_str())
+            .any(|(at, _)| !query[at + access.len()..].starts_with(is_word))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"{"entities":[{"label":"Order","attributes":[
+        {"name":"total","type":"Float"},{"name":"totalItems","type":"Integer"}]}],
+        "relations":[{"label":"SHIPPED","attributes":[{"name":"weight","type":"Float","unit":"kg"}]}]}"#;
+
+    #[test]
+    fn parses_units_and_rejects_incomplete_pairs() {
+        let units = AttributeUnits::parse("Order.total = USD millions; Movie.runtime=minutes;").unwrap();
+        assert_eq!(units.unit("Order", "total"), Some("USD millions"));
+        assert_eq!(units.unit("Movie", "runtime"), Some("minutes"));
+        assert_eq!(units.unit("Order", "runtime"), None);
+        assert!(AttributeUnits::parse("").unwrap().is_empty());
+
+        assert!(AttributeUnits::parse("total=USD").is_err());
+        assert!(AttributeUnits::parse("Order.total").is_err());
+        assert!(AttributeUnits::parse("Order.total=").is_err());
+    }
+
+    #[test]
+    fn annotates_listed_attributes_and_keeps_existing_units() {
+        let units = AttributeUnits::parse("Order.total=USD millions;SHIPPED.weight=lb").unwrap();
+        let annotated = units.annotate(SCHEMA);
+        let lines = units_for_query(
+            &annotated,
+            "MATCH ()-[s:SHIPPED]->(o:Order) RETURN sum(o.total), s.weight",
+        );
+        assert_eq!(lines, "Order.total is in USD millions\nSHIPPED.weight is in kg");
+
+        assert_eq!(units.annotate("not json"), "not json");
+        assert_eq!(AttributeUnits::default().annotate(SCHEMA), SCHEMA);
+    }
+
+    #[test]
+    fn lists_only_the_properties_the_query_reads() {
+        let annotated = AttributeUnits::parse("Order.total=USD").unwrap().annotate(SCHEMA);
+        assert_eq!(units_for_query(&annotated, "MATCH (o:Order) RETURN o.totalItems"), "");
+        assert_eq!(
+            units_for_query(&annotated, "MATCH (o:Order) RETURN o.`total`"),
+            "Order.total is in USD"
+        );
+        assert_eq!(units_for_query(SCHEMA, "MATCH (o:Order) RETURN o.total"), "");
+    }
+}
@@ -0,0 +1,399 @@
+//! Last-known graph schemas, kept so query generation survives a `FalkorDB` outage.
+//!
+//! Every successful discovery overwrites the graph's snapshot. When discovery later fails because
+//! the database is unreachable, `cypher_only` requests fall back to the snapshot (with a staleness
+//! warning) instead of failing. Snapshots live in the shared [`Storage`], so with a persistent
+//! backend they also survive a restart.
+//!
+//! Requests read schemas from [`CachedSchemas`], an in-memory cache sized and expired as
+//! [`SchemaCacheOptions`] set. It can be warmed from the snapshots at startup, and entries nearing
+//! their TTL are listed by [`CachedSchemas::due_for_refresh`] so they can be re-discovered before
+//! a request has to wait for it.
+
+use crate::storage::{self, Storage, StorageError};
+use moka::sync::Cache;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Storage namespace holding schema snapshots.
+pub const SCHEMA_NAMESPACE: &str = "schemas";
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// A discovered schema and when it was discovered.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SchemaSnapshot {
+    /// Graph the schema describes.
+    pub graph_name: String,
+    /// The schema JSON, as sent to the model.
+    pub schema: String,
+    /// Unix timestamp (seconds) of the discovery.
+    pub discovered_at: u64,
+}
+
+impl SchemaSnapshot {
+    /// Seconds since the schema was discovered.
+    #[must_use]
+    pub fn age_secs(&self) -> u64 {
+        now_secs().saturating_sub(self.discovered_at)
+    }
+}
+
+/// Saves and loads schema snapshots.
+#[derive(Debug, Clone)]
+pub struct SchemaStore {
+    storage: Arc<dyn Storage>,
+}
+
+impl SchemaStore {
+    /// Creates a store backed by `storage`.
+    #[must_use]
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Records `schema` as the latest known schema of `graph_name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the snapshot cannot be saved.
+    pub async fn save(
+        &self,
+        graph_name: &str,
+        schema: &str,
+    ) -> Result<(), StorageError> {
+        let snapshot = SchemaSnapshot {
+            graph_name: graph_name.to_string(),
+            schema: schema.to_string(),
+            discovered_at: now_secs(),
+        };
+        storage::put_json(self.storage.as_ref(), SCHEMA_NAMESPACE, graph_name, &snapshot, None).await
+    }
+
+    /// Returns the latest known schema of `graph_name`, if it was ever discovered.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the backend fails or the stored snapshot is corrupt.
+    pub async fn load(
+        &self,
+        graph_name: &str,
+    ) -> Result<Option<SchemaSnapshot>, StorageError> {
+        storage::get_json(self.storage.as_ref(), SCHEMA_NAMESPACE, graph_name).await
+    }
+
+    /// Forgets the snapshot of `graph_name`, e.g. after the graph is deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the snapshot cannot be removed.
+    pub async fn remove(
+        &self,
+        graph_name: &str,
+    ) -> Result<bool, StorageError> {
+        self.storage.delete(SCHEMA_NAMESPACE, graph_name).await
+    }
+
+    /// Returns every stored snapshot, ordered by graph name; corrupt snapshots are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the snapshots cannot be listed.
+    pub async fn all(&self) -> Result<Vec<SchemaSnapshot>, StorageError> {
+        let mut snapshots = Vec::new();
+        for graph_name in self.storage.list_keys(SCHEMA_NAMESPACE).await? {
+            match self.load(&graph_name).await {
+                Ok(Some(snapshot)) => snapshots.push(snapshot),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Skipping schema snapshot of {graph_name}: {e}"),
+            }
+        }
+        Ok(snapshots)
+    }
+}
+
+/// Schemas kept in memory when `SCHEMA_CACHE_SIZE` is not set.
+pub const DEFAULT_SCHEMA_CACHE_SIZE: u64 = 100;
+
+/// How the server's schema cache is sized and kept fresh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaCacheOptions {
+    /// Graphs whose schema is kept; the least used are evicted beyond it.
+    pub capacity: u64,
+    /// Age after which a schema is discovered again; `None` keeps it until evicted or cleared.
+    pub ttl: Option<Duration>,
+    /// Whether a background task re-discovers schemas before their TTL runs out.
+    pub refresh: bool,
+    /// Whether the cache starts with the stored snapshots younger than the TTL.
+    pub warm: bool,
+}
+
+impl Default for SchemaCacheOptions {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_SCHEMA_CACHE_SIZE,
+            ttl: None,
+            refresh: false,
+            warm: false,
+        }
+    }
+}
+
+/// A cached schema and the Unix time (seconds) it was discovered.
+#[derive(Debug, Clone)]
+struct CachedSchema {
+    schema: Arc<str>,
+    discovered_at: u64,
+}
+
+/// Expires each schema once it is `ttl` old, counting from its discovery rather than from when it
+/// entered the cache, so warmed snapshots keep their age.
+struct SchemaExpiry {
+    ttl: Duration,
+}
+
+impl SchemaExpiry {
+    fn remaining(
+        &self,
+        value: &CachedSchema,
+    ) -> Duration {
+        self.ttl
+            .saturating_sub(Duration::from_secs(now_secs().saturating_sub(value.discovered_at)))
+    }
+}
+
+impl moka::Expiry<String, CachedSchema> for SchemaExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        value: &CachedSchema,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(self.remaining(value))
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &String,
+        value: &CachedSchema,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(self.remaining(value))
+    }
+}
+
+/// Discovered schemas by graph name, shared rather than copied into each request.
+#[derive(Clone)]
+pub struct CachedSchemas {
+    cache: Cache<String, CachedSchema>,
+    options: SchemaCacheOptions,
+}
+
+impl std::fmt::Debug for CachedSchemas {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.debug_struct("CachedSchemas")
+            .field("entries", &self.cache.entry_count())
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl CachedSchemas {
+    #[must_use]
+    pub fn new(options: SchemaCacheOptions) -> Self {
+        let mut cache = Cache::builder().max_capacity(options.capacity);
+        if let Some(ttl) = options.ttl {
+            cache = cache.expire_after(SchemaExpiry { ttl });
+        }
+        Self {
+            cache: cache.build(),
+            options,
+        }
+    }
+
+    #[must_use]
+    pub const fn options(&self) -> SchemaCacheOptions {
+        self.options
+    }
+
+    #[must_use]
+    pub fn get(
+        &self,
+        graph_name: &str,
+    ) -> Option<Arc<str>> {
+        self.cache.get(graph_name).map(|cached| cached.schema)
+    }
+
+    #[must_use]
+    pub fn contains_key(
+        &self,
+        graph_name: &str,
+    ) -> bool {
+        self.cache.contains_key(graph_name)
+    }
+
+    /// Caches a schema discovered just now.
+    pub fn insert(
+        &self,
+        graph_name: String,
+        schema: Arc<str>,
+    ) {
+        self.cache.insert(
+            graph_name,
+            CachedSchema {
+                schema,
+                discovered_at: now_secs(),
+            },
+        );
+    }
+
+    /// Caches a stored snapshot with its discovery time, unless it is already past the TTL.
+    /// Returns whether it was cached.
+    #[must_use]
+    pub fn insert_snapshot(
+        &self,
+        snapshot: SchemaSnapshot,
+    ) -> bool {
+        if self
+            .options
+            .ttl
+            .is_some_and(|ttl| Duration::from_secs(snapshot.age_secs()) >= ttl)
+        {
+            return false;
+        }
+        self.cache.insert(
+            snapshot.graph_name,
+            CachedSchema {
+                schema: snapshot.schema.into(),
+                discovered_at: snapshot.discovered_at,
+            },
+        );
+        true
+    }
+
+    pub fn invalidate(
+        &self,
+        graph_name: &str,
+    ) {
+        self.cache.invalidate(graph_name);
+    }
+
+    /// How often the background refresh looks for aging schemas: a quarter of the TTL, at least a
+    /// second. `None` when refresh is off or nothing expires.
+    #[must_use]
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        self.options
+            .ttl
+            .filter(|_| self.options.refresh)
+            .map(|ttl| (ttl / 4).max(Duration::from_secs(1)))
+    }
+
+    /// Graphs whose schema has used up three quarters of its TTL, so a refresh running every
+    /// [`refresh_interval`](Self::refresh_interval) replaces them before they expire.
+    #[must_use]
+    pub fn due_for_refresh(&self) -> Vec<String> {
+        let Some(ttl) = self.options.ttl else {
+            return Vec::new();
+        };
+        let due_after = ttl.saturating_sub(ttl / 4);
+        let mut due: Vec<String> = self
+            .cache
+            .iter()
+            .filter(|(_, cached)| Duration::from_secs(now_secs().saturating_sub(cached.discovered_at)) >= due_after)
+            .map(|(graph_name, _)| graph_name.as_ref().clone())
+            .collect();
+        due.sort();
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn latest_snapshot_wins() {
+        let store = SchemaStore::new(Arc::new(InMemoryStorage::new()));
+        assert_eq!(store.load("movies").await.unwrap(), None);
+
+        store.save("movies", r#"{"entities":[]}"#).await.unwrap();
+        store.save("movies", r#"{"entities":[{"label":"Movie"}]}"#).await.unwrap();
+        let snapshot = store.load("movies").await.unwrap().unwrap();
+        assert_eq!(snapshot.schema, r#"{"entities":[{"label":"Movie"}]}"#);
+        assert!(snapshot.age_secs() < 60);
+
+        assert!(store.remove("movies").await.unwrap());
+        assert_eq!(store.load("movies").await.unwrap(), None);
+    }
+
+    fn snapshot(
+        graph_name: &str,
+        age_secs: u64,
+    ) -> SchemaSnapshot {
+        SchemaSnapshot {
+            graph_name: graph_name.to_string(),
+            schema: format!("{{\"graph\":\"{graph_name}\"}}"),
+            discovered_at: now_secs() - age_secs,
+        }
+    }
+
+    #[tokio::test]
+    async fn warms_from_snapshots_younger_than_the_ttl() {
+        let store = SchemaStore::new(Arc::new(InMemoryStorage::new()));
+        store.save("social", "{}").await.unwrap();
+        store.save("movies", "{}").await.unwrap();
+        assert_eq!(
+            store
+                .all()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|snapshot| snapshot.graph_name)
+                .collect::<Vec<_>>(),
+            vec!["movies", "social"]
+        );
+
+        let cache = CachedSchemas::new(SchemaCacheOptions {
+            ttl: Some(Duration::from_secs(600)),
+            ..SchemaCacheOptions::default()
+        });
+        assert!(cache.insert_snapshot(snapshot("movies", 60)));
+        assert!(!cache.insert_snapshot(snapshot("social", 600)));
+        assert_eq!(cache.get("movies").as_deref(), Some(r#"{"graph":"movies"}"#));
+        assert!(!cache.contains_key("social"));
+
+        cache.invalidate("movies");
+        assert_eq!(cache.get("movies"), None);
+    }
+
+    #[test]
+    fn aging_schemas_are_due_for_refresh() {
+        let cache = CachedSchemas::new(SchemaCacheOptions {
+            ttl: Some(Duration::from_secs(400)),
+            refresh: true,
+            ..SchemaCacheOptions::default()
+        });
+        assert_eq!(cache.refresh_interval(), Some(Duration::from_secs(100)));
+        cache.insert("fresh".to_string(), "{}".into());
+        assert!(cache.insert_snapshot(snapshot("aging", 310)));
+        assert!(cache.insert_snapshot(snapshot("recent", 200)));
+        assert_eq!(cache.due_for_refresh(), vec!["aging"]);
+
+        let forever = CachedSchemas::new(SchemaCacheOptions {
+            refresh: true,
+            ..SchemaCacheOptions::default()
+        });
+        assert!(forever.insert_snapshot(snapshot("old", 1_000_000)));
+        assert!(forever.contains_key("old"));
+        assert_eq!(forever.refresh_interval(), None);
+        assert!(forever.due_for_refresh().is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+//! Hybrid keyword + vector search over a graph's nodes.
+//!
+//! A [`SearchTargets`] list names what is searched: a label with a full-text index (`Movie`), or a
+//! vector property with a vector index (`Movie.embedding`). Each target ranks nodes on its own, and
+//! [`fuse`] merges the rankings with reciprocal rank fusion, so a node that both the keywords and the
+//! embedding find ranks above one that only either finds.
+//!
+//! ```rust
+//! use text_to_cypher::search::SearchTargets;
+//!
+//! let targets = SearchTargets::parse("Movie;Movie.embedding").unwrap();
+//! assert_eq!(targets.len(), 2);
+//! assert!(targets.needs_vector());
+//! ```
+
+use crate::core::{QueryParameters, declare_parameters, query_rows};
+use crate::error::TextToCypherError;
+use crate::query_result::{ResultNode, ResultValue};
+use falkordb::FalkorValue;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Rank offset of reciprocal rank fusion: a node at rank `r` of a ranking scores `1 / (RRF_K + r)`.
+/// 60 is the customary value; it keeps one ranking's top hit from drowning out the others.
+const RRF_K: f64 = 60.0;
+
+/// One index searched by [`search`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchTarget {
+    /// The full-text index of a label, queried with `db.idx.fulltext.queryNodes`.
+    FullText { label: String },
+    /// A vector property, queried with `db.idx.vector.queryNodes`.
+    Vector { label: String, property: String },
+}
+
+impl SearchTarget {
+    /// The query ranking this target's best `limit` nodes for `$text` or `$vector`.
+    fn query(
+        &self,
+        limit: usize,
+    ) -> String {
+        let call = match self {
+            Self::FullText { .. } => "CALL db.idx.fulltext.queryNodes($label, $text)".to_string(),
+            Self::Vector { .. } => {
+                format!("CALL db.idx.vector.queryNodes($label, $property, {limit}, vecf32($vector))")
+            }
+        };
+        format!("{call} YIELD node, score RETURN node, score LIMIT {limit}")
+    }
+
+    /// Whether a higher score is a better match: full-text scores grow with relevance, vector
+    /// scores are distances.
+    const fn higher_is_better(&self) -> bool {
+        matches!(self, Self::FullText { .. })
+    }
+}
+
+impl std::fmt::Display for SearchTarget {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::FullText { label } => write!(f, "fulltext:{label}"),
+            Self::Vector { label, property } => write!(f, "vector:{label}.{property}"),
+        }
+    }
+}
+
+/// The indexes a search covers, e.g. `Movie;Movie.embedding` (`SEARCH_TARGETS`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchTargets {
+    targets: Vec<SearchTarget>,
+}
+
+impl SearchTargets {
+    /// Parses targets separated by `;`: a bare label searches its full-text index, and
+    /// `Label.property` searches a vector property.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first target with an empty label or property.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut targets = Vec::new();
+        for target in spec.split(';').map(str::trim).filter(|target| !target.is_empty()) {
+            let parsed = match target.split_once('.') {
+                Some((label, property)) => SearchTarget::Vector {
+                    label: label.trim().to_string(),
+                    property: property.trim().to_string(),
+                },
+                None => SearchTarget::FullText {
+                    label: target.to_string(),
+                },
+            };
+            let complete = match &parsed {
+                SearchTarget::FullText { label } => !label.is_empty(),
+                SearchTarget::Vector { label, property } => !label.is_empty() && !property.is_empty(),
+            };
+            if !complete {
+                return Err(format!("'{target}' is not of the form Label or Label.property"));
+            }
+            if !targets.contains(&parsed) {
+                targets.push(parsed);
+            }
+        }
+        Ok(Self { targets })
+    }
+
+    /// Whether no target is set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Number of targets.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Whether any target is a vector property, which needs a query vector.
+    #[must_use]
+    pub fn needs_vector(&self) -> bool {
+        self.targets.iter().any(|target| matches!(target, SearchTarget::Vector { .. }))
+    }
+
+    /// The targets, in the order they were given.
+    pub fn iter(&self) -> impl Iterator<Item = &SearchTarget> {
+        self.targets.iter()
+    }
+}
+
+/// A node found by [`search`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct SearchHit {
+    pub id: i64,
+    pub labels: Vec<String>,
+    /// The node's properties, without the vector properties searched.
+    #[cfg_attr(feature = "server", schema(value_type = Object))]
+    pub properties: Value,
+    /// Fused score; higher is better.
+    pub score: f64,
+    /// The targets that found the node, e.g. `["fulltext:Movie", "vector:Movie.embedding"]`.
+    pub matched_by: Vec<String>,
+}
+
+/// Merges rankings (best first, each named by its target) into the `limit` best nodes, scoring each
+/// node `1 / (60 + rank)` per ranking that holds it.
+#[must_use]
+pub fn fuse(
+    rankings: Vec<(String, Vec<ResultNode>)>,
+    limit: usize,
+) -> Vec<SearchHit> {
+    let mut hits: Vec<SearchHit> = Vec::new();
+    let mut positions: HashMap<i64, usize> = HashMap::new();
+    for (target, nodes) in rankings {
+        for (rank, node) in nodes.into_iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let score = 1.0 / (RRF_K + (rank + 1) as f64);
+            if let Some(&position) = positions.get(&node.id) {
+                let hit = &mut hits[position];
+                hit.score += score;
+                if !hit.matched_by.contains(&target) {
+                    hit.matched_by.push(target.clone());
+                }
+                continue;
+            }
+            positions.insert(node.id, hits.len());
+            hits.push(SearchHit {
+                id: node.id,
+                labels: node.labels,
+                properties: serde_json::from_str(&ResultValue::Map(node.properties).to_json()).unwrap_or_default(),
+                score,
+                matched_by: vec![target.clone()],
+            });
+        }
+    }
+    // Stable, so ties keep the order in which the rankings found them
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    hits
+}
+
+/// Searches `graph_name` for `text` across `targets` and returns the `limit` best nodes.
+///
+/// Vector targets are compared against `vector`, and skipped when it is `None`. A target that
+/// fails, e.g. because its index does not exist, is skipped with a warning.
+///
+/// # Errors
+///
+/// Returns a [`TextToCypherError::Execution`] when no target could be searched.
+pub async fn search(
+    falkordb_connection: &str,
+    graph_name: &str,
+    targets: &SearchTargets,
+    text: &str,
+    vector: Option<&[f32]>,
+    limit: usize,
+) -> Result<Vec<SearchHit>, TextToCypherError> {
+    let mut rankings = Vec::new();
+    let mut last_error = None;
+    let mut vector_properties = Vec::new();
+    for target in targets.iter() {
+        let mut parameters = QueryParameters::new();
+        match target {
+            SearchTarget::FullText { label } => {
+                parameters.insert("label".to_string(), Value::from(label.as_str()));
+                parameters.insert("text".to_string(), Value::from(text));
+            }
+            SearchTarget::Vector { label, property } => {
+                let Some(vector) = vector else {
+                    tracing::warn!("Skipping search target {target}: no query vector");
+                    continue;
+                };
+                parameters.insert("label".to_string(), Value::from(label.as_str()));
+                parameters.insert("property".to_string(), Value::from(property.as_str()));
+                parameters.insert("vector".to_string(), Value::from(vector.to_vec()));
+                vector_properties.push(property.clone());
+            }
+        }
+        let query = declare_parameters(&target.query(limit), &parameters)?;
+        match query_rows(&query, graph_name, falkordb_connection, true).await {
+            Ok(rows) => rankings.push((target.to_string(), ranked_nodes(target, &rows))),
+            Err(e) => {
+                tracing::warn!("Skipping search target {target}: {e}");
+                last_error = Some(e);
+            }
+        }
+    }
+    if rankings.is_empty() {
+        return Err(last_error.unwrap_or_else(|| {
+            TextToCypherError::Execution("No search target could be searched; set a query vector".to_string())
+        }));
+    }
+
+    let mut hits = fuse(rankings, limit);
+    for hit in &mut hits {
+        if let Value::Object(properties) = &mut hit.properties {
+            properties.retain(|name, _| !vector_properties.contains(name));
+        }
+    }
+    Ok(hits)
+}
+
+/// The nodes of a target's `node, score` rows, best first.
+fn ranked_nodes(
+    target: &SearchTarget,
+    rows: &[Vec<FalkorValue>],
+) -> Vec<ResultNode> {
+    let mut scored: Vec<(f64, ResultNode)> = rows
+        .iter()
+        .filter_map(|row| match (row.first(), row.get(1)) {
+            (Some(FalkorValue::Node(node)), Some(FalkorValue::F64(score))) => Some((*score, node.into())),
+            #[allow(clippy::cast_precision_loss)]
+            (Some(FalkorValue::Node(node)), Some(FalkorValue::I64(score))) => Some((*score as f64, node.into())),
+            _ => None,
+        })
+        .collect();
+    if target.higher_is_better() {
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    } else {
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+    scored.into_iter().map(|(_, node)| node).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn node(
+        id: i64,
+        title: &str,
+    ) -> ResultNode {
+        ResultNode {
+            id,
+            labels: vec!["Movie".to_string()],
+            properties: BTreeMap::from([("title".to_string(), ResultValue::String(title.to_string()))]),
+        }
+    }
+
+    #[test]
+    fn parses_full_text_and_vector_targets() {
+        let targets = SearchTargets::parse(" Movie ; Movie.embedding;Movie").unwrap();
+        assert_eq!(
+            targets.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["fulltext:Movie", "vector:Movie.embedding"]
+        );
+        assert!(targets.needs_vector());
+        assert!(!SearchTargets::parse("Movie").unwrap().needs_vector());
+        assert!(SearchTargets::parse("").unwrap().is_empty());
+
+        assert!(SearchTargets::parse("Movie.").is_err());
+        assert!(SearchTargets::parse(".embedding").is_err());
+    }
+
+    #[test]
+    fn fusion_ranks_nodes_found_by_both_targets_first() {
+        let hits = fuse(
+            vec![
+                ("fulltext:Movie".to_string(), vec![node(1, "Heat"), node(2, "Alien")]),
+                (
+                    "vector:Movie.embedding".to_string(),
+                    vec![node(3, "Ronin"), node(2, "Alien")],
+                ),
+            ],
+            2,
+        );
+        assert_eq!(hits.iter().map(|hit| hit.id).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(hits[0].matched_by, vec!["fulltext:Movie", "vector:Movie.embedding"]);
+        assert_eq!(hits[0].properties["title"], "Alien");
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn target_queries_take_every_value_as_a_parameter() {
+        let target = SearchTarget::Vector {
+            label: "Movie".to_string(),
+            property: "embedding".to_string(),
+        };
+        assert_eq!(
+            target.query(5),
+            "CALL db.idx.vector.queryNodes($label, $property, 5, vecf32($vector)) YIELD node, score RETURN node, \
+             score LIMIT 5"
+        );
+        assert!(!target.higher_is_better());
+    }
+}
@@ -0,0 +1,419 @@
+//! Conversation sessions persisted in the server's [`Storage`].
+//!
+//! A [`Session`] holds the running chat history for one graph plus a [`SessionTurn`] per question:
+//! the generated query, metadata about its result, the answer, and any error. Sessions can be
+//! exported as a self-contained [`SessionExport`] document and imported into another deployment (or
+//! attached to a bug report) to reproduce a conversation.
+//!
+//! A store built [`with_ttl`](SessionStore::with_ttl) expires sessions left idle for that long, and
+//! keeps the schema each session last used in memory for the same time, so follow-up questions are
+//! asked against the schema the conversation started from.
+
+use crate::chat::ChatMessage;
+use crate::storage::{self, Storage, StorageError};
+use crate::usage::TokenUsage;
+use moka::sync::Cache;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Storage namespace holding sessions.
+pub const SESSIONS_NAMESPACE: &str = "sessions";
+
+/// Version of the [`SessionExport`] document format produced by this build.
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Maximum number of characters of a query result kept in [`SessionTurn::result_preview`].
+const RESULT_PREVIEW_CHARS: usize = 500;
+
+/// Maximum number of sessions whose last schema is kept in memory.
+const SCHEMA_CACHE_CAPACITY: u64 = 1_000;
+
+/// One question/answer exchange within a session.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default, PartialEq)]
+pub struct SessionTurn {
+    /// The user's question.
+    pub question: String,
+    /// Model used for the turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// The validated Cypher query, if one was generated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cypher_query: Option<String>,
+    /// Size in bytes of the formatted query result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_bytes: Option<usize>,
+    /// The beginning of the formatted query result, for context without storing full results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_preview: Option<String>,
+    /// The natural-language answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+    /// Model self-reported answer confidence (0-100).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<u8>,
+    /// Error that ended the turn, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Token usage of the turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+    /// Times the query was regenerated after it failed.
+    #[serde(default)]
+    pub heal_attempts: usize,
+    /// Unix timestamp (seconds) at which the turn finished.
+    #[serde(default)]
+    pub timestamp: u64,
+}
+
+impl SessionTurn {
+    /// Starts a turn for `question`.
+    #[must_use]
+    pub fn new(question: impl Into<String>) -> Self {
+        Self {
+            question: question.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Records result metadata (size and a short preview) instead of the full result.
+    pub fn set_result(
+        &mut self,
+        result: &str,
+    ) {
+        self.result_bytes = Some(result.len());
+        self.result_preview = Some(result.chars().take(RESULT_PREVIEW_CHARS).collect());
+    }
+}
+
+/// A conversation against one graph.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct Session {
+    /// Server-assigned identifier.
+    pub id: String,
+    /// Graph the conversation is about.
+    pub graph_name: String,
+    /// Unix timestamp (seconds) of creation.
+    pub created_at: u64,
+    /// Unix timestamp (seconds) of the last change.
+    pub updated_at: u64,
+    /// Full chat history, including assistant answers.
+    #[serde(default)]
+    pub messages: Vec<ChatMessage>,
+    /// Per-question record of queries, results, and answers.
+    #[serde(default)]
+    pub turns: Vec<SessionTurn>,
+}
+
+impl Session {
+    /// The query of the latest turn that generated one.
+    #[must_use]
+    pub fn last_query(&self) -> Option<&str> {
+        self.turns.iter().rev().find_map(|turn| turn.cypher_query.as_deref())
+    }
+}
+
+/// Portable, self-describing session document returned by export and accepted by import.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct SessionExport {
+    /// Document format version; imports reject versions newer than [`EXPORT_FORMAT_VERSION`].
+    pub format_version: u32,
+    /// Unix timestamp (seconds) of the export.
+    pub exported_at: u64,
+    /// The exported session.
+    pub session: Session,
+}
+
+/// Unix time in seconds.
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Creates, updates, exports, and imports sessions on top of a [`Storage`] backend.
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    storage: Arc<dyn Storage>,
+    ttl: Option<Duration>,
+    schemas: Cache<String, Arc<str>>,
+}
+
+impl SessionStore {
+    /// Creates a store backed by `storage` whose sessions never expire.
+    #[must_use]
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            ttl: None,
+            schemas: Cache::new(SCHEMA_CACHE_CAPACITY),
+        }
+    }
+
+    /// Expires sessions (and their last schema) once idle for `ttl`; `None` keeps them forever.
+    #[must_use]
+    pub fn with_ttl(
+        self,
+        ttl: Option<Duration>,
+    ) -> Self {
+        let mut schemas = Cache::builder().max_capacity(SCHEMA_CACHE_CAPACITY);
+        if let Some(ttl) = ttl {
+            schemas = schemas.time_to_idle(ttl);
+        }
+        Self {
+            storage: self.storage,
+            ttl,
+            schemas: schemas.build(),
+        }
+    }
+
+    /// The schema session `id` last used, unless it has been idle for longer than the TTL.
+    #[must_use]
+    pub fn last_schema(
+        &self,
+        id: &str,
+    ) -> Option<Arc<str>> {
+        self.schemas.get(id)
+    }
+
+    /// Remembers the schema session `id` used, for its next questions.
+    pub fn set_last_schema(
+        &self,
+        id: &str,
+        schema: Arc<str>,
+    ) {
+        self.schemas.insert(id.to_string(), schema);
+    }
+
+    /// Creates and persists an empty session for `graph_name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the session cannot be saved.
+    pub async fn create(
+        &self,
+        graph_name: &str,
+    ) -> Result<Session, StorageError> {
+        let now = now_secs();
+        let session = Session {
+            id: Uuid::new_v4().to_string(),
+            graph_name: graph_name.to_string(),
+            created_at: now,
+            updated_at: now,
+            messages: Vec::new(),
+            turns: Vec::new(),
+        };
+        self.save(&session).await?;
+        Ok(session)
+    }
+
+    /// Loads a session.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the backend fails or the stored session is corrupt.
+    pub async fn get(
+        &self,
+        id: &str,
+    ) -> Result<Option<Session>, StorageError> {
+        storage::get_json(self.storage.as_ref(), SESSIONS_NAMESPACE, id).await
+    }
+
+    /// Persists `session`, replacing any stored version and restarting its TTL.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the session cannot be saved.
+    pub async fn save(
+        &self,
+        session: &Session,
+    ) -> Result<(), StorageError> {
+        storage::put_json(
+            self.storage.as_ref(),
+            SESSIONS_NAMESPACE,
+            &session.id,
+            session,
+            self.ttl,
+        )
+        .await
+    }
+
+    /// Appends a finished turn, replacing the session's history with `messages`.
+    ///
+    /// Returns `false` if the session does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the session cannot be loaded or saved.
+    pub async fn record_turn(
+        &self,
+        id: &str,
+        messages: Vec<ChatMessage>,
+        mut turn: SessionTurn,
+    ) -> Result<bool, StorageError> {
+        let Some(mut session) = self.get(id).await? else {
+            return Ok(false);
+        };
+        let now = now_secs();
+        turn.timestamp = now;
+        session.messages = messages;
+        session.turns.push(turn);
+        session.updated_at = now;
+        self.save(&session).await?;
+        Ok(true)
+    }
+
+    /// Exports a session as a portable document, or `None` if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns the storage error if the session cannot be loaded.
+    pub async fn export(
+        &self,
+        id: &str,
+    ) -> Result<Option<SessionExport>, StorageError> {
+        Ok(self.get(id).await?.map(|session| SessionExport {
+            format_version: EXPORT_FORMAT_VERSION,
+            exported_at: now_secs(),
+            session,
+        }))
+    }
+
+    /// Imports an exported session as a **new** session with a fresh ID, so importing never
+    /// overwrites an existing conversation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::Serialization`] if the document uses a newer format version, or the
+    /// storage error if the session cannot be saved.
+    pub async fn import(
+        &self,
+        export: SessionExport,
+    ) -> Result<Session, StorageError> {
+        if export.format_version > EXPORT_FORMAT_VERSION {
+            return Err(StorageError::Serialization(format!(
+                "unsupported session export format_version {} (this server supports up to {EXPORT_FORMAT_VERSION})",
+                export.format_version
+            )));
+        }
+        let mut session = export.session;
+        session.id = Uuid::new_v4().to_string();
+        session.updated_at = now_secs();
+        self.save(&session).await?;
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ChatRole;
+    use crate::storage::InMemoryStorage;
+
+    fn store() -> SessionStore {
+        SessionStore::new(Arc::new(InMemoryStorage::new()))
+    }
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage {
+            role: ChatRole::User,
+            content: content.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn record_turn_appends_and_replaces_history() {
+        let store = store();
+        let session = store.create("movies").await.unwrap();
+
+        let mut turn = SessionTurn::new("Who acted in Heat?");
+        turn.cypher_query = Some("MATCH (a)-[:ACTED_IN]->(:Movie {title: 'Heat'}) RETURN a.name".into());
+        turn.set_result(&"x".repeat(RESULT_PREVIEW_CHARS + 10));
+        assert!(
+            store
+                .record_turn(&session.id, vec![user("Who acted in Heat?")], turn)
+                .await
+                .unwrap()
+        );
+
+        let stored = store.get(&session.id).await.unwrap().unwrap();
+        assert_eq!(stored.messages.len(), 1);
+        assert_eq!(stored.turns.len(), 1);
+        assert_eq!(stored.turns[0].result_bytes, Some(RESULT_PREVIEW_CHARS + 10));
+        assert_eq!(
+            stored.turns[0].result_preview.as_ref().map(String::len),
+            Some(RESULT_PREVIEW_CHARS)
+        );
+        assert!(stored.turns[0].timestamp > 0);
+
+        assert!(!store.record_turn("missing", Vec::new(), SessionTurn::new("q")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn export_then_import_creates_an_independent_copy() {
+        let store = store();
+        let session = store.create("movies").await.unwrap();
+        store
+            .record_turn(&session.id, vec![user("q")], SessionTurn::new("q"))
+            .await
+            .unwrap();
+
+        let export = store.export(&session.id).await.unwrap().unwrap();
+        assert_eq!(export.format_version, EXPORT_FORMAT_VERSION);
+        let json = serde_json::to_string(&export).unwrap();
+
+        // Import into a different store, as when moving between environments.
+        let other = self::store();
+        let imported = other.import(serde_json::from_str(&json).unwrap()).await.unwrap();
+        assert_ne!(imported.id, session.id);
+        assert_eq!(imported.graph_name, "movies");
+        assert_eq!(imported.turns, export.session.turns);
+        assert_eq!(other.get(&imported.id).await.unwrap(), Some(imported));
+
+        assert_eq!(store.export("missing").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn sessions_and_schemas_expire_once_idle() {
+        let store = SessionStore::new(Arc::new(InMemoryStorage::new())).with_ttl(Some(Duration::from_millis(50)));
+        let session = store.create("movies").await.unwrap();
+        store.set_last_schema(&session.id, Arc::from("{}"));
+        assert_eq!(store.last_schema(&session.id).as_deref(), Some("{}"));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(store.get(&session.id).await.unwrap(), None);
+        assert_eq!(store.last_schema(&session.id), None);
+    }
+
+    #[tokio::test]
+    async fn last_query_skips_turns_without_one() {
+        let store = store();
+        let session = store.create("movies").await.unwrap();
+        let mut turn = SessionTurn::new("Which movies came out in 1995?");
+        turn.cypher_query = Some("MATCH (m:Movie {released: 1995}) RETURN m.title".into());
+        store.record_turn(&session.id, Vec::new(), turn).await.unwrap();
+        store
+            .record_turn(&session.id, Vec::new(), SessionTurn::new("Hello?"))
+            .await
+            .unwrap();
+
+        let stored = store.get(&session.id).await.unwrap().unwrap();
+        assert_eq!(
+            stored.last_query(),
+            Some("MATCH (m:Movie {released: 1995}) RETURN m.title")
+        );
+        assert_eq!(session.last_query(), None);
+    }
+
+    #[tokio::test]
+    async fn import_rejects_newer_format() {
+        let store = store();
+        let session = store.create("g").await.unwrap();
+        let mut export = store.export(&session.id).await.unwrap().unwrap();
+        export.format_version = EXPORT_FORMAT_VERSION + 1;
+        assert!(matches!(
+            store.import(export).await,
+            Err(StorageError::Serialization(_))
+        ));
+    }
+}
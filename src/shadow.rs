@@ -0,0 +1,361 @@
+//! Shadow traffic for evaluating model and prompt upgrades on live requests.
+//!
+//! A [`Shadow`] re-runs Cypher generation for a sampled percentage of production requests with an
+//! alternate model and, optionally, an alternate system prompt template. The shadow query is never
+//! served or executed: it is only compared with the production query, and the running totals are
+//! reported as a [`ShadowReport`] so an operator can judge whether the candidate behaves like the
+//! current configuration before switching over.
+
+use crate::chat::ChatRequest;
+use crate::core::{GenerationOutcome, generate_cypher_outcome_with_template};
+use crate::model_options::ModelOptions;
+use crate::skills::SkillCatalog;
+use crate::usage::TokenUsage;
+use genai::Client as GenAiClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Share of requests shadowed when no percentage is configured.
+pub const DEFAULT_SHADOW_PERCENT: u8 = 10;
+
+/// What to shadow production traffic with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShadowConfig {
+    /// Model the shadow queries are generated with.
+    pub model: String,
+    /// Percentage (0-100) of requests to shadow.
+    pub percent: u8,
+    /// Alternate system prompt template, with the same placeholders as the built-in one.
+    pub system_template: Option<String>,
+}
+
+impl ShadowConfig {
+    /// Builds a configuration for `model`, shadowing `percent` percent of requests (default
+    /// [`DEFAULT_SHADOW_PERCENT`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the model is empty or the percentage is not a number from 0 to 100.
+    pub fn new(
+        model: &str,
+        percent: Option<&str>,
+        system_template: Option<String>,
+    ) -> Result<Self, String> {
+        let model = model.trim();
+        if model.is_empty() {
+            return Err("shadow model must not be empty".to_string());
+        }
+        let percent = match percent.map(str::trim) {
+            None | Some("") => DEFAULT_SHADOW_PERCENT,
+            Some(value) => value
+                .parse::<u8>()
+                .ok()
+                .filter(|percent| *percent <= 100)
+                .ok_or_else(|| format!("shadow percentage '{value}' must be a number from 0 to 100"))?,
+        };
+        Ok(Self {
+            model: model.to_string(),
+            percent,
+            system_template,
+        })
+    }
+}
+
+/// One production query compared with its shadow.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct ShadowComparison {
+    /// Model the shadow query was generated with.
+    pub shadow_model: String,
+    /// The shadow query, if generation succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow_query: Option<String>,
+    /// Why shadow generation failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow_error: Option<String>,
+    /// Whether the shadow query differs from the production query beyond whitespace.
+    pub diverged: bool,
+    /// Token overlap (Jaccard index, 0-1) between the two queries; 0 when the shadow failed.
+    pub similarity: f64,
+    /// Shadow generation time in milliseconds.
+    pub latency_ms: u64,
+}
+
+impl ShadowComparison {
+    /// Compares a production query with the shadow outcome.
+    #[must_use]
+    pub fn new(
+        shadow_model: &str,
+        production_query: &str,
+        shadow: Result<String, String>,
+        latency: Duration,
+    ) -> Self {
+        let latency_ms = u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        match shadow {
+            Ok(shadow_query) => Self {
+                shadow_model: shadow_model.to_string(),
+                diverged: normalize(&shadow_query) != normalize(production_query),
+                similarity: similarity(production_query, &shadow_query),
+                shadow_query: Some(shadow_query),
+                shadow_error: None,
+                latency_ms,
+            },
+            Err(error) => Self {
+                shadow_model: shadow_model.to_string(),
+                shadow_query: None,
+                shadow_error: Some(error),
+                diverged: true,
+                similarity: 0.0,
+                latency_ms,
+            },
+        }
+    }
+}
+
+/// Whitespace-insensitive form of a query, without a trailing semicolon.
+fn normalize(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(';')
+        .trim_end()
+        .to_string()
+}
+
+/// Jaccard index of the two queries' token sets.
+fn similarity(
+    a: &str,
+    b: &str,
+) -> f64 {
+    let tokens = |query: &str| -> HashSet<String> {
+        query
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|token| !token.is_empty())
+            .map(str::to_ascii_lowercase)
+            .collect()
+    };
+    let (a, b) = (tokens(a), tokens(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let index = a.intersection(&b).count() as f64 / union as f64;
+    index
+}
+
+/// Divergence totals since the server started.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct ShadowReport {
+    /// Model the shadow queries are generated with.
+    pub shadow_model: String,
+    /// Configured percentage of requests shadowed.
+    pub percent: u8,
+    /// Whether an alternate system prompt template is shadowed.
+    pub custom_system_prompt: bool,
+    /// Requests compared with a shadow.
+    pub compared: u64,
+    /// Shadow queries identical to production (ignoring whitespace).
+    pub identical: u64,
+    /// Shadow queries that differ from production.
+    pub diverged: u64,
+    /// Shadow generations that failed (counted as diverged too).
+    pub failed: u64,
+    /// `diverged / compared`, or 0 when nothing was compared.
+    pub divergence_rate: f64,
+    /// Mean token similarity across comparisons, or 0 when nothing was compared.
+    pub mean_similarity: f64,
+    /// Mean shadow generation time in milliseconds.
+    pub mean_latency_ms: u64,
+}
+
+/// Samples requests for shadowing and keeps the divergence totals.
+#[derive(Debug)]
+pub struct Shadow {
+    config: ShadowConfig,
+    seen: AtomicU64,
+    compared: AtomicU64,
+    diverged: AtomicU64,
+    failed: AtomicU64,
+    /// Sum of similarities in millionths, so it fits an atomic.
+    similarity_micros: AtomicU64,
+    latency_ms: AtomicU64,
+}
+
+impl Shadow {
+    #[must_use]
+    pub const fn new(config: ShadowConfig) -> Self {
+        Self {
+            config,
+            seen: AtomicU64::new(0),
+            compared: AtomicU64::new(0),
+            diverged: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            similarity_micros: AtomicU64::new(0),
+            latency_ms: AtomicU64::new(0),
+        }
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &ShadowConfig {
+        &self.config
+    }
+
+    /// Counts a production request and returns whether to shadow it. Sampling is deterministic and
+    /// evenly spread: at 10% every tenth request is shadowed.
+    pub fn sample(&self) -> bool {
+        let percent = u64::from(self.config.percent);
+        let seen = self.seen.fetch_add(1, Ordering::Relaxed) + 1;
+        seen * percent / 100 > (seen - 1) * percent / 100
+    }
+
+    /// Generates the shadow query for a request and compares it with `production_query`.
+    pub async fn compare(
+        &self,
+        client: &GenAiClient,
+        chat_request: &ChatRequest,
+        schema: &str,
+        skill_catalog: Option<&SkillCatalog>,
+        udfs: &str,
+        production_query: &str,
+    ) -> ShadowComparison {
+        let started = Instant::now();
+        let shadow = generate_cypher_outcome_with_template(
+            chat_request,
+            schema,
+            client,
+            &self.config.model,
+            &ModelOptions::default(),
+            skill_catalog,
+            udfs,
+            self.config.system_template.as_deref(),
+            None,
+            &mut TokenUsage::new(),
+        )
+        .await
+        .and_then(GenerationOutcome::into_query)
+        .map_err(|e| e.to_string());
+        let comparison = ShadowComparison::new(&self.config.model, production_query, shadow, started.elapsed());
+        self.record(&comparison);
+        comparison
+    }
+
+    /// Adds a comparison to the totals.
+    pub fn record(
+        &self,
+        comparison: &ShadowComparison,
+    ) {
+        self.compared.fetch_add(1, Ordering::Relaxed);
+        if comparison.diverged {
+            self.diverged.fetch_add(1, Ordering::Relaxed);
+        }
+        if comparison.shadow_error.is_some() {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let micros = (comparison.similarity.clamp(0.0, 1.0) * 1_000_000.0).round() as u64;
+        self.similarity_micros.fetch_add(micros, Ordering::Relaxed);
+        self.latency_ms.fetch_add(comparison.latency_ms, Ordering::Relaxed);
+    }
+
+    /// Returns the totals so far.
+    #[must_use]
+    pub fn report(&self) -> ShadowReport {
+        let compared = self.compared.load(Ordering::Relaxed);
+        let diverged = self.diverged.load(Ordering::Relaxed);
+        #[allow(clippy::cast_precision_loss)]
+        let (divergence_rate, mean_similarity) = if compared == 0 {
+            (0.0, 0.0)
+        } else {
+            (
+                diverged as f64 / compared as f64,
+                self.similarity_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0 / compared as f64,
+            )
+        };
+        ShadowReport {
+            shadow_model: self.config.model.clone(),
+            percent: self.config.percent,
+            custom_system_prompt: self.config.system_template.is_some(),
+            compared,
+            identical: compared - diverged,
+            diverged,
+            failed: self.failed.load(Ordering::Relaxed),
+            divergence_rate,
+            mean_similarity,
+            mean_latency_ms: self.latency_ms.load(Ordering::Relaxed).checked_div(compared).unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shadow(percent: &str) -> Shadow {
+        Shadow::new(ShadowConfig::new("openai:gpt-4o-mini", Some(percent), None).unwrap())
+    }
+
+    #[test]
+    fn config_validates_percentage() {
+        assert_eq!(
+            ShadowConfig::new("m", None, None).unwrap().percent,
+            DEFAULT_SHADOW_PERCENT
+        );
+        assert_eq!(ShadowConfig::new("m", Some(" 25 "), None).unwrap().percent, 25);
+        assert!(ShadowConfig::new("m", Some("101"), None).is_err());
+        assert!(ShadowConfig::new("m", Some("ten"), None).is_err());
+        assert!(ShadowConfig::new(" ", None, None).is_err());
+    }
+
+    #[test]
+    fn samples_the_configured_share() {
+        for (percent, expected) in [("0", 0), ("10", 10), ("25", 25), ("100", 100)] {
+            let shadow = shadow(percent);
+            assert_eq!((0..100).filter(|_| shadow.sample()).count(), expected, "{percent}%");
+        }
+    }
+
+    #[test]
+    fn compares_and_reports_divergence() {
+        let shadow = shadow("100");
+        let production = "MATCH (m:Movie) RETURN m.title LIMIT 10";
+
+        let same = ShadowComparison::new(
+            "s",
+            production,
+            Ok("MATCH (m:Movie)\n  RETURN m.title LIMIT 10;".into()),
+            Duration::from_millis(100),
+        );
+        assert!(!same.diverged);
+        assert!((same.similarity - 1.0).abs() < f64::EPSILON);
+
+        let different = ShadowComparison::new(
+            "s",
+            production,
+            Ok("MATCH (m:Movie) RETURN m.title LIMIT 25".into()),
+            Duration::from_millis(300),
+        );
+        assert!(different.diverged);
+        assert!(different.similarity > 0.5 && different.similarity < 1.0);
+
+        let failed = ShadowComparison::new("s", production, Err("timeout".into()), Duration::ZERO);
+        assert!(failed.diverged);
+
+        for comparison in [&same, &different, &failed] {
+            shadow.record(comparison);
+        }
+        let report = shadow.report();
+        assert_eq!(
+            (report.compared, report.identical, report.diverged, report.failed),
+            (3, 1, 2, 1)
+        );
+        assert!((report.divergence_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.mean_latency_ms, 133);
+    }
+}
@@ -32,6 +32,10 @@ const BUILTIN_SKILLS: &[(&str, &str)] = &[
         "falkordb-path-finding",
         include_str!("../../assets/cypher-skills/falkordb-path-finding/skill.md"),
     ),
+    (
+        "falkordb-graph-algorithms",
+        include_str!("../../assets/cypher-skills/falkordb-graph-algorithms/skill.md"),
+    ),
 ];
 
 /// Stable IDs of upstream `FalkorDB/skills` cypher-skills that perform writes/DDL. Under
@@ -299,13 +299,14 @@ mod tests {
     fn test_builtin_catalog_loads_read_only_skills() {
         let catalog = SkillCatalog::builtin();
         assert!(!catalog.is_empty());
-        assert_eq!(catalog.len(), 5);
+        assert_eq!(catalog.len(), 6);
         for id in [
             "falkordb-index-aware-predicates",
             "falkordb-fulltext-search",
             "falkordb-vector-search",
             "falkordb-parameterized-queries",
             "falkordb-path-finding",
+            "falkordb-graph-algorithms",
         ] {
             assert!(catalog.get_skill(id).is_some(), "missing built-in skill {id}");
         }
@@ -0,0 +1,259 @@
+//! Opt-in, anonymous usage statistics for deployment owners.
+//!
+//! [`UsageStats`] aggregates coarse counters — requests served, success/failure counts, which models
+//! were used, and a latency histogram — so an operator can see how a deployment is being used without
+//! standing up a metrics stack. Nothing identifying is recorded: no questions, graph names, queries,
+//! results, keys, or connection strings. Collection is disabled unless explicitly enabled, and a
+//! disabled collector ignores every `record` call.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Upper bounds (inclusive, in milliseconds) of the latency histogram buckets. Requests slower than
+/// the last bound land in a final overflow bucket.
+const LATENCY_BUCKETS_MS: [u64; 7] = [250, 500, 1_000, 2_500, 5_000, 10_000, 30_000];
+
+/// Maximum number of distinct model names tracked individually. Further models are folded into
+/// [`OTHER_MODELS`] so a client cycling through arbitrary model strings cannot grow the map unbounded.
+const MAX_TRACKED_MODELS: usize = 50;
+
+/// Bucket name used for models beyond [`MAX_TRACKED_MODELS`].
+const OTHER_MODELS: &str = "other";
+
+/// How a request ended, for success-rate accounting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestOutcome {
+    /// The request produced a result.
+    Success,
+    /// The request ended with an error.
+    Failure,
+}
+
+/// Thread-safe, in-process usage statistics collector.
+#[derive(Debug)]
+pub struct UsageStats {
+    enabled: bool,
+    requests: AtomicU64,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    latency: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+    models: Mutex<BTreeMap<String, u64>>,
+}
+
+impl UsageStats {
+    /// Creates a collector. When `enabled` is false every `record` call is a no-op.
+    #[must_use]
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            requests: AtomicU64::new(0),
+            succeeded: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            latency: std::array::from_fn(|_| AtomicU64::new(0)),
+            models: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns whether collection is enabled.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Records one finished request.
+    pub fn record(
+        &self,
+        model: &str,
+        outcome: RequestOutcome,
+        latency: Duration,
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        match outcome {
+            RequestOutcome::Success => self.succeeded.fetch_add(1, Ordering::Relaxed),
+            RequestOutcome::Failure => self.failed.fetch_add(1, Ordering::Relaxed),
+        };
+
+        let elapsed_ms = u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&bound| elapsed_ms <= bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.latency[bucket].fetch_add(1, Ordering::Relaxed);
+
+        let mut models = self.models.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let key = if models.contains_key(model) || models.len() < MAX_TRACKED_MODELS {
+            model
+        } else {
+            OTHER_MODELS
+        };
+        *models.entry(key.to_string()).or_insert(0) += 1;
+    }
+
+    /// Starts timing a request. The returned guard records a failure when dropped unless
+    /// [`RequestTimer::succeed`] was called first, so early returns are counted correctly.
+    #[must_use]
+    pub fn start_request(
+        &self,
+        model: &str,
+    ) -> RequestTimer<'_> {
+        RequestTimer {
+            stats: self,
+            model: model.to_string(),
+            started: Instant::now(),
+            outcome: RequestOutcome::Failure,
+        }
+    }
+
+    /// Returns a point-in-time copy of the collected statistics.
+    #[must_use]
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let requests = self.requests.load(Ordering::Relaxed);
+        let succeeded = self.succeeded.load(Ordering::Relaxed);
+        let failed = self.failed.load(Ordering::Relaxed);
+        #[allow(clippy::cast_precision_loss)]
+        let success_rate = if requests == 0 {
+            0.0
+        } else {
+            succeeded as f64 / requests as f64
+        };
+
+        let latency_buckets = self
+            .latency
+            .iter()
+            .enumerate()
+            .map(|(i, count)| LatencyBucket {
+                le_ms: LATENCY_BUCKETS_MS.get(i).copied(),
+                count: count.load(Ordering::Relaxed),
+            })
+            .collect();
+
+        let models = self.models.lock().unwrap_or_else(std::sync::PoisonError::into_inner).clone();
+
+        StatsSnapshot {
+            requests,
+            succeeded,
+            failed,
+            success_rate,
+            models,
+            latency_buckets,
+        }
+    }
+}
+
+/// Records a request into [`UsageStats`] when dropped.
+#[derive(Debug)]
+pub struct RequestTimer<'a> {
+    stats: &'a UsageStats,
+    model: String,
+    started: Instant,
+    outcome: RequestOutcome,
+}
+
+impl RequestTimer<'_> {
+    /// Marks the request as successful.
+    pub const fn succeed(&mut self) {
+        self.outcome = RequestOutcome::Success;
+    }
+}
+
+impl Drop for RequestTimer<'_> {
+    fn drop(&mut self) {
+        self.stats.record(&self.model, self.outcome, self.started.elapsed());
+    }
+}
+
+/// Serializable view of [`UsageStats`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct StatsSnapshot {
+    /// Requests recorded since the server started.
+    pub requests: u64,
+    /// Requests that produced a result.
+    pub succeeded: u64,
+    /// Requests that ended with an error.
+    pub failed: u64,
+    /// `succeeded / requests`, or 0 when no requests were recorded.
+    pub success_rate: f64,
+    /// Request count per model name.
+    pub models: BTreeMap<String, u64>,
+    /// Latency histogram, in ascending bucket order.
+    pub latency_buckets: Vec<LatencyBucket>,
+}
+
+/// A single latency histogram bucket.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct LatencyBucket {
+    /// Inclusive upper bound in milliseconds; `None` for the overflow bucket.
+    pub le_ms: Option<u64>,
+    /// Requests whose latency fell in this bucket.
+    pub count: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_collector_records_nothing() {
+        let stats = UsageStats::new(false);
+        stats.record("openai:gpt-4o", RequestOutcome::Success, Duration::from_millis(10));
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.requests, 0);
+        assert!(snapshot.models.is_empty());
+    }
+
+    #[test]
+    fn records_outcomes_models_and_latency() {
+        let stats = UsageStats::new(true);
+        stats.record("a", RequestOutcome::Success, Duration::from_millis(100));
+        stats.record("a", RequestOutcome::Failure, Duration::from_millis(700));
+        stats.record("b", RequestOutcome::Success, Duration::from_secs(60));
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.requests, 3);
+        assert_eq!(snapshot.succeeded, 2);
+        assert_eq!(snapshot.failed, 1);
+        assert!((snapshot.success_rate - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(snapshot.models.get("a"), Some(&2));
+        assert_eq!(snapshot.models.get("b"), Some(&1));
+        assert_eq!(snapshot.latency_buckets[0].count, 1);
+        assert_eq!(snapshot.latency_buckets[2].count, 1);
+        let overflow = snapshot.latency_buckets.last().unwrap();
+        assert_eq!(overflow.le_ms, None);
+        assert_eq!(overflow.count, 1);
+    }
+
+    #[test]
+    fn model_map_is_bounded() {
+        let stats = UsageStats::new(true);
+        for i in 0..(MAX_TRACKED_MODELS + 5) {
+            stats.record(&format!("model-{i}"), RequestOutcome::Success, Duration::ZERO);
+        }
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.models.len(), MAX_TRACKED_MODELS + 1);
+        assert_eq!(snapshot.models.get(OTHER_MODELS), Some(&5));
+    }
+
+    #[test]
+    fn timer_records_failure_unless_marked_successful() {
+        let stats = UsageStats::new(true);
+        drop(stats.start_request("a"));
+        let mut timer = stats.start_request("a");
+        timer.succeed();
+        drop(timer);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.failed, 1);
+        assert_eq!(snapshot.succeeded, 1);
+    }
+}
@@ -0,0 +1,363 @@
+//! At-rest encryption for any [`Storage`] backend.
+//!
+//! [`EncryptedStorage`] wraps another backend and seals every value with AES-256-GCM before it is
+//! written, so prompts, results and credentials kept by the server's subsystems never reach the
+//! backend in clear text. Each value is bound to its namespace and key, so a ciphertext copied under
+//! another key fails to decrypt instead of being served.
+//!
+//! Keys come from a [`Keyring`]: the first key encrypts, and every key decrypts. To rotate, put the
+//! new key first and keep the old ones until [`EncryptedStorage::reencrypt`] has rewritten the data
+//! they protect. Values written before encryption was enabled are still read as-is, unless the
+//! storage is [strict](EncryptedStorage::strict): once the data is rewritten, a strict storage
+//! refuses clear-text values, since they would bypass the binding to their namespace and key.
+
+use super::{Storage, StorageError};
+use async_trait::async_trait;
+use aws_lc_rs::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Prefix of encrypted values, followed by `<key id>:<base64 nonce and ciphertext>`.
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Length of an AES-256 key, in bytes.
+pub const KEY_LEN: usize = 32;
+
+/// Named AES-256-GCM keys.
+pub struct Keyring {
+    keys: Vec<(String, LessSafeKey)>,
+}
+
+impl std::fmt::Debug for Keyring {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        // Key material is never shown, only the key IDs.
+        let ids: Vec<&str> = self.keys.iter().map(|(id, _)| id.as_str()).collect();
+        f.debug_struct("Keyring").field("keys", &ids).finish()
+    }
+}
+
+impl Keyring {
+    /// Parses a comma-separated list of `id:key` entries, where `key` is 32 bytes encoded as
+    /// standard base64 (e.g. the output of `openssl rand -base64 32`). The first entry is the
+    /// current key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::Config`] if the list is empty, an entry is malformed, a key is not 32
+    /// bytes, or two entries share an ID.
+    pub fn parse(spec: &str) -> Result<Self, StorageError> {
+        let mut keys: Vec<(String, LessSafeKey)> = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (id, encoded) = entry
+                .split_once(':')
+                .map(|(id, key)| (id.trim(), key.trim()))
+                .filter(|(id, _)| !id.is_empty())
+                .ok_or_else(|| {
+                    StorageError::Config(format!("encryption key entries must be 'id:key', got '{entry}'"))
+                })?;
+            if keys.iter().any(|(existing, _)| existing == id) {
+                return Err(StorageError::Config(format!("duplicate encryption key ID '{id}'")));
+            }
+            let material = BASE64
+                .decode(encoded)
+                .ok()
+                .filter(|material| material.len() == KEY_LEN)
+                .ok_or_else(|| {
+                    StorageError::Config(format!("encryption key '{id}' must be {KEY_LEN} bytes of base64"))
+                })?;
+            let key = UnboundKey::new(&AES_256_GCM, &material)
+                .map_err(|_| StorageError::Config(format!("encryption key '{id}' is invalid")))?;
+            keys.push((id.to_string(), LessSafeKey::new(key)));
+        }
+        if keys.is_empty() {
+            return Err(StorageError::Config("no encryption keys configured".into()));
+        }
+        Ok(Self { keys })
+    }
+
+    /// ID of the key new values are encrypted with.
+    #[must_use]
+    pub fn current_key_id(&self) -> &str {
+        &self.keys[0].0
+    }
+
+    fn key(
+        &self,
+        id: &str,
+    ) -> Option<&LessSafeKey> {
+        self.keys.iter().find(|(key_id, _)| key_id == id).map(|(_, key)| key)
+    }
+}
+
+/// A [`Storage`] that encrypts values before handing them to `inner`. See the
+/// [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct EncryptedStorage {
+    inner: Arc<dyn Storage>,
+    keyring: Arc<Keyring>,
+    strict: bool,
+}
+
+impl EncryptedStorage {
+    /// Wraps `inner`, encrypting new values with the current key of `keyring`. Clear-text values
+    /// already in `inner` are still read until [`strict`](Self::strict) is set.
+    #[must_use]
+    pub fn new(
+        inner: Arc<dyn Storage>,
+        keyring: Keyring,
+    ) -> Self {
+        Self {
+            inner,
+            keyring: Arc::new(keyring),
+            strict: false,
+        }
+    }
+
+    /// Whether reads fail on values stored in clear text instead of returning them as-is. Turn
+    /// this on once [`reencrypt`](Self::reencrypt) has rewritten every namespace.
+    #[must_use]
+    pub const fn strict(
+        mut self,
+        strict: bool,
+    ) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    fn encrypt(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<String, StorageError> {
+        let (key_id, sealing_key) = &self.keyring.keys[0];
+        let mut nonce = [0u8; NONCE_LEN];
+        aws_lc_rs::rand::fill(&mut nonce)
+            .map_err(|_| StorageError::Encryption("no randomness for the nonce".into()))?;
+        let mut sealed = value.as_bytes().to_vec();
+        sealing_key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(associated_data(namespace, key)),
+                &mut sealed,
+            )
+            .map_err(|_| StorageError::Encryption("encryption failed".into()))?;
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&sealed);
+        Ok(format!("{ENCRYPTED_PREFIX}{key_id}:{}", BASE64.encode(payload)))
+    }
+
+    fn decrypt(
+        &self,
+        namespace: &str,
+        key: &str,
+        stored: String,
+    ) -> Result<String, StorageError> {
+        let undecryptable =
+            |reason: &str| StorageError::Encryption(format!("cannot decrypt {namespace}/{key}: {reason}"));
+        let Some(encrypted) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+            return if self.strict {
+                Err(undecryptable("stored in clear text"))
+            } else {
+                Ok(stored)
+            };
+        };
+        let (key_id, encoded) = encrypted.split_once(':').ok_or_else(|| undecryptable("malformed value"))?;
+        let opening_key = self
+            .keyring
+            .key(key_id)
+            .ok_or_else(|| undecryptable(&format!("unknown key '{key_id}'")))?;
+        let mut payload = BASE64.decode(encoded).map_err(|_| undecryptable("malformed value"))?;
+        if payload.len() < NONCE_LEN {
+            return Err(undecryptable("malformed value"));
+        }
+        let mut sealed = payload.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&payload).map_err(|_| undecryptable("malformed value"))?;
+        let plaintext = opening_key
+            .open_in_place(nonce, Aad::from(associated_data(namespace, key)), &mut sealed)
+            .map_err(|_| undecryptable("authentication failed"))?;
+        String::from_utf8(plaintext.to_vec()).map_err(|_| undecryptable("not UTF-8"))
+    }
+
+    /// Rewrites every value in `namespace` that is stored in clear text or under an older key with
+    /// the current key, and returns how many were rewritten. Rewritten entries keep the time they
+    /// had left to live. Once every namespace is rewritten, the old keys can be dropped. Clear-text
+    /// values are rewritten even when the storage is strict.
+    ///
+    /// # Errors
+    ///
+    /// Returns the backend error, or [`StorageError::Encryption`] if a value cannot be decrypted.
+    pub async fn reencrypt(
+        &self,
+        namespace: &str,
+    ) -> Result<usize, StorageError> {
+        let current = format!("{ENCRYPTED_PREFIX}{}:", self.keyring.current_key_id());
+        let mut rewritten = 0;
+        for key in self.inner.list_keys(namespace).await? {
+            let Some(stored) = self.inner.get(namespace, &key).await? else {
+                continue;
+            };
+            if stored.starts_with(&current) {
+                continue;
+            }
+            let ttl = self.inner.ttl(namespace, &key).await?;
+            let value = if stored.starts_with(ENCRYPTED_PREFIX) {
+                self.decrypt(namespace, &key, stored)?
+            } else {
+                stored
+            };
+            self.put(namespace, &key, &value, ttl).await?;
+            rewritten += 1;
+        }
+        Ok(rewritten)
+    }
+}
+
+/// Binds a ciphertext to where it is stored.
+fn associated_data(
+    namespace: &str,
+    key: &str,
+) -> Vec<u8> {
+    format!("{namespace}\0{key}").into_bytes()
+}
+
+#[async_trait]
+impl Storage for EncryptedStorage {
+    fn backend_name(&self) -> &'static str {
+        self.inner.backend_name()
+    }
+
+    async fn get(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<String>, StorageError> {
+        self.inner
+            .get(namespace, key)
+            .await?
+            .map(|stored| self.decrypt(namespace, key, stored))
+            .transpose()
+    }
+
+    async fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> Result<(), StorageError> {
+        let sealed = self.encrypt(namespace, key, value)?;
+        self.inner.put(namespace, key, &sealed, ttl).await
+    }
+
+    async fn delete(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<bool, StorageError> {
+        self.inner.delete(namespace, key).await
+    }
+
+    async fn list_keys(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<String>, StorageError> {
+        self.inner.list_keys(namespace).await
+    }
+
+    async fn ttl(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<Duration>, StorageError> {
+        self.inner.ttl(namespace, key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+
+    const OLD_KEY: &str = "old:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+    const NEW_KEY: &str = "new:AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=";
+
+    #[tokio::test]
+    async fn values_are_encrypted_at_rest() {
+        let inner = Arc::new(InMemoryStorage::new());
+        let storage = EncryptedStorage::new(inner.clone(), Keyring::parse(OLD_KEY).unwrap());
+        storage.put("audit", "r1", "MATCH (n) DELETE n", None).await.unwrap();
+
+        let stored = inner.get("audit", "r1").await.unwrap().unwrap();
+        assert!(stored.starts_with("enc:v1:old:") && !stored.contains("DELETE"));
+        assert_eq!(
+            storage.get("audit", "r1").await.unwrap().as_deref(),
+            Some("MATCH (n) DELETE n")
+        );
+
+        // A ciphertext moved under another key does not decrypt.
+        inner.put("audit", "r2", &stored, None).await.unwrap();
+        assert!(matches!(
+            storage.get("audit", "r2").await,
+            Err(StorageError::Encryption(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rotation_reads_old_keys_and_reencrypts() {
+        let inner = Arc::new(InMemoryStorage::new());
+        inner.put("sessions", "plain", "{}", None).await.unwrap();
+        EncryptedStorage::new(inner.clone(), Keyring::parse(OLD_KEY).unwrap())
+            .put("sessions", "s1", "hello", Some(Duration::from_secs(3600)))
+            .await
+            .unwrap();
+
+        let rotated = EncryptedStorage::new(inner.clone(), Keyring::parse(&format!("{NEW_KEY},{OLD_KEY}")).unwrap());
+        assert_eq!(rotated.get("sessions", "s1").await.unwrap().as_deref(), Some("hello"));
+        assert_eq!(rotated.reencrypt("sessions").await.unwrap(), 2);
+        assert_eq!(rotated.reencrypt("sessions").await.unwrap(), 0);
+        assert!(
+            inner
+                .ttl("sessions", "s1")
+                .await
+                .unwrap()
+                .is_some_and(|ttl| ttl > Duration::from_secs(3500))
+        );
+        assert_eq!(inner.ttl("sessions", "plain").await.unwrap(), None);
+
+        let new_only = EncryptedStorage::new(inner, Keyring::parse(NEW_KEY).unwrap());
+        assert_eq!(new_only.get("sessions", "s1").await.unwrap().as_deref(), Some("hello"));
+        assert_eq!(new_only.get("sessions", "plain").await.unwrap().as_deref(), Some("{}"));
+    }
+
+    #[tokio::test]
+    async fn strict_storage_rejects_clear_text() {
+        let inner = Arc::new(InMemoryStorage::new());
+        inner.put("sessions", "plain", "{}", None).await.unwrap();
+        let storage = EncryptedStorage::new(inner.clone(), Keyring::parse(NEW_KEY).unwrap()).strict(true);
+
+        assert!(matches!(
+            storage.get("sessions", "plain").await,
+            Err(StorageError::Encryption(_))
+        ));
+        assert_eq!(storage.reencrypt("sessions").await.unwrap(), 1);
+        assert_eq!(storage.get("sessions", "plain").await.unwrap().as_deref(), Some("{}"));
+    }
+
+    #[test]
+    fn parse_rejects_bad_keyrings() {
+        assert!(Keyring::parse("").is_err());
+        assert!(Keyring::parse("k1:c2hvcnQ=").is_err());
+        assert!(Keyring::parse(&format!("{OLD_KEY},{OLD_KEY}")).is_err());
+        assert!(Keyring::parse("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=").is_err());
+        assert_eq!(
+            Keyring::parse(&format!("{NEW_KEY}, {OLD_KEY}")).unwrap().current_key_id(),
+            "new"
+        );
+    }
+}
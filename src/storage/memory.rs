@@ -0,0 +1,145 @@
+//! Process-local [`Storage`] backend.
+
+use super::{Storage, StorageError};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+type Entries = HashMap<(String, String), (String, Option<Instant>)>;
+
+/// Keeps every entry in a process-local map. Expired entries are dropped lazily on access.
+#[derive(Debug, Default)]
+pub struct InMemoryStorage {
+    entries: Mutex<Entries>,
+}
+
+impl InMemoryStorage {
+    /// Creates an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+fn is_live(expires_at: Option<Instant>) -> bool {
+    expires_at.is_none_or(|deadline| Instant::now() < deadline)
+}
+
+#[async_trait]
+impl Storage for InMemoryStorage {
+    fn backend_name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn get(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<String>, StorageError> {
+        let id = (namespace.to_string(), key.to_string());
+        let mut entries = self.lock();
+        let value = match entries.get(&id) {
+            Some((value, expires_at)) if is_live(*expires_at) => Some(value.clone()),
+            Some(_) => {
+                entries.remove(&id);
+                None
+            }
+            None => None,
+        };
+        drop(entries);
+        Ok(value)
+    }
+
+    async fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> Result<(), StorageError> {
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        self.lock().insert(
+            (namespace.to_string(), key.to_string()),
+            (value.to_string(), expires_at),
+        );
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<bool, StorageError> {
+        Ok(self
+            .lock()
+            .remove(&(namespace.to_string(), key.to_string()))
+            .is_some_and(|(_, expires_at)| is_live(expires_at)))
+    }
+
+    async fn list_keys(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<String>, StorageError> {
+        let mut entries = self.lock();
+        entries.retain(|_, (_, expires_at)| is_live(*expires_at));
+        let mut keys: Vec<String> = entries
+            .keys()
+            .filter(|(ns, _)| ns == namespace)
+            .map(|(_, key)| key.clone())
+            .collect();
+        drop(entries);
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn ttl(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<Duration>, StorageError> {
+        Ok(self
+            .lock()
+            .get(&(namespace.to_string(), key.to_string()))
+            .and_then(|(_, expires_at)| *expires_at)
+            .map(|deadline| deadline.saturating_duration_since(Instant::now())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn put_get_delete_round_trip() {
+        let storage = InMemoryStorage::new();
+        storage.put("sessions", "a", "1", None).await.unwrap();
+        assert_eq!(storage.get("sessions", "a").await.unwrap().as_deref(), Some("1"));
+        assert_eq!(storage.get("other", "a").await.unwrap(), None);
+        assert!(storage.delete("sessions", "a").await.unwrap());
+        assert!(!storage.delete("sessions", "a").await.unwrap());
+        assert_eq!(storage.get("sessions", "a").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn list_keys_is_namespaced_and_sorted() {
+        let storage = InMemoryStorage::new();
+        storage.put("ns", "b", "", None).await.unwrap();
+        storage.put("ns", "a", "", None).await.unwrap();
+        storage.put("other", "c", "", None).await.unwrap();
+        assert_eq!(storage.list_keys("ns").await.unwrap(), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_invisible() {
+        let storage = InMemoryStorage::new();
+        storage.put("ns", "gone", "x", Some(Duration::ZERO)).await.unwrap();
+        storage.put("ns", "kept", "y", Some(Duration::from_secs(60))).await.unwrap();
+        assert_eq!(storage.get("ns", "gone").await.unwrap(), None);
+        assert_eq!(storage.list_keys("ns").await.unwrap(), vec!["kept"]);
+    }
+}
@@ -0,0 +1,284 @@
+//! Pluggable persistence for server-side state.
+//!
+//! Sessions, saved queries, examples, feedback, jobs and audit records all need somewhere to live.
+//! Rather than each subsystem inventing its own persistence, they share a single [`Storage`] handle:
+//! a namespaced key/value store whose values are opaque strings (typically JSON, see [`get_json`] and
+//! [`put_json`]).
+//!
+//! Three backends are provided:
+//!
+//! - [`InMemoryStorage`] — process-local, lost on restart (the default).
+//! - [`RedisStorage`] — any Redis-protocol server, including the `FalkorDB` instance itself.
+//! - [`SqliteStorage`] — a local `SQLite` file (requires the `sqlite` feature).
+//!
+//! The backend is selected centrally with a [`StorageConfig`] and opened with [`open`]. Any backend
+//! can be wrapped in an [`EncryptedStorage`] to encrypt values at rest.
+
+mod encrypted;
+mod memory;
+mod redis;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use encrypted::{EncryptedStorage, Keyring};
+pub use memory::InMemoryStorage;
+pub use redis::RedisStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Key prefix applied by [`RedisStorage`] when none is configured, so server state does not collide
+/// with graph keys on a shared instance.
+pub const DEFAULT_KEY_PREFIX: &str = "text-to-cypher:";
+
+/// Why a storage operation failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    /// The storage configuration is invalid (unknown backend, missing URL, disabled feature).
+    Config(String),
+    /// The backend could not be reached or rejected the operation. The message is preserved.
+    Backend(String),
+    /// A stored value could not be encoded or decoded.
+    Serialization(String),
+    /// A value could not be encrypted, or a stored value could not be decrypted.
+    Encryption(String),
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::Config(message) => write!(f, "invalid storage configuration: {message}"),
+            Self::Backend(message) => write!(f, "storage backend error: {message}"),
+            Self::Serialization(message) => write!(f, "storage serialization error: {message}"),
+            Self::Encryption(message) => write!(f, "storage encryption error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+/// A namespaced key/value store shared by the server's stateful subsystems.
+///
+/// Namespaces partition keys per subsystem (e.g. `"sessions"`, `"audit"`), so two subsystems can use
+/// the same key without clashing. Entries written with a TTL disappear once it elapses.
+#[async_trait]
+pub trait Storage: Send + Sync + std::fmt::Debug {
+    /// Short, stable backend name for logs (`"memory"`, `"redis"`, `"sqlite"`).
+    fn backend_name(&self) -> &'static str;
+
+    /// Returns the value stored under `key`, or `None` if it is absent or expired.
+    async fn get(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<String>, StorageError>;
+
+    /// Stores `value` under `key`, replacing any previous value. `ttl` of `None` never expires.
+    async fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> Result<(), StorageError>;
+
+    /// Removes `key`. Returns whether an entry was removed.
+    async fn delete(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<bool, StorageError>;
+
+    /// Lists the live keys in `namespace`, sorted.
+    async fn list_keys(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<String>, StorageError>;
+
+    /// Returns how long `key` has left to live, or `None` if it never expires or is absent.
+    async fn ttl(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<Duration>, StorageError>;
+}
+
+/// Which backend to open, and how to reach it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum StorageConfig {
+    /// Process-local storage (default).
+    #[default]
+    Memory,
+    /// A Redis-protocol server at `url`; every key is prefixed with `key_prefix`.
+    Redis { url: String, key_prefix: String },
+    /// A `SQLite` database file at `path` (`:memory:` for a private in-memory database).
+    Sqlite { path: String },
+}
+
+impl StorageConfig {
+    /// Builds a configuration from a backend name (`memory`, `redis`, `sqlite`) and an optional URL or
+    /// path. `falkordb_connection` is used as the Redis URL when none is given, so state can live on the
+    /// same instance as the graphs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::Config`] for an unknown backend name or a `sqlite` backend without a path.
+    pub fn parse(
+        backend: &str,
+        url: Option<&str>,
+        key_prefix: Option<&str>,
+        falkordb_connection: &str,
+    ) -> Result<Self, StorageError> {
+        match backend.trim().to_ascii_lowercase().as_str() {
+            "" | "memory" | "in-memory" | "inmemory" => Ok(Self::Memory),
+            "redis" => Ok(Self::Redis {
+                url: url.map_or_else(|| redis_url_from_falkordb(falkordb_connection), ToString::to_string),
+                key_prefix: key_prefix.unwrap_or(DEFAULT_KEY_PREFIX).to_string(),
+            }),
+            "sqlite" => url.map_or_else(
+                || {
+                    Err(StorageError::Config(
+                        "the sqlite backend requires STORAGE_URL (a file path)".into(),
+                    ))
+                },
+                |path| Ok(Self::Sqlite { path: path.to_string() }),
+            ),
+            other => Err(StorageError::Config(format!(
+                "unknown storage backend '{other}' (expected memory, redis or sqlite)"
+            ))),
+        }
+    }
+}
+
+/// Rewrites a `falkor://`/`falkors://` connection string to the equivalent `redis://`/`rediss://` URL.
+fn redis_url_from_falkordb(connection: &str) -> String {
+    [("falkors://", "rediss://"), ("falkor://", "redis://")]
+        .iter()
+        .find_map(|(from, to)| connection.strip_prefix(from).map(|rest| format!("{to}{rest}")))
+        .unwrap_or_else(|| connection.to_string())
+}
+
+/// Opens the configured backend.
+///
+/// Opening never performs I/O for Redis (the connection is established lazily on first use), so an
+/// unreachable server surfaces as [`StorageError::Backend`] on the first operation rather than here.
+///
+/// # Errors
+///
+/// Returns [`StorageError::Config`] if the URL is malformed or the backend was compiled out, and
+/// [`StorageError::Backend`] if a `SQLite` file cannot be opened.
+pub fn open(config: &StorageConfig) -> Result<Arc<dyn Storage>, StorageError> {
+    match config {
+        StorageConfig::Memory => Ok(Arc::new(InMemoryStorage::new())),
+        StorageConfig::Redis { url, key_prefix } => Ok(Arc::new(RedisStorage::open(url, key_prefix)?)),
+        #[cfg(feature = "sqlite")]
+        StorageConfig::Sqlite { path } => Ok(Arc::new(SqliteStorage::open(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        StorageConfig::Sqlite { .. } => Err(StorageError::Config(
+            "SQLite storage requires building with the `sqlite` feature".into(),
+        )),
+    }
+}
+
+/// Reads and deserializes a JSON value.
+///
+/// # Errors
+///
+/// Returns the backend error, or [`StorageError::Serialization`] if the stored value is not valid JSON
+/// for `T`.
+pub async fn get_json<T: DeserializeOwned>(
+    storage: &dyn Storage,
+    namespace: &str,
+    key: &str,
+) -> Result<Option<T>, StorageError> {
+    storage
+        .get(namespace, key)
+        .await?
+        .map(|raw| serde_json::from_str(&raw).map_err(|e| StorageError::Serialization(e.to_string())))
+        .transpose()
+}
+
+/// Serializes `value` as JSON and stores it.
+///
+/// # Errors
+///
+/// Returns [`StorageError::Serialization`] if `value` cannot be serialized, or the backend error.
+pub async fn put_json<T: Serialize + Sync>(
+    storage: &dyn Storage,
+    namespace: &str,
+    key: &str,
+    value: &T,
+    ttl: Option<Duration>,
+) -> Result<(), StorageError> {
+    let raw = serde_json::to_string(value).map_err(|e| StorageError::Serialization(e.to_string()))?;
+    storage.put(namespace, key, &raw, ttl).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_defaults_to_memory() {
+        assert_eq!(
+            StorageConfig::parse("", None, None, "falkor://localhost:6379").unwrap(),
+            StorageConfig::Memory
+        );
+        assert_eq!(
+            StorageConfig::parse("Memory", None, None, "falkor://localhost:6379").unwrap(),
+            StorageConfig::Memory
+        );
+    }
+
+    #[test]
+    fn parse_redis_falls_back_to_falkordb_connection() {
+        let config = StorageConfig::parse("redis", None, None, "falkor://db:6379").unwrap();
+        assert_eq!(
+            config,
+            StorageConfig::Redis {
+                url: "redis://db:6379".to_string(),
+                key_prefix: DEFAULT_KEY_PREFIX.to_string(),
+            }
+        );
+        let tls = StorageConfig::parse("redis", None, Some("app:"), "falkors://db:6380").unwrap();
+        assert_eq!(
+            tls,
+            StorageConfig::Redis {
+                url: "rediss://db:6380".to_string(),
+                key_prefix: "app:".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_rejects_unknown_backend_and_pathless_sqlite() {
+        assert!(matches!(
+            StorageConfig::parse("postgres", None, None, ""),
+            Err(StorageError::Config(_))
+        ));
+        assert!(matches!(
+            StorageConfig::parse("sqlite", None, None, ""),
+            Err(StorageError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn json_helpers_round_trip() {
+        let storage = InMemoryStorage::new();
+        put_json(&storage, "ns", "k", &vec![1, 2, 3], None).await.unwrap();
+        let value: Option<Vec<i32>> = get_json(&storage, "ns", "k").await.unwrap();
+        assert_eq!(value, Some(vec![1, 2, 3]));
+
+        storage.put("ns", "bad", "not json", None).await.unwrap();
+        let bad: Result<Option<Vec<i32>>, _> = get_json(&storage, "ns", "bad").await;
+        assert!(matches!(bad, Err(StorageError::Serialization(_))));
+    }
+}
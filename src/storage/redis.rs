@@ -0,0 +1,206 @@
+//! Redis-protocol [`Storage`] backend.
+//!
+//! Works against any Redis-compatible server, including the `FalkorDB` instance that hosts the graphs.
+//! Keys are laid out as `<prefix><namespace>:<key>`.
+
+use super::{Storage, StorageError};
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// Stores entries as plain string keys on a Redis-protocol server.
+pub struct RedisStorage {
+    client: redis::Client,
+    key_prefix: String,
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl std::fmt::Debug for RedisStorage {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        // The URL may carry credentials, so only the prefix is shown.
+        f.debug_struct("RedisStorage")
+            .field("key_prefix", &self.key_prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RedisStorage {
+    /// Creates a backend for `url`. No connection is made until the first operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::Config`] if `url` is not a valid Redis URL.
+    pub fn open(
+        url: &str,
+        key_prefix: &str,
+    ) -> Result<Self, StorageError> {
+        let client = redis::Client::open(url).map_err(|e| StorageError::Config(format!("invalid Redis URL: {e}")))?;
+        Ok(Self {
+            client,
+            key_prefix: key_prefix.to_string(),
+            connection: OnceCell::new(),
+        })
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, StorageError> {
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+            .map_err(|e| backend_error(&e))
+    }
+
+    fn full_key(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> String {
+        format!("{}{namespace}:{key}", self.key_prefix)
+    }
+}
+
+fn backend_error(e: &redis::RedisError) -> StorageError {
+    StorageError::Backend(e.to_string())
+}
+
+/// Escapes Redis glob metacharacters so a prefix is matched literally by `SCAN MATCH`.
+fn escape_glob(raw: &str) -> String {
+    let mut escaped = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[async_trait]
+impl Storage for RedisStorage {
+    fn backend_name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn get(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<String>, StorageError> {
+        let mut conn = self.connection().await?;
+        redis::cmd("GET")
+            .arg(self.full_key(namespace, key))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| backend_error(&e))
+    }
+
+    async fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> Result<(), StorageError> {
+        let mut conn = self.connection().await?;
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(self.full_key(namespace, key)).arg(value);
+        if let Some(ttl) = ttl {
+            // PX rejects 0, so clamp to the smallest expiry Redis accepts.
+            cmd.arg("PX").arg(u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1));
+        }
+        cmd.query_async::<()>(&mut conn).await.map_err(|e| backend_error(&e))
+    }
+
+    async fn delete(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<bool, StorageError> {
+        let mut conn = self.connection().await?;
+        let removed: u64 = redis::cmd("DEL")
+            .arg(self.full_key(namespace, key))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| backend_error(&e))?;
+        Ok(removed > 0)
+    }
+
+    async fn list_keys(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<String>, StorageError> {
+        let mut conn = self.connection().await?;
+        let prefix = self.full_key(namespace, "");
+        let pattern = format!("{}*", escape_glob(&prefix));
+
+        let mut keys = Vec::new();
+        let mut cursor = 0u64;
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(100)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| backend_error(&e))?;
+            keys.extend(
+                batch
+                    .into_iter()
+                    .filter_map(|k| k.strip_prefix(&prefix).map(ToString::to_string)),
+            );
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    async fn ttl(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<Duration>, StorageError> {
+        let mut conn = self.connection().await?;
+        // -1 for a key without expiry, -2 for a missing key
+        let millis: i64 = redis::cmd("PTTL")
+            .arg(self.full_key(namespace, key))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| backend_error(&e))?;
+        Ok(u64::try_from(millis).ok().map(Duration::from_millis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_rejects_invalid_url() {
+        assert!(matches!(
+            RedisStorage::open("not a url", "p:"),
+            Err(StorageError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn keys_are_prefixed_and_namespaced() {
+        let storage = RedisStorage::open("redis://127.0.0.1:6379", "t2c:").unwrap();
+        assert_eq!(storage.full_key("sessions", "abc"), "t2c:sessions:abc");
+    }
+
+    #[test]
+    fn escape_glob_escapes_metacharacters() {
+        assert_eq!(escape_glob("a*b?[c]\\"), "a\\*b\\?\\[c\\]\\\\");
+        assert_eq!(escape_glob("plain:"), "plain:");
+    }
+}
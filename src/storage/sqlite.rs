@@ -0,0 +1,206 @@
+//! `SQLite` [`Storage`] backend (behind the `sqlite` feature).
+
+use super::{Storage, StorageError};
+use async_trait::async_trait;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Stores entries in a single `kv` table of a local `SQLite` database.
+///
+/// `rusqlite` is synchronous, so every operation runs on the blocking thread pool.
+#[derive(Debug, Clone)]
+pub struct SqliteStorage {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    /// Opens (creating if needed) the database at `path`. `:memory:` opens a private in-memory database.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::Backend`] if the file cannot be opened or the schema cannot be created.
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        let connection = if path == ":memory:" {
+            Connection::open_in_memory()
+        } else {
+            Connection::open(path)
+        }
+        .map_err(|e| backend_error(&e))?;
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS kv (
+                    namespace TEXT NOT NULL,
+                    key TEXT NOT NULL,
+                    value TEXT NOT NULL,
+                    expires_at INTEGER,
+                    PRIMARY KEY (namespace, key)
+                );",
+            )
+            .map_err(|e| backend_error(&e))?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    async fn run<T, F>(
+        &self,
+        f: F,
+    ) -> Result<T, StorageError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || {
+            let guard = connection.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            f(&guard).map_err(|e| backend_error(&e))
+        })
+        .await
+        .map_err(|e| StorageError::Backend(format!("SQLite task failed: {e}")))?
+    }
+}
+
+fn backend_error(e: &rusqlite::Error) -> StorageError {
+    StorageError::Backend(e.to_string())
+}
+
+/// Milliseconds since the Unix epoch, used for TTL bookkeeping.
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    fn backend_name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn get(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<String>, StorageError> {
+        let (namespace, key) = (namespace.to_string(), key.to_string());
+        self.run(move |conn| {
+            conn.query_row(
+                "SELECT value FROM kv WHERE namespace = ?1 AND key = ?2 AND (expires_at IS NULL OR expires_at > ?3)",
+                params![namespace, key, now_millis()],
+                |row| row.get(0),
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> Result<(), StorageError> {
+        let (namespace, key, value) = (namespace.to_string(), key.to_string(), value.to_string());
+        let expires_at = ttl.map(|ttl| now_millis().saturating_add(i64::try_from(ttl.as_millis()).unwrap_or(i64::MAX)));
+        self.run(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO kv (namespace, key, value, expires_at) VALUES (?1, ?2, ?3, ?4)",
+                params![namespace, key, value, expires_at],
+            )
+            .map(|_| ())
+        })
+        .await
+    }
+
+    async fn delete(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<bool, StorageError> {
+        let (namespace, key) = (namespace.to_string(), key.to_string());
+        self.run(move |conn| {
+            conn.execute(
+                "DELETE FROM kv WHERE namespace = ?1 AND key = ?2 AND (expires_at IS NULL OR expires_at > ?3)",
+                params![namespace, key, now_millis()],
+            )
+            .map(|removed| removed > 0)
+        })
+        .await
+    }
+
+    async fn list_keys(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<String>, StorageError> {
+        let namespace = namespace.to_string();
+        self.run(move |conn| {
+            conn.execute(
+                "DELETE FROM kv WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+                params![now_millis()],
+            )?;
+            let mut stmt = conn.prepare("SELECT key FROM kv WHERE namespace = ?1 ORDER BY key")?;
+            let keys = stmt.query_map(params![namespace], |row| row.get(0))?;
+            keys.collect()
+        })
+        .await
+    }
+
+    async fn ttl(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<Duration>, StorageError> {
+        let (namespace, key) = (namespace.to_string(), key.to_string());
+        let now = now_millis();
+        let expires_at: Option<Option<i64>> = self
+            .run(move |conn| {
+                conn.query_row(
+                    "SELECT expires_at FROM kv WHERE namespace = ?1 AND key = ?2 AND (expires_at IS NULL OR expires_at > ?3)",
+                    params![namespace, key, now],
+                    |row| row.get(0),
+                )
+                .optional()
+            })
+            .await?;
+        Ok(expires_at
+            .flatten()
+            .map(|expires_at| Duration::from_millis(u64::try_from(expires_at - now).unwrap_or_default())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn put_get_delete_round_trip() {
+        let storage = SqliteStorage::open(":memory:").unwrap();
+        storage.put("ns", "a", "1", None).await.unwrap();
+        storage.put("ns", "a", "2", None).await.unwrap();
+        assert_eq!(storage.get("ns", "a").await.unwrap().as_deref(), Some("2"));
+        assert_eq!(storage.list_keys("ns").await.unwrap(), vec!["a"]);
+        assert!(storage.delete("ns", "a").await.unwrap());
+        assert_eq!(storage.get("ns", "a").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_invisible() {
+        let storage = SqliteStorage::open(":memory:").unwrap();
+        storage.put("ns", "gone", "x", Some(Duration::ZERO)).await.unwrap();
+        storage.put("ns", "kept", "y", None).await.unwrap();
+        assert_eq!(storage.get("ns", "gone").await.unwrap(), None);
+        assert_eq!(storage.list_keys("ns").await.unwrap(), vec!["kept"]);
+    }
+
+    #[tokio::test]
+    async fn persists_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let path = path.to_str().unwrap();
+        SqliteStorage::open(path).unwrap().put("ns", "k", "v", None).await.unwrap();
+        let reopened = SqliteStorage::open(path).unwrap();
+        assert_eq!(reopened.get("ns", "k").await.unwrap().as_deref(), Some("v"));
+    }
+}
@@ -0,0 +1,151 @@
+//! Progress events of a text-to-cypher request.
+//!
+//! The server sends these as the SSE events of `/text_to_cypher`; library consumers get the same
+//! events from [`TextToCypherClient::text_to_cypher_stream`](crate::TextToCypherClient::text_to_cypher_stream)
+//! and [`process_text_to_cypher_stream`](crate::processor::process_text_to_cypher_stream), so a UI
+//! can show statuses, the schema, the query and the answer as they arrive.
+
+#[cfg(feature = "server")]
+use crate::dry_run::DryRun;
+use crate::import::{ImportBatch, ImportSummary};
+use crate::usage::TokenUsage;
+use crate::validator::LintHint;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// One step of a request. A stream ends with exactly one `Result`, `NoAnswer` or `Error`
+/// (preceded by `Usage` once a model was called), followed by `Done`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub enum Progress {
+    /// Sent first when `DEBUG_BUNDLES` is enabled: the ID to fetch the request's debug bundle with.
+    RequestId(String),
+    Status(String),
+    /// The graph a request without a `graph_name` was routed to; sent before its `Schema`.
+    Graph(String),
+    /// A non-fatal issue, such as a rewritten query or a stale schema; the request goes on.
+    Warning(Warning),
+    Schema(String),
+    CypherQuery(String),
+    Lint(Vec<LintHint>),
+    /// The execution plan of a `dry_run` request's query from `GRAPH.EXPLAIN`, one operation per
+    /// line; the query is not executed.
+    QueryPlan(Vec<String>),
+    CypherResult(String),
+    ModelOutputChunk(String),
+    Result(String),
+    Confidence(u8),
+    Followups(Vec<String>),
+    #[cfg(feature = "server")]
+    DryRun(DryRun),
+    Usage(TokenUsage),
+    /// A batch of a streamed CSV import finished (`/graph_query_upload/{graph}/stream`).
+    ImportBatch(ImportBatch),
+    /// Sent before the `Result` of a streamed CSV import: the rows loaded and skipped, and the
+    /// first row errors.
+    ImportSummary(ImportSummary),
+    /// The model did not write a query, with its reason (e.g. the question is outside the schema).
+    /// Terminal, like `Result` and `Error`.
+    NoAnswer(String),
+    Error(String),
+    /// Always the last event of a stream: how the request ended, and its ID (the debug bundle ID
+    /// when `DEBUG_BUNDLES` is enabled).
+    Done {
+        status: StreamStatus,
+        request_id: String,
+    },
+}
+
+impl Progress {
+    /// How a stream ending with this event ended, for the terminal events `Result`, `NoAnswer`
+    /// and `Error`.
+    #[must_use]
+    pub const fn terminal_status(&self) -> Option<StreamStatus> {
+        match self {
+            Self::Result(_) => Some(StreamStatus::Success),
+            Self::NoAnswer(_) => Some(StreamStatus::NoAnswer),
+            Self::Error(_) => Some(StreamStatus::Error),
+            _ => None,
+        }
+    }
+}
+
+/// What kind of non-fatal issue a [`Warning`] reports, for clients to render or filter by.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum WarningCode {
+    /// A variable-length pattern was capped at the configured maximum depth.
+    VarLengthCapped,
+    /// Validation flagged the query, e.g. a hard-coded date or a missing `LIMIT`, without rejecting it.
+    ValidationHint,
+    /// The query failed validation and regeneration, and ran anyway.
+    ValidationFailed,
+    /// Schema discovery failed and the last discovered schema was used.
+    StaleSchema,
+    /// The answer may not be supported by the query result.
+    UnsupportedAnswer,
+    /// The query's estimated cost exceeds the complexity budget, and it ran anyway.
+    ExpensiveQuery,
+    /// A model call failed and the next model of the request's `model` list took over.
+    ModelFallback,
+}
+
+/// A non-fatal issue with a request: a machine-readable code and a message for people.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct Warning {
+    pub code: WarningCode,
+    pub message: String,
+}
+
+impl Warning {
+    #[must_use]
+    pub fn new(
+        code: WarningCode,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// How a stream ended, as reported by `Progress::Done`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum StreamStatus {
+    /// A `Result` was sent.
+    Success,
+    /// The model declined to write a query (`NoAnswer`).
+    NoAnswer,
+    /// An `Error` was sent, or the stream ended without a result.
+    Error,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warnings_carry_a_machine_readable_code() {
+        let event = Progress::Warning(Warning::new(WarningCode::StaleSchema, "Schema may be stale"));
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({"Warning": {"code": "stale_schema", "message": "Schema may be stale"}})
+        );
+        assert_eq!(event.terminal_status(), None);
+    }
+}
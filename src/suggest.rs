@@ -0,0 +1,105 @@
+//! "Did you mean" suggestions for misspelled names.
+//!
+//! Names are compared case-insensitively by Levenshtein distance. A candidate counts as a close
+//! match when it is at most a third of the name's length away (and at least one edit is always
+//! allowed), so `moveis` suggests `movies` but `m` does not suggest every one-letter graph.
+
+/// Returns the Levenshtein (insert / delete / substitute) distance between `a` and `b`, by
+/// character.
+#[must_use]
+pub fn edit_distance(
+    a: &str,
+    b: &str,
+) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Returns up to `limit` candidates close to `name`, closest first (ties keep candidate order).
+/// An exact case-insensitive match is the only suggestion when one exists.
+#[must_use]
+pub fn closest_matches<'a, I>(
+    name: &str,
+    candidates: I,
+    limit: usize,
+) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let name = name.to_lowercase();
+    let threshold = (name.chars().count() / 3).max(1);
+
+    let mut scored: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&name, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .collect();
+    scored.sort_by_key(|(distance, _)| *distance);
+    if scored.first().is_some_and(|(distance, _)| *distance == 0) {
+        scored.truncate(1);
+    }
+    scored.into_iter().take(limit).map(|(_, candidate)| candidate).collect()
+}
+
+/// Formats suggestions as ` Did you mean 'a' or 'b'?`, or an empty string when there are none,
+/// ready to append to an error message.
+#[must_use]
+pub fn did_you_mean(suggestions: &[&str]) -> String {
+    match suggestions {
+        [] => String::new(),
+        [only] => format!(" Did you mean '{only}'?"),
+        [rest @ .., last] => {
+            let rest = rest.iter().map(|s| format!("'{s}'")).collect::<Vec<_>>().join(", ");
+            format!(" Did you mean {rest} or '{last}'?")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        assert_eq!(edit_distance("movies", "movies"), 0);
+        assert_eq!(edit_distance("moveis", "movies"), 2);
+        assert_eq!(edit_distance("movie", "movies"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("grafé", "grafe"), 1);
+    }
+
+    #[test]
+    fn closest_matches_ranks_and_filters() {
+        let graphs = ["social", "movies", "movie_db", "Movies2", "finance"];
+        assert_eq!(closest_matches("movie", graphs, 3), vec!["movies"]);
+        assert_eq!(
+            closest_matches("movies_db", graphs, 3),
+            vec!["movie_db", "movies", "Movies2"]
+        );
+        assert_eq!(closest_matches("movies_db", graphs, 1), vec!["movie_db"]);
+        assert_eq!(closest_matches("MOVIES", graphs, 3), vec!["movies"]);
+        assert!(closest_matches("xyz", graphs, 3).is_empty());
+    }
+
+    #[test]
+    fn did_you_mean_formats_lists() {
+        assert_eq!(did_you_mean(&[]), "");
+        assert_eq!(did_you_mean(&["movies"]), " Did you mean 'movies'?");
+        assert_eq!(
+            did_you_mean(&["movies", "Movies2", "movie_db"]),
+            " Did you mean 'movies', 'Movies2' or 'movie_db'?"
+        );
+    }
+}
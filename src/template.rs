@@ -1,4 +1,24 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Who the final answer is written for. Each audience selects its own answer-template variant;
+/// when no audience is given the default answer prompt is used.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Audience {
+    /// Raw-leaning output: exact values and identifiers, minimal prose.
+    Technical,
+    /// The answer plus the methodology and the query that produced it.
+    Analyst,
+    /// A short summary that leads with the key numbers.
+    Executive,
+}
 
 pub struct TemplateEngine;
 
@@ -8,6 +28,19 @@ impl TemplateEngine {
     const USER_PROMPT: &'static str = include_str!("../templates/user_prompt.txt");
     const LAST_REQUEST_PROMPT: &'static str = include_str!("../templates/last_request_prompt.txt");
     const FALKORDB_REFERENCE: &'static str = include_str!("../templates/falkordb_reference.txt");
+    const LAST_REQUEST_PROMPT_TECHNICAL: &'static str = include_str!("../templates/last_request_prompt_technical.txt");
+    const LAST_REQUEST_PROMPT_ANALYST: &'static str = include_str!("../templates/last_request_prompt_analyst.txt");
+    const LAST_REQUEST_PROMPT_EXECUTIVE: &'static str = include_str!("../templates/last_request_prompt_executive.txt");
+    const DESTRUCTIVE_MODE_PROMPT: &'static str = include_str!("../templates/destructive_mode_prompt.txt");
+    const PARAMETERIZED_MODE_PROMPT: &'static str = include_str!("../templates/parameterized_mode_prompt.txt");
+    const AGGREGATE_MODE_PROMPT: &'static str = include_str!("../templates/aggregate_mode_prompt.txt");
+    const WRITE_MODE_PROMPT: &'static str = include_str!("../templates/write_mode_prompt.txt");
+    const SESSION_CONTEXT_PROMPT: &'static str = include_str!("../templates/session_context_prompt.txt");
+    const FEW_SHOT_PROMPT: &'static str = include_str!("../templates/few_shot_prompt.txt");
+    const FOLLOWUPS_PROMPT: &'static str = include_str!("../templates/followups_prompt.txt");
+    const FAITHFULNESS_PROMPT: &'static str = include_str!("../templates/faithfulness_prompt.txt");
+    const ENRICH_SCHEMA_PROMPT: &'static str = include_str!("../templates/enrich_schema_prompt.txt");
+    const ROUTE_GRAPH_PROMPT: &'static str = include_str!("../templates/route_graph_prompt.txt");
 
     #[must_use]
     pub fn render(
@@ -24,10 +57,53 @@ impl TemplateEngine {
         result
     }
 
+    /// Instructions that lift the read-only constraint for DELETE / DETACH DELETE, sent when a request
+    /// sets `allow_destructive`.
+    #[must_use]
+    pub const fn destructive_mode_prompt() -> &'static str {
+        Self::DESTRUCTIVE_MODE_PROMPT
+    }
+
+    /// Instructions to write question values as `$name` parameters and list them on a trailing
+    /// `PARAMETERS: {...}` line, sent when a request sets `parameterized`.
+    #[must_use]
+    pub const fn parameterized_mode_prompt() -> &'static str {
+        Self::PARAMETERIZED_MODE_PROMPT
+    }
+
+    /// Instructions to answer with aggregates only, sent for graphs in aggregate-only mode.
+    #[must_use]
+    pub const fn aggregate_mode_prompt() -> &'static str {
+        Self::AGGREGATE_MODE_PROMPT
+    }
+
+    /// Render the instructions that lift the read-only constraint for the write clauses a
+    /// validation policy allows, e.g. `CREATE, MERGE, SET and REMOVE`.
+    #[must_use]
+    pub fn render_write_mode_prompt(allowed_clauses: &str) -> String {
+        let mut variables = HashMap::new();
+        variables.insert("ALLOWED_CLAUSES", allowed_clauses);
+        Self::render(Self::WRITE_MODE_PROMPT, &variables)
+    }
+
+    /// Render the context sent with a session's follow-up question: the query that answered the
+    /// previous one.
+    #[must_use]
+    pub fn render_session_context_prompt(cypher_query: &str) -> String {
+        let mut variables = HashMap::new();
+        variables.insert("CYPHER_QUERY", cypher_query);
+        Self::render(Self::SESSION_CONTEXT_PROMPT, &variables)
+    }
+
+    /// Render the curated examples sent before a question, each a `Question:` and `Cypher:` pair.
+    #[must_use]
+    pub fn render_few_shot_prompt(examples: &str) -> String {
+        let mut variables = HashMap::new();
+        variables.insert("EXAMPLES", examples);
+        Self::render(Self::FEW_SHOT_PROMPT, &variables)
+    }
+
     /// Render the system prompt template with ontology.
-    // Retained as public API and used by the library/tests; the binary recompiles this module but
-    // only calls `render_system_prompt_with_context`, so allow dead_code for the bin build.
-    #[allow(dead_code)]
     #[must_use]
     pub fn render_system_prompt(ontology: &str) -> String {
         Self::render_system_prompt_with_skills(ontology, "")
@@ -35,7 +111,6 @@ impl TemplateEngine {
 
     /// Render the system prompt template with ontology and optional skills catalog.
     /// When `skills_catalog` is empty, renders the prompt without any skills section.
-    #[allow(dead_code)]
     #[must_use]
     pub fn render_system_prompt_with_skills(
         ontology: &str,
@@ -55,13 +130,27 @@ impl TemplateEngine {
         ontology: &str,
         skills_catalog: &str,
         udfs: &str,
+    ) -> String {
+        Self::render_system_prompt_template(Self::SYSTEM_PROMPT, ontology, skills_catalog, udfs)
+    }
+
+    /// Like [`Self::render_system_prompt_with_context`], but renders `template` (an alternate
+    /// system prompt using the same placeholders) instead of the built-in one.
+    #[must_use]
+    pub fn render_system_prompt_template(
+        template: &str,
+        ontology: &str,
+        skills_catalog: &str,
+        udfs: &str,
     ) -> String {
         let mut variables = HashMap::new();
         variables.insert("ONTOLOGY", ontology);
         variables.insert("SKILLS_CATALOG", skills_catalog);
         variables.insert("UDFS", udfs);
         variables.insert("FALKORDB_REFERENCE", Self::FALKORDB_REFERENCE);
-        let rendered = Self::render(Self::SYSTEM_PROMPT, &variables);
+        let today = current_date();
+        variables.insert("CURRENT_DATE", today.as_str());
+        let rendered = Self::render(template, &variables);
 
         if !skills_catalog.trim().is_empty() && !udfs.trim().is_empty() {
             return rendered;
@@ -93,6 +182,50 @@ impl TemplateEngine {
         result
     }
 
+    /// The `FalkorDB` reference of the system prompt without the parts about procedures `allows`
+    /// rejects, so the model is never pointed at a procedure it may not call. A section built around
+    /// such a procedure is dropped whole; in a list, only the items naming it are, and a list left
+    /// without items is dropped too.
+    #[must_use]
+    pub fn falkordb_reference(allows: impl Fn(&str) -> bool) -> String {
+        let procedure = procedure_regex();
+        let banned = |text: &str| procedure.captures_iter(text).any(|captures| !allows(&captures[1]));
+
+        let mut sections = Vec::new();
+        for section in Self::FALKORDB_REFERENCE.trim_end().split("\n\n") {
+            let mut items: Vec<String> = Vec::new();
+            for line in section.lines() {
+                // Indented lines continue the list item above them.
+                match items.last_mut() {
+                    Some(item) if line.starts_with(' ') => {
+                        item.push('\n');
+                        item.push_str(line);
+                    }
+                    _ => items.push(line.to_string()),
+                }
+            }
+            if items.iter().any(|item| item.starts_with("- ")) {
+                items.retain(|item| !(item.starts_with("- ") && banned(item)));
+                if items.iter().any(|item| item.starts_with("- ")) {
+                    sections.push(items.join("\n"));
+                }
+            } else if !banned(section) {
+                sections.push(section.to_string());
+            }
+        }
+        sections.join("\n\n") + "\n"
+    }
+
+    /// Replaces the `FalkorDB` reference in a rendered system prompt with
+    /// [`Self::falkordb_reference`] for `allows`.
+    #[must_use]
+    pub fn restrict_procedures(
+        system_prompt: &str,
+        allows: impl Fn(&str) -> bool,
+    ) -> String {
+        system_prompt.replacen(Self::FALKORDB_REFERENCE, &Self::falkordb_reference(allows), 1)
+    }
+
     /// Render the user prompt template with the given question.
     #[must_use]
     pub fn render_user_prompt(question: &str) -> String {
@@ -107,13 +240,213 @@ impl TemplateEngine {
         question: &str,
         cypher_query: &str,
         cypher_result: &str,
+    ) -> String {
+        Self::render_last_request_prompt_for_audience(question, cypher_query, cypher_result, None)
+    }
+
+    /// Render the last request prompt using the answer-template variant for `audience`.
+    /// `None` selects the default answer prompt.
+    #[must_use]
+    pub fn render_last_request_prompt_for_audience(
+        question: &str,
+        cypher_query: &str,
+        cypher_result: &str,
+        audience: Option<Audience>,
+    ) -> String {
+        Self::render_answer_prompt(question, cypher_query, cypher_result, audience, "")
+    }
+
+    /// Like [`Self::render_last_request_prompt_for_audience`], telling the model the `units` of the
+    /// values the query read (`Label.property is in unit` lines, see
+    /// [`crate::schema::units::units_for_query`]) so it states them with the numbers.
+    #[must_use]
+    pub fn render_answer_prompt(
+        question: &str,
+        cypher_query: &str,
+        cypher_result: &str,
+        audience: Option<Audience>,
+        units: &str,
+    ) -> String {
+        let template = match audience {
+            None => Self::LAST_REQUEST_PROMPT,
+            Some(Audience::Technical) => Self::LAST_REQUEST_PROMPT_TECHNICAL,
+            Some(Audience::Analyst) => Self::LAST_REQUEST_PROMPT_ANALYST,
+            Some(Audience::Executive) => Self::LAST_REQUEST_PROMPT_EXECUTIVE,
+        };
+        let mut variables = HashMap::new();
+        variables.insert("CYPHER_QUERY", cypher_query);
+        variables.insert("CYPHER_RESULT", cypher_result);
+        variables.insert("USER_QUESTION", question);
+        let units = if units.trim().is_empty() {
+            String::new()
+        } else {
+            format!(
+                "Units of the values in the result:\n{}\nState each value with its unit, e.g. \"$42M\" for 42 in USD millions.\n\n",
+                units.trim()
+            )
+        };
+        variables.insert("UNITS", units.as_str());
+        Self::render(template, &variables)
+    }
+
+    /// Render the prompt asking for follow-up question suggestions grounded in the ontology and the
+    /// current question, query, result, and answer.
+    #[must_use]
+    pub fn render_followups_prompt(
+        ontology: &str,
+        question: &str,
+        cypher_query: &str,
+        cypher_result: &str,
+        answer: &str,
     ) -> String {
         let mut variables = HashMap::new();
+        variables.insert("ONTOLOGY", ontology);
+        variables.insert("USER_QUESTION", question);
         variables.insert("CYPHER_QUERY", cypher_query);
         variables.insert("CYPHER_RESULT", cypher_result);
+        variables.insert("ANSWER", answer);
+        Self::render(Self::FOLLOWUPS_PROMPT, &variables)
+    }
+
+    /// Render the prompt asking whether an answer is supported by the query result it was derived
+    /// from.
+    #[must_use]
+    pub fn render_faithfulness_prompt(
+        question: &str,
+        cypher_query: &str,
+        cypher_result: &str,
+        answer: &str,
+    ) -> String {
+        let mut variables = HashMap::new();
         variables.insert("USER_QUESTION", question);
-        Self::render(Self::LAST_REQUEST_PROMPT, &variables)
+        variables.insert("CYPHER_QUERY", cypher_query);
+        variables.insert("CYPHER_RESULT", cypher_result);
+        variables.insert("ANSWER", answer);
+        Self::render(Self::FAITHFULNESS_PROMPT, &variables)
     }
+
+    /// Render the prompt asking for descriptions of the ontology's labels, relationships and
+    /// properties.
+    #[must_use]
+    pub fn render_enrich_schema_prompt(ontology: &str) -> String {
+        let mut variables = HashMap::new();
+        variables.insert("ONTOLOGY", ontology);
+        Self::render(Self::ENRICH_SCHEMA_PROMPT, &variables)
+    }
+
+    /// Render the prompt asking which of the listed graphs holds the data a question is about.
+    #[must_use]
+    pub fn render_route_graph_prompt(
+        graphs: &str,
+        question: &str,
+    ) -> String {
+        let mut variables = HashMap::new();
+        variables.insert("GRAPHS", graphs);
+        variables.insert("QUESTION", question);
+        Self::render(Self::ROUTE_GRAPH_PROMPT, &variables)
+    }
+
+    /// Renders every embedded template with sample values and reports each `{{PLACEHOLDER}}` left
+    /// unresolved, as `"<template>: {{NAME}}"`. Empty when every template renders cleanly.
+    #[must_use]
+    pub fn unresolved_placeholders() -> Vec<String> {
+        let (question, query, result) = ("question", "MATCH (n) RETURN n", "[]");
+        let mut rendered = vec![
+            (
+                "system_prompt",
+                Self::render_system_prompt_with_context("{}", "skills", "udfs"),
+            ),
+            ("user_prompt", Self::render_user_prompt(question)),
+            (
+                "last_request_prompt",
+                Self::render_last_request_prompt_for_audience(question, query, result, None),
+            ),
+            ("destructive_mode_prompt", Self::destructive_mode_prompt().to_string()),
+            (
+                "parameterized_mode_prompt",
+                Self::parameterized_mode_prompt().to_string(),
+            ),
+            ("write_mode_prompt", Self::render_write_mode_prompt("CREATE")),
+            ("session_context_prompt", Self::render_session_context_prompt(query)),
+            (
+                "few_shot_prompt",
+                Self::render_few_shot_prompt(&format!("Question: {question}\nCypher: {query}")),
+            ),
+            (
+                "followups_prompt",
+                Self::render_followups_prompt("{}", question, query, result, "answer"),
+            ),
+            (
+                "faithfulness_prompt",
+                Self::render_faithfulness_prompt(question, query, result, "answer"),
+            ),
+            ("enrich_schema_prompt", Self::render_enrich_schema_prompt("{}")),
+            (
+                "route_graph_prompt",
+                Self::render_route_graph_prompt("- movies\n", question),
+            ),
+        ];
+        for (name, audience) in [
+            ("last_request_prompt_technical", Audience::Technical),
+            ("last_request_prompt_analyst", Audience::Analyst),
+            ("last_request_prompt_executive", Audience::Executive),
+        ] {
+            rendered.push((
+                name,
+                Self::render_last_request_prompt_for_audience(question, query, result, Some(audience)),
+            ));
+        }
+
+        let mut unresolved = Vec::new();
+        for (name, text) in rendered {
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("{{") {
+                let Some(len) = rest[start..].find("}}") else { break };
+                let placeholder = &rest[start..start + len + 2];
+                let variable = &placeholder[2..placeholder.len() - 2];
+                if !variable.is_empty() && variable.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+                    unresolved.push(format!("{name}: {placeholder}"));
+                }
+                rest = &rest[start + len + 2..];
+            }
+        }
+        unresolved
+    }
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`, the `{{CURRENT_DATE}}` of the system prompt, so relative
+/// periods such as "last quarter" resolve against the real calendar.
+#[must_use]
+pub fn current_date() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Converts days since 1970-01-01 into a Gregorian `(year, month, day)`.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // Counts from 0000-03-01 in 400-year eras, so leap days fall at the end of each year
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    (era * 400 + year_of_era + u64::from(month <= 2), month, day)
+}
+
+/// Matches a dotted procedure name followed by its argument list, e.g. `db.idx.fulltext.queryNodes(`.
+fn procedure_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\b([A-Za-z_]\w*(?:\.[A-Za-z_]\w*)+)\s*\(").expect("valid procedure regex"))
 }
 
 #[cfg(test)]
@@ -124,14 +457,38 @@ mod tests {
     fn system_prompt_includes_falkordb_reference() {
         let prompt = TemplateEngine::render_system_prompt("{}");
         assert!(prompt.contains("db.idx.fulltext.queryNodes"));
-        assert!(prompt.contains("db.idx.vector.queryNodes"));
+        assert!(prompt.contains("db.idx.vector.queryNodes") && prompt.contains("$query_vector"));
         assert!(prompt.contains("algo.SPpaths"));
+        assert!(prompt.contains("algo.pageRank") && prompt.contains("algo.labelPropagation"));
         assert!(!prompt.contains("{{FALKORDB_REFERENCE}}"));
         assert!(!prompt.contains("{{ONTOLOGY}}"));
         assert!(!prompt.contains("{{SKILLS_CATALOG}}"));
         assert!(!prompt.contains("{{UDFS}}"));
     }
 
+    #[test]
+    fn reference_drops_procedures_that_are_not_allowed() {
+        assert_eq!(
+            TemplateEngine::falkordb_reference(|_| true),
+            TemplateEngine::FALKORDB_REFERENCE
+        );
+
+        let prompt = TemplateEngine::restrict_procedures(&TemplateEngine::render_system_prompt("{}"), |name| {
+            name == "db.idx.fulltext.queryNodes"
+        });
+        assert!(prompt.contains("db.idx.fulltext.queryNodes"));
+        assert!(!prompt.contains("db.idx.vector") && !prompt.contains("vec.cosineDistance"));
+        assert!(!prompt.contains("algo.SPpaths") && !prompt.contains("weightProp") && !prompt.contains("algo.SSpaths"));
+        assert!(prompt.contains("Paths and traversal:") && prompt.contains("shortestPath(...)"));
+        assert!(!prompt.contains("Graph algorithms") && !prompt.contains("gds.*"));
+
+        let prompt = TemplateEngine::restrict_procedures(&TemplateEngine::render_system_prompt("{}"), |name| {
+            name == "algo.pageRank"
+        });
+        assert!(prompt.contains("algo.pageRank") && prompt.contains("gds.*"));
+        assert!(!prompt.contains("algo.WCC") && !prompt.contains("algo.BFS"));
+    }
+
     #[test]
     fn system_prompt_with_skills_includes_catalog_and_reference() {
         let prompt = TemplateEngine::render_system_prompt_with_skills("{}", "Available skills:\n- foo: bar");
@@ -170,4 +527,106 @@ mod tests {
         assert!(prompt.contains("Available skills:"));
         assert!(!prompt.contains("\n\n\n"));
     }
+
+    #[test]
+    fn answer_prompt_lists_units_only_when_given() {
+        let plain = TemplateEngine::render_last_request_prompt("How much?", "MATCH (o) RETURN o.total", "[[42]]");
+        assert!(plain.contains("returned [[42]].\n\nUsing that data"));
+
+        let prompt = TemplateEngine::render_answer_prompt(
+            "How much?",
+            "MATCH (o) RETURN o.total",
+            "[[42]]",
+            Some(Audience::Executive),
+            "Order.total is in USD millions",
+        );
+        assert!(prompt.contains(
+            "Units of the values in the result:\nOrder.total is in USD millions\nState each value with its unit"
+        ));
+        assert!(!prompt.contains("{{UNITS}}"));
+    }
+
+    #[test]
+    fn system_prompt_states_the_current_date() {
+        let prompt = TemplateEngine::render_system_prompt("{}");
+        assert!(prompt.contains(&format!("Today is {}", current_date())));
+        assert!(!prompt.contains("{{CURRENT_DATE}}"));
+    }
+
+    #[test]
+    fn civil_from_days_handles_leap_years() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(19_783), (2024, 3, 1));
+        assert_eq!(civil_from_days(20_741), (2026, 10, 15));
+    }
+
+    #[test]
+    fn all_templates_render_without_unresolved_placeholders() {
+        assert_eq!(TemplateEngine::unresolved_placeholders(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn last_request_prompt_audience_variants() {
+        let default = TemplateEngine::render_last_request_prompt("q?", "MATCH (n) RETURN n", "[]");
+        assert_eq!(
+            default,
+            TemplateEngine::render_last_request_prompt_for_audience("q?", "MATCH (n) RETURN n", "[]", None)
+        );
+
+        for audience in [Audience::Technical, Audience::Analyst, Audience::Executive] {
+            let prompt = TemplateEngine::render_last_request_prompt_for_audience(
+                "q?",
+                "MATCH (n) RETURN n",
+                "[]",
+                Some(audience),
+            );
+            assert_ne!(prompt, default, "{audience:?} must use its own template");
+            assert!(prompt.contains("q?"));
+            assert!(prompt.contains("MATCH (n) RETURN n"));
+            assert!(
+                prompt.contains("CONFIDENCE: <0-100>"),
+                "{audience:?} must keep the confidence marker"
+            );
+            assert!(!prompt.contains("{{"), "{audience:?} left a placeholder unrendered");
+        }
+
+        let analyst = TemplateEngine::render_last_request_prompt_for_audience(
+            "q?",
+            "MATCH (n) RETURN n",
+            "[]",
+            Some(Audience::Analyst),
+        );
+        assert!(analyst.contains("Methodology"));
+        let executive = TemplateEngine::render_last_request_prompt_for_audience(
+            "q?",
+            "MATCH (n) RETURN n",
+            "[]",
+            Some(Audience::Executive),
+        );
+        assert!(executive.contains("at most three sentences"));
+    }
+
+    #[test]
+    fn followups_prompt_renders_all_placeholders() {
+        let prompt = TemplateEngine::render_followups_prompt(
+            "{\"entities\":[]}",
+            "Who acted in Heat?",
+            "MATCH (a)-[:ACTED_IN]->(m) RETURN a.name",
+            "[Al Pacino]",
+            "Al Pacino acted in Heat.",
+        );
+        assert!(prompt.contains("Who acted in Heat?"));
+        assert!(prompt.contains("Al Pacino acted in Heat."));
+        assert!(prompt.contains("{\"entities\":[]}"));
+        assert!(!prompt.contains("{{"));
+    }
+
+    #[test]
+    fn audience_deserializes_lowercase() {
+        let audience: Audience = serde_json::from_str("\"executive\"").unwrap();
+        assert_eq!(audience, Audience::Executive);
+        assert!(serde_json::from_str::<Audience>("\"ceo\"").is_err());
+    }
 }
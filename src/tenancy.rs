@@ -0,0 +1,462 @@
+//! Tenant isolation for graphs shared between tenants.
+//!
+//! A shared graph keeps each tenant's nodes apart by a property such as `tenant_id`. A
+//! [`TenantFilter`] binds a request to one tenant: [`TenantFilter::apply`] adds the tenant's
+//! predicate to every node pattern of a generated query, so the model cannot read another tenant's
+//! data even when asked to, and [`TenantFilter::check`] reports what the predicate cannot cover,
+//! such as procedure calls, writes to the tenant property, and the intermediate nodes of
+//! variable-length relationships.
+//!
+//! The server binds API keys to tenants with `TENANT_POLICY`, parsed by [`TenantPolicy::parse`];
+//! library users set a client's tenant with `TextToCypherClient::builder().tenant_filter(...)`.
+
+use crate::cypher_syntax::{self, NodePattern, NodeProperties, SyntaxError};
+use crate::validator::CypherValidator;
+use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+static PATTERNS: OnceLock<TenancyPatterns> = OnceLock::new();
+
+struct TenancyPatterns {
+    /// Pattern to capture the key of a property access `n.key`
+    property_access: Regex,
+    /// Pattern to detect a SET item replacing or merging a whole property map (`n = {...}`, `n += $map`)
+    map_assignment: Regex,
+    /// Pattern to detect a variable-length relationship, e.g. `-[:KNOWS*1..3]->`
+    variable_length: Regex,
+    /// Pattern to capture a path variable bound in a pattern, e.g. `p = (a)-->(b)`
+    path_variable: Regex,
+    /// Pattern to check a tenant property is a plain identifier
+    property: Regex,
+    /// Pattern to check a tenant ID can be written as a string literal without escaping
+    tenant: Regex,
+}
+
+impl TenancyPatterns {
+    fn get() -> &'static Self {
+        PATTERNS.get_or_init(|| Self {
+            property_access: Regex::new(r"\.\s*`?([A-Za-z_]\w*)").unwrap(),
+            map_assignment: Regex::new(r"(?:^|[\s,])[A-Za-z_]\w*\s*\+?=").unwrap(),
+            variable_length: Regex::new(r"-\s*\[[^\[\]]*\*[^\[\]]*\]\s*-").unwrap(),
+            path_variable: Regex::new(r"(?:^|[\s,])([A-Za-z_]\w*)\s*=").unwrap(),
+            property: Regex::new(r"^[A-Za-z_]\w*$").unwrap(),
+            tenant: Regex::new(r"^[A-Za-z0-9_.:@-]+$").unwrap(),
+        })
+    }
+}
+
+/// Restricts queries to the nodes of one tenant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantFilter {
+    property: String,
+    tenant: String,
+}
+
+impl TenantFilter {
+    /// A filter matching only nodes whose `property` is `tenant`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `property` is not a plain identifier, or `tenant` has characters other
+    /// than letters, digits, and `_ . : @ -`.
+    pub fn new(
+        property: impl Into<String>,
+        tenant: impl Into<String>,
+    ) -> Result<Self, String> {
+        let (property, tenant) = (property.into(), tenant.into());
+        let patterns = TenancyPatterns::get();
+        if !patterns.property.is_match(&property) {
+            return Err(format!("tenant property '{property}' must be a plain identifier"));
+        }
+        if !patterns.tenant.is_match(&tenant) {
+            return Err(format!("tenant '{tenant}' may only use letters, digits, and _ . : @ -"));
+        }
+        Ok(Self { property, tenant })
+    }
+
+    /// The property holding each node's tenant.
+    #[must_use]
+    pub fn property(&self) -> &str {
+        &self.property
+    }
+
+    /// The tenant queries are restricted to.
+    #[must_use]
+    pub fn tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    /// Adds the tenant predicate to every node pattern of `query` that lacks one: all nodes of
+    /// MATCH, OPTIONAL MATCH, MERGE and CREATE clauses (except nodes CREATE and MERGE reuse), and
+    /// nodes of relationship patterns elsewhere, such as pattern predicates in WHERE. Patterns
+    /// naming the tenant property or taking their properties from a parameter are left as written
+    /// for [`Self::check`] to judge, as is a query that does not parse.
+    #[must_use]
+    pub fn apply(
+        &self,
+        query: &str,
+    ) -> String {
+        let Ok((nodes, _)) = Self::parse(query) else {
+            return query.to_string();
+        };
+        let predicate = format!("{}: '{}'", self.property, self.tenant);
+        let mut insertions = Vec::new();
+        for node in nodes {
+            match node.properties {
+                Some(NodeProperties::Parameter) => {}
+                Some(NodeProperties::Map { entries, .. }) if entries.iter().any(|(key, _)| *key == self.property) => {}
+                Some(NodeProperties::Map { contents, entries }) => insertions.push(match entries.last() {
+                    Some((_, value)) => (value.end, format!(", {predicate}")),
+                    None => (contents.start, predicate.clone()),
+                }),
+                None => {
+                    let space = if node.variable.is_some() || node.labeled {
+                        " "
+                    } else {
+                        ""
+                    };
+                    insertions.push((node.head_end, format!("{space}{{{predicate}}}")));
+                }
+            }
+        }
+        // Insert back to front so earlier offsets stay valid
+        insertions.sort_by_key(|(at, _)| Reverse(*at));
+        let mut filtered = query.to_string();
+        for (at, text) in insertions {
+            filtered.insert_str(at, &text);
+        }
+        filtered
+    }
+
+    /// Errors for what in `query` could reach other tenants' data: node patterns [`Self::apply`]
+    /// would filter that lack the predicate, match another tenant or take their properties from a
+    /// parameter, procedure calls, SET or REMOVE clauses changing the tenant property, and
+    /// variable-length relationships and path variables, whose intermediate nodes no node pattern
+    /// filters. A query that does not parse cannot be checked, and is reported.
+    #[must_use]
+    pub fn check(
+        &self,
+        query: &str,
+    ) -> Vec<String> {
+        let (nodes, stripped) = match Self::parse(query) {
+            Ok(parsed) => parsed,
+            Err(error) => return vec![format!("Query cannot be checked for tenant isolation: {error}")],
+        };
+        let masked = CypherValidator::mask_string_literals(&stripped);
+        let property = &self.property;
+        let mut errors = Vec::new();
+        for node in nodes {
+            let pattern = &query[node.range];
+            let values: Vec<_> = match &node.properties {
+                Some(NodeProperties::Parameter) => {
+                    errors.push(format!(
+                        "Node pattern {pattern} takes its properties from a parameter, which the tenant filter \
+                         cannot check"
+                    ));
+                    continue;
+                }
+                Some(NodeProperties::Map { entries, .. }) => entries
+                    .iter()
+                    .filter(|(key, _)| key == property)
+                    .map(|(_, value)| query[value.clone()].trim())
+                    .collect(),
+                None => Vec::new(),
+            };
+            let own = |value: &&str| *value == format!("'{}'", self.tenant) || *value == format!("\"{}\"", self.tenant);
+            if values.is_empty() {
+                errors.push(format!(
+                    "Node pattern {pattern} is missing the tenant filter on {property}"
+                ));
+            } else if !values.iter().all(own) {
+                errors.push(format!("Node pattern {pattern} matches another tenant's {property}"));
+            }
+        }
+        for (procedure, _) in CypherValidator::procedure_calls(&stripped) {
+            errors.push(format!(
+                "Procedure {procedure} may return other tenants' nodes and is not allowed on a shared graph"
+            ));
+        }
+        let patterns = TenancyPatterns::get();
+        if patterns.variable_length.is_match(&masked) {
+            errors.push(
+                "Variable-length relationships pass through nodes the tenant filter cannot cover and are not \
+                 allowed on a shared graph"
+                    .to_string(),
+            );
+        }
+        for (clause, body) in CypherValidator::clauses(&masked) {
+            let body = &masked[body];
+            if matches!(clause.as_str(), "MATCH" | "OPTIONAL MATCH" | "MERGE" | "CREATE") {
+                for path in patterns.path_variable.captures_iter(body) {
+                    errors.push(format!(
+                        "Path variable {} may expose nodes the tenant filter cannot cover and is not allowed on a \
+                         shared graph",
+                        &path[1]
+                    ));
+                }
+            }
+            let changes_property = patterns
+                .property_access
+                .captures_iter(body)
+                .any(|access| &access[1] == property);
+            if (clause == "SET" || clause == "REMOVE") && changes_property {
+                errors.push(format!("{clause} clauses may not change {property}"));
+            } else if clause == "SET" && patterns.map_assignment.is_match(body) {
+                errors.push(format!(
+                    "SET clauses may not replace a node's property map, which holds its {property}"
+                ));
+            }
+        }
+        errors
+    }
+
+    /// The node patterns of `query` the predicate belongs in (see [`Self::apply`]), and `query`
+    /// with its comments blanked out.
+    fn parse(query: &str) -> Result<(Vec<NodePattern>, String), SyntaxError> {
+        let mut nodes = cypher_syntax::node_patterns(query)?;
+        // CREATE and MERGE cannot add properties to a node bound earlier
+        nodes.retain(|node| {
+            (node.clause.is_some() || node.related) && !(matches!(node.clause, Some("CREATE" | "MERGE")) && node.bound)
+        });
+        Ok((nodes, cypher_syntax::without_comments(query)?))
+    }
+}
+
+/// Which graphs are shared between tenants, the property their nodes hold the tenant in, and the
+/// tenant of each API key.
+///
+/// Requests on a shared graph get the [`TenantFilter`] of their key's tenant, and are refused
+/// without one.
+#[derive(Debug, Clone, Default)]
+pub struct TenantPolicy {
+    /// The property holding each node's tenant; `None` when no graph is shared
+    property: Option<String>,
+    /// The shared graphs; empty shares every graph
+    graphs: Vec<String>,
+    /// The tenant of each API key
+    tenants: HashMap<String, String>,
+}
+
+impl TenantPolicy {
+    /// Parses a `;`-separated policy such as
+    /// `property=tenant_id;graphs=shared|crm;key:<api key>=acme;key:<api key>=globex`. Without
+    /// `graphs`, every graph is shared. Blank entries are ignored, so an empty policy shares none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending entry if it is unknown or malformed, a key is bound
+    /// twice, a tenant or the property is invalid, or entries are given without `property`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut policy = Self::default();
+        for entry in spec.split(';').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (name, value) = entry
+                .split_once('=')
+                .map(|(name, value)| (name.trim(), value.trim()))
+                .ok_or_else(|| format!("tenant policy entries must be 'name=value', got '{entry}'"))?;
+            if name == "property" {
+                policy.property = Some(value.to_string());
+            } else if name == "graphs" {
+                policy.graphs = value
+                    .split('|')
+                    .map(str::trim)
+                    .filter(|graph| !graph.is_empty())
+                    .map(ToString::to_string)
+                    .collect();
+            } else if let Some(key) = name.strip_prefix("key:") {
+                if policy.tenants.insert(key.to_string(), value.to_string()).is_some() {
+                    return Err(format!("API key in '{entry}' is bound to a tenant twice"));
+                }
+            } else {
+                return Err(format!(
+                    "unknown tenant policy entry '{entry}'; expected property=, graphs= or key:<api key>="
+                ));
+            }
+        }
+        let Some(property) = &policy.property else {
+            if policy.graphs.is_empty() && policy.tenants.is_empty() {
+                return Ok(policy);
+            }
+            return Err("the tenant policy needs a property=<name> entry".to_string());
+        };
+        for tenant in policy.tenants.values() {
+            TenantFilter::new(property.as_str(), tenant.as_str())?;
+        }
+        if policy.tenants.is_empty() {
+            TenantFilter::new(property.as_str(), "-")?;
+        }
+        Ok(policy)
+    }
+
+    /// A policy sharing every graph and binding no key to a tenant, which refuses every request.
+    #[must_use]
+    pub fn refuse_all() -> Self {
+        Self {
+            property: Some("tenant".to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// Returns true when no graph is shared.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.property.is_none()
+    }
+
+    /// Whether `graph` is shared between tenants.
+    #[must_use]
+    pub fn covers(
+        &self,
+        graph: &str,
+    ) -> bool {
+        self.property.is_some() && (self.graphs.is_empty() || self.graphs.iter().any(|shared| shared == graph))
+    }
+
+    /// The filter for a request with `api_key` on `graph`, or `None` when the graph is not shared.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph is shared and the key is missing or bound to no tenant.
+    pub fn filter_for(
+        &self,
+        api_key: Option<&str>,
+        graph: &str,
+    ) -> Result<Option<TenantFilter>, String> {
+        let Some(property) = self.property.as_deref().filter(|_| self.covers(graph)) else {
+            return Ok(None);
+        };
+        let tenant = api_key
+            .and_then(|key| self.tenants.get(key))
+            .ok_or_else(|| format!("Graph '{graph}' is shared between tenants, and the API key is bound to none"))?;
+        TenantFilter::new(property, tenant.as_str()).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_every_node_pattern_by_tenant() {
+        let filter = TenantFilter::new("tenant_id", "acme").unwrap();
+        let query = "MATCH (p:Person {name: 'A, (x)'})-[:KNOWS]->(f), () WHERE (f)-->(:City) AND size(labels(f)) > 0 RETURN count(f) LIMIT 5";
+        let filtered = filter.apply(query);
+        assert_eq!(
+            filtered,
+            "MATCH (p:Person {name: 'A, (x)', tenant_id: 'acme'})-[:KNOWS]->(f {tenant_id: 'acme'}), ({tenant_id: 'acme'}) WHERE (f {tenant_id: 'acme'})-->(:City {tenant_id: 'acme'}) AND size(labels(f)) > 0 RETURN count(f) LIMIT 5"
+        );
+        assert_eq!(filter.apply(&filtered), filtered);
+        assert!(filter.check(&filtered).is_empty());
+        assert_eq!(
+            filter.check(query)[0],
+            "Node pattern (p:Person {name: 'A, (x)'}) is missing the tenant filter on tenant_id"
+        );
+
+        // CREATE reuses bound nodes as they are; new nodes belong to the tenant
+        let create = filter.apply("MATCH (a:Person {name: 'A'}) CREATE (a)-[:OWNS]->(:Car)");
+        assert_eq!(
+            create,
+            "MATCH (a:Person {name: 'A', tenant_id: 'acme'}) CREATE (a)-[:OWNS]->(:Car {tenant_id: 'acme'})"
+        );
+        assert!(filter.check(&create).is_empty());
+
+        // What the predicate cannot cover is reported
+        let leaks = "MATCH (n {tenant_id: 'globex'}) SET n.tenant_id = 'acme', n = {} WITH n CALL db.idx.fulltext.queryNodes('Person', 'x') YIELD node RETURN node";
+        assert_eq!(
+            filter.check(&filter.apply(leaks)),
+            vec![
+                "Node pattern (n {tenant_id: 'globex'}) matches another tenant's tenant_id",
+                "Procedure db.idx.fulltext.queryNodes may return other tenants' nodes and is not allowed on a shared graph",
+                "SET clauses may not change tenant_id",
+            ]
+        );
+
+        // The nodes between the ends of a variable-length path are not filtered
+        let path = "MATCH p=(a {tenant_id:'acme'})-[*1..3]-(b {tenant_id:'acme'}) RETURN nodes(p)";
+        assert_eq!(filter.apply(path), path);
+        assert_eq!(
+            filter.check(path),
+            vec![
+                "Variable-length relationships pass through nodes the tenant filter cannot cover and are not allowed on a shared graph",
+                "Path variable p may expose nodes the tenant filter cannot cover and is not allowed on a shared graph",
+            ]
+        );
+        assert_eq!(
+            filter
+                .check(&filter.apply("MATCH (a)<-[:KNOWS *]-(b) WHERE a.tenant_id = 'acme' RETURN b LIMIT 5"))
+                .len(),
+            1
+        );
+
+        let policy = TenantPolicy::parse("property=tenant_id; graphs=shared; key:k1=acme").unwrap();
+        assert_eq!(policy.filter_for(Some("k1"), "shared"), Ok(Some(filter)));
+        assert_eq!(policy.filter_for(None, "private"), Ok(None));
+        assert!(policy.filter_for(Some("k2"), "shared").is_err());
+        assert!(TenantPolicy::parse("").unwrap().is_empty());
+        assert!(TenantPolicy::parse("key:k1=acme").is_err());
+        assert!(TenantPolicy::parse("property=tenant_id;key:k1=a'b").is_err());
+        assert!(TenantPolicy::parse("property=tenant id").is_err());
+    }
+
+    #[test]
+    fn filters_node_patterns_however_they_are_written() {
+        let filter = TenantFilter::new("tenant_id", "acme").unwrap();
+        for (query, expected) in [
+            // Comments are not node pattern contents, and cannot hide one
+            (
+                "MATCH (n /* x */) RETURN n LIMIT 5",
+                "MATCH (n {tenant_id: 'acme'} /* x */) RETURN n LIMIT 5",
+            ),
+            (
+                "MATCH (n {/* tenant_id: 'acme', */ name: 'x'}) RETURN n",
+                "MATCH (n {/* tenant_id: 'acme', */ name: 'x', tenant_id: 'acme'}) RETURN n",
+            ),
+            (
+                "MATCH (n:Person|Secret) RETURN n",
+                "MATCH (n:Person|Secret {tenant_id: 'acme'}) RETURN n",
+            ),
+            (
+                "MATCH (n:Person WHERE n.age > 30) RETURN n",
+                "MATCH (n:Person {tenant_id: 'acme'} WHERE n.age > 30) RETURN n",
+            ),
+            // Only variables bound earlier are reused, not property keys or labels of the same name
+            (
+                "MATCH (p:Person) WHERE p.name IS NOT NULL MERGE (name:Person {email:'bob'}) RETURN name.ssn",
+                "MATCH (p:Person {tenant_id: 'acme'}) WHERE p.name IS NOT NULL MERGE (name:Person {email:'bob', tenant_id: 'acme'}) RETURN name.ssn",
+            ),
+            // WITH drops what it does not project
+            (
+                "MATCH (a:Person) WITH 1 AS k MERGE (a:Person) RETURN a.ssn",
+                "MATCH (a:Person {tenant_id: 'acme'}) WITH 1 AS k MERGE (a:Person {tenant_id: 'acme'}) RETURN a.ssn",
+            ),
+            (
+                "MATCH (a:Person) WITH a AS b CREATE (b)-[:OWNS]->(c:Car) RETURN c",
+                "MATCH (a:Person {tenant_id: 'acme'}) WITH a AS b CREATE (b)-[:OWNS]->(c:Car {tenant_id: 'acme'}) RETURN c",
+            ),
+        ] {
+            let filtered = filter.apply(query);
+            assert_eq!(filtered, expected);
+            assert!(filter.check(&filtered).is_empty(), "{filtered}");
+        }
+
+        // The predicate cannot be added to a parameter's properties
+        let parameter = "MATCH (n $p) RETURN n";
+        assert_eq!(filter.apply(parameter), parameter);
+        assert_eq!(
+            filter.check(parameter),
+            vec!["Node pattern (n $p) takes its properties from a parameter, which the tenant filter cannot check"]
+        );
+
+        // A repeated key cannot override the tenant
+        assert_eq!(
+            filter.check("MATCH (n {tenant_id: 'acme', tenant_id: 'globex'}) RETURN n"),
+            vec!["Node pattern (n {tenant_id: 'acme', tenant_id: 'globex'}) matches another tenant's tenant_id"]
+        );
+
+        // What does not parse cannot be filtered
+        let unreadable = "MATCH (n RETURN n";
+        assert_eq!(filter.apply(unreadable), unreadable);
+        assert!(filter.check(unreadable)[0].starts_with("Query cannot be checked for tenant isolation: Syntax error"));
+    }
+}
@@ -3,17 +3,19 @@
 //! A single request may issue several LLM calls (cypher generation, final answer
 //! generation, self-healing retries, and tool-call rounds for skills). [`TokenUsage`]
 //! aggregates the prompt, completion, and total token counts across all of those calls
-//! so the consumed tokens can be surfaced in the request result.
+//! so the consumed tokens can be surfaced in the request result, along with each call's own
+//! counts and, from a [`PriceTable`], an estimate of what the request cost.
 
 use genai::chat::Usage;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 #[cfg(feature = "server")]
 use utoipa::ToSchema;
 
 /// Aggregated token usage for a text-to-cypher request.
 ///
 /// Counts are summed across every LLM call made while serving a single request.
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 #[cfg_attr(feature = "server", derive(ToSchema))]
 #[allow(clippy::struct_field_names)]
 pub struct TokenUsage {
@@ -23,6 +25,24 @@ pub struct TokenUsage {
     pub completion_tokens: u64,
     /// Total tokens consumed across all calls.
     pub total_tokens: u64,
+    /// Each call's model and counts, in the order the calls were made.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallUsage>,
+    /// Estimated cost of all calls in US dollars, from the configured [`PriceTable`]. `None` when
+    /// a call's model has no price.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
+}
+
+/// The tokens of one LLM call.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[allow(clippy::struct_field_names)]
+pub struct CallUsage {
+    /// The model called.
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
 }
 
 impl TokenUsage {
@@ -33,25 +53,125 @@ impl TokenUsage {
             prompt_tokens: 0,
             completion_tokens: 0,
             total_tokens: 0,
+            calls: Vec::new(),
+            estimated_cost_usd: None,
         }
     }
 
-    /// Adds the counts from another [`TokenUsage`] into this one, saturating on overflow.
-    pub const fn accumulate(
+    /// Adds the counts and calls from another [`TokenUsage`] into this one, saturating on overflow.
+    pub fn accumulate(
         &mut self,
         other: &Self,
     ) {
         self.prompt_tokens = self.prompt_tokens.saturating_add(other.prompt_tokens);
         self.completion_tokens = self.completion_tokens.saturating_add(other.completion_tokens);
         self.total_tokens = self.total_tokens.saturating_add(other.total_tokens);
+        self.calls.extend(other.calls.iter().cloned());
     }
 
-    /// Adds the counts from a genai [`Usage`] into this one, saturating on overflow.
+    /// Adds the counts from a genai [`Usage`] of a call to `model` into this one, saturating on
+    /// overflow, and records the call.
     pub fn add_genai_usage(
         &mut self,
+        model: &str,
         usage: &Usage,
     ) {
-        self.accumulate(&Self::from(usage));
+        let call = Self::from(usage);
+        self.calls.push(CallUsage {
+            model: model.to_string(),
+            prompt_tokens: call.prompt_tokens,
+            completion_tokens: call.completion_tokens,
+        });
+        self.accumulate(&call);
+    }
+
+    /// Sets [`Self::estimated_cost_usd`] from the prices of the calls' models.
+    pub fn estimate_cost(
+        &mut self,
+        prices: &PriceTable,
+    ) {
+        self.estimated_cost_usd = prices.estimate(&self.calls);
+    }
+}
+
+/// What a model charges, in US dollars per million tokens.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    /// Per million input (prompt) tokens.
+    pub prompt: f64,
+    /// Per million output (completion) tokens.
+    pub completion: f64,
+}
+
+/// Model prices for estimating what requests cost, keyed by model name.
+///
+/// Written as a JSON object, e.g. `{"gpt-4o-mini": {"prompt": 0.15, "completion": 0.6}}`. A model
+/// named with its provider (`openai:gpt-4o-mini`) is also found under its bare name.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct PriceTable(HashMap<String, ModelPrice>);
+
+impl PriceTable {
+    /// Parses a price table from its JSON form.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document is not a JSON object of model prices, or a price is
+    /// negative.
+    pub fn parse(document: &str) -> Result<Self, String> {
+        let table: Self = serde_json::from_str(document).map_err(|e| format!("invalid price table: {e}"))?;
+        if let Some((model, _)) = table.0.iter().find(|(_, price)| price.prompt < 0.0 || price.completion < 0.0) {
+            return Err(format!("invalid price table: the price of '{model}' is negative"));
+        }
+        Ok(table)
+    }
+
+    /// Sets the price of `model`.
+    pub fn insert(
+        &mut self,
+        model: impl Into<String>,
+        price: ModelPrice,
+    ) {
+        self.0.insert(model.into(), price);
+    }
+
+    /// Returns true when no model has a price.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The price of `model`, or of its bare name when it is named with its provider.
+    #[must_use]
+    pub fn price(
+        &self,
+        model: &str,
+    ) -> Option<ModelPrice> {
+        self.0
+            .get(model)
+            .or_else(|| model.rsplit(':').next().and_then(|name| self.0.get(name)))
+            .copied()
+    }
+
+    /// The cost of `calls` in US dollars, or `None` when there are none or a call's model has no
+    /// price.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn estimate(
+        &self,
+        calls: &[CallUsage],
+    ) -> Option<f64> {
+        if calls.is_empty() {
+            return None;
+        }
+        calls.iter().try_fold(0.0, |cost, call| {
+            let price = self.price(&call.model)?;
+            Some(
+                (call.completion_tokens as f64)
+                    .mul_add(price.completion, call.prompt_tokens as f64 * price.prompt)
+                    .mul_add(1e-6, cost),
+            )
+        })
     }
 }
 
@@ -74,6 +194,7 @@ impl From<&Usage> for TokenUsage {
             prompt_tokens,
             completion_tokens,
             total_tokens,
+            ..Self::new()
         }
     }
 }
@@ -121,24 +242,45 @@ mod tests {
     #[test]
     fn accumulate_sums_all_fields() {
         let mut acc = TokenUsage::new();
-        acc.add_genai_usage(&usage(Some(10), Some(5), Some(15)));
-        acc.add_genai_usage(&usage(Some(3), Some(2), Some(5)));
+        acc.add_genai_usage("gpt-4o-mini", &usage(Some(10), Some(5), Some(15)));
+        acc.add_genai_usage("gpt-4o-mini", &usage(Some(3), Some(2), Some(5)));
         assert_eq!(acc.prompt_tokens, 13);
         assert_eq!(acc.completion_tokens, 7);
         assert_eq!(acc.total_tokens, 20);
+        assert_eq!(acc.calls.len(), 2);
+        assert_eq!(acc.calls[1].prompt_tokens, 3);
+    }
+
+    #[test]
+    fn cost_is_estimated_from_each_calls_model_price() {
+        let prices = PriceTable::parse(
+            r#"{"gpt-4o-mini": {"prompt": 0.15, "completion": 0.6}, "gpt-4o": {"prompt": 2.5, "completion": 10}}"#,
+        )
+        .unwrap();
+        let mut acc = TokenUsage::new();
+        acc.add_genai_usage("gpt-4o-mini", &usage(Some(1_000_000), Some(100_000), None));
+        acc.add_genai_usage("openai:gpt-4o", &usage(Some(2000), Some(500), None));
+        acc.estimate_cost(&prices);
+        let cost = acc.estimated_cost_usd.unwrap();
+        assert!((cost - (0.15 + 0.06 + 0.005 + 0.005)).abs() < 1e-9, "{cost}");
+
+        acc.add_genai_usage("claude-3-haiku", &usage(Some(10), Some(5), None));
+        acc.estimate_cost(&prices);
+        assert_eq!(acc.estimated_cost_usd, None);
+
+        assert!(PriceTable::parse(r#"{"gpt-4o": {"prompt": -1, "completion": 1}}"#).is_err());
+        assert!(PriceTable::parse(r#"{"gpt-4o": 1}"#).is_err());
     }
 
     #[test]
     fn accumulate_saturates_on_overflow() {
         let mut acc = TokenUsage {
             prompt_tokens: u64::MAX,
-            completion_tokens: 0,
-            total_tokens: 0,
+            ..TokenUsage::new()
         };
         acc.accumulate(&TokenUsage {
             prompt_tokens: 1,
-            completion_tokens: 0,
-            total_tokens: 0,
+            ..TokenUsage::new()
         });
         assert_eq!(acc.prompt_tokens, u64::MAX);
     }
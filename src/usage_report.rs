@@ -0,0 +1,206 @@
+//! Usage reports computed from the audit trail, served by `GET /admin/usage`.
+//!
+//! Every `/text_to_cypher` request leaves an [`AuditRecord`] with its graph, model, API key ID,
+//! outcome and [`RequestUsage`]. A report groups the records of a time range by graph, model or key
+//! and gives each group its request count, error rate, token spend, estimated cost, p95 latency and
+//! self-healing rate, so operators get basic reporting without an external analytics stack.
+
+use crate::audit::{AuditOutcome, AuditRecord, TEXT_TO_CYPHER_ACTION};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// What a usage report groups requests by.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGroupBy {
+    #[default]
+    Graph,
+    Model,
+    /// The API key ID (see [`crate::auth::ApiKeys::key_id`]); requests without a key are grouped
+    /// as `anonymous`.
+    Key,
+}
+
+impl FromStr for UsageGroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "graph" => Ok(Self::Graph),
+            "model" => Ok(Self::Model),
+            "key" => Ok(Self::Key),
+            other => Err(format!(
+                "Unknown group_by '{other}': expected 'graph', 'model' or 'key'"
+            )),
+        }
+    }
+}
+
+/// The usage of one group of requests.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default, PartialEq)]
+pub struct UsageGroup {
+    /// The graph, model or key ID the requests share.
+    pub group: String,
+    pub requests: u64,
+    /// Requests that ended with an error.
+    pub errors: u64,
+    /// `errors / requests`.
+    pub error_rate: f64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Estimated cost in US dollars of the requests whose models all have a price.
+    pub estimated_cost_usd: f64,
+    /// 95th percentile of the request latencies, in milliseconds.
+    pub p95_latency_ms: u64,
+    /// Requests whose query was regenerated after it failed.
+    pub healed_requests: u64,
+    /// `healed_requests / requests`.
+    pub self_healing_rate: f64,
+}
+
+/// The usage of the requests served from `from_ms` up to `to_ms`, grouped by `group_by`.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct UsageReport {
+    /// Unix timestamp (milliseconds) the report starts at.
+    pub from_ms: u64,
+    /// Unix timestamp (milliseconds) the report ends before.
+    pub to_ms: u64,
+    pub group_by: UsageGroupBy,
+    /// One entry per group, the most requested first.
+    pub groups: Vec<UsageGroup>,
+}
+
+impl UsageReport {
+    /// Aggregates the `/text_to_cypher` requests among `records`; other audited actions are
+    /// skipped.
+    #[must_use]
+    pub fn new(
+        records: &[AuditRecord],
+        from_ms: u64,
+        to_ms: u64,
+        group_by: UsageGroupBy,
+    ) -> Self {
+        let mut groups: BTreeMap<String, (UsageGroup, Vec<u64>)> = BTreeMap::new();
+        let requests = records
+            .iter()
+            .filter(|record| record.action == TEXT_TO_CYPHER_ACTION)
+            .filter_map(|record| record.usage.as_ref().map(|usage| (record, usage)));
+        for (record, usage) in requests {
+            let key = match group_by {
+                UsageGroupBy::Graph => record.graph_name.clone(),
+                UsageGroupBy::Model => record.model.clone().unwrap_or_else(|| "unknown".to_string()),
+                UsageGroupBy::Key => record.actor.clone().unwrap_or_else(|| "anonymous".to_string()),
+            };
+            let (group, latencies) = groups.entry(key).or_default();
+            group.requests += 1;
+            group.errors += u64::from(record.outcome == AuditOutcome::Failure);
+            group.prompt_tokens += usage.prompt_tokens;
+            group.completion_tokens += usage.completion_tokens;
+            group.estimated_cost_usd += usage.estimated_cost_usd.unwrap_or_default();
+            group.healed_requests += u64::from(usage.heal_attempts > 0);
+            latencies.push(usage.latency_ms);
+        }
+
+        let mut groups: Vec<UsageGroup> = groups
+            .into_iter()
+            .map(|(key, (mut group, mut latencies))| {
+                latencies.sort_unstable();
+                // Nearest rank: the smallest latency at least 95% of the requests are within
+                let rank = (latencies.len() * 95).div_ceil(100).max(1);
+                #[allow(clippy::cast_precision_loss)]
+                let requests = group.requests as f64;
+                #[allow(clippy::cast_precision_loss)]
+                {
+                    group.error_rate = group.errors as f64 / requests;
+                    group.self_healing_rate = group.healed_requests as f64 / requests;
+                }
+                group.p95_latency_ms = latencies[rank - 1];
+                group.group = key;
+                group
+            })
+            .collect();
+        groups.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.group.cmp(&b.group)));
+        Self {
+            from_ms,
+            to_ms,
+            group_by,
+            groups,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::RequestUsage;
+
+    fn request(
+        graph_name: &str,
+        model: &str,
+        outcome: AuditOutcome,
+        latency_ms: u64,
+        heal_attempts: usize,
+    ) -> AuditRecord {
+        AuditRecord::new(
+            Some("…abcd".to_string()),
+            TEXT_TO_CYPHER_ACTION,
+            graph_name,
+            "MATCH (n) RETURN count(n)",
+            outcome,
+            None,
+        )
+        .with_usage(
+            Some(model.to_string()),
+            RequestUsage {
+                latency_ms,
+                prompt_tokens: 1000,
+                completion_tokens: 50,
+                estimated_cost_usd: Some(0.001),
+                heal_attempts,
+            },
+        )
+    }
+
+    #[test]
+    fn requests_are_aggregated_per_group() {
+        let mut records: Vec<AuditRecord> = (1..=19)
+            .map(|latency| request("sales", "gpt-4o-mini", AuditOutcome::Success, latency * 100, 0))
+            .collect();
+        records.push(request("sales", "gpt-4o-mini", AuditOutcome::Failure, 9_000, 2));
+        records.push(request("hr", "anthropic:claude-3-haiku", AuditOutcome::Success, 700, 1));
+        records.push(AuditRecord::new(
+            None,
+            "graph_delete",
+            "sales",
+            "",
+            AuditOutcome::Success,
+            None,
+        ));
+
+        let report = UsageReport::new(&records, 0, u64::MAX, UsageGroupBy::Graph);
+        assert_eq!(report.groups.len(), 2);
+        let sales = &report.groups[0];
+        assert_eq!(sales.group, "sales");
+        assert_eq!(sales.requests, 20);
+        assert_eq!(sales.errors, 1);
+        assert!((sales.error_rate - 0.05).abs() < f64::EPSILON);
+        assert_eq!(sales.prompt_tokens, 20_000);
+        assert_eq!(sales.completion_tokens, 1_000);
+        assert!((sales.estimated_cost_usd - 0.02).abs() < 1e-9);
+        assert_eq!(sales.p95_latency_ms, 1_900);
+        assert_eq!(sales.healed_requests, 1);
+        assert_eq!(report.groups[1].group, "hr");
+        assert!((report.groups[1].self_healing_rate - 1.0).abs() < f64::EPSILON);
+
+        let by_model = UsageReport::new(&records, 0, u64::MAX, UsageGroupBy::Model);
+        assert_eq!(by_model.groups[0].group, "gpt-4o-mini");
+        let by_key = UsageReport::new(&records, 0, u64::MAX, UsageGroupBy::Key);
+        assert_eq!(by_key.groups[0].group, "…abcd");
+        assert_eq!(by_key.groups[0].requests, 21);
+
+        assert_eq!("Model".parse(), Ok(UsageGroupBy::Model));
+        assert!("tenant".parse::<UsageGroupBy>().is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! Organization-specific validation rules, evaluated alongside the built-in checks.
+//!
+//! A [`ValidationRule`] inspects a query and reports findings, each an error (the query is
+//! rejected) or a warning. Rules are registered with [`crate::validator::ValidationOptions::with_rules`],
+//! `TextToCypherClient::builder().validation_rule(...)`, or declared in a YAML (or JSON) file:
+//!
+//! ```yaml
+//! rules:
+//!   - name: tenant-filter
+//!     require: '\.tenant_id\s*=\s*\$tenant\b'
+//!     message: Queries must filter on tenant_id = $tenant
+//!   - name: naming
+//!     severity: warning
+//!     label_pattern: '^[A-Z][A-Za-z0-9]*$'
+//!     relationship_type_pattern: '^[A-Z][A-Z0-9_]*$'
+//! ```
+//!
+//! A declared rule may `require` a pattern to appear, `forbid` one, and constrain the labels and
+//! relationship types the query names. The server reads `VALIDATION_RULES_FILE` and reloads it
+//! whenever it changes.
+
+use crate::validator::CypherValidator;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// How a finding affects the query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The query is rejected.
+    #[default]
+    Error,
+    /// The query runs, with a warning.
+    Warning,
+}
+
+/// One problem a rule found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleFinding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl RuleFinding {
+    #[must_use]
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+/// A validation rule of a deployment's own.
+pub trait ValidationRule: Send + Sync + std::fmt::Debug {
+    /// Short, stable rule name, prefixed to the messages of its findings.
+    fn name(&self) -> &str;
+
+    /// The rule's findings for `query`; empty when the query complies.
+    fn check(
+        &self,
+        query: &str,
+    ) -> Vec<RuleFinding>;
+}
+
+/// The custom rules a validation runs.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationRules {
+    rules: Vec<Arc<dyn ValidationRule>>,
+}
+
+impl ValidationRules {
+    /// No rules.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Parses a rules file: a `rules` list of [`PatternRule`]s, in YAML or JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document is malformed, or a rule has an invalid pattern or checks
+    /// nothing.
+    pub fn parse(document: &str) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct RulesFile {
+            #[serde(default)]
+            rules: Vec<PatternRuleSpec>,
+        }
+
+        let file: RulesFile = serde_yaml_ng::from_str(document).map_err(|e| format!("invalid rules file: {e}"))?;
+        let mut rules = Self::default();
+        for spec in file.rules {
+            rules.push(PatternRule::try_from(spec)?);
+        }
+        Ok(rules)
+    }
+
+    /// Adds a rule.
+    pub fn push(
+        &mut self,
+        rule: impl ValidationRule + 'static,
+    ) {
+        self.rules.push(Arc::new(rule));
+    }
+
+    /// Adds every rule of `other`.
+    pub fn extend(
+        &mut self,
+        other: &Self,
+    ) {
+        self.rules.extend(other.rules.iter().cloned());
+    }
+
+    /// Returns true when no rule is registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The rule names, in order, for logs.
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        self.rules.iter().map(|rule| rule.name()).collect()
+    }
+
+    /// Every rule's findings for `query`, each message prefixed with its rule's name.
+    #[must_use]
+    pub fn check(
+        &self,
+        query: &str,
+    ) -> Vec<RuleFinding> {
+        self.rules
+            .iter()
+            .flat_map(|rule| {
+                rule.check(query).into_iter().map(|finding| RuleFinding {
+                    message: format!("{}: {}", rule.name(), finding.message),
+                    ..finding
+                })
+            })
+            .collect()
+    }
+}
+
+/// A [`PatternRule`] as written in a rules file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatternRuleSpec {
+    name: String,
+    #[serde(default)]
+    severity: Severity,
+    message: Option<String>,
+    require: Option<String>,
+    forbid: Option<String>,
+    label_pattern: Option<String>,
+    relationship_type_pattern: Option<String>,
+}
+
+/// A rule declared with regular expressions: patterns the query must or must not contain, and
+/// patterns every label and relationship type it names must match.
+#[derive(Debug, Clone)]
+pub struct PatternRule {
+    pub name: String,
+    pub severity: Severity,
+    /// Replaces the default message of `require` and `forbid` findings.
+    pub message: Option<String>,
+    pub require: Option<Regex>,
+    pub forbid: Option<Regex>,
+    pub label_pattern: Option<Regex>,
+    pub relationship_type_pattern: Option<Regex>,
+}
+
+impl TryFrom<PatternRuleSpec> for PatternRule {
+    type Error = String;
+
+    fn try_from(spec: PatternRuleSpec) -> Result<Self, Self::Error> {
+        let name = spec.name;
+        let compile = |pattern: Option<String>| {
+            pattern
+                .map(|pattern| Regex::new(&pattern).map_err(|e| format!("rule '{name}': invalid pattern: {e}")))
+                .transpose()
+        };
+        let rule = Self {
+            severity: spec.severity,
+            message: spec.message,
+            require: compile(spec.require)?,
+            forbid: compile(spec.forbid)?,
+            label_pattern: compile(spec.label_pattern)?,
+            relationship_type_pattern: compile(spec.relationship_type_pattern)?,
+            name: name.clone(),
+        };
+        if rule.require.is_none()
+            && rule.forbid.is_none()
+            && rule.label_pattern.is_none()
+            && rule.relationship_type_pattern.is_none()
+        {
+            return Err(format!(
+                "rule '{name}' checks nothing; set require, forbid, label_pattern or relationship_type_pattern"
+            ));
+        }
+        Ok(rule)
+    }
+}
+
+impl ValidationRule for PatternRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check(
+        &self,
+        query: &str,
+    ) -> Vec<RuleFinding> {
+        let finding = |default: String| RuleFinding {
+            severity: self.severity,
+            message: self.message.clone().unwrap_or(default),
+        };
+        let mut findings = Vec::new();
+        if let Some(require) = &self.require
+            && !require.is_match(query)
+        {
+            findings.push(finding(format!("query does not match required pattern {require}")));
+        }
+        if let Some(forbid) = &self.forbid
+            && let Some(found) = forbid.find(query)
+        {
+            findings.push(finding(format!("query contains forbidden '{}'", found.as_str())));
+        }
+        if let Some(pattern) = &self.label_pattern {
+            for label in CypherValidator::referenced_labels(query) {
+                if !pattern.is_match(&label) {
+                    findings.push(RuleFinding {
+                        severity: self.severity,
+                        message: format!("label '{label}' does not match {pattern}"),
+                    });
+                }
+            }
+        }
+        if let Some(pattern) = &self.relationship_type_pattern {
+            let masked = CypherValidator::mask_string_literals(query);
+            for relationship_type in CypherValidator::referenced_relationship_types(&masked) {
+                if !pattern.is_match(&relationship_type) {
+                    findings.push(RuleFinding {
+                        severity: self.severity,
+                        message: format!("relationship type '{relationship_type}' does not match {pattern}"),
+                    });
+                }
+            }
+        }
+        findings
+    }
+}
+
+/// A rules file that is read again whenever it changes, so rules can be edited without a restart.
+#[derive(Debug)]
+pub struct RulesFile {
+    path: PathBuf,
+    /// The file's modification time when it was last read, and the rules it held
+    loaded: Mutex<(Option<SystemTime>, ValidationRules)>,
+}
+
+impl RulesFile {
+    /// Reads the rules in `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let (modified, rules) = Self::read(&path)?;
+        Ok(Self {
+            path,
+            loaded: Mutex::new((modified, rules)),
+        })
+    }
+
+    fn read(path: &std::path::Path) -> Result<(Option<SystemTime>, ValidationRules), String> {
+        let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        let document = std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        Ok((modified, ValidationRules::parse(&document)?))
+    }
+
+    /// The current rules, re-read if the file changed. A file that no longer reads or parses
+    /// keeps the rules it last held.
+    #[must_use]
+    pub fn rules(&self) -> ValidationRules {
+        let Ok(mut loaded) = self.loaded.lock() else {
+            return ValidationRules::default();
+        };
+        let modified = std::fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok();
+        if modified != loaded.0 {
+            match Self::read(&self.path) {
+                Ok(reloaded) => {
+                    tracing::info!("Reloaded validation rules from {}", self.path.display());
+                    *loaded = reloaded;
+                }
+                Err(e) => {
+                    tracing::error!("{e}; keeping the previous validation rules");
+                    loaded.0 = modified;
+                }
+            }
+        }
+        loaded.1.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared_rules_report_their_findings() {
+        let rules = ValidationRules::parse(
+            r"
+rules:
+  - name: tenant-filter
+    require: '\.tenant_id\s*=\s*\$tenant\b'
+    message: Queries must filter on tenant_id = $tenant
+  - name: naming
+    severity: warning
+    label_pattern: '^[A-Z][A-Za-z0-9]*$'
+    relationship_type_pattern: '^[A-Z][A-Z0-9_]*$'
+  - name: no-full-scan
+    forbid: 'MATCH \(\w*\)'
+",
+        )
+        .unwrap();
+        assert_eq!(rules.names(), vec!["tenant-filter", "naming", "no-full-scan"]);
+
+        let compliant = "MATCH (p:Person)-[:WORKS_AT]->(c:Company) WHERE p.tenant_id = $tenant RETURN c LIMIT 5";
+        assert!(rules.check(compliant).is_empty());
+
+        let findings = rules.check("MATCH (p:person)-[:worksAt]->(c) MATCH (x) RETURN c LIMIT 5");
+        assert_eq!(
+            findings,
+            vec![
+                RuleFinding::error("tenant-filter: Queries must filter on tenant_id = $tenant"),
+                RuleFinding::warning("naming: label 'person' does not match ^[A-Z][A-Za-z0-9]*$"),
+                RuleFinding::warning("naming: relationship type 'worksAt' does not match ^[A-Z][A-Z0-9_]*$"),
+                RuleFinding::error("no-full-scan: query contains forbidden 'MATCH (x)'"),
+            ]
+        );
+
+        assert!(ValidationRules::parse("rules:\n  - name: empty\n").is_err());
+        assert!(ValidationRules::parse("rules:\n  - name: bad\n    forbid: '('\n").is_err());
+        assert!(ValidationRules::parse("rules:\n  - name: typo\n    requires: x\n").is_err());
+    }
+}
@@ -1,148 +1,2300 @@
+use crate::cypher_syntax;
+use crate::schema::attribute::date_format;
+use crate::schema::discovery::Schema;
+use crate::suggest;
+use crate::tenancy::TenantFilter;
+use crate::validation_rules::{Severity, ValidationRules};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 use std::sync::OnceLock;
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
 
 /// Validates Cypher queries for common syntax errors and security issues
 pub struct CypherValidator;
 
+/// The fewest rows an aggregate may summarize on an aggregate-only graph, so that no aggregate
+/// describes a single record. See [`CypherValidator::aggregate_only_errors`].
+pub const MIN_AGGREGATE_GROUP: u64 = 10;
+
 static PATTERNS: OnceLock<ValidationPatterns> = OnceLock::new();
 
 struct ValidationPatterns {
     /// Pattern to detect basic Cypher syntax
     basic_cypher: Regex,
-    /// Pattern to detect dangerous operations - matches DROP and various DELETE patterns
-    dangerous_ops: Regex,
+    /// Pattern to detect schema-dropping operations, which are never allowed
+    drop_ops: Regex,
+    /// Pattern to detect deletions (DELETE and DETACH DELETE), allowed only with `allow_destructive`
+    delete_ops: Regex,
+    /// Pattern to detect clauses that modify the graph
+    write_clause: Regex,
     /// Pattern to check for balanced parentheses
     match_clause: Regex,
     /// Pattern to check return clause exists
     return_clause: Regex,
+    /// Pattern to check limit clause exists
+    limit_clause: Regex,
+    /// Pattern to detect aggregating projections, which return bounded results without LIMIT
+    aggregate_return: Regex,
+    /// Pattern to capture the label chain of a node pattern, e.g. `:Person:Actor` in `(n:Person:Actor)`
+    node_labels: Regex,
+    /// Pattern to capture a labeled node pattern: its variable, label chain, and inline property map
+    labeled_node: Regex,
+    /// Pattern to capture a property read `n.name`: the variable, the key, and `(` when it is
+    /// really a namespaced function call such as `date.truncate(`
+    property_access: Regex,
+    /// Pattern to capture one hop `(a:A)-[r:R]->(b:B)`: source labels, `<`, relationship body, `>`,
+    /// the target node, and target labels
+    hop: Regex,
+    /// Pattern to find clause keywords, which split a query into clauses for linting
+    clause_keyword: Regex,
+    /// Pattern to capture a relationship pattern: `<`, the bracketed body, and `>`
+    relationship: Regex,
+    /// Pattern to detect an untyped, undirected hop written as `(a)--(b)`
+    bare_undirected: Regex,
+    /// Pattern to capture a variable-length spec inside a relationship body: `*`, `*2`, `*1..`, `*..3`
+    var_length: Regex,
+    /// Pattern to capture variables bound by a pattern: node / relationship variables and path names
+    pattern_variable: Regex,
+    /// Pattern to find the clauses a [`ClausePolicy`] governs. The optional first group catches
+    /// keywords that are not clauses: property keys and labels (`n.set`, `:Match`), `ON CREATE` /
+    /// `ON MATCH` in MERGE, and `STARTS WITH` / `ENDS WITH`
+    policy_clause: Regex,
+    /// Pattern to capture a procedure call: the procedure name and the opening parenthesis, if any
+    procedure_call: Regex,
+    /// Pattern to find identifiers, such as the variables passed to a procedure
+    identifier: Regex,
+    /// Pattern to capture a node pattern: its variable, label chain, and inline property map
+    node: Regex,
+    /// Pattern to find a call to an aggregate function that summarizes rows without returning them
+    aggregate_call: Regex,
+    /// Pattern to find a call to an aggregate function returning, or interpolating between, single
+    /// rows' values
+    value_aggregate_call: Regex,
+    /// Pattern to detect a projection expression counting its rows, e.g. `count(*)`
+    row_count: Regex,
+    /// Pattern to capture a condition requiring a variable to exceed a number, e.g. `n >= 10`
+    lower_bound: Regex,
+    /// Pattern to find the boolean operators of a condition
+    boolean_operator: Regex,
+    /// Pattern to capture a projection item's expression and its `AS` alias
+    projection_alias: Regex,
+    /// Pattern to capture the contents of single- or double-quoted string literals
+    string_literal: Regex,
+    /// Pattern to detect a question about a period relative to today
+    relative_period: Regex,
 }
 
 impl ValidationPatterns {
     fn get() -> &'static Self {
         PATTERNS.get_or_init(|| Self {
             basic_cypher: Regex::new(r"(?i)(MATCH|CREATE|MERGE|DELETE|SET|REMOVE|RETURN|WITH|UNWIND|CALL)").unwrap(),
-            // Simplified pattern to catch dangerous operations more reliably
-            // Matches any DROP or DELETE (with or without DETACH, with any following content)
-            dangerous_ops: Regex::new(r"(?i)(DROP\s|DELETE\s)").unwrap(),
+            // Simplified patterns to catch dangerous operations more reliably
+            drop_ops: Regex::new(r"(?i)DROP\s").unwrap(),
+            // Matches any DELETE (with or without DETACH, with any following content)
+            delete_ops: Regex::new(r"(?i)DELETE\s").unwrap(),
+            write_clause: Regex::new(r"(?i)\b(CREATE|MERGE|SET|DELETE|REMOVE)\b").unwrap(),
             match_clause: Regex::new(r"(?i)MATCH\s+").unwrap(),
             return_clause: Regex::new(r"(?i)RETURN\s+").unwrap(),
+            limit_clause: Regex::new(r"(?i)\bLIMIT\s+").unwrap(),
+            aggregate_return: Regex::new(r"(?is)RETURN\s+.*\b(count|sum|avg|min|max|collect)\s*\(").unwrap(),
+            node_labels: Regex::new(r"\(\s*(?:[A-Za-z_]\w*)?\s*((?::\s*(?:[A-Za-z_]\w*|`[^`]+`)\s*)+)").unwrap(),
+            labeled_node: Regex::new(
+                r"\(\s*([A-Za-z_]\w*)?\s*((?::\s*(?:`[^`]+`|[A-Za-z_]\w*)\s*)+)(\{[^{}]*\})?",
+            )
+            .unwrap(),
+            property_access: Regex::new(r"(?:^|[^\w.$`])([A-Za-z_]\w*)\s*\.\s*(`[^`]+`|[A-Za-z_]\w*)(\s*\()?").unwrap(),
+            hop: Regex::new(concat!(
+                r"\(\s*(?:[A-Za-z_]\w*)?\s*((?::\s*(?:`[^`]+`|[A-Za-z_]\w*)\s*)*)[^()]*\)",
+                r"\s*(<)?-\[([^\]]*)\]-(>)?\s*",
+                r"(\(\s*(?:[A-Za-z_]\w*)?\s*((?::\s*(?:`[^`]+`|[A-Za-z_]\w*)\s*)*)[^()]*\))"
+            ))
+            .unwrap(),
+            clause_keyword: Regex::new(
+                r"(?i)\b(OPTIONAL\s+MATCH|MATCH|WHERE|RETURN|WITH|UNWIND|CREATE|MERGE|SET|DETACH\s+DELETE|DELETE|REMOVE|CALL|ORDER\s+BY|SKIP|LIMIT|UNION)\b",
+            )
+            .unwrap(),
+            procedure_call: Regex::new(r"(?i)\bCALL\s+([A-Za-z_]\w*(?:\s*\.\s*[A-Za-z_]\w*)*)\s*(\()?").unwrap(),
+            identifier: Regex::new(r"\b[A-Za-z_]\w*\b").unwrap(),
+            node: Regex::new(r"\(\s*([A-Za-z_]\w*)?\s*((?::\s*(?:`[^`]+`|[A-Za-z_]\w*)\s*)*)(\{[^{}]*\})?\s*\)").unwrap(),
+            aggregate_call: Regex::new(r"(?i)\b(count|sum|avg|stdev|stdevp)\s*\(").unwrap(),
+            value_aggregate_call: Regex::new(r"(?i)\b(min|max|percentileCont|percentileDisc)\s*\(").unwrap(),
+            row_count: Regex::new(r"(?i)^\s*count\s*\(\s*(?:\*|(?:DISTINCT\s+)?[A-Za-z_]\w*)\s*\)\s*$").unwrap(),
+            lower_bound: Regex::new(r"^\s*(`[^`]+`|[A-Za-z_]\w*)\s*(>=|>)\s*(\d+)\s*$").unwrap(),
+            boolean_operator: Regex::new(r"(?i)\b(AND|OR|XOR|NOT)\b").unwrap(),
+            projection_alias: Regex::new(r"(?is)^(.*?)\s+AS\s+(`[^`]+`|[A-Za-z_]\w*)\s*$").unwrap(),
+            string_literal: Regex::new(r#"'([^'\\]*)'|"([^"\\]*)""#).unwrap(),
+            relative_period: Regex::new(concat!(
+                r"(?i)\b(today|yesterday|tomorrow|ago|recent|recently|ytd|",
+                r"(last|past|previous|this|next|current|coming)\s+(\d+\s+|few\s+|couple\s+of\s+)?",
+                r"(days?|weeks?|months?|quarters?|years?|decades?)|",
+                r"(year|quarter|month|week)[- ]to[- ]date)\b"
+            ))
+            .unwrap(),
+            relationship: Regex::new(r"(<)?-\[([^\]]*)\]-(>)?").unwrap(),
+            bare_undirected: Regex::new(r"\)\s*--\s*\(").unwrap(),
+            var_length: Regex::new(r"\*\s*(\d+)?\s*(\.\.)?\s*(\d+)?").unwrap(),
+            pattern_variable: Regex::new(r"(?:[(\[]\s*|^\s*)([A-Za-z_]\w*)\s*(?:[:)\]{*=]|$)").unwrap(),
+            policy_clause: Regex::new(
+                r"(?i)([.:]\s*|\bON\s+|\bSTARTS\s+|\bENDS\s+)?\b(OPTIONAL\s+MATCH|MATCH|RETURN|WITH|UNWIND|CALL|CREATE|MERGE|SET|DETACH\s+DELETE|DELETE|REMOVE|FOREACH|LOAD\s+CSV|UNION)\b",
+            )
+            .unwrap(),
         })
     }
-}
-
-#[derive(Debug, Clone)]
-pub struct ValidationResult {
-    pub is_valid: bool,
-    pub errors: Vec<String>,
-    pub warnings: Vec<String>,
-}
+}
+
+/// Options that tighten [`CypherValidator::validate`].
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Default)]
+pub struct ValidationOptions {
+    /// Turns the "no RETURN", "no LIMIT", unknown schema name, and "too deep" warnings into errors, so
+    /// questionable queries are refused instead of executed.
+    pub strict: bool,
+    /// Node labels that exist in the graph. When set, labels outside this list are reported;
+    /// `None` skips the check.
+    pub known_labels: Option<Vec<String>>,
+    /// Permits DELETE / DETACH DELETE. DROP is rejected regardless.
+    pub allow_destructive: bool,
+    /// Directed `(source label, relationship type, target label)` triples that exist in the graph.
+    /// When set, hops matching a relationship against its direction are errors; `None` skips the
+    /// check.
+    pub known_relations: Option<Vec<(String, String, String)>>,
+    /// Relationship types that exist in the graph. When set, read queries using other types are
+    /// reported; `None` skips the check.
+    pub known_relationship_types: Option<Vec<String>>,
+    /// The properties of each node label and relationship type. When set, read queries reading a
+    /// property that none of a variable's labels (or its type) has are reported; labels missing
+    /// from the map, or listed without properties, are not checked.
+    pub known_properties: Option<HashMap<String, Vec<String>>>,
+    /// Maximum depth for variable-length patterns. When set, unbounded patterns and patterns
+    /// reaching deeper are reported; see [`CypherValidator::cap_var_length`] for the rewrite.
+    pub max_var_length: Option<u32>,
+    /// Clauses the query may use. When set, any other clause is an error; `None` allows all.
+    pub clause_policy: Option<ClausePolicy>,
+    /// Procedures the query may call, with their argument rules; `None` allows all.
+    pub procedure_allowlist: Option<ProcedureAllowlist>,
+    /// What the query may do to the graph. When set, it alone decides on deletions, and
+    /// `allow_destructive` is ignored; `None` leaves writes other than deletions unchecked.
+    pub policy: Option<ValidationPolicy>,
+    /// A deployment's own rules; their error findings are errors and their warning findings
+    /// warnings, prefixed with the rule name.
+    pub rules: ValidationRules,
+    /// The tenant a query on a shared graph is restricted to. When set, node patterns without the
+    /// tenant's predicate, procedure calls and changes to the tenant property are errors.
+    pub tenant_filter: Option<TenantFilter>,
+    /// The graph only answers statistical questions. When set, queries returning anything but
+    /// aggregates are errors; see [`CypherValidator::aggregate_only_errors`].
+    pub aggregate_only: bool,
+    /// Layouts the graph stores text dates in, from the schema's `date_format`s. When set, date
+    /// literals written in another layout are reported; `None` skips the check.
+    pub date_formats: Option<Vec<String>>,
+    /// The question asks about a period relative to today ("past 7 days"), so any hard-coded date
+    /// in the query is reported; see [`CypherValidator::mentions_relative_period`].
+    pub relative_period: bool,
+}
+
+impl ValidationOptions {
+    /// Options with only the strict flag set.
+    #[must_use]
+    pub const fn strict(strict: bool) -> Self {
+        Self {
+            strict,
+            known_labels: None,
+            allow_destructive: false,
+            known_relations: None,
+            known_relationship_types: None,
+            known_properties: None,
+            max_var_length: None,
+            clause_policy: None,
+            procedure_allowlist: None,
+            policy: None,
+            rules: ValidationRules::new(),
+            tenant_filter: None,
+            aggregate_only: false,
+            date_formats: None,
+            relative_period: false,
+        }
+    }
+
+    /// Permits (or forbids) DELETE / DETACH DELETE.
+    #[must_use]
+    pub const fn with_allow_destructive(
+        mut self,
+        allow_destructive: bool,
+    ) -> Self {
+        self.allow_destructive = allow_destructive;
+        self
+    }
+
+    /// Sets the maximum variable-length pattern depth; `None` disables the check.
+    #[must_use]
+    pub const fn with_max_var_length(
+        mut self,
+        max_var_length: Option<u32>,
+    ) -> Self {
+        self.max_var_length = max_var_length;
+        self
+    }
+
+    /// Restricts the clauses a query may use; `None` allows all.
+    #[must_use]
+    pub fn with_clause_policy(
+        mut self,
+        clause_policy: Option<ClausePolicy>,
+    ) -> Self {
+        self.clause_policy = clause_policy;
+        self
+    }
+
+    /// Restricts the procedures a query may call; `None` allows all.
+    #[must_use]
+    pub fn with_procedure_allowlist(
+        mut self,
+        procedure_allowlist: Option<ProcedureAllowlist>,
+    ) -> Self {
+        self.procedure_allowlist = procedure_allowlist;
+        self
+    }
+
+    /// Restricts what the query may do to the graph; `None` only checks deletions.
+    #[must_use]
+    pub fn with_policy(
+        mut self,
+        policy: Option<ValidationPolicy>,
+    ) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Runs a deployment's own rules alongside the built-in checks.
+    #[must_use]
+    pub fn with_rules(
+        mut self,
+        rules: ValidationRules,
+    ) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Accepts only queries answering with aggregates.
+    #[must_use]
+    pub const fn with_aggregate_only(
+        mut self,
+        aggregate_only: bool,
+    ) -> Self {
+        self.aggregate_only = aggregate_only;
+        self
+    }
+
+    /// Restricts the query to a tenant's nodes; `None` checks no tenant filter.
+    #[must_use]
+    pub fn with_tenant_filter(
+        mut self,
+        tenant_filter: Option<TenantFilter>,
+    ) -> Self {
+        self.tenant_filter = tenant_filter;
+        self
+    }
+
+    /// Reports hard-coded dates when `question` asks about a period relative to today.
+    #[must_use]
+    pub fn with_question(
+        mut self,
+        question: &str,
+    ) -> Self {
+        self.relative_period = CypherValidator::mentions_relative_period(question);
+        self
+    }
+
+    /// Takes the known labels, relationship types and directions, properties and date layouts from
+    /// a discovered schema in its JSON form. A schema that cannot be parsed (or has no entities /
+    /// relations / dates) leaves the corresponding check disabled.
+    #[must_use]
+    pub fn with_schema(
+        mut self,
+        schema_json: &str,
+    ) -> Self {
+        let Ok(schema) = serde_json::from_str::<Schema>(schema_json) else {
+            self.known_labels = None;
+            self.known_relations = None;
+            self.known_relationship_types = None;
+            self.known_properties = None;
+            self.date_formats = None;
+            return self;
+        };
+        let mut date_formats: Vec<String> = schema
+            .entities
+            .iter()
+            .flat_map(|entity| &entity.attributes)
+            .chain(schema.relations.iter().flat_map(|relation| &relation.attributes))
+            .filter_map(|attribute| attribute.date_format.clone())
+            .collect();
+        date_formats.sort();
+        date_formats.dedup();
+        self.date_formats = (!date_formats.is_empty()).then_some(date_formats);
+        let mut known_properties: HashMap<String, Vec<String>> = HashMap::new();
+        let owners = schema
+            .entities
+            .iter()
+            .map(|entity| (&entity.label, &entity.attributes))
+            .chain(schema.relations.iter().map(|relation| (&relation.label, &relation.attributes)));
+        for (owner, attributes) in owners {
+            let properties = known_properties.entry(owner.clone()).or_default();
+            for attribute in attributes {
+                if !properties.contains(&attribute.name) {
+                    properties.push(attribute.name.clone());
+                }
+            }
+        }
+        self.known_properties = (!known_properties.is_empty()).then_some(known_properties);
+        let mut relationship_types: Vec<String> =
+            schema.relations.iter().map(|relation| relation.label.clone()).collect();
+        relationship_types.sort();
+        relationship_types.dedup();
+        self.known_relationship_types = (!relationship_types.is_empty()).then_some(relationship_types);
+        self.known_relations = (!schema.relations.is_empty()).then(|| {
+            schema
+                .relations
+                .iter()
+                .flat_map(|relation| {
+                    let targets = relation.target_label_set();
+                    relation.source_label_set().into_iter().flat_map(move |source| {
+                        targets
+                            .clone()
+                            .into_iter()
+                            .map(move |target| (source.clone(), relation.label.clone(), target))
+                    })
+                })
+                .collect()
+        });
+        self.known_labels =
+            (!schema.entities.is_empty()).then(|| schema.entities.into_iter().map(|entity| entity.label).collect());
+        self
+    }
+}
+
+/// A clause a [`ClausePolicy`] allows or forbids. OPTIONAL MATCH counts as MATCH and DETACH DELETE
+/// as DELETE; WHERE, ORDER BY, SKIP and LIMIT only refine other clauses and are always allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Clause {
+    Match,
+    Return,
+    With,
+    Unwind,
+    Call,
+    Create,
+    Merge,
+    Set,
+    Delete,
+    Remove,
+    Foreach,
+    LoadCsv,
+    Union,
+}
+
+impl FromStr for Clause {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.split_whitespace().collect::<Vec<_>>().join(" ").to_ascii_uppercase();
+        match normalized.as_str() {
+            "MATCH" | "OPTIONAL MATCH" => Ok(Self::Match),
+            "RETURN" => Ok(Self::Return),
+            "WITH" => Ok(Self::With),
+            "UNWIND" => Ok(Self::Unwind),
+            "CALL" => Ok(Self::Call),
+            "CREATE" => Ok(Self::Create),
+            "MERGE" => Ok(Self::Merge),
+            "SET" => Ok(Self::Set),
+            "DELETE" | "DETACH DELETE" => Ok(Self::Delete),
+            "REMOVE" => Ok(Self::Remove),
+            "FOREACH" => Ok(Self::Foreach),
+            "LOAD CSV" | "LOAD_CSV" => Ok(Self::LoadCsv),
+            "UNION" => Ok(Self::Union),
+            _ => Err(format!("unknown clause '{}'", s.trim())),
+        }
+    }
+}
+
+impl fmt::Display for Clause {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str(match self {
+            Self::Match => "MATCH",
+            Self::Return => "RETURN",
+            Self::With => "WITH",
+            Self::Unwind => "UNWIND",
+            Self::Call => "CALL",
+            Self::Create => "CREATE",
+            Self::Merge => "MERGE",
+            Self::Set => "SET",
+            Self::Delete => "DELETE",
+            Self::Remove => "REMOVE",
+            Self::Foreach => "FOREACH",
+            Self::LoadCsv => "LOAD CSV",
+            Self::Union => "UNION",
+        })
+    }
+}
+
+/// The clauses a query may use, e.g. `MATCH|RETURN|WITH|UNWIND` for an analyst who may neither
+/// write nor call procedures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClausePolicy {
+    allowed: Vec<Clause>,
+}
+
+impl FromIterator<Clause> for ClausePolicy {
+    fn from_iter<I: IntoIterator<Item = Clause>>(clauses: I) -> Self {
+        let mut allowed: Vec<Clause> = clauses.into_iter().collect();
+        allowed.sort_unstable();
+        allowed.dedup();
+        Self { allowed }
+    }
+}
+
+impl ClausePolicy {
+    /// Parses a `|`- or `,`-separated clause list such as `MATCH|RETURN|WITH|UNWIND`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first unknown clause.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        spec.split(['|', ','])
+            .map(str::trim)
+            .filter(|clause| !clause.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    /// Whether the policy allows `clause`.
+    #[must_use]
+    pub fn allows(
+        &self,
+        clause: Clause,
+    ) -> bool {
+        self.allowed.binary_search(&clause).is_ok()
+    }
+
+    /// The clauses both policies allow, for requests that several policies apply to.
+    #[must_use]
+    pub fn intersect(
+        &self,
+        other: &Self,
+    ) -> Self {
+        self.allowed.iter().copied().filter(|clause| other.allows(*clause)).collect()
+    }
+
+    /// The clauses `query` uses that the policy forbids, each once, in order of appearance.
+    #[must_use]
+    pub fn disallowed(
+        &self,
+        query: &str,
+    ) -> Vec<Clause> {
+        let masked = CypherValidator::mask_string_literals(query);
+        let mut disallowed = Vec::new();
+        for captures in ValidationPatterns::get().policy_clause.captures_iter(&masked) {
+            if captures.get(1).is_some() {
+                continue;
+            }
+            if let Ok(clause) = captures[2].parse::<Clause>()
+                && !self.allows(clause)
+                && !disallowed.contains(&clause)
+            {
+                disallowed.push(clause);
+            }
+        }
+        disallowed
+    }
+}
+
+/// Named clause policies ("personas") and the API keys and graphs they are bound to.
+///
+/// Policies are configured as `name=CLAUSE|CLAUSE;...`, for example
+/// `analyst=MATCH|RETURN|WITH|UNWIND;editor=MATCH|RETURN|WITH|CREATE|MERGE|SET`, and bound as
+/// `key:<api key>=name;graph:<graph>=name;...`. A request gets the policy of its API key and that of
+/// its graph; when both are bound, only clauses allowed by both are.
+#[derive(Debug, Clone, Default)]
+pub struct ClausePolicies {
+    keys: HashMap<String, ClausePolicy>,
+    graphs: HashMap<String, ClausePolicy>,
+}
+
+impl ClausePolicies {
+    /// Parses the policy list and the bindings. Blank entries are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending entry if a policy has an unknown clause or is defined
+    /// twice, or a binding is malformed or names an undefined policy.
+    pub fn parse(
+        policies: &str,
+        bindings: &str,
+    ) -> Result<Self, String> {
+        let entries = |spec: &str| -> Vec<String> {
+            spec.split(';')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+
+        let mut named: HashMap<String, ClausePolicy> = HashMap::new();
+        for entry in entries(policies) {
+            let (name, clauses) = entry
+                .split_once('=')
+                .map(|(name, clauses)| (name.trim(), clauses))
+                .filter(|(name, _)| !name.is_empty())
+                .ok_or_else(|| format!("policy entries must be 'name=CLAUSE|CLAUSE', got '{entry}'"))?;
+            let policy = ClausePolicy::parse(clauses).map_err(|e| format!("policy '{name}': {e}"))?;
+            if named.insert(name.to_string(), policy).is_some() {
+                return Err(format!("policy '{name}' is defined twice"));
+            }
+        }
+
+        let mut resolved = Self::default();
+        for entry in entries(bindings) {
+            let (target, name) = entry
+                .split_once('=')
+                .map(|(target, name)| (target.trim(), name.trim()))
+                .ok_or_else(|| {
+                    format!("policy bindings must be 'key:<key>=name' or 'graph:<graph>=name', got '{entry}'")
+                })?;
+            let policy = named
+                .get(name)
+                .ok_or_else(|| format!("binding '{entry}' names an undefined policy '{name}'"))?;
+            let (map, id) = match target.split_once(':') {
+                Some(("key", key)) => (&mut resolved.keys, key.trim()),
+                Some(("graph", graph)) => (&mut resolved.graphs, graph.trim()),
+                _ => return Err(format!("binding '{entry}' must start with 'key:' or 'graph:'")),
+            };
+            if id.is_empty() {
+                return Err(format!("binding '{entry}' has an empty key or graph"));
+            }
+            map.insert(id.to_string(), policy.clone());
+        }
+        Ok(resolved)
+    }
+
+    /// Returns true when no policy is bound.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.graphs.is_empty()
+    }
+
+    /// The policy for a request with `api_key` on `graph`, or `None` when neither is bound.
+    #[must_use]
+    pub fn policy_for(
+        &self,
+        api_key: Option<&str>,
+        graph: &str,
+    ) -> Option<ClausePolicy> {
+        let by_key = api_key.and_then(|key| self.keys.get(key));
+        match (by_key, self.graphs.get(graph)) {
+            (Some(key_policy), Some(graph_policy)) => Some(key_policy.intersect(graph_policy)),
+            (policy, None) | (None, policy) => policy.cloned(),
+        }
+    }
+}
+
+/// What generated queries may do to the graph, for deployments that need writes (or need to rule
+/// them out) beyond the per-request `allow_destructive` flag.
+///
+/// Policies are configured as `;`-separated flags, for example `allow-create;block=LOAD CSV|FOREACH`:
+///
+/// - `read-only`: no clause that modifies the graph, whatever the other flags say,
+/// - `allow-create`: CREATE, MERGE, SET and REMOVE,
+/// - `allow-delete`: DELETE and DETACH DELETE,
+/// - `block=CLAUSE|CLAUSE`: clauses rejected in every query.
+///
+/// The default policy allows reads only. DROP is rejected regardless.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationPolicy {
+    /// Rejects every write, whatever `allow_create` and `allow_delete` say.
+    pub read_only: bool,
+    /// Permits CREATE, MERGE, SET and REMOVE.
+    pub allow_create: bool,
+    /// Permits DELETE and DETACH DELETE.
+    pub allow_delete: bool,
+    /// Clauses rejected in every query.
+    pub blocked_clauses: Vec<Clause>,
+}
+
+impl ValidationPolicy {
+    /// A policy rejecting every write.
+    #[must_use]
+    pub fn read_only() -> Self {
+        Self {
+            read_only: true,
+            ..Self::default()
+        }
+    }
+
+    /// Parses a `;`-separated policy such as `allow-create;allow-delete;block=FOREACH`. Blank
+    /// entries are ignored, so an empty policy allows reads only.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending entry if a flag or a blocked clause is unknown.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut policy = Self::default();
+        for entry in spec.split(';').map(str::trim).filter(|entry| !entry.is_empty()) {
+            if let Some(clauses) = entry.strip_prefix("block=") {
+                for clause in clauses.split('|').map(str::trim).filter(|clause| !clause.is_empty()) {
+                    policy.blocked_clauses.push(clause.parse()?);
+                }
+                continue;
+            }
+            match entry.to_ascii_lowercase().as_str() {
+                "read-only" => policy.read_only = true,
+                "allow-create" => policy.allow_create = true,
+                "allow-delete" => policy.allow_delete = true,
+                _ => {
+                    return Err(format!(
+                        "unknown policy entry '{entry}'; expected read-only, allow-create, allow-delete or block=CLAUSE|CLAUSE"
+                    ));
+                }
+            }
+        }
+        Ok(policy)
+    }
+
+    /// The write clauses the policy permits.
+    #[must_use]
+    pub fn allowed_writes(&self) -> Vec<Clause> {
+        [Clause::Create, Clause::Merge, Clause::Set, Clause::Remove, Clause::Delete]
+            .into_iter()
+            .filter(|clause| {
+                let allowed = if *clause == Clause::Delete {
+                    self.allow_delete
+                } else {
+                    self.allow_create
+                };
+                allowed && !self.read_only && !self.blocked_clauses.contains(clause)
+            })
+            .collect()
+    }
+
+    /// Whether the policy permits any write, so permitted write queries must run read-write.
+    #[must_use]
+    pub fn allows_writes(&self) -> bool {
+        !self.allowed_writes().is_empty()
+    }
+
+    /// Errors for the clauses in `query` the policy forbids, each once, in order of appearance.
+    #[must_use]
+    pub fn check(
+        &self,
+        query: &str,
+    ) -> Vec<String> {
+        let allowed_writes = self.allowed_writes();
+        let mut errors = Vec::new();
+        // A clause policy allowing nothing reports every clause the query uses
+        for clause in ClausePolicy::from_iter([]).disallowed(query) {
+            let write = matches!(
+                clause,
+                Clause::Create | Clause::Merge | Clause::Set | Clause::Remove | Clause::Delete
+            );
+            if self.blocked_clauses.contains(&clause) {
+                errors.push(format!("{clause} clauses are blocked by the validation policy"));
+            } else if write && self.read_only {
+                errors.push(format!(
+                    "{clause} clauses are not allowed: the validation policy is read-only"
+                ));
+            } else if write && !allowed_writes.contains(&clause) {
+                errors.push(format!("{clause} clauses are not allowed by the validation policy"));
+            }
+        }
+        errors
+    }
+}
+
+/// Cost of a pattern or plan operation reading every node in the graph
+const FULL_SCAN_COST: u32 = 40;
+/// Cost of a plan operation reading every node with a label
+const LABEL_SCAN_COST: u32 = 10;
+/// Cost of a cartesian product, which multiplies the row counts of its inputs
+const CARTESIAN_PRODUCT_COST: u32 = 30;
+/// Cost of a variable-length pattern without an upper bound
+const UNBOUNDED_VAR_LENGTH_COST: u32 = 50;
+/// Cost of each hop a bounded variable-length pattern may take beyond the first
+const VAR_LENGTH_HOP_COST: u32 = 5;
+/// Cost of a read returning rows without LIMIT
+const MISSING_LIMIT_COST: u32 = 10;
+
+/// One reason a query is expensive, and the cost it adds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostFactor {
+    pub reason: String,
+    pub cost: u32,
+}
+
+/// A rough estimate, made before a query runs, of how much of the graph it touches. Costs are
+/// relative: an anchored, limited read costs 0, and a read of every node at least 40.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostEstimate {
+    pub cost: u32,
+    pub factors: Vec<CostFactor>,
+}
+
+impl CostEstimate {
+    fn add(
+        &mut self,
+        reason: impl Into<String>,
+        cost: u32,
+    ) {
+        self.cost = self.cost.saturating_add(cost);
+        self.factors.push(CostFactor {
+            reason: reason.into(),
+            cost,
+        });
+    }
+}
+
+impl fmt::Display for CostEstimate {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "{}", self.cost)?;
+        if !self.factors.is_empty() {
+            let factors: Vec<String> = self
+                .factors
+                .iter()
+                .map(|factor| format!("{} +{}", factor.reason, factor.cost))
+                .collect();
+            write!(f, " ({})", factors.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Estimates the cost of `query` before it runs.
+///
+/// Given its `GRAPH.EXPLAIN` plan (see
+/// [`crate::core::explain_cypher_query`]), scans and cartesian products are read from the plan;
+/// without one they are inferred from the patterns, where a pattern with no label, property map or
+/// previously bound variable scans every node. Variable-length patterns and reads without LIMIT
+/// are always taken from the query.
+#[must_use]
+pub fn estimate_cost(
+    query: &str,
+    plan: Option<&[String]>,
+) -> CostEstimate {
+    let patterns = ValidationPatterns::get();
+    let masked = CypherValidator::mask_string_literals(query);
+    let mut estimate = CostEstimate::default();
+
+    if let Some(plan) = plan {
+        for operation in plan.iter().map(|operation| operation.trim()) {
+            if operation.starts_with("All Node Scan") {
+                estimate.add(format!("{operation} reads every node"), FULL_SCAN_COST);
+            } else if operation.starts_with("Node By Label Scan") {
+                estimate.add(format!("{operation} reads every node with the label"), LABEL_SCAN_COST);
+            } else if operation.starts_with("Cartesian Product") {
+                estimate.add("the plan has a cartesian product", CARTESIAN_PRODUCT_COST);
+            }
+        }
+    } else {
+        for pattern in CypherValidator::unanchored_patterns(&masked) {
+            estimate.add(
+                format!(
+                    "pattern {pattern} has no label, property or bound variable to start from, so it reads every node"
+                ),
+                FULL_SCAN_COST,
+            );
+        }
+        if CypherValidator::lint(query)
+            .iter()
+            .any(|hint| hint.rule == LintRule::CartesianProduct)
+        {
+            estimate.add(
+                "disconnected MATCH patterns form a cartesian product",
+                CARTESIAN_PRODUCT_COST,
+            );
+        }
+    }
+
+    for var_length in CypherValidator::var_lengths(&masked) {
+        match var_length.upper {
+            None => estimate.add(
+                format!("variable-length pattern {} has no upper bound", var_length.pattern),
+                UNBOUNDED_VAR_LENGTH_COST,
+            ),
+            Some(upper) if upper > 1 => estimate.add(
+                format!(
+                    "variable-length pattern {} may take up to {upper} hops",
+                    var_length.pattern
+                ),
+                (upper - 1).saturating_mul(VAR_LENGTH_HOP_COST),
+            ),
+            Some(_) => {}
+        }
+    }
+
+    if CypherValidator::query_mode(&masked) == QueryMode::Read
+        && patterns.return_clause.is_match(&masked)
+        && !patterns.limit_clause.is_match(&masked)
+        && !patterns.aggregate_return.is_match(&masked)
+    {
+        estimate.add("the query returns rows without LIMIT", MISSING_LIMIT_COST);
+    }
+    estimate
+}
+
+/// How much estimated cost (see [`estimate_cost`]) a query may have before it runs. Queries over
+/// budget run with a warning, or are rejected when `reject` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostBudget {
+    pub max_cost: u32,
+    pub reject: bool,
+}
+
+impl CostBudget {
+    /// Parses a `;`-separated budget such as `max-cost=60;reject`. Blank entries are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending entry if an entry is unknown, or `max-cost` is
+    /// missing or not a number.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut max_cost = None;
+        let mut reject = false;
+        for entry in spec.split(';').map(str::trim).filter(|entry| !entry.is_empty()) {
+            if let Some(value) = entry.strip_prefix("max-cost=") {
+                let value = value.trim();
+                max_cost = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("max-cost must be a whole number, got '{value}'"))?,
+                );
+            } else if entry == "reject" {
+                reject = true;
+            } else if entry == "warn" {
+                reject = false;
+            } else {
+                return Err(format!(
+                    "unknown cost budget entry '{entry}'; expected max-cost=N, warn or reject"
+                ));
+            }
+        }
+        let max_cost = max_cost.ok_or("the cost budget needs a max-cost=N entry")?;
+        Ok(Self { max_cost, reject })
+    }
+
+    /// Whether `estimate` is within the budget.
+    #[must_use]
+    pub const fn allows(
+        &self,
+        estimate: &CostEstimate,
+    ) -> bool {
+        estimate.cost <= self.max_cost
+    }
+
+    /// The status reporting `estimate` against the budget.
+    #[must_use]
+    pub fn status(
+        &self,
+        estimate: &CostEstimate,
+    ) -> String {
+        format!("Estimated query cost: {estimate}, budget {}", self.max_cost)
+    }
+
+    /// The warning or error for an `estimate` over the budget.
+    #[must_use]
+    pub fn exceeded(
+        &self,
+        estimate: &CostEstimate,
+    ) -> String {
+        let reasons: Vec<&str> = estimate.factors.iter().map(|factor| factor.reason.as_str()).collect();
+        format!(
+            "The query's estimated cost {} exceeds the complexity budget of {}: {}",
+            estimate.cost,
+            self.max_cost,
+            reasons.join("; ")
+        )
+    }
+}
+
+/// An argument of a procedure call: by position (from 0), or by key in a map argument such as
+/// `algo.SPpaths({pathCount: 1})`.
+#[derive(Debug, Clone, PartialEq)]
+enum ProcedureArg {
+    Position(usize),
+    Key(String),
+}
+
+impl fmt::Display for ProcedureArg {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            Self::Position(position) => write!(f, "argument {position}"),
+            Self::Key(key) => write!(f, "`{key}`"),
+        }
+    }
+}
+
+/// What a literal argument must satisfy.
+#[derive(Debug, Clone, PartialEq)]
+enum ArgConstraint {
+    /// A number no greater than the bound.
+    AtMost(f64),
+    /// One of the listed strings.
+    OneOf(Vec<String>),
+}
+
+/// One allowlist entry: a procedure name (or a `prefix.*` family) and its argument rules.
+#[derive(Debug, Clone, PartialEq)]
+struct ProcedureRule {
+    name: String,
+    max_args: Option<usize>,
+    constraints: Vec<(ProcedureArg, ArgConstraint)>,
+}
+
+impl ProcedureRule {
+    fn parse(entry: &str) -> Result<Self, String> {
+        let (name, rules) = match entry.split_once('(') {
+            Some((name, rules)) => {
+                let rules = rules
+                    .strip_suffix(')')
+                    .ok_or_else(|| format!("procedure entry '{entry}' is missing its closing parenthesis"))?;
+                (name.trim(), rules)
+            }
+            None => (entry, ""),
+        };
+        if name.is_empty() {
+            return Err(format!("procedure entry '{entry}' has no name"));
+        }
+        let mut rule = Self {
+            name: name.to_string(),
+            max_args: None,
+            constraints: Vec::new(),
+        };
+        for constraint in rules.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            let invalid = || format!("invalid rule '{constraint}' for procedure '{name}'");
+            if let Some((arg, bound)) = constraint.split_once("<=") {
+                let (arg, bound) = (arg.trim(), bound.trim());
+                if arg == "args" {
+                    rule.max_args = Some(bound.parse().map_err(|_| invalid())?);
+                } else {
+                    let bound = ArgConstraint::AtMost(bound.parse().map_err(|_| invalid())?);
+                    rule.constraints.push((Self::arg(arg).ok_or_else(invalid)?, bound));
+                }
+            } else if let Some((arg, values)) = constraint.split_once('=') {
+                let values = values
+                    .split('|')
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+                    .collect();
+                rule.constraints
+                    .push((Self::arg(arg.trim()).ok_or_else(invalid)?, ArgConstraint::OneOf(values)));
+            } else {
+                return Err(invalid());
+            }
+        }
+        Ok(rule)
+    }
+
+    fn arg(arg: &str) -> Option<ProcedureArg> {
+        if let Ok(position) = arg.parse() {
+            return Some(ProcedureArg::Position(position));
+        }
+        let mut chars = arg.chars();
+        (chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        .then(|| ProcedureArg::Key(arg.to_string()))
+    }
+
+    fn matches(
+        &self,
+        procedure: &str,
+    ) -> bool {
+        self.name.strip_suffix('*').map_or_else(
+            || self.name.eq_ignore_ascii_case(procedure),
+            |prefix| procedure.len() >= prefix.len() && procedure[..prefix.len()].eq_ignore_ascii_case(prefix),
+        )
+    }
+
+    /// Errors for a call of this procedure with `arguments` as written.
+    fn check(
+        &self,
+        procedure: &str,
+        arguments: &[String],
+    ) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(max_args) = self.max_args
+            && arguments.len() > max_args
+        {
+            errors.push(format!(
+                "Procedure {procedure} takes at most {max_args} arguments, got {}",
+                arguments.len()
+            ));
+        }
+        for (arg, constraint) in &self.constraints {
+            let value = match arg {
+                ProcedureArg::Position(position) => arguments.get(*position).map(String::as_str),
+                ProcedureArg::Key(key) => arguments.iter().find_map(|argument| map_entry(argument, key)),
+            };
+            // A rule only applies to arguments that are given.
+            let Some(value) = value else {
+                continue;
+            };
+            let satisfied = match constraint {
+                ArgConstraint::AtMost(bound) => value.parse::<f64>().is_ok_and(|number| number <= *bound),
+                ArgConstraint::OneOf(values) => {
+                    string_literal(value).is_some_and(|literal| values.iter().any(|v| v == literal))
+                }
+            };
+            if !satisfied {
+                errors.push(match constraint {
+                    ArgConstraint::AtMost(bound) => {
+                        format!("{arg} of {procedure} must be a number no greater than {bound}, got {value}")
+                    }
+                    ArgConstraint::OneOf(values) => {
+                        format!("{arg} of {procedure} must be one of {}, got {value}", values.join(", "))
+                    }
+                });
+            }
+        }
+        errors
+    }
+}
+
+/// The value of `key` in a map literal `argument` such as `{sourceNode: a, pathCount: 1}`.
+fn map_entry<'a>(
+    argument: &'a str,
+    key: &str,
+) -> Option<&'a str> {
+    let body = argument.strip_prefix('{')?.strip_suffix('}')?;
+    let masked = CypherValidator::mask_string_literals(body);
+    CypherValidator::top_level_ranges(&masked).into_iter().find_map(|range| {
+        let (entry_key, _) = masked[range.clone()].split_once(':')?;
+        let value = &body[range.start + entry_key.len() + 1..range.end];
+        (entry_key.trim().trim_matches('`') == key).then(|| value.trim())
+    })
+}
+
+/// The contents of a quoted string literal.
+fn string_literal(value: &str) -> Option<&str> {
+    ['\'', '"']
+        .into_iter()
+        .find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote))
+}
+
+/// The graph algorithm procedures `FalkorDB` provides, with the map keys each requires when it
+/// takes a configuration map.
+const ALGORITHM_PROCEDURES: &[(&str, &[&str])] = &[
+    ("algo.SPpaths", &["sourceNode", "targetNode"]),
+    ("algo.SSpaths", &["sourceNode"]),
+    ("algo.pageRank", &[]),
+    ("algo.betweenness", &[]),
+    ("algo.WCC", &[]),
+    ("algo.labelPropagation", &[]),
+    ("algo.BFS", &[]),
+    ("algo.MSF", &[]),
+];
+
+/// The procedures queries may call, with optional rules for their arguments.
+///
+/// Entries are separated by `;`: a procedure name, or `prefix.*` for a family, optionally followed
+/// by comma-separated rules in parentheses:
+///
+/// - `args<=N`: at most `N` arguments,
+/// - `<arg><=N`: a number no greater than `N`,
+/// - `<arg>=a|b`: one of the listed strings,
+///
+/// where `<arg>` is a position (from 0) or a key of a map argument. For example
+/// `db.labels;db.idx.fulltext.queryNodes(args<=2);algo.SPpaths(pathCount<=5, relDirection=outgoing|incoming)`.
+/// Rules apply to the arguments that are given, which must then be literals. Procedure names are
+/// matched case-insensitively. The default allowlist allows no procedure.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcedureAllowlist {
+    rules: Vec<ProcedureRule>,
+}
+
+impl ProcedureAllowlist {
+    /// Parses a `;`-separated allowlist. Blank entries are ignored, so an empty list allows no
+    /// procedure.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending entry if it has no name, an unclosed rule list or a
+    /// malformed rule.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let rules = spec
+            .split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(ProcedureRule::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Whether `procedure` may be called at all.
+    #[must_use]
+    pub fn allows(
+        &self,
+        procedure: &str,
+    ) -> bool {
+        self.rules.iter().any(|rule| rule.matches(procedure))
+    }
+
+    /// Errors for the procedure calls in `query`: procedures outside the allowlist, and arguments
+    /// breaking their procedure's rules.
+    #[must_use]
+    pub fn check(
+        &self,
+        query: &str,
+    ) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (procedure, arguments) in CypherValidator::procedure_calls(query) {
+            let Some(rule) = self.rules.iter().find(|rule| rule.matches(&procedure)) else {
+                errors.push(format!("Procedure {procedure} is not allowed"));
+                continue;
+            };
+            errors.extend(rule.check(&procedure, arguments.as_deref().unwrap_or_default()));
+        }
+        errors
+    }
+}
+
+/// A variable-length relationship spec such as `*1..3`, located in the query
+struct VarLength {
+    /// The whole relationship pattern, for messages
+    pattern: String,
+    /// Byte range of the spec (from `*` to the last bound) in the query
+    spec: std::ops::Range<usize>,
+    lower: Option<u32>,
+    /// `None` for unbounded specs (`*`, `*2..`)
+    upper: Option<u32>,
+}
+
+/// Whether a query only reads the graph or also modifies it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryMode {
+    Read,
+    Write,
+}
+
+/// A style or performance rule the linter checks. Lint hints never make a query invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+    /// Disconnected patterns in MATCH clauses, which multiply their row counts
+    CartesianProduct,
+    /// A relationship pattern without an arrow, which is matched in both directions
+    UndirectedRelationship,
+    /// A variable-length pattern such as `[*]` or `[*2..]` with no upper bound
+    UnboundedVarLength,
+    /// OPTIONAL MATCH that cannot produce nulls for missing data: first in the query, or not
+    /// connected to anything matched before it
+    OptionalMatchMisuse,
+}
+
+/// One lint finding, with a message suitable for both users and a refinement prompt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct LintHint {
+    pub rule: LintRule,
+    pub message: String,
+}
+
+impl LintHint {
+    fn new(
+        rule: LintRule,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            rule,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LintHint {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// The kind of schema name a [`SchemaIssue`] is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaElement {
+    Label,
+    RelationshipType,
+    Property,
+}
+
+/// A label, relationship type or property the query uses that the schema does not have, with the
+/// closest names it does have
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaIssue {
+    pub element: SchemaElement,
+    pub name: String,
+    /// For properties, the labels (or relationship type) of the variable the property is read from
+    pub owners: Vec<String>,
+    /// Known names within edit distance of `name`, closest first
+    pub suggestions: Vec<String>,
+}
+
+impl SchemaIssue {
+    fn new<'a>(
+        element: SchemaElement,
+        name: &str,
+        owners: Vec<String>,
+        known: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        Self {
+            element,
+            name: name.to_string(),
+            owners,
+            suggestions: suggest::closest_matches(name, known, 3)
+                .into_iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for SchemaIssue {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self.element {
+            SchemaElement::Label => write!(f, "Query uses unknown label '{}'", self.name)?,
+            SchemaElement::RelationshipType => write!(f, "Query uses unknown relationship type '{}'", self.name)?,
+            SchemaElement::Property => write!(
+                f,
+                "Query uses unknown property '{}' of {}",
+                self.name,
+                self.owners.join(":")
+            )?,
+        }
+        let suggestions: Vec<&str> = self.suggestions.iter().map(String::as_str).collect();
+        f.write_str(&suggest::did_you_mean(&suggestions))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationResult {
+    pub is_valid: bool,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Style and performance hints from [`CypherValidator::lint`]
+    pub hints: Vec<LintHint>,
+    /// Labels, relationship types and properties missing from the schema; their messages are also
+    /// among the warnings (or, in strict mode, the errors)
+    pub schema_issues: Vec<SchemaIssue>,
+}
+
+impl CypherValidator {
+    /// Validates a Cypher query for syntax and safety
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The Cypher query to validate
+    ///
+    /// # Returns
+    ///
+    /// A `ValidationResult` containing validation status and any errors/warnings
+    #[must_use]
+    pub fn validate(query: &str) -> ValidationResult {
+        Self::validate_with_options(query, &ValidationOptions::default())
+    }
+
+    /// Validates a Cypher query, applying the strictness and label checks in `options`
+    #[allow(clippy::too_many_lines)]
+    #[must_use]
+    pub fn validate_with_options(
+        query: &str,
+        options: &ValidationOptions,
+    ) -> ValidationResult {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+
+        let query = query.trim();
+        let patterns = ValidationPatterns::get();
+
+        // Check if query is empty
+        if query.is_empty() {
+            errors.push("Query is empty".to_string());
+            return ValidationResult {
+                is_valid: false,
+                errors,
+                warnings,
+                hints: Vec::new(),
+                schema_issues: Vec::new(),
+            };
+        }
+
+        // Check if query contains basic Cypher keywords
+        if !patterns.basic_cypher.is_match(query) {
+            errors.push("Query does not contain valid Cypher keywords".to_string());
+        }
+
+        // Check for dangerous operations; a validation policy decides on deletions itself
+        let deletes_allowed = options.allow_destructive || options.policy.is_some();
+        if patterns.drop_ops.is_match(query) || (patterns.delete_ops.is_match(query) && !deletes_allowed) {
+            errors.push("Query contains potentially dangerous operations (DROP, DELETE ALL)".to_string());
+        }
+
+        // Check writes and blocked clauses against the validation policy
+        if let Some(policy) = &options.policy {
+            errors.extend(policy.check(query));
+        }
+
+        // Check the clauses against the caller's policy
+        if let Some(policy) = &options.clause_policy {
+            for clause in policy.disallowed(query) {
+                errors.push(format!("{clause} clauses are not allowed by the clause policy"));
+            }
+        }
+
+        // Check procedure calls and their arguments against the allowlist
+        if let Some(allowlist) = &options.procedure_allowlist {
+            errors.extend(allowlist.check(query));
+        }
+
+        // Check the query answers with aggregates only
+        if options.aggregate_only {
+            errors.extend(Self::aggregate_only_errors(query));
+        }
+
+        // Check the query cannot reach other tenants' nodes
+        if let Some(filter) = &options.tenant_filter {
+            errors.extend(filter.check(query));
+        }
+
+        // Run the deployment's own rules
+        for finding in options.rules.check(query) {
+            match finding.severity {
+                Severity::Error => errors.push(finding.message),
+                Severity::Warning => warnings.push(finding.message),
+            }
+        }
+
+        // Check graph algorithm calls against the procedures FalkorDB provides
+        errors.extend(Self::algorithm_errors(query));
+
+        // Check date literals against the question and the stored layouts
+        warnings.extend(Self::date_warnings(query, options));
+
+        // Check for MATCH clause (most queries should have one)
+        // Allow queries that start with other valid statements that don't require MATCH
+        let query_upper = query.to_uppercase();
+        let starts_with_non_match = query_upper.starts_with("CREATE")
+            || query_upper.starts_with("MERGE")
+            || query_upper.starts_with("CALL")
+            || query_upper.starts_with("UNWIND");
+
+        if !patterns.match_clause.is_match(query) && !starts_with_non_match {
+            warnings.push("Query does not contain a MATCH clause".to_string());
+        }
+
+        // Checks that strict mode turns from warnings into errors
+        let mut questionable = Vec::new();
+
+        // Check for RETURN clause
+        if patterns.return_clause.is_match(query) {
+            // Unbounded reads can return the whole graph; aggregates are already bounded
+            if !patterns.limit_clause.is_match(query) && !patterns.aggregate_return.is_match(query) {
+                questionable.push("Query does not contain a LIMIT clause".to_string());
+            }
+        } else {
+            questionable.push("Query does not contain a RETURN clause".to_string());
+        }
+
+        // Check labels, relationship types and properties against the schema
+        let schema_issues = Self::schema_issues(query, options);
+        questionable.extend(schema_issues.iter().map(ToString::to_string));
+
+        // Check variable-length depth
+        if let Some(max_depth) = options.max_var_length {
+            for var_length in Self::var_lengths(&Self::mask_string_literals(query)) {
+                if var_length.upper.is_none_or(|upper| upper > max_depth) {
+                    questionable.push(format!(
+                        "Variable-length pattern {} exceeds the maximum depth of {max_depth}",
+                        var_length.pattern
+                    ));
+                }
+            }
+        }
+
+        if options.strict {
+            errors.extend(questionable);
+        } else {
+            warnings.extend(questionable);
+        }
+
+        // Check relationship directions against the schema: a hop that only exists reversed
+        // returns nothing, so it is an error rather than a warning
+        if let Some(known_relations) = &options.known_relations {
+            let exists = |source: &[String], relationship: &str, target: &[String]| {
+                known_relations.iter().any(|(s, r, t)| {
+                    r == relationship && source.iter().any(|l| l == s) && target.iter().any(|l| l == t)
+                })
+            };
+            for (source, relationship, target) in Self::directed_hops(query) {
+                if !exists(&source, &relationship, &target) && exists(&target, &relationship, &source) {
+                    errors.push(format!(
+                        "Relationship :{relationship} points from (:{}) to (:{}), but the query follows it from (:{}) to (:{}); reverse the arrow",
+                        target.join(":"),
+                        source.join(":"),
+                        source.join(":"),
+                        target.join(":")
+                    ));
+                }
+            }
+        }
+
+        // Parse the query, which catches unbalanced brackets and quotes, unknown clauses and
+        // trailing text before they cost an execution round-trip
+        if let Err(error) = cypher_syntax::check(query) {
+            errors.push(error.to_string());
+        }
+
+        ValidationResult {
+            is_valid: errors.is_empty(),
+            errors,
+            warnings,
+            hints: Self::lint(query),
+            schema_issues,
+        }
+    }
+
+    /// Whether `question` asks about a period relative to today, such as "last quarter", "the past
+    /// 7 days" or "year to date".
+    #[must_use]
+    pub fn mentions_relative_period(question: &str) -> bool {
+        ValidationPatterns::get().relative_period.is_match(question)
+    }
+
+    /// Reports suspicious date literals: any hard-coded date when the question names a relative
+    /// period (the model does not know today's date unless told, and tends to pick the wrong year),
+    /// and dates written in a layout the graph does not store, which compare against nothing.
+    #[must_use]
+    pub fn date_warnings(
+        query: &str,
+        options: &ValidationOptions,
+    ) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for captures in ValidationPatterns::get().string_literal.captures_iter(query) {
+            let Some(literal) = captures.get(1).or_else(|| captures.get(2)).map(|m| m.as_str()) else {
+                continue;
+            };
+            let Some(layout) = date_format(literal) else {
+                continue;
+            };
+            if options.relative_period {
+                warnings.push(format!(
+                    "Query hard-codes the date '{literal}' for a relative period; compute it from date() and duration() instead"
+                ));
+            }
+            if let Some(stored) = &options.date_formats {
+                let matches =
+                    |format: &String| format == layout || (layout.contains(" or ") && layout.contains(format.as_str()));
+                if !stored.iter().any(matches) {
+                    warnings.push(format!(
+                        "Date literal '{literal}' is written as {layout}, but the graph stores dates as {}",
+                        stored.join(" or ")
+                    ));
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Reports style and performance hints: cartesian products, undirected relationships,
+    /// unbounded variable-length paths, and OPTIONAL MATCH that cannot do its job
+    #[must_use]
+    pub fn lint(query: &str) -> Vec<LintHint> {
+        let patterns = ValidationPatterns::get();
+        let masked = Self::mask_string_literals(query);
+        let query = masked.as_str();
+        let mut hints = Vec::new();
+
+        for captures in patterns.relationship.captures_iter(query) {
+            if captures.get(1).is_none() && captures.get(3).is_none() {
+                let pattern = &captures[0];
+                hints.push(LintHint::new(
+                    LintRule::UndirectedRelationship,
+                    format!("Relationship pattern {pattern} has no direction and is matched both ways; add an arrow"),
+                ));
+            }
+        }
+        for var_length in Self::var_lengths(query) {
+            if var_length.upper.is_none() {
+                hints.push(LintHint::new(
+                    LintRule::UnboundedVarLength,
+                    format!(
+                        "Variable-length pattern {} has no upper bound; cap it, e.g. [*1..4]",
+                        var_length.pattern
+                    ),
+                ));
+            }
+        }
+        if patterns.bare_undirected.is_match(query) {
+            hints.push(LintHint::new(
+                LintRule::UndirectedRelationship,
+                "Relationship pattern -- has no direction and is matched both ways; add an arrow",
+            ));
+        }
+
+        Self::lint_match_clauses(query, &mut hints);
+        hints
+    }
+
+    /// Lints the MATCH / OPTIONAL MATCH clauses of the query's first part (up to its first `WITH`,
+    /// which rescopes variables) for cartesian products and OPTIONAL MATCH misuse
+    fn lint_match_clauses(
+        query: &str,
+        hints: &mut Vec<LintHint>,
+    ) {
+        let keywords: Vec<_> = ValidationPatterns::get().clause_keyword.find_iter(query).collect();
+        // Connected groups of non-optional patterns, as the variables each group binds
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        let mut bound: Vec<String> = Vec::new();
+        let mut seen_clause = false;
+
+        for (index, keyword) in keywords.iter().enumerate() {
+            let name = keyword.as_str().to_uppercase();
+            let body = &query[keyword.end()..keywords.get(index + 1).map_or(query.len(), regex::Match::start)];
+            if name == "WITH" || name == "UNION" {
+                break;
+            }
+            if name.starts_with("OPTIONAL") {
+                let variables: Vec<String> = Self::top_level_parts(body)
+                    .iter()
+                    .flat_map(|part| Self::pattern_variables(part))
+                    .collect();
+                if !seen_clause {
+                    hints.push(LintHint::new(
+                        LintRule::OptionalMatchMisuse,
+                        "Query starts with OPTIONAL MATCH, which has nothing to keep rows for; use MATCH",
+                    ));
+                } else if !variables.iter().any(|v| bound.contains(v)) {
+                    hints.push(LintHint::new(
+                        LintRule::OptionalMatchMisuse,
+                        "OPTIONAL MATCH shares no variable with earlier patterns, so it joins every row with every match; connect it to a bound variable",
+                    ));
+                }
+                bound.extend(variables);
+            } else if name == "MATCH" {
+                for part in Self::top_level_parts(body) {
+                    let variables = Self::pattern_variables(part);
+                    // Merge every existing group this pattern shares a variable with
+                    let mut merged = variables.clone();
+                    groups.retain(|group| {
+                        if group.iter().any(|v| variables.contains(v)) {
+                            merged.extend(group.iter().cloned());
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                    groups.push(merged);
+                    bound.extend(variables);
+                }
+            } else if name == "CALL" {
+                // A procedure taking nodes from several groups, such as the source and target of
+                // algo.SPpaths, connects them
+                let arguments: Vec<&str> = ValidationPatterns::get()
+                    .identifier
+                    .find_iter(body)
+                    .map(|identifier| identifier.as_str())
+                    .collect();
+                let mut merged = Vec::new();
+                groups.retain(|group| {
+                    if group.iter().any(|v| arguments.contains(&v.as_str())) {
+                        merged.extend(group.iter().cloned());
+                        false
+                    } else {
+                        true
+                    }
+                });
+                if !merged.is_empty() {
+                    groups.push(merged);
+                }
+            }
+            seen_clause = true;
+        }
+
+        if groups.len() > 1 {
+            hints.push(LintHint::new(
+                LintRule::CartesianProduct,
+                format!(
+                    "MATCH patterns form {} disconnected groups, producing a cartesian product; connect them with a relationship or split the query",
+                    groups.len()
+                ),
+            ));
+        }
+    }
+
+    /// Rewrites variable-length patterns deeper than `max_depth` (including unbounded ones such as
+    /// `[*]` or `[*2..]`) to end at `max_depth`, e.g. `[:KNOWS*]` becomes `[:KNOWS*1..4]`. Lower
+    /// bounds are kept, so an unbounded pattern whose minimum exceeds the cap is pinned to its
+    /// minimum. Returns the rewritten query and one warning per applied cap.
+    #[must_use]
+    pub fn cap_var_length(
+        query: &str,
+        max_depth: u32,
+    ) -> (String, Vec<String>) {
+        let masked = Self::mask_string_literals(query);
+        let mut capped = query.to_string();
+        let mut warnings = Vec::new();
+        // Replace back to front so earlier offsets stay valid
+        for var_length in Self::var_lengths(&masked).into_iter().rev() {
+            let lower = var_length.lower.unwrap_or(1);
+            let upper = max_depth.max(lower);
+            if var_length.upper.is_some_and(|current| current <= upper) {
+                continue;
+            }
+            let spec = format!("*{lower}..{upper}");
+            warnings.push(format!(
+                "Capped variable-length pattern {} to {spec} (maximum depth {max_depth})",
+                var_length.pattern
+            ));
+            capped.replace_range(var_length.spec, &spec);
+        }
+        warnings.reverse();
+        (capped, warnings)
+    }
 
-impl CypherValidator {
-    /// Validates a Cypher query for syntax and safety
-    ///
-    /// # Arguments
-    ///
-    /// * `query` - The Cypher query to validate
+    /// Errors for the parts of `query` that return raw rows rather than aggregates, for graphs
+    /// that may only answer statistical questions. Every `UNION` branch must end in a `RETURN`
+    /// whose items are aggregates (`count`, `sum`, `avg`, `stdev`, `stdevp`), expressions over
+    /// them, or `WITH` aliases bound to them. `collect`, `min`, `max`, `percentileCont` and
+    /// `percentileDisc` are not aggregates here, as they return (or interpolate between) the values
+    /// they gather, and grouping keys are rejected, as each names a value of the rows it groups.
     ///
-    /// # Returns
-    ///
-    /// A `ValidationResult` containing validation status and any errors/warnings
+    /// Aggregates must also summarize at least [`MIN_AGGREGATE_GROUP`] rows, or an average over a
+    /// single record is that record's value: they are computed in a `WITH` that binds a row count
+    /// and requires it in its `WHERE`, such as
+    /// `WITH count(*) AS rows, avg(p.age) AS mean_age WHERE rows >= 10 RETURN rows, mean_age`.
     #[must_use]
-    pub fn validate(query: &str) -> ValidationResult {
+    pub fn aggregate_only_errors(query: &str) -> Vec<String> {
+        let patterns = ValidationPatterns::get();
+        let masked = Self::mask_string_literals(query);
         let mut errors = Vec::new();
-        let mut warnings = Vec::new();
+        let mut aggregates = Vec::new();
+        // Aliases of aggregates computed without the minimum group size
+        let mut unguarded = Vec::new();
+        let mut last_projection = None;
+        let clauses = Self::clauses(&masked);
+        for (index, (keyword, body)) in clauses.iter().enumerate() {
+            match keyword.as_str() {
+                "WITH" | "RETURN" => {
+                    let body = masked[body.clone()].trim();
+                    let mut item_errors = Vec::new();
+                    if body.trim_start().starts_with('*') {
+                        item_errors.push("RETURN * returns raw rows".to_string());
+                    }
+                    let items: Vec<_> = Self::top_level_parts(body)
+                        .into_iter()
+                        .filter(|item| item.trim() != "*")
+                        .map(|item| {
+                            patterns.projection_alias.captures(item).map_or((item, item, None), |captures| {
+                                (
+                                    item,
+                                    captures.get(1).map_or(item, |m| m.as_str()),
+                                    captures.get(2).map(|alias| alias.as_str().trim_matches('`')),
+                                )
+                            })
+                        })
+                        .collect();
+                    let guarded = keyword == "WITH" && Self::requires_min_group(&items, &clauses[index + 1..], &masked);
+                    for (item, expression, alias) in items {
+                        let error = if patterns.value_aggregate_call.is_match(expression) {
+                            Some("returns single rows' values")
+                        } else {
+                            Self::aggregate_expression_error(expression, &aggregates)
+                        };
+                        // An alias of an unguarded aggregate stays unguarded whatever counts the rows later
+                        let summarizes_few = (!guarded && patterns.aggregate_call.is_match(expression))
+                            || patterns
+                                .identifier
+                                .find_iter(expression)
+                                .any(|identifier| unguarded.iter().any(|alias| alias == identifier.as_str()));
+                        match error {
+                            Some(error) => item_errors.push(format!("'{}' {error}", item.trim())),
+                            None if summarizes_few => {
+                                item_errors.push(format!(
+                                    "'{}' may summarize fewer than {MIN_AGGREGATE_GROUP} rows; compute it in a WITH that \
+                                     binds count(*) to an alias and requires it to be at least {MIN_AGGREGATE_GROUP} \
+                                     in its WHERE",
+                                    item.trim()
+                                ));
+                                unguarded.extend(alias.map(ToString::to_string));
+                                aggregates.extend(alias.map(ToString::to_string));
+                            }
+                            None => aggregates.extend(alias.map(ToString::to_string)),
+                        }
+                    }
+                    last_projection = Some((keyword.as_str(), item_errors));
+                }
+                "ORDER BY" | "SKIP" | "LIMIT" | "WHERE" | "UNION" => {}
+                _ => last_projection = None,
+            }
+            if keyword == "UNION" || index + 1 == clauses.len() {
+                match last_projection.take() {
+                    Some(("RETURN", item_errors)) => errors.extend(
+                        item_errors
+                            .into_iter()
+                            .map(|error| format!("{error}; the graph only answers with aggregates")),
+                    ),
+                    _ => errors.push(
+                        "The query must end in a RETURN of aggregates such as count() or avg(); the graph only answers with aggregates"
+                            .to_string(),
+                    ),
+                }
+                aggregates.clear();
+                unguarded.clear();
+            }
+        }
+        if clauses.is_empty() {
+            errors.push("The query must end in a RETURN of aggregates such as count() or avg()".to_string());
+        }
+        errors
+    }
 
-        let query = query.trim();
+    /// Whether a WITH requires at least [`MIN_AGGREGATE_GROUP`] rows: one of its `items` (text,
+    /// expression and alias) binds a row count, and the WHERE among the clauses `after` it requires
+    /// that count to be at least the minimum, in a conjunction without OR or NOT
+    fn requires_min_group(
+        items: &[(&str, &str, Option<&str>)],
+        after: &[(String, std::ops::Range<usize>)],
+        masked: &str,
+    ) -> bool {
         let patterns = ValidationPatterns::get();
+        let counts: Vec<_> = items
+            .iter()
+            .filter(|(_, expression, _)| patterns.row_count.is_match(expression))
+            .filter_map(|(_, _, alias)| *alias)
+            .collect();
+        let Some((_, condition)) = after
+            .iter()
+            .take_while(|(keyword, _)| matches!(keyword.as_str(), "ORDER BY" | "SKIP" | "LIMIT" | "WHERE"))
+            .find(|(keyword, _)| keyword == "WHERE")
+        else {
+            return false;
+        };
+        let condition = &masked[condition.clone()];
+        if patterns
+            .boolean_operator
+            .find_iter(condition)
+            .any(|operator| !operator.as_str().eq_ignore_ascii_case("AND"))
+        {
+            return false;
+        }
+        patterns
+            .boolean_operator
+            .split(condition)
+            .filter_map(|conjunct| patterns.lower_bound.captures(conjunct))
+            .any(|bound| {
+                let minimum = bound[3].parse::<u64>().unwrap_or(0) + u64::from(&bound[2] == ">");
+                counts.contains(&bound[1].trim_matches('`')) && minimum >= MIN_AGGREGATE_GROUP
+            })
+    }
 
-        // Check if query is empty
-        if query.is_empty() {
-            errors.push("Query is empty".to_string());
-            return ValidationResult {
-                is_valid: false,
-                errors,
-                warnings,
+    /// Why a masked projection expression is not an aggregate, or `None` when it is: it calls an
+    /// aggregate function or names an alias in `aggregates`, and reads no variable outside them
+    fn aggregate_expression_error(
+        expression: &str,
+        aggregates: &[String],
+    ) -> Option<&'static str> {
+        const OPERATORS: &[&str] = &[
+            "AND", "OR", "XOR", "NOT", "NULL", "TRUE", "FALSE", "CASE", "WHEN", "THEN", "ELSE", "END", "IN", "IS",
+            "STARTS", "ENDS", "WITH", "CONTAINS", "DISTINCT",
+        ];
+        let patterns = ValidationPatterns::get();
+        // Blank out each aggregate call, arguments included
+        let mut remainder = expression.to_string();
+        let mut summarized = false;
+        while let Some(call) = patterns.aggregate_call.find(&remainder) {
+            let mut depth = 0i32;
+            let end = remainder[call.end() - 1..]
+                .char_indices()
+                .find_map(|(i, c)| {
+                    match c {
+                        '(' | '[' | '{' => depth += 1,
+                        ')' | ']' | '}' => depth -= 1,
+                        _ => {}
+                    }
+                    (depth == 0).then_some(call.end() + i)
+                })
+                .unwrap_or(remainder.len());
+            remainder.replace_range(call.start()..end, &" ".repeat(end - call.start()));
+            summarized = true;
+        }
+        for identifier in patterns.identifier.find_iter(&remainder) {
+            let before = remainder[..identifier.start()].trim_end();
+            let after = remainder[identifier.end()..].trim_start();
+            // Function names, property keys, map keys, parameters and operators read no variable
+            if after.starts_with('(')
+                || after.starts_with(':')
+                || before.ends_with('.')
+                || before.ends_with('$')
+                || OPERATORS.contains(&identifier.as_str().to_uppercase().as_str())
+            {
+                continue;
+            }
+            if aggregates.iter().any(|alias| alias == identifier.as_str()) {
+                summarized = true;
+            } else {
+                return Some("returns raw values");
+            }
+        }
+        (!summarized).then_some("is not an aggregate")
+    }
+
+    /// The MATCH patterns with nothing to start from: no node with a label, a property map, or a
+    /// variable named earlier in the query, so matching them reads every node
+    fn unanchored_patterns(query: &str) -> Vec<String> {
+        let patterns = ValidationPatterns::get();
+        let keywords: Vec<_> = patterns.clause_keyword.find_iter(query).collect();
+        let mut unanchored = Vec::new();
+        for (index, keyword) in keywords.iter().enumerate() {
+            if !keyword.as_str().to_uppercase().ends_with("MATCH") {
+                continue;
+            }
+            let body = &query[keyword.end()..keywords.get(index + 1).map_or(query.len(), regex::Match::start)];
+            let mut bound: Vec<&str> = patterns
+                .identifier
+                .find_iter(&query[..keyword.start()])
+                .map(|identifier| identifier.as_str())
+                .collect();
+            for part in Self::top_level_parts(body) {
+                let nodes: Vec<_> = patterns.node.captures_iter(part).collect();
+                let anchored = nodes.iter().any(|node| {
+                    !node[2].trim().is_empty()
+                        || node.get(3).is_some()
+                        || node.get(1).is_some_and(|variable| bound.contains(&variable.as_str()))
+                });
+                if !nodes.is_empty() && !anchored {
+                    unanchored.push(part.trim().to_string());
+                }
+                bound.extend(patterns.identifier.find_iter(part).map(|identifier| identifier.as_str()));
+            }
+        }
+        unanchored
+    }
+
+    /// Finds the variable-length specs (`*`, `*2`, `*1..`, `*..3`, `*1..3`) in relationship patterns
+    fn var_lengths(query: &str) -> Vec<VarLength> {
+        let patterns = ValidationPatterns::get();
+        let mut found = Vec::new();
+        for captures in patterns.relationship.captures_iter(query) {
+            let body = captures.get(2).expect("relationship pattern has a body");
+            let Some(spec) = patterns.var_length.captures(body.as_str()) else {
+                continue;
             };
+            let whole = spec.get(0).expect("group 0 always participates");
+            let start = body.start() + whole.start();
+            let bound = |group: usize| spec.get(group).and_then(|digits| digits.as_str().parse::<u32>().ok());
+            let (lower, upper) = if spec.get(2).is_some() {
+                (bound(1), bound(3))
+            } else {
+                (bound(1), bound(1))
+            };
+            found.push(VarLength {
+                pattern: captures[0].to_string(),
+                spec: start..start + whole.as_str().trim_end().len(),
+                lower,
+                upper,
+            });
         }
+        found
+    }
 
-        // Check if query contains basic Cypher keywords
-        if !patterns.basic_cypher.is_match(query) {
-            errors.push("Query does not contain valid Cypher keywords".to_string());
+    /// Blanks out the contents of quoted string literals (keeping byte offsets), so keywords and
+    /// punctuation inside values are not mistaken for query structure
+    pub(crate) fn mask_string_literals(query: &str) -> String {
+        let mut masked = String::with_capacity(query.len());
+        let mut quote: Option<char> = None;
+        let mut escaped = false;
+        for c in query.chars() {
+            match quote {
+                Some(q) if !escaped && c == q => {
+                    quote = None;
+                    masked.push(c);
+                }
+                Some(_) => {
+                    escaped = !escaped && c == '\\';
+                    masked.extend(std::iter::repeat_n(' ', c.len_utf8()));
+                }
+                None => {
+                    if c == '\'' || c == '"' {
+                        quote = Some(c);
+                    }
+                    masked.push(c);
+                }
+            }
         }
+        masked
+    }
 
-        // Check for dangerous operations
-        if patterns.dangerous_ops.is_match(query) {
-            errors.push("Query contains potentially dangerous operations (DROP, DELETE ALL)".to_string());
+    /// The clauses of a masked query: each keyword, upper-cased with single spaces (`OPTIONAL
+    /// MATCH`), and the byte range of its body, up to the next keyword
+    pub(crate) fn clauses(query: &str) -> Vec<(String, std::ops::Range<usize>)> {
+        let keywords: Vec<_> = ValidationPatterns::get().clause_keyword.find_iter(query).collect();
+        keywords
+            .iter()
+            .enumerate()
+            .map(|(index, keyword)| {
+                let name = keyword.as_str().split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+                let end = keywords.get(index + 1).map_or(query.len(), regex::Match::start);
+                (name, keyword.end()..end)
+            })
+            .collect()
+    }
+
+    /// Splits a clause body at commas outside parentheses, brackets, braces, and backticks
+    fn top_level_parts(body: &str) -> Vec<&str> {
+        Self::top_level_ranges(body).into_iter().map(|range| &body[range]).collect()
+    }
+
+    /// Byte ranges of the non-blank parts [`Self::top_level_parts`] returns
+    fn top_level_ranges(body: &str) -> Vec<std::ops::Range<usize>> {
+        let mut parts = Vec::new();
+        let mut depth = 0i32;
+        let mut quote: Option<char> = None;
+        let mut start = 0;
+        for (i, c) in body.char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (None, '`') => quote = Some(c),
+                (None, '(' | '[' | '{') => depth += 1,
+                (None, ')' | ']' | '}') => depth -= 1,
+                (None, ',') if depth == 0 => {
+                    parts.push(start..i);
+                    start = i + 1;
+                }
+                _ => {}
+            }
         }
+        parts.push(start..body.len());
+        parts.retain(|part| !body[part.clone()].trim().is_empty());
+        parts
+    }
 
-        // Check for MATCH clause (most queries should have one)
-        // Allow queries that start with other valid statements that don't require MATCH
-        let query_upper = query.to_uppercase();
-        let starts_with_non_match = query_upper.starts_with("CREATE")
-            || query_upper.starts_with("MERGE")
-            || query_upper.starts_with("CALL")
-            || query_upper.starts_with("UNWIND");
+    /// The procedure calls in `query`: each procedure's name and its arguments as written, or
+    /// `None` for a call without parentheses. `CALL { ... }` subqueries are not procedure calls.
+    pub(crate) fn procedure_calls(query: &str) -> Vec<(String, Option<Vec<String>>)> {
+        let masked = Self::mask_string_literals(query);
+        let mut calls = Vec::new();
+        for captures in ValidationPatterns::get().procedure_call.captures_iter(&masked) {
+            let name: String = captures[1].split_whitespace().collect();
+            let Some(open) = captures.get(2) else {
+                calls.push((name, None));
+                continue;
+            };
+            let mut depth = 0i32;
+            let close = masked[open.start()..].char_indices().find_map(|(i, c)| {
+                match c {
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth -= 1,
+                    _ => {}
+                }
+                (depth == 0).then_some(open.start() + i)
+            });
+            // An unclosed call is reported as unbalanced parentheses; its arguments are unknown.
+            let Some(close) = close else {
+                calls.push((name, Some(Vec::new())));
+                continue;
+            };
+            let body = open.end()..close;
+            let arguments = Self::top_level_ranges(&masked[body.clone()])
+                .into_iter()
+                .map(|range| query[body.start + range.start..body.start + range.end].trim().to_string())
+                .collect();
+            calls.push((name, Some(arguments)));
+        }
+        calls
+    }
 
-        if !patterns.match_clause.is_match(query) && !starts_with_non_match {
-            warnings.push("Query does not contain a MATCH clause".to_string());
+    /// Errors for graph algorithm calls `FalkorDB` cannot run: Neo4j GDS and APOC procedures, unknown
+    /// `algo.*` procedures, and path procedures missing their source or target node.
+    fn algorithm_errors(query: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (procedure, arguments) in Self::procedure_calls(query) {
+            let lower = procedure.to_lowercase();
+            if lower.starts_with("gds.") || lower.starts_with("apoc.") {
+                errors.push(format!(
+                    "{procedure} is a Neo4j procedure that FalkorDB does not provide; use an algo.* procedure"
+                ));
+                continue;
+            }
+            if !lower.starts_with("algo.") {
+                continue;
+            }
+            let Some((name, required)) = ALGORITHM_PROCEDURES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&procedure))
+            else {
+                let known: Vec<&str> = ALGORITHM_PROCEDURES.iter().map(|(name, _)| *name).collect();
+                errors.push(format!(
+                    "Unknown graph algorithm procedure {procedure}; FalkorDB provides {}",
+                    known.join(", ")
+                ));
+                continue;
+            };
+            let config = arguments.as_deref().and_then(<[String]>::first);
+            let missing: Vec<&str> = required
+                .iter()
+                .copied()
+                .filter(|key| config.is_none_or(|config| map_entry(config, key).is_none()))
+                .collect();
+            if !missing.is_empty() && !config.is_some_and(|config| config.starts_with('$')) {
+                errors.push(format!(
+                    "{name} requires {} in its configuration map",
+                    missing.join(" and ")
+                ));
+            }
         }
+        errors
+    }
 
-        // Check for RETURN clause
-        if !patterns.return_clause.is_match(query) {
-            warnings.push("Query does not contain a RETURN clause".to_string());
+    /// Returns the variables a single pattern binds. An anonymous pattern gets a unique placeholder
+    /// so it is never considered connected to anything.
+    fn pattern_variables(pattern: &str) -> Vec<String> {
+        let variables: Vec<String> = ValidationPatterns::get()
+            .pattern_variable
+            .captures_iter(pattern)
+            .map(|captures| captures[1].to_string())
+            .collect();
+        if variables.is_empty() {
+            vec![format!("<anonymous {}>", pattern.trim())]
+        } else {
+            variables
         }
+    }
 
-        // Check for balanced parentheses
-        if !Self::check_balanced_parentheses(query) {
-            errors.push("Unbalanced parentheses in query".to_string());
+    /// Classifies a query as reading or modifying the graph
+    #[must_use]
+    pub fn query_mode(query: &str) -> QueryMode {
+        if ValidationPatterns::get().write_clause.is_match(query) {
+            QueryMode::Write
+        } else {
+            QueryMode::Read
         }
+    }
+
+    /// Returns `(source labels, relationship type, target labels)` for each directed hop whose
+    /// endpoints are labeled and whose relationship has a single type, normalized so the source is
+    /// where the arrow starts
+    fn directed_hops(query: &str) -> Vec<(Vec<String>, String, Vec<String>)> {
+        let split_labels = |chain: &str| -> Vec<String> {
+            chain
+                .split(':')
+                .map(|label| label.trim().trim_matches('`'))
+                .filter(|label| !label.is_empty())
+                .map(ToString::to_string)
+                .collect()
+        };
 
-        // Check for balanced brackets
-        if !Self::check_balanced_brackets(query) {
-            errors.push("Unbalanced brackets in query".to_string());
+        let hop = &ValidationPatterns::get().hop;
+        let mut hops = Vec::new();
+        let mut position = 0;
+        // Consecutive hops share a node, so each search restarts at the previous hop's target.
+        while let Some(captures) = hop.captures_at(query, position) {
+            position = captures.get(5).map_or(query.len(), |target| target.start());
+
+            let incoming = captures.get(2).is_some();
+            let outgoing = captures.get(4).is_some();
+            let relationship = captures[3].split_once(':').map_or("", |(_, types)| {
+                types
+                    .split(|c: char| c.is_whitespace() || c == '*' || c == '{')
+                    .next()
+                    .unwrap_or("")
+            });
+            let source = split_labels(&captures[1]);
+            let target = split_labels(&captures[6]);
+            if incoming == outgoing
+                || relationship.is_empty()
+                || relationship.contains('|')
+                || source.is_empty()
+                || target.is_empty()
+            {
+                continue;
+            }
+
+            let relationship = relationship.trim_matches('`').to_string();
+            if outgoing {
+                hops.push((source, relationship, target));
+            } else {
+                hops.push((target, relationship, source));
+            }
         }
+        hops
+    }
 
-        ValidationResult {
-            is_valid: errors.is_empty(),
-            errors,
-            warnings,
+    /// The labels, relationship types and properties `query` uses that `options` does not know.
+    /// Write queries may create new relationship types and properties, so only their labels are
+    /// checked.
+    fn schema_issues(
+        query: &str,
+        options: &ValidationOptions,
+    ) -> Vec<SchemaIssue> {
+        let mut issues = Vec::new();
+        if let Some(known_labels) = &options.known_labels {
+            for label in Self::referenced_labels(query) {
+                if !known_labels.contains(&label) {
+                    let known = known_labels.iter().map(String::as_str);
+                    issues.push(SchemaIssue::new(SchemaElement::Label, &label, Vec::new(), known));
+                }
+            }
+        }
+        if Self::query_mode(query) == QueryMode::Write {
+            return issues;
+        }
+
+        let masked = Self::mask_string_literals(query);
+        if let Some(known_types) = &options.known_relationship_types {
+            for relationship_type in Self::referenced_relationship_types(&masked) {
+                if !known_types.contains(&relationship_type) {
+                    let known = known_types.iter().map(String::as_str);
+                    issues.push(SchemaIssue::new(
+                        SchemaElement::RelationshipType,
+                        &relationship_type,
+                        Vec::new(),
+                        known,
+                    ));
+                }
+            }
+        }
+        if let Some(known_properties) = &options.known_properties {
+            for (owners, property) in Self::referenced_properties(&masked) {
+                let properties: Vec<&str> = owners
+                    .iter()
+                    .filter_map(|owner| known_properties.get(owner))
+                    .flatten()
+                    .map(String::as_str)
+                    .collect();
+                let reported = issues.iter().any(|issue| issue.name == property && issue.owners == owners);
+                if !properties.is_empty() && !properties.contains(&property.as_str()) && !reported {
+                    issues.push(SchemaIssue::new(SchemaElement::Property, &property, owners, properties));
+                }
+            }
         }
+        issues
     }
 
-    /// Checks if parentheses are balanced in the query
-    fn check_balanced_parentheses(query: &str) -> bool {
-        let mut count = 0;
-        for c in query.chars() {
-            match c {
-                '(' => count += 1,
-                ')' => {
-                    count -= 1;
-                    if count < 0 {
-                        return false;
-                    }
+    /// Returns the distinct relationship types referenced in relationship patterns of a
+    /// (string-masked) query, without colons or backticks
+    pub(crate) fn referenced_relationship_types(masked: &str) -> Vec<String> {
+        let mut types: Vec<String> = Vec::new();
+        for captures in ValidationPatterns::get().relationship.captures_iter(masked) {
+            for relationship_type in Self::relationship_types(&captures[2]).1 {
+                if !types.contains(&relationship_type) {
+                    types.push(relationship_type);
                 }
-                _ => {}
             }
         }
-        count == 0
+        types
     }
 
-    /// Checks if brackets are balanced in the query
-    fn check_balanced_brackets(query: &str) -> bool {
-        let mut count = 0;
-        for c in query.chars() {
-            match c {
-                '[' => count += 1,
-                ']' => {
-                    count -= 1;
-                    if count < 0 {
-                        return false;
-                    }
+    /// Splits a relationship body such as `r:ACTED_IN|DIRECTED*1..2 {role: 'x'}` into its variable
+    /// and its types, without colons or backticks
+    fn relationship_types(body: &str) -> (Option<&str>, Vec<String>) {
+        let head = body.split(['*', '{']).next().unwrap_or_default();
+        let (variable, types) = head.split_once(':').unwrap_or((head, ""));
+        let variable = Some(variable.trim()).filter(|variable| !variable.is_empty());
+        let types = types
+            .split('|')
+            .map(|relationship_type| relationship_type.trim().trim_start_matches(':').trim().trim_matches('`'))
+            .filter(|relationship_type| !relationship_type.is_empty())
+            .map(ToString::to_string)
+            .collect();
+        (variable, types)
+    }
+
+    /// Returns the properties a (string-masked) query reads from labeled variables, and written in
+    /// inline maps of labeled patterns, each with the labels or relationship types it belongs to.
+    /// Properties of unlabeled variables cannot be checked and are left out.
+    fn referenced_properties<'a>(masked: &'a str) -> Vec<(Vec<String>, String)> {
+        let patterns = ValidationPatterns::get();
+        let mut bindings: HashMap<&str, Vec<String>> = HashMap::new();
+        let bind = |bindings: &mut HashMap<&'a str, Vec<String>>, variable: &'a str, owners: Vec<String>| {
+            let bound = bindings.entry(variable).or_default();
+            for owner in owners {
+                if !bound.contains(&owner) {
+                    bound.push(owner);
+                }
+            }
+        };
+        let mut properties = Vec::new();
+        let mut map_keys = |owners: &[String], map: &str| {
+            let body = map.trim().trim_start_matches('{').trim_end_matches('}');
+            for entry in Self::top_level_parts(body) {
+                if let Some((key, _)) = entry.split_once(':') {
+                    properties.push((owners.to_vec(), key.trim().trim_matches('`').to_string()));
+                }
+            }
+        };
+
+        for captures in patterns.labeled_node.captures_iter(masked) {
+            let labels: Vec<String> = captures[2]
+                .split(':')
+                .map(|label| label.trim().trim_matches('`'))
+                .filter(|label| !label.is_empty())
+                .map(ToString::to_string)
+                .collect();
+            if let Some(map) = captures.get(3) {
+                map_keys(&labels, map.as_str());
+            }
+            if let Some(variable) = captures.get(1) {
+                bind(&mut bindings, variable.as_str(), labels);
+            }
+        }
+        for captures in patterns.relationship.captures_iter(masked) {
+            let body = captures.get(2).map_or("", |body| body.as_str());
+            let (variable, types) = Self::relationship_types(body);
+            if types.is_empty() {
+                continue;
+            }
+            if let Some(open) = body.find('{') {
+                map_keys(&types, &body[open..]);
+            }
+            if let Some(variable) = variable {
+                bind(&mut bindings, variable, types);
+            }
+        }
+
+        for captures in patterns.property_access.captures_iter(masked) {
+            if captures.get(3).is_some() {
+                continue;
+            }
+            if let Some(owners) = bindings.get(&captures[1]) {
+                properties.push((owners.clone(), captures[2].trim_matches('`').to_string()));
+            }
+        }
+        properties
+    }
+
+    /// Returns the distinct node labels referenced in node patterns, without backticks
+    pub(crate) fn referenced_labels(query: &str) -> Vec<String> {
+        let mut labels: Vec<String> = Vec::new();
+        for captures in ValidationPatterns::get().node_labels.captures_iter(query) {
+            for label in captures[1].split(':') {
+                let label = label.trim().trim_matches('`');
+                if !label.is_empty() && !labels.iter().any(|l| l == label) {
+                    labels.push(label.to_string());
                 }
-                _ => {}
             }
         }
-        count == 0
+        labels
     }
 
     /// Suggests fixes for common query errors
@@ -220,6 +2372,9 @@ impl CypherValidator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schema::attribute::{Attribute, AttributeType};
+    use crate::schema::entity::Entity;
+    use crate::schema::relation::Relation;
 
     #[test]
     fn test_valid_query() {
@@ -252,11 +2407,636 @@ mod tests {
     }
 
     #[test]
-    fn test_balanced_parentheses() {
-        assert!(CypherValidator::check_balanced_parentheses("()"));
-        assert!(CypherValidator::check_balanced_parentheses("(())"));
-        assert!(CypherValidator::check_balanced_parentheses("(()())"));
-        assert!(!CypherValidator::check_balanced_parentheses("(()"));
-        assert!(!CypherValidator::check_balanced_parentheses("())"));
+    fn test_syntax_errors() {
+        for query in [
+            "MATCH (n:Person)) RETURN n LIMIT 1",
+            "MATCH (n:Person) RETURN n.tags[0 LIMIT 1",
+            "MATCH (n:Person) WHERE n.name = 'John RETURN n LIMIT 1",
+            "MATCH (n:Person) RETURNS n LIMIT 1",
+            "MATCH (n:Person) RETURN n LIMIT 1 garbage",
+        ] {
+            let result = CypherValidator::validate(query);
+            assert!(
+                result.errors.iter().any(|e| e.starts_with("Syntax error")),
+                "{query}: {:?}",
+                result.errors
+            );
+        }
+        // Brackets inside strings are not structure
+        assert!(CypherValidator::validate("MATCH (n:Person) WHERE n.name = 'a)' RETURN n LIMIT 1").is_valid);
+    }
+
+    #[test]
+    fn test_parameter_preamble_is_valid() {
+        let parameters = serde_json::json!({"name": "Tom Hanks", "min_rating": 7.5, "ids": [1, 2]});
+        let query = crate::core::declare_parameters(
+            "MATCH (p:Person {name: $name})-[:RATED]->(m:Movie) WHERE m.rating >= $min_rating AND id(m) IN $ids \
+             RETURN m.title LIMIT 10",
+            parameters.as_object().unwrap(),
+        )
+        .unwrap();
+        assert!(query.starts_with("CYPHER "), "{query}");
+        let options = ValidationOptions {
+            strict: true,
+            ..ValidationOptions::default()
+        };
+        let result = CypherValidator::validate_with_options(&query, &options);
+        assert!(result.is_valid, "{query}: {:?}", result.errors);
+        assert!(
+            CypherValidator::validate("CYPHER threshold=.5 MATCH (n) WHERE n.score > $threshold RETURN n LIMIT 1")
+                .is_valid
+        );
+    }
+
+    #[test]
+    fn test_strict_turns_warnings_into_errors() {
+        let query = "MATCH (n:Person) RETURN n";
+        let lenient = CypherValidator::validate(query);
+        assert!(lenient.is_valid);
+        assert!(lenient.warnings.iter().any(|w| w.contains("LIMIT")));
+
+        let strict = CypherValidator::validate_with_options(query, &ValidationOptions::strict(true));
+        assert!(!strict.is_valid);
+        assert!(strict.errors.iter().any(|e| e.contains("LIMIT")));
+
+        let no_return =
+            CypherValidator::validate_with_options("MATCH (n) SET n.x = 1", &ValidationOptions::strict(true));
+        assert!(no_return.errors.iter().any(|e| e.contains("RETURN")));
+
+        let bounded = "MATCH (n:Person) RETURN n LIMIT 10";
+        assert!(CypherValidator::validate_with_options(bounded, &ValidationOptions::strict(true)).is_valid);
+        let aggregate = "MATCH (n:Person) RETURN count(n)";
+        assert!(CypherValidator::validate_with_options(aggregate, &ValidationOptions::strict(true)).is_valid);
+    }
+
+    #[test]
+    fn test_unknown_labels() {
+        let schema = Schema {
+            entities: vec![
+                Entity::new("Person".into(), Vec::new(), None),
+                Entity::new("Big Company".into(), Vec::new(), None),
+            ],
+            relations: Vec::new(),
+            label_combinations: Vec::new(),
+            indexes: Vec::new(),
+            constraints: Vec::new(),
+        };
+        let options = ValidationOptions::strict(true).with_schema(&serde_json::to_string(&schema).unwrap());
+        assert_eq!(
+            options.known_labels,
+            Some(vec!["Person".to_string(), "Big Company".to_string()])
+        );
+
+        let known = "MATCH (p:Person)-[:WORKS_AT]->(:`Big Company`) RETURN p LIMIT 5";
+        assert!(CypherValidator::validate_with_options(known, &options).is_valid);
+
+        let unknown = "MATCH (p:Person:Actor {name: 'x'}) RETURN p LIMIT 5";
+        let result = CypherValidator::validate_with_options(unknown, &options);
+        assert_eq!(result.errors, vec!["Query uses unknown label 'Actor'".to_string()]);
+
+        // Unparseable schemas disable the check rather than rejecting every label
+        assert_eq!(ValidationOptions::default().with_schema("{}").known_labels, None);
+    }
+
+    #[test]
+    fn test_schema_issues() {
+        let attribute = |name: &str| Attribute::new(name.into(), AttributeType::String, 1, false, false);
+        let schema = Schema {
+            entities: vec![
+                Entity::new("Person".into(), vec![attribute("name"), attribute("born")], None),
+                Entity::new("Movie".into(), vec![attribute("title")], None),
+            ],
+            relations: vec![Relation::new(
+                "ACTED_IN".into(),
+                "Person".into(),
+                "Movie".into(),
+                vec![attribute("roles")],
+            )],
+            label_combinations: Vec::new(),
+            indexes: Vec::new(),
+            constraints: Vec::new(),
+        };
+        let options = ValidationOptions::strict(true).with_schema(&serde_json::to_string(&schema).unwrap());
+
+        let known = "MATCH (p:Person {name: 'x'})-[r:ACTED_IN]->(m:Movie) WHERE date.truncate('day', p.born) IS NOT NULL RETURN m.title, r.roles, p.name + '.nme' LIMIT 5";
+        let result = CypherValidator::validate_with_options(known, &options);
+        assert!(result.is_valid, "{:?}", result.errors);
+
+        let misspelled = "MATCH (p:Persn)-[r:ACTEDIN]->(m:Movie {tite: 'x'}) RETURN m.title, r.role LIMIT 5";
+        let result = CypherValidator::validate_with_options(misspelled, &options);
+        assert_eq!(
+            result.errors,
+            vec![
+                "Query uses unknown label 'Persn' Did you mean 'Person'?",
+                "Query uses unknown relationship type 'ACTEDIN' Did you mean 'ACTED_IN'?",
+                "Query uses unknown property 'tite' of Movie Did you mean 'title'?",
+            ]
+        );
+        assert_eq!(result.schema_issues[2].element, SchemaElement::Property);
+        assert_eq!(result.schema_issues[2].owners, vec!["Movie".to_string()]);
+
+        let result = CypherValidator::validate_with_options(
+            "MATCH (p:Person)-[r:ACTED_IN]->(m:Movie) RETURN p.nam, r.role LIMIT 5",
+            &options,
+        );
+        let messages: Vec<String> = result.schema_issues.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Query uses unknown property 'nam' of Person Did you mean 'name'?",
+                "Query uses unknown property 'role' of ACTED_IN Did you mean 'roles'?",
+            ]
+        );
+
+        // Write queries may add properties and relationship types
+        let write = "MATCH (p:Person) MERGE (p)-[:LIKES]->(m:Movie) SET m.rating = 5";
+        assert!(CypherValidator::validate_with_options(write, &options).schema_issues.is_empty());
+    }
+
+    #[test]
+    fn test_allow_destructive_permits_delete_but_not_drop() {
+        let delete = "MATCH (n:Orphan) WHERE NOT (n)--() DETACH DELETE n";
+        assert!(!CypherValidator::validate(delete).is_valid);
+        let options = ValidationOptions::default().with_allow_destructive(true);
+        assert!(CypherValidator::validate_with_options(delete, &options).is_valid);
+        assert!(!CypherValidator::validate_with_options("DROP INDEX ON :Person(name)", &options).is_valid);
+    }
+
+    #[test]
+    fn test_query_mode() {
+        assert_eq!(
+            CypherValidator::query_mode("MATCH (n) RETURN n LIMIT 1"),
+            QueryMode::Read
+        );
+        assert_eq!(
+            CypherValidator::query_mode("MATCH (n) DETACH DELETE n"),
+            QueryMode::Write
+        );
+        assert_eq!(
+            CypherValidator::query_mode("MATCH (n) SET n.seen = true"),
+            QueryMode::Write
+        );
+    }
+
+    #[test]
+    fn test_relationship_direction() {
+        let mut acted_in = Relation::new("ACTED_IN".into(), "Person".into(), "Movie".into(), Vec::new());
+        acted_in.target_labels = Vec::new();
+        let schema = Schema {
+            entities: vec![
+                Entity::new("Person".into(), Vec::new(), None),
+                Entity::new("Movie".into(), Vec::new(), None),
+            ],
+            relations: vec![
+                acted_in,
+                Relation::new("KNOWS".into(), "Person".into(), "Person".into(), Vec::new()),
+            ],
+            label_combinations: Vec::new(),
+            indexes: Vec::new(),
+            constraints: Vec::new(),
+        };
+        let options = ValidationOptions::default().with_schema(&serde_json::to_string(&schema).unwrap());
+
+        for valid in [
+            "MATCH (p:Person)-[:ACTED_IN]->(m:Movie) RETURN p.name LIMIT 5",
+            "MATCH (m:Movie)<-[r:ACTED_IN]-(p:Person) RETURN p.name LIMIT 5",
+            "MATCH (m:Movie)-[:ACTED_IN]-(p:Person) RETURN p.name LIMIT 5",
+            "MATCH (a:Person)-[:KNOWS]->(b:Person)-[:ACTED_IN*1..2]->(m:Movie) RETURN m LIMIT 5",
+            "MATCH (m)-[:ACTED_IN]->(p:Person) RETURN m LIMIT 5",
+        ] {
+            let result = CypherValidator::validate_with_options(valid, &options);
+            assert!(result.is_valid, "{valid}: {:?}", result.errors);
+        }
+
+        let reversed = "MATCH (p:Person)-[:KNOWS]->(f:Person)<-[:ACTED_IN]-(m:Movie) RETURN f LIMIT 5";
+        let result = CypherValidator::validate_with_options(reversed, &options);
+        assert!(!result.is_valid);
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].contains("reverse the arrow"), "{:?}", result.errors);
+    }
+
+    #[test]
+    fn test_lint_hints() {
+        let rules = |query: &str| -> Vec<LintRule> { CypherValidator::lint(query).iter().map(|h| h.rule).collect() };
+
+        for clean in [
+            "MATCH (p:Person)-[:KNOWS]->(f:Person) RETURN f LIMIT 5",
+            "MATCH (a:Person {name: 'Ann, Set'}), (a)-[:LIKES]->(m) RETURN m LIMIT 5",
+            "MATCH p = (a)-[:KNOWS*1..3]->(b) RETURN p LIMIT 5",
+            "MATCH (a)-[:KNOWS*2]->(b) RETURN b LIMIT 5",
+            "MATCH (m:Movie) OPTIONAL MATCH (m)<-[:ACTED_IN]-(a) RETURN m, a LIMIT 5",
+            "MATCH (a:Person) WITH a MATCH (b:Person) RETURN a, b LIMIT 5",
+        ] {
+            assert_eq!(rules(clean), Vec::new(), "{clean}");
+        }
+
+        assert_eq!(
+            rules("MATCH (a:Person), (b:Movie) RETURN a, b LIMIT 5"),
+            vec![LintRule::CartesianProduct]
+        );
+        assert_eq!(
+            rules("MATCH (a:Person) MATCH (b:Movie) RETURN a, b LIMIT 5"),
+            vec![LintRule::CartesianProduct]
+        );
+        assert_eq!(
+            rules("MATCH (a:Person)-[:KNOWS]-(b) RETURN b LIMIT 5"),
+            vec![LintRule::UndirectedRelationship]
+        );
+        assert_eq!(
+            rules("MATCH (a)--(b) RETURN b LIMIT 5"),
+            vec![LintRule::UndirectedRelationship]
+        );
+        for unbounded in ["[*]", "[:KNOWS*2..]", "[r *..]"] {
+            let query = format!("MATCH (a)-{unbounded}->(b) RETURN b LIMIT 5");
+            assert_eq!(rules(&query), vec![LintRule::UnboundedVarLength], "{query}");
+        }
+        assert_eq!(
+            rules("OPTIONAL MATCH (m:Movie) RETURN m LIMIT 5"),
+            vec![LintRule::OptionalMatchMisuse]
+        );
+        assert_eq!(
+            rules("MATCH (m:Movie) OPTIONAL MATCH (p:Person) RETURN m, p LIMIT 5"),
+            vec![LintRule::OptionalMatchMisuse]
+        );
+
+        // Hints are reported alongside validation and never invalidate the query
+        let result = CypherValidator::validate("MATCH (a)-[*]-(b) RETURN b LIMIT 5");
+        assert!(result.is_valid);
+        assert_eq!(result.hints.len(), 2);
+    }
+
+    #[test]
+    fn test_cap_var_length() {
+        let cap = |query: &str| CypherValidator::cap_var_length(query, 4);
+
+        let (query, warnings) = cap("MATCH (a)-[:KNOWS*]->(b {name: '[*]'}) RETURN b LIMIT 5");
+        assert_eq!(query, "MATCH (a)-[:KNOWS*1..4]->(b {name: '[*]'}) RETURN b LIMIT 5");
+        assert_eq!(
+            warnings,
+            vec!["Capped variable-length pattern -[:KNOWS*]-> to *1..4 (maximum depth 4)".to_string()]
+        );
+
+        let (query, warnings) = cap("MATCH (a)-[*2..]-(b)<-[r*0..10 {w: 1}]-(c) RETURN c LIMIT 5");
+        assert_eq!(query, "MATCH (a)-[*2..4]-(b)<-[r*0..4 {w: 1}]-(c) RETURN c LIMIT 5");
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("*2..4"), "{warnings:?}");
+
+        // Bounded within the cap and exact lengths are left alone
+        for unchanged in [
+            "MATCH (a)-[*1..3]->(b) RETURN b LIMIT 5",
+            "MATCH (a)-[*..4]->(b) RETURN b LIMIT 5",
+            "MATCH (a)-[*6]->(b) RETURN b LIMIT 5",
+        ] {
+            assert_eq!(cap(unchanged), (unchanged.to_string(), Vec::new()));
+        }
+        assert_eq!(
+            cap("MATCH (a)-[*6..]->(b) RETURN b").0,
+            "MATCH (a)-[*6..6]->(b) RETURN b"
+        );
+
+        let options = ValidationOptions::strict(true).with_max_var_length(Some(4));
+        let deep = "MATCH (a)-[*1..9]->(b) RETURN b LIMIT 5";
+        let result = CypherValidator::validate_with_options(deep, &options);
+        assert!(
+            result.errors.iter().any(|e| e.contains("maximum depth of 4")),
+            "{:?}",
+            result.errors
+        );
+        let (capped, _) = cap(deep);
+        assert!(CypherValidator::validate_with_options(&capped, &options).is_valid);
+    }
+
+    #[test]
+    fn test_clause_policy() {
+        let analyst = ClausePolicy::parse("MATCH|RETURN|WITH|UNWIND").unwrap();
+        let options = ValidationOptions::default().with_clause_policy(Some(analyst.clone()));
+        let allowed = "OPTIONAL MATCH (p:Person) WHERE p.name STARTS WITH 'CALL' WITH p RETURN p.set LIMIT 5";
+        assert!(CypherValidator::validate_with_options(allowed, &options).is_valid);
+
+        assert_eq!(
+            analyst.disallowed("CALL db.labels() YIELD label MATCH (n) MERGE (m:Tag) ON CREATE SET m.x = 1 RETURN n"),
+            vec![Clause::Call, Clause::Merge, Clause::Set]
+        );
+        let result = CypherValidator::validate_with_options("CALL db.labels()", &options);
+        assert!(!result.is_valid);
+        assert!(result.errors.iter().any(|e| e.contains("CALL clauses are not allowed")));
+
+        assert!(ClausePolicy::parse("MATCH|DROP").is_err());
+    }
+
+    #[test]
+    fn test_clause_policies_bind_keys_and_graphs() {
+        let policies = ClausePolicies::parse(
+            "analyst=MATCH|RETURN|WITH|UNWIND; reader=MATCH|RETURN|CALL",
+            "key:k1=analyst;graph:finance=reader",
+        )
+        .unwrap();
+        let allows_call = |key, graph| policies.policy_for(key, graph).map(|policy| policy.allows(Clause::Call));
+        assert_eq!(allows_call(Some("k1"), "movies"), Some(false));
+        assert_eq!(allows_call(None, "finance"), Some(true));
+        assert_eq!(allows_call(Some("k2"), "movies"), None);
+        let both = policies.policy_for(Some("k1"), "finance").unwrap();
+        assert!(both.allows(Clause::Match) && !both.allows(Clause::With) && !both.allows(Clause::Call));
+
+        assert!(ClausePolicies::parse("analyst=MATCH", "key:k1=missing").is_err());
+        assert!(ClausePolicies::parse("analyst=MATCH", "user:k1=analyst").is_err());
+        assert!(ClausePolicies::parse("a=MATCH;a=RETURN", "").is_err());
+    }
+
+    #[test]
+    fn test_validation_policy() {
+        let policy = ValidationPolicy::parse("allow-create; block=LOAD CSV|FOREACH").unwrap();
+        assert!(policy.allow_create && !policy.allow_delete && !policy.read_only);
+        assert_eq!(policy.blocked_clauses, vec![Clause::LoadCsv, Clause::Foreach]);
+        assert_eq!(
+            policy.allowed_writes(),
+            vec![Clause::Create, Clause::Merge, Clause::Set, Clause::Remove]
+        );
+        assert!(ValidationPolicy::parse("allow-everything").is_err());
+        assert!(ValidationPolicy::parse("block=TRUNCATE").is_err());
+        assert!(!ValidationPolicy::parse("read-only;allow-create").unwrap().allows_writes());
+
+        assert!(
+            policy
+                .check("MERGE (p:Person {name: 'Alice'}) SET p.age = 30 RETURN p")
+                .is_empty()
+        );
+        assert_eq!(
+            policy.check("LOAD CSV FROM 'file:///x.csv' AS row MATCH (p:Person {name: row[0]}) DELETE p"),
+            vec![
+                "LOAD CSV clauses are blocked by the validation policy",
+                "DELETE clauses are not allowed by the validation policy",
+            ]
+        );
+        assert_eq!(
+            ValidationPolicy::read_only().check("MATCH (n:Person) SET n.x = 1 RETURN n"),
+            vec!["SET clauses are not allowed: the validation policy is read-only"]
+        );
+
+        // With a policy, deletions no longer need allow_destructive; DROP stays forbidden
+        let options = ValidationOptions::default().with_policy(ValidationPolicy::parse("allow-delete").ok());
+        assert!(CypherValidator::validate_with_options("MATCH (n:Orphan) DELETE n", &options).is_valid);
+        assert!(!CypherValidator::validate_with_options("DROP INDEX ON :Person(name)", &options).is_valid);
+        let create = CypherValidator::validate_with_options("CREATE (n:Person) RETURN n", &options);
+        assert_eq!(
+            create.errors,
+            vec!["CREATE clauses are not allowed by the validation policy"]
+        );
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let anchored =
+            "MATCH (p:Person {name: 'Alice'})-[:KNOWS*1..3]->(f) WITH f MATCH (f)-[:LIKES]->(m) RETURN m LIMIT 5";
+        let estimate = estimate_cost(anchored, None);
+        assert_eq!(estimate.cost, 10);
+        assert_eq!(
+            estimate.to_string(),
+            "10 (variable-length pattern -[:KNOWS*1..3]-> may take up to 3 hops +10)"
+        );
+
+        let scan = "MATCH (a)-[*]->(b), (c:City) RETURN a, c";
+        let reasons: Vec<u32> = estimate_cost(scan, None).factors.iter().map(|factor| factor.cost).collect();
+        // A full scan, a cartesian product, an unbounded path, and no LIMIT
+        assert_eq!(reasons, vec![40, 30, 50, 10]);
+        assert_eq!(estimate_cost("MATCH (n) RETURN count(n)", None).cost, 40);
+        assert_eq!(
+            estimate_cost("MATCH (n) WHERE n.name = 'x' RETURN n LIMIT 1", None).cost,
+            40
+        );
+
+        // A plan replaces the scan and product heuristics
+        let plan = [
+            "Results".to_string(),
+            "    Project".to_string(),
+            "        Node By Label Scan | (n:Person)".to_string(),
+        ];
+        let estimate = estimate_cost("MATCH (n:Person) RETURN n", Some(&plan));
+        assert_eq!(estimate.cost, 20);
+        assert_eq!(
+            estimate.factors[0].reason,
+            "Node By Label Scan | (n:Person) reads every node with the label"
+        );
+
+        let budget = CostBudget::parse("max-cost=60; reject").unwrap();
+        assert_eq!(
+            budget,
+            CostBudget {
+                max_cost: 60,
+                reject: true
+            }
+        );
+        assert!(budget.allows(&estimate) && !budget.allows(&estimate_cost(scan, None)));
+        assert!(budget.exceeded(&estimate_cost(scan, None)).starts_with(
+            "The query's estimated cost 130 exceeds the complexity budget of 60: pattern (a)-[*]->(b) has no label"
+        ));
+        assert!(CostBudget::parse("reject").is_err());
+        assert!(CostBudget::parse("max-cost=lots").is_err());
+        assert!(CostBudget::parse("max-cost=5;fail").is_err());
+    }
+
+    #[test]
+    fn test_aggregate_only() {
+        let options = ValidationOptions::default().with_aggregate_only(true);
+        for query in [
+            "MATCH (p:Patient) WITH count(p) AS patients WHERE patients >= 10 RETURN patients",
+            "MATCH (p:Patient)-[:HAS]->(d:Diagnosis {code: 'E11'}) WITH count(*) AS n, avg(p.age) AS mean_age WHERE n >= 10 RETURN mean_age, n",
+            "MATCH (p:Patient) WITH count(p) AS total, sum(p.visits) AS visits WHERE total > 9 RETURN round(visits * 1.0 / total)",
+            "MATCH (p:Patient) WHERE p.age > $age WITH count(*) AS n, stdev(p.age) AS spread WHERE n >= 10 AND spread > 0 RETURN DISTINCT spread ORDER BY spread",
+            "MATCH (p:Patient) WITH count(p) AS n WHERE n >= 10 RETURN n UNION MATCH (p:Archived) WITH count(p) AS n WHERE n >= 10 RETURN n",
+        ] {
+            assert_eq!(
+                CypherValidator::aggregate_only_errors(query),
+                Vec::<String>::new(),
+                "{query}"
+            );
+            assert!(
+                CypherValidator::validate_with_options(query, &options).is_valid,
+                "{query}"
+            );
+        }
+
+        assert_eq!(
+            CypherValidator::aggregate_only_errors(
+                "MATCH (p:Patient) WITH p.city AS city, count(p) AS n WHERE n >= 10 RETURN city, n"
+            ),
+            vec!["'city' returns raw values; the graph only answers with aggregates".to_string()]
+        );
+        assert_eq!(
+            CypherValidator::aggregate_only_errors("MATCH (p:Patient) RETURN count(p) + p.age, collect(p.name)"),
+            vec![
+                "'count(p) + p.age' returns raw values; the graph only answers with aggregates".to_string(),
+                "'collect(p.name)' returns raw values; the graph only answers with aggregates".to_string(),
+            ]
+        );
+        assert_eq!(
+            CypherValidator::aggregate_only_errors("MATCH (p:Patient) RETURN *"),
+            vec!["RETURN * returns raw rows; the graph only answers with aggregates".to_string()]
+        );
+        for raw in [
+            "MATCH (p:Patient) RETURN p LIMIT 10",
+            "MATCH (p:Patient) WITH p.name AS name, count(*) AS n RETURN name",
+            "MATCH (p:Patient) RETURN count(p) UNION MATCH (p:Patient) RETURN p.name",
+            "CALL db.labels() YIELD label",
+            "MATCH (p:Patient) WITH count(p) AS total",
+        ] {
+            assert!(!CypherValidator::validate_with_options(raw, &options).is_valid, "{raw}");
+        }
+        // Strings cannot fake an aggregate
+        assert!(!CypherValidator::aggregate_only_errors("MATCH (p:Patient) RETURN p.name + 'count(x)'").is_empty());
+
+        // The extremes and percentiles of a group are single records' values
+        assert_eq!(
+            CypherValidator::aggregate_only_errors("MATCH (n:Person) RETURN max(n.ssn)"),
+            vec!["'max(n.ssn)' returns single rows' values; the graph only answers with aggregates".to_string()]
+        );
+        for extreme in ["min(n.ssn)", "percentileCont(n.salary, 1.0)", "percentileDisc(n.salary, 0.5)"] {
+            let query = format!("MATCH (n:Person) WITH count(*) AS n, {extreme} AS value WHERE n >= 10 RETURN value");
+            assert!(!CypherValidator::aggregate_only_errors(&query).is_empty(), "{query}");
+        }
+
+        // An aggregate over a single record is that record's value
+        assert_eq!(
+            CypherValidator::aggregate_only_errors("MATCH (n {name:'Alice'}) RETURN avg(n.salary)"),
+            vec![
+                "'avg(n.salary)' may summarize fewer than 10 rows; compute it in a WITH that binds count(*) to an \
+                 alias and requires it to be at least 10 in its WHERE; the graph only answers with aggregates"
+                    .to_string()
+            ]
+        );
+        for unguarded in [
+            "MATCH (n {name:'Alice'}) WITH avg(n.salary) AS salary RETURN salary",
+            "MATCH (n {name:'Alice'}) WITH count(*) AS n, avg(n.salary) AS salary WHERE n >= 2 RETURN salary",
+            "MATCH (n {name:'Alice'}) WITH count(*) AS n, avg(n.salary) AS salary WHERE n >= 10 OR true RETURN salary",
+            "MATCH (n {name:'Alice'}) WITH count(*) AS n, avg(n.salary) AS salary WHERE salary >= 10 RETURN salary",
+            "MATCH (n {name:'Alice'}) WITH avg(n.salary) AS salary MATCH (m) WITH salary, count(m) AS n WHERE n >= 10 RETURN salary",
+        ] {
+            assert!(
+                !CypherValidator::aggregate_only_errors(unguarded).is_empty(),
+                "{unguarded}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_procedure_allowlist() {
+        let allowlist = ProcedureAllowlist::parse(
+            "db.labels; db.idx.fulltext.queryNodes(args<=2); algo.SPpaths(pathCount<=5, relDirection=outgoing|incoming); algo.SS*",
+        )
+        .unwrap();
+        assert!(allowlist.allows("DB.LABELS") && allowlist.allows("algo.SSpaths") && !allowlist.allows("algo.BFS"));
+
+        let options = ValidationOptions::default().with_procedure_allowlist(Some(allowlist.clone()));
+        let allowed = "CALL db.idx.fulltext.queryNodes('Movie', 'Jun*, (x)') YIELD node RETURN node LIMIT 5";
+        assert!(CypherValidator::validate_with_options(allowed, &options).is_valid);
+        assert!(allowlist.check("MATCH (a), (b) CALL algo.SPpaths({sourceNode: a, targetNode: b, pathCount: 2, relDirection: 'incoming'}) YIELD path RETURN path").is_empty());
+        assert!(allowlist.check("CALL { MATCH (n) RETURN n } RETURN 1").is_empty());
+
+        assert_eq!(
+            allowlist.check("CALL db.idx.vector.queryNodes('Movie', 'plot', 5, vecf32([1.0])) YIELD node RETURN node"),
+            vec!["Procedure db.idx.vector.queryNodes is not allowed".to_string()]
+        );
+        assert_eq!(
+            allowlist.check(
+                "CALL algo.SPpaths({sourceNode: a, targetNode: b, pathCount: 0, relDirection: 'both'}) YIELD path RETURN path"
+            ),
+            vec!["`relDirection` of algo.SPpaths must be one of outgoing, incoming, got 'both'".to_string()]
+        );
+        assert_eq!(
+            allowlist
+                .check("CALL algo.SPpaths({pathCount: $n}) YIELD path RETURN path")
+                .len(),
+            1
+        );
+        assert_eq!(
+            allowlist
+                .check("CALL db.idx.fulltext.queryNodes('A', 'b', 3) YIELD node RETURN node")
+                .len(),
+            1
+        );
+
+        assert!(ProcedureAllowlist::parse("algo.SPpaths(pathCount<=many)").is_err());
+        assert!(ProcedureAllowlist::parse("algo.SPpaths(pathCount<=1").is_err());
+    }
+
+    #[test]
+    fn test_date_warnings() {
+        assert!(CypherValidator::mentions_relative_period("Orders from the past 7 days"));
+        assert!(CypherValidator::mentions_relative_period("Revenue last quarter?"));
+        assert!(CypherValidator::mentions_relative_period("sales year-to-date"));
+        assert!(!CypherValidator::mentions_relative_period(
+            "Orders placed in March 2024"
+        ));
+        assert!(!CypherValidator::mentions_relative_period(
+            "Who was the last person hired?"
+        ));
+
+        let query = "MATCH (o:Order) WHERE o.placed >= '2023-10-01' RETURN count(o)";
+        let relative = ValidationOptions::default().with_question("How many orders last quarter?");
+        let warnings = CypherValidator::date_warnings(query, &relative);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("hard-codes the date '2023-10-01'"), "{warnings:?}");
+        assert!(
+            CypherValidator::validate_with_options(query, &relative)
+                .warnings
+                .contains(&warnings[0])
+        );
+
+        // A specific date the question asked for is fine
+        let absolute = ValidationOptions::default().with_question("How many orders since 2023-10-01?");
+        assert!(CypherValidator::date_warnings(query, &absolute).is_empty());
+        let computed = "MATCH (o:Order) WHERE o.placed >= toString(date() - duration({days: 7})) RETURN count(o)";
+        assert!(CypherValidator::date_warnings(computed, &relative).is_empty());
+
+        // Literals must use the layout the graph stores dates in
+        let schema = r#"{"entities":[{"label":"Order","attributes":[
+            {"name":"placed","type":"String","date_format":"YYYY-MM-DD"}]}],"relations":[]}"#;
+        let stored = ValidationOptions::default().with_schema(schema);
+        assert_eq!(stored.date_formats, Some(vec!["YYYY-MM-DD".to_string()]));
+        assert!(CypherValidator::date_warnings(query, &stored).is_empty());
+        let slashed = "MATCH (o:Order) WHERE o.placed >= \"01/10/2023\" RETURN count(o)";
+        let warnings = CypherValidator::date_warnings(slashed, &stored);
+        assert_eq!(
+            warnings,
+            vec![
+                "Date literal '01/10/2023' is written as DD/MM/YYYY or MM/DD/YYYY, but the graph stores dates as YYYY-MM-DD"
+                    .to_string()
+            ]
+        );
+        let us_schema = schema.replace("YYYY-MM-DD", "MM/DD/YYYY");
+        let us = ValidationOptions::default().with_schema(&us_schema);
+        assert!(CypherValidator::date_warnings(slashed, &us).is_empty());
+    }
+
+    #[test]
+    fn test_graph_algorithms() {
+        let shortest = "MATCH (a:Person {name: 'Alice'}), (b:Person {name: 'Bob'}) \
+            CALL algo.SPpaths({sourceNode: a, targetNode: b, relTypes: ['KNOWS'], pathCount: 1}) YIELD path \
+            RETURN [n IN nodes(path) | n.name] AS route LIMIT 1";
+        let result = CypherValidator::validate(shortest);
+        assert!(result.is_valid, "{:?}", result.errors);
+        assert!(!result.hints.iter().any(|hint| hint.rule == LintRule::CartesianProduct));
+        assert!(
+            CypherValidator::lint("MATCH (a:Person), (b:Person) CALL db.labels() YIELD label RETURN a, b LIMIT 1")
+                .iter()
+                .any(|hint| hint.rule == LintRule::CartesianProduct)
+        );
+
+        let ranked = "CALL ALGO.PAGERANK('Person', 'FOLLOWS') YIELD node, score RETURN node.name, score ORDER BY score DESC LIMIT 10";
+        assert!(CypherValidator::validate(ranked).is_valid);
+        assert!(CypherValidator::validate("CALL algo.SPpaths($config) YIELD path RETURN path LIMIT 1").is_valid);
+
+        let errors = |query: &str| CypherValidator::validate(query).errors;
+        assert_eq!(
+            errors("MATCH (a) CALL algo.SPpaths({sourceNode: a}) YIELD path RETURN path LIMIT 1"),
+            vec!["algo.SPpaths requires targetNode in its configuration map".to_string()]
+        );
+        assert!(
+            errors("CALL algo.louvain('Person') YIELD node RETURN node LIMIT 5")[0]
+                .starts_with("Unknown graph algorithm procedure algo.louvain; FalkorDB provides algo.SPpaths")
+        );
+        assert!(
+            errors("CALL gds.pageRank.stream('g') YIELD nodeId RETURN nodeId LIMIT 5")[0].contains("Neo4j procedure")
+        );
     }
 }
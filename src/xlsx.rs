@@ -0,0 +1,704 @@
+//! Excel (`.xlsx`) uploads.
+//!
+//! An `.xlsx` workbook is a zip of XML parts. [`xlsx_to_csv`] reads one worksheet, optionally cut
+//! to a cell range, and writes it as the comma-separated text the CSV import pipeline takes.
+//! Shared and inline strings are resolved, booleans read as `true`/`false`, and numbers formatted
+//! as dates are written as ISO dates.
+
+use crate::import::csv_line;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+/// Why a workbook could not be converted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XlsxError {
+    /// The upload is not a readable `.xlsx` zip, or a part of it is not valid UTF-8.
+    Archive(String),
+    /// A part every workbook has, e.g. `xl/workbook.xml`, is missing.
+    MissingPart(String),
+    /// No sheet has the requested name or 1-based number.
+    SheetNotFound(String),
+    /// The range is not written like `A1:D100`, `B:D` or `2:50`.
+    InvalidRange(String),
+}
+
+impl std::fmt::Display for XlsxError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::Archive(message) => write!(f, "Not a readable .xlsx workbook: {message}"),
+            Self::MissingPart(part) => write!(f, "The workbook has no '{part}'"),
+            Self::SheetNotFound(sheet) => write!(f, "The workbook has no sheet '{sheet}'"),
+            Self::InvalidRange(range) => write!(f, "Invalid cell range '{range}' (expected e.g. A1:D100)"),
+        }
+    }
+}
+
+impl std::error::Error for XlsxError {}
+
+/// Which part of a workbook [`xlsx_to_csv`] converts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SheetSelection {
+    /// Sheet name, or its 1-based position; the first sheet when `None`.
+    pub sheet: Option<String>,
+    /// Cells to keep, e.g. `A1:D100`; either end may leave out the column (`2:50`) or the row
+    /// (`B:D`). The whole used area when `None`.
+    pub range: Option<String>,
+}
+
+/// Whether an upload is an `.xlsx` workbook, by its file name or, failing that, its zip signature.
+#[must_use]
+pub fn is_xlsx(
+    filename: &str,
+    bytes: &[u8],
+) -> bool {
+    let extension = std::path::Path::new(filename).extension();
+    let has_extension = |wanted: &str| extension.is_some_and(|extension| extension.eq_ignore_ascii_case(wanted));
+    has_extension("xlsx") || (!has_extension("csv") && bytes.starts_with(b"PK\x03\x04"))
+}
+
+/// The workbook's sheet names, in tab order.
+///
+/// # Errors
+///
+/// Returns an error if `bytes` is not an `.xlsx` workbook.
+pub fn sheet_names(bytes: &[u8]) -> Result<Vec<String>, XlsxError> {
+    let mut workbook = Workbook::open(bytes)?;
+    Ok(workbook.sheets()?.into_iter().map(|(name, _)| name).collect())
+}
+
+/// Converts the selected sheet and range of a workbook into CSV text, one record per row that
+/// has a value. Cells without one are written empty.
+///
+/// # Errors
+///
+/// Returns an error if `bytes` is not an `.xlsx` workbook, or the sheet or range is not found.
+pub fn xlsx_to_csv(
+    bytes: &[u8],
+    selection: &SheetSelection,
+) -> Result<String, XlsxError> {
+    let range = selection.range.as_deref().map(CellRange::parse).transpose()?;
+    let mut workbook = Workbook::open(bytes)?;
+    let sheets = workbook.sheets()?;
+    let path = selection
+        .sheet
+        .as_deref()
+        .map_or_else(
+            || sheets.first(),
+            |wanted| {
+                sheets
+                    .iter()
+                    .find(|(name, _)| name == wanted)
+                    .or_else(|| wanted.trim().parse::<usize>().ok().and_then(|n| sheets.get(n.checked_sub(1)?)))
+            },
+        )
+        .map(|(_, path)| path.clone())
+        .ok_or_else(|| XlsxError::SheetNotFound(selection.sheet.clone().unwrap_or_else(|| "1".to_string())))?;
+
+    let shared_strings = workbook.shared_strings()?;
+    let date_styles = workbook.date_styles()?;
+    let sheet = workbook.part(&path)?.ok_or(XlsxError::MissingPart(path))?;
+    let cells = read_cells(&sheet, &shared_strings, &date_styles);
+
+    let range = range.unwrap_or_default();
+    let mut rows: std::collections::BTreeMap<u32, Vec<(u32, String)>> = std::collections::BTreeMap::new();
+    for (row, column, value) in cells {
+        if range.contains(row, column) && !value.is_empty() {
+            rows.entry(row).or_default().push((column, value));
+        }
+    }
+    let first_column = range
+        .first_column
+        .or_else(|| rows.values().flatten().map(|(column, _)| *column).min())
+        .unwrap_or(0);
+    let last_column = range
+        .last_column
+        .or_else(|| rows.values().flatten().map(|(column, _)| *column).max())
+        .unwrap_or(0);
+
+    let mut csv = String::new();
+    for cells in rows.values() {
+        let mut fields = vec![String::new(); (last_column - first_column + 1) as usize];
+        for (column, value) in cells {
+            fields[(column - first_column) as usize].clone_from(value);
+        }
+        csv.push_str(&csv_line(&fields));
+    }
+    Ok(csv)
+}
+
+/// An opened workbook archive.
+struct Workbook<'a> {
+    archive: zip::ZipArchive<Cursor<&'a [u8]>>,
+}
+
+impl<'a> Workbook<'a> {
+    fn open(bytes: &'a [u8]) -> Result<Self, XlsxError> {
+        zip::ZipArchive::new(Cursor::new(bytes))
+            .map(|archive| Self { archive })
+            .map_err(|e| XlsxError::Archive(e.to_string()))
+    }
+
+    /// The text of the part at `path`, `None` when the workbook has no such part.
+    fn part(
+        &mut self,
+        path: &str,
+    ) -> Result<Option<String>, XlsxError> {
+        let mut file = match self.archive.by_name(path) {
+            Ok(file) => file,
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(XlsxError::Archive(e.to_string())),
+        };
+        let mut text = String::new();
+        file.read_to_string(&mut text)
+            .map_err(|e| XlsxError::Archive(format!("{path}: {e}")))?;
+        Ok(Some(text))
+    }
+
+    /// Each sheet's name and the path of its part, in tab order.
+    fn sheets(&mut self) -> Result<Vec<(String, String)>, XlsxError> {
+        const WORKBOOK: &str = "xl/workbook.xml";
+        const RELATIONSHIPS: &str = "xl/_rels/workbook.xml.rels";
+        let workbook = self
+            .part(WORKBOOK)?
+            .ok_or_else(|| XlsxError::MissingPart(WORKBOOK.to_string()))?;
+        let relationships = self
+            .part(RELATIONSHIPS)?
+            .ok_or_else(|| XlsxError::MissingPart(RELATIONSHIPS.to_string()))?;
+
+        let targets: HashMap<String, String> = XmlEvents::new(&relationships)
+            .filter_map(|event| match event {
+                XmlEvent::Start {
+                    name: "Relationship",
+                    attributes,
+                    ..
+                } => Some((attribute(attributes, "Id")?, attribute(attributes, "Target")?)),
+                _ => None,
+            })
+            .collect();
+        Ok(XmlEvents::new(&workbook)
+            .filter_map(|event| match event {
+                XmlEvent::Start {
+                    name: "sheet",
+                    attributes,
+                    ..
+                } => {
+                    let target = targets.get(&attribute(attributes, "id")?)?;
+                    let path = target
+                        .strip_prefix('/')
+                        .map_or_else(|| format!("xl/{target}"), ToString::to_string);
+                    Some((attribute(attributes, "name")?, path))
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// The shared string table; empty when the workbook has none.
+    fn shared_strings(&mut self) -> Result<Vec<String>, XlsxError> {
+        let Some(xml) = self.part("xl/sharedStrings.xml")? else {
+            return Ok(Vec::new());
+        };
+        let mut strings = Vec::new();
+        let mut current = String::new();
+        // Phonetic runs (`rPh`) annotate the text rather than belong to it
+        let (mut in_text, mut in_phonetic) = (false, false);
+        for event in XmlEvents::new(&xml) {
+            match event {
+                XmlEvent::Start { name: "si", .. } => current.clear(),
+                XmlEvent::End { name: "si" } => strings.push(std::mem::take(&mut current)),
+                XmlEvent::Start { name: "rPh", empty, .. } => in_phonetic = !empty,
+                XmlEvent::End { name: "rPh" } => in_phonetic = false,
+                XmlEvent::Start { name: "t", empty, .. } => in_text = !empty,
+                XmlEvent::End { name: "t" } => in_text = false,
+                XmlEvent::Text(text) if in_text && !in_phonetic => current.push_str(&unescape(text)),
+                _ => {}
+            }
+        }
+        Ok(strings)
+    }
+
+    /// For each cell style (`s` attribute) in order, whether its number format shows a date.
+    fn date_styles(&mut self) -> Result<Vec<bool>, XlsxError> {
+        let Some(xml) = self.part("xl/styles.xml")? else {
+            return Ok(Vec::new());
+        };
+        let mut custom_dates: Vec<u32> = Vec::new();
+        let mut styles = Vec::new();
+        let mut in_cell_formats = false;
+        for event in XmlEvents::new(&xml) {
+            match event {
+                XmlEvent::Start {
+                    name: "numFmt",
+                    attributes,
+                    ..
+                } => {
+                    if let (Some(id), Some(code)) =
+                        (attribute(attributes, "numFmtId"), attribute(attributes, "formatCode"))
+                        && is_date_format(&code)
+                        && let Ok(id) = id.parse()
+                    {
+                        custom_dates.push(id);
+                    }
+                }
+                XmlEvent::Start {
+                    name: "cellXfs", empty, ..
+                } => in_cell_formats = !empty,
+                XmlEvent::End { name: "cellXfs" } => in_cell_formats = false,
+                XmlEvent::Start {
+                    name: "xf", attributes, ..
+                } if in_cell_formats => {
+                    let id: u32 = attribute(attributes, "numFmtId").and_then(|id| id.parse().ok()).unwrap_or(0);
+                    styles.push(matches!(id, 14..=22 | 45..=47) || custom_dates.contains(&id));
+                }
+                _ => {}
+            }
+        }
+        Ok(styles)
+    }
+}
+
+/// Whether a custom number format code shows a date or time: it has a `d`, `m`, `y`, `h` or `s`
+/// outside quoted text, escapes and `[...]` sections.
+fn is_date_format(code: &str) -> bool {
+    let mut chars = code.chars();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' | '_' | '*' if !quoted => {
+                chars.next();
+            }
+            '[' if !quoted => {
+                chars.by_ref().find(|&c| c == ']');
+            }
+            c if !quoted && "dmyhsDMYHS".contains(c) => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Every cell's 0-based row, 0-based column and value as text.
+fn read_cells(
+    xml: &str,
+    shared_strings: &[String],
+    date_styles: &[bool],
+) -> Vec<(u32, u32, String)> {
+    let mut cells = Vec::new();
+    let (mut row, mut column) = (0u32, 0u32);
+    let mut cell: Option<(String, bool)> = None;
+    let mut value = String::new();
+    let mut in_value = false;
+    for event in XmlEvents::new(xml) {
+        match event {
+            XmlEvent::Start {
+                name: "row",
+                attributes,
+                ..
+            } => {
+                row = attribute(attributes, "r")
+                    .and_then(|r| r.parse::<u32>().ok())
+                    .map_or(row + 1, |r| r.saturating_sub(1));
+                column = 0;
+            }
+            XmlEvent::Start {
+                name: "c",
+                attributes,
+                empty,
+            } => {
+                if let Some((reference_row, reference_column)) =
+                    attribute(attributes, "r").as_deref().and_then(CellBound::parse_cell)
+                {
+                    (row, column) = (reference_row, reference_column);
+                }
+                let kind = attribute(attributes, "t").unwrap_or_default();
+                let is_date = attribute(attributes, "s")
+                    .and_then(|s| s.parse::<usize>().ok())
+                    .is_some_and(|s| date_styles.get(s).copied().unwrap_or(false));
+                value.clear();
+                if empty {
+                    column += 1;
+                } else {
+                    cell = Some((kind, is_date));
+                }
+            }
+            XmlEvent::Start {
+                name: "v" | "t", empty, ..
+            } if cell.is_some() => in_value = !empty,
+            XmlEvent::End { name: "v" | "t" } => in_value = false,
+            XmlEvent::Text(text) if in_value => value.push_str(&unescape(text)),
+            XmlEvent::End { name: "c" } => {
+                if let Some((kind, is_date)) = cell.take() {
+                    let text = match kind.as_str() {
+                        "s" => value
+                            .trim()
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|index| shared_strings.get(index).cloned())
+                            .unwrap_or_default(),
+                        "b" => (if value.trim() == "1" { "true" } else { "false" }).to_string(),
+                        "n" | "" if is_date => value
+                            .trim()
+                            .parse::<f64>()
+                            .ok()
+                            .and_then(excel_date)
+                            .unwrap_or_else(|| value.clone()),
+                        _ => value.clone(),
+                    };
+                    cells.push((row, column, text));
+                }
+                column += 1;
+            }
+            _ => {}
+        }
+    }
+    cells
+}
+
+/// An Excel date serial (days since 1899-12-30) as `YYYY-MM-DD`, with `THH:MM:SS` when it has a
+/// time of day; `None` outside years 1 to 9999.
+fn excel_date(serial: f64) -> Option<String> {
+    if !(1.0..2_958_466.0).contains(&serial) {
+        return None;
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    let days = serial.floor() as i64;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let seconds = ((serial - serial.floor()) * 86_400.0).round() as u32;
+
+    // Days from 1899-12-30 to 1970-01-01, then the civil-from-days conversion
+    let z = days - 25_569 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let date = format!("{year:04}-{month:02}-{day:02}");
+    Some(if seconds == 0 || seconds == 86_400 {
+        date
+    } else {
+        format!(
+            "{date}T{:02}:{:02}:{:02}",
+            seconds / 3_600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    })
+}
+
+/// One end of a [`CellRange`]: a column, a row, or both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CellBound {
+    column: Option<u32>,
+    row: Option<u32>,
+}
+
+impl CellBound {
+    /// Reads `B12`, `B` or `12` (0-based once read); `$` anchors are ignored.
+    fn parse(reference: &str) -> Option<Self> {
+        let reference = reference.trim().replace('$', "");
+        let split = reference.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(reference.len());
+        let (letters, digits) = reference.split_at(split);
+        let column = (!letters.is_empty()).then(|| {
+            letters.bytes().fold(0u32, |column, b| {
+                column * 26 + u32::from(b.to_ascii_uppercase() - b'A' + 1)
+            }) - 1
+        });
+        let row = if digits.is_empty() {
+            None
+        } else {
+            Some(digits.parse::<u32>().ok()?.checked_sub(1)?)
+        };
+        (column.is_some() || row.is_some()).then_some(Self { column, row })
+    }
+
+    /// Reads a full cell reference like `B12` as its 0-based row and column.
+    fn parse_cell(reference: &str) -> Option<(u32, u32)> {
+        let bound = Self::parse(reference)?;
+        Some((bound.row?, bound.column?))
+    }
+}
+
+/// The cells kept by a [`SheetSelection::range`]; unset bounds are open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CellRange {
+    first_row: Option<u32>,
+    last_row: Option<u32>,
+    first_column: Option<u32>,
+    last_column: Option<u32>,
+}
+
+impl CellRange {
+    fn parse(range: &str) -> Result<Self, XlsxError> {
+        let invalid = || XlsxError::InvalidRange(range.to_string());
+        let (start, end) = range.split_once(':').unwrap_or((range, range));
+        let (start, end) = (
+            CellBound::parse(start).ok_or_else(invalid)?,
+            CellBound::parse(end).ok_or_else(invalid)?,
+        );
+        if start.column.is_some() != end.column.is_some() || start.row.is_some() != end.row.is_some() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            first_row: start.row.min(end.row),
+            last_row: start.row.max(end.row),
+            first_column: start.column.min(end.column),
+            last_column: start.column.max(end.column),
+        })
+    }
+
+    fn contains(
+        &self,
+        row: u32,
+        column: u32,
+    ) -> bool {
+        self.first_row.is_none_or(|first| row >= first)
+            && self.last_row.is_none_or(|last| row <= last)
+            && self.first_column.is_none_or(|first| column >= first)
+            && self.last_column.is_none_or(|last| column <= last)
+    }
+}
+
+/// A piece of an XML part; element names are given without their namespace prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum XmlEvent<'a> {
+    /// An opening tag, `empty` when self-closing; `attributes` is the raw text after the name.
+    Start {
+        name: &'a str,
+        attributes: &'a str,
+        empty: bool,
+    },
+    End {
+        name: &'a str,
+    },
+    /// Text between tags, still escaped.
+    Text(&'a str),
+}
+
+/// The elements and text of an XML document, skipping declarations, comments and processing
+/// instructions. Enough for the flat, machine-written parts of a workbook.
+struct XmlEvents<'a> {
+    rest: &'a str,
+}
+
+impl<'a> XmlEvents<'a> {
+    const fn new(xml: &'a str) -> Self {
+        Self { rest: xml }
+    }
+}
+
+impl<'a> Iterator for XmlEvents<'a> {
+    type Item = XmlEvent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+            if !self.rest.starts_with('<') {
+                let end = self.rest.find('<').unwrap_or(self.rest.len());
+                let (text, rest) = self.rest.split_at(end);
+                self.rest = rest;
+                return Some(XmlEvent::Text(text));
+            }
+            if let Some(cdata) = self.rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>").unwrap_or(cdata.len());
+                self.rest = cdata.get(end + 3..).unwrap_or("");
+                return Some(XmlEvent::Text(&cdata[..end]));
+            }
+            let close = if self.rest.starts_with("<!--") { "-->" } else { ">" };
+            let end = self.rest.find(close)?;
+            let tag = &self.rest[1..end];
+            self.rest = &self.rest[end + close.len()..];
+            if tag.starts_with(['?', '!']) {
+                continue;
+            }
+            let local = |name: &'a str| name.rsplit(':').next().unwrap_or(name);
+            if let Some(name) = tag.strip_prefix('/') {
+                return Some(XmlEvent::End {
+                    name: local(name.trim()),
+                });
+            }
+            let (tag, empty) = tag.strip_suffix('/').map_or((tag, false), |tag| (tag, true));
+            let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            return Some(XmlEvent::Start {
+                name: local(name),
+                attributes,
+                empty,
+            });
+        }
+    }
+}
+
+/// The unescaped value of the attribute `name` (namespace prefix ignored) in a tag's raw attributes.
+fn attribute(
+    attributes: &str,
+    name: &str,
+) -> Option<String> {
+    let mut rest = attributes;
+    while let Some(equals) = rest.find('=') {
+        let key = rest[..equals].trim();
+        let value = rest[equals + 1..].trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let end = value[1..].find(quote)? + 1;
+        if key.rsplit(':').next() == Some(name) {
+            return Some(unescape(&value[1..end]));
+        }
+        rest = &value[end + 1..];
+    }
+    None
+}
+
+/// Resolves XML entity and character references.
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let resolved = match &rest[1..end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            entity => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        if let Some(c) = resolved {
+            unescaped.push(c);
+            rest = &rest[end + 1..];
+        } else {
+            unescaped.push('&');
+            rest = &rest[1..];
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// A two-sheet workbook: `People` with shared, inline, boolean, date and numeric cells, and an
+    /// empty `Notes`.
+    fn workbook() -> Vec<u8> {
+        let parts = [
+            (
+                "xl/workbook.xml",
+                r#"<?xml version="1.0"?><workbook xmlns:r="urn:r"><sheets>
+                <sheet name="People" sheetId="1" r:id="rId1"/><sheet name="Notes" sheetId="2" r:id="rId2"/>
+                </sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/>
+                <Relationship Id="rId2" Target="/xl/worksheets/sheet2.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>name</t></si><si><t>born</t></si><si><r><t>Ada </t></r><r><t>&amp; co, "x"</t></r>
+                <rPh><t>phonetic</t></rPh></si></sst>"#,
+            ),
+            (
+                "xl/styles.xml",
+                r#"<styleSheet><numFmts><numFmt numFmtId="164" formatCode="[$-409]dd/mm/yyyy;@"/></numFmts>
+                <cellXfs count="3"><xf numFmtId="0"/><xf numFmtId="164"/><xf numFmtId="14"/></cellXfs></styleSheet>"#,
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                r#"<worksheet><sheetData>
+                <row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c><c r="D1" t="inlineStr"><is><t>ok</t></is></c></row>
+                <row r="2"><c r="A2" t="s"><v>2</v></c><c r="B2" s="1"><v>-1</v></c><c r="C2"><v>36.5</v></c><c r="D2" t="b"><v>1</v></c></row>
+                <row r="4"><c r="A4" t="str"><v>Bob</v></c><c r="B4" s="2"><v>45292.5</v></c><c r="D4" t="b"><v>0</v></c></row>
+                </sheetData></worksheet>"#,
+            ),
+            ("xl/worksheets/sheet2.xml", "<worksheet><sheetData/></worksheet>"),
+        ];
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (path, xml) in parts {
+            writer.start_file(path, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(xml.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn converts_the_first_sheet_to_csv() {
+        let bytes = workbook();
+        assert_eq!(sheet_names(&bytes).unwrap(), vec!["People", "Notes"]);
+        assert_eq!(
+            xlsx_to_csv(&bytes, &SheetSelection::default()).unwrap(),
+            "name,born,,ok\n\"Ada & co, \"\"x\"\"\",-1,36.5,true\nBob,2024-01-01T12:00:00,,false\n"
+        );
+        assert!(is_xlsx("People.XLSX", b""));
+        assert!(is_xlsx("upload", &bytes));
+        assert!(!is_xlsx("data.csv", &bytes));
+    }
+
+    #[test]
+    fn selects_sheets_and_ranges() {
+        let bytes = workbook();
+        let select = |sheet: Option<&str>, range: Option<&str>| {
+            xlsx_to_csv(
+                &bytes,
+                &SheetSelection {
+                    sheet: sheet.map(String::from),
+                    range: range.map(String::from),
+                },
+            )
+        };
+        assert_eq!(select(Some("Notes"), None).unwrap(), "");
+        assert_eq!(select(Some("2"), None).unwrap(), "");
+        assert_eq!(
+            select(Some("Missing"), None),
+            Err(XlsxError::SheetNotFound("Missing".to_string()))
+        );
+        assert_eq!(
+            select(None, Some("$B$2:C4")).unwrap(),
+            "-1,36.5\n2024-01-01T12:00:00,\n"
+        );
+        assert_eq!(
+            select(None, Some("A:B")).unwrap(),
+            "name,born\n\"Ada & co, \"\"x\"\"\",-1\nBob,2024-01-01T12:00:00\n"
+        );
+        assert_eq!(select(None, Some("4:4")).unwrap(), "Bob,2024-01-01T12:00:00,,false\n");
+        assert!(matches!(select(None, Some("A1:4")), Err(XlsxError::InvalidRange(_))));
+        assert!(matches!(
+            xlsx_to_csv(b"a,b\n", &SheetSelection::default()),
+            Err(XlsxError::Archive(_))
+        ));
+    }
+
+    #[test]
+    fn reads_dates_and_formats() {
+        assert_eq!(excel_date(1.0).as_deref(), Some("1899-12-31"));
+        assert_eq!(excel_date(45_292.0).as_deref(), Some("2024-01-01"));
+        assert_eq!(excel_date(45_292.75).as_deref(), Some("2024-01-01T18:00:00"));
+        assert_eq!(excel_date(-1.0), None);
+        assert!(is_date_format("yyyy-mm-dd"));
+        assert!(is_date_format("[$-409]h:mm AM/PM"));
+        assert!(!is_date_format("0.00\" days\""));
+        assert!(!is_date_format("[Red]#,##0"));
+        assert_eq!(unescape("a &lt;&#65;&#x42;&gt; &bogus b"), "a <AB> &bogus b");
+    }
+}